@@ -0,0 +1,43 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use onise::signing::{encode_post_data, encode_post_data_with_nonce, sign, Signer};
+
+const SECRET: &str =
+    "kQH5HW/8p1uGOVjbgWA7FunAmGO8lsSUXNsu3eow76sz84Q18fWxnyRzBHCd3pd5nE9qa99HAZtuZuj6F1huXg==";
+const PATH: &str = "/0/private/AddOrder";
+const NONCE: u64 = 1616492376594;
+const PARAMS: [(&str, &str); 4] = [
+    ("ordertype", "limit"),
+    ("pair", "XBTUSD"),
+    ("price", "37500"),
+    ("volume", "1.25"),
+];
+
+fn signing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sign_request");
+    group.bench_function("per_call_secret", |b| {
+        b.iter(|| {
+            let mut form = vec![("nonce".to_string(), NONCE.to_string())];
+            form.extend(PARAMS.iter().map(|(k, v)| (k.to_string(), v.to_string())));
+            let body = encode_post_data(&form);
+            sign(SECRET, PATH, black_box(NONCE), &body).unwrap()
+        })
+    });
+
+    let signer = Signer::new(SECRET).unwrap();
+    group.bench_function("signer", |b| {
+        b.iter(|| {
+            let body = encode_post_data_with_nonce(black_box(NONCE), &PARAMS);
+            signer.sign(PATH, NONCE, &body)
+        })
+    });
+
+    let body = encode_post_data_with_nonce(NONCE, &PARAMS);
+    let mut signature = String::new();
+    group.bench_function("signer_sign_into", |b| {
+        b.iter(|| signer.sign_into(PATH, black_box(NONCE), &body, &mut signature))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, signing);
+criterion_main!(benches);
@@ -0,0 +1,50 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use onise::ws_models::{parse_incoming, WsIncomingMessage, WsMarketDataRef};
+
+/// A `book` snapshot with `levels` price levels per side.
+fn full_book(levels: usize) -> String {
+    let side = |start: f64, step: f64| {
+        (0..levels)
+            .map(|i| {
+                format!(
+                    r#"{{"price":"{:.1}","quantity":"{:.8}"}}"#,
+                    start + step * i as f64,
+                    0.01 + i as f64 / 1000.0
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+    format!(
+        r#"{{"channel":"book","type":"snapshot","symbol":"BTC/USD","bids":[{}],"asks":[{}]}}"#,
+        side(30000.0, -0.1),
+        side(30000.1, 0.1)
+    )
+}
+
+const TICKER: &str = r#"{"channel":"ticker","symbol":"BTC/USD","best_ask_price":"30300.1","best_ask_quantity":"1.2","best_bid_price":"30300.0","best_bid_quantity":"0.5","last_trade_price":"30303.2","last_trade_quantity":"0.00067643","volume_24h":"4412.73601799","vwap_24h":"30689.13205","trades_24h":38907,"low_24h":"29868.3","high_24h":"31631.0","open_24h":"30502.8"}"#;
+
+fn decode(c: &mut Criterion) {
+    let frames = [
+        ("book_1000", full_book(1000)),
+        ("book_25", full_book(25)),
+        ("ticker", TICKER.to_string()),
+    ];
+    for (name, frame) in &frames {
+        let mut group = c.benchmark_group(*name);
+        group.throughput(Throughput::Bytes(frame.len() as u64));
+        group.bench_function("untagged_owned", |b| {
+            b.iter(|| serde_json::from_str::<WsIncomingMessage>(black_box(frame)).unwrap())
+        });
+        group.bench_function("parse_incoming", |b| {
+            b.iter(|| parse_incoming(black_box(frame)).unwrap())
+        });
+        group.bench_function("borrowed", |b| {
+            b.iter(|| WsMarketDataRef::parse(black_box(frame)).unwrap().unwrap())
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, decode);
+criterion_main!(benches);
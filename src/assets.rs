@@ -0,0 +1,65 @@
+/// Kraken's legacy asset codes and the symbols everyone else uses for them.
+/// Assets listed more recently already use their plain symbol (e.g. "SOL",
+/// "USDT") and map to themselves.
+pub const LEGACY_ASSET_CODES: &[(&str, &str)] = &[
+    ("XXBT", "BTC"),
+    ("XBT", "BTC"),
+    ("XXDG", "DOGE"),
+    ("XDG", "DOGE"),
+    ("XETH", "ETH"),
+    ("XETC", "ETC"),
+    ("XLTC", "LTC"),
+    ("XMLN", "MLN"),
+    ("XREP", "REP"),
+    ("XXLM", "XLM"),
+    ("XXMR", "XMR"),
+    ("XXRP", "XRP"),
+    ("XZEC", "ZEC"),
+    ("ZAUD", "AUD"),
+    ("ZCAD", "CAD"),
+    ("ZEUR", "EUR"),
+    ("ZGBP", "GBP"),
+    ("ZJPY", "JPY"),
+    ("ZUSD", "USD"),
+];
+
+/// The display symbol for a Kraken asset code: "XXBT" → "BTC", "ZUSD" → "USD".
+///
+/// Balance-type suffixes are kept ("XBT.M" → "BTC.M", "ETH2.S" → "ETH2.S");
+/// codes that aren't legacy come back unchanged.
+pub fn display_name(code: &str) -> String {
+    let (asset, suffix) = match code.split_once('.') {
+        Some((asset, suffix)) => (asset, Some(suffix)),
+        None => (code, None),
+    };
+    let display = LEGACY_ASSET_CODES
+        .iter()
+        .find(|(legacy, _)| *legacy == asset)
+        .map_or(asset, |(_, display)| display);
+    match suffix {
+        Some(suffix) => format!("{display}.{suffix}"),
+        None => display.to_string(),
+    }
+}
+
+/// The Kraken asset code for a display symbol: "BTC" → "XXBT", "USD" → "ZUSD".
+/// The inverse of `display_name` for the codes Kraken uses in balances and
+/// ledgers; symbols without a legacy code come back unchanged.
+pub fn kraken_code(symbol: &str) -> String {
+    let (asset, suffix) = match symbol.split_once('.') {
+        Some((asset, suffix)) => (asset, Some(suffix)),
+        None => (symbol, None),
+    };
+    let asset = asset.to_ascii_uppercase();
+    // Suffixed balances ("XBT.M") use the short legacy form, plain ones the long form
+    let code = LEGACY_ASSET_CODES
+        .iter()
+        .filter(|(_, display)| *display == asset)
+        .map(|(legacy, _)| *legacy)
+        .find(|legacy| suffix.is_none() == (legacy.len() == 4))
+        .unwrap_or(asset.as_str());
+    match suffix {
+        Some(suffix) => format!("{code}.{suffix}"),
+        None => code.to_string(),
+    }
+}
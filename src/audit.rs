@@ -0,0 +1,165 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::logging;
+
+/// One private REST call, as written to the audit log. Sensitive parameters
+/// (`logging::SENSITIVE_PARAMS`) are already redacted.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditRecord {
+    /// When the call completed, in milliseconds since the Unix epoch
+    pub timestamp_ms: u64,
+    /// URI path, e.g. "/0/private/AddOrder"
+    pub endpoint: String,
+    /// Form parameters (excluding the nonce)
+    pub params: Vec<(String, String)>,
+    /// The nonce the request was signed with
+    pub nonce: u64,
+    /// The `X-Request-ID` that was sent
+    pub request_id: Option<String>,
+    /// HTTP status, if a response was received
+    pub status: Option<u16>,
+    /// Kraken error(s) or the transport error, if the call failed
+    pub error: Option<String>,
+    /// Every `txid`/`refid` in the result (order, trade, withdrawal and transfer IDs)
+    pub txids: Vec<String>,
+}
+
+impl AuditRecord {
+    pub(crate) fn new(endpoint: &str, params: &[(&str, &str)], nonce: u64) -> Self {
+        Self {
+            timestamp_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_millis() as u64),
+            endpoint: endpoint.to_string(),
+            params: logging::sanitize_params(params),
+            nonce,
+            request_id: None,
+            status: None,
+            error: None,
+            txids: Vec::new(),
+        }
+    }
+
+    /// Fill in the outcome from the response envelope.
+    pub(crate) fn with_response(mut self, request_id: &str, status: u16, body: &[u8]) -> Self {
+        self.request_id = Some(request_id.to_string());
+        self.status = Some(status);
+        match serde_json::from_slice::<Value>(body) {
+            Ok(envelope) => {
+                let errors: Vec<&str> = envelope["error"]
+                    .as_array()
+                    .map(|errors| errors.iter().filter_map(Value::as_str).collect())
+                    .unwrap_or_default();
+                if !errors.is_empty() {
+                    self.error = Some(errors.join(", "));
+                }
+                collect_txids(&envelope["result"], &mut self.txids);
+            }
+            Err(e) => self.error = Some(format!("unparseable response: {e}")),
+        }
+        self
+    }
+
+    /// Record a failure that produced no response.
+    pub(crate) fn with_error(mut self, request_id: Option<&str>, error: String) -> Self {
+        self.request_id = request_id.map(str::to_string);
+        self.error = Some(error);
+        self
+    }
+
+    /// This record as a single line of JSON (no trailing newline).
+    pub fn to_json_line(&self) -> String {
+        serde_json::to_string(self).expect("audit records always serialize")
+    }
+}
+
+fn collect_txids(value: &Value, txids: &mut Vec<String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                match (key.as_str(), value) {
+                    ("txid" | "refid", Value::String(id)) => txids.push(id.clone()),
+                    ("txid" | "refid", Value::Array(ids)) => {
+                        txids.extend(ids.iter().filter_map(Value::as_str).map(str::to_string))
+                    }
+                    _ => collect_txids(value, txids),
+                }
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|item| collect_txids(item, txids)),
+        _ => {}
+    }
+}
+
+/// Receives an `AuditRecord` for every private request once enabled with
+/// `KrakenClient::with_audit_sink`. Implemented for closures.
+pub trait AuditSink: Send + Sync {
+    fn record(&self, record: &AuditRecord);
+
+    /// Push out anything buffered, e.g. on shutdown. Does nothing by default.
+    fn flush(&self) {}
+}
+
+impl<F> AuditSink for F
+where
+    F: Fn(&AuditRecord) + Send + Sync,
+{
+    fn record(&self, record: &AuditRecord) {
+        self(record)
+    }
+}
+
+/// Writes each `AuditRecord` as one JSON line to `W`, flushing after every
+/// record. Write failures are reported through `tracing` and never fail the call.
+pub struct AuditLog<W> {
+    writer: Mutex<W>,
+}
+
+impl<W: Write + Send> AuditLog<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+        }
+    }
+}
+
+impl AuditLog<File> {
+    /// Append to `path`, creating it if needed. Existing records are never touched.
+    pub fn append_to_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self::new(file))
+    }
+}
+
+impl<W: Write + Send> AuditSink for AuditLog<W> {
+    fn record(&self, record: &AuditRecord) {
+        let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        let line = record.to_json_line();
+        if let Err(e) = writeln!(writer, "{line}").and_then(|_| writer.flush()) {
+            tracing::error!(error = %e, record = %line, "failed to write audit record");
+        }
+    }
+
+    fn flush(&self) {
+        let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(e) = writer.flush() {
+            tracing::error!(error = %e, "failed to flush audit log");
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct AuditHandle(pub Arc<dyn AuditSink>);
+
+impl std::fmt::Debug for AuditHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("AuditSink")
+    }
+}
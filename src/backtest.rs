@@ -0,0 +1,163 @@
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+
+use futures_util::StreamExt;
+
+use crate::error::KrakenResult;
+use crate::exchange::{ExchangeClient, Side};
+use crate::replay::Replay;
+use crate::simulated::{SimulatedExchange, SimulatedFill};
+use crate::ws_models::WsTickerMessage;
+
+/// A trading strategy driven by ticker updates.
+///
+/// It only talks to the venue through `ExchangeClient`, so the same strategy
+/// runs against `SimulatedExchange` in a `Backtester` and against the REST or
+/// WebSocket client live.
+pub trait Strategy: Send {
+    /// React to one ticker update. An error aborts the backtest; handle order
+    /// rejections you expect (e.g. insufficient funds) inside the strategy.
+    fn on_ticker<E: ExchangeClient>(
+        &mut self,
+        exchange: &E,
+        ticker: &WsTickerMessage,
+    ) -> impl Future<Output = KrakenResult<()>> + Send;
+}
+
+/// Runs a `Strategy` over a recording with a `SimulatedExchange` as the venue.
+///
+/// Each recorded `ticker` message whose symbol is a pair of the exchange (see
+/// `SimulatedExchange::with_pair`; use the WebSocket symbol, e.g. "BTC/USD")
+/// first moves that pair's quote, filling any resting orders it crosses, and
+/// is then handed to the strategy. Tickers for other symbols are skipped.
+///
+/// ```no_run
+/// # async fn run(mut strategy: impl onise::backtest::Strategy) -> onise::error::KrakenResult<()> {
+/// use onise::backtest::Backtester;
+/// use onise::replay::Replay;
+/// use onise::simulated::SimulatedExchange;
+///
+/// let exchange = SimulatedExchange::new()
+///     .with_pair("BTC/USD", "XXBT", "ZUSD")
+///     .with_balance("ZUSD", 10_000.0);
+/// let report = Backtester::new(exchange)
+///     .run(Replay::open("btc-usd.ndjson").await?, &mut strategy)
+///     .await?;
+/// println!("PnL {:.2} USD over {} fills", report.total_pnl(), report.fills.len());
+/// # Ok(())
+/// # }
+/// ```
+pub struct Backtester {
+    exchange: SimulatedExchange,
+}
+
+/// Profit and loss of one pair over a backtest, in its quote currency.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PairPnl {
+    /// Base currency bought minus sold
+    pub net_volume: f64,
+    /// Quote currency received minus spent, fees included
+    pub cash_flow: f64,
+    /// Fees paid
+    pub fees: f64,
+    /// Mid price at the last ticker seen for the pair
+    pub last_price: Option<f64>,
+    /// `cash_flow` plus `net_volume` marked at `last_price`
+    pub pnl: f64,
+}
+
+/// The outcome of `Backtester::run`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BacktestReport {
+    /// Ticker updates fed to the strategy
+    pub ticks: u64,
+    /// Every fill, oldest first
+    pub fills: Vec<SimulatedFill>,
+    /// Per-pair results
+    pub pairs: BTreeMap<String, PairPnl>,
+    pub starting_balances: BTreeMap<String, f64>,
+    pub ending_balances: BTreeMap<String, f64>,
+}
+
+impl BacktestReport {
+    /// Sum of every pair's `pnl`. Only meaningful when the pairs share a quote currency.
+    pub fn total_pnl(&self) -> f64 {
+        self.pairs.values().map(|pair| pair.pnl).sum()
+    }
+
+    /// Sum of every pair's fees.
+    pub fn total_fees(&self) -> f64 {
+        self.pairs.values().map(|pair| pair.fees).sum()
+    }
+}
+
+impl Backtester {
+    pub fn new(exchange: SimulatedExchange) -> Self {
+        Self { exchange }
+    }
+
+    /// The simulated venue, e.g. to inspect open orders after a run.
+    pub fn exchange(&self) -> &SimulatedExchange {
+        &self.exchange
+    }
+
+    /// Play `replay` through the exchange and `strategy` and report the fills
+    /// and PnL. Fills from earlier runs on the same exchange are not counted.
+    pub async fn run<S: Strategy>(
+        &self,
+        replay: Replay,
+        strategy: &mut S,
+    ) -> KrakenResult<BacktestReport> {
+        let starting_balances = self.exchange.all_balances();
+        let earlier_fills = self.exchange.fills().len();
+        let mut last_prices = HashMap::new();
+        let mut ticks = 0;
+
+        let mut tickers = replay.ticker_stream();
+        while let Some(ticker) = tickers.next().await {
+            let (Ok(bid), Ok(ask)) = (
+                ticker.best_bid_price.parse::<f64>(),
+                ticker.best_ask_price.parse::<f64>(),
+            ) else {
+                tracing::warn!(symbol = %ticker.symbol, "skipping ticker without a usable bid/ask");
+                continue;
+            };
+            if self.exchange.set_quote(&ticker.symbol, bid, ask).is_err() {
+                continue;
+            }
+            last_prices.insert(ticker.symbol.clone(), (bid + ask) / 2.0);
+            ticks += 1;
+            strategy.on_ticker(&self.exchange, &ticker).await?;
+        }
+
+        let fills = self.exchange.fills().split_off(earlier_fills);
+        let mut pairs: BTreeMap<String, PairPnl> = BTreeMap::new();
+        for fill in &fills {
+            let pair = pairs.entry(fill.pair.clone()).or_default();
+            let notional = fill.price * fill.volume;
+            match fill.side {
+                Side::Buy => {
+                    pair.net_volume += fill.volume;
+                    pair.cash_flow -= notional + fill.fee;
+                }
+                Side::Sell => {
+                    pair.net_volume -= fill.volume;
+                    pair.cash_flow += notional - fill.fee;
+                }
+            }
+            pair.fees += fill.fee;
+        }
+        for (symbol, pair) in &mut pairs {
+            pair.last_price = last_prices.get(symbol).copied();
+            pair.pnl = pair.cash_flow + pair.net_volume * pair.last_price.unwrap_or(0.0);
+        }
+
+        Ok(BacktestReport {
+            ticks,
+            fills,
+            pairs,
+            starting_balances,
+            ending_balances: self.exchange.all_balances(),
+        })
+    }
+}
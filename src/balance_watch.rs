@@ -0,0 +1,179 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use futures_util::stream::{self, Stream, StreamExt};
+
+use crate::assets;
+use crate::feed::FeedStream;
+use crate::models::LedgerInfo;
+use crate::rest_client::AuthenticatedClient;
+use crate::ws_models::WsBalancesMessage;
+
+/// How far back `BalanceWatcher` looks for the ledger entry behind a change
+/// unless `with_ledger_window` says otherwise.
+pub const DEFAULT_LEDGER_WINDOW: Duration = Duration::from_secs(300);
+
+/// One asset's balance moving, as yielded by `BalanceWatcher::watch`.
+#[derive(Debug, Clone)]
+pub struct BalanceDelta {
+    /// Asset as the balance feed names it ("XXBT" over REST, "BTC" over WebSocket)
+    pub asset: String,
+    /// Balance before the change; "0" for an asset that just appeared
+    pub previous: String,
+    pub current: String,
+    pub cause: BalanceCause,
+}
+
+impl BalanceDelta {
+    /// `current - previous`, or `None` if either doesn't parse.
+    pub fn change(&self) -> Option<f64> {
+        Some(self.current.parse::<f64>().ok()? - self.previous.parse::<f64>().ok()?)
+    }
+
+    /// `true` when no ledger entry accounts for the change.
+    pub fn is_unexplained(&self) -> bool {
+        matches!(self.cause, BalanceCause::Unexplained)
+    }
+}
+
+/// Why a balance changed.
+#[derive(Debug, Clone)]
+pub enum BalanceCause {
+    /// The recent ledger entry whose resulting balance is the new balance
+    Ledger { id: String, entry: Box<LedgerInfo> },
+    /// No recent ledger entry matches (or the ledger couldn't be read)
+    Unexplained,
+}
+
+/// Turns a balances feed into per-asset `BalanceDelta` events, each correlated
+/// with the `Ledgers` entry that produced it, so unexpected movements stand out.
+///
+/// Feed it `KrakenWsClient::balances_stream` or, where the WebSocket isn't an
+/// option, `RestPoller::balances_stream`. The first message is the baseline and
+/// yields nothing. Assets missing from a later message are taken as unchanged,
+/// since WebSocket updates only carry the assets that moved.
+///
+/// ```no_run
+/// # async fn run(client: onise::AuthenticatedClient, ws: onise::ws_client::KrakenWsClient) {
+/// use futures_util::StreamExt;
+/// use onise::balance_watch::BalanceWatcher;
+///
+/// let mut deltas = BalanceWatcher::new(client).watch(ws.balances_stream());
+/// while let Some(delta) = deltas.next().await {
+///     if delta.is_unexplained() {
+///         eprintln!("unexpected {} movement: {} -> {}", delta.asset, delta.previous, delta.current);
+///     }
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct BalanceWatcher {
+    client: AuthenticatedClient,
+    ledger_window: Duration,
+}
+
+impl BalanceWatcher {
+    pub fn new(client: AuthenticatedClient) -> Self {
+        Self {
+            client,
+            ledger_window: DEFAULT_LEDGER_WINDOW,
+        }
+    }
+
+    /// Only consider ledger entries from the last `window` as causes.
+    pub fn with_ledger_window(mut self, window: Duration) -> Self {
+        self.ledger_window = window;
+        self
+    }
+
+    /// Diff successive `balances` messages into `BalanceDelta`s.
+    pub fn watch<S>(self, balances: S) -> FeedStream<BalanceDelta>
+    where
+        S: Stream<Item = WsBalancesMessage> + Send + 'static,
+    {
+        let state = (
+            self,
+            balances.boxed(),
+            None::<HashMap<String, String>>,
+            VecDeque::new(),
+        );
+        let deltas = stream::unfold(
+            state,
+            |(watcher, mut balances, mut last, mut pending)| async move {
+                loop {
+                    if let Some(delta) = pending.pop_front() {
+                        return Some((delta, (watcher, balances, last, pending)));
+                    }
+                    let msg = balances.next().await?;
+                    let Some(known) = last.as_mut() else {
+                        last = Some(msg.balances);
+                        continue;
+                    };
+                    let changed: Vec<(String, String, String)> = msg
+                        .balances
+                        .into_iter()
+                        .filter_map(|(asset, current)| {
+                            let previous = known.insert(asset.clone(), current.clone());
+                            let previous = previous.unwrap_or_else(|| "0".to_string());
+                            (!same_amount(&previous, &current))
+                                .then_some((asset, previous, current))
+                        })
+                        .collect();
+                    if changed.is_empty() {
+                        continue;
+                    }
+                    let ledger = watcher.recent_ledger().await;
+                    pending.extend(changed.into_iter().map(|(asset, previous, current)| {
+                        let cause = cause_of(&ledger, &asset, &current);
+                        BalanceDelta {
+                            asset,
+                            previous,
+                            current,
+                            cause,
+                        }
+                    }));
+                }
+            },
+        );
+        FeedStream::from_stream(deltas)
+    }
+
+    async fn recent_ledger(&self) -> Vec<(String, LedgerInfo)> {
+        let start = SystemTime::now()
+            .checked_sub(self.ledger_window)
+            .and_then(|start| start.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |since| since.as_secs())
+            .to_string();
+        match self.client.get_ledgers(&[("start", &start)]).await {
+            Ok(ledgers) => ledgers.ledger.into_iter().collect(),
+            Err(e) => {
+                tracing::warn!(error = %e, "could not read ledgers for balance changes");
+                Vec::new()
+            }
+        }
+    }
+}
+
+/// The most recent entry for `asset` that left it at `current`.
+fn cause_of(ledger: &[(String, LedgerInfo)], asset: &str, current: &str) -> BalanceCause {
+    let asset = assets::display_name(asset);
+    ledger
+        .iter()
+        .filter(|(_, entry)| entry.display_asset() == asset && same_amount(&entry.balance, current))
+        .max_by(|(_, a), (_, b)| a.time.total_cmp(&b.time))
+        .map_or(BalanceCause::Unexplained, |(id, entry)| {
+            BalanceCause::Ledger {
+                id: id.clone(),
+                entry: Box::new(entry.clone()),
+            }
+        })
+}
+
+/// Compare decimal strings numerically ("1.50" == "1.5000"), falling back to
+/// the text when either doesn't parse.
+fn same_amount(a: &str, b: &str) -> bool {
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
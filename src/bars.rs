@@ -0,0 +1,235 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+use futures_util::stream::{self, Stream, StreamExt};
+
+use crate::feed::FeedStream;
+use crate::ws_models::{TradeData, WsTradesMessage};
+
+/// When a bar closes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BarSpec {
+    /// Every `Duration` of exchange time, aligned to the Unix epoch. Whole
+    /// seconds; intervals without trades produce no bar.
+    Time(Duration),
+    /// Every this much base-currency volume
+    Volume(f64),
+    /// Every this much quote-currency notional (price × quantity)
+    Dollar(f64),
+}
+
+/// One bar aggregated from public trades.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bar {
+    pub symbol: String,
+    /// Time of the first trade (Unix seconds), or the interval start for time bars
+    pub start: u64,
+    /// Time of the last trade (Unix seconds)
+    pub end: u64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    /// Base-currency volume
+    pub volume: f64,
+    /// Quote-currency notional
+    pub notional: f64,
+    /// Trades that contributed, counting a trade split across bars in each
+    pub trades: u64,
+}
+
+impl Bar {
+    fn open(symbol: &str, start: u64, time: u64, price: f64) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            start,
+            end: time,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume: 0.0,
+            notional: 0.0,
+            trades: 0,
+        }
+    }
+
+    fn add(&mut self, time: u64, price: f64, quantity: f64) {
+        self.end = time;
+        self.high = self.high.max(price);
+        self.low = self.low.min(price);
+        self.close = price;
+        self.volume += quantity;
+        self.notional += price * quantity;
+        self.trades += 1;
+    }
+
+    /// Volume-weighted average price.
+    pub fn vwap(&self) -> f64 {
+        if self.volume > 0.0 {
+            self.notional / self.volume
+        } else {
+            self.close
+        }
+    }
+}
+
+/// Builds bars for one symbol from its trades, in arrival order.
+///
+/// Volume and dollar bars close exactly at the threshold: a trade that crosses
+/// it is split, its remainder opening the next bar. Time bars close when a
+/// trade from a later interval arrives.
+#[derive(Debug, Clone)]
+pub struct BarBuilder {
+    symbol: String,
+    spec: BarSpec,
+    current: Option<Bar>,
+}
+
+impl BarBuilder {
+    pub fn new(symbol: impl Into<String>, spec: BarSpec) -> Self {
+        Self {
+            symbol: symbol.into(),
+            spec,
+            current: None,
+        }
+    }
+
+    /// Add one trade, returning the bars it closed. Trades whose price or
+    /// quantity doesn't parse are skipped.
+    pub fn push(&mut self, trade: &TradeData) -> Vec<Bar> {
+        let (Ok(price), Ok(quantity)) = (trade.price.parse::<f64>(), trade.quantity.parse::<f64>())
+        else {
+            tracing::warn!(?trade, "skipping unparseable trade");
+            return Vec::new();
+        };
+        let mut closed = Vec::new();
+        match self.spec {
+            BarSpec::Time(interval) => {
+                let secs = interval.as_secs().max(1);
+                let start = trade.time - trade.time % secs;
+                if self.current.as_ref().is_some_and(|bar| bar.start != start) {
+                    closed.extend(self.current.take());
+                }
+                self.current
+                    .get_or_insert_with(|| Bar::open(&self.symbol, start, trade.time, price))
+                    .add(trade.time, price, quantity);
+            }
+            BarSpec::Volume(threshold) => {
+                self.fill(trade.time, price, quantity, threshold, false, &mut closed)
+            }
+            BarSpec::Dollar(threshold) => {
+                self.fill(trade.time, price, quantity, threshold, true, &mut closed)
+            }
+        }
+        closed
+    }
+
+    /// Close and return the bar in progress, if any.
+    pub fn flush(&mut self) -> Option<Bar> {
+        self.current.take()
+    }
+
+    /// Add `quantity` to bars closing at `threshold` of volume, or of notional
+    /// if `dollar`.
+    fn fill(
+        &mut self,
+        time: u64,
+        price: f64,
+        mut quantity: f64,
+        threshold: f64,
+        dollar: bool,
+        closed: &mut Vec<Bar>,
+    ) {
+        let per_unit = if dollar { price } else { 1.0 };
+        if threshold <= 0.0 || per_unit <= 0.0 {
+            return;
+        }
+        // Remainders this small are float rounding, not volume
+        let dust = quantity * 1e-12;
+        while quantity > dust {
+            let bar = self
+                .current
+                .get_or_insert_with(|| Bar::open(&self.symbol, time, time, price));
+            let filled = if dollar { bar.notional } else { bar.volume };
+            let room = (threshold - filled) / per_unit;
+            let take = if quantity - room <= dust {
+                quantity
+            } else {
+                room
+            };
+            bar.add(time, price, take);
+            quantity -= take;
+            if take >= room - dust {
+                closed.extend(self.current.take());
+            }
+        }
+    }
+}
+
+/// Aggregate a trades feed (`KrakenWsClient::trades_stream`,
+/// `Replay::trades_stream`, ...) into bars, per symbol. The bar in progress
+/// for each symbol is yielded when the feed ends.
+pub fn bars<S>(trades: S, spec: BarSpec) -> FeedStream<Bar>
+where
+    S: Stream<Item = WsTradesMessage> + Send + 'static,
+{
+    let state = (
+        trades.boxed(),
+        HashMap::<String, BarBuilder>::new(),
+        VecDeque::new(),
+        false,
+    );
+    let bars = stream::unfold(
+        state,
+        move |(mut trades, mut builders, mut ready, mut done)| async move {
+            loop {
+                if let Some(bar) = ready.pop_front() {
+                    return Some((bar, (trades, builders, ready, done)));
+                }
+                if done {
+                    return None;
+                }
+                match trades.next().await {
+                    Some(msg) => {
+                        let builder = builders
+                            .entry(msg.symbol.clone())
+                            .or_insert_with(|| BarBuilder::new(msg.symbol.clone(), spec));
+                        for trade in &msg.trades {
+                            ready.extend(builder.push(trade));
+                        }
+                    }
+                    None => {
+                        ready.extend(builders.values_mut().filter_map(BarBuilder::flush));
+                        done = true;
+                    }
+                }
+            }
+        },
+    );
+    FeedStream::from_stream(bars)
+}
+
+/// `bars(trades, BarSpec::Time(interval))`.
+pub fn time_bars<S>(trades: S, interval: Duration) -> FeedStream<Bar>
+where
+    S: Stream<Item = WsTradesMessage> + Send + 'static,
+{
+    bars(trades, BarSpec::Time(interval))
+}
+
+/// `bars(trades, BarSpec::Volume(volume))`.
+pub fn volume_bars<S>(trades: S, volume: f64) -> FeedStream<Bar>
+where
+    S: Stream<Item = WsTradesMessage> + Send + 'static,
+{
+    bars(trades, BarSpec::Volume(volume))
+}
+
+/// `bars(trades, BarSpec::Dollar(notional))`.
+pub fn dollar_bars<S>(trades: S, notional: f64) -> FeedStream<Bar>
+where
+    S: Stream<Item = WsTradesMessage> + Send + 'static,
+{
+    bars(trades, BarSpec::Dollar(notional))
+}
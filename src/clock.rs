@@ -0,0 +1,89 @@
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Where time-dependent code reads the time: cache TTLs, quote staleness and
+/// token expiry all go through a `Clock`, so tests can swap in a `MockClock`
+/// and move time by hand instead of sleeping.
+pub trait Clock: Send + Sync + fmt::Debug {
+    /// Monotonic time, for ages and deadlines.
+    fn now(&self) -> Instant;
+
+    /// Wall-clock time, for timestamps.
+    fn system_time(&self) -> SystemTime;
+}
+
+/// A `Clock` shared between the components that read it.
+pub type SharedClock = Arc<dyn Clock>;
+
+/// The real clock: `Instant::now()` and `SystemTime::now()`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// The `SystemClock`, shared.
+pub fn system() -> SharedClock {
+    Arc::new(SystemClock)
+}
+
+/// A clock that only moves when told to. Clones share the same time.
+///
+/// ```
+/// use std::time::Duration;
+/// use onise::clock::{Clock, MockClock};
+///
+/// let clock = MockClock::new();
+/// let start = clock.now();
+/// clock.advance(Duration::from_secs(30));
+/// assert_eq!(clock.now() - start, Duration::from_secs(30));
+/// ```
+#[derive(Debug, Clone)]
+pub struct MockClock {
+    now: Arc<Mutex<(Instant, SystemTime)>>,
+}
+
+impl MockClock {
+    /// A clock stopped at the current time.
+    pub fn new() -> Self {
+        Self {
+            now: Arc::new(Mutex::new((Instant::now(), SystemTime::now()))),
+        }
+    }
+
+    /// Move both the monotonic and the wall-clock time forward by `by`.
+    pub fn advance(&self, by: Duration) {
+        let mut now = self.now.lock().unwrap();
+        now.0 += by;
+        now.1 += by;
+    }
+
+    /// Set the wall-clock time, leaving the monotonic time where it is.
+    pub fn set_system_time(&self, time: SystemTime) {
+        self.now.lock().unwrap().1 = time;
+    }
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.now.lock().unwrap().0
+    }
+
+    fn system_time(&self) -> SystemTime {
+        self.now.lock().unwrap().1
+    }
+}
@@ -0,0 +1,243 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::environment::{Endpoints, Environment};
+use crate::error::{KrakenError, KrakenResult};
+use crate::rest_client::AuthenticatedClient;
+use crate::risk::RiskLimits;
+use crate::session::KrakenSession;
+use crate::strategy_limits::StrategyLimits;
+use crate::ws_client::{KrakenWsClient, SubscriptionBudget};
+use crate::ws_models::WsSubscriptionPayload;
+pub use crate::ws_reconnect::ReconnectPolicy;
+
+/// Everything a deployment varies about a `KrakenSession`, read from a JSON
+/// (or, with the `toml` feature, TOML) file so it can be changed without
+/// recompiling. Every section is optional:
+///
+/// ```json
+/// {
+///   "environment": {
+///     "rest": "https://api.kraken.com",
+///     "ws_public": "wss://ws.kraken.com/v2",
+///     "ws_auth": "wss://ws-auth.kraken.com/v2"
+///   },
+///   "credentials": { "env": { "api_key": "KRAKEN_API_KEY", "api_secret": "KRAKEN_API_SECRET" } },
+///   "read_only": false,
+///   "subscriptions": [
+///     { "name": "book", "symbol": "BTC/USD", "depth": 10 },
+///     { "name": "ticker", "symbol": "ETH/USD" }
+///   ],
+///   "rate_limits": {
+///     "max_orders_per_minute": 60,
+///     "max_subscriptions_per_connection": 50,
+///     "ack_deadline_ms": 2000
+///   },
+///   "risk_limits": {
+///     "max_order_notional": 50000,
+///     "max_position": { "BTC/USD": 2.5 },
+///     "max_total_exposure": 250000
+///   },
+///   "reconnect": { "initial_delay_ms": 500, "max_delay_ms": 30000, "max_attempts": 10 }
+/// }
+/// ```
+///
+/// - `environment`: URLs, Kraken's production ones when left out.
+/// - `credentials`: where the API key and secret are read from, never the
+///   secrets themselves: environment variables (`env`, defaulting to
+///   `KRAKEN_API_KEY` / `KRAKEN_API_SECRET`) or a JSON file holding
+///   `api_key` and `api_secret` (`"credentials": { "file": "/run/secrets/kraken.json" }`).
+/// - `subscriptions`: WebSocket subscriptions in their wire form, sent by
+///   `connect_ws`.
+/// - `rate_limits`: the default `StrategyLimits` order rate, the
+///   `SubscriptionBudget` per connection, and the session's ack deadline.
+/// - `risk_limits`: a `RiskLimits`, for a `risk::RiskGuard`.
+/// - `reconnect`: how `connect_ws` retries a failed connect, and how the
+///   socket it returns reconnects after a drop.
+///
+/// Unknown keys are rejected, so a typo fails loudly rather than silently
+/// leaving a limit unset.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KrakenSessionConfig {
+    pub environment: Option<Endpoints>,
+    pub credentials: CredentialsSource,
+    pub read_only: bool,
+    pub subscriptions: Vec<WsSubscriptionPayload>,
+    pub rate_limits: RateLimitConfig,
+    pub risk_limits: RiskLimits,
+    pub reconnect: ReconnectPolicy,
+}
+
+/// Where `KrakenSessionConfig` finds the API key and secret.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum CredentialsSource {
+    /// Environment variables holding the key and the secret
+    Env {
+        #[serde(default = "default_api_key_var")]
+        api_key: String,
+        #[serde(default = "default_api_secret_var")]
+        api_secret: String,
+    },
+    /// A JSON file `{"api_key": "...", "api_secret": "..."}`
+    File(PathBuf),
+}
+
+impl Default for CredentialsSource {
+    fn default() -> Self {
+        CredentialsSource::Env {
+            api_key: default_api_key_var(),
+            api_secret: default_api_secret_var(),
+        }
+    }
+}
+
+fn default_api_key_var() -> String {
+    "KRAKEN_API_KEY".to_string()
+}
+
+fn default_api_secret_var() -> String {
+    "KRAKEN_API_SECRET".to_string()
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CredentialsFile {
+    api_key: String,
+    api_secret: String,
+}
+
+impl CredentialsSource {
+    /// The API key and secret.
+    pub fn resolve(&self) -> KrakenResult<(String, String)> {
+        match self {
+            CredentialsSource::Env {
+                api_key,
+                api_secret,
+            } => Ok((env_var(api_key)?, env_var(api_secret)?)),
+            CredentialsSource::File(path) => {
+                let file: CredentialsFile = serde_json::from_slice(&fs::read(path)?)?;
+                Ok((file.api_key, file.api_secret))
+            }
+        }
+    }
+}
+
+fn env_var(name: &str) -> KrakenResult<String> {
+    env::var(name)
+        .map_err(|_| KrakenError::InvalidUsage(format!("credentials variable {name} is not set")))
+}
+
+/// The `rate_limits` section of a `KrakenSessionConfig`. Unset limits aren't
+/// applied.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    /// Default `StrategyLimits::max_orders_per_minute`
+    pub max_orders_per_minute: Option<u32>,
+    /// Subscriptions `connect_ws` lets one connection carry; more are refused
+    pub max_subscriptions_per_connection: Option<usize>,
+    /// `KrakenSession::with_ack_deadline`, in milliseconds
+    pub ack_deadline_ms: Option<u64>,
+}
+
+impl KrakenSessionConfig {
+    /// Read a config file: TOML if its extension is `.toml` (with the `toml`
+    /// feature), JSON otherwise.
+    pub fn from_file(path: impl AsRef<Path>) -> KrakenResult<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        if path.extension().is_some_and(|ext| ext == "toml") {
+            return Self::from_toml(&text);
+        }
+        Self::from_json(&text)
+    }
+
+    pub fn from_json(json: &str) -> KrakenResult<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    #[cfg(feature = "toml")]
+    pub fn from_toml(toml: &str) -> KrakenResult<Self> {
+        toml::from_str(toml).map_err(|e| KrakenError::InvalidUsage(format!("config: {e}")))
+    }
+
+    #[cfg(not(feature = "toml"))]
+    pub fn from_toml(_toml: &str) -> KrakenResult<Self> {
+        Err(KrakenError::InvalidUsage(
+            "TOML config files need the `toml` feature".to_string(),
+        ))
+    }
+
+    pub fn environment(&self) -> Environment {
+        self.environment
+            .clone()
+            .map_or(Environment::Production, Environment::Custom)
+    }
+
+    /// A REST client for `environment` with the configured credentials and
+    /// `read_only` setting.
+    pub fn rest_client(&self) -> KrakenResult<AuthenticatedClient> {
+        let (api_key, api_secret) = self.credentials.resolve()?;
+        Ok(AuthenticatedClient::new(api_key, api_secret, None)?
+            .with_environment(self.environment())
+            .with_read_only(self.read_only))
+    }
+
+    /// A REST-only session over `rest_client`, with the configured ack
+    /// deadline; add a socket with `KrakenSession::with_ws`.
+    pub fn session(&self) -> KrakenResult<KrakenSession> {
+        let session = KrakenSession::new(self.rest_client()?);
+        Ok(match self.rate_limits.ack_deadline_ms {
+            Some(ms) => session.with_ack_deadline(Duration::from_millis(ms)),
+            None => session,
+        })
+    }
+
+    /// Connect to the public WebSocket of `environment`, retrying per
+    /// `reconnect`, and send every configured subscription within the
+    /// configured budget. Fails with the last connect error once the
+    /// attempts run out. The socket reconnects and resubscribes per
+    /// `reconnect` when it drops, and refuses trading requests if `read_only`.
+    pub async fn connect_ws(&self) -> KrakenResult<KrakenWsClient> {
+        let environment = self.environment();
+        let mut attempt = 0;
+        let mut ws = loop {
+            attempt += 1;
+            match KrakenWsClient::connect_public(&environment).await {
+                Ok(ws) => {
+                    break ws
+                        .with_reconnect(self.reconnect.clone())
+                        .with_read_only(self.read_only)
+                }
+                Err(e) => match self.reconnect.delay(attempt) {
+                    Some(delay) => {
+                        tracing::warn!(attempt, error = %e, retry_in = ?delay, "WebSocket connect failed");
+                        tokio::time::sleep(delay).await;
+                    }
+                    None => return Err(e),
+                },
+            }
+        };
+        if let Some(max) = self.rate_limits.max_subscriptions_per_connection {
+            ws = ws.with_subscription_budget(SubscriptionBudget::reject(max));
+        }
+        for subscription in &self.subscriptions {
+            ws.subscribe(subscription.clone(), None).await?;
+        }
+        Ok(ws)
+    }
+
+    /// Default limits for a `strategy_limits::StrategyThrottle`.
+    pub fn strategy_limits(&self) -> StrategyLimits {
+        StrategyLimits {
+            max_orders_per_minute: self.rate_limits.max_orders_per_minute,
+            ..StrategyLimits::default()
+        }
+    }
+}
@@ -0,0 +1,260 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::assets;
+use crate::error::{KrakenError, KrakenResult};
+use crate::models::{AssetPairsResponse, TickerResponse};
+use crate::numeric::Amount;
+use crate::rest_client::{AuthenticatedClient, KrakenClient};
+
+/// One trade along a `ConversionRoute`: from `from` to `to` through `pair`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConversionLeg {
+    /// Pair name as `AssetPairs` and `Ticker` key it, e.g. `"DOTUSD"`
+    pub pair: String,
+    /// Display symbol of the asset given up
+    pub from: String,
+    /// Display symbol of the asset received
+    pub to: String,
+    /// `true` when `from` is the pair's base, i.e. the leg sells at the bid;
+    /// otherwise it buys the base at the ask
+    pub sell: bool,
+}
+
+/// A chain of pairs converting one asset into another, e.g. DOT → EUR as
+/// DOT/USD then EUR/USD.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConversionRoute {
+    pub from: String,
+    pub to: String,
+    pub legs: Vec<ConversionLeg>,
+}
+
+impl ConversionRoute {
+    /// Pair names along the route, for a `Ticker` request.
+    pub fn pairs(&self) -> Vec<&str> {
+        self.legs.iter().map(|leg| leg.pair.as_str()).collect()
+    }
+
+    /// Units of `to` one unit of `from` converts into at `tickers`: each leg
+    /// sells at the bid or buys at the ask, so the rate is what trading the
+    /// route would get at the touch, before fees. Empty for an asset into
+    /// itself, which converts at 1.
+    pub fn rate(&self, tickers: &TickerResponse) -> KrakenResult<f64> {
+        let mut rate = 1.0;
+        for leg in &self.legs {
+            let ticker = tickers.tickers.get(&leg.pair).ok_or_else(|| {
+                KrakenError::InvalidUsage(format!("no Ticker entry for {}", leg.pair))
+            })?;
+            if leg.sell {
+                rate *= Amount(&ticker.b[0]).to_f64_lossy()?;
+            } else {
+                let ask = Amount(&ticker.a[0]).to_f64_lossy()?;
+                if ask <= 0.0 {
+                    return Err(KrakenError::InvalidUsage(format!(
+                        "{} has no ask to buy at",
+                        leg.pair
+                    )));
+                }
+                rate /= ask;
+            }
+        }
+        Ok(rate)
+    }
+}
+
+/// Which assets trade against which, from `AssetPairs`, for finding
+/// conversion routes between assets that don't share a pair.
+///
+/// Assets are matched by display symbol (`assets::display_name`), so `"DOT"`,
+/// `"EUR"`, `"ZEUR"` and `"XXBT"` all work. Dark pool (`.d`) and disabled
+/// pairs are left out.
+#[derive(Debug, Clone, Default)]
+pub struct ConversionGraph {
+    /// Display symbol → (pair, other asset, whether this asset is the base)
+    edges: HashMap<String, Vec<(String, String, bool)>>,
+}
+
+impl ConversionGraph {
+    pub fn from_asset_pairs(pairs: &AssetPairsResponse) -> Self {
+        let mut graph = Self::default();
+        let mut names: Vec<_> = pairs.pairs.keys().collect();
+        // Deterministic routes when several pairs tie
+        names.sort();
+        for name in names {
+            let info = &pairs.pairs[name];
+            if name.ends_with(".d") || info.status.as_deref() == Some("disabled") {
+                continue;
+            }
+            let base = assets::display_name(&info.base);
+            let quote = assets::display_name(&info.quote);
+            graph
+                .edges
+                .entry(base.clone())
+                .or_default()
+                .push((name.clone(), quote.clone(), true));
+            graph
+                .edges
+                .entry(quote)
+                .or_default()
+                .push((name.clone(), base, false));
+        }
+        graph
+    }
+
+    /// The route from `from` to `to` through the fewest pairs, or `None` if
+    /// the two aren't connected.
+    pub fn route(&self, from: &str, to: &str) -> Option<ConversionRoute> {
+        let (from, to) = (symbol(from), symbol(to));
+        let mut came_from: HashMap<String, ConversionLeg> = HashMap::new();
+        let mut visited = HashSet::from([from.clone()]);
+        let mut queue = VecDeque::from([from.clone()]);
+        while let Some(asset) = queue.pop_front() {
+            if asset == to {
+                let mut legs = Vec::new();
+                let mut at = to.clone();
+                while let Some(leg) = came_from.get(&at) {
+                    at = leg.from.clone();
+                    legs.push(leg.clone());
+                }
+                legs.reverse();
+                return Some(ConversionRoute { from, to, legs });
+            }
+            for (pair, other, sell) in self.edges.get(&asset).into_iter().flatten() {
+                if visited.insert(other.clone()) {
+                    came_from.insert(
+                        other.clone(),
+                        ConversionLeg {
+                            pair: pair.clone(),
+                            from: asset.clone(),
+                            to: other.clone(),
+                            sell: *sell,
+                        },
+                    );
+                    queue.push_back(other.clone());
+                }
+            }
+        }
+        None
+    }
+
+    /// Value `balances` (keyed by Kraken asset code or display symbol, as
+    /// `Balance` returns them) in `target` at `tickers`, which must cover
+    /// every pair on the routes. Suffixed balances (`DOT.S`, `XBT.M`) are
+    /// valued as their asset; assets with no route to `target` are listed in
+    /// `unpriced` rather than failing the whole valuation.
+    pub fn value(
+        &self,
+        balances: &HashMap<String, String>,
+        target: &str,
+        tickers: &TickerResponse,
+    ) -> KrakenResult<PortfolioValue> {
+        let mut value = PortfolioValue {
+            total: 0.0,
+            by_asset: HashMap::new(),
+            unpriced: Vec::new(),
+        };
+        for (asset, amount) in balances {
+            let amount = Amount(amount).to_f64_lossy()?;
+            if amount == 0.0 {
+                continue;
+            }
+            let Some(route) = self.route(asset, target) else {
+                value.unpriced.push(asset.clone());
+                continue;
+            };
+            let worth = amount * route.rate(tickers)?;
+            value.total += worth;
+            *value.by_asset.entry(route.from).or_default() += worth;
+        }
+        value.unpriced.sort();
+        Ok(value)
+    }
+
+    /// Every pair on the routes from `assets` to `target`, for one `Ticker`
+    /// request covering a whole `value`.
+    pub fn pairs_for(
+        &self,
+        assets: impl IntoIterator<Item = impl AsRef<str>>,
+        target: &str,
+    ) -> Vec<String> {
+        let mut pairs: Vec<String> = assets
+            .into_iter()
+            .filter_map(|asset| self.route(asset.as_ref(), target))
+            .flat_map(|route| route.legs.into_iter().map(|leg| leg.pair))
+            .collect();
+        pairs.sort();
+        pairs.dedup();
+        pairs
+    }
+}
+
+/// The display symbol for an asset code or symbol, without a balance-type
+/// suffix.
+fn symbol(asset: &str) -> String {
+    let asset = asset.split_once('.').map_or(asset, |(asset, _)| asset);
+    assets::display_name(&asset.to_ascii_uppercase())
+}
+
+/// A conversion priced by `KrakenClient::convert`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conversion {
+    pub route: ConversionRoute,
+    /// Units of `route.to` per unit of `route.from`
+    pub rate: f64,
+    /// The converted amount
+    pub amount: f64,
+}
+
+/// Balances valued in one asset by `ConversionGraph::value`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PortfolioValue {
+    pub total: f64,
+    /// Value per display symbol
+    pub by_asset: HashMap<String, f64>,
+    /// Balances with no route to the target asset, left out of `total`
+    pub unpriced: Vec<String>,
+}
+
+impl<S> KrakenClient<S> {
+    /// What `amount` of `from` is worth in `to` at the current touch, through
+    /// the shortest route over `AssetPairs` (served from the metadata cache
+    /// when enabled). Fails with `KrakenError::InvalidUsage` when no route
+    /// connects them.
+    pub async fn convert(&self, from: &str, to: &str, amount: f64) -> KrakenResult<Conversion> {
+        let graph = ConversionGraph::from_asset_pairs(&self.get_asset_pairs(&[]).await?);
+        let route = graph.route(from, to).ok_or_else(|| {
+            KrakenError::InvalidUsage(format!("no conversion route from {from} to {to}"))
+        })?;
+        let rate = if route.legs.is_empty() {
+            1.0
+        } else {
+            let tickers = self
+                .get_ticker_information(&route.pairs().join(","))
+                .await?;
+            route.rate(&tickers)?
+        };
+        Ok(Conversion {
+            route,
+            rate,
+            amount: amount * rate,
+        })
+    }
+}
+
+impl AuthenticatedClient {
+    /// The account's `Balance` valued in `target` (e.g. `"USD"`); see
+    /// `ConversionGraph::value`.
+    pub async fn portfolio_value(&self, target: &str) -> KrakenResult<PortfolioValue> {
+        let (pairs, balances) = tokio::try_join!(self.get_asset_pairs(&[]), self.get_balance())?;
+        let graph = ConversionGraph::from_asset_pairs(&pairs);
+        let needed = graph.pairs_for(balances.balances.keys(), target);
+        let tickers = if needed.is_empty() {
+            TickerResponse {
+                tickers: HashMap::new(),
+            }
+        } else {
+            self.get_ticker_information(&needed.join(",")).await?
+        };
+        graph.value(&balances.balances, target, &tickers)
+    }
+}
@@ -0,0 +1,168 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+
+use crate::error::{KrakenError, KrakenResult};
+use crate::rest_client::AuthenticatedClient;
+use crate::state::StateHandle;
+
+/// Longest timeout `CancelAllOrdersAfter` accepts.
+pub const MAX_DEADMAN_TIMEOUT: Duration = Duration::from_secs(86_400);
+
+/// Called from the refresh task whenever re-arming the timer fails, with the
+/// error and the number of consecutive failures so far. Implemented for closures.
+pub trait DeadmanFailureHandler: Send + Sync {
+    fn on_failure(&self, error: &KrakenError, consecutive_failures: u32);
+}
+
+impl<F> DeadmanFailureHandler for F
+where
+    F: Fn(&KrakenError, u32) + Send + Sync,
+{
+    fn on_failure(&self, error: &KrakenError, consecutive_failures: u32) {
+        self(error, consecutive_failures)
+    }
+}
+
+/// What a persistent dead man's switch saves to its `StateStore` each time it
+/// arms the timer, so a restarted process knows whether orders are still
+/// protected and can pick the switch back up with
+/// `AuthenticatedClient::resume_deadmans_switch`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeadmanState {
+    pub timeout_secs: u64,
+    pub refresh_interval_ms: u64,
+    /// Unix time at which Kraken cancels every open order unless refreshed
+    pub armed_until: u64,
+}
+
+/// A running REST dead man's switch, returned by
+/// `AuthenticatedClient::start_deadmans_switch`.
+///
+/// A background task calls `/0/private/CancelAllOrdersAfter` every
+/// `refresh_interval`, pushing the cancel-all deadline `timeout` into the
+/// future. If the process hangs or loses connectivity the refreshes stop and
+/// Kraken cancels every open order once the last deadline passes.
+///
+/// Dropping the handle stops refreshing but leaves the last deadline armed;
+/// call `disarm` to stop refreshing and cancel the timer on Kraken's side.
+#[derive(Debug)]
+pub struct DeadMansSwitch {
+    client: AuthenticatedClient,
+    task: JoinHandle<()>,
+    failures: Arc<AtomicU32>,
+    state: Option<StateHandle>,
+}
+
+impl DeadMansSwitch {
+    pub(crate) async fn start(
+        client: AuthenticatedClient,
+        timeout: Duration,
+        refresh_interval: Duration,
+        on_failure: Arc<dyn DeadmanFailureHandler>,
+        state: Option<StateHandle>,
+    ) -> KrakenResult<Self> {
+        if timeout.as_secs() == 0 || timeout > MAX_DEADMAN_TIMEOUT {
+            return Err(KrakenError::InvalidUsage(format!(
+                "dead man's switch timeout must be between 1s and {MAX_DEADMAN_TIMEOUT:?}, got {timeout:?}"
+            )));
+        }
+        if refresh_interval.is_zero() || refresh_interval >= timeout {
+            return Err(KrakenError::InvalidUsage(format!(
+                "refresh interval ({refresh_interval:?}) must be non-zero and shorter than the timeout ({timeout:?})"
+            )));
+        }
+
+        // Arm once up front so bad credentials or permissions surface here
+        // rather than only through the failure handler.
+        let seconds = timeout.as_secs().to_string();
+        arm(&client, &seconds).await?;
+        let save = {
+            let (client, state) = (client.clone(), state.clone());
+            move || {
+                let Some(state) = &state else {
+                    return;
+                };
+                let now = client
+                    .clock()
+                    .system_time()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default();
+                state.save_or_warn(&DeadmanState {
+                    timeout_secs: timeout.as_secs(),
+                    refresh_interval_ms: refresh_interval.as_millis() as u64,
+                    armed_until: (now + timeout).as_secs(),
+                });
+            }
+        };
+        save();
+
+        let failures = Arc::new(AtomicU32::new(0));
+        let task = tokio::spawn({
+            let client = client.clone();
+            let failures = failures.clone();
+            async move {
+                let mut ticks = tokio::time::interval(refresh_interval);
+                ticks.tick().await;
+                loop {
+                    ticks.tick().await;
+                    match arm(&client, &seconds).await {
+                        Ok(()) => {
+                            failures.store(0, Ordering::Relaxed);
+                            save();
+                        }
+                        Err(e) => {
+                            let consecutive = failures.fetch_add(1, Ordering::Relaxed) + 1;
+                            tracing::warn!(error = %e, consecutive, "failed to refresh dead man's switch");
+                            on_failure.on_failure(&e, consecutive);
+                        }
+                    }
+                }
+            }
+        });
+
+        Ok(Self {
+            client,
+            task,
+            failures,
+            state,
+        })
+    }
+
+    /// Refresh failures since the last successful refresh.
+    pub fn consecutive_failures(&self) -> u32 {
+        self.failures.load(Ordering::Relaxed)
+    }
+
+    /// Whether the refresh task is still running.
+    pub fn is_running(&self) -> bool {
+        !self.task.is_finished()
+    }
+
+    /// Stop refreshing and cancel the timer (`timeout=0`), leaving open orders
+    /// alone. A persistent switch also forgets its saved state.
+    pub async fn disarm(self) -> KrakenResult<()> {
+        self.task.abort();
+        arm(&self.client, "0").await?;
+        match &self.state {
+            Some(state) => state.remove(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for DeadMansSwitch {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn arm(client: &AuthenticatedClient, seconds: &str) -> KrakenResult<()> {
+    client
+        .cancel_all_orders_after(&[("timeout", seconds)])
+        .await
+        .map(|_| ())
+}
@@ -0,0 +1,231 @@
+//! `rust_decimal::Decimal` accessors for the price, volume and balance fields
+//! of the REST and WebSocket models (feature `decimal`).
+//!
+//! The models keep Kraken's strings, so nothing is rounded while parsing and
+//! the `Serialize` / `Deserialize` shapes don't depend on features. Each
+//! accessor converts one field exactly through `numeric::Amount::to_decimal`
+//! and fails with `KrakenError::InvalidAmount` if the text isn't a number.
+//!
+//! ```
+//! use onise::ws_models::OrderBookEntry;
+//! use rust_decimal::Decimal;
+//!
+//! let level = OrderBookEntry { price: "30300.1".into(), quantity: "0.5".into() };
+//! assert_eq!(level.price_decimal().unwrap(), Decimal::new(303001, 1));
+//! ```
+
+use std::collections::HashMap;
+
+use rust_decimal::Decimal;
+
+use crate::error::KrakenResult;
+use crate::models::*;
+use crate::numeric::Amount;
+use crate::ws_models::*;
+
+/// `$name => $field`: `pub fn $name(&self) -> KrakenResult<Decimal>` reading
+/// `self.$field`; `$name => ?$field` reads an `Option<String>` into
+/// `KrakenResult<Option<Decimal>>`.
+macro_rules! decimal_accessors {
+    (@one $name:ident => ? $($field:tt)+) => {
+        #[doc = concat!("`", stringify!($($field)+), "` as a `Decimal`, if present.")]
+        pub fn $name(&self) -> KrakenResult<Option<Decimal>> {
+            self.$($field)+.as_deref().map(|value| Amount(value).to_decimal()).transpose()
+        }
+    };
+    (@one $name:ident => $($field:tt)+) => {
+        #[doc = concat!("`", stringify!($($field)+), "` as a `Decimal`.")]
+        pub fn $name(&self) -> KrakenResult<Decimal> {
+            Amount(&self.$($field)+).to_decimal()
+        }
+    };
+    ($($model:ty { $($name:ident => [$($field:tt)+]),* $(,)? })*) => {
+        $(
+            impl $model {
+                $(decimal_accessors!(@one $name => $($field)+);)*
+            }
+        )*
+    };
+}
+
+decimal_accessors! {
+    // REST
+    AssetPairInfo {
+        ordermin_decimal => [?ordermin],
+        costmin_decimal => [?costmin],
+        tick_size_decimal => [?tick_size],
+    }
+    TickerInfo {
+        ask_price_decimal => [a[0]],
+        ask_volume_decimal => [a[2]],
+        bid_price_decimal => [b[0]],
+        bid_volume_decimal => [b[2]],
+        last_price_decimal => [c[0]],
+        last_volume_decimal => [c[1]],
+        volume_today_decimal => [v[0]],
+        volume_24h_decimal => [v[1]],
+        vwap_24h_decimal => [p[1]],
+        low_24h_decimal => [l[1]],
+        high_24h_decimal => [h[1]],
+        open_decimal => [o],
+    }
+    OhlcCandle {
+        open_decimal => [open],
+        high_decimal => [high],
+        low_decimal => [low],
+        close_decimal => [close],
+        vwap_decimal => [vwap],
+        volume_decimal => [volume],
+    }
+    PublicTrade {
+        price_decimal => [price],
+        volume_decimal => [volume],
+    }
+    SpreadEntry {
+        bid_decimal => [bid],
+        ask_decimal => [ask],
+    }
+    TradeBalanceResponse {
+        equivalent_balance_decimal => [eb],
+        trade_balance_decimal => [tb],
+        margin_decimal => [m],
+        unrealized_pnl_decimal => [n],
+        cost_basis_decimal => [c],
+        valuation_decimal => [v],
+        equity_decimal => [e],
+        free_margin_decimal => [mf],
+        margin_level_decimal => [ml],
+    }
+    OrderInfo {
+        vol_decimal => [vol],
+        vol_exec_decimal => [vol_exec],
+        cost_decimal => [cost],
+        fee_decimal => [fee],
+        price_decimal => [price],
+        stopprice_decimal => [stopprice],
+        limitprice_decimal => [limitprice],
+    }
+    OrderDescription {
+        price_decimal => [price],
+        price2_decimal => [price2],
+    }
+    TradeInfo {
+        price_decimal => [price],
+        cost_decimal => [cost],
+        fee_decimal => [fee],
+        vol_decimal => [vol],
+        margin_decimal => [margin],
+    }
+    PositionInfo {
+        cost_decimal => [cost],
+        fee_decimal => [fee],
+        vol_decimal => [vol],
+        vol_closed_decimal => [vol_closed],
+        cost_closed_decimal => [cost_closed],
+        fee_closed_decimal => [fee_closed],
+        pl_closed_decimal => [pl_closed],
+        margin_decimal => [margin],
+    }
+    LedgerInfo {
+        amount_decimal => [amount],
+        fee_decimal => [fee],
+        balance_decimal => [balance],
+    }
+    TradeVolumeResponse {
+        volume_decimal => [volume],
+    }
+    FeeInfo {
+        fee_decimal => [fee],
+    }
+    // WebSocket
+    WsTickerMessage {
+        best_ask_price_decimal => [best_ask_price],
+        best_ask_quantity_decimal => [best_ask_quantity],
+        best_bid_price_decimal => [best_bid_price],
+        best_bid_quantity_decimal => [best_bid_quantity],
+        last_trade_price_decimal => [last_trade_price],
+        last_trade_quantity_decimal => [last_trade_quantity],
+        volume_24h_decimal => [volume_24h],
+        vwap_24h_decimal => [vwap_24h],
+        low_24h_decimal => [low_24h],
+        high_24h_decimal => [high_24h],
+        open_24h_decimal => [open_24h],
+    }
+    OrderBookEntry {
+        price_decimal => [price],
+        quantity_decimal => [quantity],
+    }
+    CandleData {
+        open_decimal => [open],
+        high_decimal => [high],
+        low_decimal => [low],
+        close_decimal => [close],
+        volume_decimal => [volume],
+    }
+    TradeData {
+        price_decimal => [price],
+        quantity_decimal => [quantity],
+    }
+    InstrumentData {
+        min_volume_decimal => [min_volume],
+        max_volume_decimal => [max_volume],
+        tick_size_decimal => [tick_size],
+        lot_size_decimal => [lot_size],
+    }
+    ExecutionData {
+        quantity_decimal => [quantity],
+        price_decimal => [price],
+        cost_decimal => [cost],
+        fee_decimal => [fee],
+    }
+}
+
+impl OrderBookData {
+    /// `asks` as `(price, volume, timestamp)`, best first.
+    pub fn asks_decimal(&self) -> KrakenResult<Vec<(Decimal, Decimal, u64)>> {
+        book_levels(&self.asks)
+    }
+
+    /// `bids` as `(price, volume, timestamp)`, best first.
+    pub fn bids_decimal(&self) -> KrakenResult<Vec<(Decimal, Decimal, u64)>> {
+        book_levels(&self.bids)
+    }
+}
+
+impl AccountBalanceResponse {
+    /// Every balance as a `Decimal`, keyed by Kraken's asset name.
+    pub fn balances_decimal(&self) -> KrakenResult<HashMap<String, Decimal>> {
+        balances(&self.balances)
+    }
+}
+
+impl ExtendedBalanceResponse {
+    /// Every balance as a `Decimal`, keyed by Kraken's asset name.
+    pub fn balances_decimal(&self) -> KrakenResult<HashMap<String, Decimal>> {
+        balances(&self.balances)
+    }
+}
+
+impl WsBalancesMessage {
+    /// Every balance as a `Decimal`, keyed by asset.
+    pub fn balances_decimal(&self) -> KrakenResult<HashMap<String, Decimal>> {
+        balances(&self.balances)
+    }
+}
+
+fn book_levels(levels: &[(String, String, u64)]) -> KrakenResult<Vec<(Decimal, Decimal, u64)>> {
+    levels
+        .iter()
+        .map(|(price, volume, time)| {
+            let price = Amount(price).to_decimal()?;
+            Ok((price, Amount(volume).to_decimal()?, *time))
+        })
+        .collect()
+}
+
+fn balances(balances: &HashMap<String, String>) -> KrakenResult<HashMap<String, Decimal>> {
+    balances
+        .iter()
+        .map(|(asset, amount)| Ok((asset.clone(), Amount(amount).to_decimal()?)))
+        .collect()
+}
@@ -0,0 +1,184 @@
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::task::{Context, Poll};
+
+use futures_util::stream::{self, BoxStream, Stream};
+use tokio::sync::broadcast;
+
+use crate::ws_client::MESSAGE_BUFFER;
+use crate::ws_models::{WsBalancesMessage, WsExecutionsMessage, WsIncomingMessage};
+
+/// A private update on the connection, as mirrored to drop-copy consumers.
+#[derive(Debug, Clone)]
+pub enum PrivateEvent {
+    /// From the `executions` channel (order status changes and fills)
+    Executions(WsExecutionsMessage),
+    /// From the `balances` channel
+    Balances(WsBalancesMessage),
+}
+
+impl PrivateEvent {
+    fn from_message(message: &WsIncomingMessage) -> Option<Self> {
+        match message {
+            WsIncomingMessage::ExecutionsMsg(executions) => {
+                Some(Self::Executions(executions.clone()))
+            }
+            WsIncomingMessage::BalancesMsg(balances) => Some(Self::Balances(balances.clone())),
+            _ => None,
+        }
+    }
+}
+
+/// How one drop-copy consumer is keeping up, from `DropCopyConsumer::lag` or
+/// `KrakenWsClient::drop_copy_lag`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsumerLag {
+    pub name: String,
+    /// Events the consumer has taken
+    pub received: u64,
+    /// Events it fell more than `MESSAGE_BUFFER` behind on and skipped
+    pub missed: u64,
+    /// Events published since it started that it hasn't reached yet
+    pub behind: u64,
+}
+
+/// The per-consumer counters, shared by a consumer and the feed's registry.
+#[derive(Debug)]
+struct Tracker {
+    name: String,
+    published: Arc<AtomicU64>,
+    received: AtomicU64,
+    missed: AtomicU64,
+    /// Sequence number of the last event taken or skipped
+    position: AtomicU64,
+}
+
+impl Tracker {
+    fn lag(&self) -> ConsumerLag {
+        let published = self.published.load(Ordering::Relaxed);
+        ConsumerLag {
+            name: self.name.clone(),
+            received: self.received.load(Ordering::Relaxed),
+            missed: self.missed.load(Ordering::Relaxed),
+            behind: published.saturating_sub(self.position.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// The fan-out behind drop-copy consumers, owned by the connection's read
+/// task. Every event is numbered, so each consumer knows how far behind it is.
+pub(crate) struct DropCopyFeed {
+    sender: broadcast::Sender<(u64, PrivateEvent)>,
+    published: Arc<AtomicU64>,
+    consumers: Mutex<Vec<Weak<Tracker>>>,
+}
+
+impl DropCopyFeed {
+    pub(crate) fn new() -> Self {
+        Self {
+            sender: broadcast::channel(MESSAGE_BUFFER).0,
+            published: Arc::new(AtomicU64::new(0)),
+            consumers: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Mirror `message` to every consumer if it's a private update; `true`
+    /// if any consumer received it.
+    pub(crate) fn publish(&self, message: &WsIncomingMessage) -> bool {
+        if self.sender.receiver_count() == 0 {
+            return false;
+        }
+        let Some(event) = PrivateEvent::from_message(message) else {
+            return false;
+        };
+        let seq = self.published.fetch_add(1, Ordering::Relaxed) + 1;
+        self.sender.send((seq, event)).is_ok()
+    }
+
+    /// A new consumer named `name`, seeing every private update from now on.
+    pub(crate) fn consumer(&self, name: &str) -> DropCopyConsumer {
+        let receiver = self.sender.subscribe();
+        let tracker = Arc::new(Tracker {
+            name: name.to_string(),
+            published: self.published.clone(),
+            received: AtomicU64::new(0),
+            missed: AtomicU64::new(0),
+            position: AtomicU64::new(self.published.load(Ordering::Relaxed)),
+        });
+        let mut consumers = self.consumers.lock().unwrap();
+        consumers.retain(|consumer| consumer.strong_count() > 0);
+        consumers.push(Arc::downgrade(&tracker));
+        DropCopyConsumer::new(receiver, tracker)
+    }
+
+    /// The lag of every consumer still alive, oldest first.
+    pub(crate) fn lags(&self) -> Vec<ConsumerLag> {
+        let consumers = self.consumers.lock().unwrap();
+        consumers
+            .iter()
+            .filter_map(Weak::upgrade)
+            .map(|tracker| tracker.lag())
+            .collect()
+    }
+}
+
+/// One independent reader of a connection's private updates (`executions`
+/// and `balances`), from `KrakenWsClient::drop_copy`.
+///
+/// Each consumer has its own queue: a slow one (say, a risk monitor doing I/O)
+/// skips its own oldest events once `MESSAGE_BUFFER` behind, counted in
+/// `lag().missed`, and never holds back the strategy reading next to it.
+/// Consumers don't subscribe to anything themselves; they mirror whatever
+/// private channels the connection carries. The stream ends with the
+/// connection.
+pub struct DropCopyConsumer {
+    tracker: Arc<Tracker>,
+    inner: BoxStream<'static, PrivateEvent>,
+}
+
+impl DropCopyConsumer {
+    fn new(receiver: broadcast::Receiver<(u64, PrivateEvent)>, tracker: Arc<Tracker>) -> Self {
+        let counters = tracker.clone();
+        let inner = stream::unfold(receiver, move |mut receiver| {
+            let counters = counters.clone();
+            async move {
+                loop {
+                    match receiver.recv().await {
+                        Ok((seq, event)) => {
+                            counters.received.fetch_add(1, Ordering::Relaxed);
+                            counters.position.store(seq, Ordering::Relaxed);
+                            return Some((event, receiver));
+                        }
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            counters.missed.fetch_add(skipped, Ordering::Relaxed);
+                            counters.position.fetch_add(skipped, Ordering::Relaxed);
+                        }
+                        Err(broadcast::error::RecvError::Closed) => return None,
+                    }
+                }
+            }
+        });
+        Self {
+            tracker,
+            inner: Box::pin(inner),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.tracker.name
+    }
+
+    /// How this consumer is keeping up so far.
+    pub fn lag(&self) -> ConsumerLag {
+        self.tracker.lag()
+    }
+}
+
+impl Stream for DropCopyConsumer {
+    type Item = PrivateEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<PrivateEvent>> {
+        self.inner.as_mut().poll_next(cx)
+    }
+}
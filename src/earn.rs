@@ -0,0 +1,305 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::task::JoinHandle;
+
+use crate::assets;
+use crate::error::KrakenResult;
+use crate::models::StakingProduct;
+use crate::rest_client::AuthenticatedClient;
+
+/// How often `AutoCompounder::start` checks balances unless `with_interval`
+/// says otherwise.
+pub const DEFAULT_COMPOUND_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Spot balance of `asset` to move into the Earn strategy `method`, keeping
+/// `reserve` in spot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompoundTarget {
+    /// Asset as Kraken lists it in `Balance` or as a display symbol ("DOT", "XBT", "BTC")
+    pub asset: String,
+    /// Earn strategy, the `method` of a `ListStakingProducts` entry
+    pub method: String,
+    /// Amount left in spot, never allocated
+    pub reserve: f64,
+}
+
+impl CompoundTarget {
+    pub fn new(asset: impl Into<String>, method: impl Into<String>) -> Self {
+        Self {
+            asset: asset.into(),
+            method: method.into(),
+            reserve: 0.0,
+        }
+    }
+
+    /// Keep `reserve` of the asset in spot.
+    pub fn with_reserve(mut self, reserve: f64) -> Self {
+        self.reserve = reserve;
+        self
+    }
+}
+
+/// What the compounder did (or, in dry-run mode, would have done) for one
+/// target in one run. Every run produces exactly one event per target.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum CompoundEvent {
+    /// `amount` was allocated; `txid` is Kraken's reference
+    Allocated {
+        asset: String,
+        method: String,
+        amount: String,
+        txid: String,
+    },
+    /// Dry run: `amount` would have been allocated
+    WouldAllocate {
+        asset: String,
+        method: String,
+        amount: String,
+    },
+    /// Nothing was allocated, for `reason`
+    Skipped {
+        asset: String,
+        method: String,
+        reason: String,
+    },
+    /// The allocation was attempted and Kraken refused it
+    Failed {
+        asset: String,
+        method: String,
+        amount: String,
+        error: String,
+    },
+}
+
+/// Receives every `CompoundEvent` as it happens, e.g. to write an audit trail.
+/// Implemented for closures.
+pub trait CompoundEventHandler: Send + Sync {
+    fn on_event(&self, event: &CompoundEvent);
+}
+
+impl<F> CompoundEventHandler for F
+where
+    F: Fn(&CompoundEvent) + Send + Sync,
+{
+    fn on_event(&self, event: &CompoundEvent) {
+        self(event)
+    }
+}
+
+/// Opt-in Earn auto-compounding: moves idle spot balances into configured
+/// Earn strategies.
+///
+/// Each run reads `Balance` and `ListStakingProducts` once, then for every
+/// `CompoundTarget` allocates the balance above its reserve, capped at the
+/// strategy's `max_amount`. Nothing is allocated when that is below the
+/// strategy's `min_amount`, when the strategy isn't listed, or when it locks
+/// funds for longer than `with_max_lock_time` allows (by default, only
+/// strategies without a lock qualify). With `with_dry_run` the allocations are
+/// reported as `CompoundEvent::WouldAllocate` instead of being made.
+#[derive(Clone)]
+pub struct AutoCompounder {
+    client: AuthenticatedClient,
+    targets: Vec<CompoundTarget>,
+    interval: Duration,
+    max_lock_time: Duration,
+    dry_run: bool,
+    on_event: Option<Arc<dyn CompoundEventHandler>>,
+}
+
+impl AutoCompounder {
+    /// A compounder with no targets, checking every `DEFAULT_COMPOUND_INTERVAL`.
+    pub fn new(client: AuthenticatedClient) -> Self {
+        Self {
+            client,
+            targets: Vec::new(),
+            interval: DEFAULT_COMPOUND_INTERVAL,
+            max_lock_time: Duration::ZERO,
+            dry_run: false,
+            on_event: None,
+        }
+    }
+
+    pub fn with_target(mut self, target: CompoundTarget) -> Self {
+        self.targets.push(target);
+        self
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Also allocate into strategies that lock funds for up to `max_lock_time`.
+    pub fn with_max_lock_time(mut self, max_lock_time: Duration) -> Self {
+        self.max_lock_time = max_lock_time;
+        self
+    }
+
+    /// Report what would be allocated without calling `Staking/Stake`.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Pass every event to `handler` as well as returning it from `run_once`.
+    pub fn with_event_handler(mut self, handler: impl CompoundEventHandler + 'static) -> Self {
+        self.on_event = Some(Arc::new(handler));
+        self
+    }
+
+    /// Check every target once. Fails only if balances or strategies can't be
+    /// read; a refused allocation is a `CompoundEvent::Failed`.
+    pub async fn run_once(&self) -> KrakenResult<Vec<CompoundEvent>> {
+        let balances = self.client.get_balance().await?.by_display_name();
+        let products = self.client.list_earn_strategies().await?.products;
+
+        let mut events = Vec::with_capacity(self.targets.len());
+        for target in &self.targets {
+            let event = self.compound(target, &balances, &products).await;
+            tracing::info!(?event, dry_run = self.dry_run, "earn auto-compound");
+            if let Some(handler) = &self.on_event {
+                handler.on_event(&event);
+            }
+            events.push(event);
+        }
+        Ok(events)
+    }
+
+    /// Run every `interval` from a background task, starting now. Failed runs
+    /// are logged and retried at the next tick.
+    pub fn start(self) -> AutoCompoundHandle {
+        let task = tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(self.interval);
+            loop {
+                ticks.tick().await;
+                if let Err(e) = self.run_once().await {
+                    tracing::warn!(error = %e, "earn auto-compound run failed");
+                }
+            }
+        });
+        AutoCompoundHandle { task }
+    }
+
+    async fn compound(
+        &self,
+        target: &CompoundTarget,
+        balances: &HashMap<String, String>,
+        products: &[StakingProduct],
+    ) -> CompoundEvent {
+        let skipped = |reason: String| CompoundEvent::Skipped {
+            asset: target.asset.clone(),
+            method: target.method.clone(),
+            reason,
+        };
+        let asset = assets::display_name(&target.asset);
+        let Some(product) = products
+            .iter()
+            .find(|p| p.method == target.method && assets::display_name(&p.asset) == asset)
+        else {
+            return skipped(format!("no {asset} strategy {:?} is listed", target.method));
+        };
+        let lock = Duration::from_secs(product.lock_time.unwrap_or(0));
+        if lock > self.max_lock_time {
+            return skipped(format!(
+                "strategy locks funds for {lock:?}, over the {:?} allowed",
+                self.max_lock_time
+            ));
+        }
+
+        let balance = balances
+            .get(&asset)
+            .and_then(|b| b.parse::<f64>().ok())
+            .unwrap_or(0.0);
+        let mut amount = balance - target.reserve;
+        if let Some(max) = product.max_amount.as_deref().and_then(|m| m.parse().ok()) {
+            amount = amount.min(max);
+        }
+        let min: f64 = product.min_amount.parse().unwrap_or(0.0);
+        if amount <= 0.0 || amount < min {
+            return skipped(format!(
+                "{} {asset} available above the reserve, below the minimum of {}",
+                amount.max(0.0),
+                product.min_amount
+            ));
+        }
+
+        let amount = format_amount(amount);
+        if self.dry_run {
+            return CompoundEvent::WouldAllocate {
+                asset: target.asset.clone(),
+                method: target.method.clone(),
+                amount,
+            };
+        }
+        let params = [
+            ("asset", target.asset.as_str()),
+            ("amount", amount.as_str()),
+            ("method", target.method.as_str()),
+        ];
+        match self.client.allocate_earn_funds(&params).await {
+            Ok(allocated) => CompoundEvent::Allocated {
+                asset: target.asset.clone(),
+                method: target.method.clone(),
+                amount,
+                txid: allocated.txid,
+            },
+            Err(e) => CompoundEvent::Failed {
+                asset: target.asset.clone(),
+                method: target.method.clone(),
+                amount,
+                error: e.to_string(),
+            },
+        }
+    }
+}
+
+impl std::fmt::Debug for AutoCompounder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AutoCompounder")
+            .field("targets", &self.targets)
+            .field("interval", &self.interval)
+            .field("max_lock_time", &self.max_lock_time)
+            .field("dry_run", &self.dry_run)
+            .field("on_event", &self.on_event.is_some())
+            .finish()
+    }
+}
+
+/// A running `AutoCompounder`. Dropping it stops the schedule.
+#[derive(Debug)]
+pub struct AutoCompoundHandle {
+    task: JoinHandle<()>,
+}
+
+impl AutoCompoundHandle {
+    /// Whether the background task is still running.
+    pub fn is_running(&self) -> bool {
+        !self.task.is_finished()
+    }
+
+    /// Stop the schedule; a run in progress is abandoned.
+    pub fn stop(self) {
+        self.task.abort();
+    }
+}
+
+impl Drop for AutoCompoundHandle {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Rounded down to 8 decimals so the balance is never exceeded, without
+/// trailing zeros.
+fn format_amount(amount: f64) -> String {
+    let truncated = (amount * 1e8).floor() / 1e8;
+    let formatted = format!("{truncated:.8}");
+    formatted
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
@@ -0,0 +1,36 @@
+use serde::Deserialize;
+use serde_json::value::RawValue;
+
+use crate::error::{KrakenError, KrakenResult};
+
+/// The standard format from Kraken: if `error` is empty, `result` is the data. Otherwise, we parse the errors.
+/// `result` is kept raw so error responses (which omit it or send `{}`) never fail to parse.
+#[derive(Debug, Deserialize)]
+struct KrakenResponse<'a> {
+    #[serde(default)]
+    error: Vec<String>,
+    #[serde(borrow, default)]
+    result: Option<&'a RawValue>,
+}
+
+/// Parse a raw Kraken envelope into its result or a `KrakenError`
+pub(crate) fn parse<T>(body: &[u8]) -> KrakenResult<T>
+where
+    T: serde::de::DeserializeOwned,
+{
+    let parsed = serde_json::from_slice::<KrakenResponse>(body)?;
+    if !parsed.error.is_empty() {
+        return Err(KrakenError::from_kraken_errors(parsed.error));
+    }
+    let result = parsed.result.map_or("null", RawValue::get);
+    Ok(serde_json::from_str(result)?)
+}
+
+/// The `error` array of a raw Kraken envelope; empty if there is none or the
+/// body isn't an envelope.
+#[cfg(feature = "rest")]
+pub(crate) fn errors(body: &[u8]) -> Vec<String> {
+    serde_json::from_slice::<KrakenResponse>(body)
+        .map(|parsed| parsed.error)
+        .unwrap_or_default()
+}
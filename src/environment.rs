@@ -0,0 +1,197 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::OnceLock;
+
+use serde::Deserialize;
+
+use crate::error::{KrakenError, KrakenResult};
+
+/// Kraken's production Spot REST API.
+pub const PRODUCTION_REST_URL: &str = "https://api.kraken.com";
+
+/// Kraken's production Spot WebSocket API v2 (public market data).
+pub const PRODUCTION_WS_PUBLIC_URL: &str = "wss://ws.kraken.com/v2";
+
+/// Kraken's production Spot WebSocket API v2 (authenticated user data and trading).
+pub const PRODUCTION_WS_AUTH_URL: &str = "wss://ws-auth.kraken.com/v2";
+
+/// Kraken's production Futures REST API (`KrakenFuturesClient`).
+pub const PRODUCTION_FUTURES_REST_URL: &str = "https://futures.kraken.com";
+
+/// Kraken's Futures demo environment, for testing with paper funds.
+pub const DEMO_FUTURES_REST_URL: &str = "https://demo-futures.kraken.com";
+
+/// Kraken's production Futures WebSocket API v1 (`KrakenFuturesWsClient`).
+pub const PRODUCTION_FUTURES_WS_URL: &str = "wss://futures.kraken.com/ws/v1";
+
+/// The Futures demo environment's WebSocket API v1.
+pub const DEMO_FUTURES_WS_URL: &str = "wss://demo-futures.kraken.com/ws/v1";
+
+/// Kraken's public status page summary (components and open incidents),
+/// read by `status_page::StatusPage`.
+pub const PRODUCTION_STATUS_URL: &str = "https://status.kraken.com/api/v2/summary.json";
+
+/// The three base URLs a Kraken integration talks to.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Endpoints {
+    /// REST base URL, without a trailing slash (e.g. "https://api.kraken.com")
+    pub rest: String,
+    /// WebSocket URL for public market data
+    pub ws_public: String,
+    /// WebSocket URL for private user data and trading
+    pub ws_auth: String,
+}
+
+impl Endpoints {
+    /// Kraken's production URLs.
+    pub fn production() -> Self {
+        Self {
+            rest: PRODUCTION_REST_URL.to_string(),
+            ws_public: PRODUCTION_WS_PUBLIC_URL.to_string(),
+            ws_auth: PRODUCTION_WS_AUTH_URL.to_string(),
+        }
+    }
+}
+
+/// Which Kraken deployment the REST and WebSocket clients connect to.
+///
+/// Both `KrakenClient` and `KrakenWsClient` read their URLs from here, so the
+/// defaults live in one place.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Environment {
+    /// `api.kraken.com`, `ws.kraken.com` and `ws-auth.kraken.com`
+    #[default]
+    Production,
+    /// Any other set of URLs: a proxy, a mock server in tests, etc.
+    Custom(Endpoints),
+}
+
+impl Environment {
+    /// A custom REST base URL, keeping the production WebSocket URLs.
+    pub fn custom_rest(rest: impl Into<String>) -> Self {
+        Environment::Custom(Endpoints {
+            rest: rest.into(),
+            ..Endpoints::production()
+        })
+    }
+
+    /// REST base URL
+    pub fn rest_url(&self) -> &str {
+        match self {
+            Environment::Production => PRODUCTION_REST_URL,
+            Environment::Custom(endpoints) => &endpoints.rest,
+        }
+    }
+
+    /// Public WebSocket URL
+    pub fn ws_public_url(&self) -> &str {
+        match self {
+            Environment::Production => PRODUCTION_WS_PUBLIC_URL,
+            Environment::Custom(endpoints) => &endpoints.ws_public,
+        }
+    }
+
+    /// Authenticated WebSocket URL
+    pub fn ws_auth_url(&self) -> &str {
+        match self {
+            Environment::Production => PRODUCTION_WS_AUTH_URL,
+            Environment::Custom(endpoints) => &endpoints.ws_auth,
+        }
+    }
+
+    /// All three URLs as an owned `Endpoints`.
+    pub fn endpoints(&self) -> Endpoints {
+        match self {
+            Environment::Production => Endpoints::production(),
+            Environment::Custom(endpoints) => endpoints.clone(),
+        }
+    }
+}
+
+/// Environment variable that turns sandbox mode on ("1" / "true") or off
+/// ("0" / "false") for the process, over the `sandbox` feature's default.
+/// Any other value turns it on.
+pub const SANDBOX_ENV_VAR: &str = "ONISE_SANDBOX";
+
+/// Hosts of Kraken's live environments; `demo-futures.kraken.com` is not
+/// one of them.
+const PRODUCTION_HOSTS: &[&str] = &[
+    "api.kraken.com",
+    "ws.kraken.com",
+    "ws-auth.kraken.com",
+    "futures.kraken.com",
+];
+
+/// `set_sandbox`'s override: unset, on or off.
+static SANDBOX_OVERRIDE: AtomicU8 = AtomicU8::new(UNSET);
+const UNSET: u8 = 0;
+const ON: u8 = 1;
+const OFF: u8 = 2;
+
+/// Turn sandbox mode on or off for the whole process, over `ONISE_SANDBOX`
+/// and the `sandbox` feature.
+///
+/// In sandbox mode the REST and WebSocket clients (Spot and Futures) refuse
+/// to send anything to Kraken's production hosts and fail with
+/// `KrakenError::ProductionRefused` instead, so a test configuration can't
+/// trade on a live account by accident. Mock servers, proxies and the
+/// Futures demo environment are unaffected.
+pub fn set_sandbox(on: bool) {
+    SANDBOX_OVERRIDE.store(if on { ON } else { OFF }, Ordering::SeqCst);
+}
+
+/// Whether sandbox mode is on: from `set_sandbox` if called, else
+/// `ONISE_SANDBOX` if set, else on exactly when built with the `sandbox`
+/// feature.
+pub fn is_sandbox() -> bool {
+    match SANDBOX_OVERRIDE.load(Ordering::SeqCst) {
+        ON => true,
+        OFF => false,
+        _ => sandbox_default(),
+    }
+}
+
+/// `ONISE_SANDBOX`, read once, falling back to the `sandbox` feature when
+/// unset or empty. Only "0" / "false" turn sandbox mode off; any other value
+/// (a typo, "off") keeps it on, with a warning.
+fn sandbox_default() -> bool {
+    static DEFAULT: OnceLock<bool> = OnceLock::new();
+    *DEFAULT.get_or_init(|| {
+        let value = std::env::var(SANDBOX_ENV_VAR).unwrap_or_default();
+        match value.trim().to_ascii_lowercase().as_str() {
+            "" => cfg!(feature = "sandbox"),
+            "1" | "true" => true,
+            "0" | "false" => false,
+            _ => {
+                tracing::warn!(
+                    value = %value,
+                    "unrecognized {SANDBOX_ENV_VAR} value, keeping sandbox mode on"
+                );
+                true
+            }
+        }
+    })
+}
+
+/// `true` if `url` points at one of Kraken's live hosts.
+pub fn is_production_url(url: &str) -> bool {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = authority.rsplit('@').next().unwrap_or_default();
+    let host = host
+        .split(':')
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    PRODUCTION_HOSTS.contains(&host.trim_end_matches('.'))
+}
+
+/// Fail with `ProductionRefused` if sandbox mode is on and `url` is a
+/// production URL; the clients call this before every request and connect.
+pub fn check_sandbox(url: &str) -> KrakenResult<()> {
+    if is_sandbox() && is_production_url(url) {
+        return Err(KrakenError::ProductionRefused {
+            url: url.to_string(),
+        });
+    }
+    Ok(())
+}
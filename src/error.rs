@@ -1,10 +1,15 @@
 use std::io;
+use std::time::Duration;
 use thiserror::Error;
 
+use crate::models::ExchangeState;
+use crate::signing::SignedPayload;
+
 /// A specialized error type for Kraken.
 #[derive(Error, Debug)]
 pub enum KrakenError {
     /// HTTP or lower-level I/O error
+    #[cfg(feature = "rest")]
     #[error("HTTP error: {0}")]
     Reqwest(#[from] reqwest::Error),
 
@@ -40,6 +45,154 @@ pub enum KrakenError {
     ///variant for stsd::io::Error
     #[error("IO error: {0}")]
     IoError(#[from] io::Error),
+
+    /// A response body that could not be parsed as the expected JSON
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    /// A string amount that doesn't parse as a number (see `numeric::Amount`)
+    #[error("Not a valid amount: {0:?}")]
+    InvalidAmount(String),
+
+    /// Leverage the pair doesn't offer on that side, caught before sending the order
+    #[error("Leverage {requested} is not available to {side} {pair} (allowed: {allowed:?})")]
+    InvalidLeverage {
+        pair: String,
+        side: String,
+        requested: String,
+        allowed: Vec<u32>,
+    },
+
+    /// A mutating REST endpoint, or a WS trading request (`path` is then its
+    /// `event`, e.g. "addOrder"), on a client built `with_read_only(true)`
+    #[error("Client is read-only; refused to call {path}")]
+    ReadOnly { path: String },
+
+    /// A withdrawal stopped locally by a `SafeWithdrawer` check
+    #[error("Withdrawal blocked: {0}")]
+    WithdrawalBlocked(String),
+
+    /// No reply arrived before the caller's deadline
+    #[error("Timed out after {after:?} waiting for {operation}")]
+    Timeout {
+        operation: String,
+        after: Duration,
+    },
+
+    /// An order helper refused to price an order off `symbol`'s quote: there is
+    /// none, or it is older than `max_age`
+    #[error("Stale market data for {symbol}: {}", staleness(*.age, *.max_age))]
+    StaleMarketData {
+        symbol: String,
+        age: Option<Duration>,
+        max_age: Option<Duration>,
+    },
+
+    /// A market order stopped by a `quotes::MarketOrderGuard`: the spread is
+    /// wider than allowed
+    #[error("Spread on {symbol} is {spread_bps:.1} bps, over the {max_bps} bps allowed")]
+    SpreadTooWide {
+        symbol: String,
+        spread_bps: f64,
+        max_bps: f64,
+    },
+
+    /// A market order stopped by a `quotes::MarketOrderGuard`: walking the book
+    /// for its volume would slip further than allowed (`expected_bps` is `None`
+    /// when the visible book can't fill it at all)
+    #[error("Expected slippage on {symbol} is {}, over the {max_bps} bps allowed", expected_bps.map_or("beyond the visible book".to_string(), |bps| format!("{bps:.1} bps")))]
+    SlippageTooHigh {
+        symbol: String,
+        expected_bps: Option<f64>,
+        max_bps: f64,
+    },
+
+    /// An order refused locally because Kraken isn't accepting it in its current state
+    #[error("Kraken is in {state} mode; refused {operation} locally")]
+    ExchangeRestricted {
+        state: ExchangeState,
+        operation: String,
+    },
+
+    /// An order refused locally because the session's kill switch was pulled
+    #[error("Session halted by its kill switch; refused {operation} locally")]
+    SessionHalted { operation: String },
+
+    /// An order refused locally because the session is shutting down
+    #[error("Session is shutting down; refused {operation} locally")]
+    SessionClosed { operation: String },
+
+    /// An order refused locally because its strategy is over one of its
+    /// `StrategyLimits`
+    #[error("Strategy {strategy} is over its limit: {reason}")]
+    StrategyLimitExceeded { strategy: String, reason: String },
+
+    /// An order refused locally because, filled, it would break one of the
+    /// `risk::RiskLimits`
+    #[cfg(any(feature = "rest", feature = "ws"))]
+    #[error("{limit} on {pair} would be {value}, over the {max} allowed; refused locally")]
+    RiskLimitExceeded {
+        pair: String,
+        limit: crate::risk::RiskLimit,
+        value: f64,
+        max: f64,
+    },
+
+    /// An order that can't be sized above the pair's minimum volume or cost
+    #[error("Order in {pair} is below the minimum: {reason}")]
+    OrderBelowMinimum { pair: String, reason: String },
+
+    /// A WebSocket subscription refused locally: the connection already carries
+    /// its `SubscriptionBudget`
+    #[error("Subscription budget of {max} reached; refused {subscription}")]
+    SubscriptionBudgetExceeded { subscription: String, max: usize },
+
+    /// Kraken rejected a private request's signature (`EAPI:Invalid signature`).
+    /// `payload` is exactly what was signed and sent; `payload.post_data()` is
+    /// the body byte for byte, while its `Debug` output redacts `otp`.
+    #[error("Kraken rejected the signature for {} (nonce {}): {message}", payload.path(), payload.nonce())]
+    SignatureRejected {
+        message: String,
+        payload: Box<SignedPayload>,
+    },
+
+    /// A proof-of-reserves inclusion proof that doesn't hash up to its audit's
+    /// published root
+    #[error("Inclusion proof for audit {audit_id} does not verify: {reason}")]
+    InclusionProofInvalid { audit_id: String, reason: String },
+
+    /// `reprice_order` cancelled the order but could not place its replacement
+    #[error("Cancelled order {txid} but could not re-add it: {source}")]
+    ReplaceFailed {
+        txid: String,
+        #[source]
+        source: Box<KrakenError>,
+    },
+
+    /// A request to a production URL refused locally in sandbox mode
+    /// (`environment::set_sandbox`)
+    #[error("Sandbox mode refused to connect to production URL {url}")]
+    ProductionRefused { url: String },
+
+    /// Failure reading from or writing to a local cache
+    #[error("Cache error: {0}")]
+    Cache(String),
+
+    /// An error from a REST round trip, tagged with the `X-Request-ID` that was sent
+    #[error("{source} (request id: {request_id})")]
+    Request {
+        request_id: String,
+        #[source]
+        source: Box<KrakenError>,
+    },
+}
+
+fn staleness(age: Option<Duration>, max_age: Option<Duration>) -> String {
+    match (age, max_age) {
+        (Some(age), Some(max_age)) => format!("last update {age:?} ago, over the {max_age:?} allowed"),
+        (Some(age), None) => format!("last update {age:?} ago"),
+        (None, _) => "no quote received".to_string(),
+    }
 }
 
 /// We store `KrakenError::Kraken` for multiple error messages, but
@@ -47,6 +200,53 @@ pub enum KrakenError {
 pub type KrakenResult<T> = Result<T, KrakenError>;
 
 impl KrakenError {
+    /// Tag this error with the ID of the request that produced it.
+    pub fn with_request_id(self, request_id: &str) -> Self {
+        match self {
+            KrakenError::Request { .. } => self,
+            other => KrakenError::Request {
+                request_id: request_id.to_string(),
+                source: Box::new(other),
+            },
+        }
+    }
+
+    /// Attach what was signed to an `EAPI:Invalid signature` rejection, turning
+    /// it into `SignatureRejected`. Other errors are returned unchanged.
+    #[cfg(feature = "rest")]
+    pub(crate) fn with_signed_payload(self, payload: SignedPayload) -> Self {
+        match self {
+            KrakenError::Request { request_id, source } => KrakenError::Request {
+                request_id,
+                source: Box::new(source.with_signed_payload(payload)),
+            },
+            KrakenError::ApiError { message } if message.contains("Invalid signature") => {
+                KrakenError::SignatureRejected {
+                    message,
+                    payload: Box::new(payload),
+                }
+            }
+            other => other,
+        }
+    }
+
+    /// The request ID, if this error came from a REST round trip.
+    pub fn request_id(&self) -> Option<&str> {
+        match self {
+            KrakenError::Request { request_id, .. } => Some(request_id),
+            _ => None,
+        }
+    }
+
+    /// The underlying error with any request-ID wrapper removed. Match on this
+    /// when you care about the kind of failure (e.g. `RateLimitExceeded`).
+    pub fn inner(&self) -> &KrakenError {
+        match self {
+            KrakenError::Request { source, .. } => source.inner(),
+            other => other,
+        }
+    }
+
     /// Attempt to interpret the Kraken error array for known error codes:
     ///
     /// Examples from docs:
@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+use std::future::Future;
+
+use futures_util::future::{BoxFuture, FutureExt};
+use futures_util::stream::{Stream, StreamExt};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::error::KrakenResult;
+use crate::exchange::{ExchangeClient, OrderAmendment, OrderRequest, PlacedOrder, Position};
+use crate::ws_models::{ExecutionData, WsExecutionsMessage};
+
+/// Something that happened to an order, as forwarded to an `OrderEventSink`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OrderEvent {
+    /// The venue accepted `order` as `order_id`
+    Placed {
+        order: OrderRequest,
+        order_id: String,
+    },
+    /// `order` was refused, locally or by the venue
+    Rejected {
+        order: OrderRequest,
+        error: String,
+    },
+    Amended(OrderAmendment),
+    Cancelled {
+        order_id: String,
+    },
+    /// A fill, from the executions feed
+    Filled(ExecutionData),
+}
+
+impl OrderEvent {
+    /// The order this event is about, once the venue has given it an ID.
+    pub fn order_id(&self) -> Option<&str> {
+        match self {
+            OrderEvent::Placed { order_id, .. } | OrderEvent::Cancelled { order_id } => {
+                Some(order_id)
+            }
+            OrderEvent::Amended(amendment) => Some(&amendment.order_id),
+            OrderEvent::Filled(execution) => Some(&execution.order_id),
+            OrderEvent::Rejected { .. } => None,
+        }
+    }
+}
+
+/// Delivers `OrderEvent`s somewhere else: an HTTP webhook, a message queue, a
+/// database. Implemented for async closures returning `KrakenResult<()>`.
+pub trait OrderEventSink: Send + Sync + 'static {
+    fn deliver(&self, event: OrderEvent) -> BoxFuture<'static, KrakenResult<()>>;
+}
+
+impl<F, Fut> OrderEventSink for F
+where
+    F: Fn(OrderEvent) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = KrakenResult<()>> + Send + 'static,
+{
+    fn deliver(&self, event: OrderEvent) -> BoxFuture<'static, KrakenResult<()>> {
+        self(event).boxed()
+    }
+}
+
+/// Hands `OrderEvent`s to an `OrderEventSink` on a background task, so
+/// strategy code publishing them never waits on delivery.
+///
+/// Events are delivered one at a time in the order published; a failed
+/// delivery is logged at `warn` and dropped. Clones publish to the same sink.
+/// The task ends once every clone is dropped and the backlog is delivered,
+/// so awaiting the handle from `spawn` flushes it.
+///
+/// ```no_run
+/// # async fn run(session: onise::session::KrakenSession, ws: onise::ws_client::KrakenWsClient) -> onise::error::KrakenResult<()> {
+/// use onise::events::{EventDispatcher, NotifyingExchange, OrderEvent};
+/// use onise::exchange::{ExchangeClient, OrderRequest, Side};
+///
+/// let (events, _delivery) = EventDispatcher::spawn(|event: OrderEvent| async move {
+///     // e.g. POST it to a webhook
+///     println!("{event:?}");
+///     Ok(())
+/// });
+/// events.follow_executions(ws.executions_stream());
+/// let exchange = NotifyingExchange::new(session, events);
+/// exchange.place_order(&OrderRequest::market("BTC/USD", Side::Buy, "0.01")).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct EventDispatcher {
+    events: mpsc::UnboundedSender<OrderEvent>,
+}
+
+impl EventDispatcher {
+    /// Start delivering to `sink`.
+    pub fn spawn(sink: impl OrderEventSink) -> (Self, JoinHandle<()>) {
+        let (events, mut queue) = mpsc::unbounded_channel::<OrderEvent>();
+        let delivery = tokio::spawn(async move {
+            while let Some(event) = queue.recv().await {
+                let order_id = event.order_id().map(str::to_string);
+                if let Err(e) = sink.deliver(event).await {
+                    tracing::warn!(order_id = ?order_id, error = %e, "order event delivery failed");
+                }
+            }
+        });
+        (Self { events }, delivery)
+    }
+
+    /// Queue `event` for delivery. Events published after the delivery task
+    /// has been aborted are dropped.
+    pub fn publish(&self, event: OrderEvent) {
+        let _ = self.events.send(event);
+    }
+
+    /// Publish every execution in `executions` (e.g. `ws.executions_stream()`)
+    /// as `OrderEvent::Filled`, until the stream ends or the returned handle is
+    /// aborted.
+    pub fn follow_executions(
+        &self,
+        executions: impl Stream<Item = WsExecutionsMessage> + Send + 'static,
+    ) -> JoinHandle<()> {
+        let dispatcher = self.clone();
+        tokio::spawn(async move {
+            let mut executions = std::pin::pin!(executions);
+            while let Some(message) = executions.next().await {
+                for execution in message.executions {
+                    dispatcher.publish(OrderEvent::Filled(execution));
+                }
+            }
+        })
+    }
+}
+
+/// An `ExchangeClient` wrapper publishing every placement, rejection,
+/// amendment and cancel that goes through it to an `EventDispatcher`. Fills
+/// come from `EventDispatcher::follow_executions`.
+#[derive(Debug)]
+pub struct NotifyingExchange<E> {
+    inner: E,
+    events: EventDispatcher,
+}
+
+impl<E: ExchangeClient> NotifyingExchange<E> {
+    pub fn new(inner: E, events: EventDispatcher) -> Self {
+        Self { inner, events }
+    }
+
+    pub fn inner(&self) -> &E {
+        &self.inner
+    }
+
+    pub fn events(&self) -> &EventDispatcher {
+        &self.events
+    }
+}
+
+impl<E: ExchangeClient> ExchangeClient for NotifyingExchange<E> {
+    async fn place_order(&self, order: &OrderRequest) -> KrakenResult<PlacedOrder> {
+        let placed = self.inner.place_order(order).await;
+        self.events.publish(match &placed {
+            Ok(placed) => OrderEvent::Placed {
+                order: order.clone(),
+                order_id: placed.order_id.clone(),
+            },
+            Err(e) => OrderEvent::Rejected {
+                order: order.clone(),
+                error: e.to_string(),
+            },
+        });
+        placed
+    }
+
+    async fn cancel_order(&self, order_id: &str) -> KrakenResult<()> {
+        self.inner.cancel_order(order_id).await?;
+        self.events.publish(OrderEvent::Cancelled {
+            order_id: order_id.to_string(),
+        });
+        Ok(())
+    }
+
+    async fn amend_order(&self, amendment: &OrderAmendment) -> KrakenResult<()> {
+        self.inner.amend_order(amendment).await?;
+        self.events.publish(OrderEvent::Amended(amendment.clone()));
+        Ok(())
+    }
+
+    async fn positions(&self) -> KrakenResult<Vec<Position>> {
+        self.inner.positions().await
+    }
+
+    async fn balances(&self) -> KrakenResult<HashMap<String, String>> {
+        self.inner.balances().await
+    }
+}
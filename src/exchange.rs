@@ -0,0 +1,378 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::KrakenResult;
+use crate::numeric::IntoAmount;
+
+/// Order side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+impl Side {
+    /// "buy" / "sell", as Kraken spells it.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Side::Buy => "buy",
+            Side::Sell => "sell",
+        }
+    }
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Execution style of an `OrderRequest`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum OrderKind {
+    Market,
+    Limit { price: String },
+}
+
+/// A venue-neutral new order, as accepted by `ExchangeClient::place_order`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderRequest {
+    pub pair: String,
+    pub side: Side,
+    pub kind: OrderKind,
+    /// Quantity in base currency
+    pub volume: String,
+    /// Client order ID to tag the order with
+    pub cl_ord_id: Option<String>,
+    /// Strategy the order belongs to, for per-strategy limits
+    /// (`strategy_limits::StrategyThrottle`). Local only; never sent to Kraken.
+    pub strategy: Option<String>,
+    /// Account to place the order on, for sessions trading several
+    /// subaccounts (`session::KrakenSession::with_account`). Local only.
+    pub account: Option<String>,
+}
+
+impl OrderRequest {
+    pub fn market(pair: impl Into<String>, side: Side, volume: impl IntoAmount) -> Self {
+        Self {
+            pair: pair.into(),
+            side,
+            kind: OrderKind::Market,
+            volume: volume.into_amount(),
+            cl_ord_id: None,
+            strategy: None,
+            account: None,
+        }
+    }
+
+    pub fn limit(
+        pair: impl Into<String>,
+        side: Side,
+        volume: impl IntoAmount,
+        price: impl IntoAmount,
+    ) -> Self {
+        Self {
+            kind: OrderKind::Limit {
+                price: price.into_amount(),
+            },
+            ..Self::market(pair, side, volume)
+        }
+    }
+
+    pub fn with_cl_ord_id(mut self, cl_ord_id: impl Into<String>) -> Self {
+        self.cl_ord_id = Some(cl_ord_id.into());
+        self
+    }
+
+    pub fn with_strategy(mut self, strategy: impl Into<String>) -> Self {
+        self.strategy = Some(strategy.into());
+        self
+    }
+
+    pub fn with_account(mut self, account: impl Into<String>) -> Self {
+        self.account = Some(account.into());
+        self
+    }
+
+    /// The limit price, if this is a limit order.
+    pub fn limit_price(&self) -> Option<&str> {
+        match &self.kind {
+            OrderKind::Market => None,
+            OrderKind::Limit { price } => Some(price),
+        }
+    }
+}
+
+/// Changes to a resting order, for `ExchangeClient::amend_order`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderAmendment {
+    pub order_id: String,
+    /// New total quantity
+    pub volume: Option<String>,
+    /// New limit price
+    pub limit_price: Option<String>,
+}
+
+impl OrderAmendment {
+    pub fn new(order_id: impl Into<String>) -> Self {
+        Self {
+            order_id: order_id.into(),
+            volume: None,
+            limit_price: None,
+        }
+    }
+
+    pub fn with_volume(mut self, volume: impl IntoAmount) -> Self {
+        self.volume = Some(volume.into_amount());
+        self
+    }
+
+    pub fn with_limit_price(mut self, limit_price: impl IntoAmount) -> Self {
+        self.limit_price = Some(limit_price.into_amount());
+        self
+    }
+}
+
+/// An accepted order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlacedOrder {
+    /// The venue's order ID (a txid on Kraken)
+    pub order_id: String,
+}
+
+/// An open position.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Position {
+    pub pair: String,
+    pub side: Side,
+    /// Quantity in base currency
+    pub volume: String,
+    /// Cost in quote currency
+    pub cost: String,
+}
+
+/// The order routing surface shared by `KrakenClient` (REST), `KrakenWsClient`
+/// (WebSocket trading) and `simulated::SimulatedExchange` (paper trading), so
+/// strategies and multi-venue frameworks can depend on this trait rather than
+/// a concrete client.
+///
+/// Venue rejections come back as the usual `KrakenError` variants (e.g.
+/// `OrderError` for insufficient funds or an unknown order).
+pub trait ExchangeClient: Send + Sync {
+    /// Place a new order.
+    fn place_order(
+        &self,
+        order: &OrderRequest,
+    ) -> impl Future<Output = KrakenResult<PlacedOrder>> + Send;
+
+    /// Cancel the order with venue order ID `order_id`.
+    fn cancel_order(&self, order_id: &str) -> impl Future<Output = KrakenResult<()>> + Send;
+
+    /// Change a resting order's volume and/or limit price in place.
+    fn amend_order(
+        &self,
+        amendment: &OrderAmendment,
+    ) -> impl Future<Output = KrakenResult<()>> + Send;
+
+    /// Open (margin) positions.
+    fn positions(&self) -> impl Future<Output = KrakenResult<Vec<Position>>> + Send;
+
+    /// Balances by asset code.
+    fn balances(&self) -> impl Future<Output = KrakenResult<HashMap<String, String>>> + Send;
+}
+
+#[cfg(feature = "rest")]
+mod rest {
+    use super::*;
+    use crate::error::KrakenError;
+    use crate::params::AmendOrderRequest;
+    use crate::rest_client::AuthenticatedClient;
+
+    impl ExchangeClient for AuthenticatedClient {
+        async fn place_order(&self, order: &OrderRequest) -> KrakenResult<PlacedOrder> {
+            let mut params = vec![
+                ("pair", order.pair.as_str()),
+                ("type", order.side.as_str()),
+                ("volume", order.volume.as_str()),
+            ];
+            match order.limit_price() {
+                None => params.push(("ordertype", "market")),
+                Some(price) => params.extend([("ordertype", "limit"), ("price", price)]),
+            }
+            if let Some(cl_ord_id) = &order.cl_ord_id {
+                params.push(("cl_ord_id", cl_ord_id));
+            }
+            let added = self.add_order(&params).await?;
+            match added.txid.into_iter().next() {
+                Some(order_id) => Ok(PlacedOrder { order_id }),
+                None => Err(KrakenError::Kraken(vec![
+                    "AddOrder returned no txid".to_string()
+                ])),
+            }
+        }
+
+        async fn cancel_order(&self, order_id: &str) -> KrakenResult<()> {
+            AuthenticatedClient::cancel_order(self, &[("txid", order_id)])
+                .await
+                .map(drop)
+        }
+
+        async fn amend_order(&self, amendment: &OrderAmendment) -> KrakenResult<()> {
+            let mut request = AmendOrderRequest::by_txid(&amendment.order_id);
+            request.order_qty = amendment.volume.clone();
+            request.limit_price = amendment.limit_price.clone();
+            self.amend(&request).await.map(drop)
+        }
+
+        async fn positions(&self) -> KrakenResult<Vec<Position>> {
+            let open = self.get_open_positions(&[]).await?;
+            Ok(open
+                .positions
+                .into_values()
+                .map(|position| Position {
+                    side: if position.side == "sell" {
+                        Side::Sell
+                    } else {
+                        Side::Buy
+                    },
+                    pair: position.pair,
+                    volume: position.vol,
+                    cost: position.cost,
+                })
+                .collect())
+        }
+
+        async fn balances(&self) -> KrakenResult<HashMap<String, String>> {
+            Ok(self.get_balance().await?.balances)
+        }
+    }
+}
+
+#[cfg(feature = "ws")]
+mod ws {
+    use std::time::Duration;
+
+    use futures_util::StreamExt;
+
+    use super::*;
+    use crate::error::KrakenError;
+    use crate::ws_client::KrakenWsClient;
+    use crate::ws_models::{
+        WsAddOrderRequest, WsAmendOrderRequest, WsCancelOrderRequest, WsSubscriptionPayload,
+        WsUserTradingResponse,
+    };
+
+    /// How long WebSocket trading calls wait for their order status.
+    pub const WS_TRADING_DEADLINE: Duration = Duration::from_secs(10);
+
+    /// Trades over this connection with its `token`, waiting up to
+    /// `WS_TRADING_DEADLINE` for each reply. `positions` is not available over
+    /// the WebSocket API and fails with `InvalidUsage`.
+    impl ExchangeClient for KrakenWsClient {
+        async fn place_order(&self, order: &OrderRequest) -> KrakenResult<PlacedOrder> {
+            let request = WsAddOrderRequest {
+                event: "addOrder".to_string(),
+                token: trading_token(self)?,
+                req_id: Some(self.next_req_id()),
+                order_type: match order.kind {
+                    OrderKind::Market => "market",
+                    OrderKind::Limit { .. } => "limit",
+                }
+                .to_string(),
+                symbol: order.pair.clone(),
+                side: order.side.as_str().to_string(),
+                quantity: order.volume.clone(),
+                price: order.limit_price().map(str::to_string),
+                client_order_id: order.cl_ord_id.clone(),
+                ..Default::default()
+            };
+            match self
+                .order_request(&request, Some(WS_TRADING_DEADLINE))
+                .await?
+            {
+                WsUserTradingResponse::AddOrderStatus {
+                    txid: Some(order_id),
+                    ..
+                } => Ok(PlacedOrder { order_id }),
+                other => Err(KrakenError::InvalidUsage(format!(
+                    "addOrder was answered without a txid: {other:?}"
+                ))),
+            }
+        }
+
+        async fn cancel_order(&self, order_id: &str) -> KrakenResult<()> {
+            let request = WsCancelOrderRequest {
+                event: "cancelOrder".to_string(),
+                token: trading_token(self)?,
+                req_id: Some(self.next_req_id()),
+                txid: order_id.to_string(),
+            };
+            self.order_request(&request, Some(WS_TRADING_DEADLINE))
+                .await
+                .map(drop)
+        }
+
+        async fn amend_order(&self, amendment: &OrderAmendment) -> KrakenResult<()> {
+            let request = WsAmendOrderRequest {
+                event: "amendOrder".to_string(),
+                token: trading_token(self)?,
+                req_id: Some(self.next_req_id()),
+                txid: amendment.order_id.clone(),
+                quantity: amendment.volume.clone(),
+                limit_price: amendment.limit_price.clone(),
+                ..Default::default()
+            };
+            self.order_request(&request, Some(WS_TRADING_DEADLINE))
+                .await
+                .map(drop)
+        }
+
+        async fn positions(&self) -> KrakenResult<Vec<Position>> {
+            Err(KrakenError::InvalidUsage(
+                "open positions are not available over the WebSocket API; use the REST client"
+                    .to_string(),
+            ))
+        }
+
+        /// Subscribes to `balances`, takes the snapshot and unsubscribes again.
+        async fn balances(&self) -> KrakenResult<HashMap<String, String>> {
+            let mut updates = self.balances_stream();
+            let req_id = self.next_req_id();
+            self.subscribe_and_wait(
+                WsSubscriptionPayload::Balances,
+                req_id,
+                Some(WS_TRADING_DEADLINE),
+            )
+            .await?;
+            let snapshot = tokio::time::timeout(WS_TRADING_DEADLINE, updates.next()).await;
+            self.unsubscribe(WsSubscriptionPayload::Balances, None)
+                .await?;
+            match snapshot {
+                Ok(Some(message)) => Ok(message.balances),
+                Ok(None) => Err(KrakenError::InvalidUsage(
+                    "connection closed before the balances snapshot arrived".to_string(),
+                )),
+                Err(_) => Err(KrakenError::Timeout {
+                    operation: "balances snapshot".to_string(),
+                    after: WS_TRADING_DEADLINE,
+                }),
+            }
+        }
+    }
+
+    fn trading_token(client: &KrakenWsClient) -> KrakenResult<String> {
+        client.token.clone().ok_or_else(|| {
+            KrakenError::InvalidUsage(
+                "WebSocket trading needs `token` set on the KrakenWsClient".to_string(),
+            )
+        })
+    }
+}
+
+#[cfg(feature = "ws")]
+pub use ws::WS_TRADING_DEADLINE;
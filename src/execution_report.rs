@@ -0,0 +1,185 @@
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+use crate::error::KrakenResult;
+use crate::exchange::Side;
+use crate::numeric::Amount;
+use crate::ws_models::{ExecutionData, WsTradesMessage};
+
+/// How a parent order was executed, from `ParentExecution::report`.
+///
+/// Prices are in the pair's quote currency. Slippages are in basis points
+/// and signed against the order: positive means it paid more (buy) or
+/// received less (sell) than the reference price.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExecutionReport {
+    pub parent_id: String,
+    pub symbol: String,
+    pub side: Side,
+    /// Quantity the parent order set out to trade
+    pub quantity: f64,
+    pub filled: f64,
+    pub child_orders: usize,
+    pub fills: usize,
+    /// Mid price when the parent order started
+    pub arrival_price: f64,
+    /// Volume-weighted average price of every fill, `None` before the first
+    pub avg_fill_price: Option<f64>,
+    /// `avg_fill_price` against `arrival_price` (implementation shortfall)
+    pub arrival_slippage_bps: Option<f64>,
+    /// Each fill against the mid price at the time it came in, weighted by
+    /// quantity
+    pub mid_slippage_bps: Option<f64>,
+    /// Fees of every fill, added up in their fee currency
+    pub fee_total: f64,
+    /// `filled` as a share of the volume the market traded while the parent
+    /// order was worked (0 to 1), when that volume was fed in
+    pub participation_rate: Option<f64>,
+    /// Times of the first and last fill, as the executions carry them
+    pub first_fill_time: Option<u64>,
+    pub last_fill_time: Option<u64>,
+}
+
+impl ExecutionReport {
+    /// The whole report as pretty-printed JSON.
+    pub fn to_json(&self) -> KrakenResult<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// Best-execution bookkeeping for one parent order worked as child orders,
+/// e.g. the slices of a TWAP or the refills of an iceberg.
+///
+/// Register each child with `add_child` as it is placed, then feed in
+/// executions (`apply_execution`; others' and repeated ones are ignored),
+/// mid prices (`on_mid`, e.g. from the ticker or `OrderBook`) and public
+/// trades on the symbol (`on_market_trades`) while the parent order is
+/// worked. `report` can be called at any point.
+#[derive(Debug, Clone)]
+pub struct ParentExecution {
+    parent_id: String,
+    symbol: String,
+    side: Side,
+    quantity: f64,
+    arrival_price: f64,
+    mid: f64,
+    children: HashSet<String>,
+    exec_ids: HashSet<String>,
+    filled: f64,
+    cost: f64,
+    mid_cost: f64,
+    fees: f64,
+    market_volume: f64,
+    first_fill_time: Option<u64>,
+    last_fill_time: Option<u64>,
+}
+
+impl ParentExecution {
+    /// Start tracking `parent_id`, to trade `quantity` of `symbol` with the
+    /// mid price at `arrival_price`.
+    pub fn new(
+        parent_id: impl Into<String>,
+        symbol: impl Into<String>,
+        side: Side,
+        quantity: f64,
+        arrival_price: f64,
+    ) -> Self {
+        Self {
+            parent_id: parent_id.into(),
+            symbol: symbol.into(),
+            side,
+            quantity,
+            arrival_price,
+            mid: arrival_price,
+            children: HashSet::new(),
+            exec_ids: HashSet::new(),
+            filled: 0.0,
+            cost: 0.0,
+            mid_cost: 0.0,
+            fees: 0.0,
+            market_volume: 0.0,
+            first_fill_time: None,
+            last_fill_time: None,
+        }
+    }
+
+    /// Count `order_id`'s executions towards this parent order.
+    pub fn add_child(&mut self, order_id: impl Into<String>) {
+        self.children.insert(order_id.into());
+    }
+
+    /// The mid price now, which the following fills are measured against.
+    pub fn on_mid(&mut self, mid: f64) {
+        self.mid = mid;
+    }
+
+    /// Public trades; those on the parent order's symbol, its own fills
+    /// included, make up the market volume for `participation_rate`.
+    pub fn on_market_trades(&mut self, trades: &WsTradesMessage) -> KrakenResult<()> {
+        if trades.symbol != self.symbol {
+            return Ok(());
+        }
+        for trade in &trades.trades {
+            self.market_volume += Amount(&trade.quantity).to_f64_lossy()?;
+        }
+        Ok(())
+    }
+
+    /// Record one execution; `false` if it isn't one of the children's or
+    /// was seen before.
+    pub fn apply_execution(&mut self, execution: &ExecutionData) -> KrakenResult<bool> {
+        if !self.children.contains(&execution.order_id)
+            || self.exec_ids.contains(&execution.exec_id)
+        {
+            return Ok(false);
+        }
+        let qty = Amount(&execution.quantity).to_f64_lossy()?;
+        let price = Amount(&execution.price).to_f64_lossy()?;
+        let fee = Amount(&execution.fee).to_f64_lossy()?;
+
+        self.exec_ids.insert(execution.exec_id.clone());
+        self.filled += qty;
+        self.cost += qty * price;
+        self.mid_cost += qty * self.mid;
+        self.fees += fee;
+        self.first_fill_time.get_or_insert(execution.time);
+        self.last_fill_time = Some(execution.time);
+        Ok(true)
+    }
+
+    pub fn report(&self) -> ExecutionReport {
+        let avg_fill_price = (self.filled > 0.0).then(|| self.cost / self.filled);
+        let mid_price = (self.filled > 0.0).then(|| self.mid_cost / self.filled);
+        ExecutionReport {
+            parent_id: self.parent_id.clone(),
+            symbol: self.symbol.clone(),
+            side: self.side,
+            quantity: self.quantity,
+            filled: self.filled,
+            child_orders: self.children.len(),
+            fills: self.exec_ids.len(),
+            arrival_price: self.arrival_price,
+            avg_fill_price,
+            arrival_slippage_bps: avg_fill_price
+                .map(|price| slippage_bps(self.side, price, self.arrival_price)),
+            mid_slippage_bps: avg_fill_price
+                .zip(mid_price)
+                .map(|(price, mid)| slippage_bps(self.side, price, mid)),
+            fee_total: self.fees,
+            participation_rate: (self.market_volume > 0.0)
+                .then(|| (self.filled / self.market_volume).min(1.0)),
+            first_fill_time: self.first_fill_time,
+            last_fill_time: self.last_fill_time,
+        }
+    }
+}
+
+/// How much worse `price` is than `reference` for `side`, in basis points.
+fn slippage_bps(side: Side, price: f64, reference: f64) -> f64 {
+    let bps = (price - reference) / reference * 10_000.0;
+    match side {
+        Side::Buy => bps,
+        Side::Sell => -bps,
+    }
+}
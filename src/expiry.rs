@@ -0,0 +1,132 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use time::OffsetDateTime;
+
+use crate::error::{KrakenError, KrakenResult};
+
+/// Shortest lead time Kraken accepts for a good-til-date order.
+pub const MIN_EXPIRE_LEAD: Duration = Duration::from_secs(5);
+
+/// When a good-til-date (GTD) order expires, in the two forms Kraken accepts:
+/// relative to when the order reaches the engine, or an absolute time.
+///
+/// Render it with `to_expiretm` for REST `AddOrder`/`EditOrder` (`expiretm`)
+/// or `to_expire_time` for the WebSocket models (`expire_time`). Absolute times
+/// are compared with Kraken's clock, not yours, so check them with `validate`
+/// (or `KrakenClient::check_expire_time`) if the local clock may be off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpireTime {
+    /// Expire this long after Kraken receives the order (`+<n>`)
+    In(Duration),
+    /// Expire at this instant
+    At(SystemTime),
+}
+
+impl ExpireTime {
+    pub fn after(duration: Duration) -> Self {
+        ExpireTime::In(duration)
+    }
+
+    pub fn at(instant: impl Into<SystemTime>) -> Self {
+        ExpireTime::At(instant.into())
+    }
+
+    /// The REST `expiretm` value: `"+<seconds>"` or a Unix timestamp.
+    /// Sub-second parts are truncated, as Kraken only takes whole seconds.
+    pub fn to_expiretm(&self) -> String {
+        match self {
+            ExpireTime::In(duration) => format!("+{}", duration.as_secs()),
+            ExpireTime::At(instant) => unix_seconds(*instant).to_string(),
+        }
+    }
+
+    /// The WebSocket `expire_time` value, an RFC 3339 UTC time such as
+    /// "2023-12-31T23:59:59Z". Relative expiries are resolved against `now`,
+    /// ideally the server's time. Fails with `InvalidUsage` if the expiry is
+    /// before 1970 or past what `time` can represent.
+    pub fn to_expire_time(&self, now: SystemTime) -> KrakenResult<String> {
+        let instant = self.resolve(now)?;
+        let utc = instant
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .and_then(|since| i64::try_from(since.as_secs()).ok())
+            .and_then(|secs| OffsetDateTime::from_unix_timestamp(secs).ok())
+            .ok_or_else(|| {
+                KrakenError::InvalidUsage(format!("expire time {instant:?} is out of range"))
+            })?;
+        Ok(format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            utc.year(),
+            u8::from(utc.month()),
+            utc.day(),
+            utc.hour(),
+            utc.minute(),
+            utc.second()
+        ))
+    }
+
+    /// The absolute expiry, resolving a relative one against `now`. Fails with
+    /// `InvalidUsage` if `now` plus the duration overflows `SystemTime`.
+    pub fn resolve(&self, now: SystemTime) -> KrakenResult<SystemTime> {
+        match self {
+            ExpireTime::In(duration) => now.checked_add(*duration).ok_or_else(|| {
+                KrakenError::InvalidUsage(format!("expire time +{duration:?} is out of range"))
+            }),
+            ExpireTime::At(instant) => Ok(*instant),
+        }
+    }
+
+    /// Check that Kraken, whose clock reads `server_now`, would accept this
+    /// expiry: at least `MIN_EXPIRE_LEAD` in the future. Fails with
+    /// `InvalidUsage` naming the local/server clock skew when that explains it.
+    pub fn validate(&self, server_now: SystemTime) -> KrakenResult<()> {
+        let lead = match self {
+            ExpireTime::In(duration) => Some(*duration),
+            ExpireTime::At(instant) => instant.duration_since(server_now).ok(),
+        };
+        if lead.is_some_and(|lead| lead >= MIN_EXPIRE_LEAD) {
+            return Ok(());
+        }
+        let skew = match SystemTime::now().duration_since(server_now) {
+            Ok(ahead) => format!("local clock is {ahead:?} ahead of Kraken's"),
+            Err(behind) => format!("local clock is {:?} behind Kraken's", behind.duration()),
+        };
+        Err(KrakenError::InvalidUsage(format!(
+            "expire time {} must be at least {MIN_EXPIRE_LEAD:?} after Kraken's current time {} ({skew})",
+            self.to_expiretm(),
+            unix_seconds(server_now)
+        )))
+    }
+}
+
+impl From<Duration> for ExpireTime {
+    fn from(duration: Duration) -> Self {
+        ExpireTime::In(duration)
+    }
+}
+
+impl From<SystemTime> for ExpireTime {
+    fn from(instant: SystemTime) -> Self {
+        ExpireTime::At(instant)
+    }
+}
+
+impl From<OffsetDateTime> for ExpireTime {
+    fn from(instant: OffsetDateTime) -> Self {
+        ExpireTime::At(instant.into())
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<chrono::DateTime<chrono::Utc>> for ExpireTime {
+    fn from(instant: chrono::DateTime<chrono::Utc>) -> Self {
+        ExpireTime::At(instant.into())
+    }
+}
+
+fn unix_seconds(instant: SystemTime) -> u64 {
+    instant
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs())
+        .unwrap_or(0)
+}
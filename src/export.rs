@@ -0,0 +1,236 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures_util::stream::{self, BoxStream};
+use futures_util::{Stream, StreamExt};
+use serde::Serialize;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::error::{KrakenError, KrakenResult};
+use crate::funding::{self, WatchBackoff};
+use crate::models::ExportReportStatus;
+use crate::params::{self, LedgersParams, TradesHistoryParams};
+use crate::AuthenticatedClient;
+
+/// Where an `ExportTrades` report is, as reported by `ExportStatus`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExportProgress {
+    Queued,
+    /// Being generated; `rows` is Kraken's `totalrows` so far, if it sent one
+    Processing { rows: Option<u64> },
+    /// Ready for `RetrieveExport`
+    Finished { rows: Option<u64> },
+    /// Failed or was deleted; `status` as Kraken sent it
+    Error { status: String },
+}
+
+impl ExportProgress {
+    /// "Queued", "Processing" and "Processed" (or "Finished") map to their
+    /// variants; any other status is an `Error`.
+    pub fn from_status(report: &ExportReportStatus) -> Self {
+        let rows = report.totalrows;
+        match report.status.to_ascii_lowercase().as_str() {
+            "queued" => ExportProgress::Queued,
+            "processing" => ExportProgress::Processing { rows },
+            "processed" | "finished" => ExportProgress::Finished { rows },
+            _ => ExportProgress::Error {
+                status: report.status.clone(),
+            },
+        }
+    }
+
+    /// `true` for `Finished` and `Error`.
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            ExportProgress::Finished { .. } | ExportProgress::Error { .. }
+        )
+    }
+}
+
+/// Follows one export report until it finishes or fails; see
+/// `AuthenticatedClient::export_progress`.
+///
+/// As a `Stream` it yields the report's `ExportProgress` each time it changes
+/// (including the row count while processing), ending after `Finished` or
+/// `Error`; `finished` skips to that. Polls back off per `WatchBackoff`. A
+/// report not listed yet, and transient failures, are retried; any other
+/// error is yielded and ends the stream.
+pub struct ExportProgressStream {
+    client: AuthenticatedClient,
+    report: String,
+    id: String,
+    backoff: WatchBackoff,
+    inner: Option<BoxStream<'static, KrakenResult<ExportProgress>>>,
+}
+
+impl ExportProgressStream {
+    /// Poll on this schedule instead of `WatchBackoff::default()`.
+    pub fn with_backoff(mut self, backoff: WatchBackoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Wait for `Finished` or `Error`, ignoring the updates before it.
+    pub async fn finished(mut self) -> KrakenResult<ExportProgress> {
+        let mut last = None;
+        while let Some(progress) = self.next().await {
+            last = Some(progress?);
+        }
+        last.ok_or_else(|| KrakenError::InvalidUsage("export progress ended early".to_string()))
+    }
+
+    fn start(&self) -> BoxStream<'static, KrakenResult<ExportProgress>> {
+        let state = (
+            self.client.clone(),
+            self.report.clone(),
+            self.id.clone(),
+            self.backoff,
+            None::<Duration>,
+            None::<ExportProgress>,
+        );
+        stream::unfold(Some(state), |state| async move {
+            let (client, report, id, backoff, mut wait, mut last) = state?;
+            loop {
+                if let Some(pause) = wait {
+                    tokio::time::sleep(pause).await;
+                }
+                wait = Some(match wait {
+                    None => backoff.initial,
+                    Some(pause) => (pause * backoff.factor).min(backoff.max),
+                });
+                let reports = match client.get_export_report_status(&[("report", &report)]).await {
+                    Ok(status) => status.reports,
+                    Err(e) if funding::is_transient(&e) => {
+                        tracing::warn!(error = %e, "export status poll failed; retrying");
+                        continue;
+                    }
+                    Err(e) => return Some((Err(e), None)),
+                };
+                let Some(progress) = reports
+                    .iter()
+                    .find(|status| status.id == id)
+                    .map(ExportProgress::from_status)
+                else {
+                    continue;
+                };
+                if last.as_ref() == Some(&progress) {
+                    continue;
+                }
+                last = Some(progress.clone());
+                let next = (!progress.is_terminal())
+                    .then_some((client, report, id, backoff, wait, last));
+                return Some((Ok(progress), next));
+            }
+        })
+        .boxed()
+    }
+}
+
+impl Stream for ExportProgressStream {
+    type Item = KrakenResult<ExportProgress>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.inner.is_none() {
+            let inner = self.start();
+            self.inner = Some(inner);
+        }
+        self.inner.as_mut().expect("started").as_mut().poll_next(cx)
+    }
+}
+
+impl std::fmt::Debug for ExportProgressStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExportProgressStream")
+            .field("report", &self.report)
+            .field("id", &self.id)
+            .field("backoff", &self.backoff)
+            .finish()
+    }
+}
+
+/// One NDJSON line: the entry's fields plus its Kraken ID.
+#[derive(Serialize)]
+struct Line<'a, T> {
+    id: &'a str,
+    #[serde(flatten)]
+    entry: &'a T,
+}
+
+impl AuthenticatedClient {
+    /// Follow the export `report_id` (as returned by `ExportTrades`) through
+    /// `ExportStatus`, e.g. to drive a progress bar. `report` is the report
+    /// type it was requested with, "trades" or "ledgers".
+    pub fn export_progress(&self, report: &str, report_id: &str) -> ExportProgressStream {
+        ExportProgressStream {
+            client: self.clone(),
+            report: report.to_string(),
+            id: report_id.to_string(),
+            backoff: WatchBackoff::default(),
+            inner: None,
+        }
+    }
+
+    /// Write every ledger entry matching `filters` to `writer` as
+    /// newline-delimited JSON, newest first, one object per line with the
+    /// ledger ID as `"id"` alongside the `LedgerInfo` fields.
+    ///
+    /// Pages are fetched as the writer keeps up, so only one page is held in
+    /// memory however long the history. Returns the number of entries written;
+    /// on error, the lines already written stay in `writer`.
+    pub async fn export_ledger_ndjson<W>(
+        &self,
+        writer: &mut W,
+        filters: &LedgersParams,
+    ) -> KrakenResult<u64>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let params = filters.to_params();
+        let entries = self.ledgers_stream(&params::as_pairs(&params));
+        write_ndjson(entries, writer).await
+    }
+
+    /// `export_ledger_ndjson` for `TradesHistory`: one `TradeInfo` per line,
+    /// with the trade ID as `"id"`.
+    pub async fn export_trades_ndjson<W>(
+        &self,
+        writer: &mut W,
+        filters: &TradesHistoryParams,
+    ) -> KrakenResult<u64>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let params = filters.to_params();
+        let trades = self.trades_history_stream(&params::as_pairs(&params));
+        write_ndjson(trades, writer).await
+    }
+}
+
+async fn write_ndjson<T, S, W>(entries: S, writer: &mut W) -> KrakenResult<u64>
+where
+    T: Serialize,
+    S: Stream<Item = KrakenResult<(String, T)>>,
+    W: AsyncWrite + Unpin,
+{
+    let mut entries = std::pin::pin!(entries);
+    let mut written = 0;
+    let mut line = Vec::new();
+    while let Some(entry) = entries.next().await {
+        let (id, entry) = entry?;
+        line.clear();
+        serde_json::to_writer(
+            &mut line,
+            &Line {
+                id: &id,
+                entry: &entry,
+            },
+        )?;
+        line.push(b'\n');
+        writer.write_all(&line).await?;
+        written += 1;
+    }
+    writer.flush().await?;
+    Ok(written)
+}
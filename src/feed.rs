@@ -0,0 +1,85 @@
+use futures_util::stream::{BoxStream, Stream};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+#[cfg(feature = "ws")]
+use futures_util::stream;
+#[cfg(feature = "ws")]
+use tokio::sync::broadcast;
+
+/// A `Stream` of one kind of market or account update, so feeds compose with
+/// `StreamExt` combinators (`filter`, `merge`, `buffer_unordered`, ...).
+///
+/// WebSocket feeds (`KrakenWsClient::ticker_stream` etc.) each have their own
+/// broadcast receiver. If one falls more than `MESSAGE_BUFFER` messages behind,
+/// the oldest messages are skipped; use `KrakenWsClient::messages()` directly if
+/// you need to observe the lag. They end when the connection closes.
+///
+/// REST polling feeds (`RestPoller`) yield the same item types and run until
+/// dropped; `MarketDataProvider` returns either kind.
+pub struct FeedStream<T> {
+    inner: BoxStream<'static, T>,
+}
+
+impl<T: Send + 'static> FeedStream<T> {
+    /// The items `select` picks out of a connection's `receiver`, skipping over lag.
+    #[cfg(feature = "ws")]
+    pub(crate) fn new<M: Clone + Send + 'static>(
+        receiver: broadcast::Receiver<M>,
+        select: impl Fn(M) -> Option<T> + Send + Sync + 'static,
+    ) -> Self {
+        let inner = stream::unfold((receiver, select), |(mut receiver, select)| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(msg) => {
+                        if let Some(item) = select(msg) {
+                            return Some((item, (receiver, select)));
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        });
+        Self {
+            inner: Box::pin(inner),
+        }
+    }
+
+    /// The items on a typed `receiver` that `keep` accepts, skipping over lag.
+    #[cfg(feature = "ws")]
+    pub(crate) fn from_receiver(
+        receiver: broadcast::Receiver<T>,
+        keep: impl Fn(&T) -> bool + Send + Sync + 'static,
+    ) -> Self
+    where
+        T: Clone,
+    {
+        let inner = stream::unfold((receiver, keep), |(mut receiver, keep)| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(item) if keep(&item) => return Some((item, (receiver, keep))),
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        });
+        Self {
+            inner: Box::pin(inner),
+        }
+    }
+
+    pub(crate) fn from_stream(inner: impl Stream<Item = T> + Send + 'static) -> Self {
+        Self {
+            inner: Box::pin(inner),
+        }
+    }
+}
+
+impl<T> Stream for FeedStream<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.inner.as_mut().poll_next(cx)
+    }
+}
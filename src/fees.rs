@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+
+use crate::error::{KrakenError, KrakenResult};
+use crate::exchange::Side;
+use crate::models::{AssetPairInfo, TradeVolumeResponse};
+use crate::numeric::Amount;
+use crate::AuthenticatedClient;
+
+/// Maker and taker fees for one pair, in percent (e.g. `0.26`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeeRates {
+    pub maker: f64,
+    pub taker: f64,
+}
+
+/// The fees an account pays per pair, for estimating what an order will cost.
+///
+/// Build it from `TradeVolume` (the account's current tier) with
+/// `from_trade_volume`, fall back to a pair's published base tier with
+/// `from_asset_pair`, or set rates by hand with `with_rates`. Pairs are keyed
+/// as Kraken returns them, e.g. `"XXBTZUSD"`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FeeEstimator {
+    rates: HashMap<String, FeeRates>,
+}
+
+impl FeeEstimator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The account's fees from a `TradeVolume` response (requested with the
+    /// `pair` parameter). A pair without a maker fee pays its taker fee.
+    pub fn from_trade_volume(volume: &TradeVolumeResponse) -> KrakenResult<Self> {
+        let mut estimator = Self::new();
+        for (pair, taker) in &volume.fees {
+            let taker = Amount(&taker.fee).to_f64_lossy()?;
+            let maker = match volume.fees_maker.get(pair) {
+                Some(maker) => Amount(&maker.fee).to_f64_lossy()?,
+                None => taker,
+            };
+            estimator
+                .rates
+                .insert(pair.clone(), FeeRates { maker, taker });
+        }
+        Ok(estimator)
+    }
+
+    /// The lowest-volume tier `info` publishes, i.e. what an account with no
+    /// trading volume pays.
+    pub fn from_asset_pair(pair: &str, info: &AssetPairInfo) -> Self {
+        let base_tier = |tiers: &[Vec<f64>]| tiers.first().and_then(|tier| tier.get(1)).copied();
+        let taker = base_tier(&info.fees).unwrap_or(0.0);
+        let maker = info
+            .fees_maker
+            .as_deref()
+            .and_then(base_tier)
+            .unwrap_or(taker);
+        Self::new().with_rates(pair, FeeRates { maker, taker })
+    }
+
+    pub fn with_rates(mut self, pair: impl Into<String>, rates: FeeRates) -> Self {
+        self.rates.insert(pair.into(), rates);
+        self
+    }
+
+    /// Fees for `pair`, if known.
+    pub fn rates(&self, pair: &str) -> Option<FeeRates> {
+        self.rates.get(pair).copied()
+    }
+
+    /// The fee on `notional` (quote currency) in `pair`, as maker or taker.
+    pub fn fee(&self, pair: &str, notional: f64, maker: bool) -> Option<f64> {
+        let rates = self.rates(pair)?;
+        let percent = if maker { rates.maker } else { rates.taker };
+        Some(notional * percent / 100.0)
+    }
+}
+
+/// An order volume sized to a budget by `size_for_budget`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SizedOrder {
+    /// Volume to submit, at the pair's lot precision
+    pub volume: String,
+    /// Price the volume was sized at
+    pub price: f64,
+    /// Expected cost in quote currency (volume × price)
+    pub cost: f64,
+    /// Expected fee in quote currency
+    pub fee: f64,
+}
+
+/// The largest volume of `pair` whose cost plus fee, at `price` and
+/// `fee_percent`, stays within `quote_budget`, rounded down to the pair's
+/// `lot_decimals`.
+///
+/// Fails with `KrakenError::OrderBelowMinimum` when that volume is under the
+/// pair's `ordermin` or its cost under `costmin`.
+///
+/// ```
+/// use onise::fees::size_for_budget;
+/// # let info: onise::models::AssetPairInfo = serde_json::from_value(serde_json::json!({
+/// #     "altname": "XBTUSD", "wsname": "XBT/USD", "aclass_base": "currency", "base": "XXBT",
+/// #     "aclass_quote": "currency", "quote": "ZUSD", "lot": "unit", "pair_decimals": 1,
+/// #     "lot_decimals": 8, "lot_multiplier": 1, "fees": [[0, 0.26]],
+/// #     "ordermin": "0.0001", "costmin": "0.5"
+/// # })).unwrap();
+///
+/// // $1000 at 30000 with a 0.26% fee
+/// let sized = size_for_budget("XXBTZUSD", &info, 30_000.0, 0.26, 1_000.0).unwrap();
+/// assert_eq!(sized.volume, "0.03324689");
+/// assert!(sized.cost + sized.fee <= 1_000.0);
+/// ```
+pub fn size_for_budget(
+    pair: &str,
+    info: &AssetPairInfo,
+    price: f64,
+    fee_percent: f64,
+    quote_budget: f64,
+) -> KrakenResult<SizedOrder> {
+    if price.is_nan() || price <= 0.0 {
+        return Err(KrakenError::InvalidUsage(format!(
+            "cannot size {pair} at price {price}"
+        )));
+    }
+    let below = |reason: String| KrakenError::OrderBelowMinimum {
+        pair: pair.to_string(),
+        reason,
+    };
+    let fee_rate = fee_percent / 100.0;
+    let scale = 10f64.powi(info.lot_decimals as i32);
+    let total = |units: f64| {
+        let cost = units / scale * price;
+        (cost, cost * fee_rate)
+    };
+
+    // Round down to whole lots, stepping back if float error overshot.
+    let mut units = (quote_budget / (price * (1.0 + fee_rate)) * scale + 1e-6)
+        .floor()
+        .max(0.0);
+    let (mut cost, mut fee) = total(units);
+    while units > 0.0 && cost + fee > quote_budget {
+        units -= 1.0;
+        (cost, fee) = total(units);
+    }
+    let volume = units / scale;
+
+    if let Some(ordermin) = &info.ordermin {
+        let min = Amount(ordermin).to_f64_lossy()?;
+        if volume < min {
+            return Err(below(format!(
+                "{quote_budget} sizes to {volume} at {price}, under the minimum volume {ordermin}"
+            )));
+        }
+    }
+    if let Some(costmin) = &info.costmin {
+        let min = Amount(costmin).to_f64_lossy()?;
+        if cost < min {
+            return Err(below(format!(
+                "cost {cost} is under the minimum cost {costmin}"
+            )));
+        }
+    }
+    if units <= 0.0 {
+        return Err(below(format!(
+            "{quote_budget} doesn't cover one lot at {price}"
+        )));
+    }
+    Ok(SizedOrder {
+        volume: format!("{volume:.prec$}", prec = info.lot_decimals as usize),
+        price,
+        cost,
+        fee,
+    })
+}
+
+impl AuthenticatedClient {
+    /// Size a market order in `pair` to spend at most `quote_budget` including
+    /// the taker fee, using the pair's `AssetPairs` entry, the account's fee
+    /// from `TradeVolume` and the current touch from `Ticker` (the ask to buy,
+    /// the bid to sell). See `fees::size_for_budget`.
+    pub async fn size_order_for_budget(
+        &self,
+        pair: &str,
+        quote_budget: f64,
+        side: Side,
+    ) -> KrakenResult<SizedOrder> {
+        let params = [("pair", pair)];
+        let (pairs, volume, ticker) = tokio::try_join!(
+            self.get_asset_pairs(&params),
+            self.get_trade_volume(&params),
+            self.get_ticker_information(pair),
+        )?;
+        let missing = |what: &str| KrakenError::InvalidUsage(format!("no {what} for {pair}"));
+        let (name, info) = pairs
+            .pairs
+            .iter()
+            .next()
+            .ok_or_else(|| missing("AssetPairs entry"))?;
+        let fees = FeeEstimator::from_trade_volume(&volume)?;
+        let rates = fees
+            .rates(name)
+            .unwrap_or_else(|| FeeEstimator::from_asset_pair(name, info).rates[name]);
+        let ticker = ticker
+            .tickers
+            .get(name)
+            .or_else(|| ticker.tickers.values().next())
+            .ok_or_else(|| missing("Ticker entry"))?;
+        let price = match side {
+            Side::Buy => &ticker.a[0],
+            Side::Sell => &ticker.b[0],
+        };
+        size_for_budget(
+            name,
+            info,
+            Amount(price).to_f64_lossy()?,
+            rates.taker,
+            quote_budget,
+        )
+    }
+}
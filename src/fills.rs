@@ -0,0 +1,183 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use futures_util::stream::{self, Stream, StreamExt};
+
+use crate::error::KrakenResult;
+use crate::exchange::Side;
+use crate::feed::FeedStream;
+use crate::numeric::Amount;
+use crate::ws_models::{ExecutionData, InstrumentData, WsExecutionsMessage};
+
+/// Quantity decimals assumed for pairs `FillTracker` has no precision for
+/// (Kraken's `lot_decimals` for most spot pairs).
+pub const DEFAULT_QUANTITY_DECIMALS: u32 = 8;
+
+/// One execution against an order, with quantities as numbers rounded to the
+/// pair's quantity decimals and the order's running totals.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fill {
+    pub order_id: String,
+    pub exec_id: String,
+    pub symbol: String,
+    pub side: Side,
+    /// Quantity of this fill, in base currency
+    pub qty: f64,
+    /// Quantity of the order filled so far, this fill included
+    pub filled: f64,
+    /// Quantity still open, when the order's size is known (`expect_order`)
+    pub remaining: Option<f64>,
+    /// Volume-weighted average price of the order's fills so far
+    pub avg_price: f64,
+    /// Fee charged on this fill, in `fee_currency`
+    pub fee: f64,
+    pub fee_currency: String,
+    pub time: u64,
+}
+
+impl Fill {
+    /// `true` once nothing of the order remains open.
+    pub fn is_complete(&self) -> bool {
+        self.remaining == Some(0.0)
+    }
+}
+
+/// Turns executions into `Fill`s, keeping each order's filled quantity and
+/// average price so strategies don't redo that math on string fields.
+///
+/// Quantities are rounded to the pair's quantity decimals, set with
+/// `set_quantity_decimals` or `apply_instruments` (the `instruments`
+/// channel), `DEFAULT_QUANTITY_DECIMALS` otherwise. `remaining` needs the
+/// order's size from `expect_order`; a completed order is forgotten.
+/// Executions seen before (the same `exec_id` again, e.g. after a
+/// resubscribe) are ignored. Clones share the same state.
+#[derive(Debug, Clone, Default)]
+pub struct FillTracker {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    decimals: HashMap<String, u32>,
+    orders: HashMap<String, OrderFills>,
+}
+
+#[derive(Debug, Default)]
+struct OrderFills {
+    quantity: Option<f64>,
+    filled: f64,
+    cost: f64,
+    exec_ids: HashSet<String>,
+}
+
+impl FillTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Round `symbol`'s quantities to `decimals` places.
+    pub fn set_quantity_decimals(&self, symbol: &str, decimals: u32) {
+        self.lock().decimals.insert(symbol.to_string(), decimals);
+    }
+
+    /// Take the quantity decimals of every pair in `instruments` that has them.
+    pub fn apply_instruments(&self, instruments: &[InstrumentData]) {
+        let mut state = self.lock();
+        for pair in instruments {
+            if let Some(decimals) = pair.quantity_decimals {
+                state.decimals.insert(pair.symbol.clone(), decimals);
+            }
+        }
+    }
+
+    /// Order `order_id` was placed for `quantity`, so its fills report what remains.
+    pub fn expect_order(&self, order_id: &str, quantity: f64) {
+        self.lock()
+            .orders
+            .entry(order_id.to_string())
+            .or_default()
+            .quantity = Some(quantity);
+    }
+
+    /// Record one WebSocket execution; `None` if it was seen before.
+    pub fn apply_execution(&self, execution: &ExecutionData) -> KrakenResult<Option<Fill>> {
+        let qty = Amount(&execution.quantity).to_f64_lossy()?;
+        let price = Amount(&execution.price).to_f64_lossy()?;
+        let fee = Amount(&execution.fee).to_f64_lossy()?;
+        let side = match execution.side.as_str() {
+            "sell" => Side::Sell,
+            _ => Side::Buy,
+        };
+
+        let mut state = self.lock();
+        let decimals = state
+            .decimals
+            .get(&execution.symbol)
+            .copied()
+            .unwrap_or(DEFAULT_QUANTITY_DECIMALS);
+        let order = state.orders.entry(execution.order_id.clone()).or_default();
+        if !order.exec_ids.insert(execution.exec_id.clone()) {
+            return Ok(None);
+        }
+        let qty = round_to(qty, decimals);
+        order.filled = round_to(order.filled + qty, decimals);
+        order.cost += qty * price;
+        let remaining = order
+            .quantity
+            .map(|quantity| round_to((quantity - order.filled).max(0.0), decimals));
+        let fill = Fill {
+            order_id: execution.order_id.clone(),
+            exec_id: execution.exec_id.clone(),
+            symbol: execution.symbol.clone(),
+            side,
+            qty,
+            filled: order.filled,
+            remaining,
+            avg_price: if order.filled > 0.0 {
+                order.cost / order.filled
+            } else {
+                price
+            },
+            fee,
+            fee_currency: execution.fee_currency.clone(),
+            time: execution.time,
+        };
+        if fill.is_complete() {
+            state.orders.remove(&execution.order_id);
+        }
+        Ok(Some(fill))
+    }
+
+    /// Every new fill in `executions` (e.g. `ws.executions_stream()`), in
+    /// order. Executions with an unreadable number are logged and skipped.
+    pub fn fills<S>(&self, executions: S) -> FeedStream<Fill>
+    where
+        S: Stream<Item = WsExecutionsMessage> + Send + 'static,
+    {
+        let tracker = self.clone();
+        let fills = executions.flat_map(move |message| {
+            let fills: Vec<Fill> = message
+                .executions
+                .iter()
+                .filter_map(|execution| match tracker.apply_execution(execution) {
+                    Ok(fill) => fill,
+                    Err(e) => {
+                        tracing::warn!(exec_id = %execution.exec_id, error = %e, "skipped execution");
+                        None
+                    }
+                })
+                .collect();
+            stream::iter(fills)
+        });
+        FeedStream::from_stream(fills)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn round_to(value: f64, decimals: u32) -> f64 {
+    let scale = 10f64.powi(decimals as i32);
+    (value * scale).round() / scale
+}
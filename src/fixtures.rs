@@ -0,0 +1,242 @@
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::{KrakenError, KrakenResult};
+use crate::ws_models::WsIncomingMessage;
+
+/// A sanitized response body shipped with the crate (see the `fixtures/` directory).
+#[derive(Debug, Clone, Copy)]
+pub struct Fixture {
+    /// File stem, e.g. "open_orders" for `fixtures/rest/open_orders.json`
+    pub name: &'static str,
+    /// The file contents
+    pub body: &'static str,
+}
+
+macro_rules! fixtures {
+    ($dir:literal; $($name:literal,)*) => {
+        &[$(Fixture {
+            name: $name,
+            body: include_str!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/fixtures/",
+                $dir,
+                "/",
+                $name,
+                ".json"
+            )),
+        },)*]
+    };
+}
+
+/// One successful response per REST endpoint, as full `{"error":[],"result":...}` envelopes.
+pub const REST: &[Fixture] = fixtures!(
+    "rest";
+        "server_time",
+        "system_status",
+        "assets",
+        "asset_pairs",
+        "ticker",
+        "ohlc",
+        "depth",
+        "trades",
+        "spread",
+        "balance",
+        "balance_ex",
+        "trade_balance",
+        "open_orders",
+        "closed_orders",
+        "query_orders",
+        "trades_history",
+        "query_trades",
+        "open_positions",
+        "ledgers",
+        "query_ledgers",
+        "trade_volume",
+        "export_trades",
+        "export_status",
+        "retrieve_export",
+        "delete_export",
+        "add_order",
+        "add_order_batch",
+        "amend_order",
+        "edit_order",
+        "cancel_order",
+        "cancel_all",
+        "cancel_all_orders_after",
+        "cancel_order_batch",
+        "websockets_token",
+        "deposit_methods",
+        "deposit_addresses",
+        "deposit_status",
+        "withdrawal_methods",
+        "withdrawal_addresses",
+        "withdrawal_information",
+        "withdraw",
+        "withdraw_status",
+        "withdraw_cancel",
+        "wallet_transfer",
+        "create_subaccount",
+        "account_transfer",
+        "stake",
+        "unstake",
+        "stake_status",
+        "unstake_status",
+        "staking_assets",
+        "staking_transactions",
+        "otc_quote",
+        "otc_update_quote",
+        "otc_quotes",
+);
+
+/// One message per WebSocket channel / event type.
+pub const WS: &[Fixture] = fixtures!(
+    "ws";
+        "system_status",
+        "subscription_status",
+        "pong",
+        "heartbeat",
+        "ticker",
+        "book_snapshot",
+        "book_update",
+        "candles",
+        "trades",
+        "instruments",
+        "balances",
+        "executions",
+        "add_order_status",
+        "cancel_order_status",
+        "batch_add_status",
+);
+
+/// The canonical payload of every client → server request in `ws_models`,
+/// as sent on the wire; check a request against one with `conform`.
+pub const WS_REQUESTS: &[Fixture] = fixtures!(
+    "ws_requests";
+        "ping",
+        "heartbeat",
+        "authorize",
+        "subscribe",
+        "unsubscribe",
+        "add_order",
+        "amend_order",
+        "edit_order",
+        "cancel_order",
+        "cancel_all",
+        "cancel_on_disconnect",
+        "batch_add",
+        "batch_cancel",
+);
+
+/// The REST fixture called `name`.
+pub fn rest(name: &str) -> Option<&'static str> {
+    find(REST, name)
+}
+
+/// The WebSocket fixture called `name`.
+pub fn ws(name: &str) -> Option<&'static str> {
+    find(WS, name)
+}
+
+/// The WebSocket request fixture called `name`.
+pub fn ws_request(name: &str) -> Option<&'static str> {
+    find(WS_REQUESTS, name)
+}
+
+fn find(fixtures: &[Fixture], name: &str) -> Option<&'static str> {
+    fixtures.iter().find(|f| f.name == name).map(|f| f.body)
+}
+
+/// Read `<dir>/<name>.json` from disk, for keeping your own captured responses
+/// next to your tests.
+pub fn load<P: AsRef<Path>>(dir: P, name: &str) -> KrakenResult<String> {
+    let path = dir.as_ref().join(format!("{name}.json"));
+    Ok(std::fs::read_to_string(path)?)
+}
+
+/// Parse a REST response body exactly as `KrakenClient` does: Kraken errors
+/// become `KrakenError`s, otherwise `result` is deserialized into `T`.
+pub fn parse_rest<T: DeserializeOwned>(body: &str) -> KrakenResult<T> {
+    crate::envelope::parse(body.as_bytes())
+}
+
+/// Parse a REST body into `T`, serialize it, and parse that again. Fails if the
+/// model cannot read its own output or the two serializations differ, i.e. if
+/// a field is renamed, retyped or lost on the way through. Returns the
+/// serialized model for further assertions.
+pub fn round_trip<T>(body: &str) -> KrakenResult<Value>
+where
+    T: DeserializeOwned + Serialize,
+{
+    let first = serde_json::to_value(parse_rest::<T>(body)?)?;
+    let second = serde_json::to_value(serde_json::from_value::<T>(first.clone())?)?;
+    if first != second {
+        return Err(KrakenError::InvalidUsage(format!(
+            "round trip changed the model: {first} != {second}"
+        )));
+    }
+    Ok(first)
+}
+
+/// Parse a WebSocket message, failing if it did not match any typed variant
+/// (i.e. it would only have been delivered as `CatchAll`). Uses the same
+/// `ws_models::parse_incoming` path as the WebSocket read loop.
+pub fn parse_ws(body: &str) -> KrakenResult<WsIncomingMessage> {
+    let msg = crate::ws_models::parse_incoming(body)?;
+    match msg {
+        WsIncomingMessage::CatchAll(_) => Err(KrakenError::InvalidUsage(format!(
+            "no typed model matched: {body}"
+        ))),
+        typed => Ok(typed),
+    }
+}
+
+/// Serialize `request` and compare it to the `expected` payload as JSON
+/// values, so key order and whitespace don't matter but every field, name and
+/// type does. The error lists each difference by path, e.g.
+/// `$.orders[0].stopPrice: expected "25000.0", got nothing`.
+///
+/// Use it with `ws_request` for the crate's own models, or with `load` for
+/// payloads captured from Kraken when adding fields or new request types.
+pub fn conform<T: Serialize>(request: &T, expected: &str) -> KrakenResult<()> {
+    let expected: Value = serde_json::from_str(expected)?;
+    let actual = serde_json::to_value(request)?;
+    let mut differences = Vec::new();
+    diff("$", &expected, &actual, &mut differences);
+    if differences.is_empty() {
+        return Ok(());
+    }
+    Err(KrakenError::InvalidUsage(format!(
+        "request does not conform: {}",
+        differences.join("; ")
+    )))
+}
+
+fn diff(path: &str, expected: &Value, actual: &Value, out: &mut Vec<String>) {
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => {
+            for (key, value) in expected {
+                let path = format!("{path}.{key}");
+                match actual.get(key) {
+                    Some(actual) => diff(&path, value, actual, out),
+                    None => out.push(format!("{path}: expected {value}, got nothing")),
+                }
+            }
+            for (key, value) in actual {
+                if !expected.contains_key(key) {
+                    out.push(format!("{path}.{key}: unexpected {value}"));
+                }
+            }
+        }
+        (Value::Array(expected), Value::Array(actual)) if expected.len() == actual.len() => {
+            for (i, (expected, actual)) in expected.iter().zip(actual).enumerate() {
+                diff(&format!("{path}[{i}]"), expected, actual, out);
+            }
+        }
+        _ if expected != actual => out.push(format!("{path}: expected {expected}, got {actual}")),
+        _ => {}
+    }
+}
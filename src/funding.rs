@@ -0,0 +1,211 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures_util::future::{BoxFuture, FutureExt};
+use futures_util::stream::{self, BoxStream, Stream, StreamExt};
+
+use crate::error::{KrakenError, KrakenResult};
+use crate::models::{DepositStatusItem, WithdrawalStatusItem};
+use crate::AuthenticatedClient;
+
+/// How often a `TransferWatch` polls: every `initial` at first, growing by
+/// `factor` after each poll up to `max`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WatchBackoff {
+    pub initial: Duration,
+    pub max: Duration,
+    pub factor: u32,
+}
+
+impl Default for WatchBackoff {
+    /// 10s, doubling up to 5 minutes.
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_secs(10),
+            max: Duration::from_secs(300),
+            factor: 2,
+        }
+    }
+}
+
+/// A deposit or withdrawal status entry.
+pub trait TransferStatus {
+    /// Kraken's `status`: "Initial", "Pending", "Settled", "Success" or "Failure".
+    fn status(&self) -> &str;
+
+    /// Kraken's `status-prop`, if any.
+    fn status_prop(&self) -> Option<&str>;
+
+    /// `true` once the transfer succeeded, failed or was canceled.
+    fn is_terminal(&self) -> bool {
+        let status = self.status();
+        status.eq_ignore_ascii_case("success")
+            || status.eq_ignore_ascii_case("failure")
+            || self.status_prop() == Some("canceled")
+    }
+}
+
+impl TransferStatus for DepositStatusItem {
+    fn status(&self) -> &str {
+        &self.status
+    }
+
+    fn status_prop(&self) -> Option<&str> {
+        self.status_prop.as_deref()
+    }
+}
+
+impl TransferStatus for WithdrawalStatusItem {
+    fn status(&self) -> &str {
+        &self.status
+    }
+
+    fn status_prop(&self) -> Option<&str> {
+        self.status_prop.as_deref()
+    }
+}
+
+type Fetch<T> = Box<dyn FnMut() -> BoxFuture<'static, KrakenResult<Option<T>>> + Send>;
+
+/// Follows one deposit or withdrawal until it reaches a terminal state.
+///
+/// As a `Stream` it yields the entry each time its status (or `status-prop`)
+/// changes, ending after the terminal one; `settled` skips to that. Polls back
+/// off per `WatchBackoff`. A transfer Kraken doesn't list yet, and transient
+/// failures (rate limits, service errors, timeouts, HTTP errors), are retried;
+/// any other error is yielded and ends the watch.
+pub struct TransferWatch<T> {
+    backoff: WatchBackoff,
+    fetch: Option<Fetch<T>>,
+    inner: Option<BoxStream<'static, KrakenResult<T>>>,
+}
+
+impl<T: TransferStatus + Send + 'static> TransferWatch<T> {
+    fn new<F, Fut>(mut fetch: F) -> Self
+    where
+        F: FnMut() -> Fut + Send + 'static,
+        Fut: std::future::Future<Output = KrakenResult<Option<T>>> + Send + 'static,
+    {
+        Self {
+            backoff: WatchBackoff::default(),
+            fetch: Some(Box::new(move || fetch().boxed())),
+            inner: None,
+        }
+    }
+
+    /// Poll on this schedule instead of `WatchBackoff::default()`.
+    pub fn with_backoff(mut self, backoff: WatchBackoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Wait for the terminal entry, ignoring intermediate ones.
+    pub async fn settled(mut self) -> KrakenResult<T> {
+        let mut last = None;
+        while let Some(entry) = self.next().await {
+            last = Some(entry?);
+        }
+        last.ok_or_else(|| KrakenError::InvalidUsage("transfer watch ended early".to_string()))
+    }
+
+    fn start(&mut self) -> BoxStream<'static, KrakenResult<T>> {
+        let fetch = self.fetch.take().expect("started once");
+        let state = (
+            fetch,
+            self.backoff,
+            None::<Duration>,
+            None::<(String, Option<String>)>,
+        );
+        stream::unfold(Some(state), |state| async move {
+            let (mut fetch, backoff, mut wait, mut last) = state?;
+            loop {
+                if let Some(pause) = wait {
+                    tokio::time::sleep(pause).await;
+                }
+                wait = Some(match wait {
+                    None => backoff.initial,
+                    Some(pause) => (pause * backoff.factor).min(backoff.max),
+                });
+                let entry = match fetch().await {
+                    Ok(Some(entry)) => entry,
+                    Ok(None) => continue,
+                    Err(e) if is_transient(&e) => {
+                        tracing::warn!(error = %e, "transfer status poll failed; retrying");
+                        continue;
+                    }
+                    Err(e) => return Some((Err(e), None)),
+                };
+                let seen = (
+                    entry.status().to_string(),
+                    entry.status_prop().map(str::to_string),
+                );
+                if last.as_ref() == Some(&seen) {
+                    continue;
+                }
+                last = Some(seen);
+                let next = (!entry.is_terminal()).then_some((fetch, backoff, wait, last));
+                return Some((Ok(entry), next));
+            }
+        })
+        .boxed()
+    }
+}
+
+impl<T: TransferStatus + Send + 'static> Stream for TransferWatch<T> {
+    type Item = KrakenResult<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.inner.is_none() {
+            let inner = self.start();
+            self.inner = Some(inner);
+        }
+        self.inner.as_mut().expect("started").as_mut().poll_next(cx)
+    }
+}
+
+/// Failures a poller should retry rather than give up on.
+pub(crate) fn is_transient(e: &KrakenError) -> bool {
+    match e {
+        KrakenError::Request { source, .. } => is_transient(source),
+        KrakenError::Reqwest(_)
+        | KrakenError::RateLimitExceeded { .. }
+        | KrakenError::ServiceError { .. }
+        | KrakenError::Timeout { .. } => true,
+        _ => false,
+    }
+}
+
+impl AuthenticatedClient {
+    /// Watch the deposit of `asset` with blockchain (or reference) `txid`
+    /// through `DepositStatus`.
+    pub fn watch_deposit(&self, asset: &str, txid: &str) -> TransferWatch<DepositStatusItem> {
+        let (client, asset, txid) = (self.clone(), asset.to_string(), txid.to_string());
+        TransferWatch::new(move || {
+            let (client, asset, txid) = (client.clone(), asset.clone(), txid.clone());
+            async move {
+                let deposits = client.get_deposit_status(&[("asset", &asset)]).await?;
+                Ok(deposits
+                    .0
+                    .into_iter()
+                    .find(|deposit| deposit.txid.as_deref() == Some(txid.as_str())))
+            }
+        })
+    }
+
+    /// Watch the withdrawal with reference ID `refid` (as returned by
+    /// `Withdraw`) through `WithdrawStatus`.
+    pub fn watch_withdrawal(&self, refid: &str) -> TransferWatch<WithdrawalStatusItem> {
+        let (client, refid) = (self.clone(), refid.to_string());
+        TransferWatch::new(move || {
+            let (client, refid) = (client.clone(), refid.clone());
+            async move {
+                let withdrawals = client.get_withdraw_status(&[]).await?;
+                Ok(withdrawals
+                    .0
+                    .into_iter()
+                    .find(|withdrawal| withdrawal.refid.as_deref() == Some(refid.as_str())))
+            }
+        })
+    }
+}
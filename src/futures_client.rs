@@ -0,0 +1,211 @@
+use reqwest::header::USER_AGENT;
+use reqwest::{Client as HttpClient, RequestBuilder};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::environment::{self, PRODUCTION_FUTURES_REST_URL};
+use crate::error::{KrakenError, KrakenResult};
+use crate::futures_models::*;
+use crate::rest_client::{Authenticated, DEFAULT_USER_AGENT, REQUEST_ID_HEADER};
+use crate::signing;
+
+/// Prefix of every Futures REST path; signatures cover the path after it.
+const DERIVATIVES_PREFIX: &str = "/derivatives";
+
+/// A client for the Kraken Futures REST API v3 (`futures.kraken.com`), which
+/// has its own hosts, keys, response envelope and signing scheme
+/// (`signing::sign_futures`) apart from Spot's.
+///
+/// Market data (`instruments`, `tickers`, `order_book`, `trade_history`)
+/// needs no credentials; private endpoints (`open_positions`) need a Futures
+/// API key from `with_credentials`, or fail with `InvalidUsage`.
+#[derive(Clone, Debug)]
+pub struct KrakenFuturesClient {
+    base_url: String,
+    http: HttpClient,
+    user_agent: String,
+    credentials: Option<Authenticated>,
+    nonces: signing::NonceSource,
+}
+
+impl KrakenFuturesClient {
+    /// Create a client for public endpoints.
+    /// - `base_url` overrides the host (e.g. `environment::DEMO_FUTURES_REST_URL`
+    ///   or a mock server); `None` means production.
+    pub fn new(base_url: Option<String>) -> Self {
+        Self {
+            base_url: base_url.unwrap_or_else(|| PRODUCTION_FUTURES_REST_URL.to_string()),
+            http: HttpClient::new(),
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            credentials: None,
+            nonces: signing::NonceSource::new(),
+        }
+    }
+
+    /// Sign private requests with a Futures API key (Spot keys don't work here).
+    /// Fails with `InvalidUsage` if the secret isn't valid base64.
+    pub fn with_credentials(
+        mut self,
+        api_key: impl Into<String>,
+        api_secret: impl Into<String>,
+    ) -> KrakenResult<Self> {
+        self.credentials = Some(Authenticated::new(api_key, api_secret)?);
+        Ok(self)
+    }
+
+    /// Send `user_agent` instead of `DEFAULT_USER_AGENT`.
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Host the client sends requests to, without a trailing slash.
+    pub fn base_url(&self) -> &str {
+        self.base_url.trim_end_matches('/')
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // PUBLIC ENDPOINTS
+    // ─────────────────────────────────────────────────────────────
+
+    /// Every contract and index, with tick size, contract size and margin levels.
+    pub async fn instruments(&self) -> KrakenResult<FuturesInstrumentsResponse> {
+        self.public_get("/api/v3/instruments", &[]).await
+    }
+
+    /// The market summary of every contract and index.
+    pub async fn tickers(&self) -> KrakenResult<FuturesTickersResponse> {
+        self.public_get("/api/v3/tickers", &[]).await
+    }
+
+    /// The full order book of `symbol` (e.g. "PF_XBTUSD").
+    pub async fn order_book(&self, symbol: &str) -> KrakenResult<FuturesOrderBookResponse> {
+        self.public_get("/api/v3/orderbook", &[("symbol", symbol)])
+            .await
+    }
+
+    /// The latest public trades on `symbol`, newest first; with `last_time`
+    /// (ISO 8601), the ones before it.
+    pub async fn trade_history(
+        &self,
+        symbol: &str,
+        last_time: Option<&str>,
+    ) -> KrakenResult<FuturesHistoryResponse> {
+        let mut params = vec![("symbol", symbol)];
+        if let Some(last_time) = last_time {
+            params.push(("lastTime", last_time));
+        }
+        self.public_get("/api/v3/history", &params).await
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // PRIVATE ENDPOINTS
+    // ─────────────────────────────────────────────────────────────
+
+    /// The account's open positions.
+    pub async fn open_positions(&self) -> KrakenResult<FuturesOpenPositionsResponse> {
+        self.private_get("/api/v3/openpositions", &[]).await
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // HELPER METHODS
+    // ─────────────────────────────────────────────────────────────
+
+    async fn public_get<T>(&self, endpoint: &str, params: &[(&str, &str)]) -> KrakenResult<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let request = self.http.get(self.url(endpoint)).query(params);
+        self.execute(request).await
+    }
+
+    /// A signed GET: the query string is what `signing::sign_futures` covers.
+    async fn private_get<T>(&self, endpoint: &str, params: &[(&str, &str)]) -> KrakenResult<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let credentials = self.credentials.as_ref().ok_or_else(|| {
+            KrakenError::InvalidUsage(format!("{endpoint} needs Futures API credentials"))
+        })?;
+        let query = signing::encode_post_data(params);
+        let nonce = self.nonces.next().to_string();
+        let authent = credentials.signer().sign_futures(endpoint, &nonce, &query);
+
+        let mut url = self.url(endpoint);
+        if !query.is_empty() {
+            url = format!("{url}?{query}");
+        }
+        let request = self
+            .http
+            .get(url)
+            .header("APIKey", credentials.api_key())
+            .header("Nonce", nonce)
+            .header("Authent", authent);
+        self.execute(request).await
+    }
+
+    fn url(&self, endpoint: &str) -> String {
+        format!("{}{DERIVATIVES_PREFIX}{endpoint}", self.base_url())
+    }
+
+    /// Send `request` and unwrap the Futures envelope.
+    async fn execute<T>(&self, request: RequestBuilder) -> KrakenResult<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        environment::check_sandbox(self.base_url())?;
+        let request_id = Uuid::new_v4().to_string();
+        let response = request
+            .header(USER_AGENT, self.user_agent.as_str())
+            .header(REQUEST_ID_HEADER, request_id.as_str())
+            .send()
+            .await
+            .map_err(|e| KrakenError::from(e).with_request_id(&request_id))?;
+        let status = response.status();
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| KrakenError::from(e).with_request_id(&request_id))?;
+        parse_envelope(status, &body).map_err(|e| e.with_request_id(&request_id))
+    }
+}
+
+/// Check `result` and parse the data next to it. Kraken Futures reports
+/// failures as `{"result": "error", "error": "<code>"}`, often with a 4xx.
+fn parse_envelope<T>(status: reqwest::StatusCode, body: &[u8]) -> KrakenResult<T>
+where
+    T: serde::de::DeserializeOwned,
+{
+    let value: Value = match serde_json::from_slice(body) {
+        Ok(value) => value,
+        Err(_) if !status.is_success() => {
+            return Err(KrakenError::ServiceError {
+                message: format!("HTTP {status}"),
+            })
+        }
+        Err(e) => return Err(e.into()),
+    };
+    if value.get("result").and_then(Value::as_str) == Some("error") {
+        let code = value
+            .get("error")
+            .and_then(Value::as_str)
+            .unwrap_or("unknown error");
+        return Err(futures_error(code));
+    }
+    Ok(serde_json::from_value(value)?)
+}
+
+/// Map a Futures error code onto the closest `KrakenError`.
+fn futures_error(code: &str) -> KrakenError {
+    let message = code.to_string();
+    match code {
+        "apiLimitExceeded" => KrakenError::RateLimitExceeded { message },
+        "authenticationError"
+        | "nonceBelowThreshold"
+        | "nonceDuplicate"
+        | "invalidArgument"
+        | "requiredArgumentMissing" => KrakenError::ApiError { message },
+        "Unavailable" | "marketUnavailable" => KrakenError::ServiceError { message },
+        _ => KrakenError::GeneralError { message },
+    }
+}
@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+
+//
+// ──────────────────────────────────────────────────────────────────────────────
+//   Kraken Futures REST API v3 (`futures_client::KrakenFuturesClient`)
+//
+//   Responses carry `"result": "success"` (or `"error"` with an `error`
+//   string) next to their data; the client checks that and parses the rest
+//   into these types. Futures sends numbers as JSON numbers, not strings.
+// ──────────────────────────────────────────────────────────────────────────────
+//
+
+/// /derivatives/api/v3/instruments
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FuturesInstrumentsResponse {
+    pub instruments: Vec<FuturesInstrument>,
+    pub server_time: Option<String>,
+}
+
+/// One contract (or index) listed by `instruments`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FuturesInstrument {
+    /// e.g. "PF_XBTUSD" (perpetual) or "FI_XBTUSD_240927" (fixed maturity)
+    pub symbol: String,
+    /// e.g. "flexible_futures", "futures_inverse", "spot index"
+    #[serde(rename = "type")]
+    pub instrument_type: String,
+    pub underlying: Option<String>,
+    #[serde(default)]
+    pub tradeable: bool,
+    pub tick_size: Option<f64>,
+    pub contract_size: Option<f64>,
+    /// Decimal places allowed in an order's size; negative means multiples of 10
+    pub contract_value_trade_precision: Option<i32>,
+    pub max_position_size: Option<f64>,
+    pub opening_date: Option<String>,
+    /// Only on fixed-maturity contracts
+    pub last_trading_time: Option<String>,
+    pub post_only: Option<bool>,
+    #[serde(default)]
+    pub margin_levels: Vec<FuturesMarginLevel>,
+}
+
+/// Margin required from a position size up.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FuturesMarginLevel {
+    /// Position size (contracts) from which this level applies; Kraken sends
+    /// `numNonContractUnits` instead on multi-collateral contracts
+    #[serde(alias = "numNonContractUnits")]
+    pub contracts: Option<f64>,
+    pub initial_margin: f64,
+    pub maintenance_margin: f64,
+}
+
+/// /derivatives/api/v3/tickers
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FuturesTickersResponse {
+    pub tickers: Vec<FuturesTicker>,
+    pub server_time: Option<String>,
+}
+
+/// The market summary of one contract (or index, which only has `last`).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FuturesTicker {
+    pub symbol: String,
+    /// e.g. "perpetual", "month"
+    pub tag: Option<String>,
+    /// e.g. "XBT:USD"
+    pub pair: Option<String>,
+    pub last: Option<f64>,
+    pub last_time: Option<String>,
+    pub last_size: Option<f64>,
+    pub mark_price: Option<f64>,
+    pub index_price: Option<f64>,
+    pub bid: Option<f64>,
+    pub bid_size: Option<f64>,
+    pub ask: Option<f64>,
+    pub ask_size: Option<f64>,
+    pub vol24h: Option<f64>,
+    pub volume_quote: Option<f64>,
+    pub open_interest: Option<f64>,
+    pub open24h: Option<f64>,
+    pub high24h: Option<f64>,
+    pub low24h: Option<f64>,
+    /// Perpetuals only: the current and predicted hourly funding rates
+    pub funding_rate: Option<f64>,
+    pub funding_rate_prediction: Option<f64>,
+    #[serde(default)]
+    pub suspended: bool,
+    pub post_only: Option<bool>,
+}
+
+/// /derivatives/api/v3/orderbook
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FuturesOrderBookResponse {
+    pub order_book: FuturesOrderBook,
+    pub server_time: Option<String>,
+}
+
+/// Full depth of one contract: `(price, size)` levels, best first.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FuturesOrderBook {
+    pub bids: Vec<(f64, f64)>,
+    pub asks: Vec<(f64, f64)>,
+}
+
+/// /derivatives/api/v3/history
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FuturesHistoryResponse {
+    pub history: Vec<FuturesTrade>,
+    pub server_time: Option<String>,
+}
+
+/// One public trade on a contract, newest first in `history`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FuturesTrade {
+    pub time: String,
+    pub trade_id: Option<u64>,
+    pub price: f64,
+    pub size: f64,
+    /// "buy" or "sell", from the taker's side
+    pub side: String,
+    /// e.g. "fill", "liquidation", "termination"
+    #[serde(rename = "type")]
+    pub trade_type: Option<String>,
+    pub uid: Option<String>,
+}
+
+/// /derivatives/api/v3/openpositions
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FuturesOpenPositionsResponse {
+    pub open_positions: Vec<FuturesPosition>,
+    pub server_time: Option<String>,
+}
+
+/// An open position on one contract.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FuturesPosition {
+    pub symbol: String,
+    /// "long" or "short"
+    pub side: String,
+    /// Average entry price
+    pub price: f64,
+    pub size: f64,
+    pub fill_time: Option<String>,
+    pub unrealized_funding: Option<f64>,
+}
@@ -0,0 +1,429 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use futures_util::stream::{SplitSink, SplitStream};
+use futures_util::{SinkExt, StreamExt};
+use tokio::net::TcpStream;
+use tokio::sync::{broadcast, Mutex};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::{connect_async, tungstenite::protocol::Message, MaybeTlsStream};
+
+use crate::environment::{self, PRODUCTION_FUTURES_WS_URL};
+use crate::error::{KrakenError, KrakenResult};
+use crate::feed::FeedStream;
+use crate::futures_ws_models::{
+    self, FuturesWsBook, FuturesWsChallengeRequest, FuturesWsEvent, FuturesWsFeed, FuturesWsFills,
+    FuturesWsMessage, FuturesWsOpenOrders, FuturesWsSubscribeRequest, FuturesWsTicker,
+};
+use crate::signing::Signer;
+use crate::ws_client::MESSAGE_BUFFER;
+
+type FuturesWsStream = tokio_tungstenite::WebSocketStream<MaybeTlsStream<TcpStream>>;
+type FuturesWsSink = Mutex<SplitSink<FuturesWsStream, Message>>;
+
+/// How long `subscribe_private` waits for the server's challenge by default.
+pub const CHALLENGE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often the client sends `{"event":"ping"}` by default. Kraken Futures
+/// drops connections that send nothing for about a minute.
+pub const PING_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A Futures API key, with its secret keyed for signing challenges.
+struct Credentials {
+    api_key: String,
+    signer: Signer,
+}
+
+/// A challenge from the server and its signature; valid for the whole
+/// connection, so it's asked for once.
+#[derive(Clone)]
+struct SignedChallenge {
+    original: String,
+    signed: String,
+}
+
+/// `KrakenFuturesWsClient` manages a connection to the Kraken Futures
+/// WebSocket API v1, the way `KrakenWsClient` does for Spot:
+/// - A task reads the connection and broadcasts every parsed
+///   `FuturesWsMessage` to the receivers from `messages()`.
+/// - Public feeds (`ticker`, `book`) are subscribed by product ID.
+/// - Private feeds (`fills`, `open_orders`) authenticate with Futures'
+///   challenge flow: the client sends its API key, signs the challenge that
+///   comes back (`signing::sign_challenge`) and sends both with the
+///   subscription. The challenge is asked for once per connection.
+/// - `subscribe_*` methods return typed `FeedStream`s, filtered to their
+///   feed (and products).
+/// - A task sends `{"event":"ping"}` every `PING_INTERVAL` (see
+///   `with_ping_interval`) so quiet connections aren't dropped by the server.
+pub struct KrakenFuturesWsClient {
+    /// The write half (sink) in a Mutex for concurrency, shared weakly with
+    /// the ping task
+    write_half: Arc<FuturesWsSink>,
+
+    ping_task: JoinHandle<()>,
+
+    /// Fan-out of parsed inbound messages; the read task owns the only
+    /// strong sender, so receivers close with the connection.
+    events: broadcast::WeakSender<FuturesWsMessage>,
+
+    credentials: Option<Credentials>,
+
+    challenge: Mutex<Option<SignedChallenge>>,
+
+    challenge_timeout: Duration,
+
+    connected: Arc<AtomicBool>,
+}
+
+impl KrakenFuturesWsClient {
+    /// Connect to `url` (e.g. `environment::DEMO_FUTURES_WS_URL`) and start
+    /// reading.
+    pub async fn connect(url: &str) -> KrakenResult<Self> {
+        environment::check_sandbox(url)?;
+        let (ws_stream, _response) = connect_async(url)
+            .await
+            .map_err(|err| KrakenError::InvalidUsage(format!("WebSocket connect error: {err}")))?;
+        let (write_half, read_half) = ws_stream.split();
+
+        let (loop_events, _) = broadcast::channel(MESSAGE_BUFFER);
+        let events = loop_events.downgrade();
+        let connected = Arc::new(AtomicBool::new(true));
+        tokio::spawn(Self::read_loop(read_half, loop_events, connected.clone()));
+        let write_half = Arc::new(Mutex::new(write_half));
+        let ping_task = tokio::spawn(Self::ping_loop(Arc::downgrade(&write_half), PING_INTERVAL));
+
+        Ok(Self {
+            write_half,
+            ping_task,
+            events,
+            credentials: None,
+            challenge: Mutex::new(None),
+            challenge_timeout: CHALLENGE_TIMEOUT,
+            connected,
+        })
+    }
+
+    /// Connect to Kraken's production Futures WebSocket.
+    pub async fn connect_production() -> KrakenResult<Self> {
+        Self::connect(PRODUCTION_FUTURES_WS_URL).await
+    }
+
+    /// Authenticate private feeds with a Futures API key (Spot keys don't
+    /// work here). Fails with `InvalidUsage` if the secret isn't valid base64.
+    pub fn with_credentials(
+        mut self,
+        api_key: impl Into<String>,
+        api_secret: impl Into<String>,
+    ) -> KrakenResult<Self> {
+        self.credentials = Some(Credentials {
+            api_key: api_key.into(),
+            signer: Signer::new(&api_secret.into())?,
+        });
+        Ok(self)
+    }
+
+    /// Send `{"event":"ping"}` every `interval` instead of every `PING_INTERVAL`.
+    pub fn with_ping_interval(mut self, interval: Duration) -> Self {
+        self.ping_task.abort();
+        self.ping_task = tokio::spawn(Self::ping_loop(Arc::downgrade(&self.write_half), interval));
+        self
+    }
+
+    /// Wait up to `timeout` for the challenge (default `CHALLENGE_TIMEOUT`).
+    pub fn with_challenge_timeout(mut self, timeout: Duration) -> Self {
+        self.challenge_timeout = timeout;
+        self
+    }
+
+    /// `true` until the read task sees the connection end.
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
+    /// A receiver of every parsed inbound message from now on.
+    pub fn messages(&self) -> broadcast::Receiver<FuturesWsMessage> {
+        match self.events.upgrade() {
+            Some(events) => events.subscribe(),
+            // Already disconnected: a receiver that is closed from the start.
+            None => broadcast::channel(1).1,
+        }
+    }
+
+    /// Every parsed inbound message as a `Stream`. See `FeedStream` for lag handling.
+    pub fn message_stream(&self) -> FeedStream<FuturesWsMessage> {
+        FeedStream::new(self.messages(), Some)
+    }
+
+    /// Close the connection.
+    pub async fn close(&self) -> KrakenResult<()> {
+        let mut sink = self.write_half.lock().await;
+        sink.close()
+            .await
+            .map_err(|err| KrakenError::InvalidUsage(format!("WebSocket close error: {err}")))
+    }
+
+    // ─────────────────────────────────────────────────────────────────────
+    // SUBSCRIPTIONS
+    // ─────────────────────────────────────────────────────────────────────
+
+    /// Subscribe to the public `feed` (e.g. "ticker", "book", "trade") for
+    /// `product_ids`.
+    pub async fn subscribe(&self, feed: &str, product_ids: &[&str]) -> KrakenResult<()> {
+        self.send_json(&public_request("subscribe", feed, product_ids))
+            .await
+    }
+
+    /// Unsubscribe from the public `feed` for `product_ids`.
+    pub async fn unsubscribe(&self, feed: &str, product_ids: &[&str]) -> KrakenResult<()> {
+        self.send_json(&public_request("unsubscribe", feed, product_ids))
+            .await
+    }
+
+    /// Subscribe to the private `feed` (e.g. "fills", "open_orders",
+    /// "balances"), answering the connection's challenge first if it hasn't
+    /// been yet. Fails with `InvalidUsage` without credentials, `Timeout` if
+    /// no challenge comes, or the server's error.
+    pub async fn subscribe_private(&self, feed: &str) -> KrakenResult<()> {
+        let request = self.private_request("subscribe", feed).await?;
+        self.send_json(&request).await
+    }
+
+    /// Unsubscribe from the private `feed`.
+    pub async fn unsubscribe_private(&self, feed: &str) -> KrakenResult<()> {
+        let request = self.private_request("unsubscribe", feed).await?;
+        self.send_json(&request).await
+    }
+
+    /// Subscribe to `ticker` for `product_ids` and stream their tickers.
+    pub async fn subscribe_ticker(
+        &self,
+        product_ids: &[&str],
+    ) -> KrakenResult<FeedStream<FuturesWsTicker>> {
+        let products = owned(product_ids);
+        let stream = FeedStream::new(self.messages(), move |msg| match msg {
+            FuturesWsMessage::Feed(FuturesWsFeed::Ticker(ticker))
+                if listed(&products, &ticker.product_id) =>
+            {
+                Some(ticker)
+            }
+            _ => None,
+        });
+        self.subscribe("ticker", product_ids).await?;
+        Ok(stream)
+    }
+
+    /// Subscribe to `book` for `product_ids` and stream each one's snapshot,
+    /// then its updates.
+    pub async fn subscribe_book(
+        &self,
+        product_ids: &[&str],
+    ) -> KrakenResult<FeedStream<FuturesWsBook>> {
+        let products = owned(product_ids);
+        let stream = FeedStream::new(self.messages(), move |msg| {
+            let book = match msg {
+                FuturesWsMessage::Feed(FuturesWsFeed::BookSnapshot(snapshot)) => {
+                    FuturesWsBook::Snapshot(snapshot)
+                }
+                FuturesWsMessage::Feed(FuturesWsFeed::Book(update)) => {
+                    FuturesWsBook::Update(update)
+                }
+                _ => return None,
+            };
+            listed(&products, book.product_id()).then_some(book)
+        });
+        self.subscribe("book", product_ids).await?;
+        Ok(stream)
+    }
+
+    /// Subscribe to the account's `fills` and stream them: recent fills
+    /// once, then each new one.
+    pub async fn subscribe_fills(&self) -> KrakenResult<FeedStream<FuturesWsFills>> {
+        let stream = FeedStream::new(self.messages(), |msg| match msg {
+            FuturesWsMessage::Feed(FuturesWsFeed::FillsSnapshot(fills)) => {
+                Some(FuturesWsFills::Snapshot(fills))
+            }
+            FuturesWsMessage::Feed(FuturesWsFeed::Fills(fills)) => {
+                Some(FuturesWsFills::Update(fills))
+            }
+            _ => None,
+        });
+        self.subscribe_private("fills").await?;
+        Ok(stream)
+    }
+
+    /// Subscribe to the account's `open_orders` and stream them: every open
+    /// order once, then each change.
+    pub async fn subscribe_open_orders(&self) -> KrakenResult<FeedStream<FuturesWsOpenOrders>> {
+        let stream = FeedStream::new(self.messages(), |msg| match msg {
+            FuturesWsMessage::Feed(FuturesWsFeed::OpenOrdersSnapshot(orders)) => {
+                Some(FuturesWsOpenOrders::Snapshot(orders))
+            }
+            FuturesWsMessage::Feed(FuturesWsFeed::OpenOrders(update)) => {
+                Some(FuturesWsOpenOrders::Update(update))
+            }
+            _ => None,
+        });
+        self.subscribe_private("open_orders").await?;
+        Ok(stream)
+    }
+
+    // ─────────────────────────────────────────────────────────────────────
+    // HELPERS
+    // ─────────────────────────────────────────────────────────────────────
+
+    /// A private subscribe/unsubscribe request carrying the signed challenge.
+    async fn private_request(
+        &self,
+        event: &str,
+        feed: &str,
+    ) -> KrakenResult<FuturesWsSubscribeRequest> {
+        let credentials = self.credentials.as_ref().ok_or_else(|| {
+            KrakenError::InvalidUsage(format!("the {feed} feed needs Futures API credentials"))
+        })?;
+        let challenge = self.signed_challenge(credentials).await?;
+        Ok(FuturesWsSubscribeRequest {
+            event: event.to_string(),
+            feed: feed.to_string(),
+            product_ids: None,
+            api_key: Some(credentials.api_key.clone()),
+            original_challenge: Some(challenge.original),
+            signed_challenge: Some(challenge.signed),
+        })
+    }
+
+    /// The connection's signed challenge, asking the server for one the
+    /// first time.
+    async fn signed_challenge(&self, credentials: &Credentials) -> KrakenResult<SignedChallenge> {
+        // Held across the exchange so concurrent subscriptions ask only once.
+        let mut cached = self.challenge.lock().await;
+        if let Some(challenge) = cached.as_ref() {
+            return Ok(challenge.clone());
+        }
+        // Listen before asking so a fast reply can't slip past.
+        let mut replies = self.messages();
+        let exchange = async {
+            self.send_json(&FuturesWsChallengeRequest {
+                event: "challenge".to_string(),
+                api_key: credentials.api_key.clone(),
+            })
+            .await?;
+            loop {
+                match replies.recv().await {
+                    Ok(FuturesWsMessage::Event(FuturesWsEvent::Challenge { message })) => {
+                        return Ok(message)
+                    }
+                    Ok(FuturesWsMessage::Event(FuturesWsEvent::Error { message })) => {
+                        return Err(KrakenError::ApiError { message })
+                    }
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => {
+                        return Err(KrakenError::InvalidUsage(
+                            "connection closed before the challenge arrived".into(),
+                        ))
+                    }
+                }
+            }
+        };
+        let after = self.challenge_timeout;
+        let original = tokio::time::timeout(after, exchange)
+            .await
+            .unwrap_or_else(|_| {
+                Err(KrakenError::Timeout {
+                    operation: "challenge".to_string(),
+                    after,
+                })
+            })?;
+        let challenge = SignedChallenge {
+            signed: credentials.signer.sign_challenge(&original),
+            original,
+        };
+        *cached = Some(challenge.clone());
+        Ok(challenge)
+    }
+
+    async fn send_json<T: serde::Serialize>(&self, request: &T) -> KrakenResult<()> {
+        let json_text = serde_json::to_string(request)
+            .map_err(|err| KrakenError::InvalidUsage(format!("Serialize error: {err}")))?;
+        let mut sink = self.write_half.lock().await;
+        sink.send(Message::Text(json_text))
+            .await
+            .map_err(|err| KrakenError::InvalidUsage(format!("WebSocket send error: {err}")))
+    }
+
+    /// Parse every text frame and broadcast it, until the connection ends.
+    async fn read_loop(
+        mut read_half: SplitStream<FuturesWsStream>,
+        events: broadcast::Sender<FuturesWsMessage>,
+        connected: Arc<AtomicBool>,
+    ) {
+        while let Some(msg_result) = read_half.next().await {
+            match msg_result {
+                Ok(Message::Text(text)) => match futures_ws_models::parse_message(&text) {
+                    Ok(message) => {
+                        let _ = events.send(message);
+                    }
+                    Err(e) => {
+                        let len = text.len();
+                        tracing::warn!(error = %e, len, "Failed to parse Futures WebSocket message");
+                        tracing::debug!(text, "Unparsed Futures WebSocket message");
+                    }
+                },
+                Ok(Message::Close(close_frame)) => {
+                    tracing::debug!(?close_frame, "Futures WebSocket closed");
+                    break;
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::warn!(error = %e, "Futures WebSocket read error");
+                    break;
+                }
+            }
+        }
+        connected.store(false, Ordering::Relaxed);
+    }
+
+    /// Send a ping every `interval` until the client is dropped or a send fails.
+    async fn ping_loop(write_half: Weak<FuturesWsSink>, interval: Duration) {
+        let ping = serde_json::json!({"event": "ping"}).to_string();
+        let mut ticks = tokio::time::interval(interval);
+        ticks.tick().await;
+        loop {
+            ticks.tick().await;
+            let Some(sink) = write_half.upgrade() else {
+                return;
+            };
+            let sent = sink.lock().await.send(Message::Text(ping.clone())).await;
+            if let Err(e) = sent {
+                tracing::debug!(error = %e, "Futures WebSocket ping failed, stopping pings");
+                return;
+            }
+        }
+    }
+}
+
+impl Drop for KrakenFuturesWsClient {
+    fn drop(&mut self) {
+        self.ping_task.abort();
+    }
+}
+
+fn public_request(event: &str, feed: &str, product_ids: &[&str]) -> FuturesWsSubscribeRequest {
+    FuturesWsSubscribeRequest {
+        event: event.to_string(),
+        feed: feed.to_string(),
+        product_ids: Some(owned(product_ids)),
+        api_key: None,
+        original_challenge: None,
+        signed_challenge: None,
+    }
+}
+
+fn owned(product_ids: &[&str]) -> Vec<String> {
+    product_ids.iter().map(|id| id.to_string()).collect()
+}
+
+/// Futures echoes product IDs upper-cased, whatever case they were asked in.
+fn listed(products: &[String], product_id: &str) -> bool {
+    products.iter().any(|p| p.eq_ignore_ascii_case(product_id))
+}
@@ -0,0 +1,268 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+//
+// ──────────────────────────────────────────────────────────────────────────────
+// ── REQUESTS (CLIENT → SERVER) ──────────────────────────────────────────────
+// ──────────────────────────────────────────────────────────────────────────────
+//
+
+/// "challenge" request: the first step of authenticating private feeds
+#[derive(Debug, Serialize)]
+pub struct FuturesWsChallengeRequest {
+    pub event: String, // "challenge"
+    pub api_key: String,
+}
+
+/// "subscribe" / "unsubscribe" request. Public feeds name their
+/// `product_ids`; private feeds carry the key and the signed challenge instead.
+#[derive(Debug, Serialize)]
+pub struct FuturesWsSubscribeRequest {
+    pub event: String, // "subscribe" or "unsubscribe"
+    pub feed: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub product_ids: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_challenge: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signed_challenge: Option<String>,
+}
+
+//
+// ──────────────────────────────────────────────────────────────────────────────
+// ── RESPONSES (SERVER → CLIENT) ─────────────────────────────────────────────
+// ──────────────────────────────────────────────────────────────────────────────
+//
+
+/// Anything the Futures WebSocket sends, from `parse_message`.
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
+pub enum FuturesWsMessage {
+    /// A control message, tagged `event`
+    Event(FuturesWsEvent),
+    /// Feed data, tagged `feed`
+    Feed(FuturesWsFeed),
+    /// Valid JSON this crate doesn't model (yet)
+    Other(Value),
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum FuturesWsEvent {
+    Info {
+        version: u64,
+    },
+    Subscribed {
+        feed: String,
+        #[serde(default)]
+        product_ids: Vec<String>,
+    },
+    SubscribedFailed {
+        feed: String,
+        message: Option<String>,
+    },
+    Unsubscribed {
+        feed: String,
+        #[serde(default)]
+        product_ids: Vec<String>,
+    },
+    /// The challenge to sign (`signing::sign_challenge`) for private feeds
+    Challenge {
+        message: String,
+    },
+    Error {
+        message: String,
+    },
+    Alert {
+        message: String,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "feed", rename_all = "snake_case")]
+pub enum FuturesWsFeed {
+    Ticker(FuturesWsTicker),
+    BookSnapshot(FuturesWsBookSnapshot),
+    Book(FuturesWsBookUpdate),
+    FillsSnapshot(FuturesWsFillsMessage),
+    Fills(FuturesWsFillsMessage),
+    OpenOrdersSnapshot(FuturesWsOpenOrdersSnapshot),
+    OpenOrders(FuturesWsOpenOrderUpdate),
+    Heartbeat { time: u64 },
+}
+
+/// Parse one text frame. JSON that isn't a known event or feed comes back
+/// as `FuturesWsMessage::Other`; only invalid JSON is an error.
+pub fn parse_message(text: &str) -> serde_json::Result<FuturesWsMessage> {
+    let value: Value = serde_json::from_str(text)?;
+    let parsed = if value.get("event").is_some() {
+        FuturesWsEvent::deserialize(&value)
+            .map(FuturesWsMessage::Event)
+            .ok()
+    } else {
+        FuturesWsFeed::deserialize(&value)
+            .map(FuturesWsMessage::Feed)
+            .ok()
+    };
+    Ok(parsed.unwrap_or(FuturesWsMessage::Other(value)))
+}
+
+//
+// 1. MARKET DATA (ticker, book)
+//
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FuturesWsTicker {
+    pub product_id: String,
+    pub time: u64,
+    pub bid: Option<f64>,
+    pub ask: Option<f64>,
+    pub bid_size: Option<f64>,
+    pub ask_size: Option<f64>,
+    pub last: Option<f64>,
+    pub volume: Option<f64>,
+    pub change: Option<f64>,
+    #[serde(rename = "markPrice")]
+    pub mark_price: Option<f64>,
+    pub index: Option<f64>,
+    pub open_interest: Option<f64>,
+    /// Perpetuals only: the current and predicted hourly funding rates
+    pub funding_rate: Option<f64>,
+    pub funding_rate_prediction: Option<f64>,
+    #[serde(default)]
+    pub suspended: bool,
+    /// e.g. "perpetual", "month"
+    pub tag: Option<String>,
+    pub pair: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct FuturesWsLevel {
+    pub price: f64,
+    pub qty: f64,
+}
+
+/// The full book of one product, sent once after subscribing to `book`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FuturesWsBookSnapshot {
+    pub product_id: String,
+    pub timestamp: u64,
+    pub seq: u64,
+    pub bids: Vec<FuturesWsLevel>,
+    pub asks: Vec<FuturesWsLevel>,
+}
+
+/// One level changed: `qty` 0 removes it. `seq` follows the snapshot's.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FuturesWsBookUpdate {
+    pub product_id: String,
+    /// "buy" (bids) or "sell" (asks)
+    pub side: String,
+    pub seq: u64,
+    pub price: f64,
+    pub qty: f64,
+    pub timestamp: u64,
+}
+
+/// The `book` feed as `KrakenFuturesWsClient::subscribe_book` streams it.
+#[derive(Debug, Clone)]
+pub enum FuturesWsBook {
+    Snapshot(FuturesWsBookSnapshot),
+    Update(FuturesWsBookUpdate),
+}
+
+impl FuturesWsBook {
+    pub fn product_id(&self) -> &str {
+        match self {
+            FuturesWsBook::Snapshot(snapshot) => &snapshot.product_id,
+            FuturesWsBook::Update(update) => &update.product_id,
+        }
+    }
+}
+
+//
+// 2. PRIVATE FEEDS (fills, open_orders)
+//
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FuturesWsFillsMessage {
+    /// The account (the `username` on updates)
+    #[serde(alias = "username")]
+    pub account: Option<String>,
+    pub fills: Vec<FuturesWsFill>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FuturesWsFill {
+    pub instrument: String,
+    pub time: u64,
+    pub price: f64,
+    pub seq: u64,
+    /// `true` for a buy, `false` for a sell
+    pub buy: bool,
+    pub qty: f64,
+    pub order_id: String,
+    pub cli_ord_id: Option<String>,
+    pub fill_id: String,
+    /// e.g. "maker", "taker", "liquidation"
+    pub fill_type: String,
+    pub fee_paid: Option<f64>,
+    pub fee_currency: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FuturesWsOpenOrder {
+    pub instrument: String,
+    pub time: u64,
+    pub last_update_time: u64,
+    pub qty: f64,
+    pub filled: f64,
+    pub limit_price: Option<f64>,
+    pub stop_price: Option<f64>,
+    /// e.g. "limit", "stop", "take_profit"
+    #[serde(rename = "type")]
+    pub order_type: String,
+    pub order_id: String,
+    pub cli_ord_id: Option<String>,
+    /// 0 for a buy, 1 for a sell
+    pub direction: u8,
+    #[serde(default)]
+    pub reduce_only: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FuturesWsOpenOrdersSnapshot {
+    pub account: Option<String>,
+    pub orders: Vec<FuturesWsOpenOrder>,
+}
+
+/// An order placed, changed or gone. A cancel or full fill (`is_cancel`)
+/// only carries `order_id`; otherwise `order` has the order as it is now.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FuturesWsOpenOrderUpdate {
+    pub order: Option<FuturesWsOpenOrder>,
+    pub order_id: Option<String>,
+    pub cli_ord_id: Option<String>,
+    #[serde(default)]
+    pub is_cancel: bool,
+    /// e.g. "new_placed_order_by_user", "full_fill", "cancelled_by_user"
+    pub reason: Option<String>,
+}
+
+/// The `fills` feed as `KrakenFuturesWsClient::subscribe_fills` streams it:
+/// recent fills once, then new ones.
+#[derive(Debug, Clone)]
+pub enum FuturesWsFills {
+    Snapshot(FuturesWsFillsMessage),
+    Update(FuturesWsFillsMessage),
+}
+
+/// The `open_orders` feed as `KrakenFuturesWsClient::subscribe_open_orders`
+/// streams it: every open order once, then each change.
+#[derive(Debug, Clone)]
+pub enum FuturesWsOpenOrders {
+    Snapshot(FuturesWsOpenOrdersSnapshot),
+    Update(FuturesWsOpenOrderUpdate),
+}
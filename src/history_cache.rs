@@ -0,0 +1,203 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::future::Future;
+use std::path::Path;
+
+use crate::error::{KrakenError, KrakenResult};
+use crate::models::{LedgersResponse, OhlcDataResponse, TradesHistoryResponse, TradesResponse};
+use crate::{AuthenticatedClient, KrakenClient};
+
+/// An embedded (sled) cache for historical pages: OHLC, public trades, private
+/// trade history and ledgers.
+///
+/// Entries are keyed by endpoint plus the request parameters (pair, interval,
+/// `since`, `start`/`end`, `ofs`, ...), so asking for the same range twice is
+/// served locally. The `*_cached` client methods only cache closed ranges: OHLC
+/// and public trades need a `since`, trade history and ledgers an `end`.
+/// Open-ended queries still change over time, so they always go to Kraken.
+///
+/// Enabled with the `history-cache` feature.
+#[derive(Clone)]
+pub struct HistoryCache {
+    db: sled::Db,
+}
+
+impl HistoryCache {
+    /// Open (or create) a cache database at `path`.
+    pub fn open<P: AsRef<Path>>(path: P) -> KrakenResult<Self> {
+        let db = sled::open(path).map_err(cache_err)?;
+        Ok(Self { db })
+    }
+
+    /// A throwaway cache that is deleted when dropped. Handy for tests.
+    pub fn temporary() -> KrakenResult<Self> {
+        let db = sled::Config::new()
+            .temporary(true)
+            .open()
+            .map_err(cache_err)?;
+        Ok(Self { db })
+    }
+
+    /// Build the cache key for `endpoint` + `params`. Parameters are sorted, so
+    /// the order the caller passes them in does not matter.
+    pub fn key(endpoint: &str, params: &[(&str, &str)]) -> String {
+        let mut sorted: Vec<&(&str, &str)> = params.iter().collect();
+        sorted.sort();
+        let query = sorted
+            .iter()
+            .map(|(k, v)| format!("{k}={v}"))
+            .collect::<Vec<String>>()
+            .join("&");
+        format!("{endpoint}?{query}")
+    }
+
+    /// Read a cached value, if present.
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> KrakenResult<Option<T>> {
+        match self.db.get(key).map_err(cache_err)? {
+            Some(bytes) => {
+                let value = serde_json::from_slice(&bytes)
+                    .map_err(|e| KrakenError::Cache(format!("Corrupt entry {key}: {e}")))?;
+                Ok(Some(value))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Store a value under `key`, replacing any previous entry.
+    pub fn put<T: Serialize>(&self, key: &str, value: &T) -> KrakenResult<()> {
+        let bytes = serde_json::to_vec(value)
+            .map_err(|e| KrakenError::Cache(format!("Serialize error: {e}")))?;
+        self.db.insert(key, bytes).map_err(cache_err)?;
+        Ok(())
+    }
+
+    /// Return the cached value for `key`, or run `fetch`, store its result and return it.
+    pub async fn get_or_fetch<T, F, Fut>(&self, key: &str, fetch: F) -> KrakenResult<T>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = KrakenResult<T>>,
+    {
+        if let Some(hit) = self.get(key)? {
+            return Ok(hit);
+        }
+        let value = fetch().await?;
+        self.put(key, &value)?;
+        Ok(value)
+    }
+
+    /// `get_or_fetch` if `params` bound the range with `bound` (`since` or
+    /// `end`); otherwise the range is still open, so skip the cache and fetch.
+    async fn get_or_fetch_closed<T, F, Fut>(
+        &self,
+        endpoint: &str,
+        params: &[(&str, &str)],
+        bound: &str,
+        fetch: F,
+    ) -> KrakenResult<T>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = KrakenResult<T>>,
+    {
+        if !params.iter().any(|(k, _)| *k == bound) {
+            return fetch().await;
+        }
+        self.get_or_fetch(&Self::key(endpoint, params), fetch).await
+    }
+
+    /// Remove a single entry.
+    pub fn invalidate(&self, key: &str) -> KrakenResult<()> {
+        self.db.remove(key).map_err(cache_err)?;
+        Ok(())
+    }
+
+    /// Remove every entry.
+    pub fn clear(&self) -> KrakenResult<()> {
+        self.db.clear().map_err(cache_err)
+    }
+
+    /// Number of cached pages.
+    pub fn len(&self) -> usize {
+        self.db.len()
+    }
+
+    /// `true` if nothing is cached.
+    pub fn is_empty(&self) -> bool {
+        self.db.is_empty()
+    }
+
+    /// Flush pending writes to disk.
+    pub async fn flush(&self) -> KrakenResult<()> {
+        self.db.flush_async().await.map_err(cache_err)?;
+        Ok(())
+    }
+}
+
+fn cache_err(err: sled::Error) -> KrakenError {
+    KrakenError::Cache(err.to_string())
+}
+
+// ─────────────────────────────────────────────────────────────
+// CACHED ENDPOINT WRAPPERS
+// ─────────────────────────────────────────────────────────────
+
+impl<S> KrakenClient<S> {
+    /// `get_ohlc_data`, served from `cache` when the same pair/interval/since was
+    /// fetched before. Without `since` the request is not cached.
+    pub async fn get_ohlc_data_cached(
+        &self,
+        cache: &HistoryCache,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<OhlcDataResponse> {
+        cache
+            .get_or_fetch_closed("/0/public/OHLC", params, "since", || {
+                self.get_ohlc_data(params)
+            })
+            .await
+    }
+
+    /// `get_recent_trades`, served from `cache` when the same pair/since was
+    /// fetched before. Without `since` the request is not cached.
+    pub async fn get_recent_trades_cached(
+        &self,
+        cache: &HistoryCache,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<TradesResponse> {
+        cache
+            .get_or_fetch_closed("/0/public/Trades", params, "since", || {
+                self.get_recent_trades(params)
+            })
+            .await
+    }
+}
+
+impl AuthenticatedClient {
+    /// `get_trades_history`, served from `cache` when the same range/offset was
+    /// fetched before. Without `end` the request is not cached.
+    pub async fn get_trades_history_cached(
+        &self,
+        cache: &HistoryCache,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<TradesHistoryResponse> {
+        cache
+            .get_or_fetch_closed("/0/private/TradesHistory", params, "end", || {
+                self.get_trades_history(params)
+            })
+            .await
+    }
+
+    /// `get_ledgers`, served from `cache` when the same asset/range/offset was
+    /// fetched before. Without `end` the request is not cached.
+    pub async fn get_ledgers_cached(
+        &self,
+        cache: &HistoryCache,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<LedgersResponse> {
+        cache
+            .get_or_fetch_closed("/0/private/Ledgers", params, "end", || {
+                self.get_ledgers(params)
+            })
+            .await
+    }
+}
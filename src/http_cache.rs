@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// A cached response body plus the validators needed for a conditional refresh.
+#[derive(Debug, Clone)]
+pub(crate) struct CachedResponse {
+    pub body: Vec<u8>,
+    pub fetched_at: Instant,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+/// In-memory cache for public metadata endpoints (`/Assets`, `/AssetPairs`,
+/// `/SystemStatus`).
+///
+/// - Fresh entries (younger than `ttl`) are served without touching the network.
+/// - Expired entries are revalidated with `If-None-Match` / `If-Modified-Since`
+///   when the server sent validators; a `304 Not Modified` keeps the old body.
+#[derive(Debug)]
+pub(crate) struct MetadataCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, CachedResponse>>,
+}
+
+impl MetadataCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Cache key: the path plus the query parameters in the order given.
+    pub fn key(path: &str, params: &[(&str, &str)]) -> String {
+        let query = params
+            .iter()
+            .map(|(k, v)| format!("{k}={v}"))
+            .collect::<Vec<String>>()
+            .join("&");
+        format!("{path}?{query}")
+    }
+
+    /// Returns the entry (fresh or not) and whether it is still within the TTL
+    /// at `now`.
+    pub fn lookup(&self, key: &str, now: Instant) -> Option<(CachedResponse, bool)> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.get(key).map(|entry| {
+            let age = now.saturating_duration_since(entry.fetched_at);
+            (entry.clone(), age < self.ttl)
+        })
+    }
+
+    pub fn store(&self, key: String, entry: CachedResponse) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.insert(key, entry);
+    }
+
+    /// Mark an entry as fetched at `now` after a `304 Not Modified`.
+    pub fn touch(&self, key: &str, now: Instant) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(entry) = entries.get_mut(key) {
+            entry.fetched_at = now;
+        }
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+}
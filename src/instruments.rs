@@ -0,0 +1,149 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+use futures_util::stream::{self, Stream, StreamExt};
+
+use crate::feed::FeedStream;
+use crate::ws_models::{InstrumentData, WsInstrumentsMessage};
+
+/// A pair's trading status or precision changed on the `instruments` channel.
+#[derive(Debug, Clone)]
+pub struct PairStatusChanged {
+    pub symbol: String,
+    /// The pair as last seen; `None` for a newly listed pair
+    pub previous: Option<InstrumentData>,
+    pub current: InstrumentData,
+}
+
+impl PairStatusChanged {
+    /// `true` if `status` changed (e.g. "online" → "cancel_only").
+    pub fn status_changed(&self) -> bool {
+        self.previous
+            .as_ref()
+            .is_none_or(|previous| previous.status != self.current.status)
+    }
+
+    /// `true` if price or quantity precision, tick or lot size, or the
+    /// minimum volume changed.
+    pub fn precision_changed(&self) -> bool {
+        self.previous.as_ref().is_none_or(|previous| {
+            let current = &self.current;
+            previous.price_decimals != current.price_decimals
+                || previous.quantity_decimals != current.quantity_decimals
+                || previous.tick_size != current.tick_size
+                || previous.lot_size != current.lot_size
+                || previous.min_volume != current.min_volume
+        })
+    }
+}
+
+type Invalidate = Arc<dyn Fn() + Send + Sync>;
+
+/// Turns the WebSocket `instruments` channel into `PairStatusChanged` events
+/// for pairs whose status or precision changed, e.g. when a pair goes into
+/// maintenance or cancel-only. The first message is the baseline and yields
+/// nothing; other changes (fees, leverage) are ignored.
+///
+/// With `with_rest_client`, the client's metadata cache is dropped on every
+/// change too, so its next `AssetPairs` read (and the order validators built
+/// on it, such as `validate_leverage`) sees the new values.
+///
+/// ```no_run
+/// # async fn run(client: onise::AuthenticatedClient, ws: onise::ws_client::KrakenWsClient) {
+/// use futures_util::StreamExt;
+/// use onise::instruments::InstrumentWatcher;
+///
+/// let mut changes = InstrumentWatcher::new()
+///     .with_rest_client(&client)
+///     .watch(ws.instruments_stream());
+/// while let Some(change) = changes.next().await {
+///     if change.status_changed() {
+///         println!("{} is now {}", change.symbol, change.current.status);
+///     }
+/// }
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct InstrumentWatcher {
+    invalidate: Option<Invalidate>,
+}
+
+impl InstrumentWatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Clear `client`'s metadata cache (`with_metadata_cache`) whenever a pair changes.
+    #[cfg(feature = "rest")]
+    pub fn with_rest_client<S>(mut self, client: &crate::rest_client::KrakenClient<S>) -> Self
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        let client = client.clone();
+        self.invalidate = Some(Arc::new(move || client.clear_metadata_cache()));
+        self
+    }
+
+    /// Diff successive `instruments` messages into `PairStatusChanged` events.
+    pub fn watch<S>(self, instruments: S) -> FeedStream<PairStatusChanged>
+    where
+        S: Stream<Item = WsInstrumentsMessage> + Send + 'static,
+    {
+        let state = (
+            self,
+            instruments.boxed(),
+            None::<HashMap<String, InstrumentData>>,
+            VecDeque::new(),
+        );
+        let changes = stream::unfold(
+            state,
+            |(watcher, mut instruments, mut known, mut pending)| async move {
+                loop {
+                    if let Some(change) = pending.pop_front() {
+                        return Some((change, (watcher, instruments, known, pending)));
+                    }
+                    let msg = instruments.next().await?;
+                    let Some(pairs) = known.as_mut() else {
+                        known = Some(
+                            msg.data
+                                .into_iter()
+                                .map(|pair| (pair.symbol.clone(), pair))
+                                .collect(),
+                        );
+                        continue;
+                    };
+                    for current in msg.data {
+                        let previous = pairs.insert(current.symbol.clone(), current.clone());
+                        let change = PairStatusChanged {
+                            symbol: current.symbol.clone(),
+                            previous,
+                            current,
+                        };
+                        if change.status_changed() || change.precision_changed() {
+                            tracing::info!(
+                                symbol = %change.symbol,
+                                status = %change.current.status,
+                                "instrument changed"
+                            );
+                            pending.push_back(change);
+                        }
+                    }
+                    if !pending.is_empty() {
+                        if let Some(invalidate) = &watcher.invalidate {
+                            invalidate();
+                        }
+                    }
+                }
+            },
+        );
+        FeedStream::from_stream(changes)
+    }
+}
+
+impl std::fmt::Debug for InstrumentWatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InstrumentWatcher")
+            .field("invalidate", &self.invalidate.is_some())
+            .finish()
+    }
+}
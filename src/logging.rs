@@ -0,0 +1,127 @@
+use std::fmt;
+use std::time::Duration;
+
+/// Placeholder written in place of any secret value.
+pub const REDACTED: &str = "<redacted>";
+
+/// Request parameters whose values are never logged.
+pub const SENSITIVE_PARAMS: &[&str] = &["otp", "token", "password"];
+
+/// Headers whose values are never logged.
+pub const SENSITIVE_HEADERS: &[&str] = &["API-Key", "API-Sign"];
+
+/// One REST round trip, with secrets already removed.
+#[derive(Debug, Clone)]
+pub struct RequestLog {
+    /// "GET" or "POST"
+    pub method: &'static str,
+    /// URI path, e.g. "/0/private/Balance"
+    pub path: String,
+    /// The `X-Request-ID` that was sent
+    pub request_id: String,
+    /// Query/form parameters with sensitive values replaced by `REDACTED`
+    pub params: Vec<(String, String)>,
+    /// Names of the authentication headers that were sent (values always `REDACTED`)
+    pub redacted_headers: Vec<&'static str>,
+    /// HTTP status, if a response was received
+    pub status: Option<u16>,
+    /// Time from sending the request to reading the full body
+    pub elapsed: Duration,
+    /// Transport error, if the request failed before a response was read
+    pub error: Option<String>,
+}
+
+impl fmt::Display for RequestLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let params = self
+            .params
+            .iter()
+            .map(|(k, v)| format!("{k}={v}"))
+            .collect::<Vec<String>>()
+            .join("&");
+        let status = self
+            .status
+            .map_or_else(|| "-".to_string(), |s| s.to_string());
+        write!(
+            f,
+            "{} {} [{}] params=[{}] status={} elapsed={:?}",
+            self.method, self.path, self.request_id, params, status, self.elapsed
+        )?;
+        for header in &self.redacted_headers {
+            write!(f, " {header}={REDACTED}")?;
+        }
+        if let Some(error) = &self.error {
+            write!(f, " error={error}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Receives a `RequestLog` for every REST request once logging is enabled with
+/// `KrakenClient::with_request_logger`. Implemented for closures.
+pub trait RequestLogger: Send + Sync {
+    fn log(&self, entry: &RequestLog);
+}
+
+impl<F> RequestLogger for F
+where
+    F: Fn(&RequestLog) + Send + Sync,
+{
+    fn log(&self, entry: &RequestLog) {
+        self(entry)
+    }
+}
+
+/// Prints each request to stderr.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StderrLogger;
+
+impl RequestLogger for StderrLogger {
+    fn log(&self, entry: &RequestLog) {
+        eprintln!("{entry}");
+    }
+}
+
+/// Emits each request as a `tracing` debug event.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TracingLogger;
+
+impl RequestLogger for TracingLogger {
+    fn log(&self, entry: &RequestLog) {
+        tracing::debug!(
+            method = entry.method,
+            path = %entry.path,
+            request_id = %entry.request_id,
+            status = ?entry.status,
+            elapsed_ms = entry.elapsed.as_millis() as u64,
+            "{entry}"
+        );
+    }
+}
+
+/// Copy `params`, replacing values of `SENSITIVE_PARAMS` with `REDACTED`.
+pub fn sanitize_params(params: &[(&str, &str)]) -> Vec<(String, String)> {
+    params
+        .iter()
+        .map(|(k, v)| {
+            let value = if SENSITIVE_PARAMS.contains(k) {
+                REDACTED
+            } else {
+                v
+            };
+            (k.to_string(), value.to_string())
+        })
+        .collect()
+}
+
+/// Shared handle to a `RequestLogger` that can live inside a `Debug + Clone` client.
+#[cfg(feature = "rest")]
+#[derive(Clone)]
+pub(crate) struct LoggerHandle(pub std::sync::Arc<dyn RequestLogger>);
+
+#[cfg(feature = "rest")]
+impl fmt::Debug for LoggerHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RequestLogger")
+    }
+}
@@ -1,10 +1,10 @@
 use std::env;
-use std::time::Duration;
-use tokio::time::sleep;
+use tokio::sync::broadcast::error::RecvError;
 use dotenv::dotenv;
 
+use onise::environment::Environment;
 use onise::error::KrakenResult;
-use onise::KrakenClient;
+use onise::PublicClient;
 use onise::ws_client::KrakenWsClient;
 use onise::ws_models::WsSubscriptionPayload; // for WebSocket subscriptions
 
@@ -18,8 +18,13 @@ async fn main() -> KrakenResult<()> {
     match mode.as_str() {
         "rest" => run_rest().await,
         "ws" => run_ws().await,
+        #[cfg(feature = "tui")]
+        "book" => {
+            let symbol = env::args().nth(2).unwrap_or_else(|| "BTC/USD".to_string());
+            book_viewer::run(&symbol).await
+        }
         other => {
-            eprintln!("Unknown mode: {}. Usage: cargo run -- [rest|ws]", other);
+            eprintln!("Unknown mode: {}. Usage: cargo run -- [rest|ws|book <PAIR>]", other);
             Ok(())
         }
     }
@@ -32,7 +37,7 @@ async fn run_rest() -> KrakenResult<()> {
     let api_secret = env::var("KRAKEN_API_SECRET").ok();
 
     // Build the REST client
-    let client = KrakenClient::new(api_key, api_secret, None);
+    let client = PublicClient::new(None);
 
     // Call a public endpoint
     match client.get_server_time().await {
@@ -41,6 +46,11 @@ async fn run_rest() -> KrakenResult<()> {
     }
 
     // Call a private endpoint (requires valid credentials)
+    let (Some(api_key), Some(api_secret)) = (api_key, api_secret) else {
+        eprintln!("KRAKEN_API_KEY / KRAKEN_API_SECRET not set, skipping private calls");
+        return Ok(());
+    };
+    let client = client.with_credentials(api_key, api_secret)?;
     match client.get_balance().await {
         Ok(balance) => {
             println!("Balance: {:?}", balance.balances);
@@ -56,13 +66,15 @@ async fn run_rest() -> KrakenResult<()> {
 /// Run the Spot WebSocket API example
 async fn run_ws() -> KrakenResult<()> {
     // Read an environment variable for the WebSocket URL, default to Kraken Spot v2
-    let url = env::var("WS_URL").unwrap_or_else(|_| "wss://ws.kraken.com/v2".to_string());
+    let url = env::var("WS_URL")
+        .unwrap_or_else(|_| Environment::Production.ws_public_url().to_string());
 
     // Optionally read an auth token for private streams
     let token = env::var("KRAKEN_WS_TOKEN").ok();
 
-    // Connect to the WebSocket
+    // Connect to the WebSocket, and take a receiver before anything arrives
     let client = KrakenWsClient::connect(&url).await?;
+    let mut messages = client.messages();
 
     // If you have a token, authorize for private data
     if let Some(t) = token {
@@ -85,6 +97,150 @@ async fn run_ws() -> KrakenResult<()> {
     println!("Connected to {url}. Listening for WS messages...");
     // In real usage, we might run indefinitely, or until a signal
     loop {
-        sleep(Duration::from_secs(10)).await;
+        match messages.recv().await {
+            Ok(msg) => println!("{msg:?}"),
+            Err(RecvError::Lagged(skipped)) => eprintln!("Skipped {skipped} messages"),
+            Err(RecvError::Closed) => return Ok(()),
+        }
+    }
+}
+
+/// Live depth ladder and trade tape for one pair, drawn from the WS `book` and
+/// `trade` channels through `onise::order_book`. Run with
+/// `cargo run --features tui -- book BTC/USD`; press `q` to quit.
+#[cfg(feature = "tui")]
+mod book_viewer {
+    use std::io::{stdout, Write};
+    use std::time::Duration;
+
+    use crossterm::event::{self, Event, KeyCode, KeyModifiers};
+    use crossterm::{cursor, execute, queue, style, terminal};
+    use tokio::sync::broadcast::error::RecvError;
+
+    use onise::environment::Environment;
+    use onise::error::{KrakenError, KrakenResult};
+    use onise::order_book::{OrderBook, RecentTrades};
+    use onise::ws_client::KrakenWsClient;
+    use onise::ws_models::{WsIncomingMessage, WsSubscriptionPayload};
+
+    const DEPTH: u32 = 25;
+    const TRADES: usize = 20;
+
+    pub async fn run(symbol: &str) -> KrakenResult<()> {
+        let client = KrakenWsClient::connect_public(&Environment::Production).await?;
+        let mut messages = client.messages();
+        client
+            .subscribe(
+                WsSubscriptionPayload::Book {
+                    symbol: symbol.to_string(),
+                    depth: DEPTH,
+                },
+                Some(1),
+            )
+            .await?;
+        client
+            .subscribe(
+                WsSubscriptionPayload::Trades {
+                    symbol: symbol.to_string(),
+                },
+                Some(2),
+            )
+            .await?;
+
+        let mut book = OrderBook::new(symbol, DEPTH as usize);
+        let mut trades = RecentTrades::new(symbol, TRADES);
+
+        terminal::enable_raw_mode()?;
+        execute!(stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+
+        let mut redraw = tokio::time::interval(Duration::from_millis(200));
+        let result = loop {
+            tokio::select! {
+                msg = messages.recv() => match msg {
+                    Ok(WsIncomingMessage::BookMsg(update)) => book.apply(&update),
+                    Ok(WsIncomingMessage::TradesMsg(update)) => trades.apply(&update),
+                    Ok(_) | Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => {
+                        break Err(KrakenError::InvalidUsage("WebSocket closed".into()));
+                    }
+                },
+                _ = redraw.tick() => {
+                    if quit_requested()? {
+                        break Ok(());
+                    }
+                    draw(&book, &trades)?;
+                }
+            }
+        };
+
+        execute!(stdout(), cursor::Show, terminal::LeaveAlternateScreen)?;
+        terminal::disable_raw_mode()?;
+        result
+    }
+
+    fn quit_requested() -> std::io::Result<bool> {
+        while event::poll(Duration::ZERO)? {
+            if let Event::Key(key) = event::read()? {
+                let ctrl_c =
+                    key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+                if ctrl_c || matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) {
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    fn draw(book: &OrderBook, trades: &RecentTrades) -> std::io::Result<()> {
+        let (_, rows) = terminal::size()?;
+        let half = (rows.saturating_sub(4) / 2) as usize;
+        let mut out = stdout();
+        queue!(out, terminal::Clear(terminal::ClearType::All), cursor::MoveTo(0, 0))?;
+
+        let spread = book
+            .spread()
+            .map_or_else(|| "-".to_string(), |s| format!("{s:.8}"));
+        queue!(
+            out,
+            style::Print(format!("{}  spread {spread}   (q to quit)", book.symbol)),
+            cursor::MoveToNextLine(2)
+        )?;
+
+        // Asks above the spread (best ask nearest the middle), bids below.
+        let asks = book.asks();
+        for level in asks.iter().take(half).rev() {
+            queue!(
+                out,
+                style::SetForegroundColor(style::Color::Red),
+                style::Print(format!("{:>18} {:>18}", level.price, level.quantity)),
+                cursor::MoveToNextLine(1)
+            )?;
+        }
+        queue!(out, style::ResetColor, style::Print("-".repeat(37)), cursor::MoveToNextLine(1))?;
+        for level in book.bids().iter().take(half) {
+            queue!(
+                out,
+                style::SetForegroundColor(style::Color::Green),
+                style::Print(format!("{:>18} {:>18}", level.price, level.quantity)),
+                cursor::MoveToNextLine(1)
+            )?;
+        }
+
+        queue!(out, style::ResetColor, cursor::MoveTo(42, 2), style::Print("recent trades"))?;
+        for (row, trade) in trades.iter().enumerate() {
+            let color = if trade.side == "buy" {
+                style::Color::Green
+            } else {
+                style::Color::Red
+            };
+            queue!(
+                out,
+                cursor::MoveTo(42, 3 + row as u16),
+                style::SetForegroundColor(color),
+                style::Print(format!("{:>4} {:>18} {:>18}", trade.side, trade.price, trade.quantity)),
+            )?;
+        }
+        queue!(out, style::ResetColor)?;
+        out.flush()
     }
 }
@@ -0,0 +1,141 @@
+use std::future::Future;
+
+use crate::error::KrakenResult;
+use crate::feed::FeedStream;
+use crate::ws_models::{WsBookMessage, WsCandlesMessage, WsTickerMessage, WsTradesMessage};
+
+/// A source of public market data, so strategy code can be written once and
+/// run against the WebSocket feeds (`KrakenWsClient`) or REST polling
+/// (`polling::RestPoller`), or both at once for redundancy (e.g. merging the
+/// two streams with `futures::stream::select`).
+///
+/// Every method starts the feed for `symbol` (subscribing or starting to poll)
+/// and returns a stream of that symbol's updates only. `symbol` is passed to
+/// the backend as-is, so use a name it accepts.
+pub trait MarketDataProvider: Send + Sync {
+    /// Best bid/ask and 24h statistics.
+    fn quotes(
+        &self,
+        symbol: &str,
+    ) -> impl Future<Output = KrakenResult<FeedStream<WsTickerMessage>>> + Send;
+
+    /// Order book with `depth` levels per side: a snapshot, then updates
+    /// (WebSocket) or further snapshots (REST).
+    fn books(
+        &self,
+        symbol: &str,
+        depth: u32,
+    ) -> impl Future<Output = KrakenResult<FeedStream<WsBookMessage>>> + Send;
+
+    /// OHLC candles of `interval` minutes.
+    fn candles(
+        &self,
+        symbol: &str,
+        interval: u32,
+    ) -> impl Future<Output = KrakenResult<FeedStream<WsCandlesMessage>>> + Send;
+
+    /// Public trades.
+    fn trades(
+        &self,
+        symbol: &str,
+    ) -> impl Future<Output = KrakenResult<FeedStream<WsTradesMessage>>> + Send;
+}
+
+#[cfg(feature = "ws")]
+mod ws {
+    use futures_util::{future, StreamExt};
+
+    use super::*;
+    use crate::ws_client::KrakenWsClient;
+    use crate::ws_models::WsSubscriptionPayload;
+
+    /// Keep only `symbol`'s messages, since one connection can carry several
+    /// subscriptions on the same channel.
+    fn only<T: Send + 'static>(
+        feed: FeedStream<T>,
+        symbol: &str,
+        symbol_of: fn(&T) -> &str,
+    ) -> FeedStream<T> {
+        let symbol = symbol.to_string();
+        FeedStream::from_stream(feed.filter(move |msg| future::ready(symbol_of(msg) == symbol)))
+    }
+
+    /// Subscribes on this connection. The stream is created before the
+    /// subscribe request is sent, so the initial snapshot is never missed.
+    impl MarketDataProvider for KrakenWsClient {
+        async fn quotes(&self, symbol: &str) -> KrakenResult<FeedStream<WsTickerMessage>> {
+            let feed = only(self.ticker_stream(), symbol, |m| &m.symbol);
+            let payload = WsSubscriptionPayload::Ticker {
+                symbol: symbol.to_string(),
+            };
+            self.subscribe(payload, None).await?;
+            Ok(feed)
+        }
+
+        async fn books(&self, symbol: &str, depth: u32) -> KrakenResult<FeedStream<WsBookMessage>> {
+            let feed = only(self.book_stream(), symbol, |m| &m.symbol);
+            let payload = WsSubscriptionPayload::Book {
+                symbol: symbol.to_string(),
+                depth,
+            };
+            self.subscribe(payload, None).await?;
+            Ok(feed)
+        }
+
+        async fn candles(
+            &self,
+            symbol: &str,
+            interval: u32,
+        ) -> KrakenResult<FeedStream<WsCandlesMessage>> {
+            let feed = self.candles_stream();
+            let feed = FeedStream::from_stream({
+                let symbol = symbol.to_string();
+                feed.filter(move |m| future::ready(m.symbol == symbol && m.interval == interval))
+            });
+            let payload = WsSubscriptionPayload::Candles {
+                symbol: symbol.to_string(),
+                interval,
+            };
+            self.subscribe(payload, None).await?;
+            Ok(feed)
+        }
+
+        async fn trades(&self, symbol: &str) -> KrakenResult<FeedStream<WsTradesMessage>> {
+            let feed = only(self.trades_stream(), symbol, |m| &m.symbol);
+            let payload = WsSubscriptionPayload::Trades {
+                symbol: symbol.to_string(),
+            };
+            self.subscribe(payload, None).await?;
+            Ok(feed)
+        }
+    }
+}
+
+#[cfg(feature = "rest")]
+mod rest {
+    use super::*;
+    use crate::polling::RestPoller;
+
+    /// Polls every `RestPoller::interval`; see `RestPoller` for change detection.
+    impl<S: Clone + Send + Sync + 'static> MarketDataProvider for RestPoller<S> {
+        async fn quotes(&self, symbol: &str) -> KrakenResult<FeedStream<WsTickerMessage>> {
+            Ok(self.ticker_stream(symbol))
+        }
+
+        async fn books(&self, symbol: &str, depth: u32) -> KrakenResult<FeedStream<WsBookMessage>> {
+            Ok(self.book_stream(symbol, depth))
+        }
+
+        async fn candles(
+            &self,
+            symbol: &str,
+            interval: u32,
+        ) -> KrakenResult<FeedStream<WsCandlesMessage>> {
+            Ok(self.candles_stream(symbol, interval))
+        }
+
+        async fn trades(&self, symbol: &str) -> KrakenResult<FeedStream<WsTradesMessage>> {
+            Ok(self.trades_stream(symbol))
+        }
+    }
+}
@@ -0,0 +1,403 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Write as _};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::report::csv_field;
+
+/// Upper bounds (in milliseconds) of the histogram buckets. A final, implicit
+/// bucket collects everything slower than the last bound.
+pub const BUCKET_BOUNDS_MS: [u64; 14] = [
+    1, 2, 5, 10, 20, 50, 100, 200, 500, 1_000, 2_000, 5_000, 10_000, 30_000,
+];
+
+/// How long `Metrics` keeps the per-minute call counts behind `Metrics::usage`.
+pub const USAGE_RETENTION: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The caller a client's requests are counted under until `with_caller`.
+pub const DEFAULT_CALLER: &str = "default";
+
+/// A fixed-bucket latency histogram.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LatencyHistogram {
+    /// Number of samples
+    pub count: u64,
+    /// Sum of all samples
+    pub sum: Duration,
+    /// Fastest sample
+    pub min: Option<Duration>,
+    /// Slowest sample
+    pub max: Option<Duration>,
+    /// Sample counts per bucket: `buckets[i]` counts samples `<= BUCKET_BOUNDS_MS[i]`
+    /// (and above the previous bound); the last entry counts the overflow.
+    pub buckets: [u64; BUCKET_BOUNDS_MS.len() + 1],
+}
+
+impl LatencyHistogram {
+    /// Add one sample.
+    pub fn record(&mut self, sample: Duration) {
+        let ms = sample.as_millis() as u64;
+        let bucket = BUCKET_BOUNDS_MS
+            .iter()
+            .position(|bound| ms <= *bound)
+            .unwrap_or(BUCKET_BOUNDS_MS.len());
+        self.buckets[bucket] += 1;
+        self.count += 1;
+        self.sum += sample;
+        self.min = Some(self.min.map_or(sample, |min| min.min(sample)));
+        self.max = Some(self.max.map_or(sample, |max| max.max(sample)));
+    }
+
+    /// Average sample, if any were recorded.
+    pub fn mean(&self) -> Option<Duration> {
+        (self.count > 0).then(|| self.sum / self.count as u32)
+    }
+
+    /// Upper bound of the bucket containing quantile `q` (0.0..=1.0), e.g.
+    /// `quantile(0.99)` for p99. Samples past the last bucket report `max`.
+    pub fn quantile(&self, q: f64) -> Option<Duration> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = ((q.clamp(0.0, 1.0) * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (i, n) in self.buckets.iter().enumerate() {
+            seen += n;
+            if seen >= rank {
+                return match BUCKET_BOUNDS_MS.get(i) {
+                    Some(bound) => Some(Duration::from_millis(*bound).min(self.max?)),
+                    None => self.max,
+                };
+            }
+        }
+        self.max
+    }
+}
+
+impl fmt::Display for LatencyHistogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let show = |d: Option<Duration>| d.map_or_else(|| "-".to_string(), |d| format!("{d:?}"));
+        write!(
+            f,
+            "n={} mean={} p50={} p99={} max={}",
+            self.count,
+            show(self.mean()),
+            show(self.quantile(0.5)),
+            show(self.quantile(0.99)),
+            show(self.max)
+        )
+    }
+}
+
+/// Latency of one REST endpoint.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EndpointLatency {
+    /// Time from sending the request until the response headers arrived
+    pub ttfb: LatencyHistogram,
+    /// Time from sending the request until the whole body was read
+    pub total: LatencyHistogram,
+}
+
+/// A point-in-time copy of everything `Metrics` has recorded.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MetricsSnapshot {
+    /// TCP + TLS connection establishment. Connections are pooled and shared
+    /// across endpoints, so this is recorded once per new connection rather
+    /// than per endpoint.
+    pub connect: LatencyHistogram,
+    /// Per URI path, e.g. "/0/private/Balance"
+    pub endpoints: BTreeMap<String, EndpointLatency>,
+}
+
+/// How often Kraken has returned one error code.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorCount {
+    /// Responses carrying the code
+    pub count: u64,
+    /// When the code was first returned
+    pub first_seen: SystemTime,
+    /// When the code was last returned
+    pub last_seen: SystemTime,
+}
+
+/// The code an error message is counted under: its category and description
+/// without any trailing detail, e.g. `"EGeneral:Invalid arguments:volume"`
+/// counts as `"EGeneral:Invalid arguments"`.
+///
+/// ```
+/// use onise::metrics::error_code;
+///
+/// assert_eq!(error_code("EOrder:Insufficient funds"), "EOrder:Insufficient funds");
+/// assert_eq!(error_code("EGeneral:Invalid arguments:volume"), "EGeneral:Invalid arguments");
+/// ```
+pub fn error_code(message: &str) -> &str {
+    match message.match_indices(':').nth(1) {
+        Some((end, _)) => &message[..end],
+        None => message,
+    }
+}
+
+/// Width of the time buckets in a `UsageReport`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsageBucket {
+    Minute,
+    Hour,
+}
+
+impl UsageBucket {
+    pub fn duration(self) -> Duration {
+        match self {
+            UsageBucket::Minute => Duration::from_secs(60),
+            UsageBucket::Hour => Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+/// The calls one caller made to one endpoint within one bucket.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsageRow {
+    /// Start of the bucket, on a whole minute or hour (UTC)
+    pub start: SystemTime,
+    /// Who made the calls, as set with `KrakenClient::with_caller`
+    pub caller: String,
+    /// URI path, e.g. "/0/private/Balance"
+    pub endpoint: String,
+    pub calls: u64,
+}
+
+/// REST calls per caller and endpoint over the last `USAGE_RETENTION`, in
+/// minute or hour buckets, from `Metrics::usage`. For services sharing an
+/// API key (and so its rate budget), it shows which of them spends it.
+///
+/// Rows are ordered by bucket, then caller, then endpoint.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UsageReport {
+    pub bucket: UsageBucket,
+    pub rows: Vec<UsageRow>,
+}
+
+impl UsageReport {
+    /// Calls per caller across the whole report.
+    pub fn by_caller(&self) -> BTreeMap<String, u64> {
+        self.total_by(|row| &row.caller)
+    }
+
+    /// Calls per endpoint across the whole report.
+    pub fn by_endpoint(&self) -> BTreeMap<String, u64> {
+        self.total_by(|row| &row.endpoint)
+    }
+
+    /// One CSV row per bucket, caller and endpoint, with a header row; `start`
+    /// is in Unix seconds.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("start,caller,endpoint,calls\n");
+        for row in &self.rows {
+            let start = row.start.duration_since(UNIX_EPOCH).unwrap_or_default();
+            let _ = writeln!(
+                csv,
+                "{},{},{},{}",
+                start.as_secs(),
+                csv_field(&row.caller),
+                csv_field(&row.endpoint),
+                row.calls
+            );
+        }
+        csv
+    }
+
+    fn total_by(&self, key: impl Fn(&UsageRow) -> &String) -> BTreeMap<String, u64> {
+        let mut totals = BTreeMap::new();
+        for row in &self.rows {
+            *totals.entry(key(row).clone()).or_insert(0) += row.calls;
+        }
+        totals
+    }
+}
+
+#[derive(Debug, Default)]
+struct MetricsState {
+    latency: MetricsSnapshot,
+    errors: BTreeMap<String, ErrorCount>,
+    /// Calls by (Unix minute, caller, endpoint)
+    usage: BTreeMap<(u64, String, String), u64>,
+}
+
+/// Shared metrics handle for a `KrakenClient`, returned by `KrakenClient::metrics`.
+///
+/// Clones share the same counters, as do clients derived from each other with
+/// `with_credentials` / `to_public`.
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    state: Arc<Mutex<MetricsState>>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Copy the current latency histograms.
+    pub fn latency(&self) -> MetricsSnapshot {
+        self.lock().latency.clone()
+    }
+
+    /// Latency of a single endpoint, if it has been called.
+    pub fn endpoint_latency(&self, path: &str) -> Option<EndpointLatency> {
+        self.lock().latency.endpoints.get(path).cloned()
+    }
+
+    /// Every Kraken error code returned so far, keyed by `error_code`.
+    pub fn error_counts(&self) -> BTreeMap<String, ErrorCount> {
+        self.lock().errors.clone()
+    }
+
+    /// How often `code` (e.g. `"EOrder:Insufficient funds"`) has been
+    /// returned, if at all.
+    pub fn error_count(&self, code: &str) -> Option<ErrorCount> {
+        self.lock().errors.get(error_code(code)).cloned()
+    }
+
+    /// Calls per caller and endpoint in `bucket`-wide buckets, over the last
+    /// `USAGE_RETENTION`.
+    pub fn usage(&self, bucket: UsageBucket) -> UsageReport {
+        let width = bucket.duration().as_secs() / 60;
+        let mut buckets: BTreeMap<(u64, &str, &str), u64> = BTreeMap::new();
+        let state = self.lock();
+        for ((minute, caller, endpoint), calls) in &state.usage {
+            let start = minute - minute % width;
+            *buckets.entry((start, caller, endpoint)).or_insert(0) += calls;
+        }
+        let rows = buckets
+            .into_iter()
+            .map(|((start, caller, endpoint), calls)| UsageRow {
+                start: UNIX_EPOCH + Duration::from_secs(start * 60),
+                caller: caller.to_string(),
+                endpoint: endpoint.to_string(),
+                calls,
+            })
+            .collect();
+        UsageReport { bucket, rows }
+    }
+
+    /// Forget everything recorded so far.
+    pub fn reset(&self) {
+        *self.lock() = MetricsState::default();
+    }
+
+    pub(crate) fn record_connect(&self, elapsed: Duration) {
+        self.lock().latency.connect.record(elapsed);
+    }
+
+    /// Time one request to `path`, and count it for `caller` in the minute
+    /// of `at`.
+    pub(crate) fn record_request(
+        &self,
+        path: &str,
+        caller: &str,
+        at: SystemTime,
+        ttfb: Option<Duration>,
+        total: Duration,
+    ) {
+        let mut state = self.lock();
+        let minute = at.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() / 60;
+        let oldest = minute.saturating_sub(USAGE_RETENTION.as_secs() / 60);
+        if matches!(state.usage.first_key_value(), Some(((first, _, _), _)) if *first < oldest) {
+            let kept = (oldest, String::new(), String::new());
+            state.usage = state.usage.split_off(&kept);
+        }
+        let key = (minute, caller.to_string(), path.to_string());
+        *state.usage.entry(key).or_insert(0) += 1;
+        let endpoint = state
+            .latency
+            .endpoints
+            .entry(path.to_string())
+            .or_default();
+        if let Some(ttfb) = ttfb {
+            endpoint.ttfb.record(ttfb);
+        }
+        endpoint.total.record(total);
+    }
+
+    /// Count the entries of a response's `error` array.
+    pub(crate) fn record_kraken_errors(&self, errors: &[String]) {
+        let now = SystemTime::now();
+        let mut state = self.lock();
+        for message in errors {
+            let code = error_code(message);
+            let count = match state.errors.get_mut(code) {
+                Some(seen) => {
+                    seen.count += 1;
+                    seen.last_seen = now;
+                    seen.count
+                }
+                None => {
+                    state.errors.insert(
+                        code.to_string(),
+                        ErrorCount {
+                            count: 1,
+                            first_seen: now,
+                            last_seen: now,
+                        },
+                    );
+                    1
+                }
+            };
+            tracing::info!(code, count, message = %message, "Kraken returned an error");
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MetricsState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Connector layer that times connection establishment into `Metrics`.
+#[derive(Clone)]
+pub(crate) struct ConnectTimingLayer(pub Metrics);
+
+impl<S> tower_layer::Layer<S> for ConnectTimingLayer {
+    type Service = ConnectTiming<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ConnectTiming {
+            inner,
+            metrics: self.0.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct ConnectTiming<S> {
+    inner: S,
+    metrics: Metrics,
+}
+
+impl<S, R> tower_service::Service<R> for ConnectTiming<S>
+where
+    S: tower_service::Service<R>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: R) -> Self::Future {
+        let started = Instant::now();
+        let metrics = self.metrics.clone();
+        let connecting = self.inner.call(request);
+        Box::pin(async move {
+            let result = connecting.await;
+            if result.is_ok() {
+                metrics.record_connect(started.elapsed());
+            }
+            result
+        })
+    }
+}
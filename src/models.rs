@@ -1,6 +1,11 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::assets;
+use crate::exchange::Side;
+use crate::numeric::ExactNumber;
+use crate::order_flags::OrderFlags;
+
 //
 // ──────────────────────────────────────────────────────────────────────────────
 //   1. PUBLIC ENDPOINTS
@@ -22,6 +27,59 @@ pub struct SystemStatusResponse {
     pub timestamp: String,
 }
 
+impl SystemStatusResponse {
+    /// `status` as an `ExchangeState`, if it's one Kraken documents.
+    pub fn state(&self) -> Option<ExchangeState> {
+        ExchangeState::from_status(&self.status)
+    }
+}
+
+/// What Kraken's trading engine currently accepts, per `SystemStatus`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExchangeState {
+    /// Fully operational
+    Online,
+    /// Only post-only limit orders (and cancels) are accepted
+    PostOnly,
+    /// Existing orders can be cancelled; nothing new is accepted
+    CancelOnly,
+    /// Offline for maintenance
+    Maintenance,
+}
+
+impl ExchangeState {
+    /// Parse Kraken's "online" / "post_only" / "cancel_only" / "maintenance".
+    pub fn from_status(status: &str) -> Option<Self> {
+        match status {
+            "online" => Some(ExchangeState::Online),
+            "post_only" => Some(ExchangeState::PostOnly),
+            "cancel_only" => Some(ExchangeState::CancelOnly),
+            "maintenance" => Some(ExchangeState::Maintenance),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ExchangeState::Online => "online",
+            ExchangeState::PostOnly => "post_only",
+            ExchangeState::CancelOnly => "cancel_only",
+            ExchangeState::Maintenance => "maintenance",
+        }
+    }
+
+    /// `true` if new orders (or, in `PostOnly`, post-only limit orders) are accepted.
+    pub fn accepts_orders(self) -> bool {
+        matches!(self, ExchangeState::Online | ExchangeState::PostOnly)
+    }
+}
+
+impl std::fmt::Display for ExchangeState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 /// /0/public/Assets
 ///
 /// The result is a map from asset symbol (e.g. "ADA") to its info.
@@ -66,6 +124,9 @@ pub struct AssetPairInfo {
     /// Number of lot decimal places
     pub lot_decimals: u32,
 
+    /// Number of cost (quote currency) decimal places
+    pub cost_decimals: Option<u32>,
+
     /// Multiplicator for lot volume. Usually 1
     pub lot_multiplier: u32,
 
@@ -76,9 +137,11 @@ pub struct AssetPairInfo {
     pub leverage_sell: Option<Vec<u32>>,
 
     /// Tiers for fees, as arrays of [volume, percentFee]
+    #[serde(deserialize_with = "crate::numeric::de_f64_tiers")]
     pub fees: Vec<Vec<f64>>,
 
     /// Tiers for maker fees, if any
+    #[serde(default, deserialize_with = "crate::numeric::de_opt_f64_tiers")]
     pub fees_maker: Option<Vec<Vec<f64>>>,
 
     /// Volume currency for calculating fees
@@ -149,16 +212,105 @@ pub struct TickerInfo {
 /// {
 ///   "error": [],
 ///   "result": {
-///     "XBTUSD": [
+///     "XXBTZUSD": [
 ///       [time, open, high, low, close, vwap, volume, count], ...
 ///     ],
 ///     "last": 123456789
 ///   }
 /// }
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(try_from = "HashMap<String, serde_json::Value>")]
 pub struct OhlcDataResponse {
+    /// Candles keyed by the pair name Kraken uses (e.g. "XXBTZUSD" when
+    /// asked for "XBTUSD"), oldest first
     #[serde(flatten)]
-    pub result: HashMap<String, serde_json::Value>,
+    pub candles: HashMap<String, Vec<OhlcCandle>>,
+    /// Cursor to pass as `since` for the candles after these; the last one
+    /// returned may still be open and come again
+    pub last: u64,
+}
+
+impl TryFrom<HashMap<String, serde_json::Value>> for OhlcDataResponse {
+    type Error = serde_json::Error;
+
+    fn try_from(mut result: HashMap<String, serde_json::Value>) -> Result<Self, Self::Error> {
+        let last = take_last(&mut result)?;
+        Ok(Self {
+            candles: rows_by_pair(result)?,
+            last,
+        })
+    }
+}
+
+/// Remove the `last` cursor from a pair-keyed result, whether Kraken sent it
+/// as a string or a number.
+fn take_last(result: &mut HashMap<String, serde_json::Value>) -> serde_json::Result<u64> {
+    let last = match result.remove("last") {
+        Some(serde_json::Value::String(last)) => last.parse().ok(),
+        Some(serde_json::Value::Number(last)) => last.as_u64(),
+        _ => None,
+    };
+    last.ok_or_else(|| serde::de::Error::custom("missing or invalid `last`"))
+}
+
+/// Parse the rest of a pair-keyed result, each pair's rows into `T`s.
+fn rows_by_pair<T: serde::de::DeserializeOwned>(
+    result: HashMap<String, serde_json::Value>,
+) -> serde_json::Result<HashMap<String, Vec<T>>> {
+    result
+        .into_iter()
+        .map(|(pair, rows)| Ok((pair, serde_json::from_value(rows)?)))
+        .collect()
+}
+
+/// One `[time, open, high, low, close, vwap, volume, count]` row of
+/// `OhlcDataResponse`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(from = "OhlcRow", into = "OhlcRow")]
+pub struct OhlcCandle {
+    /// Unix time the candle starts at
+    pub time: u64,
+    pub open: String,
+    pub high: String,
+    pub low: String,
+    pub close: String,
+    /// Volume-weighted average price
+    pub vwap: String,
+    pub volume: String,
+    /// Number of trades
+    pub count: u64,
+}
+
+type OhlcRow = (u64, String, String, String, String, String, String, u64);
+
+impl From<OhlcRow> for OhlcCandle {
+    fn from((time, open, high, low, close, vwap, volume, count): OhlcRow) -> Self {
+        Self {
+            time,
+            open,
+            high,
+            low,
+            close,
+            vwap,
+            volume,
+            count,
+        }
+    }
+}
+
+impl From<OhlcCandle> for OhlcRow {
+    fn from(candle: OhlcCandle) -> Self {
+        (
+            candle.time,
+            candle.open,
+            candle.high,
+            candle.low,
+            candle.close,
+            candle.vwap,
+            candle.volume,
+            candle.count,
+        )
+    }
 }
 
 /// /0/public/Depth
@@ -175,28 +327,146 @@ pub struct OrderBookResponse {
 ///   "asks": [[price, volume, timestamp], ...],
 ///   "bids": [[price, volume, timestamp], ...]
 /// }
+/// where price and volume are strings and timestamp is an integer.
 #[derive(Debug, Deserialize, Serialize)]
 pub struct OrderBookData {
-    pub asks: Vec<[String; 3]>,
-    pub bids: Vec<[String; 3]>,
+    pub asks: Vec<(String, String, u64)>,
+    pub bids: Vec<(String, String, u64)>,
 }
 
 /// /0/public/Trades
 ///
-/// Maps pair => list of trades, plus "last" => last trade timestamp
-#[derive(Debug, Deserialize, Serialize)]
+/// Maps pair => list of trades, plus "last" => cursor for the next page
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(try_from = "HashMap<String, serde_json::Value>")]
 pub struct TradesResponse {
+    /// Trades keyed by the pair name Kraken uses, oldest first
     #[serde(flatten)]
-    pub trades: HashMap<String, serde_json::Value>,
+    pub trades: HashMap<String, Vec<PublicTrade>>,
+    /// Cursor (a timestamp in nanoseconds) to pass as `since` for the trades
+    /// after these
+    pub last: u64,
+}
+
+impl TryFrom<HashMap<String, serde_json::Value>> for TradesResponse {
+    type Error = serde_json::Error;
+
+    fn try_from(mut result: HashMap<String, serde_json::Value>) -> Result<Self, Self::Error> {
+        let last = take_last(&mut result)?;
+        Ok(Self {
+            trades: rows_by_pair(result)?,
+            last,
+        })
+    }
+}
+
+/// One `[price, volume, time, buy/sell, market/limit, miscellaneous,
+/// trade_id]` row of `TradesResponse`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(try_from = "TradeRow", into = "TradeRow")]
+pub struct PublicTrade {
+    pub price: String,
+    pub volume: String,
+    /// Unix time with sub-second digits, exact with `arbitrary-precision`
+    pub time: ExactNumber,
+    /// The taker's side
+    pub side: Side,
+    /// "m" (market) or "l" (limit)
+    pub ordertype: String,
+    pub misc: String,
+    pub trade_id: u64,
+}
+
+type TradeRow = (String, String, ExactNumber, String, String, String, u64);
+
+impl TryFrom<TradeRow> for PublicTrade {
+    type Error = String;
+
+    fn try_from(row: TradeRow) -> Result<Self, Self::Error> {
+        let (price, volume, time, side, ordertype, misc, trade_id) = row;
+        let side = match side.as_str() {
+            "b" => Side::Buy,
+            "s" => Side::Sell,
+            _ => return Err(format!("invalid trade side: {side}")),
+        };
+        Ok(Self {
+            price,
+            volume,
+            time,
+            side,
+            ordertype,
+            misc,
+            trade_id,
+        })
+    }
+}
+
+impl From<PublicTrade> for TradeRow {
+    fn from(trade: PublicTrade) -> Self {
+        let side = match trade.side {
+            Side::Buy => "b",
+            Side::Sell => "s",
+        };
+        (
+            trade.price,
+            trade.volume,
+            trade.time,
+            side.to_string(),
+            trade.ordertype,
+            trade.misc,
+            trade.trade_id,
+        )
+    }
 }
 
 /// /0/public/Spread
 ///
 /// Maps pair => list of spreads, plus "last" => last timestamp
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(try_from = "HashMap<String, serde_json::Value>")]
 pub struct SpreadsResponse {
+    /// Spreads keyed by the pair name Kraken uses, oldest first
     #[serde(flatten)]
-    pub spreads: HashMap<String, serde_json::Value>,
+    pub spreads: HashMap<String, Vec<SpreadEntry>>,
+    /// Cursor to pass as `since` for the spreads after these
+    pub last: u64,
+}
+
+impl TryFrom<HashMap<String, serde_json::Value>> for SpreadsResponse {
+    type Error = serde_json::Error;
+
+    fn try_from(mut result: HashMap<String, serde_json::Value>) -> Result<Self, Self::Error> {
+        let last = take_last(&mut result)?;
+        Ok(Self {
+            spreads: rows_by_pair(result)?,
+            last,
+        })
+    }
+}
+
+/// One `[time, bid, ask]` row of `SpreadsResponse`: the best bid and ask
+/// at `time`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(from = "SpreadRow", into = "SpreadRow")]
+pub struct SpreadEntry {
+    /// Unix time
+    pub time: u64,
+    pub bid: String,
+    pub ask: String,
+}
+
+type SpreadRow = (u64, String, String);
+
+impl From<SpreadRow> for SpreadEntry {
+    fn from((time, bid, ask): SpreadRow) -> Self {
+        Self { time, bid, ask }
+    }
+}
+
+impl From<SpreadEntry> for SpreadRow {
+    fn from(entry: SpreadEntry) -> Self {
+        (entry.time, entry.bid, entry.ask)
+    }
 }
 
 //
@@ -215,6 +485,14 @@ pub struct AccountBalanceResponse {
     pub balances: HashMap<String, String>,
 }
 
+impl AccountBalanceResponse {
+    /// The balances keyed by display symbol ("BTC", "USD") instead of Kraken's
+    /// asset codes ("XXBT", "ZUSD"); see `assets::display_name`.
+    pub fn by_display_name(&self) -> HashMap<String, String> {
+        display_keys(&self.balances)
+    }
+}
+
 /// /0/private/BalanceEx
 ///
 /// Typically same format as /Balance, but extended. If additional fields appear, you can add them here.
@@ -224,6 +502,20 @@ pub struct ExtendedBalanceResponse {
     pub balances: HashMap<String, String>,
 }
 
+impl ExtendedBalanceResponse {
+    /// The balances keyed by display symbol; see `AccountBalanceResponse::by_display_name`.
+    pub fn by_display_name(&self) -> HashMap<String, String> {
+        display_keys(&self.balances)
+    }
+}
+
+fn display_keys(balances: &HashMap<String, String>) -> HashMap<String, String> {
+    balances
+        .iter()
+        .map(|(code, amount)| (assets::display_name(code), amount.clone()))
+        .collect()
+}
+
 /// /0/private/TradeBalance
 ///
 /// Fields documented at:
@@ -270,17 +562,23 @@ pub struct ClosedOrdersResponse {
 }
 
 /// Common structure for describing an order in open/closed orders
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct OrderInfo {
     pub refid: Option<String>,
     pub userref: Option<u64>,
+    /// Client order ID the order was placed with, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cl_ord_id: Option<String>,
     /// "pending", "open", "closed", "canceled", "expired"
     pub status: String,
     /// Unix timestamp when order was placed
+    #[serde(deserialize_with = "crate::numeric::de_f64")]
     pub opentm: f64,
     /// Unix timestamp for order start time (if set)
+    #[serde(deserialize_with = "crate::numeric::de_f64")]
     pub starttm: f64,
     /// Unix timestamp for order end time (if set)
+    #[serde(deserialize_with = "crate::numeric::de_f64")]
     pub expiretm: f64,
     /// The order description
     pub descr: OrderDescription,
@@ -308,8 +606,15 @@ pub struct OrderInfo {
     pub reason: Option<String>,
 }
 
+impl OrderInfo {
+    /// `oflags` parsed into `OrderFlags`; unknown flag names are skipped.
+    pub fn flags(&self) -> OrderFlags {
+        OrderFlags::from_oflags_lossy(&self.oflags)
+    }
+}
+
 /// Detailed order description
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct OrderDescription {
     /// The trading pair (e.g. "XBTUSD")
     pub pair: String,
@@ -366,6 +671,7 @@ pub struct TradeInfo {
     /// The pair traded (e.g. "XBTUSD")
     pub pair: String,
     /// Unix timestamp of execution
+    #[serde(deserialize_with = "crate::numeric::de_f64")]
     pub time: f64,
     /// "buy" or "sell"
     #[serde(rename = "type")]
@@ -427,6 +733,7 @@ pub struct PositionInfo {
     pub margin: String,
     /// Some positions might include "terms", "rollover_time", "misc", etc.
     pub terms: Option<String>,
+    #[serde(default, deserialize_with = "crate::numeric::de_opt_f64")]
     pub rollover_time: Option<f64>,
     pub misc: Option<String>,
 }
@@ -446,10 +753,11 @@ pub struct QueryLedgersResponse {
 }
 
 /// Detailed ledger info
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct LedgerInfo {
     pub refid: String,
     /// Unix timestamp
+    #[serde(deserialize_with = "crate::numeric::de_f64")]
     pub time: f64,
     /// e.g. "trade", "withdrawal", "deposit", etc.
     #[serde(rename = "type")]
@@ -467,6 +775,13 @@ pub struct LedgerInfo {
     pub balance: String,
 }
 
+impl LedgerInfo {
+    /// `asset` as a display symbol, e.g. "BTC" for "XXBT".
+    pub fn display_asset(&self) -> String {
+        assets::display_name(&self.asset)
+    }
+}
+
 /// /0/private/TradeVolume
 #[derive(Debug, Deserialize, Serialize)]
 pub struct TradeVolumeResponse {
@@ -523,8 +838,11 @@ pub struct ExportReportStatus {
     /// "Queued", "Processing", "Finished", "Error", etc.
     pub status: String,
     /// Unix timestamp
+    #[serde(deserialize_with = "crate::numeric::de_f64")]
     pub createdtm: f64,
+    #[serde(default, deserialize_with = "crate::numeric::de_opt_f64")]
     pub finishtm: Option<f64>,
+    #[serde(default, deserialize_with = "crate::numeric::de_opt_f64")]
     pub starttm: Option<f64>,
     pub totalrows: Option<u64>,
     pub refid: Option<String>,
@@ -661,6 +979,40 @@ pub struct DepositMethod {
     pub fee: String,
     /// "AddressSetupOptions" or other
     pub gen_address: bool,
+    /// Minimum deposit amount
+    #[serde(default)]
+    pub minimum: Option<String>,
+    /// One-off fee for generating a new address, if any
+    #[serde(rename = "address-setup-fee", default)]
+    pub address_setup_fee: Option<String>,
+    /// Chain the method deposits over, e.g. "Ethereum" (ERC-20) or "Solana" (SPL)
+    #[serde(default)]
+    pub network: Option<String>,
+}
+
+impl DepositMethod {
+    /// `true` if this method deposits over `network` (case-insensitive). Methods
+    /// without a `network` field are matched on their `method` name instead.
+    pub fn on_network(&self, network: &str) -> bool {
+        self.network
+            .as_deref()
+            .unwrap_or(&self.method)
+            .eq_ignore_ascii_case(network)
+    }
+}
+
+impl DepositMethodsResponse {
+    /// The method that deposits over `network`; see `DepositMethod::on_network`.
+    pub fn for_network(&self, network: &str) -> Option<&DepositMethod> {
+        self.0.iter().find(|m| m.on_network(network))
+    }
+
+    /// The networks the asset can be deposited over, in Kraken's order.
+    pub fn networks(&self) -> impl Iterator<Item = &str> {
+        self.0
+            .iter()
+            .map(|m| m.network.as_deref().unwrap_or(&m.method))
+    }
 }
 
 /// /0/private/DepositAddresses
@@ -688,6 +1040,9 @@ pub struct DepositStatusItem {
     pub amount: String,
     pub fee: Option<String>,
     pub time: u64,
+    /// Extra state, e.g. "return" or "onhold"
+    #[serde(rename = "status-prop", default, skip_serializing_if = "Option::is_none")]
+    pub status_prop: Option<String>,
 }
 
 /// /0/private/WithdrawalMethods
@@ -700,18 +1055,54 @@ pub struct WithdrawalMethod {
     pub limit: bool,
     pub fee: String,
     pub gen_address: bool,
+    #[serde(default)]
+    pub asset: Option<String>,
+    /// Minimum withdrawal amount
+    #[serde(default)]
+    pub minimum: Option<String>,
+    /// Chain the method withdraws over, e.g. "Ethereum" (ERC-20) or "Solana" (SPL)
+    #[serde(default)]
+    pub network: Option<String>,
+}
+
+impl WithdrawalMethod {
+    /// `true` if this method withdraws over `network` (case-insensitive). Methods
+    /// without a `network` field are matched on their `method` name instead.
+    pub fn on_network(&self, network: &str) -> bool {
+        self.network
+            .as_deref()
+            .unwrap_or(&self.method)
+            .eq_ignore_ascii_case(network)
+    }
+}
+
+impl WithdrawalMethodsResponse {
+    /// The method that withdraws over `network`; see `WithdrawalMethod::on_network`.
+    pub fn for_network(&self, network: &str) -> Option<&WithdrawalMethod> {
+        self.0.iter().find(|m| m.on_network(network))
+    }
 }
 
 /// /0/private/WithdrawalAddresses
 #[derive(Debug, Deserialize, Serialize)]
 pub struct WithdrawalAddressesResponse(pub Vec<WithdrawalAddressItem>);
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct WithdrawalAddressItem {
     pub address: String,
     pub new: Option<bool>,
     pub name: Option<String>,
     pub fee: Option<String>,
+    /// The withdrawal key (address name) passed to `Withdraw` as `key`
+    #[serde(default)]
+    pub key: Option<String>,
+    #[serde(default)]
+    pub asset: Option<String>,
+    #[serde(default)]
+    pub method: Option<String>,
+    /// Whether the address has been confirmed by email
+    #[serde(default)]
+    pub verified: Option<bool>,
 }
 
 /// /0/private/WithdrawalInformation
@@ -749,6 +1140,9 @@ pub struct WithdrawalStatusItem {
     pub status: String,
     pub fee: String,
     pub time: u64,
+    /// Extra state, e.g. "cancel-pending", "canceled" or "onhold"
+    #[serde(rename = "status-prop", default, skip_serializing_if = "Option::is_none")]
+    pub status_prop: Option<String>,
 }
 
 /// /0/private/WithdrawCancel
@@ -893,6 +1287,54 @@ pub struct StakingTransaction {
     pub reward: Option<String>,
 }
 
+//
+// ──────────────────────────────────────────────────────────────────────────────
+//   6. OTC
+//   (CreateOtcQuoteRequest, UpdateOtcQuote, GetOtcActiveQuotes)
+// ──────────────────────────────────────────────────────────────────────────────
+//
+
+/// A firm price from the OTC desk for one block trade.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct OtcQuote {
+    pub quote_id: String,
+    pub pair: String,
+    /// "buy" or "sell"
+    #[serde(rename = "type")]
+    pub side: String,
+    /// Quantity in base currency
+    pub volume: String,
+    pub price: String,
+    /// Total in quote currency
+    pub cost: String,
+    /// Unix timestamp after which the quote can no longer be accepted
+    pub expires: u64,
+    /// "pending", "accepted", "rejected", "expired" or "settled"
+    pub status: String,
+}
+
+/// /0/private/CreateOtcQuoteRequest
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CreateOtcQuoteResponse {
+    pub quote: OtcQuote,
+}
+
+/// /0/private/UpdateOtcQuote
+#[derive(Debug, Deserialize, Serialize)]
+pub struct UpdateOtcQuoteResponse {
+    pub quote_id: String,
+    /// "accepted" or "rejected"
+    pub status: String,
+    /// Trade ID of the settled block, once accepted
+    pub txid: Option<String>,
+}
+
+/// /0/private/GetOtcActiveQuotes
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GetOtcActiveQuotesResponse {
+    pub quotes: Vec<OtcQuote>,
+}
+
 //
 // ──────────────────────────────────────────────────────────────────────────────
 //   END OF MODELS
@@ -0,0 +1,461 @@
+use std::fmt;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::error::{KrakenError, KrakenResult};
+
+/// A decimal amount as Kraken sends it (`"30300.10000"`, `"-0.00067643"`),
+/// borrowed from a model's string field for conversion into a numeric type.
+///
+/// Conversions into `rust_decimal::Decimal` (feature `decimal`) and
+/// `bigdecimal::BigDecimal` (feature `bigdecimal`) are exact and available as
+/// both `TryFrom` and methods. There is deliberately no `TryFrom` into `f64`:
+/// use `to_f64_lossy` or `Lossy<f64>`, so rounding is visible at the call site.
+///
+/// ```
+/// use onise::numeric::Amount;
+///
+/// let volume = String::from("0.00067643");
+/// let lossy = Amount::from(&volume).to_f64_lossy().unwrap();
+/// assert_eq!(lossy, 0.00067643);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Amount<'a>(pub &'a str);
+
+impl<'a> Amount<'a> {
+    pub fn new(value: &'a str) -> Self {
+        Amount(value)
+    }
+
+    pub fn as_str(&self) -> &'a str {
+        self.0
+    }
+
+    /// The nearest `f64`. Most Kraken amounts (8 decimal places, up to
+    /// billions) don't round-trip exactly through `f64`.
+    pub fn to_f64_lossy(self) -> KrakenResult<f64> {
+        match self.0.trim().parse::<f64>() {
+            Ok(value) if value.is_finite() => Ok(value),
+            _ => Err(self.invalid()),
+        }
+    }
+
+    /// Exact conversion into a `rust_decimal::Decimal` (28 significant digits).
+    #[cfg(feature = "decimal")]
+    pub fn to_decimal(self) -> KrakenResult<rust_decimal::Decimal> {
+        let value = self.0.trim();
+        let parsed = if value.contains(['e', 'E']) {
+            rust_decimal::Decimal::from_scientific(value)
+        } else {
+            value.parse()
+        };
+        parsed.map_err(|_| self.invalid())
+    }
+
+    /// Exact conversion into a `bigdecimal::BigDecimal` (arbitrary precision).
+    #[cfg(feature = "bigdecimal")]
+    pub fn to_bigdecimal(self) -> KrakenResult<bigdecimal::BigDecimal> {
+        self.0.trim().parse().map_err(|_| self.invalid())
+    }
+
+    fn invalid(self) -> KrakenError {
+        KrakenError::InvalidAmount(self.0.to_string())
+    }
+}
+
+impl<'a> From<&'a str> for Amount<'a> {
+    fn from(value: &'a str) -> Self {
+        Amount(value)
+    }
+}
+
+impl<'a> From<&'a String> for Amount<'a> {
+    fn from(value: &'a String) -> Self {
+        Amount(value)
+    }
+}
+
+impl fmt::Display for Amount<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+/// Marks a value produced by a conversion that may round, e.g.
+/// `Lossy::<f64>::try_from(Amount("0.1"))`.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Lossy<T>(pub T);
+
+impl<T> Lossy<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl TryFrom<Amount<'_>> for Lossy<f64> {
+    type Error = KrakenError;
+
+    fn try_from(amount: Amount<'_>) -> KrakenResult<Self> {
+        amount.to_f64_lossy().map(Lossy)
+    }
+}
+
+#[cfg(feature = "decimal")]
+impl TryFrom<Amount<'_>> for rust_decimal::Decimal {
+    type Error = KrakenError;
+
+    fn try_from(amount: Amount<'_>) -> KrakenResult<Self> {
+        amount.to_decimal()
+    }
+}
+
+#[cfg(feature = "bigdecimal")]
+impl TryFrom<Amount<'_>> for bigdecimal::BigDecimal {
+    type Error = KrakenError;
+
+    fn try_from(amount: Amount<'_>) -> KrakenResult<Self> {
+        amount.to_bigdecimal()
+    }
+}
+
+/// A JSON number kept as the text Kraken sent (`1688666559.8974`,
+/// `18446744073709551616`), for fields that overflow `u64` or lose digits as
+/// `f64`. Also accepts the number as a JSON string.
+///
+/// Only exact with the `arbitrary-precision` feature: without it `serde_json`
+/// parses numbers into `u64` / `i64` / `f64` before this sees them, and the
+/// text is that value's shortest representation.
+///
+/// ```
+/// use onise::numeric::ExactNumber;
+///
+/// let volume: ExactNumber = serde_json::from_str("\"0.00067643\"").unwrap();
+/// assert_eq!(volume.as_str(), "0.00067643");
+/// assert_eq!(volume.amount().to_f64_lossy().unwrap(), 0.00067643);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ExactNumber(String);
+
+impl ExactNumber {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The number as an `Amount`, for conversion into a numeric type.
+    pub fn amount(&self) -> Amount<'_> {
+        Amount(&self.0)
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        self.0.parse().ok()
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        self.0.parse().ok()
+    }
+}
+
+impl fmt::Display for ExactNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::str::FromStr for ExactNumber {
+    type Err = KrakenError;
+
+    fn from_str(value: &str) -> KrakenResult<Self> {
+        let value = value.trim();
+        value
+            .parse::<serde_json::Number>()
+            .map(|_| ExactNumber(value.to_string()))
+            .map_err(|_| KrakenError::InvalidAmount(value.to_string()))
+    }
+}
+
+impl<'de> Deserialize<'de> for ExactNumber {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Number(serde_json::Number),
+            Text(String),
+        }
+        match Raw::deserialize(deserializer)? {
+            Raw::Number(number) => Ok(ExactNumber(number.to_string())),
+            Raw::Text(text) => text.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// Serialized as a JSON number: exact with `arbitrary-precision`, otherwise
+/// through `f64` / `u64` / `i64` like any other number.
+impl Serialize for ExactNumber {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let number: serde_json::Number = self.0.parse().map_err(serde::ser::Error::custom)?;
+        number.serialize(serializer)
+    }
+}
+
+/// A price or volume as the order builders (`params::AddOrderRequest`,
+/// `exchange::OrderRequest`, ...) take it. Text is used as given; numbers are
+/// written in plain decimal notation, never `1e-7`: `f64` with the fewest
+/// digits that read back as the same value, `Decimal` / `BigDecimal` exactly.
+///
+/// ```
+/// use onise::numeric::IntoAmount;
+///
+/// assert_eq!(0.0000001.into_amount(), "0.0000001");
+/// assert_eq!(30_000.0.into_amount(), "30000");
+/// assert_eq!("0.25".into_amount(), "0.25");
+/// ```
+pub trait IntoAmount {
+    fn into_amount(self) -> String;
+}
+
+impl IntoAmount for String {
+    fn into_amount(self) -> String {
+        self
+    }
+}
+
+impl IntoAmount for &str {
+    fn into_amount(self) -> String {
+        self.to_string()
+    }
+}
+
+impl IntoAmount for &String {
+    fn into_amount(self) -> String {
+        self.clone()
+    }
+}
+
+// `Display` for floats never switches to exponent notation.
+impl IntoAmount for f64 {
+    fn into_amount(self) -> String {
+        self.to_string()
+    }
+}
+
+impl IntoAmount for f32 {
+    fn into_amount(self) -> String {
+        self.to_string()
+    }
+}
+
+impl IntoAmount for u64 {
+    fn into_amount(self) -> String {
+        self.to_string()
+    }
+}
+
+impl IntoAmount for u32 {
+    fn into_amount(self) -> String {
+        self.to_string()
+    }
+}
+
+#[cfg(feature = "decimal")]
+impl IntoAmount for rust_decimal::Decimal {
+    fn into_amount(self) -> String {
+        self.to_string()
+    }
+}
+
+#[cfg(feature = "bigdecimal")]
+impl IntoAmount for bigdecimal::BigDecimal {
+    fn into_amount(self) -> String {
+        self.to_plain_string()
+    }
+}
+
+/// How `round_amount` drops digits past the allowed precision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    /// Toward zero, so a volume never exceeds what was asked for
+    Down,
+    /// To the nearest, halves away from zero
+    Nearest,
+}
+
+/// `value` with at most `decimals` fraction digits, in the plain notation
+/// Kraken expects: no exponent, no trailing zeros. A leading `+`/`-` (as in
+/// a price offset) is kept, and exponent input (`1.5e-5`) is accepted.
+/// Exact: the digits are rounded as text, never through `f64`.
+///
+/// ```
+/// use onise::numeric::{round_amount, Rounding};
+///
+/// assert_eq!(round_amount("0.123456789", 8, Rounding::Down).unwrap(), "0.12345678");
+/// assert_eq!(round_amount("30000.05", 1, Rounding::Nearest).unwrap(), "30000.1");
+/// assert_eq!(round_amount("1.5e-5", 8, Rounding::Down).unwrap(), "0.000015");
+/// assert_eq!(round_amount("+2.50", 2, Rounding::Down).unwrap(), "+2.5");
+/// ```
+pub fn round_amount(value: &str, decimals: u32, rounding: Rounding) -> KrakenResult<String> {
+    let invalid = || KrakenError::InvalidAmount(value.to_string());
+    let text = value.trim();
+    let (sign, text) = match text.as_bytes().first() {
+        Some(b'+') | Some(b'-') => text.split_at(1),
+        _ => ("", text),
+    };
+    let (mantissa, exponent) = match text.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<i32>().map_err(|_| invalid())?),
+        None => (text, 0),
+    };
+    let (whole, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits: Vec<u8> = whole.bytes().chain(frac.bytes()).collect();
+    if digits.is_empty() || !digits.iter().all(u8::is_ascii_digit) || exponent.abs() > 1000 {
+        return Err(invalid());
+    }
+
+    // Lay the digits out as whole part and `decimals` fraction digits
+    let point = whole.len() as i64 + exponent as i64;
+    let (mut padded, point) = match usize::try_from(point) {
+        Ok(point) if point > 0 => (Vec::new(), point),
+        // Below one: a zero whole part, then zeros down to the first digit
+        _ => (vec![b'0'; (1 - point) as usize], 1),
+    };
+    padded.extend_from_slice(&digits);
+    let kept_len = point + decimals as usize;
+    if padded.len() < kept_len {
+        padded.resize(kept_len, b'0');
+    }
+    let round_up =
+        rounding == Rounding::Nearest && padded.get(kept_len).is_some_and(|d| *d >= b'5');
+    padded.truncate(kept_len);
+    if round_up {
+        let mut carry = true;
+        for digit in padded.iter_mut().rev() {
+            if *digit == b'9' {
+                *digit = b'0';
+            } else {
+                *digit += 1;
+                carry = false;
+                break;
+            }
+        }
+        if carry {
+            padded.insert(0, b'1');
+        }
+    }
+
+    let point = padded.len() - decimals as usize;
+    let whole = std::str::from_utf8(&padded[..point]).expect("ASCII digits");
+    let whole = match whole.trim_start_matches('0') {
+        "" => "0",
+        trimmed => trimmed,
+    };
+    let frac = std::str::from_utf8(&padded[point..]).expect("ASCII digits");
+    let frac = frac.trim_end_matches('0');
+    let sign = if sign == "-" && whole == "0" && frac.is_empty() {
+        ""
+    } else {
+        sign
+    };
+    Ok(match frac {
+        "" => format!("{sign}{whole}"),
+        frac => format!("{sign}{whole}.{frac}"),
+    })
+}
+
+/// How many decimals a pair's prices and volumes may carry, from its
+/// `AssetPairs` entry (`pair_decimals`, `lot_decimals`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PairPrecision {
+    pub price_decimals: u32,
+    pub volume_decimals: u32,
+}
+
+impl From<&crate::models::AssetPairInfo> for PairPrecision {
+    fn from(info: &crate::models::AssetPairInfo) -> Self {
+        Self {
+            price_decimals: info.pair_decimals,
+            volume_decimals: info.lot_decimals,
+        }
+    }
+}
+
+/// Writes a pair's prices and volumes the way Kraken accepts them: plain
+/// notation, trimmed to the pair's `PairPrecision`. Volumes round down by
+/// default (never more than asked for), prices to the nearest tick.
+///
+/// Set on a client with `with_amount_formatter`, it is applied to every
+/// typed `add` / `edit` request before sending.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AmountFormatter {
+    pub price: Rounding,
+    pub volume: Rounding,
+}
+
+impl Default for AmountFormatter {
+    fn default() -> Self {
+        Self {
+            price: Rounding::Nearest,
+            volume: Rounding::Down,
+        }
+    }
+}
+
+impl AmountFormatter {
+    /// `price` trimmed to the pair's price decimals. Percentage offsets
+    /// (`+1.5%`) aren't prices in quote currency and pass through unchanged.
+    pub fn price(&self, price: &str, precision: &PairPrecision) -> KrakenResult<String> {
+        if price.trim_end().ends_with('%') {
+            return Ok(price.to_string());
+        }
+        round_amount(price, precision.price_decimals, self.price)
+    }
+
+    /// `volume` trimmed to the pair's lot decimals.
+    pub fn volume(&self, volume: &str, precision: &PairPrecision) -> KrakenResult<String> {
+        round_amount(volume, precision.volume_decimals, self.volume)
+    }
+}
+
+/// An `f64` read through `serde_json::Number`, which also understands the
+/// form `arbitrary-precision` gives numbers buffered by `#[serde(flatten)]`
+/// and untagged enums (a plain `f64` field rejects it).
+struct JsonF64(f64);
+
+impl<'de> Deserialize<'de> for JsonF64 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let number = serde_json::Number::deserialize(deserializer)?;
+        match number.as_f64() {
+            Some(value) => Ok(JsonF64(value)),
+            None => Err(serde::de::Error::custom(format!("{number} is not an f64"))),
+        }
+    }
+}
+
+/// `deserialize_with` for `f64` model fields; see `JsonF64`.
+pub(crate) fn de_f64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    JsonF64::deserialize(deserializer).map(|JsonF64(value)| value)
+}
+
+/// `deserialize_with` for `Option<f64>` model fields (pair with `#[serde(default)]`).
+pub(crate) fn de_opt_f64<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<f64>, D::Error> {
+    Ok(Option::<JsonF64>::deserialize(deserializer)?.map(|JsonF64(value)| value))
+}
+
+/// `deserialize_with` for fee tiers (`[[volume, percent], ...]`).
+pub(crate) fn de_f64_tiers<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Vec<f64>>, D::Error> {
+    let tiers = Vec::<Vec<JsonF64>>::deserialize(deserializer)?;
+    Ok(tiers
+        .into_iter()
+        .map(|tier| tier.into_iter().map(|JsonF64(value)| value).collect())
+        .collect())
+}
+
+/// `de_f64_tiers` for optional tiers (pair with `#[serde(default)]`).
+pub(crate) fn de_opt_f64_tiers<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Vec<Vec<f64>>>, D::Error> {
+    #[derive(Deserialize)]
+    struct Tiers(#[serde(deserialize_with = "de_f64_tiers")] Vec<Vec<f64>>);
+    Ok(Option::<Tiers>::deserialize(deserializer)?.map(|Tiers(tiers)| tiers))
+}
@@ -0,0 +1,348 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::ws_models::{InstrumentData, OrderBookEntry, TradeData, WsBookMessage, WsTradesMessage};
+
+/// Price decimals used when the pair's own precision isn't given; enough for
+/// every Kraken spot pair.
+pub const DEFAULT_PRICE_DECIMALS: u32 = 10;
+
+/// Levels per side `BookAnalytics` looks at unless `with_analytics_levels`
+/// says otherwise.
+pub const DEFAULT_ANALYTICS_LEVELS: usize = 5;
+
+/// Figures derived from an `OrderBook`, recomputed after every message it
+/// applies so consumers don't each walk the book again.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BookAnalytics {
+    /// (bid quantity − ask quantity) / (bid quantity + ask quantity) over the
+    /// top analytics levels, from −1 (all asks) to 1 (all bids)
+    pub imbalance: Option<f64>,
+    /// Best bid and ask weighted by the quantity on the opposite side, so it
+    /// leans towards the side more likely to trade through
+    pub microprice: Option<f64>,
+    /// Midpoint of each side's quantity-weighted price over the top
+    /// analytics levels
+    pub depth_weighted_mid: Option<f64>,
+    /// Order-flow imbalance (Cont, Kukanov and Stoikov) summed over every
+    /// update since the last snapshot: quantity added at or above the best
+    /// bid and removed at or below the best ask count positive, the reverse
+    /// negative
+    pub order_flow_imbalance: f64,
+}
+
+/// A local level-2 book for one symbol, kept in sync from the WS `book` channel.
+///
+/// Snapshots replace the book; updates set a level's quantity, and a quantity of
+/// zero removes the level. The book is trimmed back to the subscribed `depth`
+/// after every message, as Kraken expects.
+///
+/// Levels are stored in `BTreeMap`s keyed by the price scaled to an integer
+/// (`price * 10^price_decimals`), so updates and best-level lookups are
+/// O(log n) and never compare floats. The original price and quantity strings
+/// are kept as-is for display.
+#[derive(Debug, Clone)]
+pub struct OrderBook {
+    pub symbol: String,
+    pub depth: usize,
+    price_decimals: u32,
+    bids: BTreeMap<u64, BookLevel>,
+    asks: BTreeMap<u64, BookLevel>,
+    analytics_levels: usize,
+    analytics: BookAnalytics,
+}
+
+/// One aggregated price level.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BookLevel {
+    pub price: String,
+    pub quantity: String,
+}
+
+impl OrderBook {
+    /// An empty book for `symbol`, trimmed to `depth` levels per side, using
+    /// `DEFAULT_PRICE_DECIMALS`.
+    pub fn new(symbol: impl Into<String>, depth: usize) -> Self {
+        Self {
+            symbol: symbol.into(),
+            depth,
+            price_decimals: DEFAULT_PRICE_DECIMALS,
+            bids: BTreeMap::new(),
+            asks: BTreeMap::new(),
+            analytics_levels: DEFAULT_ANALYTICS_LEVELS,
+            analytics: BookAnalytics::default(),
+        }
+    }
+
+    /// An empty book using the price precision from the pair's `instrument` entry.
+    pub fn for_instrument(instrument: &InstrumentData, depth: usize) -> Self {
+        let book = Self::new(instrument.symbol.clone(), depth);
+        match instrument.price_decimals {
+            Some(decimals) => book.with_price_decimals(decimals),
+            None => book,
+        }
+    }
+
+    /// Scale prices by `10^decimals` (the pair's price precision). Clears the book.
+    /// Prices with more significant decimals than this are dropped.
+    pub fn with_price_decimals(mut self, decimals: u32) -> Self {
+        self.price_decimals = decimals;
+        self.bids.clear();
+        self.asks.clear();
+        self.analytics = BookAnalytics::default();
+        self
+    }
+
+    /// Compute `imbalance` and `depth_weighted_mid` over the top `levels`
+    /// per side instead of `DEFAULT_ANALYTICS_LEVELS`.
+    pub fn with_analytics_levels(mut self, levels: usize) -> Self {
+        self.analytics_levels = levels.max(1);
+        self.refresh_analytics(0.0);
+        self
+    }
+
+    /// The analytics as of the last applied message.
+    pub fn analytics(&self) -> BookAnalytics {
+        self.analytics
+    }
+
+    /// The price precision the book is keyed with.
+    pub fn price_decimals(&self) -> u32 {
+        self.price_decimals
+    }
+
+    /// Apply a `book` message. Messages for other symbols are ignored.
+    pub fn apply(&mut self, msg: &WsBookMessage) {
+        if msg.symbol != self.symbol {
+            return;
+        }
+        let before = (top(&self.bids, true), top(&self.asks, false));
+        if msg.is_snapshot() {
+            self.bids.clear();
+            self.asks.clear();
+            self.analytics.order_flow_imbalance = 0.0;
+        }
+        Self::apply_side(&mut self.bids, &msg.bids, self.price_decimals);
+        Self::apply_side(&mut self.asks, &msg.asks, self.price_decimals);
+        self.truncate();
+        let flow = if msg.is_snapshot() {
+            0.0
+        } else {
+            order_flow(before, (top(&self.bids, true), top(&self.asks, false)))
+        };
+        self.refresh_analytics(flow);
+    }
+
+    fn refresh_analytics(&mut self, flow: f64) {
+        let levels = self.analytics_levels;
+        self.analytics = BookAnalytics {
+            imbalance: self.imbalance(levels),
+            microprice: self.microprice(),
+            depth_weighted_mid: self.depth_weighted_mid(levels),
+            order_flow_imbalance: self.analytics.order_flow_imbalance + flow,
+        };
+    }
+
+    /// Quantity imbalance over the top `levels` per side; see
+    /// `BookAnalytics::imbalance`.
+    pub fn imbalance(&self, levels: usize) -> Option<f64> {
+        let total = |side: Vec<(f64, f64)>| side.iter().map(|(_, q)| q).sum::<f64>();
+        let bid = total(parsed(self.iter_bids(), levels));
+        let ask = total(parsed(self.iter_asks(), levels));
+        (bid + ask > 0.0).then(|| (bid - ask) / (bid + ask))
+    }
+
+    /// See `BookAnalytics::microprice`.
+    pub fn microprice(&self) -> Option<f64> {
+        let (bid, bid_quantity) = *parsed(self.iter_bids(), 1).first()?;
+        let (ask, ask_quantity) = *parsed(self.iter_asks(), 1).first()?;
+        let total = bid_quantity + ask_quantity;
+        (total > 0.0).then(|| (bid * ask_quantity + ask * bid_quantity) / total)
+    }
+
+    /// See `BookAnalytics::depth_weighted_mid`.
+    pub fn depth_weighted_mid(&self, levels: usize) -> Option<f64> {
+        let weighted = |side: Vec<(f64, f64)>| {
+            let quantity: f64 = side.iter().map(|(_, q)| q).sum();
+            let notional: f64 = side.iter().map(|(p, q)| p * q).sum();
+            (quantity > 0.0).then(|| notional / quantity)
+        };
+        let bid = weighted(parsed(self.iter_bids(), levels))?;
+        let ask = weighted(parsed(self.iter_asks(), levels))?;
+        Some((bid + ask) / 2.0)
+    }
+
+    fn apply_side(side: &mut BTreeMap<u64, BookLevel>, entries: &[OrderBookEntry], decimals: u32) {
+        for entry in entries {
+            let Some(key) = scale_price(&entry.price, decimals) else {
+                tracing::warn!(price = %entry.price, decimals, "book price doesn't fit the pair precision");
+                continue;
+            };
+            if is_zero(&entry.quantity) {
+                side.remove(&key);
+            } else {
+                side.insert(
+                    key,
+                    BookLevel {
+                        price: entry.price.clone(),
+                        quantity: entry.quantity.clone(),
+                    },
+                );
+            }
+        }
+    }
+
+    fn truncate(&mut self) {
+        while self.bids.len() > self.depth {
+            self.bids.pop_first();
+        }
+        while self.asks.len() > self.depth {
+            self.asks.pop_last();
+        }
+    }
+
+    /// Bids, best (highest) first.
+    pub fn bids(&self) -> Vec<BookLevel> {
+        self.iter_bids().cloned().collect()
+    }
+
+    /// Asks, best (lowest) first.
+    pub fn asks(&self) -> Vec<BookLevel> {
+        self.iter_asks().cloned().collect()
+    }
+
+    /// Bids, best (highest) first, without copying.
+    pub fn iter_bids(&self) -> impl Iterator<Item = &BookLevel> {
+        self.bids.values().rev()
+    }
+
+    /// Asks, best (lowest) first, without copying.
+    pub fn iter_asks(&self) -> impl Iterator<Item = &BookLevel> {
+        self.asks.values()
+    }
+
+    /// Highest bid, if any.
+    pub fn best_bid(&self) -> Option<BookLevel> {
+        self.bids.last_key_value().map(|(_, level)| level.clone())
+    }
+
+    /// Lowest ask, if any.
+    pub fn best_ask(&self) -> Option<BookLevel> {
+        self.asks.first_key_value().map(|(_, level)| level.clone())
+    }
+
+    /// Best ask minus best bid.
+    pub fn spread(&self) -> Option<f64> {
+        let (ask, _) = self.asks.first_key_value()?;
+        let (bid, _) = self.bids.last_key_value()?;
+        Some((*ask as f64 - *bid as f64) / 10f64.powi(self.price_decimals as i32))
+    }
+
+    /// `true` if both sides are empty.
+    pub fn is_empty(&self) -> bool {
+        self.bids.is_empty() && self.asks.is_empty()
+    }
+}
+
+/// (Price, quantity) of the first `count` of `levels`.
+fn parsed<'a>(levels: impl Iterator<Item = &'a BookLevel>, count: usize) -> Vec<(f64, f64)> {
+    levels
+        .take(count)
+        .filter_map(|level| Some((level.price.parse().ok()?, level.quantity.parse().ok()?)))
+        .collect()
+}
+
+/// Best level of `side` as (scaled price, quantity).
+fn top(side: &BTreeMap<u64, BookLevel>, bids: bool) -> Option<(u64, f64)> {
+    let (key, level) = if bids {
+        side.last_key_value()
+    } else {
+        side.first_key_value()
+    }?;
+    Some((*key, level.quantity.parse().ok()?))
+}
+
+type Top = (Option<(u64, f64)>, Option<(u64, f64)>);
+
+/// One update's contribution to order-flow imbalance, from the best bid and
+/// ask before and after it.
+fn order_flow((bid_before, ask_before): Top, (bid_after, ask_after): Top) -> f64 {
+    let bid = match (bid_before, bid_after) {
+        (Some((before, old)), Some((after, new))) if after == before => new - old,
+        (Some((before, old)), Some((after, _))) if after < before => -old,
+        (_, Some((_, new))) => new,
+        (Some((_, old)), None) => -old,
+        (None, None) => 0.0,
+    };
+    let ask = match (ask_before, ask_after) {
+        (Some((before, old)), Some((after, new))) if after == before => new - old,
+        (Some((before, old)), Some((after, _))) if after > before => -old,
+        (_, Some((_, new))) => new,
+        (Some((_, old)), None) => -old,
+        (None, None) => 0.0,
+    };
+    bid - ask
+}
+
+/// `price * 10^decimals` as an integer, e.g. `("100.5", 2)` → `10050`.
+///
+/// `None` if `price` isn't a plain non-negative decimal, has non-zero digits past
+/// `decimals`, or overflows.
+pub fn scale_price(price: &str, decimals: u32) -> Option<u64> {
+    let (whole, fraction) = price.split_once('.').unwrap_or((price, ""));
+    if whole.is_empty() && fraction.is_empty() {
+        return None;
+    }
+    let mut scaled: u64 = 0;
+    for digit in whole.bytes() {
+        scaled = scaled.checked_mul(10)?.checked_add(digit_value(digit)?)?;
+    }
+    let mut fraction = fraction.bytes();
+    for _ in 0..decimals {
+        let digit = fraction.next().map_or(Some(0), digit_value)?;
+        scaled = scaled.checked_mul(10)?.checked_add(digit)?;
+    }
+    fraction.all(|digit| digit == b'0').then_some(scaled)
+}
+
+fn digit_value(byte: u8) -> Option<u64> {
+    byte.is_ascii_digit().then(|| u64::from(byte - b'0'))
+}
+
+/// `true` for "0", "0.0", "0.00000000" and the like.
+fn is_zero(quantity: &str) -> bool {
+    quantity.bytes().all(|b| b == b'0' || b == b'.')
+}
+
+/// The last `capacity` trades for one symbol, newest first.
+#[derive(Debug, Clone)]
+pub struct RecentTrades {
+    pub symbol: String,
+    capacity: usize,
+    trades: VecDeque<TradeData>,
+}
+
+impl RecentTrades {
+    pub fn new(symbol: impl Into<String>, capacity: usize) -> Self {
+        Self {
+            symbol: symbol.into(),
+            capacity,
+            trades: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Record the trades in a `trade` message. Messages for other symbols are ignored.
+    pub fn apply(&mut self, msg: &WsTradesMessage) {
+        if msg.symbol != self.symbol {
+            return;
+        }
+        for trade in &msg.trades {
+            self.trades.push_front(trade.clone());
+        }
+        self.trades.truncate(self.capacity);
+    }
+
+    /// Newest first.
+    pub fn iter(&self) -> impl Iterator<Item = &TradeData> {
+        self.trades.iter()
+    }
+}
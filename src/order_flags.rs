@@ -0,0 +1,93 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{KrakenError, KrakenResult};
+
+bitflags::bitflags! {
+    /// Order flags, Kraken's comma-separated `oflags` field.
+    ///
+    /// Set them on `params::AddOrderRequest` / `params::EditOrderRequest`, and
+    /// read them back from an order with `OrderInfo::flags`. `Display` renders
+    /// the wire form ("post,fciq"); `FromStr` parses it.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+    pub struct OrderFlags: u8 {
+        /// Post-only: reject the order rather than take liquidity
+        const POST = 1;
+        /// Pay the fee in base currency
+        const FCIB = 1 << 1;
+        /// Pay the fee in quote currency
+        const FCIQ = 1 << 2;
+        /// Disable market price protection for market orders
+        const NOMPP = 1 << 3;
+        /// Order volume is expressed in quote currency
+        const VIQC = 1 << 4;
+    }
+}
+
+const NAMES: &[(OrderFlags, &str)] = &[
+    (OrderFlags::POST, "post"),
+    (OrderFlags::FCIB, "fcib"),
+    (OrderFlags::FCIQ, "fciq"),
+    (OrderFlags::NOMPP, "nompp"),
+    (OrderFlags::VIQC, "viqc"),
+];
+
+impl OrderFlags {
+    /// Parse an `oflags` value as Kraken reports it, skipping flag names this
+    /// version doesn't know rather than failing.
+    pub fn from_oflags_lossy(oflags: &str) -> Self {
+        oflags
+            .split(',')
+            .filter_map(flag_named)
+            .fold(OrderFlags::empty(), |flags, flag| flags | flag)
+    }
+
+    /// Check the combination locally: Kraken takes at most one of
+    /// `FCIB` / `FCIQ`.
+    pub fn validate(self) -> KrakenResult<()> {
+        if self.contains(OrderFlags::FCIB | OrderFlags::FCIQ) {
+            return Err(KrakenError::InvalidUsage(
+                "oflags fcib and fciq are mutually exclusive".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for OrderFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut names = NAMES
+            .iter()
+            .filter(|(flag, _)| self.contains(*flag))
+            .map(|(_, name)| *name);
+        if let Some(first) = names.next() {
+            f.write_str(first)?;
+            for name in names {
+                write!(f, ",{name}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for OrderFlags {
+    type Err = KrakenError;
+
+    /// Strict parse: unknown flag names fail with `InvalidUsage`.
+    fn from_str(oflags: &str) -> KrakenResult<Self> {
+        let mut flags = OrderFlags::empty();
+        for name in oflags.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+            flags |= flag_named(name)
+                .ok_or_else(|| KrakenError::InvalidUsage(format!("unknown order flag {name:?}")))?;
+        }
+        Ok(flags)
+    }
+}
+
+fn flag_named(name: &str) -> Option<OrderFlags> {
+    let name = name.trim();
+    NAMES
+        .iter()
+        .find(|(_, known)| known.eq_ignore_ascii_case(name))
+        .map(|(flag, _)| *flag)
+}
@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{KrakenError, KrakenResult};
+use crate::exchange::{ExchangeClient, OrderAmendment, OrderRequest, PlacedOrder, Position};
+use crate::state::{SharedStateStore, StateHandle};
+
+/// An order an `OrderTracker` knows about.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrackedOrder {
+    pub order: OrderRequest,
+    /// The venue's order ID once it acknowledged the order; `None` while the
+    /// order is in flight
+    pub order_id: Option<String>,
+}
+
+/// Orders by client order ID (`cl_ord_id`): which were sent, and the venue
+/// order ID each was acknowledged with.
+///
+/// Record an order with `submitting` before sending it and `placed` once it
+/// is acknowledged (or let `TrackingExchange` do both). An order sent but
+/// never acknowledged, because the process died or the reply was lost, stays
+/// `in_flight` so it can be looked up by `cl_ord_id` on restart rather than
+/// sent twice. With `with_store` the tracker survives restarts. Clones share
+/// the same orders.
+#[derive(Debug, Clone, Default)]
+pub struct OrderTracker {
+    orders: Arc<Mutex<HashMap<String, TrackedOrder>>>,
+    state: Option<StateHandle>,
+}
+
+impl OrderTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Restore orders saved under `key` in `store`, and save them there after
+    /// every change from now on. A failed save is logged at `warn`.
+    pub fn with_store(mut self, store: SharedStateStore, key: &str) -> KrakenResult<Self> {
+        let state = StateHandle::new(store, key);
+        if let Some(saved) = state.load::<HashMap<String, TrackedOrder>>()? {
+            self.lock().extend(saved);
+        }
+        self.state = Some(state);
+        Ok(self)
+    }
+
+    /// Record that `order` is about to be sent. It must carry a `cl_ord_id`.
+    pub fn submitting(&self, order: &OrderRequest) -> KrakenResult<()> {
+        let cl_ord_id = order.cl_ord_id.clone().ok_or_else(|| {
+            KrakenError::InvalidUsage("tracked orders need a cl_ord_id".to_string())
+        })?;
+        self.update(|orders| {
+            orders.insert(
+                cl_ord_id,
+                TrackedOrder {
+                    order: order.clone(),
+                    order_id: None,
+                },
+            );
+        });
+        Ok(())
+    }
+
+    /// Record that the order sent as `cl_ord_id` was acknowledged as
+    /// `order_id`. Unknown client order IDs are ignored.
+    pub fn placed(&self, cl_ord_id: &str, order_id: &str) {
+        self.update(|orders| {
+            if let Some(tracked) = orders.get_mut(cl_ord_id) {
+                tracked.order_id = Some(order_id.to_string());
+            }
+        });
+    }
+
+    /// Stop tracking `cl_ord_id`, e.g. once the order was rejected, filled or
+    /// cancelled.
+    pub fn closed(&self, cl_ord_id: &str) {
+        self.update(|orders| {
+            orders.remove(cl_ord_id);
+        });
+    }
+
+    pub fn get(&self, cl_ord_id: &str) -> Option<TrackedOrder> {
+        self.lock().get(cl_ord_id).cloned()
+    }
+
+    /// The venue order ID `cl_ord_id` was acknowledged with.
+    pub fn order_id(&self, cl_ord_id: &str) -> Option<String> {
+        self.lock().get(cl_ord_id)?.order_id.clone()
+    }
+
+    /// The client order ID of the venue order `order_id`.
+    pub fn cl_ord_id(&self, order_id: &str) -> Option<String> {
+        self.lock()
+            .iter()
+            .find(|(_, tracked)| tracked.order_id.as_deref() == Some(order_id))
+            .map(|(cl_ord_id, _)| cl_ord_id.clone())
+    }
+
+    /// Orders sent but not acknowledged.
+    pub fn in_flight(&self) -> Vec<TrackedOrder> {
+        self.lock()
+            .values()
+            .filter(|tracked| tracked.order_id.is_none())
+            .cloned()
+            .collect()
+    }
+
+    /// Acknowledged orders not yet `closed`.
+    pub fn open(&self) -> Vec<TrackedOrder> {
+        self.lock()
+            .values()
+            .filter(|tracked| tracked.order_id.is_some())
+            .cloned()
+            .collect()
+    }
+
+    fn update(&self, change: impl FnOnce(&mut HashMap<String, TrackedOrder>)) {
+        let mut orders = self.lock();
+        change(&mut orders);
+        if let Some(state) = &self.state {
+            state.save_or_warn(&*orders);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, TrackedOrder>> {
+        self.orders.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// An `ExchangeClient` wrapper recording every order with a `cl_ord_id` in an
+/// `OrderTracker`: as in flight before it is sent, then acknowledged, or
+/// closed when it is refused or cancelled.
+///
+/// A failure that leaves the outcome unknown (a timeout, an I/O or transport
+/// error, a Kraken service error) keeps the order in flight, since it may
+/// still have reached the venue. Orders without a `cl_ord_id` pass through.
+#[derive(Debug)]
+pub struct TrackingExchange<E> {
+    inner: E,
+    tracker: OrderTracker,
+}
+
+impl<E: ExchangeClient> TrackingExchange<E> {
+    pub fn new(inner: E, tracker: OrderTracker) -> Self {
+        Self { inner, tracker }
+    }
+
+    pub fn inner(&self) -> &E {
+        &self.inner
+    }
+
+    pub fn tracker(&self) -> &OrderTracker {
+        &self.tracker
+    }
+}
+
+/// Whether a failed request may still have been carried out.
+pub(crate) fn outcome_unknown(e: &KrakenError) -> bool {
+    match e {
+        KrakenError::Request { source, .. } => outcome_unknown(source),
+        #[cfg(feature = "rest")]
+        KrakenError::Reqwest(_) => true,
+        KrakenError::IoError(_)
+        | KrakenError::ServiceError { .. }
+        | KrakenError::Timeout { .. } => true,
+        _ => false,
+    }
+}
+
+impl<E: ExchangeClient> ExchangeClient for TrackingExchange<E> {
+    async fn place_order(&self, order: &OrderRequest) -> KrakenResult<PlacedOrder> {
+        let Some(cl_ord_id) = order.cl_ord_id.as_deref() else {
+            return self.inner.place_order(order).await;
+        };
+        self.tracker.submitting(order)?;
+        let placed = self.inner.place_order(order).await;
+        match &placed {
+            Ok(placed) => self.tracker.placed(cl_ord_id, &placed.order_id),
+            Err(e) if !outcome_unknown(e) => self.tracker.closed(cl_ord_id),
+            Err(_) => {}
+        }
+        placed
+    }
+
+    async fn cancel_order(&self, order_id: &str) -> KrakenResult<()> {
+        self.inner.cancel_order(order_id).await?;
+        if let Some(cl_ord_id) = self.tracker.cl_ord_id(order_id) {
+            self.tracker.closed(&cl_ord_id);
+        }
+        Ok(())
+    }
+
+    async fn amend_order(&self, amendment: &OrderAmendment) -> KrakenResult<()> {
+        self.inner.amend_order(amendment).await
+    }
+
+    async fn positions(&self) -> KrakenResult<Vec<Position>> {
+        self.inner.positions().await
+    }
+
+    async fn balances(&self) -> KrakenResult<HashMap<String, String>> {
+        self.inner.balances().await
+    }
+}
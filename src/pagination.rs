@@ -0,0 +1,377 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::task::{Context, Poll};
+
+use futures_util::stream::{self, BoxStream, Stream, StreamExt};
+
+use crate::error::{KrakenError, KrakenResult};
+use crate::models::{LedgerInfo, OrderInfo, TradeInfo};
+use crate::params::{self, ClosedOrdersParams};
+use crate::AuthenticatedClient;
+
+/// A `Stream` over every entry of an offset-paginated private endpoint
+/// (`ClosedOrders`, `TradesHistory`, `Ledgers`).
+///
+/// Pages are fetched lazily with Kraken's `ofs` parameter as the stream is
+/// polled, and entries are yielded newest first as `(id, entry)`. The stream
+/// ends after the last page or the first error; `resume_token` records how far
+/// it got, so a long download can pick up there after a restart.
+///
+/// Offsets shift when entries arrive or disappear mid-download. The stream
+/// watches the reported `count` between pages: entries repeated across a page
+/// boundary are dropped by id, and when the count shrinks the stream steps
+/// back and re-reads the overlap rather than skipping entries. Both cases, and
+/// a history that ends short of its `count`, are logged at `warn`.
+pub struct PageStream<'a, T> {
+    /// Entries with the offset just past them
+    inner: BoxStream<'a, KrakenResult<(u64, (String, T))>>,
+    endpoint: &'static str,
+    params: Vec<(String, String)>,
+    /// Offset of the next entry to yield
+    position: u64,
+}
+
+impl<T> PageStream<'_, T> {
+    /// Where this stream has got to: its endpoint, filters and the offset of
+    /// the first entry not yet yielded. After an error this is the start of
+    /// the page that failed.
+    pub fn resume_token(&self) -> ResumeToken {
+        ResumeToken {
+            endpoint: self.endpoint.to_string(),
+            params: self.params.clone(),
+            offset: self.position,
+        }
+    }
+}
+
+impl<T> Stream for PageStream<'_, T> {
+    type Item = KrakenResult<(String, T)>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let next = self.inner.as_mut().poll_next(cx);
+        if let Poll::Ready(Some(Ok((position, _)))) = next {
+            self.position = position;
+        }
+        next.map(|item| item.map(|entry| entry.map(|(_, entry)| entry)))
+    }
+}
+
+/// A persistable position in a `PageStream`: the endpoint, the filters it was
+/// started with and the offset to continue from.
+///
+/// The token is opaque but round-trips through its `Display`/`FromStr` string
+/// (and serde, as that string), e.g. `Ledgers?asset=ZUSD&ofs=150`. Offsets
+/// count from the newest entry, so pass an `end` filter when entries may be
+/// added while the download is paused; otherwise new entries shift the pages
+/// and the resumed stream repeats a few.
+///
+/// ```
+/// use onise::pagination::ResumeToken;
+///
+/// let token: ResumeToken = "Ledgers?asset=ZUSD&end=1700000000&ofs=150".parse().unwrap();
+/// assert_eq!(token.endpoint(), "Ledgers");
+/// assert_eq!(token.offset(), 150);
+/// assert_eq!(token.to_string(), "Ledgers?asset=ZUSD&end=1700000000&ofs=150");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResumeToken {
+    endpoint: String,
+    params: Vec<(String, String)>,
+    offset: u64,
+}
+
+impl ResumeToken {
+    /// The endpoint being paged, e.g. `"ClosedOrders"`.
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// The filters the stream was started with (without `ofs`).
+    pub fn params(&self) -> &[(String, String)] {
+        &self.params
+    }
+
+    /// Entries already yielded; the resumed stream starts at this offset.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    fn expect_endpoint(&self, endpoint: &str) -> KrakenResult<()> {
+        if self.endpoint == endpoint {
+            Ok(())
+        } else {
+            Err(KrakenError::InvalidUsage(format!(
+                "resume token for {} used to resume {endpoint}",
+                self.endpoint
+            )))
+        }
+    }
+}
+
+impl fmt::Display for ResumeToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let query = form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(&self.params)
+            .append_pair("ofs", &self.offset.to_string())
+            .finish();
+        write!(f, "{}?{query}", self.endpoint)
+    }
+}
+
+impl FromStr for ResumeToken {
+    type Err = KrakenError;
+
+    fn from_str(s: &str) -> KrakenResult<Self> {
+        let invalid = || KrakenError::InvalidUsage(format!("not a resume token: {s:?}"));
+        let (endpoint, query) = s.split_once('?').ok_or_else(invalid)?;
+        if endpoint.is_empty() {
+            return Err(invalid());
+        }
+        let mut params: Vec<(String, String)> = form_urlencoded::parse(query.as_bytes())
+            .into_owned()
+            .collect();
+        let offset = match params.iter().position(|(k, _)| k == "ofs") {
+            Some(i) => params.remove(i).1.parse().map_err(|_| invalid())?,
+            None => return Err(invalid()),
+        };
+        Ok(Self {
+            endpoint: endpoint.to_string(),
+            params,
+            offset,
+        })
+    }
+}
+
+impl serde::Serialize for ResumeToken {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for ResumeToken {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+struct PageState<F> {
+    fetch: F,
+    params: Vec<(String, String)>,
+    offset: u64,
+    total: Option<u64>,
+    /// Ids already yielded, to drop repeats when offsets shift
+    seen: HashSet<String>,
+    failed: bool,
+}
+
+impl<'a, T: Send + 'a> PageStream<'a, T> {
+    /// `fetch` loads one page for the given parameters (which include `ofs`)
+    /// and returns its entries plus the total `count` Kraken reports.
+    fn new<F, Fut>(
+        endpoint: &'static str,
+        params: Vec<(String, String)>,
+        offset: u64,
+        time_of: fn(&T) -> f64,
+        fetch: F,
+    ) -> Self
+    where
+        F: FnMut(Vec<(String, String)>) -> Fut + Send + 'a,
+        Fut: Future<Output = KrakenResult<(Vec<(String, T)>, u64)>> + Send + 'a,
+    {
+        let state = PageState {
+            fetch,
+            params: params.clone(),
+            offset,
+            total: None,
+            seen: HashSet::new(),
+            failed: false,
+        };
+        let pages = stream::unfold(state, move |mut state| async move {
+            if state.failed || state.total.is_some_and(|total| state.offset >= total) {
+                return None;
+            }
+            let mut params = state.params.clone();
+            params.push(("ofs".to_string(), state.offset.to_string()));
+            match (state.fetch)(params).await {
+                Ok((mut page, total)) => {
+                    if page.is_empty() {
+                        if state.offset < total {
+                            tracing::warn!(
+                                endpoint,
+                                offset = state.offset,
+                                total,
+                                "history ended before its reported count"
+                            );
+                        }
+                        return None;
+                    }
+                    match state.total {
+                        Some(previous) if total < previous => {
+                            // Entries ahead of us went away, so this page
+                            // starts past some we haven't read: step back.
+                            let back = previous - total;
+                            tracing::warn!(
+                                endpoint,
+                                offset = state.offset,
+                                back,
+                                "history shrank mid-download; re-reading the overlap"
+                            );
+                            state.offset = state.offset.saturating_sub(back);
+                            state.total = Some(total);
+                            return Some((Ok(Vec::new()), state));
+                        }
+                        Some(previous) if total > previous => {
+                            tracing::warn!(
+                                endpoint,
+                                offset = state.offset,
+                                added = total - previous,
+                                "history grew mid-download; skipping repeated entries"
+                            );
+                        }
+                        _ => {}
+                    }
+                    let start = state.offset;
+                    state.offset += page.len() as u64;
+                    state.total = Some(total);
+                    // Kraken pages are maps; restore newest-first order.
+                    page.sort_by(|a, b| time_of(&b.1).total_cmp(&time_of(&a.1)));
+                    let mut entries = Vec::with_capacity(page.len());
+                    for (at, (id, entry)) in (start + 1..).zip(page) {
+                        if state.seen.insert(id.clone()) {
+                            entries.push((at, (id, entry)));
+                        } else {
+                            tracing::warn!(endpoint, id = %id, "skipped duplicate entry");
+                        }
+                    }
+                    Some((Ok(entries), state))
+                }
+                Err(e) => {
+                    state.failed = true;
+                    Some((Err(e), state))
+                }
+            }
+        });
+        let entries = pages.flat_map(|page| match page {
+            Ok(entries) => stream::iter(entries.into_iter().map(Ok).collect::<Vec<_>>()),
+            Err(e) => stream::iter(vec![Err(e)]),
+        });
+        Self {
+            inner: Box::pin(entries),
+            endpoint,
+            params,
+            position: offset,
+        }
+    }
+}
+
+fn as_params(params: &[(String, String)]) -> Vec<(&str, &str)> {
+    params.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect()
+}
+
+fn owned_params(params: &[(&str, &str)]) -> Vec<(String, String)> {
+    params
+        .iter()
+        .filter(|(k, _)| *k != "ofs")
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+impl AuthenticatedClient {
+    /// Every closed order matching `params`, fetching further pages as needed.
+    pub fn closed_orders_stream<'a>(
+        &'a self,
+        params: &[(&str, &str)],
+    ) -> PageStream<'a, OrderInfo> {
+        self.closed_orders_pages(owned_params(params), 0)
+    }
+
+    /// `closed_orders_stream` with typed options: starts at `params.ofs` (or
+    /// the newest order) and follows `ofs` page by page, 50 orders at a time,
+    /// until Kraken's reported `count` has been yielded.
+    pub fn closed_orders_paged(&self, params: &ClosedOrdersParams) -> PageStream<'_, OrderInfo> {
+        let pairs = params.to_params();
+        let filters = owned_params(&params::as_pairs(&pairs));
+        self.closed_orders_pages(filters, params.ofs.unwrap_or(0))
+    }
+
+    /// Continue a `closed_orders_stream` from its `resume_token`.
+    pub fn resume_closed_orders_stream<'a>(
+        &'a self,
+        token: &ResumeToken,
+    ) -> KrakenResult<PageStream<'a, OrderInfo>> {
+        token.expect_endpoint("ClosedOrders")?;
+        Ok(self.closed_orders_pages(token.params.clone(), token.offset))
+    }
+
+    /// Every trade matching `params`, fetching further pages as needed.
+    pub fn trades_history_stream<'a>(
+        &'a self,
+        params: &[(&str, &str)],
+    ) -> PageStream<'a, TradeInfo> {
+        self.trades_history_pages(owned_params(params), 0)
+    }
+
+    /// Continue a `trades_history_stream` from its `resume_token`.
+    pub fn resume_trades_history_stream<'a>(
+        &'a self,
+        token: &ResumeToken,
+    ) -> KrakenResult<PageStream<'a, TradeInfo>> {
+        token.expect_endpoint("TradesHistory")?;
+        Ok(self.trades_history_pages(token.params.clone(), token.offset))
+    }
+
+    /// Every ledger entry matching `params`, fetching further pages as needed.
+    pub fn ledgers_stream<'a>(&'a self, params: &[(&str, &str)]) -> PageStream<'a, LedgerInfo> {
+        self.ledgers_pages(owned_params(params), 0)
+    }
+
+    /// Continue a `ledgers_stream` from its `resume_token`.
+    pub fn resume_ledgers_stream<'a>(
+        &'a self,
+        token: &ResumeToken,
+    ) -> KrakenResult<PageStream<'a, LedgerInfo>> {
+        token.expect_endpoint("Ledgers")?;
+        Ok(self.ledgers_pages(token.params.clone(), token.offset))
+    }
+
+    fn closed_orders_pages(
+        &self,
+        params: Vec<(String, String)>,
+        offset: u64,
+    ) -> PageStream<'_, OrderInfo> {
+        let time_of = |order: &OrderInfo| order.opentm;
+        PageStream::new("ClosedOrders", params, offset, time_of, move |params| async move {
+            let page = self.get_closed_orders(&as_params(&params)).await?;
+            let total = page.count.unwrap_or(page.closed.len() as u64);
+            Ok((page.closed.into_iter().collect(), total))
+        })
+    }
+
+    fn trades_history_pages(
+        &self,
+        params: Vec<(String, String)>,
+        offset: u64,
+    ) -> PageStream<'_, TradeInfo> {
+        let time_of = |trade: &TradeInfo| trade.time;
+        PageStream::new("TradesHistory", params, offset, time_of, move |params| async move {
+            let page = self.get_trades_history(&as_params(&params)).await?;
+            Ok((page.trades.into_iter().collect(), page.count))
+        })
+    }
+
+    fn ledgers_pages(
+        &self,
+        params: Vec<(String, String)>,
+        offset: u64,
+    ) -> PageStream<'_, LedgerInfo> {
+        let time_of = |entry: &LedgerInfo| entry.time;
+        PageStream::new("Ledgers", params, offset, time_of, move |params| async move {
+            let page = self.get_ledgers(&as_params(&params)).await?;
+            Ok((page.ledger.into_iter().collect(), page.count))
+        })
+    }
+}
@@ -0,0 +1,1023 @@
+use crate::error::{KrakenError, KrakenResult};
+use crate::exchange::Side;
+use crate::expiry::ExpireTime;
+use crate::numeric::{AmountFormatter, IntoAmount, PairPrecision};
+use crate::order_flags::OrderFlags;
+
+/// A `start`/`end` bound: a Unix timestamp or an order/trade txid (exclusive).
+#[derive(Debug, Clone, PartialEq)]
+pub enum TimeBound {
+    Timestamp(u64),
+    Txid(String),
+}
+
+impl From<u64> for TimeBound {
+    fn from(timestamp: u64) -> Self {
+        TimeBound::Timestamp(timestamp)
+    }
+}
+
+impl From<&str> for TimeBound {
+    fn from(txid: &str) -> Self {
+        TimeBound::Txid(txid.to_string())
+    }
+}
+
+impl TimeBound {
+    fn value(&self) -> String {
+        match self {
+            TimeBound::Timestamp(timestamp) => timestamp.to_string(),
+            TimeBound::Txid(txid) => txid.clone(),
+        }
+    }
+}
+
+/// Which timestamp `ClosedOrders` filters `start`/`end` on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloseTime {
+    Open,
+    Close,
+    Both,
+}
+
+impl CloseTime {
+    fn as_str(self) -> &'static str {
+        match self {
+            CloseTime::Open => "open",
+            CloseTime::Close => "close",
+            CloseTime::Both => "both",
+        }
+    }
+}
+
+/// Options for `/0/private/OpenOrders`, passed to `AuthenticatedClient::open_orders`
+/// instead of string tuples. Like the other `*Params` types, `to_params` renders
+/// the exact form keys Kraken expects.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OpenOrdersParams {
+    /// Include the trade IDs that filled each order
+    pub trades: bool,
+    /// Only orders with this user reference
+    pub userref: Option<i64>,
+    /// Only the order with this client order ID
+    pub cl_ord_id: Option<String>,
+}
+
+impl OpenOrdersParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_trades(mut self, trades: bool) -> Self {
+        self.trades = trades;
+        self
+    }
+
+    pub fn with_userref(mut self, userref: i64) -> Self {
+        self.userref = Some(userref);
+        self
+    }
+
+    pub fn with_cl_ord_id(mut self, cl_ord_id: impl Into<String>) -> Self {
+        self.cl_ord_id = Some(cl_ord_id.into());
+        self
+    }
+
+    /// The form parameters for this request.
+    pub fn to_params(&self) -> Vec<(&'static str, String)> {
+        let mut params = Vec::new();
+        push_flag(&mut params, "trades", self.trades);
+        push_opt(&mut params, "userref", self.userref);
+        push_opt(&mut params, "cl_ord_id", self.cl_ord_id.as_ref());
+        params
+    }
+}
+
+/// Options for `/0/private/ClosedOrders`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClosedOrdersParams {
+    /// Include the trade IDs that filled each order
+    pub trades: bool,
+    /// Only orders with this user reference
+    pub userref: Option<i64>,
+    /// Only the order with this client order ID
+    pub cl_ord_id: Option<String>,
+    /// Exclusive lower bound
+    pub start: Option<TimeBound>,
+    /// Inclusive upper bound
+    pub end: Option<TimeBound>,
+    /// Result offset, for pagination
+    pub ofs: Option<u64>,
+    /// Which time `start`/`end` apply to (Kraken's default is `Both`)
+    pub closetime: Option<CloseTime>,
+}
+
+impl ClosedOrdersParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_trades(mut self, trades: bool) -> Self {
+        self.trades = trades;
+        self
+    }
+
+    pub fn with_userref(mut self, userref: i64) -> Self {
+        self.userref = Some(userref);
+        self
+    }
+
+    pub fn with_cl_ord_id(mut self, cl_ord_id: impl Into<String>) -> Self {
+        self.cl_ord_id = Some(cl_ord_id.into());
+        self
+    }
+
+    pub fn with_start(mut self, start: impl Into<TimeBound>) -> Self {
+        self.start = Some(start.into());
+        self
+    }
+
+    pub fn with_end(mut self, end: impl Into<TimeBound>) -> Self {
+        self.end = Some(end.into());
+        self
+    }
+
+    pub fn with_offset(mut self, ofs: u64) -> Self {
+        self.ofs = Some(ofs);
+        self
+    }
+
+    pub fn with_closetime(mut self, closetime: CloseTime) -> Self {
+        self.closetime = Some(closetime);
+        self
+    }
+
+    /// The form parameters for this request.
+    pub fn to_params(&self) -> Vec<(&'static str, String)> {
+        let mut params = Vec::new();
+        push_flag(&mut params, "trades", self.trades);
+        push_opt(&mut params, "userref", self.userref);
+        push_opt(&mut params, "cl_ord_id", self.cl_ord_id.as_ref());
+        push_opt(
+            &mut params,
+            "start",
+            self.start.as_ref().map(TimeBound::value),
+        );
+        push_opt(&mut params, "end", self.end.as_ref().map(TimeBound::value));
+        push_opt(&mut params, "ofs", self.ofs);
+        push_opt(
+            &mut params,
+            "closetime",
+            self.closetime.map(CloseTime::as_str),
+        );
+        params
+    }
+}
+
+/// Options for `/0/private/QueryOrders`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryOrdersParams {
+    /// Up to 50 order txids to look up
+    pub txids: Vec<String>,
+    /// Include the trade IDs that filled each order
+    pub trades: bool,
+    /// Only orders with this user reference
+    pub userref: Option<i64>,
+    /// Consolidate taker trades by order (Kraken's default is `true`)
+    pub consolidate_taker: Option<bool>,
+}
+
+impl QueryOrdersParams {
+    /// Look up `txids`.
+    pub fn new<I, T>(txids: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        Self {
+            txids: txids.into_iter().map(Into::into).collect(),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_trades(mut self, trades: bool) -> Self {
+        self.trades = trades;
+        self
+    }
+
+    pub fn with_userref(mut self, userref: i64) -> Self {
+        self.userref = Some(userref);
+        self
+    }
+
+    pub fn with_consolidate_taker(mut self, consolidate_taker: bool) -> Self {
+        self.consolidate_taker = Some(consolidate_taker);
+        self
+    }
+
+    /// The form parameters for this request.
+    pub fn to_params(&self) -> Vec<(&'static str, String)> {
+        let mut params = Vec::new();
+        if !self.txids.is_empty() {
+            params.push(("txid", self.txids.join(",")));
+        }
+        push_flag(&mut params, "trades", self.trades);
+        push_opt(&mut params, "userref", self.userref);
+        push_opt(&mut params, "consolidate_taker", self.consolidate_taker);
+        params
+    }
+}
+
+/// Filters for `/0/private/Ledgers`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LedgersParams {
+    /// Only entries for these assets (all when empty)
+    pub assets: Vec<String>,
+    /// Only this asset class (Kraken's default is "currency")
+    pub aclass: Option<String>,
+    /// Only this entry type: "trade", "deposit", "withdrawal", "staking", ...
+    pub entry_type: Option<String>,
+    /// Exclusive lower bound
+    pub start: Option<TimeBound>,
+    /// Inclusive upper bound
+    pub end: Option<TimeBound>,
+}
+
+impl LedgersParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_asset(mut self, asset: impl Into<String>) -> Self {
+        self.assets.push(asset.into());
+        self
+    }
+
+    pub fn with_aclass(mut self, aclass: impl Into<String>) -> Self {
+        self.aclass = Some(aclass.into());
+        self
+    }
+
+    pub fn with_entry_type(mut self, entry_type: impl Into<String>) -> Self {
+        self.entry_type = Some(entry_type.into());
+        self
+    }
+
+    pub fn with_start(mut self, start: impl Into<TimeBound>) -> Self {
+        self.start = Some(start.into());
+        self
+    }
+
+    pub fn with_end(mut self, end: impl Into<TimeBound>) -> Self {
+        self.end = Some(end.into());
+        self
+    }
+
+    /// The form parameters for this request.
+    pub fn to_params(&self) -> Vec<(&'static str, String)> {
+        let mut params = Vec::new();
+        if !self.assets.is_empty() {
+            params.push(("asset", self.assets.join(",")));
+        }
+        push_opt(&mut params, "aclass", self.aclass.as_ref());
+        push_opt(&mut params, "type", self.entry_type.as_ref());
+        push_opt(
+            &mut params,
+            "start",
+            self.start.as_ref().map(TimeBound::value),
+        );
+        push_opt(&mut params, "end", self.end.as_ref().map(TimeBound::value));
+        params
+    }
+}
+
+/// Filters for `/0/private/TradesHistory`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TradesHistoryParams {
+    /// "all", "any position", "closed position", "closing position" or "no position"
+    pub trade_type: Option<String>,
+    /// Include the trade IDs related to each position
+    pub trades: bool,
+    /// Exclusive lower bound
+    pub start: Option<TimeBound>,
+    /// Inclusive upper bound
+    pub end: Option<TimeBound>,
+    /// Consolidate taker trades by order (Kraken's default is `true`)
+    pub consolidate_taker: Option<bool>,
+}
+
+impl TradesHistoryParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_trade_type(mut self, trade_type: impl Into<String>) -> Self {
+        self.trade_type = Some(trade_type.into());
+        self
+    }
+
+    pub fn with_trades(mut self, trades: bool) -> Self {
+        self.trades = trades;
+        self
+    }
+
+    pub fn with_start(mut self, start: impl Into<TimeBound>) -> Self {
+        self.start = Some(start.into());
+        self
+    }
+
+    pub fn with_end(mut self, end: impl Into<TimeBound>) -> Self {
+        self.end = Some(end.into());
+        self
+    }
+
+    pub fn with_consolidate_taker(mut self, consolidate_taker: bool) -> Self {
+        self.consolidate_taker = Some(consolidate_taker);
+        self
+    }
+
+    /// The form parameters for this request.
+    pub fn to_params(&self) -> Vec<(&'static str, String)> {
+        let mut params = Vec::new();
+        push_opt(&mut params, "type", self.trade_type.as_ref());
+        push_flag(&mut params, "trades", self.trades);
+        push_opt(
+            &mut params,
+            "start",
+            self.start.as_ref().map(TimeBound::value),
+        );
+        push_opt(&mut params, "end", self.end.as_ref().map(TimeBound::value));
+        push_opt(&mut params, "consolidate_taker", self.consolidate_taker);
+        params
+    }
+}
+
+/// Parameters for `/0/private/AmendOrder`, which changes an order in place
+/// (keeping its txid and queue priority where possible).
+///
+/// Identify the order with exactly one of `txid` / `cl_ord_id` and set at least
+/// one field to change. `limit_price` and `trigger_price` accept an absolute
+/// price ("27500.5") or, for trigger and trailing orders, an offset from the
+/// reference price: a `+`/`-` prefix and/or a `%` suffix ("+100", "-1.5%").
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AmendOrderRequest {
+    /// Kraken order ID
+    pub txid: Option<String>,
+    /// Client order ID the order was placed with
+    pub cl_ord_id: Option<String>,
+    /// New total quantity, in base currency
+    pub order_qty: Option<String>,
+    /// New visible quantity of an iceberg order
+    pub display_qty: Option<String>,
+    /// New limit price
+    pub limit_price: Option<String>,
+    /// New trigger price of a stop/take-profit/trailing order
+    pub trigger_price: Option<String>,
+    /// Reject the amend if the new limit price would take liquidity
+    pub post_only: bool,
+    /// RFC 3339 time after which the amend is rejected
+    pub deadline: Option<String>,
+}
+
+impl AmendOrderRequest {
+    /// Amend the order with Kraken order ID `txid`.
+    pub fn by_txid(txid: impl Into<String>) -> Self {
+        Self {
+            txid: Some(txid.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Amend the order placed with client order ID `cl_ord_id`.
+    pub fn by_cl_ord_id(cl_ord_id: impl Into<String>) -> Self {
+        Self {
+            cl_ord_id: Some(cl_ord_id.into()),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_order_qty(mut self, order_qty: impl IntoAmount) -> Self {
+        self.order_qty = Some(order_qty.into_amount());
+        self
+    }
+
+    pub fn with_display_qty(mut self, display_qty: impl IntoAmount) -> Self {
+        self.display_qty = Some(display_qty.into_amount());
+        self
+    }
+
+    pub fn with_limit_price(mut self, limit_price: impl IntoAmount) -> Self {
+        self.limit_price = Some(limit_price.into_amount());
+        self
+    }
+
+    pub fn with_trigger_price(mut self, trigger_price: impl IntoAmount) -> Self {
+        self.trigger_price = Some(trigger_price.into_amount());
+        self
+    }
+
+    pub fn with_post_only(mut self, post_only: bool) -> Self {
+        self.post_only = post_only;
+        self
+    }
+
+    pub fn with_deadline(mut self, deadline: impl Into<String>) -> Self {
+        self.deadline = Some(deadline.into());
+        self
+    }
+
+    /// Check the request locally, before it costs a round trip.
+    pub fn validate(&self) -> KrakenResult<()> {
+        match (&self.txid, &self.cl_ord_id) {
+            (None, None) => return Err(invalid("AmendOrder needs a txid or a cl_ord_id")),
+            (Some(_), Some(_)) => {
+                return Err(invalid("AmendOrder takes a txid or a cl_ord_id, not both"))
+            }
+            _ => {}
+        }
+        if self.order_qty.is_none()
+            && self.display_qty.is_none()
+            && self.limit_price.is_none()
+            && self.trigger_price.is_none()
+        {
+            return Err(invalid("AmendOrder must change at least one of order_qty, display_qty, limit_price or trigger_price"));
+        }
+        for (key, qty) in [
+            ("order_qty", &self.order_qty),
+            ("display_qty", &self.display_qty),
+        ] {
+            if let Some(qty) = qty {
+                if !is_decimal(qty) {
+                    return Err(invalid(&format!(
+                        "{key} must be a plain decimal, got {qty:?}"
+                    )));
+                }
+            }
+        }
+        for (key, price) in [
+            ("limit_price", &self.limit_price),
+            ("trigger_price", &self.trigger_price),
+        ] {
+            if let Some(price) = price {
+                if !is_price_or_offset(price) {
+                    return Err(invalid(&format!(
+                        "{key} must be a price or an offset like \"+10\" or \"-1.5%\", got {price:?}"
+                    )));
+                }
+            }
+        }
+        if self.post_only && self.limit_price.is_none() {
+            return Err(invalid("post_only only applies when amending limit_price"));
+        }
+        Ok(())
+    }
+
+    /// The form parameters for this request. Call `validate` first;
+    /// `AuthenticatedClient::amend` does.
+    pub fn to_params(&self) -> Vec<(&'static str, String)> {
+        let mut params = Vec::new();
+        push_opt(&mut params, "txid", self.txid.as_ref());
+        push_opt(&mut params, "cl_ord_id", self.cl_ord_id.as_ref());
+        push_opt(&mut params, "order_qty", self.order_qty.as_ref());
+        push_opt(&mut params, "display_qty", self.display_qty.as_ref());
+        push_opt(&mut params, "limit_price", self.limit_price.as_ref());
+        push_opt(&mut params, "trigger_price", self.trigger_price.as_ref());
+        push_flag(&mut params, "post_only", self.post_only);
+        push_opt(&mut params, "deadline", self.deadline.as_ref());
+        params
+    }
+}
+
+/// The price a trigger order (stop-loss, take-profit, trailing-stop and their
+/// `-limit` variants) watches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    /// Kraken's index price for the pair
+    Index,
+    /// The last traded price (Kraken's default)
+    Last,
+}
+
+impl Trigger {
+    fn as_str(self) -> &'static str {
+        match self {
+            Trigger::Index => "index",
+            Trigger::Last => "last",
+        }
+    }
+}
+
+/// Order types whose `price` is a trigger price.
+const TRIGGER_ORDER_TYPES: &[&str] = &[
+    "stop-loss",
+    "take-profit",
+    "stop-loss-limit",
+    "take-profit-limit",
+    "trailing-stop",
+    "trailing-stop-limit",
+];
+
+/// Parameters for `/0/private/AddOrder`, passed to `AuthenticatedClient::add`.
+///
+/// `ordertype` is Kraken's order type ("market", "limit", "stop-loss", ...);
+/// `market`, `limit`, `stop_loss` and `take_profit` cover the common cases.
+/// Order flags are set with `with_oflags` rather than by joining strings.
+///
+/// `price` and `price2` take an absolute price or an offset from the last
+/// traded price: a `+`/`-` prefix, or `#` for whichever direction suits the
+/// side, with an optional `%` suffix ("+50", "#2.5%"). Trailing stops need a
+/// `+` offset. Margin orders set `leverage`, and `reduce_only` to only shrink
+/// an existing position.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AddOrderRequest {
+    pub pair: String,
+    pub side: Side,
+    pub ordertype: String,
+    /// Quantity in base currency (quote currency with `OrderFlags::VIQC`)
+    pub volume: String,
+    /// Limit price, or trigger price for stop/take-profit orders
+    pub price: Option<String>,
+    /// Secondary price, e.g. the limit price of a stop-loss-limit order
+    pub price2: Option<String>,
+    pub oflags: OrderFlags,
+    /// "GTC", "IOC" or "GTD"
+    pub timeinforce: Option<String>,
+    /// Expiry of a GTD order
+    pub expiretm: Option<ExpireTime>,
+    pub userref: Option<i64>,
+    pub cl_ord_id: Option<String>,
+    /// Leverage for a margin order ("2", "5:1", or "none")
+    pub leverage: Option<String>,
+    /// Only reduce an existing margin position
+    pub reduce_only: bool,
+    /// Price that fires a trigger order; Kraken defaults to `Last`
+    pub trigger: Option<Trigger>,
+    /// Validate server-side only; don't submit the order
+    pub validate: bool,
+}
+
+impl AddOrderRequest {
+    pub fn new(
+        pair: impl Into<String>,
+        side: Side,
+        ordertype: impl Into<String>,
+        volume: impl IntoAmount,
+    ) -> Self {
+        Self {
+            pair: pair.into(),
+            side,
+            ordertype: ordertype.into(),
+            volume: volume.into_amount(),
+            price: None,
+            price2: None,
+            oflags: OrderFlags::empty(),
+            timeinforce: None,
+            expiretm: None,
+            userref: None,
+            cl_ord_id: None,
+            leverage: None,
+            reduce_only: false,
+            trigger: None,
+            validate: false,
+        }
+    }
+
+    pub fn market(pair: impl Into<String>, side: Side, volume: impl IntoAmount) -> Self {
+        Self::new(pair, side, "market", volume)
+    }
+
+    pub fn limit(
+        pair: impl Into<String>,
+        side: Side,
+        volume: impl IntoAmount,
+        price: impl IntoAmount,
+    ) -> Self {
+        Self::new(pair, side, "limit", volume).with_price(price)
+    }
+
+    /// A market order placed once `trigger_price` (absolute or an offset) is reached.
+    pub fn stop_loss(
+        pair: impl Into<String>,
+        side: Side,
+        volume: impl IntoAmount,
+        trigger_price: impl IntoAmount,
+    ) -> Self {
+        Self::new(pair, side, "stop-loss", volume).with_price(trigger_price)
+    }
+
+    /// A market order placed once `trigger_price` (absolute or an offset) is reached.
+    pub fn take_profit(
+        pair: impl Into<String>,
+        side: Side,
+        volume: impl IntoAmount,
+        trigger_price: impl IntoAmount,
+    ) -> Self {
+        Self::new(pair, side, "take-profit", volume).with_price(trigger_price)
+    }
+
+    pub fn with_price(mut self, price: impl IntoAmount) -> Self {
+        self.price = Some(price.into_amount());
+        self
+    }
+
+    pub fn with_price2(mut self, price2: impl IntoAmount) -> Self {
+        self.price2 = Some(price2.into_amount());
+        self
+    }
+
+    pub fn with_oflags(mut self, oflags: OrderFlags) -> Self {
+        self.oflags = oflags;
+        self
+    }
+
+    pub fn with_timeinforce(mut self, timeinforce: impl Into<String>) -> Self {
+        self.timeinforce = Some(timeinforce.into());
+        self
+    }
+
+    pub fn with_expiretm(mut self, expiretm: impl Into<ExpireTime>) -> Self {
+        self.expiretm = Some(expiretm.into());
+        self
+    }
+
+    pub fn with_userref(mut self, userref: i64) -> Self {
+        self.userref = Some(userref);
+        self
+    }
+
+    pub fn with_cl_ord_id(mut self, cl_ord_id: impl Into<String>) -> Self {
+        self.cl_ord_id = Some(cl_ord_id.into());
+        self
+    }
+
+    pub fn with_leverage(mut self, leverage: impl Into<String>) -> Self {
+        self.leverage = Some(leverage.into());
+        self
+    }
+
+    pub fn with_reduce_only(mut self, reduce_only: bool) -> Self {
+        self.reduce_only = reduce_only;
+        self
+    }
+
+    pub fn with_trigger(mut self, trigger: Trigger) -> Self {
+        self.trigger = Some(trigger);
+        self
+    }
+
+    pub fn with_validate(mut self, validate: bool) -> Self {
+        self.validate = validate;
+        self
+    }
+
+    /// The request with `volume`, `price` and `price2` written by `formatter`
+    /// to the pair's `precision`.
+    pub fn formatted(
+        mut self,
+        formatter: &AmountFormatter,
+        precision: &PairPrecision,
+    ) -> KrakenResult<Self> {
+        self.volume = formatter.volume(&self.volume, precision)?;
+        self.price = self
+            .price
+            .map(|price| formatter.price(&price, precision))
+            .transpose()?;
+        self.price2 = self
+            .price2
+            .map(|price2| formatter.price(&price2, precision))
+            .transpose()?;
+        Ok(self)
+    }
+
+    /// Check the request locally, before it costs a round trip.
+    pub fn validate(&self) -> KrakenResult<()> {
+        if !is_decimal(&self.volume) {
+            return Err(invalid(&format!(
+                "volume must be a plain decimal, got {:?}",
+                self.volume
+            )));
+        }
+        if self.ordertype != "market" && self.price.is_none() {
+            return Err(invalid(&format!(
+                "{} orders need a price",
+                self.ordertype
+            )));
+        }
+        self.oflags.validate()?;
+        if self.oflags.contains(OrderFlags::POST) && self.ordertype != "limit" {
+            return Err(invalid("oflags post only applies to limit orders"));
+        }
+        if self.oflags.contains(OrderFlags::NOMPP) && self.ordertype != "market" {
+            return Err(invalid("oflags nompp only applies to market orders"));
+        }
+        if self.userref.is_some() && self.cl_ord_id.is_some() {
+            return Err(invalid("AddOrder takes a userref or a cl_ord_id, not both"));
+        }
+        for (key, price) in [("price", &self.price), ("price2", &self.price2)] {
+            if let Some(price) = price {
+                if !is_relative_price(price) {
+                    return Err(invalid(&format!(
+                        "{key} must be a price or an offset like \"+10\", \"#5\" or \"-1.5%\", got {price:?}"
+                    )));
+                }
+            }
+        }
+        if self.ordertype.starts_with("trailing-stop")
+            && self.price.as_ref().is_some_and(|price| !price.starts_with('+'))
+        {
+            return Err(invalid("trailing stops take a \"+\" offset as price"));
+        }
+        if self.trigger.is_some() && !TRIGGER_ORDER_TYPES.contains(&self.ordertype.as_str()) {
+            return Err(invalid(&format!(
+                "trigger only applies to stop/take-profit/trailing orders, not {}",
+                self.ordertype
+            )));
+        }
+        if let Some(leverage) = &self.leverage {
+            let ratio = leverage.strip_suffix(":1").unwrap_or(leverage);
+            let whole = !ratio.is_empty() && ratio.bytes().all(|b| b.is_ascii_digit());
+            if leverage != "none" && !whole {
+                return Err(invalid(&format!(
+                    "leverage must be a whole number like \"3\" or \"3:1\", or \"none\", got {leverage:?}"
+                )));
+            }
+        }
+        if self.reduce_only && self.leverage.as_ref().is_none_or(|l| l == "none") {
+            return Err(invalid("reduce_only only applies to margin orders; set leverage"));
+        }
+        Ok(())
+    }
+
+    /// The form parameters for this request. Call `validate` first;
+    /// `AuthenticatedClient::add` does.
+    pub fn to_params(&self) -> Vec<(&'static str, String)> {
+        let mut params = vec![
+            ("pair", self.pair.clone()),
+            ("type", self.side.as_str().to_string()),
+            ("ordertype", self.ordertype.clone()),
+            ("volume", self.volume.clone()),
+        ];
+        push_opt(&mut params, "price", self.price.as_ref());
+        push_opt(&mut params, "price2", self.price2.as_ref());
+        push_oflags(&mut params, self.oflags);
+        push_opt(&mut params, "timeinforce", self.timeinforce.as_ref());
+        push_opt(
+            &mut params,
+            "expiretm",
+            self.expiretm.map(|expiretm| expiretm.to_expiretm()),
+        );
+        push_opt(&mut params, "userref", self.userref);
+        push_opt(&mut params, "cl_ord_id", self.cl_ord_id.as_ref());
+        push_opt(&mut params, "leverage", self.leverage.as_ref());
+        push_flag(&mut params, "reduce_only", self.reduce_only);
+        push_opt(&mut params, "trigger", self.trigger.map(Trigger::as_str));
+        push_flag(&mut params, "validate", self.validate);
+        params
+    }
+}
+
+/// Parameters for `/0/private/EditOrder`, passed to `AuthenticatedClient::edit`.
+/// Unlike `AmendOrderRequest`, an edit cancels the order and replaces it with a
+/// new txid; `oflags` replaces the original order's flags.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EditOrderRequest {
+    /// Kraken order ID (or user reference) of the order to edit
+    pub txid: String,
+    pub pair: String,
+    pub volume: Option<String>,
+    pub price: Option<String>,
+    pub price2: Option<String>,
+    pub oflags: Option<OrderFlags>,
+    pub userref: Option<i64>,
+    /// RFC 3339 time after which the edit is rejected
+    pub deadline: Option<String>,
+    /// Validate server-side only; don't edit the order
+    pub validate: bool,
+}
+
+impl EditOrderRequest {
+    pub fn new(txid: impl Into<String>, pair: impl Into<String>) -> Self {
+        Self {
+            txid: txid.into(),
+            pair: pair.into(),
+            volume: None,
+            price: None,
+            price2: None,
+            oflags: None,
+            userref: None,
+            deadline: None,
+            validate: false,
+        }
+    }
+
+    pub fn with_volume(mut self, volume: impl IntoAmount) -> Self {
+        self.volume = Some(volume.into_amount());
+        self
+    }
+
+    pub fn with_price(mut self, price: impl IntoAmount) -> Self {
+        self.price = Some(price.into_amount());
+        self
+    }
+
+    pub fn with_price2(mut self, price2: impl IntoAmount) -> Self {
+        self.price2 = Some(price2.into_amount());
+        self
+    }
+
+    pub fn with_oflags(mut self, oflags: OrderFlags) -> Self {
+        self.oflags = Some(oflags);
+        self
+    }
+
+    pub fn with_userref(mut self, userref: i64) -> Self {
+        self.userref = Some(userref);
+        self
+    }
+
+    pub fn with_deadline(mut self, deadline: impl Into<String>) -> Self {
+        self.deadline = Some(deadline.into());
+        self
+    }
+
+    pub fn with_validate(mut self, validate: bool) -> Self {
+        self.validate = validate;
+        self
+    }
+
+    /// The request with `volume`, `price` and `price2` written by `formatter`
+    /// to the pair's `precision`.
+    pub fn formatted(
+        mut self,
+        formatter: &AmountFormatter,
+        precision: &PairPrecision,
+    ) -> KrakenResult<Self> {
+        self.volume = self
+            .volume
+            .map(|volume| formatter.volume(&volume, precision))
+            .transpose()?;
+        self.price = self
+            .price
+            .map(|price| formatter.price(&price, precision))
+            .transpose()?;
+        self.price2 = self
+            .price2
+            .map(|price2| formatter.price(&price2, precision))
+            .transpose()?;
+        Ok(self)
+    }
+
+    /// Check the request locally, before it costs a round trip.
+    pub fn validate(&self) -> KrakenResult<()> {
+        if let Some(volume) = &self.volume {
+            if !is_decimal(volume) {
+                return Err(invalid(&format!(
+                    "volume must be a plain decimal, got {volume:?}"
+                )));
+            }
+        }
+        if let Some(oflags) = self.oflags {
+            oflags.validate()?;
+        }
+        Ok(())
+    }
+
+    /// The form parameters for this request. Call `validate` first;
+    /// `AuthenticatedClient::edit` does.
+    pub fn to_params(&self) -> Vec<(&'static str, String)> {
+        let mut params = vec![("txid", self.txid.clone()), ("pair", self.pair.clone())];
+        push_opt(&mut params, "volume", self.volume.as_ref());
+        push_opt(&mut params, "price", self.price.as_ref());
+        push_opt(&mut params, "price2", self.price2.as_ref());
+        if let Some(oflags) = self.oflags {
+            push_oflags(&mut params, oflags);
+        }
+        push_opt(&mut params, "userref", self.userref);
+        push_opt(&mut params, "deadline", self.deadline.as_ref());
+        push_flag(&mut params, "validate", self.validate);
+        params
+    }
+}
+
+/// How an `OtcQuoteRequest` is sized.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OtcAmount {
+    /// Quantity in base currency, e.g. 25 BTC
+    Volume(String),
+    /// Total in quote currency, e.g. 750,000 USD worth
+    Cost(String),
+}
+
+/// Parameters for `/0/private/CreateOtcQuoteRequest`, passed to
+/// `AuthenticatedClient::request_otc_quote`: a block of `pair` to buy or sell
+/// through the OTC desk rather than the order book.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OtcQuoteRequest {
+    pub pair: String,
+    pub side: Side,
+    pub amount: OtcAmount,
+}
+
+impl OtcQuoteRequest {
+    /// A quote for `volume` of `pair`'s base currency.
+    pub fn volume(pair: impl Into<String>, side: Side, volume: impl Into<String>) -> Self {
+        Self {
+            pair: pair.into(),
+            side,
+            amount: OtcAmount::Volume(volume.into()),
+        }
+    }
+
+    /// A quote for `cost` worth of `pair`'s quote currency.
+    pub fn cost(pair: impl Into<String>, side: Side, cost: impl Into<String>) -> Self {
+        Self {
+            pair: pair.into(),
+            side,
+            amount: OtcAmount::Cost(cost.into()),
+        }
+    }
+
+    /// Check the request locally, before it costs a round trip.
+    pub fn validate(&self) -> KrakenResult<()> {
+        let (key, amount) = self.amount();
+        if !is_decimal(amount) {
+            return Err(invalid(&format!(
+                "{key} must be a plain decimal, got {amount:?}"
+            )));
+        }
+        Ok(())
+    }
+
+    /// The form parameters for this request.
+    pub fn to_params(&self) -> Vec<(&'static str, String)> {
+        let (key, amount) = self.amount();
+        vec![
+            ("pair", self.pair.clone()),
+            ("type", self.side.as_str().to_string()),
+            (key, amount.to_string()),
+        ]
+    }
+
+    fn amount(&self) -> (&'static str, &str) {
+        match &self.amount {
+            OtcAmount::Volume(volume) => ("volume", volume),
+            OtcAmount::Cost(cost) => ("cost", cost),
+        }
+    }
+}
+
+fn invalid(message: &str) -> KrakenError {
+    KrakenError::InvalidUsage(message.to_string())
+}
+
+/// "123", "0.5", "27500.25"
+fn is_decimal(value: &str) -> bool {
+    let mut parts = value.splitn(2, '.');
+    let whole = parts.next().unwrap_or("");
+    let frac = parts.next();
+    !whole.is_empty()
+        && whole.bytes().all(|b| b.is_ascii_digit())
+        && frac.is_none_or(|f| !f.is_empty() && f.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// A decimal with an optional `+`/`-` prefix and optional `%` suffix.
+fn is_price_or_offset(value: &str) -> bool {
+    let value = value.strip_prefix(['+', '-']).unwrap_or(value);
+    let value = value.strip_suffix('%').unwrap_or(value);
+    is_decimal(value)
+}
+
+/// `is_price_or_offset`, or a `#` offset (direction picked by the order side).
+fn is_relative_price(value: &str) -> bool {
+    match value.strip_prefix('#') {
+        Some(offset) => !offset.starts_with(['+', '-']) && is_price_or_offset(offset),
+        None => is_price_or_offset(value),
+    }
+}
+
+/// Borrow owned params as the `&[(&str, &str)]` the endpoint methods take.
+pub(crate) fn as_pairs<'a>(params: &'a [(&'static str, String)]) -> Vec<(&'static str, &'a str)> {
+    params.iter().map(|(k, v)| (*k, v.as_str())).collect()
+}
+
+fn push_flag(params: &mut Vec<(&'static str, String)>, key: &'static str, flag: bool) {
+    if flag {
+        params.push((key, "true".to_string()));
+    }
+}
+
+fn push_oflags(params: &mut Vec<(&'static str, String)>, oflags: OrderFlags) {
+    if !oflags.is_empty() {
+        params.push(("oflags", oflags.to_string()));
+    }
+}
+
+fn push_opt<T: ToString>(
+    params: &mut Vec<(&'static str, String)>,
+    key: &'static str,
+    value: Option<T>,
+) {
+    if let Some(value) = value {
+        params.push((key, value.to_string()));
+    }
+}
@@ -0,0 +1,346 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use futures_util::stream::{self, Stream, StreamExt};
+use tokio::time::{Interval, MissedTickBehavior};
+
+use crate::error::KrakenResult;
+use crate::feed::FeedStream;
+use crate::models::{
+    OhlcCandle, OhlcDataResponse, OrderBookData, OrderInfo, PublicTrade, TickerInfo,
+};
+use crate::rest_client::{Authenticated, AuthenticatedClient, KrakenClient};
+use crate::ws_models::{
+    CandleData, OrderBookEntry, TradeData, WsBalancesMessage, WsBookMessage, WsCandlesMessage,
+    WsTickerMessage, WsTradesMessage,
+};
+
+/// REST fallback for the WebSocket feeds, for networks where WS is blocked.
+///
+/// Each stream polls one endpoint every `interval` and yields only when the
+/// result differs from the previous poll, using the same item types as the
+/// corresponding `KrakenWsClient` feed. Book polls are always full snapshots
+/// (`is_snapshot()` is `true`). A failed poll is logged through `tracing` and
+/// retried on the next tick; streams run until dropped.
+///
+/// Every poll costs a request, so keep `interval` within the REST rate limits
+/// (private endpoints especially).
+#[derive(Clone, Debug)]
+pub struct RestPoller<S> {
+    client: KrakenClient<S>,
+    interval: Duration,
+}
+
+impl<S: Clone + Send + Sync + 'static> RestPoller<S> {
+    pub fn new(client: KrakenClient<S>, interval: Duration) -> Self {
+        Self { client, interval }
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// `/0/public/Ticker` for `pair`, like `KrakenWsClient::ticker_stream`.
+    pub fn ticker_stream(&self, pair: &str) -> FeedStream<WsTickerMessage> {
+        let client = self.client.clone();
+        let pair = pair.to_string();
+        poll(self.interval, move || {
+            let client = client.clone();
+            let pair = pair.clone();
+            async move {
+                let response = client.get_ticker_information(&pair).await?;
+                Ok(response
+                    .tickers
+                    .values()
+                    .next()
+                    .map(|info| ticker_message(&pair, info)))
+            }
+        })
+    }
+
+    /// `/0/public/Depth` for `pair`, `depth` levels per side, like
+    /// `KrakenWsClient::book_stream`.
+    pub fn book_stream(&self, pair: &str, depth: u32) -> FeedStream<WsBookMessage> {
+        let client = self.client.clone();
+        let pair = pair.to_string();
+        let depth = depth.to_string();
+        poll(self.interval, move || {
+            let client = client.clone();
+            let pair = pair.clone();
+            let depth = depth.clone();
+            async move {
+                let params = [("pair", pair.as_str()), ("count", depth.as_str())];
+                let response = client.get_order_book(&params).await?;
+                Ok(response
+                    .orderbook
+                    .values()
+                    .next()
+                    .map(|book| book_message(&pair, book)))
+            }
+        })
+    }
+
+    /// `/0/public/OHLC` for `pair` in `interval`-minute candles, like
+    /// `KrakenWsClient::candles_stream`. The first item carries the history
+    /// Kraken returns (up to 720 candles); later items carry the still-open
+    /// candle plus any newly closed ones.
+    pub fn candles_stream(&self, pair: &str, interval: u32) -> FeedStream<WsCandlesMessage> {
+        let client = self.client.clone();
+        let pair = pair.to_string();
+        let since = Arc::new(Mutex::new(None::<String>));
+        poll(self.interval, move || {
+            let client = client.clone();
+            let pair = pair.clone();
+            let since = since.clone();
+            async move {
+                let minutes = interval.to_string();
+                let mut params = vec![("pair", pair.as_str()), ("interval", minutes.as_str())];
+                let cursor = lock(&since).clone();
+                if let Some(cursor) = &cursor {
+                    params.push(("since", cursor));
+                }
+                let response = client.get_ohlc_data(&params).await?;
+                *lock(&since) = Some(response.last.to_string());
+                Ok(Some(WsCandlesMessage {
+                    channel: "ohlc".to_string(),
+                    symbol: pair,
+                    interval,
+                    data: candles(response).map(candle).collect(),
+                }))
+            }
+        })
+    }
+
+    /// `/0/public/Trades` for `pair`, like `KrakenWsClient::trades_stream`.
+    /// Polls with Kraken's `since` cursor, so each item holds only new trades.
+    pub fn trades_stream(&self, pair: &str) -> FeedStream<WsTradesMessage> {
+        let client = self.client.clone();
+        let pair = pair.to_string();
+        let since = Arc::new(Mutex::new(None::<String>));
+        poll(self.interval, move || {
+            let client = client.clone();
+            let pair = pair.clone();
+            let since = since.clone();
+            async move {
+                let mut params = vec![("pair", pair.as_str())];
+                let cursor = lock(&since).clone();
+                if let Some(cursor) = &cursor {
+                    params.push(("since", cursor));
+                }
+                let response = client.get_recent_trades(&params).await?;
+                *lock(&since) = Some(response.last.to_string());
+                let trades: Vec<TradeData> =
+                    response.trades.into_values().flatten().map(trade).collect();
+                Ok((!trades.is_empty()).then(|| WsTradesMessage {
+                    channel: "trade".to_string(),
+                    symbol: pair,
+                    trades,
+                }))
+            }
+        })
+    }
+
+    /// Pass `candles` (e.g. `KrakenWsClient::candles_stream`) through, filling
+    /// any hole a reconnect left with candles fetched from `/0/public/OHLC`.
+    ///
+    /// A gap is a message whose earliest candle starts more than one interval
+    /// after the latest candle seen for its symbol and interval. The missing
+    /// candles are yielded as one message just before it, so consumers see
+    /// every candle in order. If the fetch fails the gap is logged at `warn`
+    /// and the live message passes through as is.
+    pub fn fill_candle_gaps(
+        &self,
+        candles: impl Stream<Item = WsCandlesMessage> + Send + 'static,
+    ) -> FeedStream<WsCandlesMessage> {
+        let client = self.client.clone();
+        let latest: HashMap<(String, u32), u64> = HashMap::new();
+        let filled = stream::unfold(
+            (Box::pin(candles), client, latest),
+            |(mut candles, client, mut latest)| async move {
+                let message = candles.next().await?;
+                let key = (message.symbol.clone(), message.interval);
+                let first = message.data.iter().map(|c| c.time).min();
+                let mut out = Vec::with_capacity(2);
+                if let (Some(&last), Some(first)) = (latest.get(&key), first) {
+                    if first > last + u64::from(message.interval) * 60 {
+                        match missing_candles(&client, &message, last, first).await {
+                            Ok(missing) if !missing.data.is_empty() => out.push(missing),
+                            Ok(_) => {}
+                            Err(e) => tracing::warn!(
+                                symbol = %message.symbol,
+                                from = last,
+                                to = first,
+                                error = %e,
+                                "could not fill candle gap over REST"
+                            ),
+                        }
+                    }
+                }
+                if let Some(newest) = message.data.iter().map(|c| c.time).max() {
+                    let seen = latest.entry(key).or_insert(newest);
+                    *seen = (*seen).max(newest);
+                }
+                out.push(message);
+                Some((stream::iter(out), (candles, client, latest)))
+            },
+        );
+        FeedStream::from_stream(filled.flatten())
+    }
+}
+
+/// The candles strictly between `last` and `first` for `live`'s symbol and
+/// interval, as a message like `live`.
+async fn missing_candles<S>(
+    client: &KrakenClient<S>,
+    live: &WsCandlesMessage,
+    last: u64,
+    first: u64,
+) -> KrakenResult<WsCandlesMessage> {
+    let (minutes, since) = (live.interval.to_string(), last.to_string());
+    let params = [
+        ("pair", live.symbol.as_str()),
+        ("interval", minutes.as_str()),
+        ("since", since.as_str()),
+    ];
+    let response = client.get_ohlc_data(&params).await?;
+    let mut data: Vec<CandleData> = candles(response)
+        .map(candle)
+        .filter(|c| c.time > last && c.time < first)
+        .collect();
+    data.sort_by_key(|c| c.time);
+    Ok(WsCandlesMessage {
+        channel: live.channel.clone(),
+        symbol: live.symbol.clone(),
+        interval: live.interval,
+        data,
+    })
+}
+
+impl RestPoller<Authenticated> {
+    /// `/0/private/OpenOrders`, keyed by txid. Yields the full set whenever an
+    /// order is added, removed or changes (e.g. a partial fill).
+    pub fn open_orders_stream(&self) -> FeedStream<HashMap<String, OrderInfo>> {
+        let client: AuthenticatedClient = self.client.clone();
+        poll(self.interval, move || {
+            let client = client.clone();
+            async move { Ok(Some(client.get_open_orders(&[]).await?.open)) }
+        })
+    }
+
+    /// `/0/private/Balance`, like `KrakenWsClient::balances_stream`.
+    pub fn balances_stream(&self) -> FeedStream<WsBalancesMessage> {
+        let client: AuthenticatedClient = self.client.clone();
+        poll(self.interval, move || {
+            let client = client.clone();
+            async move {
+                Ok(Some(WsBalancesMessage {
+                    channel: "balances".to_string(),
+                    balances: client.get_balance().await?.balances,
+                }))
+            }
+        })
+    }
+}
+
+struct PollState<T, F> {
+    ticks: Interval,
+    fetch: F,
+    last: Option<T>,
+}
+
+/// Call `fetch` every `interval`, yielding results that differ from the last one.
+fn poll<T, F, Fut>(interval: Duration, fetch: F) -> FeedStream<T>
+where
+    T: PartialEq + Clone + Send + 'static,
+    F: FnMut() -> Fut + Send + 'static,
+    Fut: Future<Output = KrakenResult<Option<T>>> + Send,
+{
+    let mut ticks = tokio::time::interval(interval);
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let state = PollState {
+        ticks,
+        fetch,
+        last: None,
+    };
+    FeedStream::from_stream(stream::unfold(state, |mut state| async move {
+        loop {
+            state.ticks.tick().await;
+            match (state.fetch)().await {
+                Ok(Some(item)) if state.last.as_ref() != Some(&item) => {
+                    state.last = Some(item.clone());
+                    return Some((item, state));
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!(error = %e, "REST poll failed; retrying next tick"),
+            }
+        }
+    }))
+}
+
+fn ticker_message(pair: &str, info: &TickerInfo) -> WsTickerMessage {
+    WsTickerMessage {
+        channel: "ticker".to_string(),
+        symbol: pair.to_string(),
+        best_ask_price: info.a[0].clone(),
+        best_ask_quantity: info.a[2].clone(),
+        best_bid_price: info.b[0].clone(),
+        best_bid_quantity: info.b[2].clone(),
+        last_trade_price: info.c[0].clone(),
+        last_trade_quantity: info.c[1].clone(),
+        volume_24h: info.v[1].clone(),
+        vwap_24h: info.p[1].clone(),
+        trades_24h: info.t[1],
+        low_24h: info.l[1].clone(),
+        high_24h: info.h[1].clone(),
+        open_24h: info.o.clone(),
+    }
+}
+
+fn book_message(pair: &str, book: &OrderBookData) -> WsBookMessage {
+    let levels = |side: &[(String, String, u64)]| {
+        side.iter()
+            .map(|(price, quantity, _)| OrderBookEntry {
+                price: price.clone(),
+                quantity: quantity.clone(),
+            })
+            .collect()
+    };
+    WsBookMessage {
+        channel: "book".to_string(),
+        update_type: Some("snapshot".to_string()),
+        symbol: pair.to_string(),
+        bids: levels(&book.bids),
+        asks: levels(&book.asks),
+    }
+}
+
+/// The candles of the one pair asked for, whatever Kraken named it.
+fn candles(response: OhlcDataResponse) -> impl Iterator<Item = OhlcCandle> {
+    response.candles.into_values().flatten()
+}
+
+fn candle(ohlc: OhlcCandle) -> CandleData {
+    CandleData {
+        time: ohlc.time,
+        open: ohlc.open,
+        high: ohlc.high,
+        low: ohlc.low,
+        close: ohlc.close,
+        volume: ohlc.volume,
+    }
+}
+
+fn trade(public: PublicTrade) -> TradeData {
+    TradeData {
+        price: public.price,
+        quantity: public.volume,
+        time: public.time.amount().to_f64_lossy().unwrap_or_default() as u64,
+        side: public.side.as_str().to_string(),
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use futures_util::stream::{Stream, StreamExt};
+use tokio::task::JoinHandle;
+
+use crate::error::KrakenResult;
+use crate::exchange::Side;
+use crate::numeric::Amount;
+use crate::state::{SharedStateStore, StateHandle};
+use crate::ws_models::{ExecutionData, WsExecutionsMessage};
+
+/// Net position per pair in base currency (positive long, negative short),
+/// kept up to date from fills.
+///
+/// Feed it from the WebSocket `executions` channel (`follow_executions`), or
+/// by hand with `apply_fill`; `set_position` seeds it, e.g. from balances at
+/// start-up. Pairs are keyed exactly as given, so use one naming (`BTC/USD`
+/// or `XBTUSD`) throughout. `with_store` keeps them across restarts. Clones
+/// share the same positions.
+#[derive(Debug, Clone, Default)]
+pub struct PositionTracker {
+    positions: Arc<Mutex<HashMap<String, f64>>>,
+    state: Option<StateHandle>,
+}
+
+impl PositionTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Restore positions saved under `key` in `store`, and save them there
+    /// after every change from now on. A failed save is logged at `warn`.
+    pub fn with_store(mut self, store: SharedStateStore, key: &str) -> KrakenResult<Self> {
+        let state = StateHandle::new(store, key);
+        if let Some(saved) = state.load::<HashMap<String, f64>>()? {
+            self.lock().extend(saved);
+        }
+        self.state = Some(state);
+        Ok(self)
+    }
+
+    /// Net position in `pair`; zero if nothing has been recorded.
+    pub fn position(&self, pair: &str) -> f64 {
+        self.lock().get(pair).copied().unwrap_or(0.0)
+    }
+
+    /// Every non-flat position.
+    pub fn positions(&self) -> HashMap<String, f64> {
+        self.lock()
+            .iter()
+            .filter(|(_, volume)| **volume != 0.0)
+            .map(|(pair, volume)| (pair.clone(), *volume))
+            .collect()
+    }
+
+    /// Overwrite the position in `pair`.
+    pub fn set_position(&self, pair: &str, volume: f64) {
+        let mut positions = self.lock();
+        positions.insert(pair.to_string(), volume);
+        self.persist(&positions);
+    }
+
+    /// Record a fill of `volume` on `side`.
+    pub fn apply_fill(&self, pair: &str, side: Side, volume: f64) {
+        let signed = match side {
+            Side::Buy => volume,
+            Side::Sell => -volume,
+        };
+        let mut positions = self.lock();
+        *positions.entry(pair.to_string()).or_default() += signed;
+        self.persist(&positions);
+    }
+
+    /// Record one WebSocket execution.
+    pub fn apply_execution(&self, execution: &ExecutionData) -> KrakenResult<()> {
+        let volume = Amount(&execution.quantity).to_f64_lossy()?;
+        let side = match execution.side.as_str() {
+            "sell" => Side::Sell,
+            _ => Side::Buy,
+        };
+        self.apply_fill(&execution.symbol, side, volume);
+        Ok(())
+    }
+
+    /// Keep positions updated from `executions` (e.g.
+    /// `ws.executions_stream()`) in a background task, until the stream ends
+    /// or the returned handle is aborted. Executions with an unreadable
+    /// quantity are logged and skipped.
+    pub fn follow_executions(
+        &self,
+        executions: impl Stream<Item = WsExecutionsMessage> + Send + 'static,
+    ) -> JoinHandle<()> {
+        let tracker = self.clone();
+        tokio::spawn(async move {
+            let mut executions = std::pin::pin!(executions);
+            while let Some(message) = executions.next().await {
+                for execution in &message.executions {
+                    if let Err(e) = tracker.apply_execution(execution) {
+                        tracing::warn!(exec_id = %execution.exec_id, error = %e, "skipped execution");
+                    }
+                }
+            }
+        })
+    }
+
+    fn persist(&self, positions: &HashMap<String, f64>) {
+        if let Some(state) = &self.state {
+            state.save_or_warn(positions);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, f64>> {
+        self.positions.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
@@ -0,0 +1,453 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures_util::stream::{Stream, StreamExt};
+use tokio::task::JoinHandle;
+
+use crate::clock::{self, Clock, SharedClock};
+use crate::error::{KrakenError, KrakenResult};
+use crate::exchange::{OrderRequest, Side};
+use crate::numeric::IntoAmount;
+use crate::order_book::OrderBook;
+use crate::ws_models::WsTickerMessage;
+
+/// Best bid and ask for one symbol, and how old they are.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Touch {
+    pub bid: String,
+    pub ask: String,
+    pub age: Duration,
+}
+
+/// Limits `QuoteCache::market_buy` / `market_sell` check against the current
+/// book before building a market order. Unset limits aren't checked.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MarketOrderGuard {
+    /// Widest spread accepted, in basis points of the mid price
+    pub max_spread_bps: Option<f64>,
+    /// Largest expected slippage accepted, in basis points: how far the
+    /// volume-weighted fill price from walking the book is from the best price
+    pub max_slippage_bps: Option<f64>,
+}
+
+impl MarketOrderGuard {
+    pub fn with_max_spread_bps(mut self, bps: f64) -> Self {
+        self.max_spread_bps = Some(bps);
+        self
+    }
+
+    pub fn with_max_slippage_bps(mut self, bps: f64) -> Self {
+        self.max_slippage_bps = Some(bps);
+        self
+    }
+}
+
+#[derive(Default)]
+struct Quotes {
+    tickers: HashMap<String, (WsTickerMessage, Instant)>,
+    books: HashMap<String, (OrderBook, Instant)>,
+}
+
+/// The latest ticker and book per symbol, stamped with when they arrived, and
+/// order helpers that price off them.
+///
+/// Feed it from any source (`follow_tickers` / `follow_books` with a WebSocket,
+/// `RestPoller` or `Replay` stream, or `update_ticker` / `update_book` by hand).
+/// With `with_max_age` set, the helpers refuse to build an order from a quote
+/// older than that and return `KrakenError::StaleMarketData`, so a stalled feed
+/// doesn't lead to trading on old prices. They always refuse when no quote has
+/// arrived for the symbol. `market_buy` / `market_sell` only look at quotes
+/// when a `MarketOrderGuard` is set, and then refuse to trade into a wide
+/// spread or a thin book.
+///
+/// ```no_run
+/// # async fn run(ws: onise::ws_client::KrakenWsClient) -> onise::error::KrakenResult<()> {
+/// use std::time::Duration;
+/// use onise::exchange::{ExchangeClient, Side};
+/// use onise::quotes::QuoteCache;
+///
+/// let quotes = QuoteCache::new().with_max_age(Duration::from_secs(5));
+/// quotes.follow_tickers(ws.ticker_stream());
+/// // ...
+/// let order = quotes.marketable_limit("BTC/USD", Side::Buy, "0.01", 0.001)?;
+/// ws.place_order(&order).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct QuoteCache {
+    quotes: Arc<Mutex<Quotes>>,
+    max_age: Option<Duration>,
+    market_guard: Option<MarketOrderGuard>,
+    clock: SharedClock,
+}
+
+impl Default for QuoteCache {
+    fn default() -> Self {
+        Self {
+            quotes: Arc::default(),
+            max_age: None,
+            market_guard: None,
+            clock: clock::system(),
+        }
+    }
+}
+
+impl QuoteCache {
+    /// An empty cache that accepts quotes of any age.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Refuse to price orders off quotes older than `max_age`.
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Check spread and expected slippage before `market_buy` / `market_sell`.
+    pub fn with_market_guard(mut self, guard: MarketOrderGuard) -> Self {
+        self.market_guard = Some(guard);
+        self
+    }
+
+    /// Stamp and age quotes with `clock` rather than the system clock.
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    pub fn update_ticker(&self, ticker: WsTickerMessage) {
+        let mut quotes = self.quotes.lock().unwrap();
+        quotes
+            .tickers
+            .insert(ticker.symbol.clone(), (ticker, self.clock.now()));
+    }
+
+    pub fn update_book(&self, book: OrderBook) {
+        let mut quotes = self.quotes.lock().unwrap();
+        quotes
+            .books
+            .insert(book.symbol.clone(), (book, self.clock.now()));
+    }
+
+    /// Keep the cache updated from `tickers` in a background task, until the
+    /// stream ends or the returned handle is aborted.
+    pub fn follow_tickers(
+        &self,
+        tickers: impl Stream<Item = WsTickerMessage> + Send + 'static,
+    ) -> JoinHandle<()> {
+        let cache = self.clone();
+        tokio::spawn(async move {
+            let mut tickers = std::pin::pin!(tickers);
+            while let Some(ticker) = tickers.next().await {
+                cache.update_ticker(ticker);
+            }
+        })
+    }
+
+    /// Keep the cache updated from `books` (e.g. `Replay::order_book_stream`)
+    /// in a background task.
+    pub fn follow_books(
+        &self,
+        books: impl Stream<Item = OrderBook> + Send + 'static,
+    ) -> JoinHandle<()> {
+        let cache = self.clone();
+        tokio::spawn(async move {
+            let mut books = std::pin::pin!(books);
+            while let Some(book) = books.next().await {
+                cache.update_book(book);
+            }
+        })
+    }
+
+    /// The latest ticker for `symbol` and its age.
+    pub fn ticker(&self, symbol: &str) -> Option<(WsTickerMessage, Duration)> {
+        let quotes = self.quotes.lock().unwrap();
+        let (ticker, at) = quotes.tickers.get(symbol)?;
+        Some((ticker.clone(), self.age(*at)))
+    }
+
+    /// The latest book for `symbol` and its age.
+    pub fn book(&self, symbol: &str) -> Option<(OrderBook, Duration)> {
+        let quotes = self.quotes.lock().unwrap();
+        let (book, at) = quotes.books.get(symbol)?;
+        Some((book.clone(), self.age(*at)))
+    }
+
+    fn age(&self, at: Instant) -> Duration {
+        self.clock.now().saturating_duration_since(at)
+    }
+
+    /// Best bid and ask for `symbol` from whichever of its book and ticker is
+    /// newer, checked against `with_max_age`.
+    pub fn touch(&self, symbol: &str) -> KrakenResult<Touch> {
+        let from_book = self.book(symbol).and_then(|(book, age)| {
+            Some(Touch {
+                bid: book.best_bid()?.price,
+                ask: book.best_ask()?.price,
+                age,
+            })
+        });
+        let from_ticker = self.ticker(symbol).map(|(ticker, age)| Touch {
+            bid: ticker.best_bid_price,
+            ask: ticker.best_ask_price,
+            age,
+        });
+        let touch = match (from_book, from_ticker) {
+            (Some(book), Some(ticker)) if ticker.age < book.age => Some(ticker),
+            (Some(book), _) => Some(book),
+            (None, ticker) => ticker,
+        };
+        let stale = |age| KrakenError::StaleMarketData {
+            symbol: symbol.to_string(),
+            age,
+            max_age: self.max_age,
+        };
+        let touch = touch.ok_or_else(|| stale(None))?;
+        if self.max_age.is_some_and(|max_age| touch.age > max_age) {
+            return Err(stale(Some(touch.age)));
+        }
+        Ok(touch)
+    }
+
+    /// A market buy of `volume`, checked against `with_market_guard` (and then
+    /// `with_max_age`) if a guard is set.
+    pub fn market_buy(
+        &self,
+        symbol: &str,
+        volume: impl Into<String>,
+    ) -> KrakenResult<OrderRequest> {
+        self.guarded_market(symbol, Side::Buy, volume.into())
+    }
+
+    /// A market sell of `volume`; see `market_buy`.
+    pub fn market_sell(
+        &self,
+        symbol: &str,
+        volume: impl Into<String>,
+    ) -> KrakenResult<OrderRequest> {
+        self.guarded_market(symbol, Side::Sell, volume.into())
+    }
+
+    fn guarded_market(
+        &self,
+        symbol: &str,
+        side: Side,
+        volume: String,
+    ) -> KrakenResult<OrderRequest> {
+        let Some(guard) = self.market_guard else {
+            return Ok(OrderRequest::market(symbol, side, volume));
+        };
+        let touch = self.touch(symbol)?;
+        let (bid, ask) = match (touch.bid.parse::<f64>(), touch.ask.parse::<f64>()) {
+            (Ok(bid), Ok(ask)) => (bid, ask),
+            _ => {
+                return Err(KrakenError::InvalidUsage(format!(
+                    "unparseable {symbol} quote {}/{}",
+                    touch.bid, touch.ask
+                )))
+            }
+        };
+        if let Some(max_bps) = guard.max_spread_bps {
+            let spread_bps = (ask - bid) / ((ask + bid) / 2.0) * 10_000.0;
+            if spread_bps > max_bps {
+                return Err(KrakenError::SpreadTooWide {
+                    symbol: symbol.to_string(),
+                    spread_bps,
+                    max_bps,
+                });
+            }
+        }
+        if let Some(max_bps) = guard.max_slippage_bps {
+            let quantity: f64 = volume
+                .parse()
+                .map_err(|_| KrakenError::InvalidAmount(volume.clone()))?;
+            let expected_bps = self.expected_slippage_bps(symbol, side, quantity);
+            if expected_bps.is_none_or(|bps| bps > max_bps) {
+                return Err(KrakenError::SlippageTooHigh {
+                    symbol: symbol.to_string(),
+                    expected_bps,
+                    max_bps,
+                });
+            }
+        }
+        Ok(OrderRequest::market(symbol, side, volume))
+    }
+
+    /// How far, in basis points, the volume-weighted price of taking `volume`
+    /// from the far side of the freshest quote is from its best price; `None`
+    /// if the visible levels can't fill it (a ticker only shows the best one).
+    pub fn expected_slippage_bps(&self, symbol: &str, side: Side, volume: f64) -> Option<f64> {
+        let levels: Vec<(f64, f64)> = self
+            .far_levels(symbol, side)
+            .iter()
+            .filter_map(|(price, quantity)| Some((price.parse().ok()?, quantity.parse().ok()?)))
+            .collect();
+        let best = levels.first()?.0;
+        let (mut remaining, mut cost) = (volume, 0.0);
+        for (price, quantity) in levels {
+            let take = remaining.min(quantity);
+            cost += take * price;
+            remaining -= take;
+            if remaining <= 0.0 {
+                let average = cost / volume;
+                return Some((average - best).abs() / best * 10_000.0);
+            }
+        }
+        None
+    }
+
+    /// The side a `side` market order takes from, best level first, from the
+    /// book if it is at least as fresh as the ticker.
+    fn far_levels(&self, symbol: &str, side: Side) -> Vec<(String, String)> {
+        let book = self.book(symbol);
+        let ticker = self.ticker(symbol);
+        match (book, ticker) {
+            (Some((book, book_age)), ticker)
+                if ticker.as_ref().is_none_or(|(_, age)| book_age <= *age) =>
+            {
+                let levels = match side {
+                    Side::Buy => book.asks(),
+                    Side::Sell => book.bids(),
+                };
+                levels.into_iter().map(|l| (l.price, l.quantity)).collect()
+            }
+            (_, Some((ticker, _))) => match side {
+                Side::Buy => vec![(ticker.best_ask_price, ticker.best_ask_quantity)],
+                Side::Sell => vec![(ticker.best_bid_price, ticker.best_bid_quantity)],
+            },
+            _ => Vec::new(),
+        }
+    }
+
+    /// A limit order joining the near side of the book: at the best bid to buy,
+    /// the best ask to sell.
+    pub fn limit_at_touch(
+        &self,
+        symbol: &str,
+        side: Side,
+        volume: impl IntoAmount,
+    ) -> KrakenResult<OrderRequest> {
+        let touch = self.touch(symbol)?;
+        let price = match side {
+            Side::Buy => touch.bid,
+            Side::Sell => touch.ask,
+        };
+        Ok(OrderRequest::limit(symbol, side, volume, price))
+    }
+
+    /// A limit order crossing the spread, priced `slippage` (a fraction, e.g.
+    /// 0.001 for 0.1%) beyond the far side so it fills like a market order
+    /// but never worse than that.
+    pub fn marketable_limit(
+        &self,
+        symbol: &str,
+        side: Side,
+        volume: impl IntoAmount,
+        slippage: f64,
+    ) -> KrakenResult<OrderRequest> {
+        let touch = self.touch(symbol)?;
+        let (far, factor) = match side {
+            Side::Buy => (touch.ask, 1.0 + slippage),
+            Side::Sell => (touch.bid, 1.0 - slippage),
+        };
+        let reference: f64 = far.parse().map_err(|_| {
+            KrakenError::InvalidUsage(format!("unparseable {symbol} quote price {far:?}"))
+        })?;
+        let decimals = far
+            .split_once('.')
+            .map_or(0, |(_, fraction)| fraction.len());
+        let price = format!("{:.decimals$}", reference * factor);
+        Ok(OrderRequest::limit(symbol, side, volume, price))
+    }
+
+    /// A limit order sized in quote currency: buy `quote_amount` worth of the
+    /// base asset (spend 100 USD of BTC), or sell enough to raise it. Kraken
+    /// spot no longer accepts volumes in quote currency (`viqc`), so this walks
+    /// the far side of the book for the volume-weighted price of
+    /// `quote_amount`, moves it `slippage` (a fraction) against the order, and
+    /// sizes the base volume at that price.
+    ///
+    /// The order is a limit at that price, so a buy never spends more than
+    /// `quote_amount` and a sell never raises less; if the book moves further
+    /// than `slippage` it rests rather than chasing. The price keeps the book's
+    /// price decimals and the volume is rounded (down to buy, up to sell) to
+    /// the decimals its quantities are quoted with. Fails with
+    /// `KrakenError::SlippageTooHigh` (no expected slippage) if the visible
+    /// levels can't absorb `quote_amount`.
+    pub fn quote_volume_order(
+        &self,
+        symbol: &str,
+        side: Side,
+        quote_amount: f64,
+        slippage: f64,
+    ) -> KrakenResult<OrderRequest> {
+        if quote_amount.is_nan() || quote_amount <= 0.0 {
+            return Err(KrakenError::InvalidAmount(quote_amount.to_string()));
+        }
+        self.touch(symbol)?;
+        let levels = self.far_levels(symbol, side);
+        let decimals = |value: &str| value.split_once('.').map_or(0, |(_, f)| f.len());
+        let price_decimals = levels.first().map_or(0, |(price, _)| decimals(price));
+        let volume_decimals = levels
+            .iter()
+            .map(|(_, quantity)| decimals(quantity))
+            .max()
+            .unwrap_or(0);
+
+        let (mut remaining, mut base) = (quote_amount, 0.0);
+        for (price, quantity) in &levels {
+            let (Ok(price), Ok(quantity)) = (price.parse::<f64>(), quantity.parse::<f64>()) else {
+                continue;
+            };
+            if price * quantity >= remaining {
+                base += remaining / price;
+                remaining = 0.0;
+                break;
+            }
+            base += quantity;
+            remaining -= price * quantity;
+        }
+        if remaining > 0.0 || base <= 0.0 {
+            return Err(KrakenError::SlippageTooHigh {
+                symbol: symbol.to_string(),
+                expected_bps: None,
+                max_bps: slippage * 10_000.0,
+            });
+        }
+
+        let average = quote_amount / base;
+        let price_scale = 10f64.powi(price_decimals as i32);
+        let volume_scale = 10f64.powi(volume_decimals as i32);
+        let (price, volume) = match side {
+            Side::Buy => {
+                let price = (average * (1.0 + slippage) * price_scale).floor() / price_scale;
+                (price, (quote_amount / price * volume_scale).floor())
+            }
+            Side::Sell => {
+                let price = (average * (1.0 - slippage) * price_scale).ceil() / price_scale;
+                (price, (quote_amount / price * volume_scale).ceil())
+            }
+        };
+        Ok(OrderRequest::limit(
+            symbol,
+            side,
+            format!("{:.volume_decimals$}", volume / volume_scale),
+            format!("{price:.price_decimals$}"),
+        ))
+    }
+}
+
+impl std::fmt::Debug for QuoteCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let quotes = self.quotes.lock().unwrap();
+        f.debug_struct("QuoteCache")
+            .field("tickers", &quotes.tickers.len())
+            .field("books", &quotes.books.len())
+            .field("max_age", &self.max_age)
+            .field("market_guard", &self.market_guard)
+            .finish()
+    }
+}
@@ -7,6 +7,8 @@ use governor::{
 use std::num::NonZeroU32;
 use tokio::time::Duration;
 
+use crate::error::{KrakenError, KrakenResult};
+
 /// Our custom RateLimiter struct that wraps governor's RateLimiter
 pub struct RateLimiter {
     // Note the full generic signature in 0.8:
@@ -34,6 +36,17 @@ impl RateLimiter {
         Self { inner: limiter }
     }
 
+    /// A limiter that hands out at most one permit per `interval`, with no burst.
+    /// A zero `interval` is `KrakenError::InvalidUsage`.
+    pub fn with_interval(interval: Duration) -> KrakenResult<Self> {
+        let quota = Quota::with_period(interval).ok_or_else(|| {
+            KrakenError::InvalidUsage("rate limiter interval must be non-zero".to_string())
+        })?;
+        Ok(Self {
+            inner: GovRateLimiter::direct(quota),
+        })
+    }
+
     /// Acquire 1 permit, asynchronously blocking until available.
     pub async fn acquire(&self) {
         self.inner.until_ready().await;
@@ -0,0 +1,286 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::models::{LedgerInfo, TradeInfo};
+
+/// Ledger types that move funds in or out of the account (or between wallets).
+/// Every entry of these types must be accounted for via `Reconciler::expect_transfer`.
+const TRANSFER_TYPES: &[&str] = &["deposit", "withdrawal", "transfer", "staking", "earn"];
+
+/// Ledger types that are expected to carry a fee without a matching fill.
+const FEE_BEARING_TYPES: &[&str] = &["deposit", "withdrawal", "margin", "rollover", "settled"];
+
+/// One problem found while replaying the ledger against tracked state.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Discrepancy {
+    /// A `trade` ledger entry references a trade ID we have no fill for.
+    MissingFill {
+        ledger_id: String,
+        refid: String,
+        asset: String,
+    },
+
+    /// A tracked fill never showed up in the ledger.
+    UnmatchedFill { trade_id: String },
+
+    /// The ledger charged a fee that does not match what the fill(s) reported,
+    /// or a fee appeared on an entry type that should not carry one.
+    UnexpectedFee {
+        ledger_id: String,
+        refid: String,
+        asset: String,
+        charged: f64,
+        expected: f64,
+    },
+
+    /// A deposit/withdrawal/transfer that was not registered as expected.
+    UnaccountedTransfer {
+        ledger_id: String,
+        refid: String,
+        ledger_type: String,
+        asset: String,
+        amount: f64,
+    },
+
+    /// The most recent ledger balance for an asset differs from the tracked balance.
+    BalanceMismatch {
+        asset: String,
+        tracked: f64,
+        ledger: f64,
+    },
+
+    /// A ledger entry whose numeric fields could not be parsed.
+    MalformedEntry { ledger_id: String, field: String },
+}
+
+/// Outcome of `Reconciler::reconcile`.
+#[derive(Debug, Clone, Default)]
+pub struct ReconciliationReport {
+    /// Number of ledger entries inspected
+    pub entries_checked: usize,
+    pub discrepancies: Vec<Discrepancy>,
+}
+
+impl ReconciliationReport {
+    /// `true` if the ledger fully agrees with the tracked balances and fills.
+    pub fn is_clean(&self) -> bool {
+        self.discrepancies.is_empty()
+    }
+}
+
+/// Replays ledger entries (from `/0/private/Ledgers` or `/0/private/QueryLedgers`)
+/// against the balances and fills an application has been tracking, and reports
+/// anything that does not line up.
+///
+/// ```ignore
+/// let mut reconciler = Reconciler::new();
+/// reconciler.track_balance("ZUSD", "1000.0");
+/// reconciler.record_fills(&trades.trades);
+/// reconciler.expect_transfer("FTQcuak-V6Za8qrWnhzTx67yYHz8Tg");
+/// let report = reconciler.reconcile(&ledgers.ledger);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Reconciler {
+    tracked_balances: HashMap<String, f64>,
+    /// trade ID => fee reported by the fill
+    fills: HashMap<String, f64>,
+    expected_transfers: HashSet<String>,
+    tolerance: f64,
+}
+
+impl Default for Reconciler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Reconciler {
+    /// Create an empty reconciler with a tolerance of `1e-8` for amount comparisons.
+    pub fn new() -> Self {
+        Self {
+            tracked_balances: HashMap::new(),
+            fills: HashMap::new(),
+            expected_transfers: HashSet::new(),
+            tolerance: 1e-8,
+        }
+    }
+
+    /// Absolute tolerance used when comparing balances and fees.
+    pub fn with_tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Record the balance the application believes it holds for `asset`
+    /// (e.g. "ZUSD", "XXBT"). Unparseable amounts are ignored.
+    pub fn track_balance(&mut self, asset: &str, amount: &str) {
+        if let Ok(value) = amount.parse::<f64>() {
+            self.tracked_balances.insert(asset.to_string(), value);
+        }
+    }
+
+    /// Record every balance from e.g. `AccountBalanceResponse::balances`.
+    pub fn track_balances(&mut self, balances: &HashMap<String, String>) {
+        for (asset, amount) in balances {
+            self.track_balance(asset, amount);
+        }
+    }
+
+    /// Record a fill the application knows about, keyed by trade ID.
+    pub fn record_fill(&mut self, trade_id: &str, fill: &TradeInfo) {
+        let fee = fill.fee.parse::<f64>().unwrap_or(0.0);
+        self.fills.insert(trade_id.to_string(), fee);
+    }
+
+    /// Record every fill from e.g. `TradesHistoryResponse::trades`.
+    pub fn record_fills(&mut self, fills: &HashMap<String, TradeInfo>) {
+        for (trade_id, fill) in fills {
+            self.record_fill(trade_id, fill);
+        }
+    }
+
+    /// Mark a deposit/withdrawal/transfer reference ID as accounted for.
+    pub fn expect_transfer(&mut self, refid: &str) {
+        self.expected_transfers.insert(refid.to_string());
+    }
+
+    /// Replay `ledger` (ledger ID => entry) and produce a report.
+    pub fn reconcile(&self, ledger: &HashMap<String, LedgerInfo>) -> ReconciliationReport {
+        let mut report = ReconciliationReport {
+            entries_checked: ledger.len(),
+            discrepancies: Vec::new(),
+        };
+
+        // Replay in chronological order so the "latest balance" per asset is correct.
+        let mut entries: Vec<(&String, &LedgerInfo)> = ledger.iter().collect();
+        entries.sort_by(|a, b| a.1.time.total_cmp(&b.1.time).then_with(|| a.0.cmp(b.0)));
+
+        let mut latest_balance: HashMap<&str, f64> = HashMap::new();
+        // refid => total fee charged across all ledger legs of that trade
+        let mut trade_fees: HashMap<&str, (f64, &str, &str)> = HashMap::new();
+        let mut seen_fills: HashSet<&str> = HashSet::new();
+
+        for (ledger_id, entry) in entries {
+            let Some(amount) = parse_field(ledger_id, "amount", &entry.amount, &mut report) else {
+                continue;
+            };
+            let Some(fee) = parse_field(ledger_id, "fee", &entry.fee, &mut report) else {
+                continue;
+            };
+            if let Some(balance) = parse_field(ledger_id, "balance", &entry.balance, &mut report) {
+                latest_balance.insert(entry.asset.as_str(), balance);
+            }
+
+            let ledger_type = entry.ledger_type.as_str();
+            if ledger_type == "trade" {
+                if self.fills.contains_key(&entry.refid) {
+                    seen_fills.insert(entry.refid.as_str());
+                    let slot = trade_fees.entry(entry.refid.as_str()).or_insert((
+                        0.0,
+                        ledger_id.as_str(),
+                        entry.asset.as_str(),
+                    ));
+                    slot.0 += fee;
+                    if fee != 0.0 {
+                        slot.1 = ledger_id.as_str();
+                        slot.2 = entry.asset.as_str();
+                    }
+                } else {
+                    report.discrepancies.push(Discrepancy::MissingFill {
+                        ledger_id: ledger_id.clone(),
+                        refid: entry.refid.clone(),
+                        asset: entry.asset.clone(),
+                    });
+                }
+            } else if TRANSFER_TYPES.contains(&ledger_type)
+                && !self.expected_transfers.contains(&entry.refid)
+            {
+                report.discrepancies.push(Discrepancy::UnaccountedTransfer {
+                    ledger_id: ledger_id.clone(),
+                    refid: entry.refid.clone(),
+                    ledger_type: entry.ledger_type.clone(),
+                    asset: entry.asset.clone(),
+                    amount,
+                });
+            }
+
+            if ledger_type != "trade"
+                && !FEE_BEARING_TYPES.contains(&ledger_type)
+                && fee.abs() > self.tolerance
+            {
+                report.discrepancies.push(Discrepancy::UnexpectedFee {
+                    ledger_id: ledger_id.clone(),
+                    refid: entry.refid.clone(),
+                    asset: entry.asset.clone(),
+                    charged: fee,
+                    expected: 0.0,
+                });
+            }
+        }
+
+        // Compare the fees charged per trade with what the fill reported.
+        let mut fee_checks: Vec<_> = trade_fees.into_iter().collect();
+        fee_checks.sort_by(|a, b| a.0.cmp(b.0));
+        for (refid, (charged, ledger_id, asset)) in fee_checks {
+            let expected = self.fills.get(refid).copied().unwrap_or(0.0);
+            if (charged - expected).abs() > self.tolerance {
+                report.discrepancies.push(Discrepancy::UnexpectedFee {
+                    ledger_id: ledger_id.to_string(),
+                    refid: refid.to_string(),
+                    asset: asset.to_string(),
+                    charged,
+                    expected,
+                });
+            }
+        }
+
+        // Fills that the ledger never mentioned.
+        let mut unmatched: Vec<&String> = self
+            .fills
+            .keys()
+            .filter(|id| !seen_fills.contains(id.as_str()))
+            .collect();
+        unmatched.sort();
+        for trade_id in unmatched {
+            report.discrepancies.push(Discrepancy::UnmatchedFill {
+                trade_id: trade_id.clone(),
+            });
+        }
+
+        // Latest ledger balance vs tracked balance.
+        let mut assets: Vec<&String> = self.tracked_balances.keys().collect();
+        assets.sort();
+        for asset in assets {
+            let tracked = self.tracked_balances[asset];
+            if let Some(&ledger) = latest_balance.get(asset.as_str()) {
+                if (tracked - ledger).abs() > self.tolerance {
+                    report.discrepancies.push(Discrepancy::BalanceMismatch {
+                        asset: asset.clone(),
+                        tracked,
+                        ledger,
+                    });
+                }
+            }
+        }
+
+        report
+    }
+}
+
+/// Parse a numeric ledger field, recording a `MalformedEntry` if it is not a number.
+fn parse_field(
+    ledger_id: &str,
+    field: &str,
+    raw: &str,
+    report: &mut ReconciliationReport,
+) -> Option<f64> {
+    match raw.parse::<f64>() {
+        Ok(value) => Some(value),
+        Err(_) => {
+            report.discrepancies.push(Discrepancy::MalformedEntry {
+                ledger_id: ledger_id.to_string(),
+                field: field.to_string(),
+            });
+            None
+        }
+    }
+}
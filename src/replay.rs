@@ -0,0 +1,294 @@
+use std::path::Path;
+use std::time::Duration;
+
+use futures_util::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader, Lines};
+
+use crate::error::KrakenResult;
+use crate::feed::FeedStream;
+use crate::order_book::OrderBook;
+use crate::ws_models::{
+    self, WsBookMessage, WsCandlesMessage, WsIncomingMessage, WsTickerMessage, WsTradesMessage,
+};
+
+/// One line of a recording: when the frame arrived (Unix milliseconds) and the
+/// frame itself, verbatim.
+#[derive(Serialize, Deserialize)]
+struct RecordedFrame<'a> {
+    t: u64,
+    #[serde(borrow)]
+    msg: &'a RawValue,
+}
+
+/// How fast a `Replay` plays a recording back.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplaySpeed {
+    /// Keep the recorded gaps between messages
+    Realtime,
+    /// Divide the recorded gaps by this factor (`Accelerated(10.0)` is 10x)
+    Accelerated(f64),
+    /// No waiting at all
+    AsFastAsPossible,
+}
+
+impl ReplaySpeed {
+    fn pause(self, gap_ms: u64) -> Option<Duration> {
+        let gap = Duration::from_millis(gap_ms);
+        match self {
+            ReplaySpeed::Realtime => Some(gap),
+            ReplaySpeed::Accelerated(factor) if factor > 0.0 => Some(gap.div_f64(factor)),
+            ReplaySpeed::Accelerated(_) | ReplaySpeed::AsFastAsPossible => None,
+        }
+        .filter(|pause| !pause.is_zero())
+    }
+}
+
+type Source = Lines<BufReader<Box<dyn AsyncRead + Send + Unpin>>>;
+
+/// Plays back a WebSocket recording made by `Recorder`, through the same
+/// parsing and typed `FeedStream`s as a live `KrakenWsClient`, so a strategy
+/// can be backtested against the exact event pipeline it runs on live.
+///
+/// The recording is read lazily, line by line, as the stream is polled. Lines
+/// that don't parse are skipped with a warning; a read error ends the stream.
+///
+/// ```no_run
+/// # async fn run() -> onise::error::KrakenResult<()> {
+/// use futures_util::StreamExt;
+/// use onise::replay::{Replay, ReplaySpeed};
+///
+/// let replay = Replay::open("btc-usd.ndjson").await?.with_speed(ReplaySpeed::Accelerated(60.0));
+/// let mut books = replay.order_book_stream("BTC/USD", 10);
+/// while let Some(book) = books.next().await {
+///     println!("{:?} / {:?}", book.best_bid(), book.best_ask());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct Replay {
+    source: Source,
+    speed: ReplaySpeed,
+}
+
+impl Replay {
+    /// Replay the recording at `path`, as fast as possible unless `with_speed` says otherwise.
+    pub async fn open(path: impl AsRef<Path>) -> KrakenResult<Self> {
+        let file = tokio::fs::File::open(path).await?;
+        Ok(Self::from_reader(file))
+    }
+
+    /// Replay a recording read from `reader`.
+    pub fn from_reader(reader: impl AsyncRead + Send + Unpin + 'static) -> Self {
+        let reader: Box<dyn AsyncRead + Send + Unpin> = Box::new(reader);
+        Self {
+            source: BufReader::new(reader).lines(),
+            speed: ReplaySpeed::AsFastAsPossible,
+        }
+    }
+
+    pub fn with_speed(mut self, speed: ReplaySpeed) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Every recorded message, parsed as `KrakenWsClient::message_stream` would.
+    pub fn message_stream(self) -> FeedStream<WsIncomingMessage> {
+        self.select(Some)
+    }
+
+    /// Recorded `ticker` updates.
+    pub fn ticker_stream(self) -> FeedStream<WsTickerMessage> {
+        self.select(|msg| match msg {
+            WsIncomingMessage::TickerMsg(ticker) => Some(ticker),
+            _ => None,
+        })
+    }
+
+    /// Recorded `book` snapshots and updates.
+    pub fn book_stream(self) -> FeedStream<WsBookMessage> {
+        self.select(|msg| match msg {
+            WsIncomingMessage::BookMsg(book) => Some(book),
+            _ => None,
+        })
+    }
+
+    /// Recorded `ohlc` candles.
+    pub fn candles_stream(self) -> FeedStream<WsCandlesMessage> {
+        self.select(|msg| match msg {
+            WsIncomingMessage::CandlesMsg(candles) => Some(candles),
+            _ => None,
+        })
+    }
+
+    /// Recorded `trade` updates.
+    pub fn trades_stream(self) -> FeedStream<WsTradesMessage> {
+        self.select(|msg| match msg {
+            WsIncomingMessage::TradesMsg(trades) => Some(trades),
+            _ => None,
+        })
+    }
+
+    /// The `symbol` book rebuilt by `OrderBook`, yielded after every recorded
+    /// `book` message for it.
+    pub fn order_book_stream(self, symbol: &str, depth: usize) -> FeedStream<OrderBook> {
+        let book = OrderBook::new(symbol, depth);
+        let books = self.book_stream().scan(book, |book, msg| {
+            let changed = msg.symbol == book.symbol;
+            book.apply(&msg);
+            futures_util::future::ready(Some(changed.then(|| book.clone())))
+        });
+        FeedStream::from_stream(books.filter_map(futures_util::future::ready))
+    }
+
+    fn select<T: Send + 'static>(
+        self,
+        select: fn(WsIncomingMessage) -> Option<T>,
+    ) -> FeedStream<T> {
+        let state = (self, None::<u64>);
+        let messages = stream::unfold(state, move |(mut replay, mut last)| async move {
+            loop {
+                let line = match replay.source.next_line().await {
+                    Ok(Some(line)) => line,
+                    Ok(None) => return None,
+                    Err(e) => {
+                        tracing::warn!(error = %e, "replay read failed; ending replay");
+                        return None;
+                    }
+                };
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let frame: RecordedFrame = match serde_json::from_str(&line) {
+                    Ok(frame) => frame,
+                    Err(e) => {
+                        tracing::warn!(error = %e, "skipping malformed recording line");
+                        continue;
+                    }
+                };
+                if let Some(pause) =
+                    last.and_then(|last| replay.speed.pause(frame.t.saturating_sub(last)))
+                {
+                    tokio::time::sleep(pause).await;
+                }
+                last = Some(frame.t);
+                let item = match ws_models::parse_incoming(frame.msg.get()) {
+                    Ok(msg) => select(msg),
+                    Err(e) => {
+                        tracing::warn!(error = %e, "skipping unparseable recorded message");
+                        None
+                    }
+                };
+                if let Some(item) = item {
+                    return Some((item, (replay, last)));
+                }
+            }
+        });
+        FeedStream::from_stream(messages)
+    }
+}
+
+#[cfg(feature = "ws")]
+pub use recorder::Recorder;
+
+#[cfg(feature = "ws")]
+mod recorder {
+    use std::path::Path;
+    use std::sync::Arc;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use serde_json::value::RawValue;
+    use tokio::io::{AsyncWrite, AsyncWriteExt, BufWriter};
+    use tokio::sync::{broadcast, oneshot};
+    use tokio::task::JoinHandle;
+
+    use super::RecordedFrame;
+    use crate::error::{KrakenError, KrakenResult};
+    use crate::ws_client::KrakenWsClient;
+
+    /// Records every inbound frame of a `KrakenWsClient` as newline-delimited
+    /// JSON (`{"t":<unix ms>,"msg":<frame>}`), the format `Replay` reads.
+    ///
+    /// Recording runs in a background task from `start` until the connection
+    /// closes or `stop` is called. Frames missed because the writer fell more
+    /// than `MESSAGE_BUFFER` behind are logged and skipped.
+    pub struct Recorder {
+        stop: Option<oneshot::Sender<()>>,
+        task: JoinHandle<KrakenResult<u64>>,
+    }
+
+    impl Recorder {
+        /// Record `client`'s frames from now on into `writer`.
+        pub fn start<W>(client: &KrakenWsClient, writer: W) -> Self
+        where
+            W: AsyncWrite + Send + Unpin + 'static,
+        {
+            let frames = client.raw_messages();
+            let (stop, stopped) = oneshot::channel();
+            let task = tokio::spawn(record(frames, writer, stopped));
+            Self {
+                stop: Some(stop),
+                task,
+            }
+        }
+
+        /// Record `client`'s frames into a new file at `path`.
+        pub async fn create(client: &KrakenWsClient, path: impl AsRef<Path>) -> KrakenResult<Self> {
+            let file = tokio::fs::File::create(path).await?;
+            Ok(Self::start(client, BufWriter::new(file)))
+        }
+
+        /// Stop recording, flush, and return how many frames were written.
+        pub async fn stop(mut self) -> KrakenResult<u64> {
+            if let Some(stop) = self.stop.take() {
+                let _ = stop.send(());
+            }
+            match (&mut self.task).await {
+                Ok(written) => written,
+                Err(e) => Err(KrakenError::InvalidUsage(format!(
+                    "recorder task failed: {e}"
+                ))),
+            }
+        }
+    }
+
+    async fn record<W>(
+        mut frames: broadcast::Receiver<Arc<str>>,
+        mut writer: W,
+        mut stopped: oneshot::Receiver<()>,
+    ) -> KrakenResult<u64>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let mut written = 0;
+        let mut line = Vec::new();
+        loop {
+            let text = tokio::select! {
+                _ = &mut stopped => break,
+                frame = frames.recv() => match frame {
+                    Ok(text) => text,
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        tracing::warn!(missed, "recorder fell behind; frames skipped");
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+            };
+            let Ok(msg) = serde_json::from_str::<&RawValue>(&text) else {
+                tracing::warn!("not recording a non-JSON frame");
+                continue;
+            };
+            let t = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_millis() as u64);
+            line.clear();
+            serde_json::to_writer(&mut line, &RecordedFrame { t, msg })?;
+            line.push(b'\n');
+            writer.write_all(&line).await?;
+            written += 1;
+        }
+        writer.flush().await?;
+        Ok(written)
+    }
+}
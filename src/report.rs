@@ -0,0 +1,310 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt::Write as _;
+
+use futures_util::TryStreamExt;
+use serde::Serialize;
+
+use crate::assets;
+use crate::error::KrakenResult;
+use crate::models::{LedgerInfo, TradeInfo};
+use crate::numeric::Amount;
+use crate::params::{self, LedgersParams, TimeBound, TradesHistoryParams};
+use crate::AuthenticatedClient;
+
+/// Volumes below this are treated as an emptied lot, so float dust doesn't
+/// leave phantom holdings behind.
+const DUST: f64 = 1e-12;
+
+/// How a sale's cost basis is picked from the lots bought before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LotMethod {
+    /// Oldest lots are sold first
+    Fifo,
+    /// Every sale costs the running average price of the holding
+    AverageCost,
+}
+
+/// One sale and the gain or loss it realized, in the pair's quote currency.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Disposal {
+    pub trade_id: String,
+    /// Unix timestamp of the trade
+    pub time: f64,
+    /// Display symbol sold, e.g. "BTC"
+    pub asset: String,
+    /// Display symbol it was sold for, e.g. "USD"
+    pub quote: String,
+    pub volume: f64,
+    /// Cost of the trade less its fee
+    pub proceeds: f64,
+    /// What the sold volume cost, fees included
+    pub cost_basis: f64,
+    pub pnl: f64,
+    /// Volume sold beyond what the report saw bought (e.g. held from before
+    /// its start), counted at zero cost
+    pub uncovered_volume: f64,
+}
+
+/// Totals for one asset traded against one quote currency.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct AssetPnl {
+    pub asset: String,
+    pub quote: String,
+    pub bought: f64,
+    pub sold: f64,
+    /// Fees paid on both sides
+    pub fees: f64,
+    pub proceeds: f64,
+    pub cost_basis: f64,
+    pub realized_pnl: f64,
+    /// Volume still held from the report's buys
+    pub open_volume: f64,
+    /// What `open_volume` cost
+    pub open_cost: f64,
+}
+
+/// Realized profit and loss per asset from a trade history, for tax
+/// reporting and the like.
+///
+/// Each trade's base and quote asset are read from its two `trade` ledger
+/// entries (the trade ID is their `refid`), so pairs never need splitting by
+/// name; trades without both entries are listed in `unmatched` and left out.
+/// Buys add lots costing their cost plus fee, sells realize proceeds (cost
+/// less fee) against lots picked by `LotMethod`. Figures are per pair, in its
+/// quote currency: BTC sold for USD and for EUR are reported separately.
+///
+/// `to_json` / `to_csv` export it; the CSV has one row per disposal.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PnlReport {
+    pub method: LotMethod,
+    /// Oldest first
+    pub disposals: Vec<Disposal>,
+    /// Sorted by asset, then quote
+    pub assets: Vec<AssetPnl>,
+    pub unmatched: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Lot {
+    volume: f64,
+    cost: f64,
+}
+
+impl PnlReport {
+    /// Build the report from trades and ledger entries keyed by ID, as
+    /// `TradesHistory` and `Ledgers` return them (in any order). Ledger
+    /// entries that aren't trade legs are ignored.
+    pub fn build<'a>(
+        trades: impl IntoIterator<Item = (&'a String, &'a TradeInfo)>,
+        ledgers: impl IntoIterator<Item = &'a LedgerInfo>,
+        method: LotMethod,
+    ) -> KrakenResult<Self> {
+        let mut legs: HashMap<&str, Vec<&LedgerInfo>> = HashMap::new();
+        for entry in ledgers {
+            if entry.ledger_type == "trade" {
+                legs.entry(entry.refid.as_str()).or_default().push(entry);
+            }
+        }
+        let mut trades: Vec<_> = trades.into_iter().collect();
+        trades.sort_by(|(a_id, a), (b_id, b)| a.time.total_cmp(&b.time).then(a_id.cmp(b_id)));
+
+        let mut report = PnlReport {
+            method,
+            disposals: Vec::new(),
+            assets: Vec::new(),
+            unmatched: Vec::new(),
+        };
+        let mut books: BTreeMap<(String, String), (AssetPnl, VecDeque<Lot>)> = BTreeMap::new();
+        for (id, trade) in trades {
+            let buy = trade.trade_type == "buy";
+            let Some((asset, quote)) = legs
+                .get(id.as_str())
+                .and_then(|legs| base_and_quote(legs, buy))
+            else {
+                report.unmatched.push(id.clone());
+                continue;
+            };
+            let volume = Amount(&trade.vol).to_f64_lossy()?;
+            let cost = Amount(&trade.cost).to_f64_lossy()?;
+            let fee = Amount(&trade.fee).to_f64_lossy()?;
+            let (totals, lots) = books
+                .entry((asset.clone(), quote.clone()))
+                .or_insert_with(|| {
+                    let totals = AssetPnl {
+                        asset: asset.clone(),
+                        quote: quote.clone(),
+                        ..AssetPnl::default()
+                    };
+                    (totals, VecDeque::new())
+                });
+            totals.fees += fee;
+            if buy {
+                totals.bought += volume;
+                add_lot(
+                    lots,
+                    method,
+                    Lot {
+                        volume,
+                        cost: cost + fee,
+                    },
+                );
+                continue;
+            }
+
+            let (cost_basis, uncovered_volume) = take_lots(lots, volume);
+            let proceeds = cost - fee;
+            let pnl = proceeds - cost_basis;
+            totals.sold += volume;
+            totals.proceeds += proceeds;
+            totals.cost_basis += cost_basis;
+            totals.realized_pnl += pnl;
+            report.disposals.push(Disposal {
+                trade_id: id.clone(),
+                time: trade.time,
+                asset,
+                quote,
+                volume,
+                proceeds,
+                cost_basis,
+                pnl,
+                uncovered_volume,
+            });
+        }
+        report.assets = books
+            .into_values()
+            .map(|(mut totals, lots)| {
+                totals.open_volume = lots.iter().map(|lot| lot.volume).sum();
+                totals.open_cost = lots.iter().map(|lot| lot.cost).sum();
+                totals
+            })
+            .collect();
+        Ok(report)
+    }
+
+    /// Realized PnL across every asset quoted in `quote` (a display symbol).
+    pub fn realized_pnl(&self, quote: &str) -> f64 {
+        self.assets
+            .iter()
+            .filter(|totals| totals.quote == quote)
+            .map(|totals| totals.realized_pnl)
+            .sum()
+    }
+
+    /// The whole report as pretty-printed JSON.
+    pub fn to_json(&self) -> KrakenResult<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// One CSV row per disposal, with a header row.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "trade_id,time,asset,quote,volume,proceeds,cost_basis,pnl,uncovered_volume\n",
+        );
+        for d in &self.disposals {
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{},{},{},{},{}",
+                csv_field(&d.trade_id),
+                d.time,
+                csv_field(&d.asset),
+                csv_field(&d.quote),
+                d.volume,
+                d.proceeds,
+                d.cost_basis,
+                d.pnl,
+                d.uncovered_volume
+            );
+        }
+        csv
+    }
+}
+
+/// The display symbols of a trade's base (the leg moving with the trade
+/// side: in on a buy, out on a sell) and quote, from its ledger legs.
+fn base_and_quote(legs: &[&LedgerInfo], buy: bool) -> Option<(String, String)> {
+    let [a, b] = legs else {
+        return None;
+    };
+    let incoming = |entry: &LedgerInfo| !entry.amount.trim_start().starts_with('-');
+    let (base, quote) = match (incoming(a) == buy, incoming(b) == buy) {
+        (true, false) => (a, b),
+        (false, true) => (b, a),
+        _ => return None,
+    };
+    Some((
+        assets::display_name(&base.asset),
+        assets::display_name(&quote.asset),
+    ))
+}
+
+fn add_lot(lots: &mut VecDeque<Lot>, method: LotMethod, lot: Lot) {
+    match (method, lots.front_mut()) {
+        (LotMethod::AverageCost, Some(holding)) => {
+            holding.volume += lot.volume;
+            holding.cost += lot.cost;
+        }
+        _ => lots.push_back(lot),
+    }
+}
+
+/// Remove `volume` from the front of `lots`, returning its cost and whatever
+/// volume the lots didn't cover.
+fn take_lots(lots: &mut VecDeque<Lot>, volume: f64) -> (f64, f64) {
+    let (mut remaining, mut cost) = (volume, 0.0);
+    while remaining > DUST {
+        let Some(lot) = lots.front_mut() else {
+            break;
+        };
+        let take = remaining.min(lot.volume);
+        let taken_cost = lot.cost * take / lot.volume;
+        cost += taken_cost;
+        lot.volume -= take;
+        lot.cost -= taken_cost;
+        remaining -= take;
+        if lot.volume <= DUST {
+            lots.pop_front();
+        }
+    }
+    (cost, remaining.max(0.0))
+}
+
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+impl AuthenticatedClient {
+    /// Fetch every trade matching `filters` and the `trade` ledger entries
+    /// over the same period, and build a `PnlReport`. Timestamp bounds carry
+    /// over to the ledger query; txid bounds don't (ledger IDs differ), so
+    /// the ledger side is then unbounded on that end.
+    pub async fn pnl_report(
+        &self,
+        filters: &TradesHistoryParams,
+        method: LotMethod,
+    ) -> KrakenResult<PnlReport> {
+        let mut ledger_filters = LedgersParams::new().with_entry_type("trade");
+        if let Some(TimeBound::Timestamp(start)) = filters.start {
+            ledger_filters = ledger_filters.with_start(start);
+        }
+        if let Some(TimeBound::Timestamp(end)) = filters.end {
+            ledger_filters = ledger_filters.with_end(end);
+        }
+        let (trade_params, ledger_params) = (filters.to_params(), ledger_filters.to_params());
+        let (trades, ledgers) = tokio::try_join!(
+            self.trades_history_stream(&params::as_pairs(&trade_params))
+                .try_collect::<Vec<_>>(),
+            self.ledgers_stream(&params::as_pairs(&ledger_params))
+                .try_collect::<Vec<_>>(),
+        )?;
+        PnlReport::build(
+            trades.iter().map(|(id, trade)| (id, trade)),
+            ledgers.iter().map(|(_, entry)| entry),
+            method,
+        )
+    }
+}
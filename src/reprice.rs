@@ -0,0 +1,144 @@
+use crate::error::{KrakenError, KrakenResult};
+use crate::exchange::Side;
+use crate::models::{AddOrderResponse, AmendOrderResponse, OrderInfo};
+use crate::params::{AddOrderRequest, AmendOrderRequest, QueryOrdersParams};
+use crate::rest_client::AuthenticatedClient;
+
+/// Which way `reprice_order` moved the order.
+#[derive(Debug)]
+pub enum RepriceOutcome {
+    /// Amended in place: same txid, queue priority kept where Kraken allows
+    Amended(AmendOrderResponse),
+    /// `AmendOrder` isn't permitted for the order, so it was cancelled and
+    /// its unfilled remainder placed again at the new price
+    Replaced {
+        /// The cancelled order
+        cancelled: String,
+        /// The new order, with the old one's `cl_ord_id` or `userref`
+        order: AddOrderResponse,
+    },
+}
+
+impl RepriceOutcome {
+    pub fn was_replaced(&self) -> bool {
+        matches!(self, RepriceOutcome::Replaced { .. })
+    }
+}
+
+/// Whether `error` is Kraken refusing `AmendOrder` for the order itself (its
+/// type or flags don't allow amending) rather than for the request, which is
+/// when `reprice_order` falls back to cancel and re-add.
+pub fn amend_not_permitted(error: &KrakenError) -> bool {
+    match error {
+        KrakenError::OrderError { message } | KrakenError::GeneralError { message } => {
+            message.to_ascii_lowercase().contains("amend")
+        }
+        KrakenError::Request { source, .. } => amend_not_permitted(source),
+        _ => false,
+    }
+}
+
+impl AuthenticatedClient {
+    // POST /0/private/AmendOrder, falling back to QueryOrders, CancelOrder, AddOrder
+    /// Move the limit price of open order `txid` to `new_price`.
+    ///
+    /// Tries `AmendOrder` first. If Kraken refuses to amend this order
+    /// (`amend_not_permitted`), looks the order up, cancels it and places its
+    /// unfilled volume again with the same pair, side, type, flags, leverage
+    /// and `cl_ord_id` (free again once the original is cancelled) or
+    /// `userref`. The replacement is validated before anything is cancelled.
+    /// The outcome says which path was taken. Any other amend error, e.g. an
+    /// unknown or already filled order, is returned as is. If the cancel
+    /// succeeds but the new order is rejected, the error is
+    /// `KrakenError::ReplaceFailed`: the order is gone.
+    pub async fn reprice_order(&self, txid: &str, new_price: &str) -> KrakenResult<RepriceOutcome> {
+        let amend = AmendOrderRequest::by_txid(txid).with_limit_price(new_price);
+        match self.amend(&amend).await {
+            Ok(amended) => return Ok(RepriceOutcome::Amended(amended)),
+            Err(e) if amend_not_permitted(&e) => {
+                tracing::info!(txid, error = %e, "amend not permitted, cancelling and re-adding")
+            }
+            Err(e) => return Err(e),
+        }
+
+        let mut orders = self.query_orders(&QueryOrdersParams::new([txid])).await?;
+        let order = orders.orders.remove(txid).ok_or_else(|| {
+            KrakenError::InvalidUsage(format!("QueryOrders did not return order {txid}"))
+        })?;
+        let replacement = replacement(&order, new_price)?;
+        self.cancel_order(&[("txid", txid)]).await?;
+        let placed = self
+            .add(&replacement)
+            .await
+            .map_err(|e| KrakenError::ReplaceFailed {
+                txid: txid.to_string(),
+                source: Box::new(e),
+            })?;
+        Ok(RepriceOutcome::Replaced {
+            cancelled: txid.to_string(),
+            order: placed,
+        })
+    }
+}
+
+/// The order replacing `order`'s unfilled volume at `new_price`.
+fn replacement(order: &OrderInfo, new_price: &str) -> KrakenResult<AddOrderRequest> {
+    let descr = &order.descr;
+    let side = match descr.side.as_str() {
+        "buy" => Side::Buy,
+        "sell" => Side::Sell,
+        other => {
+            return Err(KrakenError::InvalidUsage(format!(
+                "order has unknown side {other:?}"
+            )))
+        }
+    };
+    let remaining = subtract(&order.vol, &order.vol_exec).ok_or_else(|| {
+        KrakenError::InvalidUsage(format!(
+            "order has nothing left to re-add: vol {} executed {}",
+            order.vol, order.vol_exec
+        ))
+    })?;
+    let mut request = AddOrderRequest::new(&descr.pair, side, &descr.ordertype, remaining)
+        .with_oflags(order.flags());
+    // The limit price of a stop/take-profit-limit order is the secondary one
+    request = if descr.ordertype.ends_with("-limit") {
+        request.with_price(&descr.price).with_price2(new_price)
+    } else {
+        request.with_price(new_price)
+    };
+    if descr.leverage != "none" && !descr.leverage.is_empty() {
+        request = request.with_leverage(&descr.leverage);
+    }
+    // AddOrder refuses an order carrying both
+    request = match (&order.cl_ord_id, order.userref) {
+        (Some(cl_ord_id), _) => request.with_cl_ord_id(cl_ord_id),
+        (None, Some(userref)) if userref != 0 => request.with_userref(userref as i64),
+        _ => request,
+    };
+    request.validate()?;
+    Ok(request)
+}
+
+/// `a - b` for plain decimal strings, exactly; `None` unless positive.
+fn subtract(a: &str, b: &str) -> Option<String> {
+    let scale = [a, b]
+        .iter()
+        .map(|n| n.split_once('.').map_or(0, |(_, frac)| frac.len()))
+        .max()?;
+    let units = |n: &str| -> Option<i128> {
+        let (int, frac) = n.split_once('.').unwrap_or((n, ""));
+        format!("{int}{frac:0<scale$}").parse().ok()
+    };
+    let diff = units(a)? - units(b)?;
+    if diff <= 0 {
+        return None;
+    }
+    let digits = format!("{diff:0>width$}", width = scale + 1);
+    let (int, frac) = digits.split_at(digits.len() - scale);
+    Some(if scale == 0 {
+        int.to_string()
+    } else {
+        format!("{int}.{frac}")
+    })
+}
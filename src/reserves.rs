@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::{KrakenError, KrakenResult};
+
+/// One sibling on the path from a leaf to the Merkle root.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct MerkleProofNode {
+    /// Hex SHA-256
+    pub hash: String,
+    /// "left" or "right": which side of the running hash the sibling sits on
+    pub position: String,
+}
+
+/// A proof-of-reserves inclusion proof for one account record, as handed out
+/// by the auditor. Kraken's REST API has no documented endpoint for these, so
+/// the proof (and the audit's published root) come from the caller; this crate
+/// only checks them offline.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct InclusionProof {
+    pub audit_id: String,
+    /// The account's record ID in the audit
+    pub record_id: String,
+    /// Hex SHA-256 of the account's record
+    pub leaf_hash: String,
+    /// The balances the record committed to, by asset
+    pub balances: HashMap<String, String>,
+    /// Siblings from the leaf up to the root
+    pub path: Vec<MerkleProofNode>,
+    pub root_hash: String,
+}
+
+impl InclusionProof {
+    /// Check that `leaf_hash` hashes up `path` to `root_hash`: at each step the
+    /// running hash and the sibling are concatenated in the order `position`
+    /// gives (raw bytes, not hex) and hashed with SHA-256.
+    ///
+    /// This proves the record is part of the tree; whether `root_hash` is the
+    /// one the auditor published is checked by `verify_against`.
+    pub fn verify(&self) -> KrakenResult<()> {
+        let mut hash = self.decode("leaf_hash", &self.leaf_hash)?;
+        for (i, node) in self.path.iter().enumerate() {
+            let sibling = self.decode(&format!("path[{i}]"), &node.hash)?;
+            let mut hasher = Sha256::new();
+            match node.position.as_str() {
+                "left" => {
+                    hasher.update(sibling);
+                    hasher.update(hash);
+                }
+                "right" => {
+                    hasher.update(hash);
+                    hasher.update(sibling);
+                }
+                other => {
+                    return Err(self.invalid(format!(
+                        "path[{i}] has position {other:?}, expected \"left\" or \"right\""
+                    )))
+                }
+            }
+            hash = hasher.finalize().into();
+        }
+        if hash != self.decode("root_hash", &self.root_hash)? {
+            return Err(self.invalid(format!(
+                "path hashes to {}, not the root {}",
+                hex(&hash),
+                self.root_hash
+            )));
+        }
+        Ok(())
+    }
+
+    /// `verify`, plus a check that `root_hash` is `published_root`, the root
+    /// the auditor published for `audit_id` (compared as hex, ignoring case).
+    pub fn verify_against(&self, published_root: &str) -> KrakenResult<()> {
+        if !self.root_hash.eq_ignore_ascii_case(published_root) {
+            return Err(self.invalid(format!(
+                "proof root {} is not the published root {published_root}",
+                self.root_hash
+            )));
+        }
+        self.verify()
+    }
+
+    fn decode(&self, field: &str, value: &str) -> KrakenResult<[u8; 32]> {
+        let bytes = value.as_bytes();
+        if bytes.len() != 64 {
+            return Err(self.invalid(format!("{field} is not a hex SHA-256: {value:?}")));
+        }
+        let mut hash = [0; 32];
+        for (byte, pair) in hash.iter_mut().zip(bytes.chunks(2)) {
+            let digits = std::str::from_utf8(pair).ok();
+            *byte = digits
+                .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                .ok_or_else(|| self.invalid(format!("{field} is not a hex SHA-256: {value:?}")))?;
+        }
+        Ok(hash)
+    }
+
+    fn invalid(&self, reason: String) -> KrakenError {
+        KrakenError::InclusionProofInvalid {
+            audit_id: self.audit_id.clone(),
+            reason,
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
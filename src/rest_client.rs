@@ -0,0 +1,1460 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use reqwest::header::{
+    CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, USER_AGENT,
+};
+use reqwest::{Client as HttpClient, RequestBuilder, StatusCode};
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::audit::{AuditHandle, AuditRecord, AuditSink};
+use crate::clock::{self, Clock, SharedClock};
+use crate::deadman::{DeadMansSwitch, DeadmanFailureHandler, DeadmanState};
+use crate::environment::{self, Environment};
+use crate::expiry::ExpireTime;
+use crate::error::{KrakenError, KrakenResult};
+use crate::http_cache::{CachedResponse, MetadataCache};
+use crate::logging::{LoggerHandle, RequestLog, RequestLogger};
+use crate::metrics::{ConnectTimingLayer, Metrics, DEFAULT_CALLER};
+use crate::models::*;
+use crate::numeric::{AmountFormatter, PairPrecision};
+use crate::state::{SharedStateStore, StateHandle};
+use crate::params::{
+    self, AddOrderRequest, AmendOrderRequest, ClosedOrdersParams, EditOrderRequest,
+    OpenOrdersParams, OtcQuoteRequest, QueryOrdersParams,
+};
+use crate::{logging, signing};
+
+/// Default `User-Agent` sent with every REST request.
+pub const DEFAULT_USER_AGENT: &str = concat!("onise/", env!("CARGO_PKG_VERSION"));
+
+/// Header carrying the per-request UUID. The same ID is recorded on the
+/// `kraken_request` tracing span and attached to any error the request returns.
+pub const REQUEST_ID_HEADER: &str = "X-Request-ID";
+
+/// Private endpoints that place, change or cancel orders, move funds or stake.
+/// A read-only client (`with_read_only(true)`) refuses these locally.
+pub const MUTATING_ENDPOINTS: &[&str] = &[
+    "/0/private/AddOrder",
+    "/0/private/AddOrderBatch",
+    "/0/private/AmendOrder",
+    "/0/private/EditOrder",
+    "/0/private/CancelOrder",
+    "/0/private/CancelAll",
+    "/0/private/CancelAllOrdersAfter",
+    "/0/private/CancelOrderBatch",
+    "/0/private/Withdraw",
+    "/0/private/WithdrawCancel",
+    "/0/private/WalletTransfer",
+    "/0/private/CreateSubaccount",
+    "/0/private/AccountTransfer",
+    "/0/private/Staking/Stake",
+    "/0/private/Staking/Unstake",
+    "/0/private/UpdateOtcQuote",
+];
+
+/// A fully-read HTTP response, before the Kraken envelope is parsed.
+struct RawResponse {
+    request_id: String,
+    status: StatusCode,
+    headers: reqwest::header::HeaderMap,
+    body: bytes::Bytes,
+}
+
+impl RawResponse {
+    /// Parse the Kraken envelope, tagging any error with the request ID.
+    fn parse<T>(&self) -> KrakenResult<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        crate::envelope::parse(&self.body).map_err(|e| e.with_request_id(&self.request_id))
+    }
+}
+
+/// Typestate marker: no credentials, only public endpoints are callable.
+#[derive(Clone, Debug, Default)]
+pub struct Public;
+
+/// Typestate marker holding API credentials; unlocks the private endpoints.
+#[derive(Clone)]
+pub struct Authenticated {
+    api_key: String,
+    /// Keyed once from the secret
+    signer: signing::Signer,
+}
+
+impl std::fmt::Debug for Authenticated {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Authenticated")
+            .field("api_key", &self.api_key)
+            .field("api_secret", &"<redacted>")
+            .finish()
+    }
+}
+
+impl Authenticated {
+    /// A credential set to pass to `AuthenticatedClient::with_call_credentials`.
+    /// The secret is decoded and keyed into a `signing::Signer` once, here;
+    /// fails with `KrakenError::InvalidUsage` if it isn't valid base64.
+    pub fn new(api_key: impl Into<String>, api_secret: impl Into<String>) -> KrakenResult<Self> {
+        Ok(Self {
+            api_key: api_key.into(),
+            signer: signing::Signer::new(&api_secret.into())?,
+        })
+    }
+
+    /// The API key requests are signed for.
+    pub fn api_key(&self) -> &str {
+        &self.api_key
+    }
+
+    /// The signer keyed from the secret.
+    pub(crate) fn signer(&self) -> &signing::Signer {
+        &self.signer
+    }
+}
+
+/// A minimal client for **all** Kraken Spot REST endpoints.
+///
+/// The type parameter tracks whether credentials are present:
+/// - `PublicClient` (`KrakenClient<Public>`) only exposes market data endpoints.
+/// - `AuthenticatedClient` (`KrakenClient<Authenticated>`, the default) exposes everything.
+///
+/// Calling a private endpoint on a `PublicClient` is a compile-time error rather
+/// than an "API key not set" error at runtime.
+#[derive(Clone, Debug)]
+pub struct KrakenClient<S = Authenticated> {
+    credentials: S,
+    pub environment: Environment,
+    http: HttpClient,
+    user_agent: String,
+    metadata_cache: Option<Arc<MetadataCache>>,
+    hedge_delay: Option<Duration>,
+    logger: Option<LoggerHandle>,
+    metrics: Metrics,
+    read_only: bool,
+    audit: Option<AuditHandle>,
+    schema_drift: bool,
+    clock: SharedClock,
+    nonces: signing::NonceSource,
+    amount_formatter: Option<AmountFormatter>,
+    /// Who this client's requests are counted for in `Metrics::usage`
+    caller: String,
+}
+
+/// A client without credentials (public endpoints only).
+pub type PublicClient = KrakenClient<Public>;
+
+/// A client with credentials (public and private endpoints).
+pub type AuthenticatedClient = KrakenClient<Authenticated>;
+
+impl PublicClient {
+    /// Create a client for public endpoints.
+    /// - `base_url` overrides the REST URL (e.g. a mock server); `None` means `Environment::Production`.
+    pub fn new(base_url: Option<String>) -> Self {
+        let builder = Self::builder();
+        let builder = match base_url {
+            Some(base_url) => builder.base_url(base_url),
+            None => builder,
+        };
+        builder
+            .build()
+            .expect("default HTTP client configuration is valid")
+    }
+
+    /// Configure the HTTP client (timeouts, proxy) along with the environment,
+    /// user agent and credentials before building; see `KrakenClientBuilder`.
+    pub fn builder() -> KrakenClientBuilder<Public> {
+        KrakenClientBuilder {
+            credentials: Public,
+            environment: Environment::Production,
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            timeout: None,
+            connect_timeout: None,
+            proxy: None,
+        }
+    }
+
+    /// Attach credentials, keeping the HTTP pool, environment and caches.
+    /// The secret is decoded and keyed into a `signing::Signer` once, here;
+    /// fails with `KrakenError::InvalidUsage` if it isn't valid base64.
+    pub fn with_credentials(
+        self,
+        api_key: impl Into<String>,
+        api_secret: impl Into<String>,
+    ) -> KrakenResult<AuthenticatedClient> {
+        Ok(KrakenClient {
+            credentials: Authenticated::new(api_key, api_secret)?,
+            environment: self.environment,
+            http: self.http,
+            user_agent: self.user_agent,
+            metadata_cache: self.metadata_cache,
+            hedge_delay: self.hedge_delay,
+            logger: self.logger,
+            metrics: self.metrics,
+            read_only: self.read_only,
+            schema_drift: self.schema_drift,
+            audit: self.audit,
+            clock: self.clock,
+            nonces: self.nonces,
+            amount_formatter: self.amount_formatter,
+            caller: self.caller,
+        })
+    }
+}
+
+impl AuthenticatedClient {
+    /// Create a client for public and private endpoints.
+    /// - `base_url` overrides the REST URL (e.g. a mock server); `None` means `Environment::Production`.
+    /// - Fails with `KrakenError::InvalidUsage` if `api_secret` isn't valid base64.
+    pub fn new(
+        api_key: impl Into<String>,
+        api_secret: impl Into<String>,
+        base_url: Option<String>,
+    ) -> KrakenResult<Self> {
+        PublicClient::new(base_url).with_credentials(api_key, api_secret)
+    }
+
+    /// The API key this client signs with.
+    pub fn api_key(&self) -> &str {
+        &self.credentials.api_key
+    }
+
+    /// The same client signing with `credentials` instead, e.g. to act on a
+    /// subaccount's key from a manager's client for one call:
+    /// `client.with_call_credentials(&sub).get_balance()`. The HTTP pool,
+    /// environment, caches, metrics, logger and audit sink stay shared, and
+    /// nothing on `self` changes.
+    pub fn with_call_credentials(&self, credentials: &Authenticated) -> AuthenticatedClient {
+        KrakenClient {
+            credentials: credentials.clone(),
+            ..self.clone()
+        }
+    }
+
+    /// A credential-less view sharing the same HTTP pool, environment and caches.
+    pub fn to_public(&self) -> PublicClient {
+        KrakenClient {
+            credentials: Public,
+            environment: self.environment.clone(),
+            http: self.http.clone(),
+            user_agent: self.user_agent.clone(),
+            metadata_cache: self.metadata_cache.clone(),
+            hedge_delay: self.hedge_delay,
+            logger: self.logger.clone(),
+            metrics: self.metrics.clone(),
+            read_only: self.read_only,
+            schema_drift: self.schema_drift,
+            audit: self.audit.clone(),
+            clock: self.clock.clone(),
+            nonces: self.nonces.clone(),
+            amount_formatter: self.amount_formatter,
+            caller: self.caller.clone(),
+        }
+    }
+}
+
+/// Builds a `KrakenClient` with its own HTTP settings, from
+/// `KrakenClient::builder()`. Without `credentials` it builds a
+/// `PublicClient`; with them, an `AuthenticatedClient`.
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use onise::rest_client::KrakenClient;
+///
+/// # fn run() -> onise::error::KrakenResult<()> {
+/// let client = KrakenClient::builder()
+///     .timeout(Duration::from_secs(10))
+///     .connect_timeout(Duration::from_secs(2))
+///     .proxy("http://proxy.internal:3128")
+///     .credentials("api-key", "c2VjcmV0")?
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct KrakenClientBuilder<S = Public> {
+    credentials: S,
+    environment: Environment,
+    user_agent: String,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    proxy: Option<String>,
+}
+
+impl KrakenClientBuilder<Public> {
+    /// Sign private requests with this key; the built client is an
+    /// `AuthenticatedClient`. Fails with `KrakenError::InvalidUsage` if
+    /// `api_secret` isn't valid base64.
+    pub fn credentials(
+        self,
+        api_key: impl Into<String>,
+        api_secret: impl Into<String>,
+    ) -> KrakenResult<KrakenClientBuilder<Authenticated>> {
+        Ok(KrakenClientBuilder {
+            credentials: Authenticated::new(api_key, api_secret)?,
+            environment: self.environment,
+            user_agent: self.user_agent,
+            timeout: self.timeout,
+            connect_timeout: self.connect_timeout,
+            proxy: self.proxy,
+        })
+    }
+}
+
+impl<S> KrakenClientBuilder<S> {
+    /// Talk to `environment` (default `Environment::Production`).
+    pub fn environment(mut self, environment: Environment) -> Self {
+        self.environment = environment;
+        self
+    }
+
+    /// Send REST requests to `base_url` (e.g. a mock server) instead of the
+    /// environment's.
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.environment = Environment::custom_rest(base_url);
+        self
+    }
+
+    /// Send `user_agent` instead of `DEFAULT_USER_AGENT`.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Fail a request that hasn't completed within `timeout`, from connecting
+    /// to reading the body. No timeout by default.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Give up on a TCP/TLS connection that isn't up within `timeout`.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Send every request (HTTP and HTTPS) through the proxy at `url`, e.g.
+    /// "http://proxy:3128" or "socks5://proxy:1080". Credentials go in the
+    /// URL. Without one, the `HTTPS_PROXY` / `HTTP_PROXY` variables apply.
+    pub fn proxy(mut self, url: impl Into<String>) -> Self {
+        self.proxy = Some(url.into());
+        self
+    }
+
+    /// Build the client. Fails with `KrakenError::Reqwest` if the proxy URL
+    /// is invalid or the HTTP client can't be set up.
+    pub fn build(self) -> KrakenResult<KrakenClient<S>> {
+        let metrics = Metrics::new();
+        let mut http = HttpClient::builder().connector_layer(ConnectTimingLayer(metrics.clone()));
+        if let Some(timeout) = self.timeout {
+            http = http.timeout(timeout);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            http = http.connect_timeout(timeout);
+        }
+        if let Some(proxy) = &self.proxy {
+            http = http.proxy(reqwest::Proxy::all(proxy)?);
+        }
+        Ok(KrakenClient {
+            credentials: self.credentials,
+            environment: self.environment,
+            http: http.build()?,
+            user_agent: self.user_agent,
+            metadata_cache: None,
+            hedge_delay: None,
+            logger: None,
+            metrics,
+            read_only: false,
+            schema_drift: false,
+            audit: None,
+            clock: clock::system(),
+            nonces: signing::NonceSource::new(),
+            amount_formatter: None,
+            caller: DEFAULT_CALLER.to_string(),
+        })
+    }
+}
+
+impl<S> KrakenClient<S> {
+    /// Point the client at a different `Environment`.
+    pub fn with_environment(mut self, environment: Environment) -> Self {
+        self.environment = environment;
+        self
+    }
+
+    /// Send `user_agent` instead of `DEFAULT_USER_AGENT`.
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Log every request through `logger` (e.g. `logging::StderrLogger`).
+    /// Logged records never contain the `API-Key`/`API-Sign` values or `otp`.
+    pub fn with_request_logger(mut self, logger: impl RequestLogger + 'static) -> Self {
+        self.logger = Some(LoggerHandle(Arc::new(logger)));
+        self
+    }
+
+    /// Refuse every endpoint in `MUTATING_ENDPOINTS` locally with
+    /// `KrakenError::ReadOnly`, so a monitoring deployment can't trade or move
+    /// funds even with a full-permission key. Reads are unaffected. This only
+    /// covers REST; give a `KrakenWsClient` trading on the same key
+    /// `KrakenWsClient::with_read_only` too.
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// `true` if mutating endpoints are refused.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Record every private request (endpoint, sanitized params, nonce, outcome and
+    /// any txids) to `sink`, e.g. `audit::AuditLog::append_to_file("audit.jsonl")`.
+    pub fn with_audit_sink(mut self, sink: impl AuditSink + 'static) -> Self {
+        self.audit = Some(AuditHandle(Arc::new(sink)));
+        self
+    }
+
+    /// Flush the audit sink, if one is set (see `AuditSink::flush`).
+    pub fn flush_audit(&self) {
+        if let Some(audit) = &self.audit {
+            audit.0.flush();
+        }
+    }
+
+    /// Diagnostic mode: re-serialize every parsed response and log (at `warn`)
+    /// the fields Kraken sent that the model dropped, via
+    /// `schema_drift::missing_fields`. Costs a second parse per response, so
+    /// meant for keeping the models in sync with the API rather than production.
+    pub fn with_schema_drift_detection(mut self, enabled: bool) -> Self {
+        self.schema_drift = enabled;
+        self
+    }
+
+    /// Read the time from `clock` (e.g. a `clock::MockClock` in tests) for
+    /// metadata cache expiry and anything built on this client.
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// The clock this client reads the time from.
+    pub fn clock(&self) -> &SharedClock {
+        &self.clock
+    }
+
+    /// Draw private-request nonces from `nonces`. Clones of a client already
+    /// share one source; this is for separately built clients signing with
+    /// the same key.
+    pub fn with_nonce_source(mut self, nonces: signing::NonceSource) -> Self {
+        self.nonces = nonces;
+        self
+    }
+
+    /// Where this client's private-request nonces come from.
+    pub fn nonce_source(&self) -> &signing::NonceSource {
+        &self.nonces
+    }
+
+    /// The REST base URL currently in use.
+    pub fn base_url(&self) -> &str {
+        self.environment.rest_url()
+    }
+
+    /// Cache `/Assets`, `/AssetPairs` and `/SystemStatus` responses for `ttl`.
+    /// Once an entry expires it is revalidated with `If-None-Match`/`If-Modified-Since`
+    /// when the server provided an `ETag`/`Last-Modified`, otherwise refetched.
+    pub fn with_metadata_cache(mut self, ttl: Duration) -> Self {
+        self.metadata_cache = Some(Arc::new(MetadataCache::new(ttl)));
+        self
+    }
+
+    /// Write the prices and volumes of every typed `add` / `edit` request
+    /// with `formatter`, to the precision of the order's pair (looked up with
+    /// `pair_precision`, from the metadata cache when enabled), so
+    /// `0.30000000000000004` goes out as `0.3` instead of being rejected.
+    pub fn with_amount_formatter(mut self, formatter: AmountFormatter) -> Self {
+        self.amount_formatter = Some(formatter);
+        self
+    }
+
+    /// Hedge public market-data reads (`Time`, `Ticker`, `OHLC`, `Depth`, `Trades`,
+    /// `Spread`): if no response has arrived after `delay`, send a duplicate request
+    /// and use whichever answers first. An error from one attempt is only returned
+    /// if the other fails too. Private endpoints are never hedged.
+    pub fn with_hedged_reads(mut self, delay: Duration) -> Self {
+        self.hedge_delay = Some(delay);
+        self
+    }
+
+    /// Latency histograms (connect, time-to-first-byte and total, per endpoint)
+    /// for every request this client and the clients derived from it have sent.
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    /// Count this client's requests under `caller` (e.g. the service name) in
+    /// `Metrics::usage`, instead of `metrics::DEFAULT_CALLER`. Clients sharing
+    /// a `Metrics` keep their own callers.
+    pub fn with_caller(mut self, caller: impl Into<String>) -> Self {
+        self.caller = caller.into();
+        self
+    }
+
+    /// Drop every cached metadata response, forcing the next call to hit the API.
+    pub fn clear_metadata_cache(&self) {
+        if let Some(cache) = &self.metadata_cache {
+            cache.clear();
+        }
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // PUBLIC ENDPOINTS (Market Data)
+    // ─────────────────────────────────────────────────────────────
+
+    // GET /0/public/Time
+    pub async fn get_server_time(&self) -> KrakenResult<ServerTimeResponse> {
+        self.public_get("/0/public/Time").await
+    }
+
+    // GET /0/public/Time
+    /// Check a GTD `expire` against Kraken's clock rather than the local one;
+    /// see `ExpireTime::validate`.
+    pub async fn check_expire_time(&self, expire: &ExpireTime) -> KrakenResult<()> {
+        let server_time = self.get_server_time().await?;
+        expire.validate(UNIX_EPOCH + Duration::from_secs(server_time.unixtime))
+    }
+
+    // GET /0/public/SystemStatus
+    pub async fn get_system_status(&self) -> KrakenResult<SystemStatusResponse> {
+        self.metadata_get("/0/public/SystemStatus", &[]).await
+    }
+
+    // GET /0/public/Assets
+    pub async fn get_asset_info(&self, params: &[(&str, &str)]) -> KrakenResult<AssetInfoResponse> {
+        self.metadata_get("/0/public/Assets", params).await
+    }
+
+    // GET /0/public/AssetPairs
+    pub async fn get_asset_pairs(
+        &self,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<AssetPairsResponse> {
+        self.metadata_get("/0/public/AssetPairs", params).await
+    }
+
+    // GET /0/public/AssetPairs
+    /// How many decimals `pair`'s prices and volumes may carry. Served from
+    /// the metadata cache when enabled.
+    pub async fn pair_precision(&self, pair: &str) -> KrakenResult<PairPrecision> {
+        let pairs = self.get_asset_pairs(&[("pair", pair)]).await?;
+        let info = pairs.pairs.values().next().ok_or_else(|| {
+            KrakenError::InvalidUsage(format!("AssetPairs returned nothing for {pair}"))
+        })?;
+        Ok(PairPrecision::from(info))
+    }
+
+    // GET /0/public/AssetPairs
+    /// Check that `pair` offers `leverage` ("3" or "3:1") for `side` ("buy"
+    /// checks `leverage_buy`, "sell" `leverage_sell`), failing locally with
+    /// `KrakenError::InvalidLeverage` rather than an `EOrder:Invalid leverage`
+    /// rejection. "none" always passes; any other `side` is
+    /// `KrakenError::InvalidUsage`. Served from the metadata cache when enabled.
+    pub async fn validate_leverage(&self, pair: &str, side: &str, leverage: &str) -> KrakenResult<()> {
+        if !matches!(side, "buy" | "sell") {
+            return Err(KrakenError::InvalidUsage(format!(
+                "order side must be \"buy\" or \"sell\", got {side:?}"
+            )));
+        }
+        if leverage == "none" {
+            return Ok(());
+        }
+        let pairs = self.get_asset_pairs(&[("pair", pair)]).await?;
+        let info = pairs.pairs.values().next().ok_or_else(|| {
+            KrakenError::InvalidUsage(format!("AssetPairs returned nothing for {pair}"))
+        })?;
+        let allowed = match side {
+            "buy" => info.leverage_buy.clone(),
+            _ => info.leverage_sell.clone(),
+        }
+        .unwrap_or_default();
+        let requested = leverage.split(':').next().unwrap_or(leverage).trim();
+        if requested.parse::<u32>().is_ok_and(|n| allowed.contains(&n)) {
+            return Ok(());
+        }
+        Err(KrakenError::InvalidLeverage {
+            pair: pair.to_string(),
+            side: side.to_string(),
+            requested: leverage.to_string(),
+            allowed,
+        })
+    }
+
+    // GET /0/public/Ticker
+    pub async fn get_ticker_information(&self, pair: &str) -> KrakenResult<TickerResponse> {
+        let p = [("pair", pair)];
+        self.public_get_with_params("/0/public/Ticker", &p).await
+    }
+
+    // GET /0/public/OHLC
+    pub async fn get_ohlc_data(&self, params: &[(&str, &str)]) -> KrakenResult<OhlcDataResponse> {
+        self.public_get_with_params("/0/public/OHLC", params).await
+    }
+
+    // GET /0/public/Depth
+    pub async fn get_order_book(&self, params: &[(&str, &str)]) -> KrakenResult<OrderBookResponse> {
+        self.public_get_with_params("/0/public/Depth", params).await
+    }
+
+    // GET /0/public/Trades
+    pub async fn get_recent_trades(&self, params: &[(&str, &str)]) -> KrakenResult<TradesResponse> {
+        self.public_get_with_params("/0/public/Trades", params)
+            .await
+    }
+
+    // GET /0/public/Spread
+    pub async fn get_recent_spreads(
+        &self,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<SpreadsResponse> {
+        self.public_get_with_params("/0/public/Spread", params)
+            .await
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // HELPER METHODS
+    // ─────────────────────────────────────────────────────────────
+
+    /// General public GET helper without query parameters
+    async fn public_get<T>(&self, path: &str) -> KrakenResult<T>
+    where
+        T: serde::de::DeserializeOwned + serde::Serialize,
+    {
+        self.public_get_with_params(path, &[]).await
+    }
+
+    /// General public GET helper with query parameters
+    async fn public_get_with_params<T>(
+        &self,
+        path: &str,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<T>
+    where
+        T: serde::de::DeserializeOwned + serde::Serialize,
+    {
+        let Some(delay) = self.hedge_delay else {
+            return self.public_attempt(path, params).await;
+        };
+
+        let primary = self.public_attempt(path, params);
+        tokio::pin!(primary);
+        tokio::select! {
+            result = &mut primary => return result,
+            _ = tokio::time::sleep(delay) => {}
+        }
+
+        let hedge = self.public_attempt(path, params);
+        tokio::pin!(hedge);
+        tokio::select! {
+            result = &mut primary => match result {
+                Ok(value) => Ok(value),
+                Err(_) => hedge.await,
+            },
+            result = &mut hedge => match result {
+                Ok(value) => Ok(value),
+                Err(_) => primary.await,
+            },
+        }
+    }
+
+    /// A single public GET round trip.
+    async fn public_attempt<T>(&self, path: &str, params: &[(&str, &str)]) -> KrakenResult<T>
+    where
+        T: serde::de::DeserializeOwned + serde::Serialize,
+    {
+        let url = format!("{}{}", self.base_url(), path);
+        let raw = self
+            .execute("GET", path, params, &[], self.http.get(&url).query(params))
+            .await?;
+        self.decode(path, &raw)
+    }
+
+    /// Parse `raw`, checking it for schema drift when enabled.
+    fn decode<T>(&self, path: &str, raw: &RawResponse) -> KrakenResult<T>
+    where
+        T: serde::de::DeserializeOwned + serde::Serialize,
+    {
+        let parsed = raw.parse().inspect_err(|_| {
+            self.metrics
+                .record_kraken_errors(&crate::envelope::errors(&raw.body));
+        })?;
+        if self.schema_drift {
+            crate::schema_drift::check::<T>(path, &raw.body);
+        }
+        Ok(parsed)
+    }
+
+    /// Public GET for rarely-changing metadata, served from the metadata cache when enabled
+    async fn metadata_get<T>(&self, path: &str, params: &[(&str, &str)]) -> KrakenResult<T>
+    where
+        T: serde::de::DeserializeOwned + serde::Serialize,
+    {
+        let Some(cache) = &self.metadata_cache else {
+            return self.public_get_with_params(path, params).await;
+        };
+
+        let key = MetadataCache::key(path, params);
+        let cached = cache.lookup(&key, self.clock.now());
+        if let Some((entry, true)) = &cached {
+            return crate::envelope::parse(&entry.body);
+        }
+
+        let url = format!("{}{}", self.base_url(), path);
+        let mut request = self.http.get(&url).query(params);
+        if let Some((entry, _)) = &cached {
+            if let Some(etag) = &entry.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &entry.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+        let raw = self.execute("GET", path, params, &[], request).await?;
+
+        if raw.status == StatusCode::NOT_MODIFIED {
+            if let Some((entry, _)) = cached {
+                cache.touch(&key, self.clock.now());
+                return crate::envelope::parse(&entry.body);
+            }
+        }
+
+        // Only successful responses are cached; Kraken errors propagate as usual.
+        let parsed = self.decode(path, &raw)?;
+        let header = |name| {
+            raw.headers
+                .get(name)
+                .and_then(|v: &reqwest::header::HeaderValue| v.to_str().ok())
+                .map(str::to_string)
+        };
+        cache.store(
+            key,
+            CachedResponse {
+                etag: header(ETAG),
+                last_modified: header(LAST_MODIFIED),
+                body: raw.body.to_vec(),
+                fetched_at: self.clock.now(),
+            },
+        );
+        Ok(parsed)
+    }
+
+    /// Send a request: attach the User-Agent and a fresh request ID, run it inside a
+    /// `kraken_request` tracing span, read the whole body, record its latency, and
+    /// hand a sanitized record to the request logger (if any).
+    ///
+    /// `params` are only used for logging; `redacted_headers` names headers whose
+    /// presence is logged but never their values.
+    async fn execute(
+        &self,
+        method: &'static str,
+        path: &str,
+        params: &[(&str, &str)],
+        redacted_headers: &[&'static str],
+        request: RequestBuilder,
+    ) -> KrakenResult<RawResponse> {
+        environment::check_sandbox(self.base_url())?;
+        let request_id = Uuid::new_v4().to_string();
+        let request = request
+            .header(USER_AGENT, self.user_agent.as_str())
+            .header(REQUEST_ID_HEADER, request_id.as_str());
+        let span = tracing::debug_span!("kraken_request", method, path, %request_id);
+
+        let started = Instant::now();
+        let mut ttfb = None;
+        let result = async {
+            let resp = request.send().await?;
+            ttfb = Some(started.elapsed());
+            let status = resp.status();
+            let headers = resp.headers().clone();
+            let body = resp.bytes().await?;
+            Ok::<_, reqwest::Error>((status, headers, body))
+        }
+        .instrument(span)
+        .await;
+        let at = self.clock.system_time();
+        self.metrics
+            .record_request(path, &self.caller, at, ttfb, started.elapsed());
+
+        if let Some(logger) = &self.logger {
+            logger.0.log(&RequestLog {
+                method,
+                path: path.to_string(),
+                request_id: request_id.clone(),
+                params: logging::sanitize_params(params),
+                redacted_headers: redacted_headers.to_vec(),
+                status: result.as_ref().ok().map(|(status, _, _)| status.as_u16()),
+                elapsed: started.elapsed(),
+                error: result.as_ref().err().map(|e| e.to_string()),
+            });
+        }
+
+        let (status, headers, body) =
+            result.map_err(|e| KrakenError::from(e).with_request_id(&request_id))?;
+        Ok(RawResponse {
+            request_id,
+            status,
+            headers,
+            body,
+        })
+    }
+}
+
+impl AuthenticatedClient {
+    // ─────────────────────────────────────────────────────────────
+    // PRIVATE ENDPOINTS (User Data)
+    // ─────────────────────────────────────────────────────────────
+
+    // POST /0/private/Balance
+    pub async fn get_balance(&self) -> KrakenResult<AccountBalanceResponse> {
+        self.private_post("/0/private/Balance", &[]).await
+    }
+
+    // POST /0/private/BalanceEx
+    pub async fn get_extended_balance(&self) -> KrakenResult<ExtendedBalanceResponse> {
+        self.private_post("/0/private/BalanceEx", &[]).await
+    }
+
+    // POST /0/private/TradeBalance
+    pub async fn get_trade_balance(
+        &self,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<TradeBalanceResponse> {
+        self.private_post("/0/private/TradeBalance", params).await
+    }
+
+    // POST /0/private/OpenOrders
+    pub async fn get_open_orders(
+        &self,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<OpenOrdersResponse> {
+        self.private_post("/0/private/OpenOrders", params).await
+    }
+
+    // POST /0/private/ClosedOrders
+    pub async fn get_closed_orders(
+        &self,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<ClosedOrdersResponse> {
+        self.private_post("/0/private/ClosedOrders", params).await
+    }
+
+    // POST /0/private/QueryOrders
+    pub async fn query_orders_info(
+        &self,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<QueryOrdersResponse> {
+        self.private_post("/0/private/QueryOrders", params).await
+    }
+
+    // POST /0/private/OpenOrders
+    /// `get_open_orders` with typed options.
+    pub async fn open_orders(&self, params: &OpenOrdersParams) -> KrakenResult<OpenOrdersResponse> {
+        let params = params.to_params();
+        self.get_open_orders(&params::as_pairs(&params)).await
+    }
+
+    // POST /0/private/OpenOrders
+    /// Open orders on `pair` only, keyed by txid. Kraken has no server-side pair
+    /// filter, so this fetches all open orders and matches `descr.pair`, ignoring
+    /// case and any `/` (so "XBT/USD" and "xbtusd" both match "XBTUSD").
+    pub async fn open_orders_for_pair(&self, pair: &str) -> KrakenResult<HashMap<String, OrderInfo>> {
+        let wanted = normalize_pair(pair);
+        let mut orders = self.open_orders(&OpenOrdersParams::new()).await?.open;
+        orders.retain(|_, order| normalize_pair(&order.descr.pair) == wanted);
+        Ok(orders)
+    }
+
+    // POST /0/private/ClosedOrders
+    /// `get_closed_orders` with typed options.
+    pub async fn closed_orders(
+        &self,
+        params: &ClosedOrdersParams,
+    ) -> KrakenResult<ClosedOrdersResponse> {
+        let params = params.to_params();
+        self.get_closed_orders(&params::as_pairs(&params)).await
+    }
+
+    // POST /0/private/QueryOrders
+    /// `query_orders_info` with typed options.
+    pub async fn query_orders(&self, params: &QueryOrdersParams) -> KrakenResult<QueryOrdersResponse> {
+        let params = params.to_params();
+        self.query_orders_info(&params::as_pairs(&params)).await
+    }
+
+    // POST /0/private/TradesHistory
+    pub async fn get_trades_history(
+        &self,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<TradesHistoryResponse> {
+        self.private_post("/0/private/TradesHistory", params).await
+    }
+
+    // POST /0/private/QueryTrades
+    pub async fn query_trades_info(
+        &self,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<QueryTradesResponse> {
+        self.private_post("/0/private/QueryTrades", params).await
+    }
+
+    // POST /0/private/OpenPositions
+    pub async fn get_open_positions(
+        &self,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<OpenPositionsResponse> {
+        self.private_post("/0/private/OpenPositions", params).await
+    }
+
+    // POST /0/private/Ledgers
+    pub async fn get_ledgers(&self, params: &[(&str, &str)]) -> KrakenResult<LedgersResponse> {
+        self.private_post("/0/private/Ledgers", params).await
+    }
+
+    // POST /0/private/QueryLedgers
+    pub async fn query_ledgers(
+        &self,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<QueryLedgersResponse> {
+        self.private_post("/0/private/QueryLedgers", params).await
+    }
+
+    // POST /0/private/TradeVolume
+    pub async fn get_trade_volume(
+        &self,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<TradeVolumeResponse> {
+        self.private_post("/0/private/TradeVolume", params).await
+    }
+
+    // POST /0/private/ExportTrades
+    pub async fn request_export_report(
+        &self,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<ExportTradesResponse> {
+        self.private_post("/0/private/ExportTrades", params).await
+    }
+
+    // POST /0/private/ExportStatus
+    pub async fn get_export_report_status(
+        &self,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<ExportStatusResponse> {
+        self.private_post("/0/private/ExportStatus", params).await
+    }
+
+    // POST /0/private/RetrieveExport
+    pub async fn retrieve_export(
+        &self,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<RetrieveExportResponse> {
+        self.private_post("/0/private/RetrieveExport", params).await
+    }
+
+    // POST /0/private/DeleteExport
+    pub async fn delete_export(
+        &self,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<DeleteExportResponse> {
+        self.private_post("/0/private/DeleteExport", params).await
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // TRADING
+    // ─────────────────────────────────────────────────────────────
+
+    // POST /0/private/AddOrder
+    /// With the metadata cache enabled, a `leverage` parameter is first checked
+    /// with `validate_leverage`. Without it the order goes straight to Kraken,
+    /// which rejects unsupported leverage itself (`EOrder:Invalid leverage`);
+    /// the local check is skipped so each order doesn't cost an `AssetPairs`
+    /// round trip. Call `validate_leverage` directly to check it anyway.
+    pub async fn add_order(&self, params: &[(&str, &str)]) -> KrakenResult<AddOrderResponse> {
+        if self.metadata_cache.is_some() {
+            let param = |key| params.iter().find(|(k, _)| *k == key).map(|(_, v)| *v);
+            if let (Some(pair), Some(side), Some(leverage)) =
+                (param("pair"), param("type"), param("leverage"))
+            {
+                self.validate_leverage(pair, side, leverage).await?;
+            }
+        }
+        self.private_post("/0/private/AddOrder", params).await
+    }
+
+    // POST /0/private/AddOrder
+    /// `add_order` with a typed request, validated locally before sending.
+    /// With `with_amount_formatter`, its amounts are formatted first.
+    pub async fn add(&self, request: &AddOrderRequest) -> KrakenResult<AddOrderResponse> {
+        let formatted;
+        let request = match &self.amount_formatter {
+            Some(formatter) => {
+                let precision = self.pair_precision(&request.pair).await?;
+                formatted = request.clone().formatted(formatter, &precision)?;
+                &formatted
+            }
+            None => request,
+        };
+        request.validate()?;
+        let params = request.to_params();
+        self.add_order(&params::as_pairs(&params)).await
+    }
+
+    // POST /0/private/AddOrderBatch
+    pub async fn add_order_batch(
+        &self,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<AddOrderBatchResponse> {
+        self.private_post("/0/private/AddOrderBatch", params).await
+    }
+
+    // POST /0/private/AmendOrder
+    pub async fn amend_order(&self, params: &[(&str, &str)]) -> KrakenResult<AmendOrderResponse> {
+        self.private_post("/0/private/AmendOrder", params).await
+    }
+
+    // POST /0/private/AmendOrder
+    /// `amend_order` with a typed request, validated locally before sending.
+    pub async fn amend(&self, request: &AmendOrderRequest) -> KrakenResult<AmendOrderResponse> {
+        request.validate()?;
+        let params = request.to_params();
+        self.amend_order(&params::as_pairs(&params)).await
+    }
+
+    // POST /0/private/EditOrder
+    pub async fn edit_order(&self, params: &[(&str, &str)]) -> KrakenResult<EditOrderResponse> {
+        self.private_post("/0/private/EditOrder", params).await
+    }
+
+    // POST /0/private/EditOrder
+    /// `edit_order` with a typed request, validated locally before sending.
+    /// With `with_amount_formatter`, its amounts are formatted first.
+    pub async fn edit(&self, request: &EditOrderRequest) -> KrakenResult<EditOrderResponse> {
+        let formatted;
+        let request = match &self.amount_formatter {
+            Some(formatter) => {
+                let precision = self.pair_precision(&request.pair).await?;
+                formatted = request.clone().formatted(formatter, &precision)?;
+                &formatted
+            }
+            None => request,
+        };
+        request.validate()?;
+        let params = request.to_params();
+        self.edit_order(&params::as_pairs(&params)).await
+    }
+
+    // POST /0/private/CancelOrder
+    pub async fn cancel_order(&self, params: &[(&str, &str)]) -> KrakenResult<CancelOrderResponse> {
+        self.private_post("/0/private/CancelOrder", params).await
+    }
+
+    // POST /0/private/CancelAll
+    pub async fn cancel_all_orders(&self) -> KrakenResult<CancelAllOrdersResponse> {
+        self.private_post("/0/private/CancelAll", &[]).await
+    }
+
+    // POST /0/private/CancelAllOrdersAfter
+    pub async fn cancel_all_orders_after(
+        &self,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<CancelAllOrdersAfterResponse> {
+        self.private_post("/0/private/CancelAllOrdersAfter", params)
+            .await
+    }
+
+    // POST /0/private/CancelAllOrdersAfter
+    /// Keep a `timeout` cancel-all timer alive by re-arming it every
+    /// `refresh_interval` from a background task; see `DeadMansSwitch`.
+    /// The first arm happens before this returns, so its errors come back here;
+    /// later refresh failures go to `on_failure`.
+    pub async fn start_deadmans_switch(
+        &self,
+        timeout: Duration,
+        refresh_interval: Duration,
+        on_failure: impl DeadmanFailureHandler + 'static,
+    ) -> KrakenResult<DeadMansSwitch> {
+        DeadMansSwitch::start(
+            self.clone(),
+            timeout,
+            refresh_interval,
+            Arc::new(on_failure),
+            None,
+        )
+        .await
+    }
+
+    // POST /0/private/CancelAllOrdersAfter
+    /// `start_deadmans_switch`, saving a `DeadmanState` under `key` in `store`
+    /// each time the timer is armed so a restart can `resume_deadmans_switch`.
+    pub async fn start_persistent_deadmans_switch(
+        &self,
+        timeout: Duration,
+        refresh_interval: Duration,
+        on_failure: impl DeadmanFailureHandler + 'static,
+        store: SharedStateStore,
+        key: &str,
+    ) -> KrakenResult<DeadMansSwitch> {
+        DeadMansSwitch::start(
+            self.clone(),
+            timeout,
+            refresh_interval,
+            Arc::new(on_failure),
+            Some(StateHandle::new(store, key)),
+        )
+        .await
+    }
+
+    // POST /0/private/CancelAllOrdersAfter
+    /// Restart the persistent dead man's switch saved under `key` in `store`,
+    /// with the timeout and refresh interval it was started with. `None` if
+    /// nothing is saved there (it was never started, or was disarmed).
+    pub async fn resume_deadmans_switch(
+        &self,
+        on_failure: impl DeadmanFailureHandler + 'static,
+        store: SharedStateStore,
+        key: &str,
+    ) -> KrakenResult<Option<DeadMansSwitch>> {
+        let state = StateHandle::new(store, key);
+        let Some(saved) = state.load::<DeadmanState>()? else {
+            return Ok(None);
+        };
+        DeadMansSwitch::start(
+            self.clone(),
+            Duration::from_secs(saved.timeout_secs),
+            Duration::from_millis(saved.refresh_interval_ms),
+            Arc::new(on_failure),
+            Some(state),
+        )
+        .await
+        .map(Some)
+    }
+
+    // POST /0/private/CancelOrderBatch
+    pub async fn cancel_order_batch(
+        &self,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<CancelOrderBatchResponse> {
+        self.private_post("/0/private/CancelOrderBatch", params)
+            .await
+    }
+
+    // POST /0/private/GetWebSocketsToken
+    pub async fn get_websockets_token(&self) -> KrakenResult<GetWebSocketsTokenResponse> {
+        self.private_post("/0/private/GetWebSocketsToken", &[])
+            .await
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // FUNDING
+    // ─────────────────────────────────────────────────────────────
+
+    // POST /0/private/DepositMethods
+    pub async fn get_deposit_methods(
+        &self,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<DepositMethodsResponse> {
+        self.private_post("/0/private/DepositMethods", params).await
+    }
+
+    // POST /0/private/DepositAddresses
+    pub async fn get_deposit_addresses(
+        &self,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<DepositAddressesResponse> {
+        self.private_post("/0/private/DepositAddresses", params)
+            .await
+    }
+
+    // POST /0/private/DepositMethods
+    /// Deposit methods for `asset`, typically one per network it can arrive on.
+    pub async fn deposit_methods_for(&self, asset: &str) -> KrakenResult<DepositMethodsResponse> {
+        self.get_deposit_methods(&[("asset", asset)]).await
+    }
+
+    // POST /0/private/DepositMethods
+    /// The method for depositing `asset` over `network` (e.g. `("USDT", "Solana")`).
+    /// Fails with `InvalidUsage` naming the available networks if there is none.
+    pub async fn deposit_method(&self, asset: &str, network: &str) -> KrakenResult<DepositMethod> {
+        let methods = self.deposit_methods_for(asset).await?;
+        let available: Vec<String> = methods.networks().map(str::to_string).collect();
+        methods
+            .0
+            .into_iter()
+            .find(|m| m.on_network(network))
+            .ok_or_else(|| {
+                KrakenError::InvalidUsage(format!(
+                    "no {asset} deposit method on network {network}; available: {available:?}"
+                ))
+            })
+    }
+
+    // POST /0/private/DepositAddresses
+    /// Deposit addresses for `asset` on `network`, resolving the method name first.
+    /// `new` asks Kraken to generate a fresh address.
+    pub async fn deposit_addresses_on(
+        &self,
+        asset: &str,
+        network: &str,
+        new: bool,
+    ) -> KrakenResult<DepositAddressesResponse> {
+        let method = self.deposit_method(asset, network).await?;
+        let new = if new { "true" } else { "false" };
+        self.get_deposit_addresses(&[("asset", asset), ("method", &method.method), ("new", new)])
+            .await
+    }
+
+    // POST /0/private/DepositStatus
+    pub async fn get_deposit_status(
+        &self,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<DepositStatusResponse> {
+        self.private_post("/0/private/DepositStatus", params).await
+    }
+
+    // POST /0/private/WithdrawalMethods
+    pub async fn get_withdrawal_methods(
+        &self,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<WithdrawalMethodsResponse> {
+        self.private_post("/0/private/WithdrawalMethods", params)
+            .await
+    }
+
+    // POST /0/private/WithdrawalAddresses
+    pub async fn get_withdrawal_addresses(
+        &self,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<WithdrawalAddressesResponse> {
+        self.private_post("/0/private/WithdrawalAddresses", params)
+            .await
+    }
+
+    // POST /0/private/WithdrawalInformation
+    pub async fn get_withdrawal_information(
+        &self,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<WithdrawalInformationResponse> {
+        self.private_post("/0/private/WithdrawalInformation", params)
+            .await
+    }
+
+    // POST /0/private/Withdraw
+    pub async fn withdraw_funds(
+        &self,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<WithdrawFundsResponse> {
+        self.private_post("/0/private/Withdraw", params).await
+    }
+
+    // POST /0/private/WithdrawStatus
+    pub async fn get_withdraw_status(
+        &self,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<WithdrawStatusResponse> {
+        self.private_post("/0/private/WithdrawStatus", params).await
+    }
+
+    // POST /0/private/WithdrawCancel
+    pub async fn request_withdrawal_cancellation(
+        &self,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<WithdrawCancelResponse> {
+        self.private_post("/0/private/WithdrawCancel", params).await
+    }
+
+    // POST /0/private/WalletTransfer
+    pub async fn request_wallet_transfer(
+        &self,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<WalletTransferResponse> {
+        self.private_post("/0/private/WalletTransfer", params).await
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // SUBACCOUNTS
+    // ─────────────────────────────────────────────────────────────
+
+    // POST /0/private/CreateSubaccount
+    pub async fn create_subaccount(
+        &self,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<CreateSubaccountResponse> {
+        self.private_post("/0/private/CreateSubaccount", params)
+            .await
+    }
+
+    // POST /0/private/AccountTransfer
+    pub async fn account_transfer(
+        &self,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<AccountTransferResponse> {
+        self.private_post("/0/private/AccountTransfer", params)
+            .await
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // EARN / STAKING
+    // ─────────────────────────────────────────────────────────────
+
+    // POST /0/private/Staking/Stake
+    pub async fn allocate_earn_funds(
+        &self,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<AllocateEarnFundsResponse> {
+        self.private_post("/0/private/Staking/Stake", params).await
+    }
+
+    // POST /0/private/Staking/Unstake
+    pub async fn deallocate_earn_funds(
+        &self,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<DeallocateEarnFundsResponse> {
+        self.private_post("/0/private/Staking/Unstake", params)
+            .await
+    }
+
+    // POST /0/private/Staking/GetStakeStatus
+    pub async fn get_allocation_status(&self) -> KrakenResult<GetAllocationStatusResponse> {
+        self.private_post("/0/private/Staking/GetStakeStatus", &[])
+            .await
+    }
+
+    // POST /0/private/Staking/GetUnstakeStatus
+    pub async fn get_deallocation_status(&self) -> KrakenResult<GetDeallocationStatusResponse> {
+        self.private_post("/0/private/Staking/GetUnstakeStatus", &[])
+            .await
+    }
+
+    // POST /0/private/Staking/ListStakingProducts
+    pub async fn list_earn_strategies(&self) -> KrakenResult<ListEarnStrategiesResponse> {
+        self.private_post("/0/private/Staking/ListStakingProducts", &[])
+            .await
+    }
+
+    // POST /0/private/Staking/ListStakingTransactions
+    pub async fn list_earn_allocations(&self) -> KrakenResult<ListEarnAllocationsResponse> {
+        self.private_post("/0/private/Staking/ListStakingTransactions", &[])
+            .await
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // OTC
+    // ─────────────────────────────────────────────────────────────
+
+    // POST /0/private/CreateOtcQuoteRequest
+    pub async fn create_otc_quote(
+        &self,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<CreateOtcQuoteResponse> {
+        self.private_post("/0/private/CreateOtcQuoteRequest", params)
+            .await
+    }
+
+    // POST /0/private/CreateOtcQuoteRequest
+    /// Ask the OTC desk for a firm quote on a block trade, validated locally
+    /// before sending. Nothing trades until the quote is accepted with
+    /// `accept_otc_quote` before its `expires` time.
+    pub async fn request_otc_quote(&self, request: &OtcQuoteRequest) -> KrakenResult<OtcQuote> {
+        request.validate()?;
+        let params = request.to_params();
+        Ok(self
+            .create_otc_quote(&params::as_pairs(&params))
+            .await?
+            .quote)
+    }
+
+    // POST /0/private/UpdateOtcQuote
+    pub async fn update_otc_quote(
+        &self,
+        params: &[(&str, &str)],
+    ) -> KrakenResult<UpdateOtcQuoteResponse> {
+        self.private_post("/0/private/UpdateOtcQuote", params).await
+    }
+
+    // POST /0/private/UpdateOtcQuote
+    /// Trade at quote `quote_id`.
+    pub async fn accept_otc_quote(&self, quote_id: &str) -> KrakenResult<UpdateOtcQuoteResponse> {
+        self.update_otc_quote(&[("quote_id", quote_id), ("action", "accept")])
+            .await
+    }
+
+    // POST /0/private/UpdateOtcQuote
+    /// Decline quote `quote_id` rather than letting it expire.
+    pub async fn reject_otc_quote(&self, quote_id: &str) -> KrakenResult<UpdateOtcQuoteResponse> {
+        self.update_otc_quote(&[("quote_id", quote_id), ("action", "reject")])
+            .await
+    }
+
+    // POST /0/private/GetOtcActiveQuotes
+    /// Quotes not yet settled, rejected or expired, with their status.
+    pub async fn get_otc_quotes(&self) -> KrakenResult<GetOtcActiveQuotesResponse> {
+        self.private_post("/0/private/GetOtcActiveQuotes", &[])
+            .await
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // PRIVATE HELPER METHODS
+    // ─────────────────────────────────────────────────────────────
+
+    /// Generic private POST call with form parameters
+    async fn private_post<T>(&self, path: &str, params: &[(&str, &str)]) -> KrakenResult<T>
+    where
+        T: serde::de::DeserializeOwned + serde::Serialize,
+    {
+        if self.read_only && MUTATING_ENDPOINTS.contains(&path) {
+            return Err(KrakenError::ReadOnly {
+                path: path.to_string(),
+            });
+        }
+        let api_key = &self.credentials.api_key;
+        let signer = self.credentials.signer();
+
+        // Sign and send the very same encoded bytes, with a fresh nonce first
+        let payload = signing::SignedPayload::new(path, self.nonces.next(), params);
+        let signature = payload.sign(signer);
+
+        let url = format!("{}{}", self.base_url(), path);
+        let request = self
+            .http
+            .post(&url)
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(payload.post_data().to_string())
+            .header("API-Key", api_key)
+            .header("API-Sign", signature);
+        let raw = self
+            .execute("POST", path, params, logging::SENSITIVE_HEADERS, request)
+            .await;
+        if let Some(audit) = &self.audit {
+            let record = AuditRecord::new(path, params, payload.nonce());
+            let record = match &raw {
+                Ok(raw) => record.with_response(&raw.request_id, raw.status.as_u16(), &raw.body),
+                Err(e) => record.with_error(e.request_id(), e.inner().to_string()),
+            };
+            audit.0.record(&record);
+        }
+        self.decode(path, &raw?)
+            .map_err(|e| e.with_signed_payload(payload))
+    }
+}
+
+fn normalize_pair(pair: &str) -> String {
+    pair.chars()
+        .filter(|c| *c != '/')
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::Deserialize;
+
+use crate::error::{KrakenError, KrakenResult};
+use crate::exchange::{
+    ExchangeClient, OrderAmendment, OrderKind, OrderRequest, PlacedOrder, Position, Side,
+};
+use crate::numeric::Amount;
+use crate::positions::PositionTracker;
+use crate::quotes::QuoteCache;
+
+/// Which of the `RiskLimits` an order would break.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RiskLimit {
+    /// `max_order_notional`
+    OrderNotional,
+    /// `max_position` for the order's pair
+    Position,
+    /// `max_total_exposure`
+    TotalExposure,
+}
+
+impl fmt::Display for RiskLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RiskLimit::OrderNotional => "order notional",
+            RiskLimit::Position => "position",
+            RiskLimit::TotalExposure => "total exposure",
+        })
+    }
+}
+
+/// Account-wide risk limits checked before an order is sent. Unset limits
+/// aren't checked.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RiskLimits {
+    /// Largest quote-currency notional (volume × price) of a single order
+    pub max_order_notional: Option<f64>,
+    /// Largest absolute net position per pair, in base currency, that an
+    /// order may leave once filled
+    pub max_position: HashMap<String, f64>,
+    /// Largest sum over pairs of |position| × mid price, in quote currency,
+    /// once the order is filled
+    pub max_total_exposure: Option<f64>,
+}
+
+impl RiskLimits {
+    pub fn with_max_order_notional(mut self, notional: f64) -> Self {
+        self.max_order_notional = Some(notional);
+        self
+    }
+
+    pub fn with_max_position(mut self, pair: impl Into<String>, volume: f64) -> Self {
+        self.max_position.insert(pair.into(), volume);
+        self
+    }
+
+    pub fn with_max_total_exposure(mut self, exposure: f64) -> Self {
+        self.max_total_exposure = Some(exposure);
+        self
+    }
+}
+
+/// An `ExchangeClient` wrapper that checks every new order against
+/// `RiskLimits` and refuses violations locally with
+/// `KrakenError::RiskLimitExceeded`.
+///
+/// Positions come from a `PositionTracker` and prices from a `QuoteCache`:
+/// limit orders are valued at their limit price, market orders at the touch,
+/// and exposure at each pair's mid. An order that needs a price the cache
+/// doesn't have (or has only stale, under `QuoteCache::with_max_age`) is
+/// refused with that error. Both quotes and positions use the same pair
+/// names as the orders. Cancels and amendments pass through.
+///
+/// ```no_run
+/// # async fn run(session: onise::session::KrakenSession, ws: onise::ws_client::KrakenWsClient) -> onise::error::KrakenResult<()> {
+/// use onise::exchange::{ExchangeClient, OrderRequest, Side};
+/// use onise::positions::PositionTracker;
+/// use onise::quotes::QuoteCache;
+/// use onise::risk::{RiskGuard, RiskLimits};
+///
+/// let (quotes, positions) = (QuoteCache::new(), PositionTracker::new());
+/// quotes.follow_tickers(ws.ticker_stream());
+/// positions.follow_executions(ws.executions_stream());
+/// let limits = RiskLimits::default()
+///     .with_max_order_notional(25_000.0)
+///     .with_max_position("BTC/USD", 2.0)
+///     .with_max_total_exposure(100_000.0);
+/// let exchange = RiskGuard::new(session, limits, positions, quotes);
+/// exchange.place_order(&OrderRequest::market("BTC/USD", Side::Buy, "0.5")).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct RiskGuard<E> {
+    inner: E,
+    limits: RiskLimits,
+    positions: PositionTracker,
+    quotes: QuoteCache,
+}
+
+impl<E: ExchangeClient> RiskGuard<E> {
+    pub fn new(
+        inner: E,
+        limits: RiskLimits,
+        positions: PositionTracker,
+        quotes: QuoteCache,
+    ) -> Self {
+        Self {
+            inner,
+            limits,
+            positions,
+            quotes,
+        }
+    }
+
+    pub fn inner(&self) -> &E {
+        &self.inner
+    }
+
+    pub fn limits(&self) -> &RiskLimits {
+        &self.limits
+    }
+
+    pub fn positions(&self) -> &PositionTracker {
+        &self.positions
+    }
+
+    /// Check `order` against every limit without sending it.
+    pub fn check(&self, order: &OrderRequest) -> KrakenResult<()> {
+        let exceeded = |limit, value, max| KrakenError::RiskLimitExceeded {
+            pair: order.pair.clone(),
+            limit,
+            value,
+            max,
+        };
+        let volume = Amount(&order.volume).to_f64_lossy()?;
+        let signed = match order.side {
+            Side::Buy => volume,
+            Side::Sell => -volume,
+        };
+
+        if let Some(max) = self.limits.max_order_notional {
+            let notional = volume * self.order_price(order)?;
+            if notional > max {
+                return Err(exceeded(RiskLimit::OrderNotional, notional, max));
+            }
+        }
+
+        if let Some(max) = self.limits.max_position.get(&order.pair) {
+            let after = (self.positions.position(&order.pair) + signed).abs();
+            if after > *max {
+                return Err(exceeded(RiskLimit::Position, after, *max));
+            }
+        }
+
+        if let Some(max) = self.limits.max_total_exposure {
+            let mut positions = self.positions.positions();
+            *positions.entry(order.pair.clone()).or_default() += signed;
+            let mut exposure = 0.0;
+            for (pair, volume) in positions.iter().filter(|(_, v)| **v != 0.0) {
+                exposure += volume.abs() * self.mid(pair)?;
+            }
+            if exposure > max {
+                return Err(exceeded(RiskLimit::TotalExposure, exposure, max));
+            }
+        }
+        Ok(())
+    }
+
+    fn order_price(&self, order: &OrderRequest) -> KrakenResult<f64> {
+        match &order.kind {
+            OrderKind::Limit { price } => Amount(price).to_f64_lossy(),
+            OrderKind::Market => {
+                let touch = self.quotes.touch(&order.pair)?;
+                let price = match order.side {
+                    Side::Buy => touch.ask,
+                    Side::Sell => touch.bid,
+                };
+                Amount(&price).to_f64_lossy()
+            }
+        }
+    }
+
+    fn mid(&self, pair: &str) -> KrakenResult<f64> {
+        let touch = self.quotes.touch(pair)?;
+        let bid = Amount(&touch.bid).to_f64_lossy()?;
+        let ask = Amount(&touch.ask).to_f64_lossy()?;
+        Ok((bid + ask) / 2.0)
+    }
+}
+
+impl<E: fmt::Debug> fmt::Debug for RiskGuard<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RiskGuard")
+            .field("inner", &self.inner)
+            .field("limits", &self.limits)
+            .field("positions", &self.positions)
+            .finish()
+    }
+}
+
+impl<E: ExchangeClient> ExchangeClient for RiskGuard<E> {
+    async fn place_order(&self, order: &OrderRequest) -> KrakenResult<PlacedOrder> {
+        self.check(order)?;
+        self.inner.place_order(order).await
+    }
+
+    async fn cancel_order(&self, order_id: &str) -> KrakenResult<()> {
+        self.inner.cancel_order(order_id).await
+    }
+
+    async fn amend_order(&self, amendment: &OrderAmendment) -> KrakenResult<()> {
+        self.inner.amend_order(amendment).await
+    }
+
+    async fn positions(&self) -> KrakenResult<Vec<Position>> {
+        self.inner.positions().await
+    }
+
+    async fn balances(&self) -> KrakenResult<HashMap<String, String>> {
+        self.inner.balances().await
+    }
+}
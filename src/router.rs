@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use crate::error::{KrakenError, KrakenResult};
+use crate::exchange::{ExchangeClient, OrderAmendment, OrderRequest, PlacedOrder, Position};
+use crate::params::{ClosedOrdersParams, OpenOrdersParams};
+use crate::rest_client::AuthenticatedClient;
+use crate::ws_client::KrakenWsClient;
+
+/// How long `OrderRouter` waits for a WebSocket acknowledgement before failing
+/// over to REST, unless set with `with_ack_deadline`.
+pub const DEFAULT_ACK_DEADLINE: Duration = Duration::from_secs(3);
+
+/// Routes orders over the WebSocket trading channel when it's usable and falls
+/// back to REST (`AddOrder`, `CancelOrder`, `AmendOrder`) when it isn't.
+///
+/// The WebSocket is skipped when it has no `token` or is disconnected. A request
+/// sent over it fails over to REST if it isn't acknowledged within the ack
+/// deadline or the socket drops meanwhile; rejections by Kraken (e.g.
+/// `OrderError`) are returned as-is.
+///
+/// New orders always carry a `cl_ord_id` (a UUID is generated when the request
+/// has none). Before resubmitting an unacknowledged order over REST, the router
+/// looks that ID up in open and closed orders, so an order that did reach the
+/// engine is returned rather than placed twice; Kraken also refuses a second
+/// open order with the same `cl_ord_id`.
+///
+/// `positions` and `balances` always use REST.
+pub struct OrderRouter {
+    rest: AuthenticatedClient,
+    ws: Option<Arc<KrakenWsClient>>,
+    ack_deadline: Duration,
+}
+
+impl OrderRouter {
+    /// A router that only uses `rest` until `with_ws` adds a socket.
+    pub fn new(rest: AuthenticatedClient) -> Self {
+        Self {
+            rest,
+            ws: None,
+            ack_deadline: DEFAULT_ACK_DEADLINE,
+        }
+    }
+
+    /// Prefer `ws` (with its `token` set) for trading.
+    pub fn with_ws(mut self, ws: Arc<KrakenWsClient>) -> Self {
+        self.ws = Some(ws);
+        self
+    }
+
+    pub fn with_ack_deadline(mut self, ack_deadline: Duration) -> Self {
+        self.ack_deadline = ack_deadline;
+        self
+    }
+
+    /// The socket to try first, if it can trade right now.
+    fn usable_ws(&self) -> Option<&KrakenWsClient> {
+        self.ws
+            .as_deref()
+            .filter(|ws| ws.token.is_some() && ws.is_connected())
+    }
+
+    /// Await `attempt`, sent over `ws`, within the ack deadline. `Ok(None)`
+    /// means it wasn't acknowledged or the socket dropped: fail over.
+    async fn acknowledged<T>(
+        &self,
+        ws: &KrakenWsClient,
+        operation: &str,
+        attempt: impl Future<Output = KrakenResult<T>>,
+    ) -> KrakenResult<Option<T>> {
+        match tokio::time::timeout(self.ack_deadline, attempt).await {
+            Ok(Ok(value)) => Ok(Some(value)),
+            Ok(Err(e)) if ws.is_connected() && !matches!(e, KrakenError::Timeout { .. }) => Err(e),
+            Ok(Err(e)) => {
+                tracing::warn!(operation, error = %e, "WebSocket trading failed; falling back to REST");
+                Ok(None)
+            }
+            Err(_) => {
+                tracing::warn!(operation, after = ?self.ack_deadline, "WebSocket trading unacknowledged; falling back to REST");
+                Ok(None)
+            }
+        }
+    }
+
+    /// The txid of the order placed with `cl_ord_id`, if Kraken has one.
+    async fn find_by_cl_ord_id(&self, cl_ord_id: &str) -> KrakenResult<Option<String>> {
+        let open = self
+            .rest
+            .open_orders(&OpenOrdersParams::new().with_cl_ord_id(cl_ord_id))
+            .await?;
+        if let Some(txid) = open.open.into_keys().next() {
+            return Ok(Some(txid));
+        }
+        let closed = self
+            .rest
+            .closed_orders(&ClosedOrdersParams::new().with_cl_ord_id(cl_ord_id))
+            .await?;
+        Ok(closed.closed.into_keys().next())
+    }
+}
+
+impl ExchangeClient for OrderRouter {
+    async fn place_order(&self, order: &OrderRequest) -> KrakenResult<PlacedOrder> {
+        let mut order = order.clone();
+        let cl_ord_id = order
+            .cl_ord_id
+            .get_or_insert_with(|| Uuid::new_v4().to_string())
+            .clone();
+        if let Some(ws) = self.usable_ws() {
+            let attempt = ExchangeClient::place_order(ws, &order);
+            if let Some(placed) = self.acknowledged(ws, "addOrder", attempt).await? {
+                return Ok(placed);
+            }
+            // The unacknowledged order may still have reached the engine
+            if let Some(order_id) = self.find_by_cl_ord_id(&cl_ord_id).await? {
+                return Ok(PlacedOrder { order_id });
+            }
+        }
+        ExchangeClient::place_order(&self.rest, &order).await
+    }
+
+    async fn cancel_order(&self, order_id: &str) -> KrakenResult<()> {
+        if let Some(ws) = self.usable_ws() {
+            let attempt = ExchangeClient::cancel_order(ws, order_id);
+            if let Some(()) = self.acknowledged(ws, "cancelOrder", attempt).await? {
+                return Ok(());
+            }
+        }
+        ExchangeClient::cancel_order(&self.rest, order_id).await
+    }
+
+    async fn amend_order(&self, amendment: &OrderAmendment) -> KrakenResult<()> {
+        if let Some(ws) = self.usable_ws() {
+            let attempt = ExchangeClient::amend_order(ws, amendment);
+            if let Some(()) = self.acknowledged(ws, "amendOrder", attempt).await? {
+                return Ok(());
+            }
+        }
+        ExchangeClient::amend_order(&self.rest, amendment).await
+    }
+
+    async fn positions(&self) -> KrakenResult<Vec<Position>> {
+        ExchangeClient::positions(&self.rest).await
+    }
+
+    async fn balances(&self) -> KrakenResult<HashMap<String, String>> {
+        ExchangeClient::balances(&self.rest).await
+    }
+}
@@ -0,0 +1,282 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use futures_util::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use crate::clock::{self, Clock, SharedClock};
+use crate::error::{KrakenError, KrakenResult};
+use crate::exchange::{ExchangeClient, OrderRequest, PlacedOrder};
+use crate::order_tracker::outcome_unknown;
+use crate::state::{SharedStateStore, StateHandle};
+
+/// When an `OrderScheduler` sends a scheduled order.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StartCondition {
+    /// At this Unix time (seconds) or later, like AddOrder's `starttm` but
+    /// held locally
+    At { unix_time: u64 },
+    /// Once a price of the order's pair comes in below `price`
+    PriceBelow { price: f64 },
+    /// Once a price of the order's pair comes in above `price`
+    PriceAbove { price: f64 },
+}
+
+impl StartCondition {
+    /// `At` `time`, to the second.
+    pub fn at(time: SystemTime) -> Self {
+        let unix_time = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+        StartCondition::At {
+            unix_time: unix_time.as_secs(),
+        }
+    }
+
+    fn due_at(&self, now: SystemTime) -> bool {
+        match self {
+            StartCondition::At { unix_time } => now >= UNIX_EPOCH + Duration::from_secs(*unix_time),
+            _ => false,
+        }
+    }
+
+    fn due_on(&self, price: f64) -> bool {
+        match self {
+            StartCondition::PriceBelow { price: below } => price < *below,
+            StartCondition::PriceAbove { price: above } => price > *above,
+            StartCondition::At { .. } => false,
+        }
+    }
+}
+
+/// Where a scheduled order is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduleStatus {
+    /// Its condition hasn't been met yet
+    Waiting,
+    /// Triggered and sent, without a definite answer: the process stopped
+    /// mid-send, or the send failed in a way that leaves the outcome unknown
+    Submitting,
+}
+
+/// An order waiting in an `OrderScheduler`, keyed by its `cl_ord_id`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduledOrder {
+    pub order: OrderRequest,
+    pub condition: StartCondition,
+    pub status: ScheduleStatus,
+}
+
+impl ScheduledOrder {
+    pub fn cl_ord_id(&self) -> &str {
+        self.order.cl_ord_id.as_deref().unwrap_or_default()
+    }
+}
+
+/// A scheduled order whose condition was met, and what sending it returned.
+#[derive(Debug)]
+pub struct Triggered {
+    pub scheduled: ScheduledOrder,
+    pub result: KrakenResult<PlacedOrder>,
+}
+
+/// Holds orders until a start condition is met, a time (`StartCondition::At`)
+/// or a price (`PriceBelow` / `PriceAbove`), then sends them through an
+/// `ExchangeClient`.
+///
+/// Prices come from the caller, through `on_price` (or the stream given to
+/// `run`), matched against each order's `pair` as spelled there; times are
+/// checked by `on_time` against the scheduler's clock.
+///
+/// Every scheduled order needs a `cl_ord_id`. With `with_store`, waiting
+/// orders survive restarts, and an order is marked `Submitting` before it is
+/// sent: one still `Submitting` after a restart may or may not have reached
+/// the venue, so it isn't sent again. Look it up by `cl_ord_id`, then
+/// `cancel` it here. Clones share the same orders.
+#[derive(Debug, Clone)]
+pub struct OrderScheduler {
+    orders: Arc<Mutex<BTreeMap<String, ScheduledOrder>>>,
+    state: Option<StateHandle>,
+    clock: SharedClock,
+}
+
+impl Default for OrderScheduler {
+    fn default() -> Self {
+        Self {
+            orders: Arc::default(),
+            state: None,
+            clock: clock::system(),
+        }
+    }
+}
+
+impl OrderScheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read the time for `StartCondition::At` from `clock`.
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Restore orders saved under `key` in `store`, and save them there after
+    /// every change from now on. A failed save is logged at `warn`.
+    pub fn with_store(mut self, store: SharedStateStore, key: &str) -> KrakenResult<Self> {
+        let state = StateHandle::new(store, key);
+        if let Some(saved) = state.load::<BTreeMap<String, ScheduledOrder>>()? {
+            self.lock().extend(saved);
+        }
+        self.state = Some(state);
+        Ok(self)
+    }
+
+    /// Hold `order` until `condition` is met. It must carry a `cl_ord_id` not
+    /// already scheduled.
+    pub fn schedule(&self, order: OrderRequest, condition: StartCondition) -> KrakenResult<()> {
+        let cl_ord_id = order.cl_ord_id.clone().ok_or_else(|| {
+            KrakenError::InvalidUsage("scheduled orders need a cl_ord_id".to_string())
+        })?;
+        let mut orders = self.lock();
+        if orders.contains_key(&cl_ord_id) {
+            return Err(KrakenError::InvalidUsage(format!(
+                "an order with cl_ord_id {cl_ord_id} is already scheduled"
+            )));
+        }
+        let scheduled = ScheduledOrder {
+            order,
+            condition,
+            status: ScheduleStatus::Waiting,
+        };
+        orders.insert(cl_ord_id, scheduled);
+        self.save(&orders);
+        Ok(())
+    }
+
+    /// Stop holding `cl_ord_id`, whether it is waiting or `Submitting`.
+    pub fn cancel(&self, cl_ord_id: &str) -> Option<ScheduledOrder> {
+        let mut orders = self.lock();
+        let removed = orders.remove(cl_ord_id);
+        if removed.is_some() {
+            self.save(&orders);
+        }
+        removed
+    }
+
+    /// Orders whose condition hasn't been met yet.
+    pub fn waiting(&self) -> Vec<ScheduledOrder> {
+        self.with_status(ScheduleStatus::Waiting)
+    }
+
+    /// Orders sent without a definite answer; see `ScheduleStatus::Submitting`.
+    pub fn submitting(&self) -> Vec<ScheduledOrder> {
+        self.with_status(ScheduleStatus::Submitting)
+    }
+
+    /// Send every waiting order on `pair` whose price condition `price` meets.
+    pub async fn on_price(
+        &self,
+        exchange: &impl ExchangeClient,
+        pair: &str,
+        price: f64,
+    ) -> Vec<Triggered> {
+        let due = self.take_due(|scheduled| {
+            scheduled.order.pair == pair && scheduled.condition.due_on(price)
+        });
+        self.submit(exchange, due).await
+    }
+
+    /// Send every waiting order whose start time has come.
+    pub async fn on_time(&self, exchange: &impl ExchangeClient) -> Vec<Triggered> {
+        let now = self.clock.system_time();
+        let due = self.take_due(|scheduled| scheduled.condition.due_at(now));
+        self.submit(exchange, due).await
+    }
+
+    /// Drive the scheduler until `prices` ends: each `(pair, price)` goes to
+    /// `on_price`, and `on_time` runs every `interval`. Outcomes are logged
+    /// (`info` when placed, `warn` when not).
+    pub async fn run(
+        &self,
+        exchange: &impl ExchangeClient,
+        prices: impl Stream<Item = (String, f64)>,
+        interval: Duration,
+    ) {
+        let mut prices = std::pin::pin!(prices);
+        let mut ticks = tokio::time::interval(interval);
+        loop {
+            let triggered = tokio::select! {
+                price = prices.next() => match price {
+                    Some((pair, price)) => self.on_price(exchange, &pair, price).await,
+                    None => return,
+                },
+                _ = ticks.tick() => self.on_time(exchange).await,
+            };
+            for Triggered { scheduled, result } in triggered {
+                let cl_ord_id = scheduled.cl_ord_id();
+                match result {
+                    Ok(placed) => {
+                        let order_id = placed.order_id.as_str();
+                        tracing::info!(cl_ord_id, order_id, "scheduled order placed");
+                    }
+                    Err(e) => tracing::warn!(cl_ord_id, error = %e, "scheduled order failed"),
+                }
+            }
+        }
+    }
+
+    /// Mark the waiting orders `due` picks as `Submitting`, saved before
+    /// anything is sent, and return them.
+    fn take_due(&self, due: impl Fn(&ScheduledOrder) -> bool) -> Vec<ScheduledOrder> {
+        let mut orders = self.lock();
+        let mut taken = Vec::new();
+        for scheduled in orders.values_mut() {
+            if scheduled.status == ScheduleStatus::Waiting && due(scheduled) {
+                scheduled.status = ScheduleStatus::Submitting;
+                taken.push(scheduled.clone());
+            }
+        }
+        if !taken.is_empty() {
+            self.save(&orders);
+        }
+        taken
+    }
+
+    /// Send `due` one by one, forgetting each order once the venue has
+    /// definitely placed or refused it.
+    async fn submit(
+        &self,
+        exchange: &impl ExchangeClient,
+        due: Vec<ScheduledOrder>,
+    ) -> Vec<Triggered> {
+        let mut triggered = Vec::with_capacity(due.len());
+        for scheduled in due {
+            let result = exchange.place_order(&scheduled.order).await;
+            if !matches!(&result, Err(e) if outcome_unknown(e)) {
+                self.cancel(scheduled.cl_ord_id());
+            }
+            triggered.push(Triggered { scheduled, result });
+        }
+        triggered
+    }
+
+    fn with_status(&self, status: ScheduleStatus) -> Vec<ScheduledOrder> {
+        self.lock()
+            .values()
+            .filter(|scheduled| scheduled.status == status)
+            .cloned()
+            .collect()
+    }
+
+    fn save(&self, orders: &BTreeMap<String, ScheduledOrder>) {
+        if let Some(state) = &self.state {
+            state.save_or_warn(orders);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, ScheduledOrder>> {
+        self.orders.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
@@ -0,0 +1,84 @@
+use std::collections::BTreeSet;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::KrakenResult;
+
+/// Fields of `raw` (a response `result`, as sent) that `T` drops: the JSON
+/// paths present on the wire but missing once `raw` is deserialized into `T`
+/// and serialized back, e.g. `["XXBTZUSD.new_field"]`.
+///
+/// Array elements share one path (`trades[].misc`), and `null`s in `raw` are
+/// ignored, since a model without the field loses nothing.
+///
+/// ```
+/// use onise::models::ServerTimeResponse;
+/// use onise::schema_drift::missing_fields;
+///
+/// let raw = serde_json::json!({"unixtime": 1, "rfc1123": "...", "leap_second": false});
+/// assert_eq!(missing_fields::<ServerTimeResponse>(&raw).unwrap(), ["leap_second"]);
+/// ```
+pub fn missing_fields<T>(raw: &Value) -> KrakenResult<Vec<String>>
+where
+    T: DeserializeOwned + Serialize,
+{
+    let model: T = serde_json::from_value(raw.clone())?;
+    let reserialized = serde_json::to_value(&model)?;
+    let mut missing = BTreeSet::new();
+    diff(raw, &reserialized, &mut String::new(), &mut missing);
+    Ok(missing.into_iter().collect())
+}
+
+/// Log (at `warn`) any fields of `body`'s `result` that `T` drops. Used by
+/// `KrakenClient::with_schema_drift_detection`; errors and bodies that aren't
+/// JSON are left to the normal parse.
+pub(crate) fn check<T>(endpoint: &str, body: &[u8])
+where
+    T: DeserializeOwned + Serialize,
+{
+    let Ok(Value::Object(mut envelope)) = serde_json::from_slice::<Value>(body) else {
+        return;
+    };
+    let Some(result) = envelope.remove("result") else {
+        return;
+    };
+    match missing_fields::<T>(&result) {
+        Ok(missing) if !missing.is_empty() => {
+            tracing::warn!(endpoint, fields = ?missing, "response fields missing from model");
+        }
+        _ => {}
+    }
+}
+
+fn diff(raw: &Value, model: &Value, path: &mut String, missing: &mut BTreeSet<String>) {
+    match (raw, model) {
+        (Value::Object(raw), Value::Object(model)) => {
+            for (key, value) in raw {
+                let len = path.len();
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(key);
+                match model.get(key) {
+                    Some(reserialized) => diff(value, reserialized, path, missing),
+                    None if !value.is_null() => {
+                        missing.insert(path.clone());
+                    }
+                    None => {}
+                }
+                path.truncate(len);
+            }
+        }
+        (Value::Array(raw), Value::Array(model)) => {
+            let len = path.len();
+            path.push_str("[]");
+            for (value, reserialized) in raw.iter().zip(model) {
+                diff(value, reserialized, path, missing);
+            }
+            path.truncate(len);
+        }
+        _ => {}
+    }
+}
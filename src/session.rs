@@ -0,0 +1,663 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+use futures_util::future::{join_all, try_join_all};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+use crate::error::{KrakenError, KrakenResult};
+use crate::exchange::{
+    ExchangeClient, OrderAmendment, OrderKind, OrderRequest, PlacedOrder, Position, Side,
+    WS_TRADING_DEADLINE,
+};
+use crate::feed::FeedStream;
+use crate::models::ExchangeState;
+use crate::numeric::Amount;
+use crate::replay::Recorder;
+use crate::rest_client::AuthenticatedClient;
+use crate::router::{OrderRouter, DEFAULT_ACK_DEADLINE};
+use crate::status_page::{StatusEvent, StatusPage};
+use crate::ws_client::KrakenWsClient;
+use crate::ws_models::{WsCancelAllRequest, WsUserTradingResponse};
+
+/// The session's own account, as named in `ConsolidatedView` and accepted
+/// as an order's `account`.
+pub const MAIN_ACCOUNT: &str = "main";
+
+/// How long after an order rejection moves `KrakenSession::exchange_state` to
+/// `CancelOnly`/`PostOnly` the session re-reads `SystemStatus`, unless changed
+/// with `KrakenSession::with_state_recheck`.
+pub const DEFAULT_STATE_RECHECK: Duration = Duration::from_secs(30);
+
+/// Longest wait between `SystemStatus` retries after a restricted-state
+/// rejection, while the reads keep failing.
+const MAX_STATE_RECHECK: Duration = Duration::from_secs(300);
+
+/// What `KrakenSession::kill_switch` cancelled, per route.
+#[derive(Debug)]
+pub struct KillSwitchReport {
+    /// Orders REST `CancelAll` cancelled, or why it failed
+    pub rest: KrakenResult<u64>,
+    /// Orders the WebSocket `cancelAll` cancelled, or why it failed; `None`
+    /// when the session had no connected socket with a `token`
+    pub ws: Option<KrakenResult<u64>>,
+    /// The same for every subaccount added with `with_account`, by account ID
+    pub accounts: BTreeMap<String, KillSwitchReport>,
+}
+
+impl KillSwitchReport {
+    /// Orders cancelled across both routes and every account. The two routes
+    /// race, so an order is only counted by the one that reached it first.
+    pub fn cancelled(&self) -> u64 {
+        let ws = self.ws.as_ref().and_then(|ws| ws.as_ref().ok());
+        let accounts: u64 = self.accounts.values().map(Self::cancelled).sum();
+        self.rest.as_ref().unwrap_or(&0) + ws.unwrap_or(&0) + accounts
+    }
+
+    /// `true` if at least one route confirmed its cancel-all, on every account.
+    pub fn confirmed(&self) -> bool {
+        (self.rest.is_ok() || matches!(self.ws, Some(Ok(_))))
+            && self.accounts.values().all(Self::confirmed)
+    }
+}
+
+/// Positions and balances across a session's accounts, from
+/// `KrakenSession::consolidated`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConsolidatedView {
+    /// Open positions by account ID (`MAIN_ACCOUNT` for the session's own)
+    pub positions: BTreeMap<String, Vec<Position>>,
+    /// Balances by account ID, then asset
+    pub balances: BTreeMap<String, HashMap<String, String>>,
+}
+
+impl ConsolidatedView {
+    /// Each asset's balance summed over every account.
+    pub fn total_balances(&self) -> KrakenResult<BTreeMap<String, f64>> {
+        let mut totals = BTreeMap::new();
+        for (asset, balance) in self.balances.values().flatten() {
+            *totals.entry(asset.clone()).or_insert(0.0) += Amount(balance).to_f64_lossy()?;
+        }
+        Ok(totals)
+    }
+
+    /// The net position on each pair over every account, in base currency:
+    /// longs add, shorts subtract.
+    pub fn net_positions(&self) -> KrakenResult<BTreeMap<String, f64>> {
+        let mut net = BTreeMap::new();
+        for position in self.positions.values().flatten() {
+            let volume = Amount(&position.volume).to_f64_lossy()?;
+            let signed = match position.side {
+                Side::Buy => volume,
+                Side::Sell => -volume,
+            };
+            *net.entry(position.pair.clone()).or_insert(0.0) += signed;
+        }
+        Ok(net)
+    }
+}
+
+/// What `KrakenSession::shutdown` did.
+#[derive(Debug)]
+pub struct ShutdownReport {
+    /// `false` if order calls were still in flight when the grace period ran out
+    pub drained: bool,
+    /// The cancel-all, when `with_cancel_on_shutdown` is set
+    pub cancelled: Option<KillSwitchReport>,
+    /// Frames the attached `Recorder` wrote, or why it failed
+    pub recorded: Option<KrakenResult<u64>>,
+    /// Closing the WebSocket, when the session has one
+    pub ws_closed: Option<KrakenResult<()>>,
+}
+
+/// A trading session: a REST client, optionally a WebSocket connection, and
+/// what the session knows about the exchange's state.
+///
+/// Orders go through an `OrderRouter` (WebSocket first, REST as fallback),
+/// gated on `exchange_state`: in `CancelOnly` or `Maintenance` new orders and
+/// amendments are refused locally with `KrakenError::ExchangeRestricted`, and
+/// in `PostOnly` so are market orders. Cancels always go through.
+///
+/// The state starts as `Online`. `refresh_exchange_state` (or a running
+/// `start_status_monitor`) updates it from `SystemStatus`, and an order
+/// rejected with `EService:Market in cancel_only mode` (or `post_only`) moves
+/// it there straight away. Such a rejection is not sticky: the session
+/// re-reads `SystemStatus` after `with_state_recheck` (`DEFAULT_STATE_RECHECK`),
+/// retrying with a growing backoff while the read fails, and takes whatever
+/// state it reports.
+///
+/// `kill_switch` cancels everything and halts the session: until `resume`,
+/// new orders and amendments fail with `KrakenError::SessionHalted`.
+/// `shutdown` winds the session down for good.
+///
+/// A session can trade for subaccounts too (`with_account`): an order tagged
+/// with an account (`OrderRequest::with_account`) goes through that account's
+/// clients, and later cancels and amendments of it follow. Halts, shutdown
+/// and the exchange state apply to every account; `consolidated` reads them
+/// all at once.
+pub struct KrakenSession {
+    rest: AuthenticatedClient,
+    ws: Option<Arc<KrakenWsClient>>,
+    router: OrderRouter,
+    ack_deadline: Duration,
+    accounts: BTreeMap<String, Account>,
+    /// Which subaccount placed each order, by order ID
+    owners: Mutex<HashMap<String, String>>,
+    state: Arc<RwLock<ExchangeState>>,
+    /// Delay before re-reading `SystemStatus` after a restricted-state rejection
+    state_recheck: Duration,
+    /// Set while a re-read is scheduled, so rejections don't stack them up
+    rechecking: Arc<AtomicBool>,
+    status_page: StatusPage,
+    halted: AtomicBool,
+    closing: AtomicBool,
+    cancel_on_shutdown: bool,
+    /// Order calls under way, for `shutdown` to wait on
+    in_flight: watch::Sender<usize>,
+    recorder: Mutex<Option<Recorder>>,
+}
+
+impl KrakenSession {
+    /// A REST-only session until `with_ws` adds a socket.
+    pub fn new(rest: AuthenticatedClient) -> Self {
+        Self {
+            router: OrderRouter::new(rest.clone()),
+            rest,
+            ws: None,
+            ack_deadline: DEFAULT_ACK_DEADLINE,
+            accounts: BTreeMap::new(),
+            owners: Mutex::new(HashMap::new()),
+            state: Arc::new(RwLock::new(ExchangeState::Online)),
+            state_recheck: DEFAULT_STATE_RECHECK,
+            rechecking: Arc::new(AtomicBool::new(false)),
+            status_page: StatusPage::default(),
+            halted: AtomicBool::new(false),
+            closing: AtomicBool::new(false),
+            cancel_on_shutdown: false,
+            in_flight: watch::Sender::new(0),
+            recorder: Mutex::new(None),
+        }
+    }
+
+    /// Trade over `ws` (with its `token` set) when it's connected.
+    pub fn with_ws(mut self, ws: Arc<KrakenWsClient>) -> Self {
+        self.router = self.router.with_ws(ws.clone());
+        self.ws = Some(ws);
+        self
+    }
+
+    /// See `OrderRouter::with_ack_deadline`. Applies to every account.
+    pub fn with_ack_deadline(mut self, ack_deadline: Duration) -> Self {
+        self.router = self.router.with_ack_deadline(ack_deadline);
+        self.ack_deadline = ack_deadline;
+        for account in self.accounts.values_mut() {
+            account.router = route(&account.rest, account.ws.as_ref(), ack_deadline);
+        }
+        self
+    }
+
+    /// Trade for subaccount `account` as well: orders tagged with it go
+    /// through `rest`, signed with the subaccount's key (e.g. from
+    /// `with_call_credentials`), and `ws` when given, connected with a
+    /// `token` of the subaccount's own. `MAIN_ACCOUNT` is taken.
+    pub fn with_account(
+        mut self,
+        account: impl Into<String>,
+        rest: AuthenticatedClient,
+        ws: Option<Arc<KrakenWsClient>>,
+    ) -> Self {
+        let router = route(&rest, ws.as_ref(), self.ack_deadline);
+        let added = Account { rest, ws, router };
+        self.accounts.insert(account.into(), added);
+        self
+    }
+
+    /// Read incidents for `status_events` from `status_page` instead of
+    /// Kraken's (`PRODUCTION_STATUS_URL`).
+    pub fn with_status_page(mut self, status_page: StatusPage) -> Self {
+        self.status_page = status_page;
+        self
+    }
+
+    /// Re-read `SystemStatus` this long after an order rejection moves the
+    /// exchange state to `CancelOnly`/`PostOnly`, instead of
+    /// `DEFAULT_STATE_RECHECK`.
+    pub fn with_state_recheck(mut self, delay: Duration) -> Self {
+        self.state_recheck = delay;
+        self
+    }
+
+    /// Have `shutdown` cancel every open order once in-flight calls drain.
+    pub fn with_cancel_on_shutdown(mut self, cancel: bool) -> Self {
+        self.cancel_on_shutdown = cancel;
+        self
+    }
+
+    /// Stop and flush `recorder` on `shutdown`.
+    pub fn with_recorder(self, recorder: Recorder) -> Self {
+        *self.recorder.lock().unwrap_or_else(|e| e.into_inner()) = Some(recorder);
+        self
+    }
+
+    pub fn rest(&self) -> &AuthenticatedClient {
+        &self.rest
+    }
+
+    pub fn ws(&self) -> Option<&Arc<KrakenWsClient>> {
+        self.ws.as_ref()
+    }
+
+    /// IDs of the subaccounts added with `with_account`, sorted.
+    pub fn accounts(&self) -> impl Iterator<Item = &str> {
+        self.accounts.keys().map(String::as_str)
+    }
+
+    /// Positions and balances of the session's own account and every
+    /// subaccount, read concurrently. Fails if any account can't be read.
+    pub async fn consolidated(&self) -> KrakenResult<ConsolidatedView> {
+        let routers = self
+            .accounts
+            .iter()
+            .map(|(id, account)| (id.as_str(), &account.router));
+        let reads = std::iter::once((MAIN_ACCOUNT, &self.router))
+            .chain(routers)
+            .map(|(id, router)| async move {
+                let (positions, balances) =
+                    tokio::try_join!(router.positions(), router.balances())?;
+                Ok::<_, KrakenError>((id.to_string(), positions, balances))
+            });
+        let mut view = ConsolidatedView::default();
+        for (id, positions, balances) in try_join_all(reads).await? {
+            view.positions.insert(id.clone(), positions);
+            view.balances.insert(id, balances);
+        }
+        Ok(view)
+    }
+
+    /// The exchange state as last observed.
+    pub fn exchange_state(&self) -> ExchangeState {
+        *self.state.read().unwrap()
+    }
+
+    /// Read `SystemStatus` and update `exchange_state` from it.
+    pub async fn refresh_exchange_state(&self) -> KrakenResult<ExchangeState> {
+        refresh(&self.rest, &self.state).await
+    }
+
+    /// Refresh `exchange_state` every `interval` from a background task until
+    /// the handle is aborted. Failed reads are logged and keep the last state.
+    pub fn start_status_monitor(&self, interval: Duration) -> JoinHandle<()> {
+        let (rest, state) = (self.rest.clone(), self.state.clone());
+        tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(interval);
+            loop {
+                ticks.tick().await;
+                if let Err(e) = refresh(&rest, &state).await {
+                    tracing::warn!(error = %e, "failed to refresh exchange state");
+                }
+            }
+        })
+    }
+
+    /// Incidents and component changes on Kraken's status page, polled every
+    /// `interval` (see `StatusPage::events`). The session doesn't act on
+    /// them itself: e.g. pull `kill_switch` on `IncidentOpened`, or stop
+    /// quoting while a component is degraded.
+    pub fn status_events(&self, interval: Duration) -> FeedStream<StatusEvent> {
+        self.status_page.events(interval)
+    }
+
+    /// Emergency risk-off: halt the session, then cancel every open order
+    /// over REST `CancelAll` and, when the socket can trade, WebSocket
+    /// `cancelAll` at the same time, so either route getting through is
+    /// enough. Every subaccount is cancelled the same way, concurrently.
+    /// Orders placed after this call are refused until `resume`.
+    pub async fn kill_switch(&self) -> KillSwitchReport {
+        self.halted.store(true, Ordering::SeqCst);
+        tracing::warn!("kill switch pulled; cancelling all orders");
+        let accounts = self.accounts.iter().map(|(id, account)| async move {
+            let report = cancel_all(&account.rest, account.ws.as_deref()).await;
+            (id.clone(), report)
+        });
+        let (mut report, accounts) = tokio::join!(
+            cancel_all(&self.rest, self.ws.as_deref()),
+            join_all(accounts)
+        );
+        report.accounts = accounts.into_iter().collect();
+        if !report.confirmed() {
+            tracing::warn!(?report, "kill switch could not confirm a cancel-all");
+        }
+        report
+    }
+
+    /// `true` once `kill_switch` has been pulled, until `resume`.
+    pub fn is_halted(&self) -> bool {
+        self.halted.load(Ordering::SeqCst)
+    }
+
+    /// Accept orders again after `kill_switch`.
+    pub fn resume(&self) {
+        if self.halted.swap(false, Ordering::SeqCst) {
+            tracing::info!("session resumed after kill switch");
+        }
+    }
+
+    /// Wind the session down, e.g. from a SIGTERM handler: refuse new orders
+    /// and amendments (`KrakenError::SessionClosed`), wait up to `grace` for
+    /// order calls already under way, cancel every open order if
+    /// `with_cancel_on_shutdown` is set, stop the `Recorder`, flush the audit
+    /// log of every account, and close the WebSockets. Cancels keep working
+    /// throughout.
+    ///
+    /// ```no_run
+    /// # async fn run(session: onise::session::KrakenSession) {
+    /// use std::time::Duration;
+    ///
+    /// tokio::signal::ctrl_c().await.unwrap();
+    /// let report = session.shutdown(Duration::from_secs(10)).await;
+    /// if !report.drained {
+    ///     eprintln!("exited with order calls still in flight");
+    /// }
+    /// # }
+    /// ```
+    pub async fn shutdown(&self, grace: Duration) -> ShutdownReport {
+        self.closing.store(true, Ordering::SeqCst);
+        tracing::info!(?grace, "session shutting down");
+        let mut in_flight = self.in_flight.subscribe();
+        let drained = tokio::time::timeout(grace, in_flight.wait_for(|calls| *calls == 0))
+            .await
+            .is_ok();
+        if !drained {
+            tracing::warn!(
+                in_flight = *self.in_flight.borrow(),
+                "order calls still in flight after the shutdown grace period"
+            );
+        }
+        let cancelled = if self.cancel_on_shutdown {
+            let was_halted = self.halted.load(Ordering::SeqCst);
+            let report = self.kill_switch().await;
+            self.halted.store(was_halted, Ordering::SeqCst);
+            Some(report)
+        } else {
+            None
+        };
+        let recorder = self
+            .recorder
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take();
+        let recorded = match recorder {
+            Some(recorder) => Some(recorder.stop().await),
+            None => None,
+        };
+        self.rest.flush_audit();
+        for account in self.accounts.values() {
+            account.rest.flush_audit();
+        }
+        let ws_closed = match &self.ws {
+            Some(ws) if ws.is_connected() => Some(ws.close().await),
+            _ => None,
+        };
+        for (account, ws) in self.account_sockets() {
+            if let Err(e) = ws.close().await {
+                tracing::warn!(account, error = %e, "failed to close subaccount WebSocket");
+            }
+        }
+        ShutdownReport {
+            drained,
+            cancelled,
+            recorded,
+            ws_closed,
+        }
+    }
+
+    /// `true` once `shutdown` has been called.
+    pub fn is_closing(&self) -> bool {
+        self.closing.load(Ordering::SeqCst)
+    }
+
+    /// Count an order call as under way until the guard drops.
+    fn begin(&self) -> InFlight<'_> {
+        self.in_flight.send_modify(|calls| *calls += 1);
+        InFlight(&self.in_flight)
+    }
+
+    /// The connected sockets of subaccounts.
+    fn account_sockets(&self) -> impl Iterator<Item = (&str, &KrakenWsClient)> {
+        self.accounts.iter().filter_map(|(id, account)| {
+            let ws = account.ws.as_deref().filter(|ws| ws.is_connected())?;
+            Some((id.as_str(), ws))
+        })
+    }
+
+    /// The router for `account`; `None` is the session's own.
+    fn router_for(&self, account: Option<&str>) -> KrakenResult<&OrderRouter> {
+        match account {
+            None | Some(MAIN_ACCOUNT) => Ok(&self.router),
+            Some(id) => self
+                .accounts
+                .get(id)
+                .map(|account| &account.router)
+                .ok_or_else(|| {
+                    KrakenError::InvalidUsage(format!(
+                        "unknown account {id:?}; add it with KrakenSession::with_account"
+                    ))
+                }),
+        }
+    }
+
+    /// The subaccount that placed `order_id` through this session, if any.
+    fn owner(&self, order_id: &str) -> Option<String> {
+        self.owners.lock().unwrap().get(order_id).cloned()
+    }
+
+    fn set_state(&self, next: ExchangeState) {
+        set_state(&self.state, next);
+    }
+
+    /// Refuse `operation` locally unless the exchange would accept it.
+    fn check(&self, operation: &str, market: bool) -> KrakenResult<()> {
+        if self.is_closing() {
+            return Err(KrakenError::SessionClosed {
+                operation: operation.to_string(),
+            });
+        }
+        if self.is_halted() {
+            return Err(KrakenError::SessionHalted {
+                operation: operation.to_string(),
+            });
+        }
+        let state = self.exchange_state();
+        let allowed = match state {
+            ExchangeState::Online => true,
+            ExchangeState::PostOnly => !market,
+            ExchangeState::CancelOnly | ExchangeState::Maintenance => false,
+        };
+        if allowed {
+            Ok(())
+        } else {
+            Err(KrakenError::ExchangeRestricted {
+                state,
+                operation: operation.to_string(),
+            })
+        }
+    }
+
+    /// Pass `result` through, noting a restricted market it reports.
+    fn observe<T>(&self, result: KrakenResult<T>) -> KrakenResult<T> {
+        if let Err(KrakenError::ServiceError { message }) = result.as_ref().map_err(|e| e.inner()) {
+            if message.contains("cancel_only") {
+                self.set_state(ExchangeState::CancelOnly);
+                self.schedule_recheck();
+            } else if message.contains("post_only") {
+                self.set_state(ExchangeState::PostOnly);
+                self.schedule_recheck();
+            }
+        }
+        result
+    }
+
+    /// Re-read `SystemStatus` after `state_recheck`, backing off while the
+    /// read fails, so a state learned from a rejection doesn't stick. At most
+    /// one re-read is pending; it stops once the session is dropped.
+    fn schedule_recheck(&self) {
+        if self.rechecking.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let rest = self.rest.clone();
+        let state = Arc::downgrade(&self.state);
+        let rechecking = self.rechecking.clone();
+        let mut delay = self.state_recheck;
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(delay).await;
+                let Some(state) = state.upgrade() else {
+                    return;
+                };
+                match refresh(&rest, &state).await {
+                    Ok(next) => {
+                        tracing::info!(%next, "re-read exchange state after a rejection");
+                        break;
+                    }
+                    Err(e) => {
+                        tracing::warn!(error = %e, ?delay, "failed to re-read exchange state");
+                        delay = (delay * 2).clamp(Duration::from_secs(1), MAX_STATE_RECHECK);
+                    }
+                }
+            }
+            rechecking.store(false, Ordering::SeqCst);
+        });
+    }
+}
+
+impl std::fmt::Debug for KrakenSession {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KrakenSession")
+            .field("rest", &self.rest)
+            .field("ws", &self.ws.is_some())
+            .field("accounts", &self.accounts.keys().collect::<Vec<_>>())
+            .field("exchange_state", &self.exchange_state())
+            .field("halted", &self.is_halted())
+            .field("closing", &self.is_closing())
+            .finish()
+    }
+}
+
+/// A subaccount's clients; see `KrakenSession::with_account`.
+struct Account {
+    rest: AuthenticatedClient,
+    ws: Option<Arc<KrakenWsClient>>,
+    router: OrderRouter,
+}
+
+fn route(
+    rest: &AuthenticatedClient,
+    ws: Option<&Arc<KrakenWsClient>>,
+    ack_deadline: Duration,
+) -> OrderRouter {
+    let router = OrderRouter::new(rest.clone()).with_ack_deadline(ack_deadline);
+    match ws {
+        Some(ws) => router.with_ws(ws.clone()),
+        None => router,
+    }
+}
+
+/// One order call under way; see `KrakenSession::begin`.
+struct InFlight<'a>(&'a watch::Sender<usize>);
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.0.send_modify(|calls| *calls -= 1);
+    }
+}
+
+impl ExchangeClient for KrakenSession {
+    async fn place_order(&self, order: &OrderRequest) -> KrakenResult<PlacedOrder> {
+        let _call = self.begin();
+        self.check("place_order", order.kind == OrderKind::Market)?;
+        let router = self.router_for(order.account.as_deref())?;
+        let placed = self.observe(router.place_order(order).await)?;
+        if let Some(account) = order.account.as_deref().filter(|a| *a != MAIN_ACCOUNT) {
+            let mut owners = self.owners.lock().unwrap();
+            owners.insert(placed.order_id.clone(), account.to_string());
+        }
+        Ok(placed)
+    }
+
+    async fn cancel_order(&self, order_id: &str) -> KrakenResult<()> {
+        let _call = self.begin();
+        let router = self.router_for(self.owner(order_id).as_deref())?;
+        self.observe(router.cancel_order(order_id).await)?;
+        self.owners.lock().unwrap().remove(order_id);
+        Ok(())
+    }
+
+    async fn amend_order(&self, amendment: &OrderAmendment) -> KrakenResult<()> {
+        let _call = self.begin();
+        self.check("amend_order", false)?;
+        let router = self.router_for(self.owner(&amendment.order_id).as_deref())?;
+        self.observe(router.amend_order(amendment).await)
+    }
+
+    /// The session's own account; see `consolidated` for every account.
+    async fn positions(&self) -> KrakenResult<Vec<Position>> {
+        self.router.positions().await
+    }
+
+    async fn balances(&self) -> KrakenResult<HashMap<String, String>> {
+        self.router.balances().await
+    }
+}
+
+/// Cancel every order of one account over both routes at once.
+async fn cancel_all(rest: &AuthenticatedClient, ws: Option<&KrakenWsClient>) -> KillSwitchReport {
+    let rest = async { Ok(u64::from(rest.cancel_all_orders().await?.count)) };
+    let ws = async {
+        let ws = ws.filter(|ws| ws.token.is_some() && ws.is_connected())?;
+        Some(ws_cancel_all(ws).await)
+    };
+    let (rest, ws) = tokio::join!(rest, ws);
+    KillSwitchReport {
+        rest,
+        ws,
+        accounts: BTreeMap::new(),
+    }
+}
+
+async fn ws_cancel_all(ws: &KrakenWsClient) -> KrakenResult<u64> {
+    let request = WsCancelAllRequest {
+        event: "cancelAll".to_string(),
+        token: ws.token.clone().unwrap_or_default(),
+        req_id: Some(ws.next_req_id()),
+    };
+    match ws
+        .order_request(&request, Some(WS_TRADING_DEADLINE))
+        .await?
+    {
+        WsUserTradingResponse::CancelAllStatus { count, .. } => Ok(count.unwrap_or(0)),
+        other => Err(KrakenError::InvalidUsage(format!(
+            "cancelAll was answered with {other:?}"
+        ))),
+    }
+}
+
+async fn refresh(
+    rest: &AuthenticatedClient,
+    state: &RwLock<ExchangeState>,
+) -> KrakenResult<ExchangeState> {
+    let status = rest.get_system_status().await?;
+    let next = status.state().ok_or_else(|| {
+        KrakenError::InvalidUsage(format!("unknown system status {:?}", status.status))
+    })?;
+    set_state(state, next);
+    Ok(next)
+}
+
+fn set_state(state: &RwLock<ExchangeState>, next: ExchangeState) {
+    let previous = std::mem::replace(&mut *state.write().unwrap(), next);
+    if previous != next {
+        tracing::warn!(%previous, %next, "exchange state changed");
+    }
+}
@@ -0,0 +1,387 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, MutexGuard};
+
+use crate::error::{KrakenError, KrakenResult};
+use crate::exchange::{ExchangeClient, OrderAmendment, OrderRequest, PlacedOrder, Position, Side};
+
+/// One execution on a `SimulatedExchange`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulatedFill {
+    pub order_id: String,
+    pub cl_ord_id: Option<String>,
+    pub pair: String,
+    pub side: Side,
+    pub price: f64,
+    pub volume: f64,
+    /// Fee charged, in quote currency
+    pub fee: f64,
+}
+
+#[derive(Debug, Clone)]
+struct Market {
+    base: String,
+    quote: String,
+    bid: Option<f64>,
+    ask: Option<f64>,
+}
+
+#[derive(Debug, Clone)]
+struct RestingOrder {
+    cl_ord_id: Option<String>,
+    pair: String,
+    side: Side,
+    price: f64,
+    volume: f64,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    markets: HashMap<String, Market>,
+    balances: BTreeMap<String, f64>,
+    resting: BTreeMap<String, RestingOrder>,
+    fills: Vec<SimulatedFill>,
+    next_order: u64,
+}
+
+/// An in-memory paper-trading venue implementing `ExchangeClient`.
+///
+/// Register pairs with `with_pair`, fund the account with `with_balance`, and
+/// drive prices with `set_quote` (from a live feed or recorded data). Market
+/// orders fill immediately at the touch; limit orders fill at the touch if
+/// marketable, otherwise rest and fill at their limit price once a later quote
+/// crosses them. Every fill pays `fee_rate` of its notional, in quote currency.
+///
+/// Funds are checked when an order is placed, counting what resting orders
+/// already hold; orders that can't be covered are rejected with
+/// `KrakenError::OrderError` ("EOrder:Insufficient funds"), as Kraken would.
+/// `positions` reports the net base-currency holding built up per pair.
+#[derive(Debug)]
+pub struct SimulatedExchange {
+    fee_rate: f64,
+    state: Mutex<State>,
+}
+
+impl Default for SimulatedExchange {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SimulatedExchange {
+    /// An empty exchange with Kraken's base taker fee (0.40%).
+    pub fn new() -> Self {
+        Self {
+            fee_rate: 0.004,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// Fee charged per fill, as a fraction of notional (e.g. `0.0026`).
+    pub fn with_fee_rate(mut self, fee_rate: f64) -> Self {
+        self.fee_rate = fee_rate;
+        self
+    }
+
+    /// Make `pair` tradable, settling in `base` / `quote` balances.
+    pub fn with_pair(self, pair: &str, base: &str, quote: &str) -> Self {
+        self.lock().markets.insert(
+            pair.to_string(),
+            Market {
+                base: base.to_string(),
+                quote: quote.to_string(),
+                bid: None,
+                ask: None,
+            },
+        );
+        self
+    }
+
+    /// Start with `amount` of `asset`.
+    pub fn with_balance(self, asset: &str, amount: f64) -> Self {
+        self.lock().balances.insert(asset.to_string(), amount);
+        self
+    }
+
+    /// Update the best bid/ask of `pair` and fill any resting orders it crosses.
+    /// Returns the fills this caused.
+    pub fn set_quote(&self, pair: &str, bid: f64, ask: f64) -> KrakenResult<Vec<SimulatedFill>> {
+        let mut state = self.lock();
+        let market = state
+            .markets
+            .get_mut(pair)
+            .ok_or_else(|| unknown_pair(pair))?;
+        market.bid = Some(bid);
+        market.ask = Some(ask);
+
+        let crossed: Vec<String> = state
+            .resting
+            .iter()
+            .filter(|(_, order)| {
+                order.pair == pair
+                    && match order.side {
+                        Side::Buy => ask <= order.price,
+                        Side::Sell => bid >= order.price,
+                    }
+            })
+            .map(|(order_id, _)| order_id.clone())
+            .collect();
+        let mut fills = Vec::with_capacity(crossed.len());
+        for order_id in crossed {
+            if let Some(order) = state.resting.remove(&order_id) {
+                fills.push(self.fill(&mut state, order_id, order));
+            }
+        }
+        Ok(fills)
+    }
+
+    /// Every fill so far, oldest first.
+    pub fn fills(&self) -> Vec<SimulatedFill> {
+        self.lock().fills.clone()
+    }
+
+    /// IDs of orders still resting.
+    pub fn open_order_ids(&self) -> Vec<String> {
+        self.lock().resting.keys().cloned().collect()
+    }
+
+    /// Current balance of `asset` (zero if never funded).
+    pub fn balance(&self, asset: &str) -> f64 {
+        self.lock().balances.get(asset).copied().unwrap_or(0.0)
+    }
+
+    /// Every balance, by asset.
+    pub fn all_balances(&self) -> BTreeMap<String, f64> {
+        self.lock().balances.clone()
+    }
+
+    fn place(&self, order: &OrderRequest) -> KrakenResult<PlacedOrder> {
+        let volume = parse_amount("volume", &order.volume)?;
+        let limit = order
+            .limit_price()
+            .map(|price| parse_amount("price", price))
+            .transpose()?;
+
+        let mut state = self.lock();
+        let market = state
+            .markets
+            .get(&order.pair)
+            .ok_or_else(|| unknown_pair(&order.pair))?
+            .clone();
+        let touch = match order.side {
+            Side::Buy => market.ask,
+            Side::Sell => market.bid,
+        };
+        let marketable = match (limit, touch) {
+            (_, None) => false,
+            (None, Some(_)) => true,
+            (Some(limit), Some(touch)) => match order.side {
+                Side::Buy => touch <= limit,
+                Side::Sell => touch >= limit,
+            },
+        };
+        let price = match (marketable, touch, limit) {
+            (true, Some(touch), _) => touch,
+            (false, _, Some(limit)) => limit,
+            _ => {
+                return Err(KrakenError::OrderError {
+                    message: format!("EOrder:No price for {} yet", order.pair),
+                })
+            }
+        };
+
+        let (asset, needed) = match order.side {
+            Side::Buy => (&market.quote, volume * price * (1.0 + self.fee_rate)),
+            Side::Sell => (&market.base, volume),
+        };
+        let held = held(&state, self.fee_rate, asset);
+        let available = state.balances.get(asset).copied().unwrap_or(0.0) - held;
+        if needed > available + f64::EPSILON {
+            return Err(KrakenError::OrderError {
+                message: "EOrder:Insufficient funds".to_string(),
+            });
+        }
+
+        state.next_order += 1;
+        let order_id = format!("SIM-{:06}", state.next_order);
+        let resting = RestingOrder {
+            cl_ord_id: order.cl_ord_id.clone(),
+            pair: order.pair.clone(),
+            side: order.side,
+            price,
+            volume,
+        };
+        if marketable {
+            self.fill(&mut state, order_id.clone(), resting);
+        } else {
+            state.resting.insert(order_id.clone(), resting);
+        }
+        Ok(PlacedOrder { order_id })
+    }
+
+    fn fill(&self, state: &mut State, order_id: String, order: RestingOrder) -> SimulatedFill {
+        let market = &state.markets[&order.pair];
+        let (base, quote) = (market.base.clone(), market.quote.clone());
+        let notional = order.price * order.volume;
+        let fee = notional * self.fee_rate;
+        let (base_delta, quote_delta) = match order.side {
+            Side::Buy => (order.volume, -(notional + fee)),
+            Side::Sell => (-order.volume, notional - fee),
+        };
+        *state.balances.entry(base).or_default() += base_delta;
+        *state.balances.entry(quote).or_default() += quote_delta;
+        let fill = SimulatedFill {
+            order_id,
+            cl_ord_id: order.cl_ord_id,
+            pair: order.pair,
+            side: order.side,
+            price: order.price,
+            volume: order.volume,
+            fee,
+        };
+        state.fills.push(fill.clone());
+        fill
+    }
+
+    fn cancel(&self, order_id: &str) -> KrakenResult<()> {
+        self.lock()
+            .resting
+            .remove(order_id)
+            .map(drop)
+            .ok_or_else(|| unknown_order(order_id))
+    }
+
+    fn amend(&self, amendment: &OrderAmendment) -> KrakenResult<()> {
+        let volume = amendment
+            .volume
+            .as_deref()
+            .map(|volume| parse_amount("volume", volume))
+            .transpose()?;
+        let price = amendment
+            .limit_price
+            .as_deref()
+            .map(|price| parse_amount("price", price))
+            .transpose()?;
+
+        let mut state = self.lock();
+        let State {
+            markets, resting, ..
+        } = &mut *state;
+        let order = resting
+            .get_mut(&amendment.order_id)
+            .ok_or_else(|| unknown_order(&amendment.order_id))?;
+        if let Some(volume) = volume {
+            order.volume = volume;
+        }
+        if let Some(price) = price {
+            order.price = price;
+        }
+        // A new price that crosses the current quote takes liquidity at the touch
+        let market = &markets[&order.pair];
+        let touch = match order.side {
+            Side::Buy => market.ask.filter(|ask| *ask <= order.price),
+            Side::Sell => market.bid.filter(|bid| *bid >= order.price),
+        };
+        if let Some(touch) = touch {
+            if let Some(mut order) = state.resting.remove(&amendment.order_id) {
+                order.price = touch;
+                self.fill(&mut state, amendment.order_id.clone(), order);
+            }
+        }
+        Ok(())
+    }
+
+    fn net_positions(&self) -> Vec<Position> {
+        let state = self.lock();
+        let mut net: BTreeMap<&str, (f64, f64)> = BTreeMap::new();
+        for fill in &state.fills {
+            let (volume, cost) = net.entry(&fill.pair).or_default();
+            let sign = match fill.side {
+                Side::Buy => 1.0,
+                Side::Sell => -1.0,
+            };
+            *volume += sign * fill.volume;
+            *cost += sign * fill.volume * fill.price;
+        }
+        net.into_iter()
+            .filter(|(_, (volume, _))| volume.abs() > f64::EPSILON)
+            .map(|(pair, (volume, cost))| Position {
+                pair: pair.to_string(),
+                side: if volume > 0.0 { Side::Buy } else { Side::Sell },
+                volume: format_amount(volume.abs()),
+                cost: format_amount(cost.abs()),
+            })
+            .collect()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl ExchangeClient for SimulatedExchange {
+    async fn place_order(&self, order: &OrderRequest) -> KrakenResult<PlacedOrder> {
+        self.place(order)
+    }
+
+    async fn cancel_order(&self, order_id: &str) -> KrakenResult<()> {
+        self.cancel(order_id)
+    }
+
+    async fn amend_order(&self, amendment: &OrderAmendment) -> KrakenResult<()> {
+        self.amend(amendment)
+    }
+
+    async fn positions(&self) -> KrakenResult<Vec<Position>> {
+        Ok(self.net_positions())
+    }
+
+    async fn balances(&self) -> KrakenResult<HashMap<String, String>> {
+        Ok(self
+            .lock()
+            .balances
+            .iter()
+            .map(|(asset, amount)| (asset.clone(), format_amount(*amount)))
+            .collect())
+    }
+}
+
+/// What resting orders currently reserve of `asset`.
+fn held(state: &State, fee_rate: f64, asset: &str) -> f64 {
+    state
+        .resting
+        .values()
+        .map(|order| {
+            let market = &state.markets[&order.pair];
+            match order.side {
+                Side::Buy if market.quote == asset => order.volume * order.price * (1.0 + fee_rate),
+                Side::Sell if market.base == asset => order.volume,
+                _ => 0.0,
+            }
+        })
+        .sum()
+}
+
+fn parse_amount(what: &str, value: &str) -> KrakenResult<f64> {
+    match value.parse::<f64>() {
+        Ok(amount) if amount.is_finite() && amount > 0.0 => Ok(amount),
+        _ => Err(KrakenError::GeneralError {
+            message: format!("EGeneral:Invalid arguments:{what} {value:?}"),
+        }),
+    }
+}
+
+fn format_amount(amount: f64) -> String {
+    format!("{amount:.8}")
+}
+
+fn unknown_pair(pair: &str) -> KrakenError {
+    KrakenError::GeneralError {
+        message: format!("EQuery:Unknown asset pair {pair}"),
+    }
+}
+
+fn unknown_order(order_id: &str) -> KrakenError {
+    KrakenError::OrderError {
+        message: format!("EOrder:Unknown order {order_id}"),
+    }
+}
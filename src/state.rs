@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::error::{KrakenError, KrakenResult};
+
+/// Somewhere trackers keep their state across restarts, as one value per key.
+///
+/// `FileStateStore` keeps each key in a file; `MemoryStateStore` is for
+/// tests. Anything else (a SQLite table, a key-value service) only needs
+/// these three methods. Values are JSON written by the trackers themselves.
+pub trait StateStore: Send + Sync {
+    /// The value saved under `key`, if any.
+    fn load(&self, key: &str) -> KrakenResult<Option<Vec<u8>>>;
+    /// Replace the value under `key`.
+    fn save(&self, key: &str, value: &[u8]) -> KrakenResult<()>;
+    /// Forget `key`; removing a missing key is not an error.
+    fn remove(&self, key: &str) -> KrakenResult<()>;
+}
+
+/// A `StateStore` shared between the trackers using it.
+pub type SharedStateStore = Arc<dyn StateStore>;
+
+/// One file per key in a directory, replaced atomically (written to a
+/// temporary file, then renamed over the old one) so a crash mid-write leaves
+/// the previous state intact.
+#[derive(Debug, Clone)]
+pub struct FileStateStore {
+    dir: PathBuf,
+}
+
+impl FileStateStore {
+    /// Keep state in `dir`, creating it if needed.
+    pub fn open(dir: impl Into<PathBuf>) -> KrakenResult<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, key: &str) -> KrakenResult<PathBuf> {
+        if key.is_empty()
+            || !key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+            || key.starts_with('.')
+        {
+            return Err(KrakenError::InvalidUsage(format!(
+                "state key {key:?} must be letters, digits, '-', '_' or '.', not starting with '.'"
+            )));
+        }
+        Ok(self.dir.join(format!("{key}.json")))
+    }
+}
+
+impl StateStore for FileStateStore {
+    fn load(&self, key: &str) -> KrakenResult<Option<Vec<u8>>> {
+        match fs::read(self.path(key)?) {
+            Ok(value) => Ok(Some(value)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn save(&self, key: &str, value: &[u8]) -> KrakenResult<()> {
+        let path = self.path(key)?;
+        let temp = path.with_extension("json.tmp");
+        fs::write(&temp, value)?;
+        fs::rename(&temp, &path)?;
+        Ok(())
+    }
+
+    fn remove(&self, key: &str) -> KrakenResult<()> {
+        match fs::remove_file(self.path(key)?) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// A `StateStore` in memory, for tests: clones share the same values, so a
+/// "restarted" tracker can be handed a clone of the store the old one used.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStateStore {
+    values: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+}
+
+impl MemoryStateStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Vec<u8>>> {
+        self.values.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl StateStore for MemoryStateStore {
+    fn load(&self, key: &str) -> KrakenResult<Option<Vec<u8>>> {
+        Ok(self.lock().get(key).cloned())
+    }
+
+    fn save(&self, key: &str, value: &[u8]) -> KrakenResult<()> {
+        self.lock().insert(key.to_string(), value.to_vec());
+        Ok(())
+    }
+
+    fn remove(&self, key: &str) -> KrakenResult<()> {
+        self.lock().remove(key);
+        Ok(())
+    }
+}
+
+/// A store and the key one tracker keeps its state under.
+#[derive(Clone)]
+pub(crate) struct StateHandle {
+    store: SharedStateStore,
+    key: String,
+}
+
+impl StateHandle {
+    pub(crate) fn new(store: SharedStateStore, key: impl Into<String>) -> Self {
+        Self {
+            store,
+            key: key.into(),
+        }
+    }
+
+    pub(crate) fn load<T: DeserializeOwned>(&self) -> KrakenResult<Option<T>> {
+        load_json(&*self.store, &self.key)
+    }
+
+    pub(crate) fn save<T: Serialize + ?Sized>(&self, value: &T) -> KrakenResult<()> {
+        save_json(&*self.store, &self.key, value)
+    }
+
+    #[cfg(feature = "rest")]
+    pub(crate) fn remove(&self) -> KrakenResult<()> {
+        self.store.remove(&self.key)
+    }
+
+    /// `save`, logging rather than returning a failure, for trackers updated
+    /// from places that can't fail.
+    pub(crate) fn save_or_warn<T: Serialize + ?Sized>(&self, value: &T) {
+        if let Err(e) = self.save(value) {
+            tracing::warn!(key = %self.key, error = %e, "failed to persist state");
+        }
+    }
+}
+
+impl std::fmt::Debug for StateHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StateHandle")
+            .field("key", &self.key)
+            .finish()
+    }
+}
+
+fn load_json<T: DeserializeOwned>(store: &dyn StateStore, key: &str) -> KrakenResult<Option<T>> {
+    store
+        .load(key)?
+        .map(|value| serde_json::from_slice(&value))
+        .transpose()
+        .map_err(Into::into)
+}
+
+fn save_json<T: Serialize + ?Sized>(
+    store: &dyn StateStore,
+    key: &str,
+    value: &T,
+) -> KrakenResult<()> {
+    store.save(key, &serde_json::to_vec(value)?)
+}
@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use futures_util::stream::{self, StreamExt};
+use reqwest::header::USER_AGENT;
+use reqwest::Client as HttpClient;
+use serde::{Deserialize, Serialize};
+use tokio::time::MissedTickBehavior;
+
+use crate::environment::PRODUCTION_STATUS_URL;
+use crate::error::{KrakenError, KrakenResult};
+use crate::feed::FeedStream;
+use crate::rest_client::DEFAULT_USER_AGENT;
+
+/// How a component of Kraken's status page is doing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComponentStatus {
+    Operational,
+    DegradedPerformance,
+    PartialOutage,
+    MajorOutage,
+    UnderMaintenance,
+    /// A status this crate doesn't know (yet)
+    #[serde(other)]
+    Unknown,
+}
+
+impl ComponentStatus {
+    /// Anything but `Operational`, unknown statuses included.
+    pub fn is_degraded(self) -> bool {
+        self != ComponentStatus::Operational
+    }
+}
+
+/// One component of the status page, e.g. "Spot Trading" or "Funding".
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatusComponent {
+    pub id: String,
+    pub name: String,
+    pub status: ComponentStatus,
+    /// Groups only sum up the components under them
+    #[serde(default)]
+    pub group: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IncidentUpdate {
+    pub status: String,
+    pub body: String,
+    pub created_at: Option<String>,
+}
+
+/// An incident posted on the status page.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Incident {
+    pub id: String,
+    pub name: String,
+    /// "investigating", "identified", "monitoring", "resolved" or "postmortem"
+    pub status: String,
+    /// "none", "minor", "major" or "critical"
+    pub impact: String,
+    /// Link to the incident's page
+    pub shortlink: Option<String>,
+    pub updated_at: Option<String>,
+    /// Newest first
+    #[serde(default)]
+    pub incident_updates: Vec<IncidentUpdate>,
+}
+
+impl Incident {
+    pub fn is_resolved(&self) -> bool {
+        matches!(self.status.as_str(), "resolved" | "postmortem")
+    }
+}
+
+/// The status page as a whole, from `StatusPage::summary`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StatusSummary {
+    #[serde(default)]
+    pub components: Vec<StatusComponent>,
+    /// Incidents not yet resolved
+    #[serde(default)]
+    pub incidents: Vec<Incident>,
+}
+
+impl StatusSummary {
+    /// What changed from `previous` to this summary. Components new since
+    /// `previous` count as having been operational, so the changes since
+    /// `StatusSummary::default()` are every open incident and degraded
+    /// component.
+    pub fn changes_since(&self, previous: &StatusSummary) -> Vec<StatusEvent> {
+        let mut events = Vec::new();
+
+        let before: HashMap<&str, &Incident> = previous
+            .incidents
+            .iter()
+            .map(|incident| (incident.id.as_str(), incident))
+            .collect();
+        for incident in &self.incidents {
+            match before.get(incident.id.as_str()) {
+                Some(old) if old.is_resolved() || *old == incident => {}
+                Some(_) if incident.is_resolved() => {
+                    events.push(StatusEvent::IncidentResolved(incident.clone()))
+                }
+                Some(_) => events.push(StatusEvent::IncidentUpdated(incident.clone())),
+                None if incident.is_resolved() => {}
+                None => events.push(StatusEvent::IncidentOpened(incident.clone())),
+            }
+        }
+        // Dropped from the summary: resolved, as last seen
+        for old in &previous.incidents {
+            let gone = !self.incidents.iter().any(|incident| incident.id == old.id);
+            if gone && !old.is_resolved() {
+                events.push(StatusEvent::IncidentResolved(old.clone()));
+            }
+        }
+
+        let before: HashMap<&str, ComponentStatus> = previous
+            .components
+            .iter()
+            .map(|component| (component.id.as_str(), component.status))
+            .collect();
+        for component in &self.components {
+            let previous = before
+                .get(component.id.as_str())
+                .copied()
+                .unwrap_or(ComponentStatus::Operational);
+            if component.status == previous {
+                continue;
+            }
+            let component = component.clone();
+            events.push(if component.status.is_degraded() {
+                StatusEvent::ComponentDegraded {
+                    component,
+                    previous,
+                }
+            } else {
+                StatusEvent::ComponentRecovered {
+                    component,
+                    previous,
+                }
+            });
+        }
+        events
+    }
+}
+
+/// A change on Kraken's status page, from `StatusPage::events`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatusEvent {
+    IncidentOpened(Incident),
+    /// A new status or update on an open incident
+    IncidentUpdated(Incident),
+    IncidentResolved(Incident),
+    /// A component left `Operational`, or went from one degraded status to
+    /// another
+    ComponentDegraded {
+        component: StatusComponent,
+        previous: ComponentStatus,
+    },
+    /// A component is `Operational` again
+    ComponentRecovered {
+        component: StatusComponent,
+        previous: ComponentStatus,
+    },
+}
+
+/// Client for Kraken's public status page (status.kraken.com), where Kraken
+/// posts incidents and the state of each part of the exchange, often before
+/// `SystemStatus` changes.
+///
+/// `events` polls it and yields what changed, so a bot can reduce risk (e.g.
+/// `KrakenSession::kill_switch`) when an incident opens or a component it
+/// relies on degrades.
+#[derive(Debug, Clone)]
+pub struct StatusPage {
+    url: String,
+    http: HttpClient,
+    user_agent: String,
+}
+
+impl Default for StatusPage {
+    fn default() -> Self {
+        Self::new(PRODUCTION_STATUS_URL)
+    }
+}
+
+impl StatusPage {
+    /// Read the status summary JSON at `url` (e.g. `PRODUCTION_STATUS_URL`,
+    /// or a mock server).
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            http: HttpClient::new(),
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+        }
+    }
+
+    /// Send `user_agent` instead of `DEFAULT_USER_AGENT`.
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Every component and open incident, as the page shows them now.
+    pub async fn summary(&self) -> KrakenResult<StatusSummary> {
+        let response = self
+            .http
+            .get(&self.url)
+            .header(USER_AGENT, self.user_agent.as_str())
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(KrakenError::ServiceError {
+                message: format!("status page: HTTP {status}"),
+            });
+        }
+        Ok(serde_json::from_slice(&response.bytes().await?)?)
+    }
+
+    /// Poll the page every `interval` and yield each change, starting with
+    /// the incidents open and components degraded at the first poll. A
+    /// failed poll is logged and retried on the next tick; the stream runs
+    /// until dropped.
+    pub fn events(&self, interval: Duration) -> FeedStream<StatusEvent> {
+        let mut ticks = tokio::time::interval(interval);
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let state = (self.clone(), ticks, StatusSummary::default());
+        let polls = stream::unfold(state, |(page, mut ticks, last)| async move {
+            ticks.tick().await;
+            match page.summary().await {
+                Ok(current) => {
+                    let events = current.changes_since(&last);
+                    Some((events, (page, ticks, current)))
+                }
+                Err(e) => {
+                    tracing::warn!(error = %e, "status page poll failed; retrying next tick");
+                    Some((Vec::new(), (page, ticks, last)))
+                }
+            }
+        });
+        FeedStream::from_stream(polls.flat_map(stream::iter))
+    }
+}
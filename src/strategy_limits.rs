@@ -0,0 +1,286 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::clock::{self, Clock, SharedClock};
+use crate::error::{KrakenError, KrakenResult};
+use crate::exchange::{
+    ExchangeClient, OrderAmendment, OrderKind, OrderRequest, PlacedOrder, Position, Side,
+};
+use crate::numeric::Amount;
+use crate::quotes::QuoteCache;
+
+/// The window `StrategyLimits::max_orders_per_minute` counts over.
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Limits one strategy's orders are held to. Unset limits aren't checked.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct StrategyLimits {
+    /// New orders accepted in any 60-second window
+    pub max_orders_per_minute: Option<u32>,
+    /// Quote-currency notional (volume × price) of the strategy's open orders
+    pub max_open_notional: Option<f64>,
+}
+
+impl StrategyLimits {
+    pub fn with_max_orders_per_minute(mut self, orders: u32) -> Self {
+        self.max_orders_per_minute = Some(orders);
+        self
+    }
+
+    pub fn with_max_open_notional(mut self, notional: f64) -> Self {
+        self.max_open_notional = Some(notional);
+        self
+    }
+}
+
+#[derive(Debug, Default)]
+struct StrategyState {
+    /// When recent orders were accepted, oldest first
+    placed: VecDeque<Instant>,
+    /// Notional per open order ID (or pending placeholder)
+    open: HashMap<String, f64>,
+}
+
+impl StrategyState {
+    fn open_notional(&self) -> f64 {
+        self.open.values().sum()
+    }
+}
+
+/// An `ExchangeClient` wrapper enforcing `StrategyLimits` per
+/// `OrderRequest::strategy`, so one misbehaving strategy in a multi-strategy
+/// process can't use up the whole account's rate limits or risk.
+///
+/// An order over its strategy's limits is refused locally with
+/// `KrakenError::StrategyLimitExceeded`. Orders without a strategy, and
+/// strategies with no limits of their own when no `with_default_limits` is
+/// set, pass straight through.
+///
+/// Open notional counts orders placed through the throttle until they're
+/// cancelled through it or handed to `release` (e.g. from the executions
+/// feed when they fill). Limit orders are valued at their limit price; market
+/// orders at the touch from `with_quotes`, and are refused under a notional
+/// limit without one. Amendments pass through unchecked.
+///
+/// ```no_run
+/// # async fn run(client: onise::AuthenticatedClient) -> onise::error::KrakenResult<()> {
+/// use onise::exchange::{ExchangeClient, OrderRequest, Side};
+/// use onise::strategy_limits::{StrategyLimits, StrategyThrottle};
+///
+/// let exchange = StrategyThrottle::new(client).with_limits(
+///     "mean-reversion",
+///     StrategyLimits::default()
+///         .with_max_orders_per_minute(30)
+///         .with_max_open_notional(50_000.0),
+/// );
+/// let order = OrderRequest::limit("XBTUSD", Side::Buy, "0.1", "30000")
+///     .with_strategy("mean-reversion");
+/// exchange.place_order(&order).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct StrategyThrottle<E> {
+    inner: E,
+    limits: HashMap<String, StrategyLimits>,
+    default_limits: Option<StrategyLimits>,
+    quotes: Option<QuoteCache>,
+    clock: SharedClock,
+    state: Mutex<HashMap<String, StrategyState>>,
+    /// Numbers the placeholders holding notional while an order is in flight
+    pending: AtomicU64,
+}
+
+impl<E: ExchangeClient> StrategyThrottle<E> {
+    /// Wrap `inner`, with no limits until `with_limits` / `with_default_limits`.
+    pub fn new(inner: E) -> Self {
+        Self {
+            inner,
+            limits: HashMap::new(),
+            default_limits: None,
+            quotes: None,
+            clock: clock::system(),
+            state: Mutex::new(HashMap::new()),
+            pending: AtomicU64::new(0),
+        }
+    }
+
+    /// Hold orders tagged `strategy` to `limits`.
+    pub fn with_limits(mut self, strategy: impl Into<String>, limits: StrategyLimits) -> Self {
+        self.limits.insert(strategy.into(), limits);
+        self
+    }
+
+    /// Limits for tagged strategies without limits of their own. Each
+    /// strategy still gets its own allowance.
+    pub fn with_default_limits(mut self, limits: StrategyLimits) -> Self {
+        self.default_limits = Some(limits);
+        self
+    }
+
+    /// Value market orders at the touch in `quotes` (ask to buy, bid to sell).
+    pub fn with_quotes(mut self, quotes: QuoteCache) -> Self {
+        self.quotes = Some(quotes);
+        self
+    }
+
+    /// Count the order-rate window with `clock` rather than the system clock.
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    pub fn inner(&self) -> &E {
+        &self.inner
+    }
+
+    /// Notional of `strategy`'s open orders, as tracked here.
+    pub fn open_notional(&self, strategy: &str) -> f64 {
+        self.lock()
+            .get(strategy)
+            .map_or(0.0, StrategyState::open_notional)
+    }
+
+    /// Stop counting `order_id` towards its strategy's open notional, e.g.
+    /// once it has filled. Unknown IDs are ignored.
+    pub fn release(&self, order_id: &str) {
+        for state in self.lock().values_mut() {
+            if state.open.remove(order_id).is_some() {
+                return;
+            }
+        }
+    }
+
+    fn limits_for(&self, strategy: &str) -> Option<StrategyLimits> {
+        self.limits.get(strategy).copied().or(self.default_limits)
+    }
+
+    fn notional(&self, order: &OrderRequest) -> KrakenResult<f64> {
+        let volume = Amount(&order.volume).to_f64_lossy()?;
+        let price = match &order.kind {
+            OrderKind::Limit { price } => Amount(price).to_f64_lossy()?,
+            OrderKind::Market => {
+                let quotes = self.quotes.as_ref().ok_or_else(|| {
+                    KrakenError::InvalidUsage(
+                        "a notional limit on market orders needs `with_quotes`".to_string(),
+                    )
+                })?;
+                let touch = quotes.touch(&order.pair)?;
+                let price = match order.side {
+                    Side::Buy => touch.ask,
+                    Side::Sell => touch.bid,
+                };
+                Amount(&price).to_f64_lossy()?
+            }
+        };
+        Ok(volume * price)
+    }
+
+    /// Check `order` against `limits` and hold its slot and notional under a
+    /// placeholder, returned for `settle`.
+    fn reserve(
+        &self,
+        strategy: &str,
+        limits: StrategyLimits,
+        order: &OrderRequest,
+    ) -> KrakenResult<String> {
+        let notional = match limits.max_open_notional {
+            Some(_) => self.notional(order)?,
+            None => 0.0,
+        };
+        let now = self.clock.now();
+        let exceeded = |reason: String| KrakenError::StrategyLimitExceeded {
+            strategy: strategy.to_string(),
+            reason,
+        };
+        let mut states = self.lock();
+        let state = states.entry(strategy.to_string()).or_default();
+        while state
+            .placed
+            .front()
+            .is_some_and(|at| now.saturating_duration_since(*at) >= RATE_WINDOW)
+        {
+            state.placed.pop_front();
+        }
+        if let Some(max) = limits.max_orders_per_minute {
+            if state.placed.len() >= max as usize {
+                return Err(exceeded(format!("{max} orders per minute")));
+            }
+        }
+        if let Some(max) = limits.max_open_notional {
+            let open = state.open_notional();
+            if open + notional > max {
+                return Err(exceeded(format!(
+                    "open notional {open} + {notional} would exceed {max}"
+                )));
+            }
+        }
+        let placeholder = format!("pending-{}", self.pending.fetch_add(1, Ordering::Relaxed));
+        state.placed.push_back(now);
+        state.open.insert(placeholder.clone(), notional);
+        Ok(placeholder)
+    }
+
+    /// Swap `placeholder` for the placed order's ID, or give its notional
+    /// back if the order wasn't placed. A rejected order still counts
+    /// towards the rate, as it did reach the venue.
+    fn settle(&self, strategy: &str, placeholder: &str, placed: Option<&PlacedOrder>) {
+        let mut states = self.lock();
+        let Some(state) = states.get_mut(strategy) else {
+            return;
+        };
+        if let Some(notional) = state.open.remove(placeholder) {
+            if let Some(placed) = placed {
+                state.open.insert(placed.order_id.clone(), notional);
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, StrategyState>> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<E: std::fmt::Debug> std::fmt::Debug for StrategyThrottle<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StrategyThrottle")
+            .field("inner", &self.inner)
+            .field("limits", &self.limits)
+            .field("default_limits", &self.default_limits)
+            .finish()
+    }
+}
+
+impl<E: ExchangeClient> ExchangeClient for StrategyThrottle<E> {
+    async fn place_order(&self, order: &OrderRequest) -> KrakenResult<PlacedOrder> {
+        let Some(strategy) = order.strategy.as_deref() else {
+            return self.inner.place_order(order).await;
+        };
+        let Some(limits) = self.limits_for(strategy) else {
+            return self.inner.place_order(order).await;
+        };
+        let placeholder = self.reserve(strategy, limits, order)?;
+        let placed = self.inner.place_order(order).await;
+        self.settle(strategy, &placeholder, placed.as_ref().ok());
+        placed
+    }
+
+    async fn cancel_order(&self, order_id: &str) -> KrakenResult<()> {
+        self.inner.cancel_order(order_id).await?;
+        self.release(order_id);
+        Ok(())
+    }
+
+    async fn amend_order(&self, amendment: &OrderAmendment) -> KrakenResult<()> {
+        self.inner.amend_order(amendment).await
+    }
+
+    async fn positions(&self) -> KrakenResult<Vec<Position>> {
+        self.inner.positions().await
+    }
+
+    async fn balances(&self) -> KrakenResult<HashMap<String, String>> {
+        self.inner.balances().await
+    }
+}
@@ -0,0 +1,488 @@
+use serde_json::{json, Value};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+use crate::fixtures;
+use crate::{AuthenticatedClient, PublicClient};
+
+/// API key used by `MockKraken::authenticated_client`.
+pub const TEST_API_KEY: &str = "testkit-api-key";
+
+/// A valid base64 API secret used by `MockKraken::authenticated_client`
+/// (the example secret from Kraken's signing documentation).
+pub const TEST_API_SECRET: &str =
+    "kQH5HW/8p1uGOVjbgWA7FunAmGO8lsSUXNsu3eow76sz84Q18fWxnyRzBHCd3pd5nE9qa99HAZtuZuj6F1huXg==";
+
+/// A REST endpoint the client supports. Its realistic successful response is
+/// the REST fixture of the same name.
+#[derive(Debug, Clone, Copy)]
+pub struct Endpoint {
+    /// Short snake_case name, e.g. "open_orders"
+    pub name: &'static str,
+    /// "GET" for public endpoints, "POST" for private ones
+    pub method: &'static str,
+    /// URI path, e.g. "/0/private/OpenOrders"
+    pub path: &'static str,
+}
+
+impl Endpoint {
+    /// The endpoint's success fixture (`fixtures/rest/<name>.json`).
+    pub fn sample(&self) -> &'static str {
+        fixtures::rest(self.name).unwrap_or_else(|| panic!("testkit: no fixture for {}", self.name))
+    }
+}
+
+/// Every REST endpoint exposed by `KrakenClient`, in client order.
+pub const ENDPOINTS: &[Endpoint] = &[
+    Endpoint {
+        name: "server_time",
+        method: "GET",
+        path: "/0/public/Time",
+    },
+    Endpoint {
+        name: "system_status",
+        method: "GET",
+        path: "/0/public/SystemStatus",
+    },
+    Endpoint {
+        name: "assets",
+        method: "GET",
+        path: "/0/public/Assets",
+    },
+    Endpoint {
+        name: "asset_pairs",
+        method: "GET",
+        path: "/0/public/AssetPairs",
+    },
+    Endpoint {
+        name: "ticker",
+        method: "GET",
+        path: "/0/public/Ticker",
+    },
+    Endpoint {
+        name: "ohlc",
+        method: "GET",
+        path: "/0/public/OHLC",
+    },
+    Endpoint {
+        name: "depth",
+        method: "GET",
+        path: "/0/public/Depth",
+    },
+    Endpoint {
+        name: "trades",
+        method: "GET",
+        path: "/0/public/Trades",
+    },
+    Endpoint {
+        name: "spread",
+        method: "GET",
+        path: "/0/public/Spread",
+    },
+    Endpoint {
+        name: "balance",
+        method: "POST",
+        path: "/0/private/Balance",
+    },
+    Endpoint {
+        name: "balance_ex",
+        method: "POST",
+        path: "/0/private/BalanceEx",
+    },
+    Endpoint {
+        name: "trade_balance",
+        method: "POST",
+        path: "/0/private/TradeBalance",
+    },
+    Endpoint {
+        name: "open_orders",
+        method: "POST",
+        path: "/0/private/OpenOrders",
+    },
+    Endpoint {
+        name: "closed_orders",
+        method: "POST",
+        path: "/0/private/ClosedOrders",
+    },
+    Endpoint {
+        name: "query_orders",
+        method: "POST",
+        path: "/0/private/QueryOrders",
+    },
+    Endpoint {
+        name: "trades_history",
+        method: "POST",
+        path: "/0/private/TradesHistory",
+    },
+    Endpoint {
+        name: "query_trades",
+        method: "POST",
+        path: "/0/private/QueryTrades",
+    },
+    Endpoint {
+        name: "open_positions",
+        method: "POST",
+        path: "/0/private/OpenPositions",
+    },
+    Endpoint {
+        name: "ledgers",
+        method: "POST",
+        path: "/0/private/Ledgers",
+    },
+    Endpoint {
+        name: "query_ledgers",
+        method: "POST",
+        path: "/0/private/QueryLedgers",
+    },
+    Endpoint {
+        name: "trade_volume",
+        method: "POST",
+        path: "/0/private/TradeVolume",
+    },
+    Endpoint {
+        name: "export_trades",
+        method: "POST",
+        path: "/0/private/ExportTrades",
+    },
+    Endpoint {
+        name: "export_status",
+        method: "POST",
+        path: "/0/private/ExportStatus",
+    },
+    Endpoint {
+        name: "retrieve_export",
+        method: "POST",
+        path: "/0/private/RetrieveExport",
+    },
+    Endpoint {
+        name: "delete_export",
+        method: "POST",
+        path: "/0/private/DeleteExport",
+    },
+    Endpoint {
+        name: "add_order",
+        method: "POST",
+        path: "/0/private/AddOrder",
+    },
+    Endpoint {
+        name: "add_order_batch",
+        method: "POST",
+        path: "/0/private/AddOrderBatch",
+    },
+    Endpoint {
+        name: "amend_order",
+        method: "POST",
+        path: "/0/private/AmendOrder",
+    },
+    Endpoint {
+        name: "edit_order",
+        method: "POST",
+        path: "/0/private/EditOrder",
+    },
+    Endpoint {
+        name: "cancel_order",
+        method: "POST",
+        path: "/0/private/CancelOrder",
+    },
+    Endpoint {
+        name: "cancel_all",
+        method: "POST",
+        path: "/0/private/CancelAll",
+    },
+    Endpoint {
+        name: "cancel_all_orders_after",
+        method: "POST",
+        path: "/0/private/CancelAllOrdersAfter",
+    },
+    Endpoint {
+        name: "cancel_order_batch",
+        method: "POST",
+        path: "/0/private/CancelOrderBatch",
+    },
+    Endpoint {
+        name: "websockets_token",
+        method: "POST",
+        path: "/0/private/GetWebSocketsToken",
+    },
+    Endpoint {
+        name: "deposit_methods",
+        method: "POST",
+        path: "/0/private/DepositMethods",
+    },
+    Endpoint {
+        name: "deposit_addresses",
+        method: "POST",
+        path: "/0/private/DepositAddresses",
+    },
+    Endpoint {
+        name: "deposit_status",
+        method: "POST",
+        path: "/0/private/DepositStatus",
+    },
+    Endpoint {
+        name: "withdrawal_methods",
+        method: "POST",
+        path: "/0/private/WithdrawalMethods",
+    },
+    Endpoint {
+        name: "withdrawal_addresses",
+        method: "POST",
+        path: "/0/private/WithdrawalAddresses",
+    },
+    Endpoint {
+        name: "withdrawal_information",
+        method: "POST",
+        path: "/0/private/WithdrawalInformation",
+    },
+    Endpoint {
+        name: "withdraw",
+        method: "POST",
+        path: "/0/private/Withdraw",
+    },
+    Endpoint {
+        name: "withdraw_status",
+        method: "POST",
+        path: "/0/private/WithdrawStatus",
+    },
+    Endpoint {
+        name: "withdraw_cancel",
+        method: "POST",
+        path: "/0/private/WithdrawCancel",
+    },
+    Endpoint {
+        name: "wallet_transfer",
+        method: "POST",
+        path: "/0/private/WalletTransfer",
+    },
+    Endpoint {
+        name: "create_subaccount",
+        method: "POST",
+        path: "/0/private/CreateSubaccount",
+    },
+    Endpoint {
+        name: "account_transfer",
+        method: "POST",
+        path: "/0/private/AccountTransfer",
+    },
+    Endpoint {
+        name: "stake",
+        method: "POST",
+        path: "/0/private/Staking/Stake",
+    },
+    Endpoint {
+        name: "unstake",
+        method: "POST",
+        path: "/0/private/Staking/Unstake",
+    },
+    Endpoint {
+        name: "stake_status",
+        method: "POST",
+        path: "/0/private/Staking/GetStakeStatus",
+    },
+    Endpoint {
+        name: "unstake_status",
+        method: "POST",
+        path: "/0/private/Staking/GetUnstakeStatus",
+    },
+    Endpoint {
+        name: "staking_assets",
+        method: "POST",
+        path: "/0/private/Staking/ListStakingProducts",
+    },
+    Endpoint {
+        name: "staking_transactions",
+        method: "POST",
+        path: "/0/private/Staking/ListStakingTransactions",
+    },
+    Endpoint {
+        name: "otc_quote",
+        method: "POST",
+        path: "/0/private/CreateOtcQuoteRequest",
+    },
+    Endpoint {
+        name: "otc_update_quote",
+        method: "POST",
+        path: "/0/private/UpdateOtcQuote",
+    },
+    Endpoint {
+        name: "otc_quotes",
+        method: "POST",
+        path: "/0/private/GetOtcActiveQuotes",
+    },
+];
+
+/// Look up an endpoint by URI path.
+pub fn endpoint(path: &str) -> Option<&'static Endpoint> {
+    ENDPOINTS.iter().find(|e| e.path == path)
+}
+
+/// The Kraken error families the client distinguishes (see `KrakenError::from_kraken_errors`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// `EGeneral:` → `KrakenError::GeneralError`
+    General,
+    /// `EAPI:` → `KrakenError::ApiError`
+    Api,
+    /// `EService:` → `KrakenError::ServiceError`
+    Service,
+    /// `EOrder:` → `KrakenError::OrderError`
+    Order,
+    /// `ETrade:` → `KrakenError::TradingError`
+    Trading,
+    /// `EAPI:Rate limit exceeded` → `KrakenError::RateLimitExceeded`
+    RateLimit,
+    /// An unrecognized prefix → `KrakenError::Kraken`
+    Unknown,
+}
+
+impl ErrorClass {
+    pub const ALL: [ErrorClass; 7] = [
+        ErrorClass::General,
+        ErrorClass::Api,
+        ErrorClass::Service,
+        ErrorClass::Order,
+        ErrorClass::Trading,
+        ErrorClass::RateLimit,
+        ErrorClass::Unknown,
+    ];
+
+    /// A representative error string Kraken sends for this class.
+    pub fn message(self) -> &'static str {
+        match self {
+            ErrorClass::General => "EGeneral:Invalid arguments",
+            ErrorClass::Api => "EAPI:Invalid key",
+            ErrorClass::Service => "EService:Unavailable",
+            ErrorClass::Order => "EOrder:Insufficient funds",
+            ErrorClass::Trading => "ETrade:Locked",
+            ErrorClass::RateLimit => "EAPI:Rate limit exceeded",
+            ErrorClass::Unknown => "EUnknown:Something new",
+        }
+    }
+}
+
+/// Priority of the catch-all mocks from `mock_all_success`, so any mock
+/// mounted explicitly for a path wins over them.
+const FALLBACK_PRIORITY: u8 = 10;
+
+/// A local HTTP server that behaves like Kraken's REST API.
+///
+/// ```no_run
+/// # async fn demo() {
+/// use onise::testkit::{ErrorClass, MockKraken};
+///
+/// let kraken = MockKraken::start().await;
+/// kraken.mock_all_success().await;
+/// kraken.mock_error("/0/private/AddOrder", ErrorClass::Order).await;
+///
+/// let client = kraken.authenticated_client();
+/// assert!(client.get_balance().await.is_ok());
+/// assert!(client.add_order(&[("pair", "XBTUSD")]).await.is_err());
+/// # }
+/// ```
+///
+/// Enabled with the `testkit` feature.
+pub struct MockKraken {
+    server: MockServer,
+}
+
+impl MockKraken {
+    /// Start a server with no mocks mounted; unmatched requests get a 404.
+    pub async fn start() -> Self {
+        Self {
+            server: MockServer::start().await,
+        }
+    }
+
+    /// Base URL to pass as a client's `base_url`.
+    pub fn uri(&self) -> String {
+        self.server.uri()
+    }
+
+    /// The underlying wiremock server, for custom matchers and expectations.
+    pub fn server(&self) -> &MockServer {
+        &self.server
+    }
+
+    /// A public client pointed at this server.
+    pub fn public_client(&self) -> PublicClient {
+        PublicClient::new(Some(self.uri()))
+    }
+
+    /// An authenticated client pointed at this server, using `TEST_API_KEY` / `TEST_API_SECRET`.
+    pub fn authenticated_client(&self) -> AuthenticatedClient {
+        AuthenticatedClient::new(TEST_API_KEY, TEST_API_SECRET, Some(self.uri()))
+            .expect("TEST_API_SECRET is valid base64")
+    }
+
+    /// Answer every known endpoint with its sample response. Mocks mounted for a
+    /// specific path (before or after) take precedence.
+    pub async fn mock_all_success(&self) {
+        for endpoint in ENDPOINTS {
+            Self::mock_for(endpoint)
+                .respond_with(json_body(200, endpoint.sample()))
+                .with_priority(FALLBACK_PRIORITY)
+                .mount(&self.server)
+                .await;
+        }
+    }
+
+    /// Answer `path` with its sample response.
+    pub async fn mock_success(&self, path: &str) {
+        let endpoint = known(path);
+        self.mount(endpoint, json_body(200, endpoint.sample()))
+            .await;
+    }
+
+    /// Answer `path` with a custom `result` payload.
+    pub async fn mock_result(&self, path: &str, result: Value) {
+        let body = json!({ "error": [], "result": result }).to_string();
+        self.mount(known(path), json_body(200, &body)).await;
+    }
+
+    /// Answer `path` with a Kraken error of the given class.
+    pub async fn mock_error(&self, path: &str, class: ErrorClass) {
+        self.mock_errors(path, &[class.message()]).await;
+    }
+
+    /// Answer `path` with an arbitrary Kraken `error` array.
+    pub async fn mock_errors(&self, path: &str, errors: &[&str]) {
+        let body = json!({ "error": errors, "result": {} }).to_string();
+        self.mount(known(path), json_body(200, &body)).await;
+    }
+
+    /// Answer `path` with a successful envelope whose `result` has the wrong shape.
+    pub async fn mock_malformed(&self, path: &str) {
+        let body = r#"{"error":[],"result":"unexpected"}"#;
+        self.mount(known(path), json_body(200, body)).await;
+    }
+
+    /// Answer `path` with a non-JSON body, as an upstream proxy or CDN might.
+    pub async fn mock_http_status(&self, path: &str, status: u16) {
+        let response = ResponseTemplate::new(status).set_body_string("<html>upstream error</html>");
+        self.mount(known(path), response).await;
+    }
+
+    /// Every request the server has received so far.
+    pub async fn received_requests(&self) -> Vec<Request> {
+        self.server.received_requests().await.unwrap_or_default()
+    }
+
+    async fn mount(&self, endpoint: &Endpoint, response: ResponseTemplate) {
+        Self::mock_for(endpoint)
+            .respond_with(response)
+            .mount(&self.server)
+            .await;
+    }
+
+    fn mock_for(endpoint: &Endpoint) -> wiremock::MockBuilder {
+        Mock::given(method(endpoint.method)).and(path(endpoint.path))
+    }
+}
+
+fn known(path: &str) -> &'static Endpoint {
+    endpoint(path).unwrap_or_else(|| panic!("testkit: unknown endpoint {path}"))
+}
+
+fn json_body(status: u16, body: &str) -> ResponseTemplate {
+    ResponseTemplate::new(status).set_body_raw(body.to_string(), "application/json")
+}
@@ -0,0 +1,103 @@
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures_util::stream::{self, BoxStream, Stream, StreamExt};
+
+use crate::rate_limiter::RateLimiter;
+
+/// Rate-limit any stream (a `FeedStream`, a `PageStream`, or your own) with a
+/// `RateLimiter`, e.g. to cap how often a strategy reacts to book updates.
+///
+/// The limiter is shared, so one `Arc<RateLimiter>` can cap several streams
+/// together.
+///
+/// ```no_run
+/// # use futures_util::Stream;
+/// # use onise::ws_models::WsBookMessage;
+/// # async fn run(book_stream: impl Stream<Item = WsBookMessage> + Send + 'static) {
+/// use std::sync::Arc;
+/// use std::time::Duration;
+/// use futures_util::StreamExt;
+/// use onise::rate_limiter::RateLimiter;
+/// use onise::throttle::ThrottleExt;
+///
+/// // e.g. `ws.book_stream()`
+/// let limiter = Arc::new(RateLimiter::with_interval(Duration::from_millis(250)).unwrap());
+/// let mut books = book_stream.throttle_latest(limiter);
+/// while let Some(book) = books.next().await {
+///     // at most 4 times a second, always with the newest update
+/// }
+/// # }
+/// ```
+pub trait ThrottleExt: Stream + Sized + Send + 'static
+where
+    Self::Item: Send + 'static,
+{
+    /// Wait for a permit before yielding each item. Nothing is dropped; the
+    /// source is simply read more slowly (a `FeedStream` that falls too far
+    /// behind skips ahead, see `MESSAGE_BUFFER`).
+    fn throttle(self, limiter: Arc<RateLimiter>) -> Throttled<Self::Item> {
+        let inner = self.then(move |item| {
+            let limiter = limiter.clone();
+            async move {
+                limiter.acquire().await;
+                item
+            }
+        });
+        Throttled {
+            inner: Box::pin(inner),
+        }
+    }
+
+    /// Yield at most one item per permit, always the newest: items that arrive
+    /// while waiting for a permit replace the one being held. Suits state-like
+    /// feeds (ticker, book) where only the latest value matters.
+    fn throttle_latest(self, limiter: Arc<RateLimiter>) -> Throttled<Self::Item> {
+        let source = Box::pin(self);
+        let inner = stream::unfold(Some(source), move |source| {
+            let limiter = limiter.clone();
+            async move {
+                let mut source = source?;
+                let mut latest = source.next().await?;
+                loop {
+                    tokio::select! {
+                        biased;
+                        _ = limiter.acquire() => return Some((latest, Some(source))),
+                        next = source.next() => match next {
+                            Some(item) => latest = item,
+                            None => {
+                                // Source ended: still deliver what we hold.
+                                limiter.acquire().await;
+                                return Some((latest, None));
+                            }
+                        },
+                    }
+                }
+            }
+        });
+        Throttled {
+            inner: Box::pin(inner),
+        }
+    }
+}
+
+impl<S> ThrottleExt for S
+where
+    S: Stream + Send + 'static,
+    S::Item: Send + 'static,
+{
+}
+
+/// A stream returned by `ThrottleExt`.
+pub struct Throttled<T> {
+    inner: BoxStream<'static, T>,
+}
+
+impl<T> Stream for Throttled<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.inner.as_mut().poll_next(cx)
+    }
+}
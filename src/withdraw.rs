@@ -0,0 +1,326 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use crate::assets;
+use crate::error::{KrakenError, KrakenResult};
+use crate::models::{WithdrawFundsResponse, WithdrawalAddressItem, WithdrawalInformationResponse};
+use crate::rest_client::AuthenticatedClient;
+
+/// The account's whitelisted withdrawal addresses, as listed by
+/// `WithdrawalAddresses`, keyed by asset and key name.
+///
+/// Assets are matched by display name, so "XBT", "XXBT" and "BTC" find the
+/// same entries. Kraken lists unverified addresses too; check
+/// `is_verified` (or the entry's `verified`) before sending funds to one.
+#[derive(Debug, Clone)]
+pub struct AddressBook {
+    entries: BTreeMap<(String, String), WithdrawalAddressItem>,
+    synced_at: SystemTime,
+}
+
+impl AddressBook {
+    /// Fetch every withdrawal address on the account.
+    pub async fn sync(client: &AuthenticatedClient) -> KrakenResult<Self> {
+        let addresses = client.get_withdrawal_addresses(&[]).await?;
+        Ok(Self::from_items(None, addresses.0))
+    }
+
+    /// Fetch the withdrawal addresses for `asset` only.
+    pub async fn sync_asset(client: &AuthenticatedClient, asset: &str) -> KrakenResult<Self> {
+        let addresses = client
+            .get_withdrawal_addresses(&[("asset", asset)])
+            .await?;
+        Ok(Self::from_items(Some(asset), addresses.0))
+    }
+
+    /// Build a book from `items`; entries without an `asset` are filed under
+    /// `asset`, and entries with neither a key nor a name are dropped.
+    pub fn from_items(asset: Option<&str>, items: Vec<WithdrawalAddressItem>) -> Self {
+        let entries = items
+            .into_iter()
+            .filter_map(|item| {
+                let asset = assets::display_name(item.asset.as_deref().or(asset)?);
+                let key = item.key.clone().or_else(|| item.name.clone())?;
+                Some(((asset, key), item))
+            })
+            .collect();
+        Self {
+            entries,
+            synced_at: SystemTime::now(),
+        }
+    }
+
+    /// The address withdrawn to with key `key` for `asset`.
+    pub fn get(&self, asset: &str, key: &str) -> Option<&WithdrawalAddressItem> {
+        self.entries
+            .get(&(assets::display_name(asset), key.to_string()))
+    }
+
+    /// `true` if `key` is listed for `asset` and Kraken reports it verified.
+    pub fn is_verified(&self, asset: &str, key: &str) -> bool {
+        self.get(asset, key)
+            .is_some_and(|item| item.verified == Some(true))
+    }
+
+    /// Every (key, address) listed for `asset`, by key.
+    pub fn for_asset<'a>(
+        &'a self,
+        asset: &str,
+    ) -> impl Iterator<Item = (&'a str, &'a WithdrawalAddressItem)> + 'a {
+        let asset = assets::display_name(asset);
+        self.entries
+            .iter()
+            .filter(move |((listed, _), _)| *listed == asset)
+            .map(|((_, key), item)| (key.as_str(), item))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// When the addresses were fetched.
+    pub fn synced_at(&self) -> SystemTime {
+        self.synced_at
+    }
+}
+
+/// One withdrawal, as passed to `SafeWithdrawer::withdraw`.
+#[derive(Debug, Clone)]
+pub struct WithdrawalRequest {
+    /// Asset to withdraw, e.g. "XBT"
+    pub asset: String,
+    /// Withdrawal key (the address name set up on Kraken)
+    pub key: String,
+    /// Amount to withdraw, as a decimal string
+    pub amount: String,
+    /// Optional address the key must resolve to; Kraken rejects a mismatch too
+    pub address: Option<String>,
+}
+
+impl WithdrawalRequest {
+    pub fn new(
+        asset: impl Into<String>,
+        key: impl Into<String>,
+        amount: impl Into<String>,
+    ) -> Self {
+        Self {
+            asset: asset.into(),
+            key: key.into(),
+            amount: amount.into(),
+            address: None,
+        }
+    }
+
+    /// Also require the key to point at `address`.
+    pub fn with_address(mut self, address: impl Into<String>) -> Self {
+        self.address = Some(address.into());
+        self
+    }
+}
+
+/// What a confirmation callback gets to look at before the withdrawal is sent.
+#[derive(Debug)]
+pub struct WithdrawalPlan<'a> {
+    pub request: &'a WithdrawalRequest,
+    /// The whitelisted address the key resolved to
+    pub destination: &'a WithdrawalAddressItem,
+    /// Kraken's fee and limit quote, if `with_information_check` is on
+    pub information: Option<&'a WithdrawalInformationResponse>,
+}
+
+type Confirm = Arc<dyn Fn(&WithdrawalPlan<'_>) -> bool + Send + Sync>;
+
+/// Guard rails around `withdraw_funds`. Before anything is sent, a withdrawal must:
+///
+/// 1. be for an asset with a configured maximum (`with_max_amount`), and not exceed it;
+/// 2. go to a key listed by `WithdrawalAddresses` for the asset (and not known to be
+///    unverified), matching `WithdrawalRequest::address` if given; the list is
+///    fetched per withdrawal unless an `AddressBook` is set with `with_address_book`;
+/// 3. optionally (`with_information_check`) fit within the limit and maximum fee
+///    quoted by `WithdrawalInformation`;
+/// 4. be approved by the confirmation callback, if one is set.
+///
+/// Any failed check returns `KrakenError::WithdrawalBlocked` without calling `Withdraw`.
+#[derive(Clone)]
+pub struct SafeWithdrawer {
+    client: AuthenticatedClient,
+    max_amounts: HashMap<String, f64>,
+    max_fees: HashMap<String, f64>,
+    check_information: bool,
+    confirm: Option<Confirm>,
+    address_book: Option<AddressBook>,
+}
+
+impl SafeWithdrawer {
+    /// A withdrawer that allows nothing until maximums are configured.
+    pub fn new(client: AuthenticatedClient) -> Self {
+        Self {
+            client,
+            max_amounts: HashMap::new(),
+            max_fees: HashMap::new(),
+            check_information: false,
+            confirm: None,
+            address_book: None,
+        }
+    }
+
+    /// Allow withdrawing up to `max` of `asset` per withdrawal.
+    pub fn with_max_amount(mut self, asset: impl Into<String>, max: f64) -> Self {
+        self.max_amounts.insert(asset.into(), max);
+        self
+    }
+
+    /// Quote the withdrawal with `WithdrawalInformation` first and block it if the
+    /// amount exceeds Kraken's limit.
+    pub fn with_information_check(mut self) -> Self {
+        self.check_information = true;
+        self
+    }
+
+    /// Block `asset` withdrawals whose quoted fee exceeds `max_fee`. Implies
+    /// `with_information_check`.
+    pub fn with_max_fee(mut self, asset: impl Into<String>, max_fee: f64) -> Self {
+        self.max_fees.insert(asset.into(), max_fee);
+        self.check_information = true;
+        self
+    }
+
+    /// Ask `confirm` before sending; returning `false` blocks the withdrawal.
+    pub fn with_confirmation(
+        mut self,
+        confirm: impl Fn(&WithdrawalPlan<'_>) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.confirm = Some(Arc::new(confirm));
+        self
+    }
+
+    /// Resolve keys in `book` instead of fetching `WithdrawalAddresses` on
+    /// every withdrawal. Addresses added since `book` was synced are blocked.
+    pub fn with_address_book(mut self, book: AddressBook) -> Self {
+        self.address_book = Some(book);
+        self
+    }
+
+    /// The wrapped client.
+    pub fn client(&self) -> &AuthenticatedClient {
+        &self.client
+    }
+
+    /// Run every check, then withdraw.
+    pub async fn withdraw(
+        &self,
+        request: &WithdrawalRequest,
+    ) -> KrakenResult<WithdrawFundsResponse> {
+        let asset = request.asset.as_str();
+        let amount: f64 = request
+            .amount
+            .parse()
+            .map_err(|_| blocked(format!("amount {:?} is not a number", request.amount)))?;
+        let Some(max) = self.max_amounts.get(asset) else {
+            return Err(blocked(format!("no maximum configured for {asset}")));
+        };
+        if !(amount > 0.0 && amount <= *max) {
+            return Err(blocked(format!(
+                "{amount} {asset} is outside the allowed range (0, {max}]"
+            )));
+        }
+
+        let fetched;
+        let book = match &self.address_book {
+            Some(book) => book,
+            None => {
+                fetched = AddressBook::sync_asset(&self.client, asset).await?;
+                &fetched
+            }
+        };
+        let destination = book
+            .get(asset, &request.key)
+            .ok_or_else(|| {
+                blocked(format!(
+                    "{:?} is not a whitelisted {asset} address",
+                    request.key
+                ))
+            })?;
+        if destination.verified == Some(false) {
+            return Err(blocked(format!("{:?} is not verified", request.key)));
+        }
+        if let Some(address) = &request.address {
+            if *address != destination.address {
+                return Err(blocked(format!(
+                    "{:?} points at {}, not {address}",
+                    request.key, destination.address
+                )));
+            }
+        }
+
+        let information = if self.check_information {
+            let info = self
+                .client
+                .get_withdrawal_information(&[
+                    ("asset", asset),
+                    ("key", &request.key),
+                    ("amount", &request.amount),
+                ])
+                .await?;
+            let limit: f64 = info.limit.parse().unwrap_or(0.0);
+            if amount > limit {
+                return Err(blocked(format!(
+                    "{amount} {asset} exceeds Kraken's limit of {limit}"
+                )));
+            }
+            if let Some(max_fee) = self.max_fees.get(asset) {
+                let fee: f64 = info.fee.parse().unwrap_or(f64::INFINITY);
+                if fee > *max_fee {
+                    return Err(blocked(format!(
+                        "fee {fee} {asset} exceeds the maximum of {max_fee}"
+                    )));
+                }
+            }
+            Some(info)
+        } else {
+            None
+        };
+
+        if let Some(confirm) = &self.confirm {
+            let plan = WithdrawalPlan {
+                request,
+                destination,
+                information: information.as_ref(),
+            };
+            if !confirm(&plan) {
+                return Err(blocked("declined by the confirmation callback".to_string()));
+            }
+        }
+
+        let mut params = vec![
+            ("asset", asset),
+            ("key", request.key.as_str()),
+            ("amount", request.amount.as_str()),
+        ];
+        if let Some(address) = &request.address {
+            params.push(("address", address));
+        }
+        self.client.withdraw_funds(&params).await
+    }
+}
+
+impl std::fmt::Debug for SafeWithdrawer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SafeWithdrawer")
+            .field("max_amounts", &self.max_amounts)
+            .field("max_fees", &self.max_fees)
+            .field("check_information", &self.check_information)
+            .field("confirm", &self.confirm.is_some())
+            .field("address_book", &self.address_book.as_ref().map(AddressBook::len))
+            .finish()
+    }
+}
+
+fn blocked(reason: String) -> KrakenError {
+    KrakenError::WithdrawalBlocked(reason)
+}
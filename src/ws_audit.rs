@@ -0,0 +1,246 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::ws_models::WsUserTradingResponse;
+
+/// How many trading requests `KrakenWsClient::outbound_log` keeps by default.
+pub const OUTBOUND_LOG_CAPACITY: usize = 1024;
+
+/// The `event`s of the trading requests `OutboundLog` records.
+pub const TRADING_EVENTS: &[&str] = &[
+    "addOrder",
+    "amendOrder",
+    "editOrder",
+    "cancelOrder",
+    "cancelAll",
+    "cancelOnDisconnect",
+    "batchAdd",
+    "batchCancel",
+];
+
+/// The `event` of the request in `json`, if it's one of `TRADING_EVENTS`.
+pub(crate) fn trading_event(json: &str) -> Option<String> {
+    #[derive(Deserialize)]
+    struct Event {
+        event: String,
+    }
+    let Event { event } = serde_json::from_str(json).ok()?;
+    TRADING_EVENTS.contains(&event.as_str()).then_some(event)
+}
+
+/// One trading request a `KrakenWsClient` sent (or tried to), and the reply
+/// linked to it by `req_id`.
+#[derive(Debug, Clone)]
+pub struct OutboundRecord {
+    /// Position in the connection's trading requests, from 0
+    pub seq: u64,
+    /// When the request was handed to the socket, in milliseconds since the Unix epoch
+    pub sent_ms: u64,
+    /// The request's `event`, e.g. "cancelOrder"
+    pub event: String,
+    pub req_id: Option<u64>,
+    /// Every `clientOrderId` in the request (one per order for `batchAdd`)
+    pub cl_ord_ids: Vec<String>,
+    /// Every order ID the request targets (`txid`, or the `batchCancel` list)
+    pub txids: Vec<String>,
+    /// Why the request never reached the socket; `None` once it was written
+    pub send_error: Option<String>,
+    /// The server's answer; always `None` for requests without a `req_id`
+    pub reply: Option<OutboundReply>,
+}
+
+impl OutboundRecord {
+    /// Written to the socket, whether or not it was answered.
+    pub fn went_out(&self) -> bool {
+        self.send_error.is_none()
+    }
+}
+
+/// The reply to an `OutboundRecord`.
+#[derive(Debug, Clone)]
+pub struct OutboundReply {
+    /// When the reply was read, in milliseconds since the Unix epoch
+    pub received_ms: u64,
+    /// The server's error, if it rejected the request
+    pub error: Option<String>,
+    pub message: WsUserTradingResponse,
+}
+
+/// The fields of an outgoing request the log keeps.
+#[derive(Deserialize)]
+struct Outbound {
+    event: String,
+    req_id: Option<u64>,
+    #[serde(rename = "clientOrderId")]
+    client_order_id: Option<String>,
+    txid: Option<String>,
+    #[serde(default)]
+    orders: Vec<Value>,
+}
+
+/// A ring buffer of the trading requests sent on one connection, newest
+/// last, each linked to its reply as it arrives. When full, the oldest
+/// record makes room. Cheap to clone; clones share the buffer.
+#[derive(Debug, Clone)]
+pub struct OutboundLog {
+    inner: Arc<Mutex<Ring>>,
+}
+
+#[derive(Debug)]
+struct Ring {
+    records: VecDeque<OutboundRecord>,
+    capacity: usize,
+    next_seq: u64,
+}
+
+impl OutboundLog {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Ring {
+                records: VecDeque::new(),
+                capacity,
+                next_seq: 0,
+            })),
+        }
+    }
+
+    /// Keep at most `capacity` records from now on, dropping the oldest.
+    pub fn set_capacity(&self, capacity: usize) {
+        let mut ring = self.inner.lock().unwrap();
+        ring.capacity = capacity;
+        while ring.records.len() > capacity {
+            ring.records.pop_front();
+        }
+    }
+
+    /// Every record still held, oldest first.
+    pub fn records(&self) -> Vec<OutboundRecord> {
+        self.inner.lock().unwrap().records.iter().cloned().collect()
+    }
+
+    /// The latest request sent with `req_id`.
+    pub fn find_by_req_id(&self, req_id: u64) -> Option<OutboundRecord> {
+        self.find(|record| record.req_id == Some(req_id))
+    }
+
+    /// The latest request carrying `cl_ord_id`.
+    pub fn find_by_cl_ord_id(&self, cl_ord_id: &str) -> Option<OutboundRecord> {
+        self.find(|record| record.cl_ord_ids.iter().any(|id| id == cl_ord_id))
+    }
+
+    /// Every request targeting order `txid`, oldest first: did my cancel go out?
+    pub fn for_txid(&self, txid: &str) -> Vec<OutboundRecord> {
+        let ring = self.inner.lock().unwrap();
+        let targets = |record: &&OutboundRecord| record.txids.iter().any(|id| id == txid);
+        ring.records.iter().filter(targets).cloned().collect()
+    }
+
+    /// Requests written with a `req_id` and not answered yet, oldest first.
+    pub fn unanswered(&self) -> Vec<OutboundRecord> {
+        let ring = self.inner.lock().unwrap();
+        ring.records
+            .iter()
+            .filter(|record| record.went_out() && record.req_id.is_some() && record.reply.is_none())
+            .cloned()
+            .collect()
+    }
+
+    fn find(&self, matches: impl Fn(&OutboundRecord) -> bool) -> Option<OutboundRecord> {
+        let ring = self.inner.lock().unwrap();
+        ring.records
+            .iter()
+            .rev()
+            .find(|record| matches(record))
+            .cloned()
+    }
+
+    /// Record the outgoing message `json` if it is a trading request;
+    /// returns its `seq` for `send_failed`.
+    pub(crate) fn record(&self, json: &str) -> Option<u64> {
+        let outbound: Outbound = serde_json::from_str(json).ok()?;
+        if !TRADING_EVENTS.contains(&outbound.event.as_str()) {
+            return None;
+        }
+        let mut cl_ord_ids: Vec<String> = outbound.client_order_id.into_iter().collect();
+        let mut txids: Vec<String> = outbound.txid.into_iter().collect();
+        for order in &outbound.orders {
+            match order {
+                Value::String(txid) => txids.push(txid.clone()),
+                order => cl_ord_ids.extend(order["clientOrderId"].as_str().map(str::to_string)),
+            }
+        }
+
+        let mut ring = self.inner.lock().unwrap();
+        let seq = ring.next_seq;
+        ring.next_seq += 1;
+        if ring.capacity == 0 {
+            return None;
+        }
+        if ring.records.len() == ring.capacity {
+            ring.records.pop_front();
+        }
+        ring.records.push_back(OutboundRecord {
+            seq,
+            sent_ms: now_ms(),
+            event: outbound.event,
+            req_id: outbound.req_id,
+            cl_ord_ids,
+            txids,
+            send_error: None,
+            reply: None,
+        });
+        Some(seq)
+    }
+
+    /// Writing record `seq` to the socket failed with `error`.
+    pub(crate) fn send_failed(&self, seq: u64, error: String) {
+        self.update(
+            |record| record.seq == seq,
+            |record| record.send_error = Some(error),
+        );
+    }
+
+    /// Link `reply` to the latest unanswered request with its `req_id`.
+    pub(crate) fn reply(&self, reply: &WsUserTradingResponse) {
+        let Some(req_id) = reply.req_id() else {
+            return;
+        };
+        self.update(
+            |record| record.req_id == Some(req_id) && record.reply.is_none(),
+            |record| {
+                record.reply = Some(OutboundReply {
+                    received_ms: now_ms(),
+                    error: reply.error_message().map(str::to_string),
+                    message: reply.clone(),
+                })
+            },
+        );
+    }
+
+    fn update(
+        &self,
+        matches: impl Fn(&OutboundRecord) -> bool,
+        apply: impl FnOnce(&mut OutboundRecord),
+    ) {
+        let mut ring = self.inner.lock().unwrap();
+        if let Some(record) = ring.records.iter_mut().rev().find(|record| matches(record)) {
+            apply(record);
+        }
+    }
+}
+
+impl Default for OutboundLog {
+    fn default() -> Self {
+        Self::new(OUTBOUND_LOG_CAPACITY)
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
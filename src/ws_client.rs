@@ -1,32 +1,35 @@
+use futures_util::stream::{SplitSink, SplitStream};
 use futures_util::{SinkExt, StreamExt};
-use std::sync::Arc;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
 use tokio::net::TcpStream;
-use tokio::sync::Mutex;
-use tokio_tungstenite::{connect_async, tungstenite::protocol::Message, MaybeTlsStream};
+use tokio::sync::{broadcast, oneshot, Mutex};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::{client_async, tungstenite::protocol::Message, MaybeTlsStream};
 
+use crate::drop_copy::{ConsumerLag, DropCopyConsumer, DropCopyFeed};
+use crate::environment::{self, Environment};
 use crate::error::{KrakenError, KrakenResult};
+use crate::ws_audit::{self, OutboundLog};
+use crate::ws_deflate::{self, InflateStream};
+use crate::ws_models;
 use crate::ws_models::{
-    WsAddOrderRequest,
-    WsAdminResponse,
-    WsAmendOrderRequest,
-    WsAuthorizeRequest,
-    WsBatchAddRequest,
-    WsBatchCancelRequest,
-
-    WsCancelAllRequest,
-    WsCancelOnDisconnectRequest,
-    WsCancelOrderRequest,
-    WsEditOrderRequest,
-    WsHeartbeatRequest,
-    // Responses (server → client)
-    WsIncomingMessage,
-    // Requests (client → server)
-    WsPingRequest,
-    WsSubscribeRequest,
-    WsSubscriptionPayload,
-    WsUnsubscribeRequest,
-    WsUserTradingResponse,
+    WsAddOrderRequest, WsAmendOrderRequest, WsAuthorizeRequest, WsBalancesMessage,
+    WsBatchAddRequest, WsBatchCancelRequest, WsBookMessage, WsCancelAllRequest,
+    WsCancelOnDisconnectRequest, WsCancelOrderRequest, WsCandlesMessage, WsCorrelated,
+    WsEditOrderRequest, WsExecutionsMessage, WsHeartbeatRequest, WsIncomingMessage,
+    WsInstrumentsMessage, WsPingRequest, WsSubscribeRequest, WsSubscriptionPayload,
+    WsTickerMessage, WsTradesMessage, WsUnsubscribeRequest, WsUserTradingResponse,
 };
+use crate::ws_reconnect::{ConnectionEvent, ReconnectPolicy};
+
+pub use crate::feed::FeedStream;
+
+/// The socket under a `KrakenWsClient`.
+type WsStream = tokio_tungstenite::WebSocketStream<InflateStream<MaybeTlsStream<TcpStream>>>;
 
 /// `KrakenWsClient` manages a connection to the Spot WebSocket API v2.
 /// - It splits the WebSocket into read (stream) and write (sink) halves.
@@ -35,55 +38,833 @@ use crate::ws_models::{
 ///   user trading requests like `add_order`, etc.
 /// - It handles all tungstenite `Message` variants, including `Frame(_)`.
 /// - It maps inbound JSON into typed `WsIncomingMessage` from `models_ws.rs`.
+/// - Parsed messages are broadcast to every receiver from `messages()`; with no
+///   receivers they are only logged at `trace`.
 pub struct KrakenWsClient {
-    /// The write half (sink) wrapped in a Mutex for concurrency,
-    /// and in an Arc for shared ownership.
-    write_half: Arc<
-        Mutex<
-            futures_util::stream::SplitSink<
-                tokio_tungstenite::WebSocketStream<MaybeTlsStream<TcpStream>>,
-                Message,
-            >,
-        >,
-    >,
+    /// The write half and what it takes to reconnect, shared with the read task.
+    link: Arc<Link>,
+
+    /// Fan-out of parsed inbound messages to `messages()` receivers. The read
+    /// task owns the only strong sender, so receivers close with the
+    /// connection (with `with_reconnect`, once reconnecting gives up).
+    events: broadcast::WeakSender<WsIncomingMessage>,
+
+    /// Fan-out of the raw text frames, for `raw_messages()` receivers.
+    raw: broadcast::WeakSender<Arc<str>>,
+
+    /// Per-channel fan-out for the `subscribe_*` streams, owned by the read task.
+    feeds: Weak<ChannelFeeds>,
 
     /// If you need an auth token for user data / trading, store it here.
     pub token: Option<String>,
+
+    /// Source of `next_req_id`.
+    req_ids: AtomicU64,
+
+    budget: Option<SubscriptionBudget>,
+
+    /// Trading requests sent on this connection, linked to their replies.
+    outbound: OutboundLog,
+
+    /// Requests `request` is waiting on, answered by the read task.
+    pending: PendingReplies,
+
+    /// Refuse trading requests locally, like a read-only REST client.
+    read_only: bool,
+}
+
+/// The state of a connection the read task needs to bring it back. The
+/// task holds it weakly, so dropping the client stops reconnecting.
+struct Link {
+    url: String,
+
+    /// The write half (sink) in a Mutex for concurrency; replaced on reconnect.
+    write_half: Mutex<SplitSink<WsStream, Message>>,
+
+    /// Channel/symbol subscriptions sent and not unsubscribed, by
+    /// `subscription_key`; sent again on reconnect.
+    subscriptions: std::sync::Mutex<BTreeMap<String, WsSubscriptionPayload>>,
+
+    /// The token last sent with `authorize`, sent again on reconnect.
+    authorized: std::sync::Mutex<Option<String>>,
+
+    reconnect: std::sync::Mutex<Option<ReconnectPolicy>>,
+
+    connected: AtomicBool,
+
+    /// Set by `close`: a connection closed on purpose isn't brought back.
+    closing: AtomicBool,
+
+    connection_events: broadcast::Sender<ConnectionEvent>,
+}
+
+impl Link {
+    /// The policy to reconnect with, unless reconnecting is off or the
+    /// connection was closed on purpose.
+    fn reconnect_policy(&self) -> Option<ReconnectPolicy> {
+        if self.closing.load(Ordering::Relaxed) {
+            return None;
+        }
+        self.reconnect.lock().unwrap().clone()
+    }
+
+    fn emit(&self, event: ConnectionEvent) {
+        let _ = self.connection_events.send(event);
+    }
+
+    async fn send_text(&self, json_text: String) -> KrakenResult<()> {
+        let mut sink = self.write_half.lock().await;
+        sink.send(Message::Text(json_text))
+            .await
+            .map_err(|err| KrakenError::InvalidUsage(format!("WebSocket send error: {err}")))
+    }
+
+    async fn send_json<T: serde::Serialize>(&self, request: &T) -> KrakenResult<()> {
+        let json_text = serde_json::to_string(request)
+            .map_err(|err| KrakenError::InvalidUsage(format!("Serialize error: {err}")))?;
+        self.send_text(json_text).await
+    }
+
+    /// On a fresh connection, send the last `authorize` token and every
+    /// subscription again, without `req_id`s. Returns how many subscriptions.
+    async fn resume(&self) -> KrakenResult<usize> {
+        let token = self.authorized.lock().unwrap().clone();
+        let subscriptions: Vec<_> = {
+            let active = self.subscriptions.lock().unwrap();
+            active.values().cloned().collect()
+        };
+        if let Some(token) = token {
+            self.send_json(&WsAuthorizeRequest {
+                event: "authorize".to_string(),
+                token,
+                req_id: None,
+            })
+            .await?;
+        }
+        for subscription in &subscriptions {
+            self.send_json(&WsSubscribeRequest {
+                event: "subscribe".to_string(),
+                req_id: None,
+                subscription: subscription.clone(),
+            })
+            .await?;
+        }
+        Ok(subscriptions.len())
+    }
+}
+
+/// Typed updates fanned out per channel for the `subscribe_*` streams, so a
+/// slow `book` reader can't make a `ticker` reader lag. The read task holds
+/// the only strong reference, so the streams end with the connection.
+struct ChannelFeeds {
+    ticker: broadcast::Sender<WsTickerMessage>,
+    book: broadcast::Sender<WsBookMessage>,
+    trades: broadcast::Sender<WsTradesMessage>,
+    candles: broadcast::Sender<WsCandlesMessage>,
+    executions: broadcast::Sender<WsExecutionsMessage>,
+    balances: broadcast::Sender<WsBalancesMessage>,
+    drop_copy: DropCopyFeed,
+}
+
+impl ChannelFeeds {
+    fn new() -> Self {
+        Self {
+            ticker: broadcast::channel(MESSAGE_BUFFER).0,
+            book: broadcast::channel(MESSAGE_BUFFER).0,
+            trades: broadcast::channel(MESSAGE_BUFFER).0,
+            candles: broadcast::channel(MESSAGE_BUFFER).0,
+            executions: broadcast::channel(MESSAGE_BUFFER).0,
+            balances: broadcast::channel(MESSAGE_BUFFER).0,
+            drop_copy: DropCopyFeed::new(),
+        }
+    }
+
+    /// Hand `message` to its channel's receivers and, if private, to the
+    /// drop-copy consumers; `true` if there were any.
+    fn publish(&self, message: &WsIncomingMessage) -> bool {
+        fn send<T: Clone>(feed: &broadcast::Sender<T>, item: &T) -> bool {
+            feed.receiver_count() > 0 && feed.send(item.clone()).is_ok()
+        }
+        let mirrored = self.drop_copy.publish(message);
+        let sent = match message {
+            WsIncomingMessage::TickerMsg(ticker) => send(&self.ticker, ticker),
+            WsIncomingMessage::BookMsg(book) => send(&self.book, book),
+            WsIncomingMessage::TradesMsg(trades) => send(&self.trades, trades),
+            WsIncomingMessage::CandlesMsg(candles) => send(&self.candles, candles),
+            WsIncomingMessage::ExecutionsMsg(executions) => send(&self.executions, executions),
+            WsIncomingMessage::BalancesMsg(balances) => send(&self.balances, balances),
+            _ => false,
+        };
+        mirrored || sent
+    }
+}
+
+/// Requests awaiting their reply, by `req_id`. The read task hands each
+/// reply to its waiter as it arrives; when the connection drops the waiters
+/// are let go, since those replies never come. Clones share the map.
+#[derive(Clone, Default)]
+struct PendingReplies {
+    inner: Arc<std::sync::Mutex<Waiters>>,
+}
+
+#[derive(Default)]
+struct Waiters {
+    by_req_id: HashMap<u64, (u64, oneshot::Sender<WsIncomingMessage>)>,
+    next_ticket: u64,
+    /// Set once the read task is gone for good.
+    closed: bool,
+}
+
+/// One request's place in `PendingReplies`, given up when dropped (answered,
+/// timed out or cancelled).
+struct PendingReply {
+    pending: PendingReplies,
+    req_id: u64,
+    ticket: u64,
+    reply: oneshot::Receiver<WsIncomingMessage>,
+}
+
+impl PendingReplies {
+    /// Wait for the reply to `req_id`. Fails if another request is already
+    /// waiting on `req_id`, or the connection is closed.
+    fn wait_for(&self, req_id: u64, operation: &str) -> KrakenResult<PendingReply> {
+        let mut waiters = self.inner.lock().unwrap();
+        if waiters.closed {
+            return Err(KrakenError::InvalidUsage(format!(
+                "connection closed, {operation} (req_id {req_id}) can't be answered"
+            )));
+        }
+        let waiting = waiters.by_req_id.get(&req_id);
+        if waiting.is_some_and(|(_, tx)| !tx.is_closed()) {
+            return Err(KrakenError::InvalidUsage(format!(
+                "req_id {req_id} is already awaiting a reply"
+            )));
+        }
+        let (tx, reply) = oneshot::channel();
+        let ticket = waiters.next_ticket;
+        waiters.next_ticket += 1;
+        waiters.by_req_id.insert(req_id, (ticket, tx));
+        Ok(PendingReply {
+            pending: self.clone(),
+            req_id,
+            ticket,
+            reply,
+        })
+    }
+
+    /// Hand `message` to the request waiting on its `req_id`; `false` if none is.
+    fn answer(&self, message: &WsIncomingMessage) -> bool {
+        let Some(req_id) = message.req_id() else {
+            return false;
+        };
+        let waiter = self.inner.lock().unwrap().by_req_id.remove(&req_id);
+        waiter.is_some_and(|(_, tx)| tx.send(message.clone()).is_ok())
+    }
+
+    /// Let every waiter go; with `closed`, refuse new ones from now on.
+    fn release_all(&self, closed: bool) {
+        let mut waiters = self.inner.lock().unwrap();
+        waiters.by_req_id.clear();
+        waiters.closed |= closed;
+    }
+}
+
+impl Drop for PendingReply {
+    fn drop(&mut self) {
+        let mut waiters = self.pending.inner.lock().unwrap();
+        let current = waiters.by_req_id.get(&self.req_id);
+        if current.is_some_and(|(ticket, _)| *ticket == self.ticket) {
+            waiters.by_req_id.remove(&self.req_id);
+        }
+    }
+}
+
+/// What happens when a subscription would take a connection over its
+/// `SubscriptionBudget`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetPolicy {
+    /// Subscribe anyway and log a warning
+    Warn,
+    /// Refuse with `KrakenError::SubscriptionBudgetExceeded`
+    Reject,
+}
+
+/// The most channel/symbol subscriptions (a `book` on one symbol is one) a
+/// connection should carry, set with `KrakenWsClient::with_subscription_budget`.
+/// `ws_pool::WsPool` uses it to spread subscriptions over several connections.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubscriptionBudget {
+    pub max_subscriptions: usize,
+    pub policy: BudgetPolicy,
+}
+
+impl SubscriptionBudget {
+    pub fn warn(max_subscriptions: usize) -> Self {
+        Self {
+            max_subscriptions,
+            policy: BudgetPolicy::Warn,
+        }
+    }
+
+    pub fn reject(max_subscriptions: usize) -> Self {
+        Self {
+            max_subscriptions,
+            policy: BudgetPolicy::Reject,
+        }
+    }
 }
 
+/// Identifies a subscription for budgeting: its payload as JSON. `None` for
+/// payloads that aren't subscriptions (`ping`, `heartbeat`).
+pub(crate) fn subscription_key(subscription: &WsSubscriptionPayload) -> Option<String> {
+    match subscription {
+        WsSubscriptionPayload::Ping | WsSubscriptionPayload::Heartbeat => None,
+        other => serde_json::to_string(other).ok(),
+    }
+}
+
+/// First ID handed out by `next_req_id`, well clear of hand-picked `req_id`s.
+pub const FIRST_GENERATED_REQ_ID: u64 = 1_000_000_000;
+
+/// How many inbound messages a slow `messages()` receiver may lag behind
+/// before it starts missing them.
+pub const MESSAGE_BUFFER: usize = 1024;
+
 impl KrakenWsClient {
     /// Connect to the specified WebSocket `url` (e.g. "wss://ws.kraken.com/v2").
     /// Splits into read & write halves, spawns a read loop task, and returns `KrakenWsClient`.
+    ///
+    /// The handshake offers permessage-deflate; if the server accepts, inbound
+    /// messages (full order books especially) arrive compressed and are
+    /// inflated before parsing. Outbound messages are never compressed.
     pub async fn connect(url: &str) -> KrakenResult<Self> {
-        let (ws_stream, _response) = connect_async(url)
-            .await
-            .map_err(|err| KrakenError::InvalidUsage(format!("WebSocket connect error: {err}")))?;
+        let ws_stream = Self::open(url).await?;
 
         // Split into a write sink and read stream
         let (write_half, read_half) = ws_stream.split();
 
-        // Arc<Mutex<...>> so multiple calls can lock and send messages
-        let write_half = Arc::new(Mutex::new(write_half));
+        let (connection_events, _) = broadcast::channel(MESSAGE_BUFFER);
+        let link = Arc::new(Link {
+            url: url.to_string(),
+            // Mutex so multiple calls can lock and send messages
+            write_half: Mutex::new(write_half),
+            subscriptions: std::sync::Mutex::new(BTreeMap::new()),
+            authorized: std::sync::Mutex::new(None),
+            reconnect: std::sync::Mutex::new(None),
+            connected: AtomicBool::new(true),
+            closing: AtomicBool::new(false),
+            connection_events,
+        });
+
+        let (loop_events, _) = broadcast::channel(MESSAGE_BUFFER);
+        let events = loop_events.downgrade();
+        let (loop_raw, _) = broadcast::channel(MESSAGE_BUFFER);
+        let raw = loop_raw.downgrade();
+
+        let loop_feeds = Arc::new(ChannelFeeds::new());
+        let feeds = Arc::downgrade(&loop_feeds);
+
+        let outbound = OutboundLog::default();
+        let loop_outbound = outbound.clone();
+        let pending = PendingReplies::default();
+        let loop_pending = pending.clone();
 
         // Spawn the read loop in the background
-        tokio::spawn(async move {
-            if let Err(e) = Self::read_loop(read_half).await {
-                eprintln!("Read loop ended with error: {e}");
-            }
-        });
+        tokio::spawn(Self::supervise(
+            read_half,
+            loop_events,
+            loop_raw,
+            loop_feeds,
+            loop_outbound,
+            loop_pending,
+            Arc::downgrade(&link),
+        ));
 
         Ok(Self {
-            write_half,
+            link,
+            events,
+            raw,
+            feeds,
             token: None,
+            req_ids: AtomicU64::new(FIRST_GENERATED_REQ_ID),
+            budget: None,
+            outbound,
+            pending,
+            read_only: false,
+        })
+    }
+
+    /// The WebSocket handshake with `url`, offering permessage-deflate.
+    async fn open(url: &str) -> KrakenResult<WsStream> {
+        environment::check_sandbox(url)?;
+        let connect_error =
+            |err| KrakenError::InvalidUsage(format!("WebSocket connect error: {err}"));
+        let mut request = url.into_client_request().map_err(connect_error)?;
+        request.headers_mut().insert(
+            "Sec-WebSocket-Extensions",
+            HeaderValue::from_static(ws_deflate::OFFER),
+        );
+        let stream = ws_deflate::connect(request.uri()).await?;
+        let (ws_stream, _response) = client_async(request, InflateStream::new(stream))
+            .await
+            .map_err(connect_error)?;
+        Ok(ws_stream)
+    }
+
+    /// When the connection drops (read error or close from the server),
+    /// connect again to the same URL, waiting per `policy` between attempts,
+    /// then send the last `authorize` token and every active subscription
+    /// again. `messages()` and the other streams carry on across the gap;
+    /// replies to requests sent before the drop never arrive. Each step is
+    /// reported on `connection_events`. Closing the client with `close` or
+    /// dropping it stops reconnecting.
+    pub fn with_reconnect(self, policy: ReconnectPolicy) -> Self {
+        *self.link.reconnect.lock().unwrap() = Some(policy);
+        self
+    }
+
+    /// Every `ConnectionEvent` from now on: drops, reconnect attempts and
+    /// their outcome.
+    pub fn connection_events(&self) -> broadcast::Receiver<ConnectionEvent> {
+        self.link.connection_events.subscribe()
+    }
+
+    /// Keep the last `capacity` trading requests in `outbound_log` (default
+    /// `ws_audit::OUTBOUND_LOG_CAPACITY`); 0 turns the log off.
+    pub fn with_outbound_log_capacity(self, capacity: usize) -> Self {
+        self.outbound.set_capacity(capacity);
+        self
+    }
+
+    /// Every trading request (add, amend, edit, cancel and batch) sent on
+    /// this connection, with its `req_id`, client order IDs and send time,
+    /// linked to the reply carrying the same `req_id` once it arrives.
+    pub fn outbound_log(&self) -> &OutboundLog {
+        &self.outbound
+    }
+
+    /// Refuse every trading request (`ws_audit::TRADING_EVENTS`: add, amend,
+    /// edit, cancel and batch) locally with `KrakenError::ReadOnly`, as
+    /// `KrakenClient::with_read_only` does for `MUTATING_ENDPOINTS`, so a
+    /// monitoring deployment can't trade over the socket either. Market and
+    /// private data subscriptions are unaffected.
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// `true` if trading requests are refused.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Check subscriptions against `budget` from now on.
+    pub fn with_subscription_budget(mut self, budget: SubscriptionBudget) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// How many channel/symbol subscriptions this connection has sent and not
+    /// unsubscribed. Subscriptions Kraken rejected still count when sent with
+    /// `subscribe`; `subscribe_and_wait` only counts accepted ones.
+    pub fn subscription_count(&self) -> usize {
+        self.link.subscriptions.lock().unwrap().len()
+    }
+
+    /// `true` if `subscription` is among those counted by `subscription_count`.
+    pub fn is_subscribed(&self, subscription: &WsSubscriptionPayload) -> bool {
+        subscription_key(subscription)
+            .is_some_and(|key| self.link.subscriptions.lock().unwrap().contains_key(&key))
+    }
+
+    /// Count `subscription` against the budget, failing if the budget rejects it.
+    /// Returns the key to release if sending it fails.
+    fn reserve(&self, subscription: &WsSubscriptionPayload) -> KrakenResult<Option<String>> {
+        let Some(key) = subscription_key(subscription) else {
+            return Ok(None);
+        };
+        let mut subscriptions = self.link.subscriptions.lock().unwrap();
+        if subscriptions.contains_key(&key) {
+            return Ok(None);
+        }
+        if let Some(budget) = self.budget {
+            if subscriptions.len() >= budget.max_subscriptions {
+                match budget.policy {
+                    BudgetPolicy::Warn => tracing::warn!(
+                        subscription = %key,
+                        max = budget.max_subscriptions,
+                        "subscription budget exceeded"
+                    ),
+                    BudgetPolicy::Reject => {
+                        return Err(KrakenError::SubscriptionBudgetExceeded {
+                            subscription: key,
+                            max: budget.max_subscriptions,
+                        })
+                    }
+                }
+            }
+        }
+        subscriptions.insert(key.clone(), subscription.clone());
+        Ok(Some(key))
+    }
+
+    fn release(&self, key: Option<String>) {
+        if let Some(key) = key {
+            self.link.subscriptions.lock().unwrap().remove(&key);
+        }
+    }
+
+    /// `true` until the read loop sees the socket close or fail, and again
+    /// once `with_reconnect` has brought it back.
+    pub fn is_connected(&self) -> bool {
+        self.link.connected.load(Ordering::Relaxed)
+    }
+
+    /// A fresh `req_id`, unique on this connection. Used by the `ExchangeClient`
+    /// implementation; handy for your own correlated requests too.
+    pub fn next_req_id(&self) -> u64 {
+        self.req_ids.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Start the closing handshake: send a close frame and shut the write
+    /// half. The read loop ends once the server answers, closing every
+    /// stream on this connection; it isn't reconnected.
+    pub async fn close(&self) -> KrakenResult<()> {
+        self.link.closing.store(true, Ordering::Relaxed);
+        let mut sink = self.link.write_half.lock().await;
+        sink.close()
+            .await
+            .map_err(|err| KrakenError::InvalidUsage(format!("WebSocket close error: {err}")))
+    }
+
+    /// Subscribe to every parsed inbound message from now on.
+    ///
+    /// A receiver that falls more than `MESSAGE_BUFFER` messages behind gets
+    /// `RecvError::Lagged` and skips ahead; the stream ends when the socket
+    /// closes (with `with_reconnect`, when reconnecting stops).
+    pub fn messages(&self) -> broadcast::Receiver<WsIncomingMessage> {
+        match self.events.upgrade() {
+            Some(events) => events.subscribe(),
+            // Already disconnected: hand out a receiver that is closed from the start.
+            None => broadcast::channel(1).1,
+        }
+    }
+
+    /// Every inbound text frame from now on, exactly as received, e.g. for
+    /// `replay::Recorder`. Lags and closes like `messages()`.
+    pub fn raw_messages(&self) -> broadcast::Receiver<Arc<str>> {
+        match self.raw.upgrade() {
+            Some(raw) => raw.subscribe(),
+            None => broadcast::channel(1).1,
+        }
+    }
+
+    /// Every parsed inbound message as a `Stream`. See `FeedStream` for lag handling.
+    pub fn message_stream(&self) -> FeedStream<WsIncomingMessage> {
+        FeedStream::new(self.messages(), Some)
+    }
+
+    /// `ticker` channel updates.
+    pub fn ticker_stream(&self) -> FeedStream<WsTickerMessage> {
+        FeedStream::new(self.messages(), |msg| match msg {
+            WsIncomingMessage::TickerMsg(ticker) => Some(ticker),
+            _ => None,
+        })
+    }
+
+    /// `book` channel snapshots and updates.
+    pub fn book_stream(&self) -> FeedStream<WsBookMessage> {
+        FeedStream::new(self.messages(), |msg| match msg {
+            WsIncomingMessage::BookMsg(book) => Some(book),
+            _ => None,
+        })
+    }
+
+    /// `ohlc` channel candles.
+    pub fn candles_stream(&self) -> FeedStream<WsCandlesMessage> {
+        FeedStream::new(self.messages(), |msg| match msg {
+            WsIncomingMessage::CandlesMsg(candles) => Some(candles),
+            _ => None,
+        })
+    }
+
+    /// `trade` channel updates.
+    pub fn trades_stream(&self) -> FeedStream<WsTradesMessage> {
+        FeedStream::new(self.messages(), |msg| match msg {
+            WsIncomingMessage::TradesMsg(trades) => Some(trades),
+            _ => None,
+        })
+    }
+
+    /// `instrument` channel updates.
+    pub fn instruments_stream(&self) -> FeedStream<WsInstrumentsMessage> {
+        FeedStream::new(self.messages(), |msg| match msg {
+            WsIncomingMessage::InstrumentsMsg(instruments) => Some(instruments),
+            _ => None,
         })
     }
 
+    /// `balances` channel updates (authenticated connection).
+    pub fn balances_stream(&self) -> FeedStream<WsBalancesMessage> {
+        FeedStream::new(self.messages(), |msg| match msg {
+            WsIncomingMessage::BalancesMsg(balances) => Some(balances),
+            _ => None,
+        })
+    }
+
+    /// `executions` channel updates (authenticated connection).
+    pub fn executions_stream(&self) -> FeedStream<WsExecutionsMessage> {
+        FeedStream::new(self.messages(), |msg| match msg {
+            WsIncomingMessage::ExecutionsMsg(executions) => Some(executions),
+            _ => None,
+        })
+    }
+
+    /// Subscribe to `symbol`'s ticker and stream its updates. Like every
+    /// `subscribe_*` stream, it reads its own channel's queue, so it only lags
+    /// (skipping the oldest updates) when it falls `MESSAGE_BUFFER` updates
+    /// of its own channel behind, and ends when the connection closes.
+    pub async fn subscribe_ticker(
+        &self,
+        symbol: &str,
+    ) -> KrakenResult<FeedStream<WsTickerMessage>> {
+        let subscription = WsSubscriptionPayload::Ticker {
+            symbol: symbol.to_string(),
+        };
+        let symbol = symbol.to_string();
+        self.subscribe_feed(
+            subscription,
+            |feeds| &feeds.ticker,
+            move |msg| msg.symbol == symbol,
+        )
+        .await
+    }
+
+    /// Subscribe to `symbol`'s book at `depth` and stream its snapshot and updates.
+    pub async fn subscribe_book(
+        &self,
+        symbol: &str,
+        depth: u32,
+    ) -> KrakenResult<FeedStream<WsBookMessage>> {
+        let subscription = WsSubscriptionPayload::Book {
+            symbol: symbol.to_string(),
+            depth,
+        };
+        let symbol = symbol.to_string();
+        self.subscribe_feed(
+            subscription,
+            |feeds| &feeds.book,
+            move |msg| msg.symbol == symbol,
+        )
+        .await
+    }
+
+    /// Subscribe to `symbol`'s trades and stream them.
+    pub async fn subscribe_trades(
+        &self,
+        symbol: &str,
+    ) -> KrakenResult<FeedStream<WsTradesMessage>> {
+        let subscription = WsSubscriptionPayload::Trades {
+            symbol: symbol.to_string(),
+        };
+        let symbol = symbol.to_string();
+        self.subscribe_feed(
+            subscription,
+            |feeds| &feeds.trades,
+            move |msg| msg.symbol == symbol,
+        )
+        .await
+    }
+
+    /// Subscribe to `symbol`'s candles of `interval` minutes and stream them.
+    pub async fn subscribe_candles(
+        &self,
+        symbol: &str,
+        interval: u32,
+    ) -> KrakenResult<FeedStream<WsCandlesMessage>> {
+        let subscription = WsSubscriptionPayload::Candles {
+            symbol: symbol.to_string(),
+            interval,
+        };
+        let symbol = symbol.to_string();
+        self.subscribe_feed(
+            subscription,
+            |feeds| &feeds.candles,
+            move |msg| msg.symbol == symbol && msg.interval == interval,
+        )
+        .await
+    }
+
+    /// Subscribe to the account's executions (authenticated connection) and stream them.
+    pub async fn subscribe_executions(&self) -> KrakenResult<FeedStream<WsExecutionsMessage>> {
+        self.subscribe_feed(
+            WsSubscriptionPayload::Executions,
+            |feeds| &feeds.executions,
+            |_| true,
+        )
+        .await
+    }
+
+    /// Subscribe to the account's balances (authenticated connection) and stream them.
+    pub async fn subscribe_balances(&self) -> KrakenResult<FeedStream<WsBalancesMessage>> {
+        self.subscribe_feed(
+            WsSubscriptionPayload::Balances,
+            |feeds| &feeds.balances,
+            |_| true,
+        )
+        .await
+    }
+
+    /// A new drop-copy consumer named `name`: every `executions` and
+    /// `balances` update this connection receives from now on, on its own
+    /// queue so several readers (a strategy, a risk monitor) don't interfere.
+    /// It subscribes to nothing; subscribe to the private channels once with
+    /// `subscribe` or `subscribe_executions`. Ends at once if the connection
+    /// is gone.
+    pub fn drop_copy(&self, name: &str) -> DropCopyConsumer {
+        match self.feeds.upgrade() {
+            Some(feeds) => feeds.drop_copy.consumer(name),
+            None => DropCopyFeed::new().consumer(name),
+        }
+    }
+
+    /// How every live drop-copy consumer is keeping up, oldest first.
+    pub fn drop_copy_lag(&self) -> Vec<ConsumerLag> {
+        self.feeds
+            .upgrade()
+            .map(|feeds| feeds.drop_copy.lags())
+            .unwrap_or_default()
+    }
+
+    /// Open a receiver on `feed` (before subscribing, so the first update
+    /// can't slip past), send `subscription`, and stream what `keep` accepts.
+    async fn subscribe_feed<T: Clone + Send + 'static>(
+        &self,
+        subscription: WsSubscriptionPayload,
+        feed: fn(&ChannelFeeds) -> &broadcast::Sender<T>,
+        keep: impl Fn(&T) -> bool + Send + Sync + 'static,
+    ) -> KrakenResult<FeedStream<T>> {
+        let receiver = match self.feeds.upgrade() {
+            Some(feeds) => feed(&feeds).subscribe(),
+            // Already disconnected: hand out a receiver that is closed from the start.
+            None => broadcast::channel(1).1,
+        };
+        self.subscribe(subscription, None).await?;
+        Ok(FeedStream::from_receiver(receiver, keep))
+    }
+
+    /// Connect to the public (market data) WebSocket of `environment`.
+    pub async fn connect_public(environment: &Environment) -> KrakenResult<Self> {
+        Self::connect(environment.ws_public_url()).await
+    }
+
+    /// Connect to the authenticated (user data / trading) WebSocket of `environment`.
+    pub async fn connect_authenticated(environment: &Environment) -> KrakenResult<Self> {
+        Self::connect(environment.ws_auth_url()).await
+    }
+
+    /// The read task: `read_loop` on the first connection and, with
+    /// `with_reconnect`, on every one replacing it, for as long as the client
+    /// behind `link` is alive and not closed.
+    async fn supervise(
+        mut read_half: SplitStream<WsStream>,
+        events: broadcast::Sender<WsIncomingMessage>,
+        raw: broadcast::Sender<Arc<str>>,
+        feeds: Arc<ChannelFeeds>,
+        outbound: OutboundLog,
+        pending: PendingReplies,
+        link: Weak<Link>,
+    ) {
+        loop {
+            let error = Self::read_loop(read_half, &events, &raw, &feeds, &outbound, &pending)
+                .await
+                .err()
+                .map(|e| e.to_string());
+            pending.release_all(false);
+            let Some((live, policy)) = link.upgrade().and_then(|live| {
+                live.connected.store(false, Ordering::Relaxed);
+                let policy = live.reconnect_policy()?;
+                Some((live, policy))
+            }) else {
+                if let Some(e) = error {
+                    tracing::debug!(error = %e, "WebSocket read loop ended");
+                }
+                pending.release_all(true);
+                return;
+            };
+            tracing::warn!(error = ?error, "WebSocket disconnected, reconnecting");
+            live.emit(ConnectionEvent::Disconnected { error });
+            drop(live);
+            match Self::reconnect(&link, &policy).await {
+                Some(next) => read_half = next,
+                None => {
+                    pending.release_all(true);
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Connect `link` again, waiting per `policy` before each attempt, and
+    /// resume its authorization and subscriptions. `None` once the attempts
+    /// run out, or if the client is closed or dropped meanwhile.
+    async fn reconnect(
+        link: &Weak<Link>,
+        policy: &ReconnectPolicy,
+    ) -> Option<SplitStream<WsStream>> {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let Some(delay) = policy.wait_before(attempt) else {
+                tracing::error!(attempts = attempt - 1, "WebSocket reconnect gave up");
+                link.upgrade()?.emit(ConnectionEvent::GaveUp {
+                    attempts: attempt - 1,
+                });
+                return None;
+            };
+            link.upgrade()?
+                .emit(ConnectionEvent::Reconnecting { attempt, delay });
+            tokio::time::sleep(delay).await;
+
+            let url = link.upgrade()?.url.clone();
+            let error = match Self::open(&url).await {
+                Ok(ws_stream) => {
+                    let live = link.upgrade()?;
+                    if live.closing.load(Ordering::Relaxed) {
+                        return None;
+                    }
+                    let (write_half, read_half) = ws_stream.split();
+                    *live.write_half.lock().await = write_half;
+                    match live.resume().await {
+                        Ok(resubscribed) => {
+                            live.connected.store(true, Ordering::Relaxed);
+                            tracing::info!(attempt, resubscribed, "WebSocket reconnected");
+                            live.emit(ConnectionEvent::Reconnected {
+                                attempt,
+                                resubscribed,
+                            });
+                            return Some(read_half);
+                        }
+                        Err(e) => e.to_string(),
+                    }
+                }
+                Err(e) => e.to_string(),
+            };
+            tracing::warn!(attempt, error = %error, "WebSocket reconnect failed");
+            link.upgrade()?
+                .emit(ConnectionEvent::ReconnectFailed { attempt, error });
+        }
+    }
+
     /// The continuous read loop. Reads messages, matches their type, and parses
-    /// them into `WsIncomingMessage` if they are textual JSON.
+    /// them into `WsIncomingMessage` if they are JSON, in text or binary frames.
     async fn read_loop(
-        mut read_half: futures_util::stream::SplitStream<
-            tokio_tungstenite::WebSocketStream<MaybeTlsStream<TcpStream>>,
-        >,
+        mut read_half: SplitStream<WsStream>,
+        events: &broadcast::Sender<WsIncomingMessage>,
+        raw: &broadcast::Sender<Arc<str>>,
+        feeds: &ChannelFeeds,
+        outbound: &OutboundLog,
+        pending: &PendingReplies,
     ) -> KrakenResult<()> {
         while let Some(msg_result) = read_half.next().await {
             let msg = msg_result
@@ -91,220 +872,83 @@ impl KrakenWsClient {
 
             match msg {
                 Message::Text(text) => {
-                    // Attempt to parse the text as WsIncomingMessage
-                    match serde_json::from_str::<WsIncomingMessage>(&text) {
-                        Ok(incoming) => {
-                            Self::handle_incoming(incoming).await;
-                        }
-                        Err(e) => {
-                            eprintln!("Failed to parse text: {e}\nRaw text: {text}");
-                        }
-                    }
-                }
-                Message::Binary(bin) => {
-                    eprintln!("Received binary message: {bin:?}");
+                    Self::dispatch(&text, events, raw, feeds, outbound, pending).await
                 }
+                // JSON sent as a binary frame is handled like text
+                Message::Binary(bin) => match std::str::from_utf8(&bin) {
+                    Ok(text) => Self::dispatch(text, events, raw, feeds, outbound, pending).await,
+                    Err(_) => tracing::trace!(len = bin.len(), "Ignoring non-UTF-8 binary frame"),
+                },
                 Message::Ping(payload) => {
-                    eprintln!("Received ping: {payload:?}");
+                    tracing::trace!(?payload, "Received ping");
                 }
                 Message::Pong(payload) => {
-                    eprintln!("Received pong: {payload:?}");
+                    tracing::trace!(?payload, "Received pong");
                 }
                 Message::Close(close_frame) => {
-                    eprintln!("WebSocket closed: {close_frame:?}");
+                    tracing::debug!(?close_frame, "WebSocket closed");
                     break;
                 }
                 Message::Frame(frame) => {
-                    eprintln!("Received raw frame: {frame:?}");
+                    tracing::trace!(?frame, "Received raw frame");
                 }
             }
         }
         Ok(())
     }
 
-    /// Handle a typed incoming message variant.
-    async fn handle_incoming(msg: WsIncomingMessage) {
-        match msg {
-            WsIncomingMessage::Admin(admin_resp) => match admin_resp {
-                WsAdminResponse::SystemStatus { status, version } => {
-                    eprintln!("SystemStatus => status={status}, version={version}");
-                }
-                WsAdminResponse::SubscriptionStatus {
-                    channel,
-                    status,
-                    req_id,
-                    error_message,
-                } => {
-                    eprintln!(
-                            "SubscriptionStatus => channel={channel}, status={status}, req_id={req_id:?}, error={error_message:?}"
-                        );
-                }
-                WsAdminResponse::PingStatus { req_id } => {
-                    eprintln!("PingStatus => req_id={req_id:?}");
-                }
-                WsAdminResponse::Heartbeat {} => {
-                    eprintln!("Heartbeat => received");
+    /// Publish one inbound text message: raw to `raw_messages()` receivers,
+    /// parsed to the request awaiting it, `messages()` and `subscribe_*`
+    /// receivers (logged at `trace` when none takes it). Trading replies are
+    /// linked in the outbound log first.
+    async fn dispatch(
+        text: &str,
+        events: &broadcast::Sender<WsIncomingMessage>,
+        raw: &broadcast::Sender<Arc<str>>,
+        feeds: &ChannelFeeds,
+        outbound: &OutboundLog,
+        pending: &PendingReplies,
+    ) {
+        if raw.receiver_count() > 0 {
+            let _ = raw.send(text.into());
+        }
+        // Attempt to parse the text as WsIncomingMessage
+        match ws_models::parse_incoming(text) {
+            Ok(incoming) => {
+                if let WsIncomingMessage::Trading(reply) = &incoming {
+                    outbound.reply(reply);
                 }
-                WsAdminResponse::Unknown => {
-                    eprintln!("Unknown Admin event => unrecognized fields");
+                let published = pending.answer(&incoming) | feeds.publish(&incoming);
+                if events.receiver_count() > 0 {
+                    let _ = events.send(incoming);
+                } else if !published {
+                    tracing::trace!(message = ?incoming, "No receiver for WebSocket message");
                 }
-            },
-
-            // Market Data
-            WsIncomingMessage::TickerMsg(ticker) => {
-                eprintln!(
-                    "Ticker => symbol={}, bestBid={}, bestAsk={}",
-                    ticker.symbol, ticker.best_bid_price, ticker.best_ask_price
-                );
-            }
-            WsIncomingMessage::BookMsg(book) => {
-                eprintln!(
-                    "Book => symbol={}, #bids={}, #asks={}",
-                    book.symbol,
-                    book.bids.len(),
-                    book.asks.len()
-                );
-            }
-            WsIncomingMessage::CandlesMsg(candles) => {
-                eprintln!(
-                    "Candles => symbol={}, interval={}, #data={}",
-                    candles.symbol,
-                    candles.interval,
-                    candles.data.len()
-                );
-            }
-            WsIncomingMessage::TradesMsg(trades) => {
-                eprintln!(
-                    "Trades => symbol={}, #trades={}",
-                    trades.symbol,
-                    trades.trades.len()
-                );
-            }
-            WsIncomingMessage::InstrumentsMsg(instr) => {
-                eprintln!("Instruments => #instruments={}", instr.data.len());
             }
-
-            // User Data
-            WsIncomingMessage::BalancesMsg(balances_msg) => {
-                eprintln!(
-                    "Balances => channel={}, #assets={}",
-                    balances_msg.channel,
-                    balances_msg.balances.len()
-                );
-            }
-            WsIncomingMessage::ExecutionsMsg(exec_msg) => {
-                eprintln!(
-                    "Executions => channel={}, #executions={}",
-                    exec_msg.channel,
-                    exec_msg.executions.len()
-                );
-            }
-
-            // User Trading
-            WsIncomingMessage::Trading(trade_resp) => match trade_resp {
-                WsUserTradingResponse::AddOrderStatus {
-                    status,
-                    txid,
-                    req_id,
-                    error_message,
-                } => {
-                    eprintln!(
-                            "AddOrderStatus => status={status}, txid={txid:?}, req_id={req_id:?}, error={error_message:?}"
-                        );
-                }
-                WsUserTradingResponse::AmendOrderStatus {
-                    status,
-                    txid,
-                    req_id,
-                    error_message,
-                } => {
-                    eprintln!(
-                            "AmendOrderStatus => status={status}, txid={txid:?}, req_id={req_id:?}, error={error_message:?}"
-                        );
-                }
-                WsUserTradingResponse::EditOrderStatus {
-                    status,
-                    txid,
-                    req_id,
-                    error_message,
-                } => {
-                    eprintln!(
-                            "EditOrderStatus => status={status}, txid={txid:?}, req_id={req_id:?}, error={error_message:?}"
-                        );
-                }
-                WsUserTradingResponse::CancelOrderStatus {
-                    status,
-                    txid,
-                    req_id,
-                    error_message,
-                } => {
-                    eprintln!(
-                            "CancelOrderStatus => status={status}, txid={txid:?}, req_id={req_id:?}, error={error_message:?}"
-                        );
-                }
-                WsUserTradingResponse::CancelAllStatus {
-                    status,
-                    count,
-                    req_id,
-                    error_message,
-                } => {
-                    eprintln!(
-                            "CancelAllStatus => status={status}, count={count:?}, req_id={req_id:?}, error={error_message:?}"
-                        );
-                }
-                WsUserTradingResponse::CancelOnDisconnectStatus {
-                    status,
-                    req_id,
-                    error_message,
-                } => {
-                    eprintln!(
-                            "CancelOnDisconnectStatus => status={status}, req_id={req_id:?}, error={error_message:?}"
-                        );
-                }
-                WsUserTradingResponse::BatchAddStatus {
-                    status,
-                    results,
-                    req_id,
-                    error_message,
-                } => {
-                    eprintln!(
-                            "BatchAddStatus => status={status}, req_id={req_id:?}, err={error_message:?}, results={:?}",
-                            results
-                        );
-                }
-                WsUserTradingResponse::BatchCancelStatus {
-                    status,
-                    results,
-                    req_id,
-                    error_message,
-                } => {
-                    eprintln!(
-                            "BatchCancelStatus => status={status}, req_id={req_id:?}, err={error_message:?}, results={:?}",
-                            results
-                        );
-                }
-                WsUserTradingResponse::Unknown => {
-                    eprintln!("Unknown user trading response => unrecognized fields");
-                }
-            },
-
-            // CatchAll for untagged or unknown messages
-            WsIncomingMessage::CatchAll(unparsed) => {
-                eprintln!("CatchAll => unparsed: {unparsed}");
+            Err(e) => {
+                tracing::warn!(error = %e, len = text.len(), "Failed to parse WebSocket message");
+                tracing::debug!(text, "Unparsed WebSocket message");
             }
         }
     }
 
     /// Helper to send a request object T as JSON text over the WebSocket.
+    /// Trading requests are recorded in the outbound log, or refused on a
+    /// read-only client.
     async fn send_message<T: serde::Serialize>(&self, request: &T) -> KrakenResult<()> {
         let json_text = serde_json::to_string(request)
             .map_err(|err| KrakenError::InvalidUsage(format!("Serialize error: {err}")))?;
-        let mut sink = self.write_half.lock().await;
-        // Use .into() so it matches the expected tungstenite text type
-        sink.send(Message::Text(json_text.into()))
-            .await
-            .map_err(|err| KrakenError::InvalidUsage(format!("WebSocket send error: {err}")))?;
-        Ok(())
+        if self.read_only {
+            if let Some(event) = ws_audit::trading_event(&json_text) {
+                return Err(KrakenError::ReadOnly { path: event });
+            }
+        }
+        let seq = self.outbound.record(&json_text);
+        let sent = self.link.send_text(json_text).await;
+        if let (Some(seq), Err(err)) = (seq, &sent) {
+            self.outbound.send_failed(seq, err.to_string());
+        }
+        sent
     }
 
     // ─────────────────────────────────────────────────────────────────────
@@ -329,28 +973,37 @@ impl KrakenWsClient {
         self.send_message(&hb_req).await
     }
 
-    /// Authorize with a token (WsAuthorizeRequest)
+    /// Authorize with a token (WsAuthorizeRequest). The token is kept and
+    /// sent again after a reconnect.
     pub async fn authorize(&self, token: &str, req_id: Option<u64>) -> KrakenResult<()> {
         let auth_req = WsAuthorizeRequest {
             event: "authorize".to_string(),
             token: token.to_string(),
             req_id,
         };
-        self.send_message(&auth_req).await
+        self.send_message(&auth_req).await?;
+        *self.link.authorized.lock().unwrap() = Some(token.to_string());
+        Ok(())
     }
 
-    /// Subscribe to a channel (WsSubscribeRequest)
+    /// Subscribe to a channel (WsSubscribeRequest), within the subscription
+    /// budget if one is set.
     pub async fn subscribe(
         &self,
         subscription: WsSubscriptionPayload,
         req_id: Option<u64>,
     ) -> KrakenResult<()> {
+        let reserved = self.reserve(&subscription)?;
         let req = WsSubscribeRequest {
             event: "subscribe".to_string(),
             req_id,
             subscription,
         };
-        self.send_message(&req).await
+        let sent = self.send_message(&req).await;
+        if sent.is_err() {
+            self.release(reserved);
+        }
+        sent
     }
 
     /// Unsubscribe from a channel (WsUnsubscribeRequest)
@@ -359,12 +1012,15 @@ impl KrakenWsClient {
         subscription: WsSubscriptionPayload,
         req_id: Option<u64>,
     ) -> KrakenResult<()> {
+        let key = subscription_key(&subscription);
         let req = WsUnsubscribeRequest {
             event: "unsubscribe".to_string(),
             req_id,
             subscription,
         };
-        self.send_message(&req).await
+        self.send_message(&req).await?;
+        self.release(key);
+        Ok(())
     }
 
     /// Add order (WsAddOrderRequest)
@@ -406,4 +1062,144 @@ impl KrakenWsClient {
     pub async fn batch_cancel(&self, req: WsBatchCancelRequest) -> KrakenResult<()> {
         self.send_message(&req).await
     }
+
+    // ─────────────────────────────────────────────────────────────────────
+    // CORRELATED REQUESTS (wait for the reply, with an optional deadline)
+    // ─────────────────────────────────────────────────────────────────────
+
+    /// Send `request` and wait for the reply carrying its `req_id`.
+    ///
+    /// - `deadline`: give up with `KrakenError::Timeout` if sending and the reply
+    ///   together take longer; `None` waits until the connection closes.
+    ///
+    /// The reply is returned as-is, even if it reports an error; the `*_and_wait`
+    /// helpers and `order_request` turn those into `Err`. It still goes out on
+    /// `messages()` as well. Fails with `InvalidUsage` if `request` has no
+    /// `req_id`, another request is waiting on the same `req_id`, or the
+    /// connection drops first (even if it reconnects).
+    pub async fn request<R: WsCorrelated>(
+        &self,
+        request: &R,
+        deadline: Option<Duration>,
+    ) -> KrakenResult<WsIncomingMessage> {
+        let operation = request.operation();
+        let req_id = request.req_id().ok_or_else(|| {
+            KrakenError::InvalidUsage(format!("{operation} needs a req_id to await its reply"))
+        })?;
+        // Wait before sending so a fast reply can't slip past.
+        let mut pending = self.pending.wait_for(req_id, operation)?;
+        let exchange = async {
+            self.send_message(request).await?;
+            (&mut pending.reply).await.map_err(|_| {
+                KrakenError::InvalidUsage(format!(
+                    "connection dropped before {operation} (req_id {req_id}) was answered"
+                ))
+            })
+        };
+        match deadline {
+            None => exchange.await,
+            Some(after) => tokio::time::timeout(after, exchange)
+                .await
+                .unwrap_or_else(|_| {
+                    Err(KrakenError::Timeout {
+                        operation: format!("{operation} (req_id {req_id})"),
+                        after,
+                    })
+                }),
+        }
+    }
+
+    /// `request`, turning a reply that reports an error into `Err`.
+    async fn request_ok<R: WsCorrelated>(
+        &self,
+        request: &R,
+        deadline: Option<Duration>,
+    ) -> KrakenResult<WsIncomingMessage> {
+        let reply = self.request(request, deadline).await?;
+        match reply.error_message() {
+            Some(message) => Err(KrakenError::from_kraken_errors(vec![message.to_string()])),
+            None => Ok(reply),
+        }
+    }
+
+    /// `send_ping`, then wait for the matching `pingStatus`.
+    pub async fn ping_and_wait(&self, req_id: u64, deadline: Option<Duration>) -> KrakenResult<()> {
+        let ping_req = WsPingRequest {
+            event: "ping".to_string(),
+            req_id: Some(req_id),
+        };
+        self.request_ok(&ping_req, deadline).await.map(drop)
+    }
+
+    /// `authorize`, then wait for the server to acknowledge `req_id`.
+    pub async fn authorize_and_wait(
+        &self,
+        token: &str,
+        req_id: u64,
+        deadline: Option<Duration>,
+    ) -> KrakenResult<()> {
+        let auth_req = WsAuthorizeRequest {
+            event: "authorize".to_string(),
+            token: token.to_string(),
+            req_id: Some(req_id),
+        };
+        self.request_ok(&auth_req, deadline).await?;
+        *self.link.authorized.lock().unwrap() = Some(token.to_string());
+        Ok(())
+    }
+
+    /// `subscribe`, then wait for a successful `subscriptionStatus`.
+    pub async fn subscribe_and_wait(
+        &self,
+        subscription: WsSubscriptionPayload,
+        req_id: u64,
+        deadline: Option<Duration>,
+    ) -> KrakenResult<()> {
+        let reserved = self.reserve(&subscription)?;
+        let req = WsSubscribeRequest {
+            event: "subscribe".to_string(),
+            req_id: Some(req_id),
+            subscription,
+        };
+        let accepted = self.request_ok(&req, deadline).await.map(drop);
+        if accepted.is_err() {
+            self.release(reserved);
+        }
+        accepted
+    }
+
+    /// `unsubscribe`, then wait for a successful `subscriptionStatus`.
+    pub async fn unsubscribe_and_wait(
+        &self,
+        subscription: WsSubscriptionPayload,
+        req_id: u64,
+        deadline: Option<Duration>,
+    ) -> KrakenResult<()> {
+        let key = subscription_key(&subscription);
+        let req = WsUnsubscribeRequest {
+            event: "unsubscribe".to_string(),
+            req_id: Some(req_id),
+            subscription,
+        };
+        self.request_ok(&req, deadline).await?;
+        self.release(key);
+        Ok(())
+    }
+
+    /// Send a trading request (`WsAddOrderRequest`, `WsCancelOrderRequest`, ...,
+    /// with `req_id` set) and wait for its order status. A rejected order comes
+    /// back as the matching `KrakenError` (e.g. `OrderError`).
+    pub async fn order_request<R: WsCorrelated>(
+        &self,
+        request: &R,
+        deadline: Option<Duration>,
+    ) -> KrakenResult<WsUserTradingResponse> {
+        match self.request_ok(request, deadline).await? {
+            WsIncomingMessage::Trading(status) => Ok(status),
+            other => Err(KrakenError::InvalidUsage(format!(
+                "expected an order status for {}, got {other:?}",
+                request.operation()
+            ))),
+        }
+    }
 }
@@ -0,0 +1,337 @@
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use flate2::{Decompress, FlushDecompress};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::http::Uri;
+use tokio_tungstenite::MaybeTlsStream;
+
+use crate::error::{KrakenError, KrakenResult};
+
+/// The `Sec-WebSocket-Extensions` value `KrakenWsClient::connect` sends. The
+/// client never compresses what it sends, so it asks for nothing on its side.
+pub(crate) const OFFER: &str = "permessage-deflate";
+
+/// Larger frames are refused rather than buffered (tungstenite's own default).
+const MAX_FRAME_SIZE: usize = 16 << 20;
+
+/// Larger inflated messages are refused (tungstenite's own default).
+const MAX_MESSAGE_SIZE: usize = 64 << 20;
+
+const CONTINUATION: u8 = 0x0;
+const TEXT: u8 = 0x1;
+const BINARY: u8 = 0x2;
+
+/// Open the TCP (and, for `wss`, TLS) connection to `uri`, ready for the
+/// WebSocket handshake.
+pub(crate) async fn connect(uri: &Uri) -> KrakenResult<MaybeTlsStream<TcpStream>> {
+    let host = uri
+        .host()
+        .ok_or_else(|| KrakenError::InvalidUsage(format!("WebSocket URL has no host: {uri}")))?
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+    let tls = uri.scheme_str() == Some("wss");
+    let port = uri.port_u16().unwrap_or(if tls { 443 } else { 80 });
+    let tcp = TcpStream::connect((host, port)).await?;
+    if !tls {
+        return Ok(MaybeTlsStream::Plain(tcp));
+    }
+    let connector = tokio_native_tls::native_tls::TlsConnector::new()
+        .map_err(|e| KrakenError::InvalidUsage(format!("TLS setup error: {e}")))?;
+    let stream = tokio_native_tls::TlsConnector::from(connector)
+        .connect(host, tcp)
+        .await
+        .map_err(|e| KrakenError::InvalidUsage(format!("TLS handshake error: {e}")))?;
+    Ok(MaybeTlsStream::NativeTls(stream))
+}
+
+/// Sits between the socket and tungstenite, which can't decode
+/// permessage-deflate (RFC 7692) itself: reads the server's handshake
+/// response to learn whether the extension was accepted and, if it was,
+/// rewrites every compressed message into the plain, unfragmented frame
+/// tungstenite expects. Everything else, and everything written, passes
+/// through untouched.
+pub(crate) struct InflateStream<S> {
+    inner: S,
+    /// Read from `inner`, not yet a whole handshake response or frame
+    pending: Vec<u8>,
+    /// Handed to tungstenite from `served` on
+    ready: Vec<u8>,
+    served: usize,
+    state: State,
+    eof: bool,
+}
+
+enum State {
+    /// Until the end of the handshake response
+    Handshake,
+    /// The server declined the extension
+    Plain,
+    Deflate(Inflater),
+}
+
+impl<S> InflateStream<S> {
+    pub(crate) fn new(inner: S) -> Self {
+        Self {
+            inner,
+            pending: Vec::new(),
+            ready: Vec::new(),
+            served: 0,
+            state: State::Handshake,
+            eof: false,
+        }
+    }
+
+    /// Move whatever in `pending` is complete over to `ready`.
+    fn process(&mut self) -> io::Result<()> {
+        match &mut self.state {
+            State::Handshake => {
+                let Some(end) = self.pending.windows(4).position(|w| w == b"\r\n\r\n") else {
+                    return Ok(());
+                };
+                let head: Vec<u8> = self.pending.drain(..end + 4).collect();
+                self.state = match negotiated(&head) {
+                    Some(no_context_takeover) => State::Deflate(Inflater::new(no_context_takeover)),
+                    None => State::Plain,
+                };
+                self.ready.extend_from_slice(&head);
+                self.process()
+            }
+            State::Plain => {
+                self.ready.append(&mut self.pending);
+                Ok(())
+            }
+            State::Deflate(inflater) => {
+                while let Some(header) = FrameHeader::parse(&self.pending)? {
+                    let len = header.header_len + header.payload_len;
+                    inflater.frame(&self.pending[..len], &header, &mut self.ready)?;
+                    self.pending.drain(..len);
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// `Some(server_no_context_takeover)` if the handshake response `head`
+/// accepts permessage-deflate.
+fn negotiated(head: &[u8]) -> Option<bool> {
+    String::from_utf8_lossy(head).lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        (name.trim().eq_ignore_ascii_case("sec-websocket-extensions")
+            && value.contains("permessage-deflate"))
+        .then(|| value.contains("server_no_context_takeover"))
+    })
+}
+
+struct FrameHeader {
+    fin: bool,
+    rsv1: bool,
+    masked: bool,
+    opcode: u8,
+    header_len: usize,
+    payload_len: usize,
+}
+
+impl FrameHeader {
+    /// The header of the first frame in `bytes`, once the whole frame is there.
+    fn parse(bytes: &[u8]) -> io::Result<Option<Self>> {
+        let [b0, b1, ..] = *bytes else {
+            return Ok(None);
+        };
+        let masked = b1 & 0x80 != 0;
+        let (payload_len, mut header_len) = match b1 & 0x7f {
+            126 => match bytes.get(2..4) {
+                Some(len) => (u16::from_be_bytes([len[0], len[1]]) as u64, 4),
+                None => return Ok(None),
+            },
+            127 => match bytes.get(2..10) {
+                Some(len) => (u64::from_be_bytes(len.try_into().expect("8 bytes")), 10),
+                None => return Ok(None),
+            },
+            len => (len as u64, 2),
+        };
+        if masked {
+            header_len += 4;
+        }
+        if payload_len > MAX_FRAME_SIZE as u64 {
+            return Err(invalid(format!("{payload_len} byte frame")));
+        }
+        let payload_len = payload_len as usize;
+        if bytes.len() < header_len + payload_len {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            fin: b0 & 0x80 != 0,
+            rsv1: b0 & 0x40 != 0,
+            masked,
+            opcode: b0 & 0x0f,
+            header_len,
+            payload_len,
+        }))
+    }
+}
+
+struct Inflater {
+    decompress: Decompress,
+    /// The server starts every message with an empty window
+    no_context_takeover: bool,
+    /// Opcode and payload so far of a compressed message split over frames
+    message: Option<(u8, Vec<u8>)>,
+}
+
+impl Inflater {
+    fn new(no_context_takeover: bool) -> Self {
+        Self {
+            decompress: Decompress::new(false),
+            no_context_takeover,
+            message: None,
+        }
+    }
+
+    /// Write `frame` to `out` as is, or, once a compressed message is
+    /// complete, as one uncompressed frame.
+    fn frame(&mut self, frame: &[u8], header: &FrameHeader, out: &mut Vec<u8>) -> io::Result<()> {
+        let compressed = match header.opcode {
+            TEXT | BINARY => header.rsv1 && !header.masked,
+            CONTINUATION => self.message.is_some(),
+            _ => false,
+        };
+        if !compressed {
+            out.extend_from_slice(frame);
+            return Ok(());
+        }
+        let (_, payload) = self
+            .message
+            .get_or_insert_with(|| (header.opcode, Vec::new()));
+        payload.extend_from_slice(&frame[header.header_len..]);
+        if payload.len() > MAX_FRAME_SIZE {
+            return Err(invalid(format!(
+                "{} byte compressed message",
+                payload.len()
+            )));
+        }
+        if header.fin {
+            let (opcode, payload) = self.message.take().expect("message in progress");
+            let data = self.inflate(payload)?;
+            write_frame(out, opcode, &data);
+        }
+        Ok(())
+    }
+
+    fn inflate(&mut self, mut payload: Vec<u8>) -> io::Result<Vec<u8>> {
+        // The sender strips the empty block ending each message (RFC 7692 7.2.2)
+        payload.extend_from_slice(&[0x00, 0x00, 0xff, 0xff]);
+        let mut data = Vec::with_capacity(payload.len() * 4);
+        let mut consumed = 0;
+        loop {
+            if data.len() == data.capacity() {
+                data.reserve(data.capacity());
+            }
+            let (total_in, written) = (self.decompress.total_in(), data.len());
+            self.decompress
+                .decompress_vec(&payload[consumed..], &mut data, FlushDecompress::Sync)
+                .map_err(|e| invalid(format!("inflate error: {e}")))?;
+            consumed += (self.decompress.total_in() - total_in) as usize;
+            if consumed == payload.len() && data.len() < data.capacity() {
+                break;
+            }
+            if data.len() > MAX_MESSAGE_SIZE {
+                return Err(invalid(format!("{} byte inflated message", data.len())));
+            }
+            if consumed < payload.len()
+                && self.decompress.total_in() == total_in
+                && data.len() == written
+            {
+                return Err(invalid("inflate made no progress".to_string()));
+            }
+        }
+        if self.no_context_takeover {
+            self.decompress.reset(false);
+        }
+        Ok(data)
+    }
+}
+
+/// An unmasked, unfragmented server frame carrying `data`.
+fn write_frame(out: &mut Vec<u8>, opcode: u8, data: &[u8]) {
+    out.push(0x80 | opcode);
+    match data.len() {
+        len @ 0..=125 => out.push(len as u8),
+        len @ 126..=0xffff => {
+            out.push(126);
+            out.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            out.push(127);
+            out.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    out.extend_from_slice(data);
+}
+
+fn invalid(reason: String) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("permessage-deflate: {reason}"),
+    )
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for InflateStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        loop {
+            if this.served < this.ready.len() {
+                let n = buf.remaining().min(this.ready.len() - this.served);
+                buf.put_slice(&this.ready[this.served..this.served + n]);
+                this.served += n;
+                if this.served == this.ready.len() {
+                    this.ready.clear();
+                    this.served = 0;
+                }
+                return Poll::Ready(Ok(()));
+            }
+            if this.eof {
+                // Hand over any truncated frame so tungstenite reports it
+                if this.pending.is_empty() {
+                    return Poll::Ready(Ok(()));
+                }
+                this.ready.append(&mut this.pending);
+                continue;
+            }
+            let mut chunk = [0; 8192];
+            let mut chunk = ReadBuf::new(&mut chunk);
+            ready!(Pin::new(&mut this.inner).poll_read(cx, &mut chunk))?;
+            if chunk.filled().is_empty() {
+                this.eof = true;
+            } else {
+                this.pending.extend_from_slice(chunk.filled());
+                this.process()?;
+            }
+        }
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for InflateStream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::collections::HashMap;
 
 //
@@ -61,7 +62,7 @@ pub struct WsUnsubscribeRequest {
 }
 
 /// Each subscription has a "name" plus specific fields (symbol, depth, interval, etc.)
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "name", rename_all = "lowercase")]
 pub enum WsSubscriptionPayload {
     Ticker {
@@ -97,7 +98,7 @@ pub enum WsSubscriptionPayload {
 //
 
 /// Add Order request
-#[derive(Debug, Serialize)]
+#[derive(Debug, Default, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct WsAddOrderRequest {
     pub event: String, // "addOrder"
@@ -177,7 +178,7 @@ pub struct WsAddOrderRequest {
 }
 
 /// Amend Order request
-#[derive(Debug, Serialize)]
+#[derive(Debug, Default, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct WsAmendOrderRequest {
     pub event: String, // "amendOrder"
@@ -239,7 +240,7 @@ pub struct WsAmendOrderRequest {
 }
 
 /// Edit Order request
-#[derive(Debug, Serialize)]
+#[derive(Debug, Default, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct WsEditOrderRequest {
     pub event: String, // "editOrder"
@@ -344,7 +345,7 @@ pub struct WsBatchAddRequest {
 }
 
 /// One order spec in batchAdd
-#[derive(Debug, Serialize)]
+#[derive(Debug, Default, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct BatchAddOrderSpec {
     #[serde(rename = "orderType")]
@@ -422,6 +423,46 @@ pub struct WsBatchCancelRequest {
     pub orders: Vec<String>,
 }
 
+/// A client → server message whose reply echoes its `req_id`, so
+/// `KrakenWsClient::request` can wait for that reply.
+pub trait WsCorrelated: Serialize {
+    /// The `req_id` the reply will carry.
+    fn req_id(&self) -> Option<u64>;
+    /// The request's `event`, e.g. "subscribe"; used in error messages.
+    fn operation(&self) -> &str;
+}
+
+macro_rules! impl_ws_correlated {
+    ($($request:ty),* $(,)?) => {
+        $(
+            impl WsCorrelated for $request {
+                fn req_id(&self) -> Option<u64> {
+                    self.req_id
+                }
+
+                fn operation(&self) -> &str {
+                    &self.event
+                }
+            }
+        )*
+    };
+}
+
+impl_ws_correlated!(
+    WsPingRequest,
+    WsAuthorizeRequest,
+    WsSubscribeRequest,
+    WsUnsubscribeRequest,
+    WsAddOrderRequest,
+    WsAmendOrderRequest,
+    WsEditOrderRequest,
+    WsCancelOrderRequest,
+    WsCancelAllRequest,
+    WsCancelOnDisconnectRequest,
+    WsBatchAddRequest,
+    WsBatchCancelRequest,
+);
+
 //
 // ──────────────────────────────────────────────────────────────────────────────
 // ── RESPONSES / UPDATES (SERVER → CLIENT) ───────────────────────────────────
@@ -432,7 +473,7 @@ pub struct WsBatchCancelRequest {
 // 1. ADMIN / CONTROL
 //
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 #[serde(tag = "event", rename_all = "camelCase")]
 pub enum WsAdminResponse {
     /// systemStatus
@@ -461,7 +502,9 @@ pub enum WsAdminResponse {
     #[serde(rename = "heartbeat")]
     Heartbeat {},
 
-    #[serde(other)]
+    /// Never produced by deserialization: unrecognized events fall through to
+    /// `WsIncomingMessage::CatchAll` so the other tagged enums still get a chance.
+    #[serde(skip_deserializing)]
     Unknown,
 }
 
@@ -470,7 +513,7 @@ pub enum WsAdminResponse {
 //
 
 /// Ticker message (level 1).
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct WsTickerMessage {
     pub channel: String,
     pub symbol: String,
@@ -489,23 +532,33 @@ pub struct WsTickerMessage {
 }
 
 /// Book (level 2) snapshot or updates
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct WsBookMessage {
     pub channel: String,
+    /// "snapshot" or "update"
+    #[serde(rename = "type", default)]
+    pub update_type: Option<String>,
     pub symbol: String,
     pub bids: Vec<OrderBookEntry>,
     pub asks: Vec<OrderBookEntry>,
 }
 
+impl WsBookMessage {
+    /// `true` if this message replaces the whole book rather than updating it.
+    pub fn is_snapshot(&self) -> bool {
+        self.update_type.as_deref() == Some("snapshot")
+    }
+}
+
 /// One side of the order book
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct OrderBookEntry {
     pub price: String,
     pub quantity: String,
 }
 
 /// Candles (OHLC)
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct WsCandlesMessage {
     pub channel: String,
     pub symbol: String,
@@ -513,7 +566,7 @@ pub struct WsCandlesMessage {
     pub data: Vec<CandleData>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct CandleData {
     pub time: u64,
     pub open: String,
@@ -524,14 +577,14 @@ pub struct CandleData {
 }
 
 /// Trades feed
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct WsTradesMessage {
     pub channel: String,
     pub symbol: String,
     pub trades: Vec<TradeData>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct TradeData {
     pub price: String,
     pub quantity: String,
@@ -540,7 +593,7 @@ pub struct TradeData {
 }
 
 /// Instruments
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct WsInstrumentsMessage {
     pub channel: String,
     #[serde(default)]
@@ -548,7 +601,7 @@ pub struct WsInstrumentsMessage {
     pub data: Vec<InstrumentData>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct InstrumentData {
     pub symbol: String,
     pub status: String,
@@ -570,23 +623,270 @@ pub struct InstrumentData {
     pub lot_size: String,
 }
 
+//
+// 2b. BORROWED MARKET DATA (hot path)
+//
+// Ticker, book and trade frames make up nearly all WS traffic. These views
+// deserialize straight from the frame text: strings borrow from it unless they
+// contain JSON escapes, and nothing is buffered the way the untagged
+// `WsIncomingMessage` has to. Use `into_owned` to get the regular model types.
+//
+
+/// Capacity reserved up front for `bids`/`asks`/`trades`, so typical updates
+/// and depth-25 snapshots fill without reallocating.
+pub const LEVELS_CAPACITY_HINT: usize = 32;
+
+/// Borrowed view of a `WsTickerMessage`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WsTickerRef<'a> {
+    #[serde(borrow)]
+    pub channel: Cow<'a, str>,
+    #[serde(borrow)]
+    pub symbol: Cow<'a, str>,
+    #[serde(borrow)]
+    pub best_ask_price: Cow<'a, str>,
+    #[serde(borrow)]
+    pub best_ask_quantity: Cow<'a, str>,
+    #[serde(borrow)]
+    pub best_bid_price: Cow<'a, str>,
+    #[serde(borrow)]
+    pub best_bid_quantity: Cow<'a, str>,
+    #[serde(borrow)]
+    pub last_trade_price: Cow<'a, str>,
+    #[serde(borrow)]
+    pub last_trade_quantity: Cow<'a, str>,
+    #[serde(borrow)]
+    pub volume_24h: Cow<'a, str>,
+    #[serde(borrow)]
+    pub vwap_24h: Cow<'a, str>,
+    pub trades_24h: u64,
+    #[serde(borrow)]
+    pub low_24h: Cow<'a, str>,
+    #[serde(borrow)]
+    pub high_24h: Cow<'a, str>,
+    #[serde(borrow)]
+    pub open_24h: Cow<'a, str>,
+}
+
+impl WsTickerRef<'_> {
+    pub fn into_owned(self) -> WsTickerMessage {
+        WsTickerMessage {
+            channel: self.channel.into_owned(),
+            symbol: self.symbol.into_owned(),
+            best_ask_price: self.best_ask_price.into_owned(),
+            best_ask_quantity: self.best_ask_quantity.into_owned(),
+            best_bid_price: self.best_bid_price.into_owned(),
+            best_bid_quantity: self.best_bid_quantity.into_owned(),
+            last_trade_price: self.last_trade_price.into_owned(),
+            last_trade_quantity: self.last_trade_quantity.into_owned(),
+            volume_24h: self.volume_24h.into_owned(),
+            vwap_24h: self.vwap_24h.into_owned(),
+            trades_24h: self.trades_24h,
+            low_24h: self.low_24h.into_owned(),
+            high_24h: self.high_24h.into_owned(),
+            open_24h: self.open_24h.into_owned(),
+        }
+    }
+}
+
+/// Borrowed view of a `WsBookMessage`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WsBookRef<'a> {
+    #[serde(borrow)]
+    pub channel: Cow<'a, str>,
+    /// "snapshot" or "update"
+    #[serde(rename = "type", borrow, default)]
+    pub update_type: Option<Cow<'a, str>>,
+    #[serde(borrow)]
+    pub symbol: Cow<'a, str>,
+    #[serde(borrow, deserialize_with = "presized")]
+    pub bids: Vec<OrderBookEntryRef<'a>>,
+    #[serde(borrow, deserialize_with = "presized")]
+    pub asks: Vec<OrderBookEntryRef<'a>>,
+}
+
+impl WsBookRef<'_> {
+    /// `true` if this message replaces the whole book rather than updating it.
+    pub fn is_snapshot(&self) -> bool {
+        self.update_type.as_deref() == Some("snapshot")
+    }
+
+    pub fn into_owned(self) -> WsBookMessage {
+        WsBookMessage {
+            channel: self.channel.into_owned(),
+            update_type: self.update_type.map(Cow::into_owned),
+            symbol: self.symbol.into_owned(),
+            bids: self.bids.into_iter().map(OrderBookEntryRef::into_owned).collect(),
+            asks: self.asks.into_iter().map(OrderBookEntryRef::into_owned).collect(),
+        }
+    }
+}
+
+/// Borrowed view of an `OrderBookEntry`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct OrderBookEntryRef<'a> {
+    #[serde(borrow)]
+    pub price: Cow<'a, str>,
+    #[serde(borrow)]
+    pub quantity: Cow<'a, str>,
+}
+
+impl OrderBookEntryRef<'_> {
+    pub fn into_owned(self) -> OrderBookEntry {
+        OrderBookEntry {
+            price: self.price.into_owned(),
+            quantity: self.quantity.into_owned(),
+        }
+    }
+}
+
+/// Borrowed view of a `WsTradesMessage`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WsTradesRef<'a> {
+    #[serde(borrow)]
+    pub channel: Cow<'a, str>,
+    #[serde(borrow)]
+    pub symbol: Cow<'a, str>,
+    #[serde(borrow, deserialize_with = "presized")]
+    pub trades: Vec<TradeDataRef<'a>>,
+}
+
+impl WsTradesRef<'_> {
+    pub fn into_owned(self) -> WsTradesMessage {
+        WsTradesMessage {
+            channel: self.channel.into_owned(),
+            symbol: self.symbol.into_owned(),
+            trades: self.trades.into_iter().map(TradeDataRef::into_owned).collect(),
+        }
+    }
+}
+
+/// Borrowed view of a `TradeData`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TradeDataRef<'a> {
+    #[serde(borrow)]
+    pub price: Cow<'a, str>,
+    #[serde(borrow)]
+    pub quantity: Cow<'a, str>,
+    pub time: u64,
+    #[serde(borrow)]
+    pub side: Cow<'a, str>,
+}
+
+impl TradeDataRef<'_> {
+    pub fn into_owned(self) -> TradeData {
+        TradeData {
+            price: self.price.into_owned(),
+            quantity: self.quantity.into_owned(),
+            time: self.time,
+            side: self.side.into_owned(),
+        }
+    }
+}
+
+/// A borrowed ticker, book or trade frame.
+#[derive(Debug, Clone)]
+pub enum WsMarketDataRef<'a> {
+    Ticker(WsTickerRef<'a>),
+    Book(WsBookRef<'a>),
+    Trades(WsTradesRef<'a>),
+}
+
+impl<'a> WsMarketDataRef<'a> {
+    /// Parse `text` if it is a ticker, book or trade frame; `None` for anything else.
+    ///
+    /// The frame is routed on its `channel` field, so only the matching struct is
+    /// ever attempted.
+    pub fn parse(text: &'a str) -> Option<serde_json::Result<Self>> {
+        #[derive(Deserialize)]
+        struct Route<'a> {
+            #[serde(borrow, default)]
+            event: Option<Cow<'a, str>>,
+            #[serde(borrow, default)]
+            channel: Option<Cow<'a, str>>,
+        }
+
+        let route: Route = serde_json::from_str(text).ok()?;
+        if route.event.is_some() {
+            // e.g. subscriptionStatus, which also carries a `channel`
+            return None;
+        }
+        Some(match route.channel.as_deref()? {
+            "ticker" => serde_json::from_str(text).map(Self::Ticker),
+            "book" => serde_json::from_str(text).map(Self::Book),
+            "trade" => serde_json::from_str(text).map(Self::Trades),
+            _ => return None,
+        })
+    }
+
+    pub fn into_owned(self) -> WsIncomingMessage {
+        match self {
+            Self::Ticker(ticker) => WsIncomingMessage::TickerMsg(ticker.into_owned()),
+            Self::Book(book) => WsIncomingMessage::BookMsg(book.into_owned()),
+            Self::Trades(trades) => WsIncomingMessage::TradesMsg(trades.into_owned()),
+        }
+    }
+}
+
+/// Deserialize a WS text frame. Equivalent to
+/// `serde_json::from_str::<WsIncomingMessage>(text)`, but ticker, book and trade
+/// frames take the `WsMarketDataRef` fast path, skipping the untagged enum's
+/// buffering and trial decodes. The result is still owned, so every string is
+/// copied once; for zero-copy decoding, run `WsMarketDataRef::parse` on frames
+/// from `KrakenWsClient::raw_messages()` instead.
+pub fn parse_incoming(text: &str) -> serde_json::Result<WsIncomingMessage> {
+    match WsMarketDataRef::parse(text) {
+        Some(Ok(market_data)) => Ok(market_data.into_owned()),
+        // Malformed market data still ends up as `CatchAll`, as before.
+        Some(Err(_)) | None => serde_json::from_str(text),
+    }
+}
+
+/// Deserialize a JSON array into a `Vec` with `LEVELS_CAPACITY_HINT` reserved.
+fn presized<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    struct Presized<T>(std::marker::PhantomData<T>);
+
+    impl<'de, T: Deserialize<'de>> serde::de::Visitor<'de> for Presized<T> {
+        type Value = Vec<T>;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str("an array")
+        }
+
+        fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<T>, A::Error> {
+            let mut items =
+                Vec::with_capacity(seq.size_hint().unwrap_or(0).max(LEVELS_CAPACITY_HINT));
+            while let Some(item) = seq.next_element()? {
+                items.push(item);
+            }
+            Ok(items)
+        }
+    }
+
+    deserializer.deserialize_seq(Presized(std::marker::PhantomData))
+}
+
 //
 // 3. USER DATA (balances, executions)
 //
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct WsBalancesMessage {
     pub channel: String,
     pub balances: HashMap<String, String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct WsExecutionsMessage {
     pub channel: String,
     pub executions: Vec<ExecutionData>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct ExecutionData {
     pub symbol: String,
     pub order_id: String,
@@ -605,7 +905,7 @@ pub struct ExecutionData {
 // 4. USER TRADING RESPONSES (addOrderStatus, etc.)
 //
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 #[serde(tag = "event", rename_all = "camelCase")]
 pub enum WsUserTradingResponse {
     #[serde(rename = "addOrderStatus")]
@@ -694,11 +994,47 @@ pub enum WsUserTradingResponse {
         error_message: Option<String>,
     },
 
-    #[serde(other)]
+    /// Never produced by deserialization: unrecognized events fall through to
+    /// `WsIncomingMessage::CatchAll` so the other tagged enums still get a chance.
+    #[serde(skip_deserializing)]
     Unknown,
 }
 
-#[derive(Debug, Deserialize)]
+impl WsUserTradingResponse {
+    /// The `req_id` of the request this answers.
+    pub fn req_id(&self) -> Option<u64> {
+        match self {
+            Self::AddOrderStatus { req_id, .. }
+            | Self::AmendOrderStatus { req_id, .. }
+            | Self::EditOrderStatus { req_id, .. }
+            | Self::CancelOrderStatus { req_id, .. }
+            | Self::CancelAllStatus { req_id, .. }
+            | Self::CancelOnDisconnectStatus { req_id, .. }
+            | Self::BatchAddStatus { req_id, .. }
+            | Self::BatchCancelStatus { req_id, .. } => *req_id,
+            Self::Unknown => None,
+        }
+    }
+
+    /// The server's error, if the request was rejected.
+    pub fn error_message(&self) -> Option<&str> {
+        match self {
+            Self::AddOrderStatus { status, error_message, .. }
+            | Self::AmendOrderStatus { status, error_message, .. }
+            | Self::EditOrderStatus { status, error_message, .. }
+            | Self::CancelOrderStatus { status, error_message, .. }
+            | Self::CancelAllStatus { status, error_message, .. }
+            | Self::CancelOnDisconnectStatus { status, error_message, .. }
+            | Self::BatchAddStatus { status, error_message, .. }
+            | Self::BatchCancelStatus { status, error_message, .. } => {
+                error_message.as_deref().or((status == "error").then_some(status.as_str()))
+            }
+            Self::Unknown => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
 pub struct BatchAddResult {
     #[serde(default)]
     pub txid: Option<String>,
@@ -710,7 +1046,7 @@ pub struct BatchAddResult {
     pub client_order_id: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct BatchCancelResult {
     #[serde(default)]
     pub txid: Option<String>,
@@ -722,8 +1058,9 @@ pub struct BatchCancelResult {
 // 5. UNIFIED "WsIncomingMessage" - a top-level enum if you want to parse everything
 //
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 #[serde(untagged)]
+#[allow(clippy::large_enum_variant)]
 pub enum WsIncomingMessage {
     Admin(WsAdminResponse),
 
@@ -744,3 +1081,34 @@ pub enum WsIncomingMessage {
     /// Catch-all for unknown or non-matching messages
     CatchAll(serde_json::Value),
 }
+
+impl WsIncomingMessage {
+    /// The `req_id` echoed back by the server, if this message answers a request.
+    pub fn req_id(&self) -> Option<u64> {
+        match self {
+            Self::Admin(WsAdminResponse::SubscriptionStatus { req_id, .. })
+            | Self::Admin(WsAdminResponse::PingStatus { req_id }) => *req_id,
+            Self::Trading(trading) => trading.req_id(),
+            Self::CatchAll(value) => value.get("req_id").and_then(|id| id.as_u64()),
+            _ => None,
+        }
+    }
+
+    /// The server's error, if this message reports a rejected request.
+    pub fn error_message(&self) -> Option<&str> {
+        match self {
+            Self::Admin(WsAdminResponse::SubscriptionStatus {
+                status,
+                error_message,
+                ..
+            }) => error_message
+                .as_deref()
+                .or((status == "error").then_some(status.as_str())),
+            Self::Trading(trading) => trading.error_message(),
+            Self::CatchAll(value) => ["errorMessage", "error_message", "error"]
+                .iter()
+                .find_map(|key| value.get(key)?.as_str()),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use std::sync::Arc;
+
+use tokio::sync::{broadcast, Mutex};
+use tokio::task::JoinHandle;
+
+use crate::error::KrakenResult;
+use crate::feed::FeedStream;
+use crate::ws_client::{subscription_key, KrakenWsClient, SubscriptionBudget};
+use crate::ws_models::{
+    WsBookMessage, WsCandlesMessage, WsIncomingMessage, WsSubscriptionPayload, WsTickerMessage,
+    WsTradesMessage,
+};
+
+/// How many messages `WsPool::messages()` receivers may fall behind.
+const POOL_BUFFER: usize = 4096;
+
+/// Public WebSocket connections sharing a set of subscriptions, each kept
+/// within a per-connection `SubscriptionBudget`.
+///
+/// `subscribe` puts a subscription on the first connected socket with room,
+/// opening a new connection when every socket is full, and merges every
+/// connection's messages into `messages()` and the typed streams.
+///
+/// ```no_run
+/// # async fn run() -> onise::error::KrakenResult<()> {
+/// use onise::ws_models::WsSubscriptionPayload;
+/// use onise::ws_pool::WsPool;
+///
+/// let pool = WsPool::new("wss://ws.kraken.com/v2", 50);
+/// let tickers = pool.ticker_stream();
+/// for symbol in ["BTC/USD", "ETH/USD", "SOL/USD"] {
+///     pool.subscribe(WsSubscriptionPayload::Ticker { symbol: symbol.to_string() }).await?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct WsPool {
+    url: String,
+    max_per_connection: usize,
+    connections: Mutex<Vec<Arc<KrakenWsClient>>>,
+    events: broadcast::Sender<WsIncomingMessage>,
+    /// One task per connection copying its messages into `events`
+    forwarders: std::sync::Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl WsPool {
+    /// A pool connecting to `url` as needed, at most `max_per_connection`
+    /// subscriptions per socket. Nothing connects until the first `subscribe`.
+    pub fn new(url: impl Into<String>, max_per_connection: usize) -> Self {
+        Self {
+            url: url.into(),
+            max_per_connection: max_per_connection.max(1),
+            connections: Mutex::new(Vec::new()),
+            events: broadcast::channel(POOL_BUFFER).0,
+            forwarders: std::sync::Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Subscribe on a connection with room (or an existing one already carrying
+    /// `subscription`), returning that connection.
+    pub async fn subscribe(
+        &self,
+        subscription: WsSubscriptionPayload,
+    ) -> KrakenResult<Arc<KrakenWsClient>> {
+        let mut connections = self.connections.lock().await;
+        if let Some(existing) = connections.iter().find(|ws| ws.is_subscribed(&subscription)) {
+            return Ok(existing.clone());
+        }
+        let free = connections.iter().find(|ws| {
+            ws.is_connected()
+                && (subscription_key(&subscription).is_none()
+                    || ws.subscription_count() < self.max_per_connection)
+        });
+        let ws = match free {
+            Some(ws) => ws.clone(),
+            None => {
+                let ws = self.open().await?;
+                connections.push(ws.clone());
+                ws
+            }
+        };
+        ws.subscribe(subscription, None).await?;
+        Ok(ws)
+    }
+
+    /// Unsubscribe on whichever connection carries `subscription`. A
+    /// subscription the pool doesn't carry is left alone.
+    pub async fn unsubscribe(&self, subscription: WsSubscriptionPayload) -> KrakenResult<()> {
+        let connections = self.connections.lock().await;
+        match connections.iter().find(|ws| ws.is_subscribed(&subscription)) {
+            Some(ws) => ws.unsubscribe(subscription, None).await,
+            None => Ok(()),
+        }
+    }
+
+    /// The pool's connections, in the order they were opened, including any
+    /// that have since disconnected.
+    pub async fn connections(&self) -> Vec<Arc<KrakenWsClient>> {
+        self.connections.lock().await.clone()
+    }
+
+    /// Subscriptions across all connections.
+    pub async fn subscription_count(&self) -> usize {
+        let connections = self.connections.lock().await;
+        connections.iter().map(|ws| ws.subscription_count()).sum()
+    }
+
+    /// Every parsed message from every connection, from now on. Lags like
+    /// `KrakenWsClient::messages`; never closes while the pool is alive.
+    pub fn messages(&self) -> broadcast::Receiver<WsIncomingMessage> {
+        self.events.subscribe()
+    }
+
+    pub fn ticker_stream(&self) -> FeedStream<WsTickerMessage> {
+        FeedStream::new(self.messages(), |msg| match msg {
+            WsIncomingMessage::TickerMsg(ticker) => Some(ticker),
+            _ => None,
+        })
+    }
+
+    pub fn book_stream(&self) -> FeedStream<WsBookMessage> {
+        FeedStream::new(self.messages(), |msg| match msg {
+            WsIncomingMessage::BookMsg(book) => Some(book),
+            _ => None,
+        })
+    }
+
+    pub fn candles_stream(&self) -> FeedStream<WsCandlesMessage> {
+        FeedStream::new(self.messages(), |msg| match msg {
+            WsIncomingMessage::CandlesMsg(candles) => Some(candles),
+            _ => None,
+        })
+    }
+
+    pub fn trades_stream(&self) -> FeedStream<WsTradesMessage> {
+        FeedStream::new(self.messages(), |msg| match msg {
+            WsIncomingMessage::TradesMsg(trades) => Some(trades),
+            _ => None,
+        })
+    }
+
+    async fn open(&self) -> KrakenResult<Arc<KrakenWsClient>> {
+        let ws = KrakenWsClient::connect(&self.url)
+            .await?
+            .with_subscription_budget(SubscriptionBudget::reject(self.max_per_connection));
+        let mut messages = ws.messages();
+        let events = self.events.clone();
+        let forwarder = tokio::spawn(async move {
+            loop {
+                match messages.recv().await {
+                    Ok(msg) => {
+                        let _ = events.send(msg);
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!(skipped, "pool connection lagged");
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+        self.forwarders.lock().unwrap().push(forwarder);
+        tracing::info!(url = %self.url, "opened pooled WebSocket connection");
+        Ok(Arc::new(ws))
+    }
+}
+
+impl Drop for WsPool {
+    fn drop(&mut self) {
+        for forwarder in self.forwarders.lock().unwrap().drain(..) {
+            forwarder.abort();
+        }
+    }
+}
+
+impl std::fmt::Debug for WsPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WsPool")
+            .field("url", &self.url)
+            .field("max_per_connection", &self.max_per_connection)
+            .finish()
+    }
+}
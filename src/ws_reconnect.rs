@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+use serde::Deserialize;
+
+/// Exponential backoff between connection attempts: `initial_delay_ms`,
+/// doubling up to `max_delay_ms`, for at most `max_attempts` attempts
+/// (unlimited when unset).
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReconnectPolicy {
+    pub initial_delay_ms: u64,
+    pub max_delay_ms: u64,
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay_ms: 500,
+            max_delay_ms: 30_000,
+            max_attempts: Some(5),
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// How long to wait after failed attempt number `attempt` (counting from
+    /// 1), or `None` once the attempts are used up.
+    pub fn delay(&self, attempt: u32) -> Option<Duration> {
+        if self.max_attempts.is_some_and(|max| attempt >= max) {
+            return None;
+        }
+        let doubled = self
+            .initial_delay_ms
+            .saturating_mul(1u64 << attempt.saturating_sub(1).min(32));
+        Some(Duration::from_millis(doubled.min(self.max_delay_ms)))
+    }
+
+    /// How long to wait before reconnect attempt number `attempt` (counting
+    /// from 1): `initial_delay_ms` before the first, then `delay` of the one
+    /// before. `None` once the attempts are used up.
+    pub fn wait_before(&self, attempt: u32) -> Option<Duration> {
+        if attempt > 1 {
+            return self.delay(attempt - 1);
+        }
+        (self.max_attempts != Some(0))
+            .then(|| Duration::from_millis(self.initial_delay_ms.min(self.max_delay_ms)))
+    }
+}
+
+/// A change in the connection of a `KrakenWsClient` set up
+/// `with_reconnect`, from `KrakenWsClient::connection_events`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionEvent {
+    /// The connection dropped; `error` is the read error, `None` when the
+    /// server closed it
+    Disconnected { error: Option<String> },
+    /// Reconnect attempt `attempt` (from 1) starts after `delay`
+    Reconnecting { attempt: u32, delay: Duration },
+    /// Reconnect attempt `attempt` failed with `error`
+    ReconnectFailed { attempt: u32, error: String },
+    /// Connected again on attempt `attempt`; the last `authorize` token and
+    /// `resubscribed` subscriptions were sent again
+    Reconnected { attempt: u32, resubscribed: usize },
+    /// The attempts ran out after `attempts`; the client's streams close
+    GaveUp { attempts: u32 },
+}
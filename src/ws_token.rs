@@ -0,0 +1,71 @@
+use std::time::{Duration, Instant};
+
+use tokio::sync::Mutex;
+
+use crate::error::KrakenResult;
+use crate::AuthenticatedClient;
+
+/// How long before its `expires` a token is replaced by default.
+pub const DEFAULT_REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
+/// Hands out `GetWebSocketsToken` tokens, fetching a new one only when the
+/// last has expired (or is within the refresh margin of expiring).
+///
+/// Expiry is read from the client's `Clock`, so with a `clock::MockClock` on
+/// the client tests can step past a token's lifetime without waiting.
+///
+/// ```no_run
+/// # async fn run(client: onise::AuthenticatedClient) -> onise::error::KrakenResult<()> {
+/// use onise::ws_token::TokenManager;
+///
+/// let tokens = TokenManager::new(client);
+/// let token = tokens.token().await?;
+/// // ... authorize a WebSocket connection with `token`
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct TokenManager {
+    client: AuthenticatedClient,
+    refresh_margin: Duration,
+    /// The current token and when it stops being handed out
+    current: Mutex<Option<(String, Instant)>>,
+}
+
+impl TokenManager {
+    pub fn new(client: AuthenticatedClient) -> Self {
+        Self {
+            client,
+            refresh_margin: DEFAULT_REFRESH_MARGIN,
+            current: Mutex::new(None),
+        }
+    }
+
+    /// Replace tokens `margin` before they expire instead of
+    /// `DEFAULT_REFRESH_MARGIN`.
+    pub fn with_refresh_margin(mut self, margin: Duration) -> Self {
+        self.refresh_margin = margin;
+        self
+    }
+
+    /// A token that is still valid, fetching a new one if needed. Concurrent
+    /// callers share a single fetch.
+    pub async fn token(&self) -> KrakenResult<String> {
+        let mut current = self.current.lock().await;
+        let now = self.client.clock().now();
+        if let Some((token, valid_until)) = current.as_ref() {
+            if now < *valid_until {
+                return Ok(token.clone());
+            }
+        }
+        let fresh = self.client.get_websockets_token().await?;
+        let lifetime = Duration::from_secs(fresh.expires).saturating_sub(self.refresh_margin);
+        *current = Some((fresh.token.clone(), now + lifetime));
+        Ok(fresh.token)
+    }
+
+    /// Forget the current token, e.g. after a connection rejected it.
+    pub async fn invalidate(&self) {
+        *self.current.lock().await = None;
+    }
+}
@@ -0,0 +1,48 @@
+use onise::assets::{display_name, kraken_code, LEGACY_ASSET_CODES};
+use onise::models::{AccountBalanceResponse, LedgersResponse};
+
+#[test]
+fn test_legacy_codes_round_trip() {
+    assert_eq!(display_name("XXBT"), "BTC");
+    assert_eq!(display_name("ZUSD"), "USD");
+    assert_eq!(display_name("XETH"), "ETH");
+    assert_eq!(display_name("XBT.M"), "BTC.M");
+    assert_eq!(display_name("SOL"), "SOL");
+    assert_eq!(display_name("ETH2.S"), "ETH2.S");
+
+    assert_eq!(kraken_code("BTC"), "XXBT");
+    assert_eq!(kraken_code("btc"), "XXBT");
+    assert_eq!(kraken_code("DOGE"), "XXDG");
+    assert_eq!(kraken_code("BTC.M"), "XBT.M");
+    assert_eq!(kraken_code("ETH.F"), "ETH.F");
+    assert_eq!(kraken_code("USDT"), "USDT");
+
+    for (code, display) in LEGACY_ASSET_CODES.iter().filter(|(code, _)| code.len() == 4) {
+        assert_eq!(display_name(code), *display);
+        assert_eq!(kraken_code(display), *code);
+    }
+}
+
+#[test]
+fn test_balance_and_ledger_helpers() {
+    let balance: AccountBalanceResponse =
+        serde_json::from_str(r#"{"XXBT":"0.5","ZUSD":"100.0","XBT.M":"0.1","USDT":"3"}"#).unwrap();
+    let by_name = balance.by_display_name();
+    assert_eq!(by_name["BTC"], "0.5");
+    assert_eq!(by_name["USD"], "100.0");
+    assert_eq!(by_name["BTC.M"], "0.1");
+    assert_eq!(by_name["USDT"], "3");
+
+    let ledgers: LedgersResponse = serde_json::from_value(serde_json::json!({
+        "count": 1,
+        "ledger": {
+            "L4UESK-KG3EQ-UFO4T5": {
+                "refid": "TJKLXX-PGMUI-4NTLXU", "time": 1688464484.1787,
+                "type": "trade", "subtype": "", "aclass": "currency", "asset": "XETH",
+                "amount": "-0.25", "fee": "0.0", "balance": "1.75"
+            }
+        }
+    }))
+    .unwrap();
+    assert_eq!(ledgers.ledger["L4UESK-KG3EQ-UFO4T5"].display_asset(), "ETH");
+}
@@ -0,0 +1,44 @@
+use onise::testkit::{ErrorClass, MockKraken};
+
+#[tokio::test]
+async fn test_audit_log_records_private_calls() {
+    use onise::audit::AuditLog;
+
+    let path = std::env::temp_dir().join(format!("onise-audit-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let kraken = MockKraken::start().await;
+    kraken.mock_error("/0/private/CancelOrder", ErrorClass::Order).await;
+    kraken.mock_all_success().await;
+    let c = kraken
+        .authenticated_client()
+        .with_audit_sink(AuditLog::append_to_file(&path).expect("writable"));
+
+    c.add_order(&[("pair", "XBTUSD"), ("otp", "123456")])
+        .await
+        .expect("AddOrder");
+    c.cancel_order(&[("txid", "OUF4EM-FRGI2-MQMWZD")])
+        .await
+        .expect_err("mocked order error");
+    c.get_server_time().await.expect("public calls are not audited");
+
+    let lines: Vec<serde_json::Value> = std::fs::read_to_string(&path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(lines.len(), 2);
+
+    let add = &lines[0];
+    assert_eq!(add["endpoint"], "/0/private/AddOrder");
+    assert_eq!(add["status"], 200);
+    assert!(add["nonce"].as_u64().unwrap() > 0);
+    assert_eq!(add["params"][1], serde_json::json!(["otp", onise::logging::REDACTED]));
+    assert!(!add["txids"].as_array().unwrap().is_empty());
+    assert!(add["error"].is_null());
+
+    let cancel = &lines[1];
+    assert_eq!(cancel["endpoint"], "/0/private/CancelOrder");
+    assert!(cancel["error"].as_str().unwrap().starts_with("EOrder:"));
+}
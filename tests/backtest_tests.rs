@@ -0,0 +1,136 @@
+use std::io::Cursor;
+
+use onise::backtest::{Backtester, Strategy};
+use onise::error::{KrakenError, KrakenResult};
+use onise::exchange::{ExchangeClient, OrderRequest, Side};
+use onise::replay::Replay;
+use onise::simulated::SimulatedExchange;
+use onise::ws_models::WsTickerMessage;
+
+fn recording(quotes: &[(&str, &str, &str)]) -> Replay {
+    let lines: Vec<String> = quotes
+        .iter()
+        .enumerate()
+        .map(|(i, (symbol, bid, ask))| {
+            format!(
+                r#"{{"t":{i},"msg":{{"channel":"ticker","symbol":"{symbol}","best_ask_price":"{ask}","best_ask_quantity":"1","best_bid_price":"{bid}","best_bid_quantity":"1","last_trade_price":"{bid}","last_trade_quantity":"1","volume_24h":"1","vwap_24h":"1","trades_24h":1,"low_24h":"1","high_24h":"1","open_24h":"1"}}}}"#
+            )
+        })
+        .collect();
+    Replay::from_reader(Cursor::new(lines.join("\n")))
+}
+
+/// Buys 1 BTC with a limit order below the first ask, sells once the bid is 10% higher.
+#[derive(Default)]
+struct BuyTheDip {
+    entry: Option<f64>,
+    sold: bool,
+}
+
+impl Strategy for BuyTheDip {
+    async fn on_ticker<E: ExchangeClient>(
+        &mut self,
+        exchange: &E,
+        ticker: &WsTickerMessage,
+    ) -> KrakenResult<()> {
+        let bid: f64 = ticker.best_bid_price.parse().unwrap();
+        match self.entry {
+            None => {
+                let ask: f64 = ticker.best_ask_price.parse().unwrap();
+                let price = ask - 5.0;
+                exchange
+                    .place_order(&OrderRequest::limit(
+                        &ticker.symbol,
+                        Side::Buy,
+                        "1",
+                        price.to_string(),
+                    ))
+                    .await?;
+                self.entry = Some(price);
+            }
+            Some(entry) if !self.sold && bid >= entry * 1.1 => {
+                exchange
+                    .place_order(&OrderRequest::market(&ticker.symbol, Side::Sell, "1"))
+                    .await?;
+                self.sold = true;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_backtest_reports_fills_and_pnl() {
+    let exchange = SimulatedExchange::new()
+        .with_fee_rate(0.001)
+        .with_pair("BTC/USD", "XXBT", "ZUSD")
+        .with_balance("ZUSD", 1_000.0);
+    let backtester = Backtester::new(exchange);
+    let replay = recording(&[
+        ("BTC/USD", "99", "100"),
+        ("ETH/USD", "5", "6"),
+        ("BTC/USD", "94", "95"),
+        ("BTC/USD", "110", "111"),
+        ("BTC/USD", "120", "121"),
+    ]);
+
+    let report = backtester
+        .run(replay, &mut BuyTheDip::default())
+        .await
+        .expect("backtest");
+
+    // ETH/USD isn't a pair of the exchange
+    assert_eq!(report.ticks, 4);
+    assert_eq!(report.fills.len(), 2);
+    assert_eq!(
+        (report.fills[0].side, report.fills[0].price),
+        (Side::Buy, 95.0)
+    );
+    assert_eq!(
+        (report.fills[1].side, report.fills[1].price),
+        (Side::Sell, 110.0)
+    );
+
+    let btc = &report.pairs["BTC/USD"];
+    assert_eq!(btc.net_volume, 0.0);
+    assert!((btc.fees - 0.205).abs() < 1e-9);
+    assert!((report.total_pnl() - (15.0 - 0.205)).abs() < 1e-9);
+    assert_eq!(btc.last_price, Some(120.5));
+    assert_eq!(report.starting_balances["ZUSD"], 1_000.0);
+    assert!((report.ending_balances["ZUSD"] - 1_014.795).abs() < 1e-9);
+}
+
+#[tokio::test]
+async fn test_backtest_marks_open_positions_and_propagates_errors() {
+    let exchange = SimulatedExchange::new()
+        .with_fee_rate(0.0)
+        .with_pair("BTC/USD", "XXBT", "ZUSD")
+        .with_balance("ZUSD", 1_000.0);
+    let backtester = Backtester::new(exchange);
+    let report = backtester
+        .run(
+            recording(&[("BTC/USD", "99", "100"), ("BTC/USD", "89", "91")]),
+            &mut BuyTheDip::default(),
+        )
+        .await
+        .unwrap();
+    // Bought at 95, marked at the 90 mid
+    assert_eq!(report.pairs["BTC/USD"].net_volume, 1.0);
+    assert_eq!(report.total_pnl(), -5.0);
+
+    // Too little cash for the strategy's order: the rejection aborts the run
+    let poor = Backtester::new(
+        SimulatedExchange::new()
+            .with_pair("BTC/USD", "XXBT", "ZUSD")
+            .with_balance("ZUSD", 10.0),
+    );
+    assert!(matches!(
+        poor.run(
+            recording(&[("BTC/USD", "99", "100")]),
+            &mut BuyTheDip::default()
+        )
+        .await,
+        Err(KrakenError::OrderError { .. })
+    ));
+}
@@ -0,0 +1,43 @@
+use onise::testkit::MockKraken;
+
+#[tokio::test]
+async fn test_balance_deltas_correlated_with_ledger() {
+    use futures_util::StreamExt;
+    use onise::balance_watch::{BalanceCause, BalanceWatcher};
+    use onise::ws_models::WsBalancesMessage;
+
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+    let message = |balances: &[(&str, &str)]| WsBalancesMessage {
+        channel: "balances".to_string(),
+        balances: balances
+            .iter()
+            .map(|(asset, balance)| (asset.to_string(), balance.to_string()))
+            .collect(),
+    };
+    let feed = futures_util::stream::iter(vec![
+        message(&[("ZGBP", "459592.4661"), ("XXBT", "1.0")]),
+        // Same amounts written differently: no change
+        message(&[("ZGBP", "459592.46610"), ("XXBT", "1")]),
+        message(&[("ZGBP", "459567.9171")]),
+        message(&[("XXBT", "0.5")]),
+    ]);
+    let deltas: Vec<_> = BalanceWatcher::new(kraken.authenticated_client())
+        .watch(feed)
+        .collect()
+        .await;
+
+    assert_eq!(deltas.len(), 2, "{deltas:?}");
+    assert_eq!(deltas[0].asset, "ZGBP");
+    assert_eq!(deltas[0].previous, "459592.46610");
+    assert!((deltas[0].change().unwrap() + 24.549).abs() < 1e-6);
+    match &deltas[0].cause {
+        BalanceCause::Ledger { id, entry } => {
+            assert_eq!(id, "L4UESK-KG3EQ-UFO4T5");
+            assert_eq!(entry.refid, "TJKLXX-PGMUI-4NTLXU");
+        }
+        BalanceCause::Unexplained => panic!("expected the trade ledger entry"),
+    }
+    assert_eq!(deltas[1].asset, "XXBT");
+    assert!(deltas[1].is_unexplained());
+}
@@ -0,0 +1,93 @@
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use onise::bars::{self, BarBuilder, BarSpec};
+use onise::ws_models::{TradeData, WsTradesMessage};
+
+fn trade(time: u64, price: &str, quantity: &str) -> TradeData {
+    TradeData {
+        price: price.to_string(),
+        quantity: quantity.to_string(),
+        time,
+        side: "buy".to_string(),
+    }
+}
+
+fn message(symbol: &str, trades: Vec<TradeData>) -> WsTradesMessage {
+    WsTradesMessage {
+        channel: "trade".to_string(),
+        symbol: symbol.to_string(),
+        trades,
+    }
+}
+
+#[test]
+fn test_volume_bars_split_trades_at_the_threshold() {
+    let mut builder = BarBuilder::new("BTC/USD", BarSpec::Volume(1.0));
+    assert!(builder.push(&trade(1, "100", "0.4")).is_empty());
+    let closed = builder.push(&trade(2, "110", "1.8"));
+    assert_eq!(closed.len(), 2);
+    assert_eq!(closed[0].volume, 1.0);
+    assert_eq!(
+        (closed[0].open, closed[0].high, closed[0].close),
+        (100.0, 110.0, 110.0)
+    );
+    assert!((closed[0].vwap() - 106.0).abs() < 1e-9);
+    assert_eq!(closed[1].volume, 1.0);
+    assert_eq!(closed[1].open, 110.0);
+    let rest = builder.flush().unwrap();
+    assert!((rest.volume - 0.2).abs() < 1e-9);
+    assert!(builder.flush().is_none());
+
+    // Amounts that only add up with rounding still close the bar
+    let mut builder = BarBuilder::new("BTC/USD", BarSpec::Volume(0.3));
+    builder.push(&trade(1, "100", "0.1"));
+    builder.push(&trade(1, "100", "0.1"));
+    assert_eq!(builder.push(&trade(1, "100", "0.1")).len(), 1);
+    assert!(builder.flush().is_none());
+}
+
+#[test]
+fn test_dollar_bars_close_on_notional() {
+    let mut builder = BarBuilder::new("ETH/USD", BarSpec::Dollar(1000.0));
+    assert!(builder.push(&trade(1, "200", "3")).is_empty());
+    let closed = builder.push(&trade(2, "250", "2"));
+    assert_eq!(closed.len(), 1);
+    assert_eq!(closed[0].notional, 1000.0);
+    assert_eq!(closed[0].volume, 3.0 + 400.0 / 250.0);
+    assert!((builder.flush().unwrap().notional - 100.0).abs() < 1e-9);
+}
+
+#[tokio::test]
+async fn test_time_bars_per_symbol() {
+    let feed = futures_util::stream::iter(vec![
+        message(
+            "BTC/USD",
+            vec![trade(60, "100", "1"), trade(119, "90", "1")],
+        ),
+        message("ETH/USD", vec![trade(61, "10", "5")]),
+        // An empty minute yields no bar
+        message("BTC/USD", vec![trade(185, "95", "2")]),
+    ]);
+    let bars: Vec<_> = bars::time_bars(feed, Duration::from_secs(60))
+        .collect()
+        .await;
+
+    assert_eq!(bars.len(), 3, "{bars:?}");
+    let btc = &bars[0];
+    assert_eq!(
+        (btc.symbol.as_str(), btc.start, btc.end),
+        ("BTC/USD", 60, 119)
+    );
+    assert_eq!(
+        (btc.open, btc.low, btc.close, btc.trades),
+        (100.0, 90.0, 90.0, 2)
+    );
+    // The bars still open when the feed ends follow, in no particular order
+    let mut open: Vec<_> = bars[1..]
+        .iter()
+        .map(|b| (b.symbol.as_str(), b.start))
+        .collect();
+    open.sort();
+    assert_eq!(open, [("BTC/USD", 180), ("ETH/USD", 60)]);
+}
@@ -0,0 +1,40 @@
+use onise::testkit::MockKraken;
+
+#[tokio::test]
+async fn test_mock_clock_drives_token_and_cache_expiry() {
+    use std::time::Duration;
+
+    use onise::clock::MockClock;
+    use onise::ws_token::TokenManager;
+
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+    let clock = MockClock::new();
+    let calls = |requests: &[wiremock::Request], path: &str| {
+        requests.iter().filter(|r| r.url.path() == path).count()
+    };
+
+    // The sample token lives 900s; with a 60s margin it is replaced after 840s.
+    let tokens = TokenManager::new(kraken.authenticated_client().with_clock(clock.clone()));
+    tokens.token().await.expect("token");
+    clock.advance(Duration::from_secs(839));
+    tokens.token().await.expect("cached token");
+    let path = "/0/private/GetWebSocketsToken";
+    assert_eq!(calls(&kraken.received_requests().await, path), 1);
+    clock.advance(Duration::from_secs(1));
+    tokens.token().await.expect("fresh token");
+    assert_eq!(calls(&kraken.received_requests().await, path), 2);
+
+    let client = kraken
+        .public_client()
+        .with_metadata_cache(Duration::from_secs(60))
+        .with_clock(clock.clone());
+    let p: &[(&str, &str)] = &[];
+    client.get_asset_info(p).await.expect("Assets");
+    clock.advance(Duration::from_secs(59));
+    client.get_asset_info(p).await.expect("cached Assets");
+    assert_eq!(calls(&kraken.received_requests().await, "/0/public/Assets"), 1);
+    clock.advance(Duration::from_secs(1));
+    client.get_asset_info(p).await.expect("expired Assets");
+    assert_eq!(calls(&kraken.received_requests().await, "/0/public/Assets"), 2);
+}
@@ -0,0 +1,39 @@
+//! Helpers shared by the integration test crates, each pulling them in with
+//! `mod common;`. Not every crate uses every helper.
+#![allow(dead_code)]
+
+use onise::simulated::SimulatedExchange;
+use onise::ws_models::WsTickerMessage;
+
+/// The example secret from Kraken's signing documentation.
+pub const API_SECRET: &str =
+    "kQH5HW/8p1uGOVjbgWA7FunAmGO8lsSUXNsu3eow76sz84Q18fWxnyRzBHCd3pd5nE9qa99HAZtuZuj6F1huXg==";
+
+/// A ticker quoting `bid`/`ask` at the touch, with `1` in every other field.
+pub fn ticker(symbol: &str, bid: &str, ask: &str) -> WsTickerMessage {
+    WsTickerMessage {
+        channel: "ticker".to_string(),
+        symbol: symbol.to_string(),
+        best_ask_price: ask.to_string(),
+        best_ask_quantity: "1".to_string(),
+        best_bid_price: bid.to_string(),
+        best_bid_quantity: "1".to_string(),
+        last_trade_price: bid.to_string(),
+        last_trade_quantity: "1".to_string(),
+        volume_24h: "1".to_string(),
+        vwap_24h: "1".to_string(),
+        trades_24h: 1,
+        low_24h: "1".to_string(),
+        high_24h: "1".to_string(),
+        open_24h: "1".to_string(),
+    }
+}
+
+/// A paper venue quoting XBTUSD at 30000/30010, with `usd` ZUSD to spend.
+pub fn xbtusd_exchange(usd: f64) -> SimulatedExchange {
+    let sim = SimulatedExchange::new()
+        .with_pair("XBTUSD", "XXBT", "ZUSD")
+        .with_balance("ZUSD", usd);
+    sim.set_quote("XBTUSD", 30_000.0, 30_010.0).unwrap();
+    sim
+}
@@ -0,0 +1,72 @@
+use onise::error::KrakenError;
+use onise::testkit::MockKraken;
+
+#[tokio::test]
+async fn test_convert_and_value_across_pairs() {
+    use serde_json::json;
+
+    let pair = |base: &str, quote: &str| {
+        json!({
+            "altname": format!("{base}{quote}"), "aclass_base": "currency", "base": base,
+            "aclass_quote": "currency", "quote": quote, "lot": "unit", "pair_decimals": 4,
+            "lot_decimals": 8, "lot_multiplier": 1, "fees": [[0, 0.26]]
+        })
+    };
+    let ticker = |bid: &str, ask: &str| {
+        json!({
+            "a": [ask, "1", "1.000"], "b": [bid, "1", "1.000"], "c": [bid, "0.1"],
+            "v": ["0", "0"], "p": [bid, bid], "t": [0, 0], "l": [bid, bid],
+            "h": [ask, ask], "o": bid
+        })
+    };
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+    kraken
+        .mock_result(
+            "/0/public/AssetPairs",
+            json!({
+                "DOTUSD": pair("DOT", "ZUSD"),
+                "ZEURZUSD": pair("ZEUR", "ZUSD"),
+                "XXBTZUSD": pair("XXBT", "ZUSD"),
+                "XXBTZUSD.d": pair("XXBT", "ZUSD"),
+            }),
+        )
+        .await;
+    kraken
+        .mock_result(
+            "/0/public/Ticker",
+            json!({
+                "DOTUSD": ticker("5.0000", "5.0100"),
+                "ZEURZUSD": ticker("1.0800", "1.1000"),
+            }),
+        )
+        .await;
+    kraken
+        .mock_result(
+            "/0/private/Balance",
+            json!({ "DOT": "10", "ZEUR": "100", "ZUSD": "50", "FOO": "3", "XXBT": "0" }),
+        )
+        .await;
+    let c = kraken.authenticated_client();
+
+    // DOT → USD at the bid, then USD → EUR at the ask
+    let conversion = c.convert("DOT", "EUR", 10.0).await.expect("convert");
+    assert_eq!(conversion.route.pairs(), ["DOTUSD", "ZEURZUSD"]);
+    assert!(conversion.route.legs[0].sell);
+    assert!(!conversion.route.legs[1].sell);
+    assert!((conversion.rate - 5.0 / 1.1).abs() < 1e-12);
+    assert!((conversion.amount - 50.0 / 1.1).abs() < 1e-9);
+
+    let same = c.convert("ZUSD", "USD", 7.0).await.expect("identity");
+    assert!(same.route.legs.is_empty());
+    assert_eq!(same.amount, 7.0);
+
+    let err = c.convert("DOT", "FOO", 1.0).await.unwrap_err();
+    assert!(matches!(err, KrakenError::InvalidUsage(_)), "{err:?}");
+
+    // 10 DOT at 5 + 100 EUR at 1.08 + 50 USD
+    let value = c.portfolio_value("USD").await.expect("value");
+    assert!((value.total - 208.0).abs() < 1e-9, "{value:?}");
+    assert!((value.by_asset["EUR"] - 108.0).abs() < 1e-9);
+    assert_eq!(value.unpriced, ["FOO"]);
+}
@@ -0,0 +1,112 @@
+use onise::error::KrakenError;
+use onise::testkit::{ErrorClass, MockKraken};
+
+#[tokio::test]
+async fn test_deadmans_switch_refreshes_and_reports_failures() {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+    let c = kraken.authenticated_client();
+    let no_op = |_: &KrakenError, _: u32| {};
+
+    assert!(matches!(
+        c.start_deadmans_switch(Duration::from_secs(60), Duration::from_secs(60), no_op).await,
+        Err(KrakenError::InvalidUsage(_))
+    ));
+
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let switch = c
+        .start_deadmans_switch(Duration::from_secs(60), Duration::from_millis(50), {
+            let seen = seen.clone();
+            move |e: &KrakenError, n: u32| seen.lock().unwrap().push((e.to_string(), n))
+        })
+        .await
+        .expect("armed");
+    tokio::time::sleep(Duration::from_millis(180)).await;
+    let timeouts: Vec<String> = kraken
+        .received_requests()
+        .await
+        .iter()
+        .map(|r| String::from_utf8(r.body.clone()).unwrap())
+        .collect();
+    assert!(timeouts.len() >= 3, "{timeouts:?}");
+    assert!(timeouts.iter().all(|b| b.ends_with("&timeout=60")));
+    assert!(seen.lock().unwrap().is_empty());
+
+    // Kraken starts rejecting the refreshes
+    kraken.server().reset().await;
+    kraken
+        .mock_error("/0/private/CancelAllOrdersAfter", ErrorClass::Service)
+        .await;
+    tokio::time::sleep(Duration::from_millis(130)).await;
+    assert!(switch.is_running());
+    assert!(switch.consecutive_failures() >= 2);
+    assert_eq!(seen.lock().unwrap()[1].1, 2);
+
+    kraken.server().reset().await;
+    kraken.mock_all_success().await;
+    switch.disarm().await.expect("disarmed");
+    let received = kraken.received_requests().await;
+    let last = String::from_utf8(received.last().unwrap().body.clone()).unwrap();
+    assert!(last.ends_with("&timeout=0"), "{last}");
+}
+
+#[tokio::test]
+async fn test_persistent_deadmans_switch_saves_and_resumes() {
+    use onise::clock::MockClock;
+    use onise::deadman::DeadmanState;
+    use onise::state::{MemoryStateStore, StateStore};
+    use std::sync::Arc;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+    let clock = MockClock::new();
+    clock.set_system_time(UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+    let c = kraken.authenticated_client().with_clock(clock);
+    let store = MemoryStateStore::new();
+    let no_op = |_: &KrakenError, _: u32| {};
+
+    assert!(c
+        .resume_deadmans_switch(no_op, Arc::new(store.clone()), "deadman")
+        .await
+        .unwrap()
+        .is_none());
+    let switch = c
+        .start_persistent_deadmans_switch(
+            Duration::from_secs(60),
+            Duration::from_secs(20),
+            no_op,
+            Arc::new(store.clone()),
+            "deadman",
+        )
+        .await
+        .expect("armed");
+    let saved: DeadmanState =
+        serde_json::from_slice(&store.load("deadman").unwrap().unwrap()).unwrap();
+    assert_eq!(
+        saved,
+        DeadmanState {
+            timeout_secs: 60,
+            refresh_interval_ms: 20_000,
+            armed_until: 1_700_000_060,
+        }
+    );
+    // The process dies without disarming; the next one picks it back up
+    drop(switch);
+
+    let resumed = c
+        .resume_deadmans_switch(no_op, Arc::new(store.clone()), "deadman")
+        .await
+        .unwrap()
+        .expect("resumed");
+    assert!(resumed.is_running());
+    let received = kraken.received_requests().await;
+    let last = String::from_utf8(received.last().unwrap().body.clone()).unwrap();
+    assert!(last.ends_with("&timeout=60"), "{last}");
+
+    resumed.disarm().await.expect("disarmed");
+    assert_eq!(store.load("deadman").unwrap(), None);
+}
@@ -0,0 +1,84 @@
+use onise::testkit::MockKraken;
+
+#[tokio::test]
+async fn test_earn_auto_compounder() {
+    use onise::earn::{AutoCompounder, CompoundEvent, CompoundTarget};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    let kraken = MockKraken::start().await;
+    kraken
+        .mock_result("/0/private/Balance", serde_json::json!({ "DOT": "25.5" }))
+        .await;
+    kraken.mock_all_success().await;
+    let audit = Arc::new(Mutex::new(Vec::new()));
+    let compounder = AutoCompounder::new(kraken.authenticated_client())
+        .with_target(CompoundTarget::new("DOT", "polkadot-staked").with_reserve(0.5))
+        .with_target(CompoundTarget::new("XBT", "bitcoin-staked"))
+        .with_event_handler({
+            let audit = audit.clone();
+            move |event: &CompoundEvent| audit.lock().unwrap().push(event.clone())
+        });
+
+    let planned = compounder.clone().with_dry_run(true).run_once().await.unwrap();
+    assert_eq!(
+        planned[0],
+        CompoundEvent::WouldAllocate {
+            asset: "DOT".into(),
+            method: "polkadot-staked".into(),
+            amount: "25".into(),
+        }
+    );
+    assert!(matches!(planned[1], CompoundEvent::Skipped { .. }));
+    assert!(kraken
+        .received_requests()
+        .await
+        .iter()
+        .all(|r| r.url.path() != "/0/private/Staking/Stake"));
+
+    let events = compounder.run_once().await.unwrap();
+    assert!(
+        matches!(&events[0], CompoundEvent::Allocated { amount, txid, .. } if amount == "25" && txid == "BOG5AE5-KSCNR4-VPNPEV"),
+        "{events:?}"
+    );
+    let stake = kraken
+        .received_requests()
+        .await
+        .into_iter()
+        .find(|r| r.url.path() == "/0/private/Staking/Stake")
+        .unwrap();
+    let body = String::from_utf8(stake.body).unwrap();
+    assert!(body.contains("asset=DOT&amount=25&method=polkadot-staked"), "{body}");
+    assert_eq!(audit.lock().unwrap().len(), 4);
+
+    // Locked strategies need an explicit allowance
+    kraken.server().reset().await;
+    kraken
+        .mock_result("/0/private/Balance", serde_json::json!({ "DOT": "25.5" }))
+        .await;
+    kraken
+        .mock_result(
+            "/0/private/Staking/ListStakingProducts",
+            serde_json::json!({ "products": [{
+                "asset": "DOT", "title": "Polkadot", "apy": "15.00", "method": "polkadot-staked",
+                "min_amount": "30", "max_amount": null, "lock_time": 86400, "interval": "weekly"
+            }]}),
+        )
+        .await;
+    let events = compounder.clone().with_dry_run(true).run_once().await.unwrap();
+    assert!(
+        matches!(&events[0], CompoundEvent::Skipped { reason, .. } if reason.contains("locks")),
+        "{events:?}"
+    );
+    let events = compounder
+        .with_dry_run(true)
+        .with_max_lock_time(Duration::from_secs(86400))
+        .run_once()
+        .await
+        .unwrap();
+    // ...and the balance above the reserve is under the minimum
+    assert!(
+        matches!(&events[0], CompoundEvent::Skipped { reason, .. } if reason.contains("minimum")),
+        "{events:?}"
+    );
+}
@@ -0,0 +1,82 @@
+use onise::environment::{
+    Endpoints, Environment, PRODUCTION_REST_URL, PRODUCTION_WS_AUTH_URL, PRODUCTION_WS_PUBLIC_URL,
+};
+use onise::ws_client::KrakenWsClient;
+use onise::PublicClient;
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+use tokio_tungstenite::accept_async;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[test]
+fn test_production_and_custom_urls() {
+    let production = Environment::default();
+    assert_eq!(production, Environment::Production);
+    assert_eq!(production.rest_url(), PRODUCTION_REST_URL);
+    assert_eq!(production.ws_public_url(), PRODUCTION_WS_PUBLIC_URL);
+    assert_eq!(production.ws_auth_url(), PRODUCTION_WS_AUTH_URL);
+    assert_eq!(production.endpoints(), Endpoints::production());
+
+    // Overriding REST alone keeps the production sockets
+    let proxied = Environment::custom_rest("http://proxy.internal:8080");
+    assert_eq!(proxied.rest_url(), "http://proxy.internal:8080");
+    assert_eq!(proxied.ws_public_url(), PRODUCTION_WS_PUBLIC_URL);
+    assert_eq!(proxied.ws_auth_url(), PRODUCTION_WS_AUTH_URL);
+
+    let endpoints: Endpoints = serde_json::from_str(
+        r#"{"rest": "http://a", "ws_public": "ws://b", "ws_auth": "ws://c"}"#,
+    )
+    .unwrap();
+    let custom = Environment::Custom(endpoints.clone());
+    assert_eq!(
+        (custom.rest_url(), custom.ws_public_url(), custom.ws_auth_url()),
+        ("http://a", "ws://b", "ws://c")
+    );
+    assert_eq!(custom.endpoints(), endpoints);
+}
+
+#[tokio::test]
+async fn test_clients_connect_to_the_environment_urls() {
+    let rest = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/0/public/Time"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            r#"{"error":[],"result":{"unixtime":1672531199,"rfc1123":""}}"#,
+            "application/json",
+        ))
+        .mount(&rest)
+        .await;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let ws_addr = listener.local_addr().unwrap();
+    let (accepted_tx, accepted) = oneshot::channel();
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let ws = accept_async(stream).await.unwrap();
+        let _ = accepted_tx.send(());
+        // Hold the connection open until the test ends
+        std::future::pending::<()>().await;
+        drop(ws);
+    });
+
+    let environment = Environment::Custom(Endpoints {
+        rest: rest.uri(),
+        ws_public: format!("ws://{ws_addr}"),
+        ws_auth: "ws://127.0.0.1:1".to_string(),
+    });
+
+    // Both clients take their URLs from the one Environment
+    let client = PublicClient::builder()
+        .environment(environment.clone())
+        .build()
+        .unwrap();
+    assert_eq!(client.base_url(), rest.uri());
+    assert_eq!(client.get_server_time().await.unwrap().unixtime, 1672531199);
+    assert_eq!(rest.received_requests().await.unwrap().len(), 1);
+
+    let ws = KrakenWsClient::connect_public(&environment).await.unwrap();
+    accepted.await.expect("public socket reached the custom URL");
+    assert!(ws.is_connected());
+    assert!(KrakenWsClient::connect_authenticated(&environment).await.is_err());
+}
@@ -0,0 +1,227 @@
+use std::sync::{Arc, Mutex};
+
+use onise::error::KrakenError;
+use onise::events::{EventDispatcher, NotifyingExchange, OrderEvent};
+use onise::exchange::{ExchangeClient, OrderAmendment, OrderRequest, Side};
+use onise::ws_models::{ExecutionData, WsExecutionsMessage};
+
+mod common;
+use common::xbtusd_exchange;
+
+fn execution(order_id: &str) -> ExecutionData {
+    ExecutionData {
+        symbol: "BTC/USD".to_string(),
+        order_id: order_id.to_string(),
+        exec_id: format!("E-{order_id}"),
+        quantity: "0.1".to_string(),
+        price: "30000".to_string(),
+        side: "buy".to_string(),
+        time: 0,
+        cost: "3000".to_string(),
+        fee: "7.8".to_string(),
+        fee_currency: "USD".to_string(),
+        liquidity: "taker".to_string(),
+    }
+}
+
+#[tokio::test]
+async fn test_order_events_reach_the_sink_in_order() {
+    let delivered = Arc::new(Mutex::new(Vec::new()));
+    let sink = {
+        let delivered = delivered.clone();
+        move |event: OrderEvent| {
+            let delivered = delivered.clone();
+            async move {
+                // A failing delivery is logged and doesn't stop later ones
+                if matches!(event, OrderEvent::Amended(_)) {
+                    return Err(KrakenError::InvalidUsage("webhook down".to_string()));
+                }
+                delivered.lock().unwrap().push(event);
+                Ok(())
+            }
+        }
+    };
+    let (events, delivery) = EventDispatcher::spawn(sink);
+    let exchange = NotifyingExchange::new(xbtusd_exchange(100_000.0), events.clone());
+
+    let resting = OrderRequest::limit("XBTUSD", Side::Buy, "0.1", "20000");
+    let placed = exchange.place_order(&resting).await.unwrap();
+    exchange
+        .amend_order(&OrderAmendment::new(&placed.order_id).with_volume("0.2"))
+        .await
+        .unwrap();
+    exchange.cancel_order(&placed.order_id).await.unwrap();
+    let too_big = OrderRequest::market("XBTUSD", Side::Buy, "100");
+    exchange.place_order(&too_big).await.unwrap_err();
+    // A failed cancel isn't an event
+    exchange.cancel_order("missing").await.unwrap_err();
+
+    let fills = futures_util::stream::iter([WsExecutionsMessage {
+        channel: "executions".to_string(),
+        executions: vec![execution("O1"), execution("O2")],
+    }]);
+    events.follow_executions(fills).await.unwrap();
+
+    drop((events, exchange));
+    delivery.await.unwrap();
+    let delivered = delivered.lock().unwrap();
+    assert_eq!(delivered.len(), 5, "{delivered:?}");
+    assert_eq!(
+        delivered[0],
+        OrderEvent::Placed {
+            order: resting,
+            order_id: placed.order_id.clone(),
+        }
+    );
+    assert_eq!(delivered[1].order_id(), Some(placed.order_id.as_str()));
+    assert!(matches!(delivered[1], OrderEvent::Cancelled { .. }));
+    assert!(
+        matches!(&delivered[2], OrderEvent::Rejected { order, .. } if *order == too_big),
+        "{:?}",
+        delivered[2]
+    );
+    assert_eq!(delivered[3], OrderEvent::Filled(execution("O1")));
+    assert_eq!(delivered[4].order_id(), Some("O2"));
+}
+
+#[tokio::test]
+async fn test_fills_normalize_quantities_and_track_remaining() {
+    use futures_util::{stream, StreamExt};
+    use onise::fills::FillTracker;
+
+    let fill = |order_id: &str, exec_id: &str, quantity: &str, price: &str| ExecutionData {
+        order_id: order_id.to_string(),
+        exec_id: exec_id.to_string(),
+        quantity: quantity.to_string(),
+        price: price.to_string(),
+        ..execution(order_id)
+    };
+    let tracker = FillTracker::new();
+    tracker.set_quantity_decimals("BTC/USD", 4);
+    tracker.expect_order("O1", 0.3);
+
+    let messages = vec![
+        WsExecutionsMessage {
+            channel: "executions".to_string(),
+            executions: vec![
+                fill("O1", "E1", "0.1", "30000"),
+                fill("O1", "E2", "0.1", "30300"),
+            ],
+        },
+        WsExecutionsMessage {
+            channel: "executions".to_string(),
+            executions: vec![
+                // Sent again after a resubscribe
+                fill("O1", "E1", "0.1", "30000"),
+                fill("O2", "E3", "0.25", "29000"),
+                fill("O1", "E4", "0.10000000004", "30600"),
+            ],
+        },
+    ];
+    let fills: Vec<_> = tracker.fills(stream::iter(messages)).collect().await;
+    assert_eq!(fills.len(), 4);
+
+    let first = &fills[0];
+    assert_eq!(
+        (first.qty, first.filled, first.remaining),
+        (0.1, 0.1, Some(0.2))
+    );
+    assert_eq!(first.avg_price, 30_000.0);
+    assert_eq!((first.fee, first.fee_currency.as_str()), (7.8, "USD"));
+    assert_eq!(first.side, Side::Buy);
+    assert!((fills[1].avg_price - 30_150.0).abs() < 1e-9);
+    assert_eq!(fills[1].remaining, Some(0.1));
+
+    // Unknown size: no remaining
+    assert_eq!(
+        (fills[2].order_id.as_str(), fills[2].remaining),
+        ("O2", None)
+    );
+
+    // Rounded to 4 decimals, the last fill completes the order exactly
+    let last = &fills[3];
+    assert_eq!(
+        (last.qty, last.filled, last.remaining),
+        (0.1, 0.3, Some(0.0))
+    );
+    assert!(last.is_complete());
+    assert!((last.avg_price - 30_300.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_parent_execution_reports_slippage_fees_and_participation() {
+    use onise::execution_report::ParentExecution;
+    use onise::ws_models::{TradeData, WsTradesMessage};
+
+    let fill = |order_id: &str, exec_id: &str, price: &str, time: u64| ExecutionData {
+        exec_id: exec_id.to_string(),
+        price: price.to_string(),
+        time,
+        ..execution(order_id)
+    };
+    let trades = |symbol: &str, quantities: &[&str]| WsTradesMessage {
+        channel: "trade".to_string(),
+        symbol: symbol.to_string(),
+        trades: quantities
+            .iter()
+            .map(|quantity| TradeData {
+                price: "30000".to_string(),
+                quantity: quantity.to_string(),
+                time: 0,
+                side: "buy".to_string(),
+            })
+            .collect(),
+    };
+
+    let mut parent = ParentExecution::new("TWAP-1", "BTC/USD", Side::Buy, 0.4, 30_000.0);
+    let report = parent.report();
+    assert_eq!((report.filled, report.avg_fill_price), (0.0, None));
+
+    parent.add_child("C1");
+    assert!(parent
+        .apply_execution(&fill("C1", "E1", "30010", 1))
+        .unwrap());
+    parent.on_mid(30_100.0);
+    parent.add_child("C2");
+    assert!(parent
+        .apply_execution(&fill("C2", "E2", "30110", 2))
+        .unwrap());
+    // Repeated, and not a child of this parent
+    assert!(!parent
+        .apply_execution(&fill("C1", "E1", "30010", 1))
+        .unwrap());
+    assert!(!parent.apply_execution(&fill("O9", "E3", "1", 3)).unwrap());
+
+    parent
+        .on_market_trades(&trades("BTC/USD", &["0.3", "0.5"]))
+        .unwrap();
+    parent
+        .on_market_trades(&trades("ETH/USD", &["10"]))
+        .unwrap();
+
+    let report = parent.report();
+    assert_eq!((report.child_orders, report.fills), (2, 2));
+    assert!((report.filled - 0.2).abs() < 1e-12);
+    assert!((report.avg_fill_price.unwrap() - 30_060.0).abs() < 1e-9);
+    // Paid 60 over the arrival mid, 10 over the mid at each fill
+    assert!((report.arrival_slippage_bps.unwrap() - 20.0).abs() < 1e-9);
+    let mid_slippage = 10.0 / 30_050.0 * 10_000.0;
+    assert!((report.mid_slippage_bps.unwrap() - mid_slippage).abs() < 1e-9);
+    assert!((report.fee_total - 15.6).abs() < 1e-9);
+    assert!((report.participation_rate.unwrap() - 0.25).abs() < 1e-9);
+    assert_eq!(
+        (report.first_fill_time, report.last_fill_time),
+        (Some(1), Some(2))
+    );
+    assert!(report
+        .to_json()
+        .unwrap()
+        .contains("\"parent_id\": \"TWAP-1\""));
+
+    // A sell filled below the arrival mid slipped too
+    let mut sell = ParentExecution::new("ICE-1", "BTC/USD", Side::Sell, 0.1, 30_000.0);
+    sell.add_child("C3");
+    sell.apply_execution(&fill("C3", "E4", "29970", 4)).unwrap();
+    let slippage = sell.report().arrival_slippage_bps.unwrap();
+    assert!((slippage - 10.0).abs() < 1e-9);
+}
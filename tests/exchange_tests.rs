@@ -0,0 +1,38 @@
+use onise::testkit::MockKraken;
+
+#[tokio::test]
+async fn test_rest_client_as_exchange_client() {
+    use onise::exchange::{ExchangeClient, OrderAmendment, OrderRequest, Side};
+
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+    let c = kraken.authenticated_client();
+
+    let order = OrderRequest::limit("XBTUSD", Side::Buy, "1.25", "27500").with_cl_ord_id("c-1");
+    let placed = c.place_order(&order).await.expect("AddOrder");
+    assert_eq!(placed.order_id, "OU22CG-KLAF2-FWUDD7");
+    ExchangeClient::cancel_order(&c, &placed.order_id)
+        .await
+        .expect("CancelOrder");
+    let amendment = OrderAmendment::new(&placed.order_id).with_limit_price("27600");
+    ExchangeClient::amend_order(&c, &amendment)
+        .await
+        .expect("AmendOrder");
+
+    let positions = c.positions().await.expect("OpenPositions");
+    assert_eq!(positions[0].pair, "XXBTZUSD");
+    assert_eq!(positions[0].side, Side::Buy);
+    assert!(c.balances().await.expect("Balance").contains_key("ZUSD"));
+
+    let bodies: Vec<String> = kraken
+        .received_requests()
+        .await
+        .iter()
+        .map(|r| String::from_utf8(r.body.clone()).unwrap())
+        .collect();
+    assert!(bodies[0].ends_with(
+        "&pair=XBTUSD&type=buy&volume=1.25&ordertype=limit&price=27500&cl_ord_id=c-1"
+    ));
+    assert!(bodies[1].ends_with("&txid=OU22CG-KLAF2-FWUDD7"));
+    assert!(bodies[2].ends_with("&txid=OU22CG-KLAF2-FWUDD7&limit_price=27600"));
+}
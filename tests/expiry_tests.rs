@@ -0,0 +1,77 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use onise::error::KrakenError;
+use onise::expiry::ExpireTime;
+
+#[test]
+fn test_expire_time_forms() {
+    let in_an_hour = ExpireTime::after(Duration::from_millis(3_600_500));
+    assert_eq!(in_an_hour.to_expiretm(), "+3600");
+
+    let new_year = UNIX_EPOCH + Duration::from_secs(1_704_067_199);
+    let absolute = ExpireTime::at(new_year);
+    assert_eq!(absolute.to_expiretm(), "1704067199");
+    assert_eq!(absolute.to_expire_time(SystemTime::now()).unwrap(), "2023-12-31T23:59:59Z");
+
+    let server_now = UNIX_EPOCH + Duration::from_secs(1_704_063_599);
+    assert_eq!(in_an_hour.to_expire_time(server_now).unwrap(), "2023-12-31T23:59:59Z");
+
+    // Out of range expiries are refused rather than rendered as 1970
+    let forever = ExpireTime::after(Duration::MAX);
+    assert!(matches!(forever.resolve(server_now), Err(KrakenError::InvalidUsage(_))));
+    assert!(matches!(forever.to_expire_time(server_now), Err(KrakenError::InvalidUsage(_))));
+    let before_epoch = ExpireTime::at(UNIX_EPOCH - Duration::from_secs(1));
+    assert!(before_epoch.to_expire_time(server_now).is_err());
+    let far_future = ExpireTime::at(UNIX_EPOCH + Duration::from_secs(1 << 40));
+    assert!(far_future.to_expire_time(server_now).is_err());
+
+    let from_time: ExpireTime = time::OffsetDateTime::from_unix_timestamp(1_704_067_199)
+        .unwrap()
+        .into();
+    assert_eq!(from_time, absolute);
+}
+
+#[test]
+fn test_expire_time_is_validated_against_server_time() {
+    let server_now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+    assert!(ExpireTime::after(Duration::from_secs(5)).validate(server_now).is_ok());
+    assert!(ExpireTime::after(Duration::from_secs(4)).validate(server_now).is_err());
+    assert!(ExpireTime::at(server_now + Duration::from_secs(60))
+        .validate(server_now)
+        .is_ok());
+    // Fine by a local clock running two minutes fast, already past for Kraken
+    let err = ExpireTime::at(server_now - Duration::from_secs(60))
+        .validate(server_now)
+        .unwrap_err();
+    assert!(matches!(&err, KrakenError::InvalidUsage(message) if message.contains("ahead of Kraken's")));
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn test_expire_time_from_chrono() {
+    let new_year = chrono::DateTime::from_timestamp(1_704_067_199, 0).unwrap();
+    assert_eq!(ExpireTime::from(new_year).to_expiretm(), "1704067199");
+}
+
+#[cfg(feature = "testkit")]
+#[tokio::test]
+async fn test_check_expire_time_uses_server_clock() {
+    use onise::testkit::MockKraken;
+    use onise::expiry::ExpireTime;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+    let c = kraken.public_client();
+    // The fixture's server time is 1688669448
+    let server_now = UNIX_EPOCH + Duration::from_secs(1_688_669_448);
+
+    let ok = ExpireTime::at(server_now + Duration::from_secs(30));
+    c.check_expire_time(&ok).await.expect("in the future for Kraken");
+    let too_soon = ExpireTime::at(server_now + Duration::from_secs(2));
+    assert!(matches!(
+        c.check_expire_time(&too_soon).await,
+        Err(KrakenError::InvalidUsage(_))
+    ));
+}
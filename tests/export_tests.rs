@@ -0,0 +1,94 @@
+use onise::testkit::{ErrorClass, MockKraken};
+
+#[tokio::test]
+async fn test_export_trades_ndjson() {
+    use onise::params::TradesHistoryParams;
+
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+    let c = kraken.authenticated_client();
+
+    let mut out = Vec::new();
+    let filters = TradesHistoryParams::new().with_trade_type("no position");
+    let written = c
+        .export_trades_ndjson(&mut out, &filters)
+        .await
+        .expect("export");
+    assert_eq!(written, 1);
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.ends_with('\n'));
+    let line: serde_json::Value = serde_json::from_str(out.trim_end()).unwrap();
+    assert!(line["id"].is_string());
+    assert!(line["ordertxid"].is_string());
+
+    kraken.server().reset().await;
+    kraken.mock_error("/0/private/TradesHistory", ErrorClass::Service).await;
+    assert!(c
+        .export_trades_ndjson(&mut Vec::new(), &filters)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_export_progress_reports_rows_until_finished() {
+    use futures_util::StreamExt;
+    use onise::export::ExportProgress;
+    use onise::funding::WatchBackoff;
+    use std::time::Duration;
+
+    let backoff = WatchBackoff {
+        initial: Duration::from_millis(10),
+        max: Duration::from_millis(20),
+        factor: 2,
+    };
+    let status = |status: &str, rows: Option<u64>| {
+        serde_json::json!({"reports": [{
+            "id": "VSKC", "report": "trades", "format": "CSV", "description": "my_trades_1",
+            "status": status, "createdtm": 1688669085, "starttm": 1688669093,
+            "finishtm": null, "totalrows": rows, "refid": null
+        }]})
+    };
+    let kraken = MockKraken::start().await;
+    let c = kraken.authenticated_client();
+    let mut progress = c.export_progress("trades", "VSKC").with_backoff(backoff);
+
+    // Not listed yet: keep polling
+    kraken
+        .mock_result("/0/private/ExportStatus", serde_json::json!({"reports": []}))
+        .await;
+    let first = tokio::time::timeout(Duration::from_millis(100), progress.next()).await;
+    assert!(first.is_err());
+
+    for (state, rows, expected) in [
+        ("Queued", None, ExportProgress::Queued),
+        ("Processing", Some(10), ExportProgress::Processing { rows: Some(10) }),
+        ("Processing", Some(50), ExportProgress::Processing { rows: Some(50) }),
+        ("Processed", Some(80), ExportProgress::Finished { rows: Some(80) }),
+    ] {
+        kraken.server().reset().await;
+        kraken
+            .mock_result("/0/private/ExportStatus", status(state, rows))
+            .await;
+        assert_eq!(progress.next().await.unwrap().unwrap(), expected);
+    }
+    assert!(progress.next().await.is_none());
+    let bodies: Vec<String> = kraken
+        .received_requests()
+        .await
+        .iter()
+        .map(|r| String::from_utf8(r.body.clone()).unwrap())
+        .collect();
+    assert!(bodies.iter().all(|body| body.ends_with("&report=trades")));
+
+    kraken.server().reset().await;
+    kraken
+        .mock_result("/0/private/ExportStatus", status("Error", None))
+        .await;
+    let done = c
+        .export_progress("trades", "VSKC")
+        .with_backoff(backoff)
+        .finished()
+        .await
+        .unwrap();
+    assert_eq!(done, ExportProgress::Error { status: "Error".to_string() });
+}
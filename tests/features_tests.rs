@@ -0,0 +1,63 @@
+//! What each client feature leaves available. Run under
+//! `--no-default-features`, `--no-default-features --features rest` and
+//! `--no-default-features --features ws` as well as the defaults: the shared
+//! modules must work in all four, each client in its own.
+
+use onise::models::ServerTimeResponse;
+use onise::ws_models::{parse_incoming, WsIncomingMessage};
+
+const TIME: &str = r#"{"unixtime":1672531199,"rfc1123":"Sun, 01 Jan 23 00:59:59 +0000"}"#;
+const SECRET: &str =
+    "kQH5HW/8p1uGOVjbgWA7FunAmGO8lsSUXNsu3eow76sz84Q18fWxnyRzBHCd3pd5nE9qa99HAZtuZuj6F1huXg==";
+
+#[test]
+fn test_shared_modules_need_neither_client() {
+    // Models, WebSocket message parsing, signing and rate limiting
+    let time: ServerTimeResponse = serde_json::from_str(TIME).unwrap();
+    assert_eq!(time.unixtime, 1672531199);
+
+    let ticker = parse_incoming(include_str!("../fixtures/ws/ticker.json")).unwrap();
+    assert!(matches!(ticker, WsIncomingMessage::TickerMsg(_)));
+
+    assert_eq!(
+        onise::signing::sign(SECRET, "/0/private/Balance", 1, "nonce=1").unwrap().len(),
+        88
+    );
+    let interval = std::time::Duration::from_millis(1);
+    assert!(onise::rate_limiter::RateLimiter::with_interval(interval).is_ok());
+}
+
+#[cfg(feature = "rest")]
+#[tokio::test]
+async fn test_rest_client_stands_alone() {
+    use wiremock::matchers::path;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let server = MockServer::start().await;
+    Mock::given(path("/0/public/Time"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            format!(r#"{{"error":[],"result":{TIME}}}"#),
+            "application/json",
+        ))
+        .mount(&server)
+        .await;
+    let client = onise::PublicClient::new(Some(server.uri()));
+    assert_eq!(client.get_server_time().await.unwrap().unixtime, 1672531199);
+}
+
+#[cfg(feature = "ws")]
+#[tokio::test]
+async fn test_ws_client_stands_alone() {
+    use onise::ws_client::KrakenWsClient;
+    use tokio::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        tokio_tungstenite::accept_async(stream).await.unwrap()
+    });
+    let client = KrakenWsClient::connect(&url).await.unwrap();
+    let _socket = server.await.unwrap();
+    assert!(client.is_connected());
+}
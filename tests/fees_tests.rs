@@ -0,0 +1,31 @@
+use onise::error::KrakenError;
+use onise::testkit::MockKraken;
+
+#[tokio::test]
+async fn test_size_order_for_budget_uses_fees_and_minimums() {
+    use onise::exchange::Side;
+
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+    let c = kraken.authenticated_client();
+
+    // Sample ask 30300.1, taker fee 0.26%, 8 lot decimals
+    let sized = c
+        .size_order_for_budget("XBTUSD", 1_000.0, Side::Buy)
+        .await
+        .expect("sized");
+    assert_eq!(sized.volume, "0.03291760");
+    assert_eq!(sized.price, 30_300.1);
+    assert!(sized.cost + sized.fee <= 1_000.0);
+    assert!((sized.fee - sized.cost * 0.0026).abs() < 1e-9);
+
+    // ordermin 0.0001 costs about $3
+    let err = c
+        .size_order_for_budget("XBTUSD", 2.0, Side::Sell)
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(&err, KrakenError::OrderBelowMinimum { pair, reason } if pair == "XXBTZUSD" && reason.contains("0.0001")),
+        "{err:?}"
+    );
+}
@@ -0,0 +1,378 @@
+use onise::exchange::Side;
+use onise::fixtures::{self, parse_ws, round_trip, REST, WS};
+use onise::models::*;
+use onise::ws_models::{WsAdminResponse, WsIncomingMessage, WsUserTradingResponse};
+
+/// Round-trip the REST fixture `name` through model `T`.
+fn golden<T>(name: &str) -> serde_json::Value
+where
+    T: serde::de::DeserializeOwned + serde::Serialize,
+{
+    let body = fixtures::rest(name).unwrap_or_else(|| panic!("missing fixture {name}"));
+    round_trip::<T>(body).unwrap_or_else(|e| panic!("{name}: {e}"))
+}
+
+#[test]
+fn test_rest_fixtures_round_trip() {
+    golden::<ServerTimeResponse>("server_time");
+    golden::<SystemStatusResponse>("system_status");
+    golden::<AssetInfoResponse>("assets");
+    golden::<AssetPairsResponse>("asset_pairs");
+    golden::<TickerResponse>("ticker");
+    golden::<OhlcDataResponse>("ohlc");
+    golden::<OrderBookResponse>("depth");
+    golden::<TradesResponse>("trades");
+    golden::<SpreadsResponse>("spread");
+    golden::<AccountBalanceResponse>("balance");
+    golden::<ExtendedBalanceResponse>("balance_ex");
+    golden::<TradeBalanceResponse>("trade_balance");
+    golden::<OpenOrdersResponse>("open_orders");
+    golden::<ClosedOrdersResponse>("closed_orders");
+    golden::<QueryOrdersResponse>("query_orders");
+    golden::<TradesHistoryResponse>("trades_history");
+    golden::<QueryTradesResponse>("query_trades");
+    golden::<OpenPositionsResponse>("open_positions");
+    golden::<LedgersResponse>("ledgers");
+    golden::<QueryLedgersResponse>("query_ledgers");
+    golden::<TradeVolumeResponse>("trade_volume");
+    golden::<ExportTradesResponse>("export_trades");
+    golden::<ExportStatusResponse>("export_status");
+    golden::<RetrieveExportResponse>("retrieve_export");
+    golden::<DeleteExportResponse>("delete_export");
+    golden::<AddOrderResponse>("add_order");
+    golden::<AddOrderBatchResponse>("add_order_batch");
+    golden::<AmendOrderResponse>("amend_order");
+    golden::<EditOrderResponse>("edit_order");
+    golden::<CancelOrderResponse>("cancel_order");
+    golden::<CancelAllOrdersResponse>("cancel_all");
+    golden::<CancelAllOrdersAfterResponse>("cancel_all_orders_after");
+    golden::<CancelOrderBatchResponse>("cancel_order_batch");
+    golden::<GetWebSocketsTokenResponse>("websockets_token");
+    golden::<DepositMethodsResponse>("deposit_methods");
+    golden::<DepositAddressesResponse>("deposit_addresses");
+    golden::<DepositStatusResponse>("deposit_status");
+    golden::<WithdrawalMethodsResponse>("withdrawal_methods");
+    golden::<WithdrawalAddressesResponse>("withdrawal_addresses");
+    golden::<WithdrawalInformationResponse>("withdrawal_information");
+    golden::<WithdrawFundsResponse>("withdraw");
+    golden::<WithdrawStatusResponse>("withdraw_status");
+    golden::<WithdrawCancelResponse>("withdraw_cancel");
+    golden::<WalletTransferResponse>("wallet_transfer");
+    golden::<CreateSubaccountResponse>("create_subaccount");
+    golden::<AccountTransferResponse>("account_transfer");
+    golden::<AllocateEarnFundsResponse>("stake");
+    golden::<DeallocateEarnFundsResponse>("unstake");
+    golden::<GetAllocationStatusResponse>("stake_status");
+    golden::<GetDeallocationStatusResponse>("unstake_status");
+    golden::<ListEarnStrategiesResponse>("staking_assets");
+    golden::<ListEarnAllocationsResponse>("staking_transactions");
+    golden::<CreateOtcQuoteResponse>("otc_quote");
+    golden::<UpdateOtcQuoteResponse>("otc_update_quote");
+    golden::<GetOtcActiveQuotesResponse>("otc_quotes");
+    assert_eq!(REST.len(), 55);
+
+    let depth = golden::<OrderBookResponse>("depth");
+    assert_eq!(depth["XXBTZUSD"]["asks"][0][2], 1688671659);
+
+    let ohlc = golden::<OhlcDataResponse>("ohlc");
+    let ohlc: OhlcDataResponse = serde_json::from_value(ohlc).unwrap();
+    let candle = &ohlc.candles["XXBTZUSD"][1];
+    assert_eq!((candle.time, candle.count), (1688671260, 18));
+    assert_eq!(candle.open, "30304.5");
+    assert_eq!(candle.close, "30300.0");
+    assert_eq!(ohlc.last, 1688672160);
+    // `last` also comes as a string, and is required
+    let empty: OhlcDataResponse = serde_json::from_str(r#"{"last":"60"}"#).unwrap();
+    assert_eq!((empty.candles.len(), empty.last), (0, 60));
+    assert!(serde_json::from_str::<OhlcDataResponse>(r#"{"XBTUSD":[]}"#).is_err());
+
+    let trades = golden::<TradesResponse>("trades");
+    let trades: TradesResponse = serde_json::from_value(trades).unwrap();
+    let trade = &trades.trades["XXBTZUSD"][1];
+    assert_eq!(trade.price, "30243.30000");
+    assert_eq!(trade.volume, "0.00376960");
+    assert_eq!((trade.side, trade.ordertype.as_str()), (Side::Sell, "l"));
+    assert_eq!(trade.trade_id, 61044953);
+    assert_eq!(trades.last, 1688671969993150842);
+    #[cfg(feature = "arbitrary-precision")]
+    assert_eq!(trade.time.as_str(), "1688669598.2804112");
+
+    let spreads = golden::<SpreadsResponse>("spread");
+    let spreads: SpreadsResponse = serde_json::from_value(spreads).unwrap();
+    let spread = &spreads.spreads["XXBTZUSD"][1];
+    assert_eq!(spread.time, 1688671834);
+    assert_eq!(spread.bid, "30292.10000");
+    assert_eq!(spread.ask, "30296.70000");
+    assert_eq!(spreads.last, 1688672106);
+}
+
+#[test]
+fn test_ws_fixtures_match_typed_variants() {
+    for fixture in WS {
+        let msg = parse_ws(fixture.body).unwrap_or_else(|e| panic!("{}: {e}", fixture.name));
+        let expected = match fixture.name {
+            "system_status" => matches!(
+                msg,
+                WsIncomingMessage::Admin(WsAdminResponse::SystemStatus { .. })
+            ),
+            "subscription_status" => matches!(
+                msg,
+                WsIncomingMessage::Admin(WsAdminResponse::SubscriptionStatus { .. })
+            ),
+            "pong" => matches!(
+                msg,
+                WsIncomingMessage::Admin(WsAdminResponse::PingStatus { .. })
+            ),
+            "heartbeat" => matches!(msg, WsIncomingMessage::Admin(WsAdminResponse::Heartbeat {})),
+            "ticker" => matches!(msg, WsIncomingMessage::TickerMsg(_)),
+            "book_snapshot" => matches!(&msg, WsIncomingMessage::BookMsg(b) if b.is_snapshot()),
+            "book_update" => matches!(&msg, WsIncomingMessage::BookMsg(b) if !b.is_snapshot()),
+            "candles" => matches!(msg, WsIncomingMessage::CandlesMsg(_)),
+            "trades" => matches!(msg, WsIncomingMessage::TradesMsg(_)),
+            "instruments" => matches!(msg, WsIncomingMessage::InstrumentsMsg(_)),
+            "balances" => matches!(msg, WsIncomingMessage::BalancesMsg(_)),
+            "executions" => matches!(msg, WsIncomingMessage::ExecutionsMsg(_)),
+            "add_order_status" => matches!(
+                msg,
+                WsIncomingMessage::Trading(WsUserTradingResponse::AddOrderStatus { .. })
+            ),
+            "cancel_order_status" => matches!(
+                msg,
+                WsIncomingMessage::Trading(WsUserTradingResponse::CancelOrderStatus { .. })
+            ),
+            "batch_add_status" => matches!(
+                msg,
+                WsIncomingMessage::Trading(WsUserTradingResponse::BatchAddStatus { .. })
+            ),
+            other => panic!("no expectation for ws fixture {other}"),
+        };
+        assert!(expected, "{} parsed as {msg:?}", fixture.name);
+    }
+}
+
+#[test]
+fn test_load_from_disk_and_kraken_errors() {
+    let body = fixtures::load(
+        concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/rest"),
+        "balance",
+    )
+    .expect("readable");
+    assert_eq!(body, fixtures::rest("balance").unwrap());
+
+    let err = fixtures::parse_rest::<ServerTimeResponse>(r#"{"error":["EAPI:Invalid nonce"]}"#)
+        .expect_err("Kraken error");
+    assert!(matches!(err, onise::error::KrakenError::ApiError { .. }));
+}
+
+#[test]
+fn test_borrowed_market_data_matches_owned_models() {
+    use onise::ws_models::{parse_incoming, WsMarketDataRef};
+    use std::borrow::Cow;
+
+    for fixture in WS.iter().filter(|f| WsMarketDataRef::parse(f.body).is_some()) {
+        let fast = parse_incoming(fixture.body).unwrap();
+        let slow: WsIncomingMessage = serde_json::from_str(fixture.body).unwrap();
+        assert_eq!(format!("{fast:?}"), format!("{slow:?}"), "{}", fixture.name);
+    }
+
+    let book = fixtures::ws("book_snapshot").unwrap();
+    let Some(Ok(WsMarketDataRef::Book(view))) = WsMarketDataRef::parse(book) else {
+        panic!("book frame not routed to the borrowed path");
+    };
+    assert!(view.is_snapshot());
+    assert!(matches!(view.bids[0].price, Cow::Borrowed(_)));
+
+    // Escaped strings can't borrow, but still parse.
+    let escaped = r#"{"channel":"trade","symbol":"BTC\/USD","trades":[]}"#;
+    let Some(Ok(WsMarketDataRef::Trades(view))) = WsMarketDataRef::parse(escaped) else {
+        panic!("trade frame not routed to the borrowed path");
+    };
+    assert!(matches!(view.symbol, Cow::Owned(_)));
+    assert_eq!(view.symbol, "BTC/USD");
+
+    let status = fixtures::ws("subscription_status").unwrap();
+    assert!(WsMarketDataRef::parse(status).is_none());
+}
+
+#[test]
+fn test_ws_requests_conform_to_canonical_payloads() {
+    use onise::ws_models::*;
+
+    const TOKEN: &str = "WW91ciBhdXRoZW50aWNhdGlvbiB0b2tlbiBnb2VzIGhlcmUu";
+    const TXID: &str = "OHYO67-6LP66-HMQ437";
+    let s = |v: &str| Some(v.to_string());
+
+    fn check<T: serde::Serialize>(name: &str, request: &T) {
+        let expected = fixtures::ws_request(name).unwrap_or_else(|| panic!("missing {name}"));
+        fixtures::conform(request, expected).unwrap_or_else(|e| panic!("{name}: {e}"));
+    }
+
+    check(
+        "ping",
+        &WsPingRequest {
+            event: "ping".into(),
+            req_id: Some(42),
+        },
+    );
+    check(
+        "heartbeat",
+        &WsHeartbeatRequest {
+            event: "heartbeat".into(),
+            req_id: None,
+        },
+    );
+    check(
+        "authorize",
+        &WsAuthorizeRequest {
+            event: "authorize".into(),
+            token: TOKEN.into(),
+            req_id: Some(1),
+        },
+    );
+    check(
+        "subscribe",
+        &WsSubscribeRequest {
+            event: "subscribe".into(),
+            req_id: Some(2),
+            subscription: WsSubscriptionPayload::Book {
+                symbol: "BTC/USD".into(),
+                depth: 10,
+            },
+        },
+    );
+    check(
+        "unsubscribe",
+        &WsUnsubscribeRequest {
+            event: "unsubscribe".into(),
+            req_id: Some(3),
+            subscription: WsSubscriptionPayload::Ticker {
+                symbol: "BTC/USD".into(),
+            },
+        },
+    );
+    check(
+        "add_order",
+        &WsAddOrderRequest {
+            event: "addOrder".into(),
+            token: TOKEN.into(),
+            req_id: Some(4),
+            order_type: "limit".into(),
+            symbol: "BTC/USD".into(),
+            side: "buy".into(),
+            quantity: "1.25".into(),
+            price: s("26500.5"),
+            time_in_force: s("GTC"),
+            post_only: Some(true),
+            client_order_id: s("my-order-1"),
+            ..Default::default()
+        },
+    );
+    check(
+        "amend_order",
+        &WsAmendOrderRequest {
+            event: "amendOrder".into(),
+            token: TOKEN.into(),
+            req_id: Some(5),
+            txid: TXID.into(),
+            quantity: s("1.5"),
+            price: s("26400.0"),
+            ..Default::default()
+        },
+    );
+    check(
+        "edit_order",
+        &WsEditOrderRequest {
+            event: "editOrder".into(),
+            token: TOKEN.into(),
+            req_id: Some(6),
+            txid: TXID.into(),
+            price: s("26350.0"),
+            reduce_only: Some(false),
+            ..Default::default()
+        },
+    );
+    check(
+        "cancel_order",
+        &WsCancelOrderRequest {
+            event: "cancelOrder".into(),
+            token: TOKEN.into(),
+            req_id: Some(7),
+            txid: TXID.into(),
+        },
+    );
+    check(
+        "cancel_all",
+        &WsCancelAllRequest {
+            event: "cancelAll".into(),
+            token: TOKEN.into(),
+            req_id: Some(8),
+        },
+    );
+    check(
+        "cancel_on_disconnect",
+        &WsCancelOnDisconnectRequest {
+            event: "cancelOnDisconnect".into(),
+            token: TOKEN.into(),
+            req_id: Some(9),
+            enable: true,
+        },
+    );
+    check(
+        "batch_add",
+        &WsBatchAddRequest {
+            event: "batchAdd".into(),
+            token: TOKEN.into(),
+            req_id: Some(10),
+            orders: vec![
+                BatchAddOrderSpec {
+                    order_type: "limit".into(),
+                    symbol: "BTC/USD".into(),
+                    side: "buy".into(),
+                    quantity: "0.5".into(),
+                    price: s("26000.0"),
+                    client_order_id: s("ladder-1"),
+                    ..Default::default()
+                },
+                BatchAddOrderSpec {
+                    order_type: "stop-loss".into(),
+                    symbol: "BTC/USD".into(),
+                    side: "sell".into(),
+                    quantity: "0.5".into(),
+                    stop_price: s("25000.0"),
+                    trigger_signal: s("last_price"),
+                    ..Default::default()
+                },
+            ],
+        },
+    );
+    check(
+        "batch_cancel",
+        &WsBatchCancelRequest {
+            event: "batchCancel".into(),
+            token: TOKEN.into(),
+            req_id: Some(11),
+            orders: vec![TXID.into(), "OLQCVY-B27XU-MBPCL5".into()],
+        },
+    );
+    assert_eq!(fixtures::WS_REQUESTS.len(), 13);
+
+    // A renamed or dropped field is reported by path
+    let err = fixtures::conform(
+        &WsCancelAllRequest {
+            event: "cancel_all".into(),
+            token: TOKEN.into(),
+            req_id: None,
+        },
+        fixtures::ws_request("cancel_all").unwrap(),
+    )
+    .expect_err("differs");
+    let message = err.to_string();
+    assert!(
+        message.contains(r#"$.event: expected "cancelAll", got "cancel_all""#),
+        "{message}"
+    );
+    assert!(
+        message.contains("$.req_id: expected 8, got nothing"),
+        "{message}"
+    );
+}
@@ -0,0 +1,88 @@
+use onise::error::KrakenError;
+use onise::testkit::{ErrorClass, MockKraken};
+
+#[tokio::test]
+async fn test_deposit_method_by_network() {
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+    let c = kraken.authenticated_client();
+
+    let methods = c.deposit_methods_for("USDT").await.expect("DepositMethods");
+    assert_eq!(methods.networks().collect::<Vec<_>>(), vec!["Ethereum", "Solana"]);
+    assert_eq!(
+        methods.for_network("ethereum").unwrap().method,
+        "Tether USD (ERC20)"
+    );
+
+    c.deposit_addresses_on("USDT", "Solana", true)
+        .await
+        .expect("DepositAddresses");
+    let requests = kraken.received_requests().await;
+    let body = String::from_utf8(requests.last().unwrap().body.clone()).unwrap();
+    assert!(body.contains("method=Tether+USD+%28SPL%29"), "{body}");
+    assert!(body.contains("new=true"));
+
+    let err = c.deposit_method("USDT", "Tron").await.expect_err("no Tron");
+    assert!(matches!(err, KrakenError::InvalidUsage(msg) if msg.contains("Solana")));
+}
+
+#[tokio::test]
+async fn test_funding_watchers_follow_status_to_terminal() {
+    use futures_util::StreamExt;
+    use onise::funding::{TransferStatus, WatchBackoff};
+    use std::time::Duration;
+
+    let backoff = WatchBackoff {
+        initial: Duration::from_millis(10),
+        max: Duration::from_millis(20),
+        factor: 2,
+    };
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+    let c = kraken.authenticated_client();
+
+    let txid = "6544b41b607d8b2512baf801755a3a87b6890eacdb451be8a94059fb11f0a8d9";
+    let deposit = c
+        .watch_deposit("XBT", txid)
+        .with_backoff(backoff)
+        .settled()
+        .await
+        .unwrap();
+    assert_eq!(deposit.status, "Success");
+
+    let refid = "FTQcuak-V6Za8qrWnhzTx67yYHz8Tg";
+    let mut withdrawal = c.watch_withdrawal(refid).with_backoff(backoff);
+    let pending = withdrawal.next().await.unwrap().unwrap();
+    assert_eq!(pending.status, "Pending");
+    assert!(!pending.is_terminal());
+
+    // Unchanged polls are swallowed until the withdrawal settles
+    kraken.server().reset().await;
+    kraken
+        .mock_result(
+            "/0/private/WithdrawStatus",
+            serde_json::json!([{
+                "method": "Bitcoin", "aclass": "currency", "asset": "XXBT",
+                "refid": refid, "txid": "THVRQM-33VKH-UCI7BS", "info": "mzp6yUVMRxfasyfwzTZjjy38dHqMX7Z3GR",
+                "amount": "0.72485000", "fee": "0.00015000", "time": 1688014586,
+                "status": "Success"
+            }]),
+        )
+        .await;
+    let done = withdrawal.next().await.unwrap().unwrap();
+    assert_eq!(done.status, "Success");
+    assert!(withdrawal.next().await.is_none());
+
+    // Errors that aren't transient end the watch
+    kraken.server().reset().await;
+    kraken
+        .mock_error("/0/private/WithdrawStatus", ErrorClass::General)
+        .await;
+    let err = c
+        .watch_withdrawal(refid)
+        .with_backoff(backoff)
+        .settled()
+        .await
+        .unwrap_err();
+    assert!(matches!(err.inner(), KrakenError::GeneralError { .. }), "{err:?}");
+}
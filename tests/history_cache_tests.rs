@@ -0,0 +1,81 @@
+use onise::history_cache::HistoryCache;
+use onise::testkit::MockKraken;
+
+async fn hits(kraken: &MockKraken, path: &str) -> usize {
+    kraken
+        .received_requests()
+        .await
+        .iter()
+        .filter(|r| r.url.path() == path)
+        .count()
+}
+
+#[tokio::test]
+async fn test_closed_range_is_served_from_cache() {
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+    let client = kraken.public_client();
+    let cache = HistoryCache::temporary().unwrap();
+
+    let params = [("pair", "XBTUSD"), ("interval", "60"), ("since", "1688671200")];
+    let first = client.get_ohlc_data_cached(&cache, &params).await.unwrap();
+    assert_eq!(hits(&kraken, "/0/public/OHLC").await, 1);
+    assert_eq!(cache.len(), 1);
+
+    // Same range, parameters in another order: a hit, nothing sent
+    let reordered = [("since", "1688671200"), ("interval", "60"), ("pair", "XBTUSD")];
+    let second = client.get_ohlc_data_cached(&cache, &reordered).await.unwrap();
+    assert_eq!(hits(&kraken, "/0/public/OHLC").await, 1);
+    assert_eq!(
+        serde_json::to_value(&first).unwrap(),
+        serde_json::to_value(&second).unwrap()
+    );
+}
+
+#[tokio::test]
+async fn test_other_range_misses_the_cache() {
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+    let client = kraken.authenticated_client();
+    let cache = HistoryCache::temporary().unwrap();
+
+    let q1 = [("start", "1688000000"), ("end", "1688600000")];
+    let q2 = [("start", "1688600000"), ("end", "1689200000")];
+    client.get_ledgers_cached(&cache, &q1).await.unwrap();
+    client.get_ledgers_cached(&cache, &q2).await.unwrap();
+    assert_eq!(hits(&kraken, "/0/private/Ledgers").await, 2);
+    assert_eq!(cache.len(), 2);
+
+    client.get_ledgers_cached(&cache, &q1).await.unwrap();
+    assert_eq!(hits(&kraken, "/0/private/Ledgers").await, 2);
+}
+
+#[tokio::test]
+async fn test_open_range_is_refetched_every_time() {
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+    let public = kraken.public_client();
+    let private = kraken.authenticated_client();
+    let cache = HistoryCache::temporary().unwrap();
+
+    // No `since`: the latest candles/trades, which keep changing
+    for _ in 0..2 {
+        public
+            .get_ohlc_data_cached(&cache, &[("pair", "XBTUSD")])
+            .await
+            .unwrap();
+        public
+            .get_recent_trades_cached(&cache, &[("pair", "XBTUSD")])
+            .await
+            .unwrap();
+        // A `start` but no `end` runs up to now
+        private
+            .get_trades_history_cached(&cache, &[("start", "1688000000")])
+            .await
+            .unwrap();
+    }
+    assert_eq!(hits(&kraken, "/0/public/OHLC").await, 2);
+    assert_eq!(hits(&kraken, "/0/public/Trades").await, 2);
+    assert_eq!(hits(&kraken, "/0/private/TradesHistory").await, 2);
+    assert!(cache.is_empty());
+}
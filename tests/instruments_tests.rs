@@ -0,0 +1,63 @@
+use onise::testkit::MockKraken;
+
+#[tokio::test]
+async fn test_instrument_changes_refresh_metadata_cache() {
+    use futures_util::StreamExt;
+    use onise::instruments::InstrumentWatcher;
+    use onise::ws_models::WsInstrumentsMessage;
+    use std::time::Duration;
+
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+    let c = kraken
+        .authenticated_client()
+        .with_metadata_cache(Duration::from_secs(3600));
+    let p: &[(&str, &str)] = &[];
+    let pair_reads = || async {
+        kraken
+            .received_requests()
+            .await
+            .iter()
+            .filter(|r| r.url.path() == "/0/public/AssetPairs")
+            .count()
+    };
+    c.get_asset_pairs(p).await.unwrap();
+    c.get_asset_pairs(p).await.unwrap();
+    assert_eq!(pair_reads().await, 1);
+
+    let message = |pairs: &[(&str, &str, u32)]| -> WsInstrumentsMessage {
+        let data: Vec<_> = pairs
+            .iter()
+            .map(|(symbol, status, price_decimals)| {
+                serde_json::json!({
+                    "symbol": symbol, "status": status, "base_currency": "X", "quote_currency": "USD",
+                    "price_decimals": price_decimals, "quantity_decimals": 8, "marginable": false,
+                    "margin_ratio": "0", "max_leverage": "1", "min_leverage": "1", "maker_fee": "0.16",
+                    "taker_fee": "0.26", "min_volume": "0.0001", "max_volume": "1000",
+                    "tick_size": "0.1", "lot_size": "0.00000001"
+                })
+            })
+            .collect();
+        serde_json::from_value(serde_json::json!({ "channel": "instrument", "data": data })).unwrap()
+    };
+    let feed = futures_util::stream::iter(vec![
+        message(&[("BTC/USD", "online", 1), ("ETH/USD", "online", 2)]),
+        // Nothing changed
+        message(&[("BTC/USD", "online", 1)]),
+        message(&[("BTC/USD", "cancel_only", 1), ("ETH/USD", "online", 3)]),
+    ]);
+    let changes: Vec<_> = InstrumentWatcher::new()
+        .with_rest_client(&c)
+        .watch(feed)
+        .collect()
+        .await;
+
+    assert_eq!(changes.len(), 2, "{changes:?}");
+    assert_eq!(changes[0].symbol, "BTC/USD");
+    assert!(changes[0].status_changed() && !changes[0].precision_changed());
+    assert_eq!(changes[0].current.status, "cancel_only");
+    assert!(!changes[1].status_changed() && changes[1].precision_changed());
+
+    c.get_asset_pairs(p).await.unwrap();
+    assert_eq!(pair_reads().await, 2);
+}
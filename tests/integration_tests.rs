@@ -1,9 +1,11 @@
-use onise::KrakenClient;
+use onise::{AuthenticatedClient, PublicClient};
 use wiremock::matchers::{method, path};
 use wiremock::{Mock, MockServer, ResponseTemplate};
 
 use std::env;
-use tokio;
+
+mod common;
+use common::API_SECRET;
 
 #[tokio::test]
 async fn test_get_server_time_mock() {
@@ -26,9 +28,7 @@ async fn test_get_server_time_mock() {
         .await;
 
     // Create a client pointing to the mock server
-    let client = KrakenClient::new(
-        None,
-        None,
+    let client = PublicClient::new(
         Some(mock_server.uri()), // base_url override
     );
 
@@ -47,11 +47,873 @@ async fn test_get_server_time_live() {
     }
 
     // We assume no API key/secret needed for public endpoint:
-    let client = KrakenClient::new(
-        None, None, None, // default base URL => https://api.kraken.com
+    let client = PublicClient::new(
+        None, // default base URL => https://api.kraken.com
     );
 
     let resp = client.get_server_time().await.expect("Live call failed");
     println!("Live server time response: {:?}", resp);
     assert!(resp.unixtime > 0);
 }
+
+#[tokio::test]
+async fn test_metadata_cache_serves_repeat_calls() {
+    let mock_server = MockServer::start().await;
+
+    let mock_body = r#"{
+      "error": [],
+      "result": {
+        "status": "online",
+        "timestamp": "2023-07-06T18:52:00Z"
+      }
+    }"#;
+
+    // The endpoint must only be hit once; the second call is served from the cache.
+    Mock::given(method("GET"))
+        .and(path("/0/public/SystemStatus"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(mock_body, "application/json"))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = PublicClient::new(Some(mock_server.uri()))
+        .with_metadata_cache(std::time::Duration::from_secs(60));
+
+    let first = client.get_system_status().await.expect("Should succeed");
+    let second = client.get_system_status().await.expect("Should succeed");
+    assert_eq!(first.status, "online");
+    assert_eq!(second.timestamp, "2023-07-06T18:52:00Z");
+}
+
+#[tokio::test]
+async fn test_private_post_signs_the_sent_body() {
+    let mock_server = MockServer::start().await;
+
+    let mock_body = r#"{ "error": [], "result": { "refid": "AGBSO6T-UFMTTQ-I7KGS6" } }"#;
+    Mock::given(method("POST"))
+        .and(path("/0/private/Withdraw"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(mock_body, "application/json"))
+        .mount(&mock_server)
+        .await;
+
+    let client = AuthenticatedClient::new("key", API_SECRET, Some(mock_server.uri())).unwrap();
+
+    // Values with characters that need form encoding
+    let resp = client
+        .withdraw_funds(&[("asset", "XBT"), ("key", "my cold+wallet&co=1"), ("amount", "0.5")])
+        .await
+        .expect("Should succeed");
+    assert_eq!(resp.refid, "AGBSO6T-UFMTTQ-I7KGS6");
+
+    let requests = mock_server.received_requests().await.expect("recording enabled");
+    let request = &requests[0];
+    let body = String::from_utf8(request.body.clone()).unwrap();
+    assert!(body.contains("key=my+cold%2Bwallet%26co%3D1"));
+
+    let nonce: u64 = body
+        .split('&')
+        .find_map(|kv| kv.strip_prefix("nonce="))
+        .and_then(|n| n.parse().ok())
+        .expect("nonce in body");
+    let signature = request.headers.get("API-Sign").unwrap().to_str().unwrap();
+    let endpoint = "/0/private/Withdraw";
+    assert!(onise::signing::verify(API_SECRET, endpoint, nonce, &body, signature).unwrap());
+}
+
+#[tokio::test]
+async fn test_user_agent_and_request_id_on_errors() {
+    let mock_server = MockServer::start().await;
+
+    let mock_body = r#"{ "error": ["EGeneral:Invalid arguments"], "result": {} }"#;
+    Mock::given(method("GET"))
+        .and(path("/0/public/Ticker"))
+        .and(wiremock::matchers::header("User-Agent", "my-bot/2.0"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(mock_body, "application/json"))
+        .mount(&mock_server)
+        .await;
+
+    let client = PublicClient::new(Some(mock_server.uri())).with_user_agent("my-bot/2.0");
+    let err = client
+        .get_ticker_information("NOPE")
+        .await
+        .expect_err("Kraken error expected");
+
+    let requests = mock_server.received_requests().await.expect("recording enabled");
+    let sent_id = requests[0]
+        .headers
+        .get(onise::REQUEST_ID_HEADER)
+        .expect("request id header")
+        .to_str()
+        .unwrap();
+    assert_eq!(err.request_id(), Some(sent_id));
+    assert!(matches!(
+        err.inner(),
+        onise::error::KrakenError::GeneralError { .. }
+    ));
+}
+
+#[tokio::test]
+async fn test_request_logger_redacts_secrets() {
+    use std::sync::{Arc, Mutex};
+
+    let mock_server = MockServer::start().await;
+
+    let mock_body = r#"{ "error": [], "result": { "refid": "AGBSO6T-UFMTTQ-I7KGS6" } }"#;
+    Mock::given(method("POST"))
+        .and(path("/0/private/Withdraw"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(mock_body, "application/json"))
+        .mount(&mock_server)
+        .await;
+
+    let logs = Arc::new(Mutex::new(Vec::new()));
+    let sink = logs.clone();
+    let client = AuthenticatedClient::new("my-api-key", API_SECRET, Some(mock_server.uri())).unwrap()
+        .with_request_logger(move |entry: &onise::logging::RequestLog| {
+            sink.lock().unwrap().push(entry.clone())
+        });
+
+    client
+        .withdraw_funds(&[("asset", "XBT"), ("amount", "0.5"), ("otp", "123456")])
+        .await
+        .expect("Should succeed");
+
+    let logs = logs.lock().unwrap();
+    assert_eq!(logs.len(), 1);
+    let entry = &logs[0];
+    assert_eq!(entry.method, "POST");
+    assert_eq!(entry.path, "/0/private/Withdraw");
+    assert_eq!(entry.status, Some(200));
+    assert!(entry
+        .params
+        .contains(&("otp".to_string(), onise::logging::REDACTED.to_string())));
+
+    let line = entry.to_string();
+    assert!(line.contains("asset=XBT"));
+    assert!(!line.contains("123456"));
+    assert!(!line.contains("my-api-key"));
+    assert!(!line.contains(API_SECRET));
+}
+
+#[tokio::test]
+async fn test_ledgers_stream_follows_offsets() {
+    use futures_util::TryStreamExt;
+    use wiremock::matchers::body_string_contains;
+
+    let mock_server = MockServer::start().await;
+
+    let entry = |refid: &str, time: f64| {
+        serde_json::json!({
+            "refid": refid, "time": time, "type": "trade", "subtype": "", "aclass": "currency",
+            "asset": "ZUSD", "amount": "-1.0", "fee": "0.0", "balance": "10.0"
+        })
+    };
+    let page = |ledger: serde_json::Value| {
+        ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "error": [], "result": { "ledger": ledger, "count": 3 }
+        }))
+    };
+    Mock::given(method("POST"))
+        .and(path("/0/private/Ledgers"))
+        .and(body_string_contains("ofs=0"))
+        .respond_with(page(serde_json::json!({
+            "L2": entry("R2", 2.0), "L3": entry("R3", 3.0)
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/0/private/Ledgers"))
+        .and(body_string_contains("ofs=2"))
+        .respond_with(page(serde_json::json!({ "L1": entry("R1", 1.0) })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = AuthenticatedClient::new("key", API_SECRET, Some(mock_server.uri())).unwrap();
+    let ids: Vec<String> = client
+        .ledgers_stream(&[("asset", "ZUSD")])
+        .map_ok(|(id, _)| id)
+        .try_collect()
+        .await
+        .expect("all pages");
+    assert_eq!(ids, vec!["L3", "L2", "L1"]);
+}
+
+#[tokio::test]
+async fn test_closed_orders_paged_follows_offsets_from_params() {
+    use futures_util::TryStreamExt;
+    use onise::params::ClosedOrdersParams;
+    use wiremock::matchers::body_string_contains;
+
+    let mock_server = MockServer::start().await;
+
+    let order = |opentm: f64| {
+        serde_json::json!({
+            "refid": null, "userref": 7, "status": "closed", "opentm": opentm,
+            "starttm": 0, "expiretm": 0,
+            "descr": {
+                "pair": "XBTUSD", "side": "buy", "ordertype": "limit", "price": "30000.0",
+                "price2": "0", "leverage": "none", "order": "buy 0.1 XBTUSD @ limit 30000.0",
+                "close": ""
+            },
+            "vol": "0.1", "vol_exec": "0.1", "cost": "3000.0", "fee": "4.8", "price": "30000.0",
+            "stopprice": "0", "limitprice": "0", "misc": "", "oflags": "fciq"
+        })
+    };
+    let page = |closed: serde_json::Value| {
+        ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "error": [], "result": { "closed": closed, "count": 4 }
+        }))
+    };
+    let first = serde_json::json!({ "O3": order(3.0), "O2": order(2.0) });
+    Mock::given(method("POST"))
+        .and(path("/0/private/ClosedOrders"))
+        .and(body_string_contains("userref=7&ofs=1"))
+        .respond_with(page(first))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/0/private/ClosedOrders"))
+        .and(body_string_contains("userref=7&ofs=3"))
+        .respond_with(page(serde_json::json!({ "O1": order(1.0) })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = AuthenticatedClient::new("key", API_SECRET, Some(mock_server.uri())).unwrap();
+    let params = ClosedOrdersParams::new().with_userref(7).with_offset(1);
+    let stream = client.closed_orders_paged(&params);
+    let ids: Vec<String> = stream
+        .map_ok(|(id, _)| id)
+        .try_collect()
+        .await
+        .expect("all pages");
+    assert_eq!(ids, vec!["O3", "O2", "O1"]);
+}
+
+#[tokio::test]
+async fn test_ledgers_stream_resumes_from_token() {
+    use futures_util::StreamExt;
+    use onise::pagination::ResumeToken;
+    use wiremock::matchers::body_string_contains;
+
+    let mock_server = MockServer::start().await;
+
+    let entry = |refid: &str, time: f64| {
+        serde_json::json!({
+            "refid": refid, "time": time, "type": "trade", "subtype": "", "aclass": "currency",
+            "asset": "ZUSD", "amount": "-1.0", "fee": "0.0", "balance": "10.0"
+        })
+    };
+    Mock::given(method("POST"))
+        .and(path("/0/private/Ledgers"))
+        .and(body_string_contains("ofs=0"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "error": [], "result": {
+                "ledger": { "L2": entry("R2", 2.0), "L3": entry("R3", 3.0) }, "count": 3
+            }
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/0/private/Ledgers"))
+        .and(body_string_contains("asset=ZUSD&end=1700000000&ofs=2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "error": [], "result": { "ledger": { "L1": entry("R1", 1.0) }, "count": 3 }
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = AuthenticatedClient::new("key", API_SECRET, Some(mock_server.uri())).unwrap();
+
+    // Take the first page, then "crash" and persist where we got to.
+    let mut stream = client.ledgers_stream(&[("asset", "ZUSD"), ("end", "1700000000")]);
+    let first = stream.next().await.unwrap().expect("first entry").0;
+    let second = stream.next().await.unwrap().expect("second entry").0;
+    assert_eq!((first.as_str(), second.as_str()), ("L3", "L2"));
+    let saved = serde_json::to_string(&stream.resume_token()).unwrap();
+    drop(stream);
+    assert_eq!(saved, r#""Ledgers?asset=ZUSD&end=1700000000&ofs=2""#);
+
+    let token: ResumeToken = serde_json::from_str(&saved).unwrap();
+    assert!(client.resume_trades_history_stream(&token).is_err());
+    let mut resumed = client.resume_ledgers_stream(&token).expect("ledgers token");
+    assert_eq!(resumed.next().await.unwrap().expect("resumed entry").0, "L1");
+    assert!(resumed.next().await.is_none());
+    assert_eq!(resumed.resume_token().offset(), 3);
+}
+
+#[tokio::test]
+async fn test_page_streams_reconcile_shifted_offsets() {
+    use futures_util::TryStreamExt;
+    use wiremock::matchers::body_string_contains;
+
+    let mock_server = MockServer::start().await;
+
+    let entry = |refid: &str, time: f64| {
+        serde_json::json!({
+            "refid": refid, "time": time, "type": "trade", "subtype": "", "aclass": "currency",
+            "asset": "ZUSD", "amount": "-1.0", "fee": "0.0", "balance": "10.0"
+        })
+    };
+    let trade = |time: f64| {
+        serde_json::json!({
+            "ordertxid": "O1", "postxid": "", "pair": "XXBTZUSD", "time": time, "type": "buy",
+            "ordertype": "limit", "price": "1", "cost": "1", "fee": "0", "vol": "1",
+            "margin": "0", "misc": ""
+        })
+    };
+    let page = |endpoint: &str, offset: u64, result: serde_json::Value| {
+        Mock::given(method("POST"))
+            .and(path(format!("/0/private/{endpoint}")))
+            .and(body_string_contains(format!("ofs={offset}")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "error": [], "result": result
+            })))
+            .expect(1)
+    };
+
+    // A trade arrives after the first page, so T2 comes round again.
+    page("TradesHistory", 0, serde_json::json!({
+        "trades": { "T3": trade(3.0), "T2": trade(2.0) }, "count": 3
+    }))
+    .mount(&mock_server)
+    .await;
+    page("TradesHistory", 2, serde_json::json!({
+        "trades": { "T2": trade(2.0), "T1": trade(1.0) }, "count": 4
+    }))
+    .mount(&mock_server)
+    .await;
+
+    // L5 disappears after the first page, so offset 2 would skip L3.
+    page("Ledgers", 0, serde_json::json!({
+        "ledger": { "L5": entry("R5", 5.0), "L4": entry("R4", 4.0) }, "count": 5
+    }))
+    .mount(&mock_server)
+    .await;
+    page("Ledgers", 2, serde_json::json!({
+        "ledger": { "L2": entry("R2", 2.0), "L1": entry("R1", 1.0) }, "count": 4
+    }))
+    .mount(&mock_server)
+    .await;
+    page("Ledgers", 1, serde_json::json!({
+        "ledger": { "L4": entry("R4", 4.0), "L3": entry("R3", 3.0) }, "count": 4
+    }))
+    .mount(&mock_server)
+    .await;
+    page("Ledgers", 3, serde_json::json!({
+        "ledger": { "L2": entry("R2", 2.0), "L1": entry("R1", 1.0) }, "count": 4
+    }))
+    .mount(&mock_server)
+    .await;
+
+    let client = AuthenticatedClient::new("key", API_SECRET, Some(mock_server.uri())).unwrap();
+
+    let trades: Vec<String> = client
+        .trades_history_stream(&[])
+        .map_ok(|(id, _)| id)
+        .try_collect()
+        .await
+        .expect("all trades");
+    assert_eq!(trades, vec!["T3", "T2", "T1"]);
+
+    let ledger: Vec<String> = client
+        .ledgers_stream(&[])
+        .map_ok(|(id, _)| id)
+        .try_collect()
+        .await
+        .expect("all ledger entries");
+    assert_eq!(ledger, vec!["L5", "L4", "L3", "L2", "L1"]);
+}
+
+#[tokio::test]
+async fn test_export_ledger_ndjson_streams_every_page() {
+    use onise::params::LedgersParams;
+    use wiremock::matchers::body_string_contains;
+
+    let mock_server = MockServer::start().await;
+
+    let entry = |refid: &str, time: f64| {
+        serde_json::json!({
+            "refid": refid, "time": time, "type": "deposit", "subtype": "", "aclass": "currency",
+            "asset": "XXBT", "amount": "0.5", "fee": "0.0", "balance": "0.5"
+        })
+    };
+    let page = |ledger: serde_json::Value| {
+        ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "error": [], "result": { "ledger": ledger, "count": 2 }
+        }))
+    };
+    Mock::given(method("POST"))
+        .and(path("/0/private/Ledgers"))
+        .and(body_string_contains("asset=XXBT&type=deposit&start=1600000000&ofs=0"))
+        .respond_with(page(serde_json::json!({ "L2": entry("R2", 2.0) })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/0/private/Ledgers"))
+        .and(body_string_contains("ofs=1"))
+        .respond_with(page(serde_json::json!({ "L1": entry("R1", 1.0) })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = AuthenticatedClient::new("key", API_SECRET, Some(mock_server.uri())).unwrap();
+    let filters = LedgersParams::new()
+        .with_asset("XXBT")
+        .with_entry_type("deposit")
+        .with_start(1_600_000_000);
+    let mut out = Vec::new();
+    let written = client
+        .export_ledger_ndjson(&mut out, &filters)
+        .await
+        .expect("export");
+    assert_eq!(written, 2);
+
+    let lines: Vec<serde_json::Value> = String::from_utf8(out)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["id"], "L2");
+    assert_eq!(lines[0]["refid"], "R2");
+    assert_eq!(lines[1]["id"], "L1");
+    assert_eq!(lines[1]["asset"], "XXBT");
+}
+
+#[tokio::test]
+async fn test_hedged_read_takes_fastest_response() {
+    use std::time::{Duration, Instant};
+
+    let mock_server = MockServer::start().await;
+    let body = r#"{ "error": [], "result": { "unixtime": 1688669448, "rfc1123": "Thu, 06 Jul 23 18:50:48 +0000" } }"#;
+
+    // First request is slow, the hedge is fast.
+    Mock::given(method("GET"))
+        .and(path("/0/public/Time"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_raw(body, "application/json")
+                .set_delay(Duration::from_secs(5)),
+        )
+        .up_to_n_times(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/0/public/Time"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(body, "application/json"))
+        .mount(&mock_server)
+        .await;
+
+    let client = PublicClient::new(Some(mock_server.uri()))
+        .with_hedged_reads(Duration::from_millis(50));
+    let started = Instant::now();
+    let time = client.get_server_time().await.expect("hedge answers");
+    assert_eq!(time.unixtime, 1688669448);
+    assert!(started.elapsed() < Duration::from_secs(2));
+    assert_eq!(mock_server.received_requests().await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_metrics_record_endpoint_latency() {
+    let mock_server = MockServer::start().await;
+    let body = r#"{ "error": [], "result": { "unixtime": 1688669448, "rfc1123": "Thu, 06 Jul 23 18:50:48 +0000" } }"#;
+    Mock::given(method("GET"))
+        .and(path("/0/public/Time"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_raw(body, "application/json")
+                .set_delay(std::time::Duration::from_millis(30)),
+        )
+        .mount(&mock_server)
+        .await;
+
+    let client = PublicClient::new(Some(mock_server.uri()));
+    client.get_server_time().await.expect("Should succeed");
+    client.get_server_time().await.expect("Should succeed");
+
+    let latency = client.metrics().latency();
+    assert_eq!(latency.connect.count, 1, "connection is pooled");
+    let time = &latency.endpoints["/0/public/Time"];
+    assert_eq!(time.ttfb.count, 2);
+    assert_eq!(time.total.count, 2);
+    assert!(time.ttfb.min.unwrap() >= std::time::Duration::from_millis(30));
+    assert!(time.total.max >= time.ttfb.max);
+    assert!(time.total.quantile(0.99).is_some());
+
+    client.metrics().reset();
+    assert!(client.metrics().endpoint_latency("/0/public/Time").is_none());
+}
+
+#[tokio::test]
+async fn test_metrics_count_kraken_error_codes() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/0/private/AddOrder"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "error": ["EOrder:Insufficient funds"]
+        })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/0/private/Balance"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "error": ["EGeneral:Invalid arguments:volume"]
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let client = AuthenticatedClient::new("key", API_SECRET, Some(mock_server.uri())).unwrap();
+    let order = [("pair", "XBTUSD"), ("type", "buy"), ("ordertype", "market"), ("volume", "1")];
+    assert!(client.add_order(&order).await.is_err());
+    assert!(client.add_order(&order).await.is_err());
+    assert!(client.get_balance().await.is_err());
+
+    let metrics = client.metrics();
+    let funds = metrics.error_count("EOrder:Insufficient funds").expect("counted");
+    assert_eq!(funds.count, 2);
+    assert!(funds.last_seen >= funds.first_seen);
+    let counts = metrics.error_counts();
+    assert_eq!(counts.len(), 2);
+    assert_eq!(counts["EGeneral:Invalid arguments"].count, 1);
+
+    metrics.reset();
+    assert!(metrics.error_counts().is_empty());
+}
+
+#[tokio::test]
+async fn test_invalid_secret_fails_before_sending() {
+    let mock_server = MockServer::start().await;
+    let err = AuthenticatedClient::new("key", "not base64!", Some(mock_server.uri()))
+        .expect_err("bad secret");
+    assert!(matches!(err, onise::error::KrakenError::InvalidUsage(_)));
+
+    let sub = onise::Authenticated::new("sub-key", "not base64!").expect_err("bad secret");
+    assert!(matches!(sub, onise::error::KrakenError::InvalidUsage(_)));
+    assert!(mock_server.received_requests().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_call_credentials_override_one_call() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/0/private/Balance"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "error": [], "result": { "ZUSD": "10.0" }
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let manager = AuthenticatedClient::new("manager-key", API_SECRET, Some(mock_server.uri())).unwrap();
+    let sub = onise::Authenticated::new("sub-key", API_SECRET).unwrap();
+
+    manager.get_balance().await.expect("Should succeed");
+    manager
+        .with_call_credentials(&sub)
+        .get_balance()
+        .await
+        .expect("Should succeed");
+    manager.get_balance().await.expect("Should succeed");
+
+    let keys: Vec<String> = mock_server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .map(|request| request.headers["API-Key"].to_str().unwrap().to_string())
+        .collect();
+    assert_eq!(keys, ["manager-key", "sub-key", "manager-key"]);
+    assert_eq!(manager.api_key(), "manager-key");
+    let latency = manager.metrics().latency();
+    assert_eq!(latency.connect.count, 1, "connection is pooled");
+    assert_eq!(latency.endpoints["/0/private/Balance"].total.count, 3);
+}
+
+#[tokio::test]
+async fn test_cloned_clients_never_reuse_a_nonce() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/0/private/Balance"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "error": [], "result": { "ZUSD": "10.0" }
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let client = AuthenticatedClient::new("key", API_SECRET, Some(mock_server.uri())).unwrap();
+    let calls = (0..32).map(|_| {
+        let client = client.clone();
+        tokio::spawn(async move { client.get_balance().await })
+    });
+    for call in calls.collect::<Vec<_>>() {
+        call.await.unwrap().expect("Should succeed");
+    }
+
+    let mut nonces: Vec<u64> = mock_server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .map(|request| {
+            let body = std::str::from_utf8(&request.body).unwrap();
+            body.strip_prefix("nonce=").unwrap().parse().unwrap()
+        })
+        .collect();
+    nonces.sort_unstable();
+    nonces.dedup();
+    assert_eq!(nonces.len(), 32);
+    assert!(client.nonce_source().next() > nonces[31]);
+}
+
+#[tokio::test]
+async fn test_futures_client_public_and_signed_endpoints() {
+    use onise::error::KrakenError;
+    use onise::futures_client::KrakenFuturesClient;
+    use onise::signing::sign_futures;
+    use wiremock::matchers::query_param;
+
+    let mock_server = MockServer::start().await;
+    let ok = |body: serde_json::Value| ResponseTemplate::new(200).set_body_json(body);
+    Mock::given(method("GET"))
+        .and(path("/derivatives/api/v3/tickers"))
+        .respond_with(ok(serde_json::json!({
+            "result": "success",
+            "tickers": [
+                {"symbol": "PF_XBTUSD", "tag": "perpetual", "pair": "XBT:USD", "last": 64000.5,
+                 "markPrice": 64001.0, "bid": 64000.0, "bidSize": 1.2, "ask": 64001.5,
+                 "askSize": 0.4, "fundingRate": 0.0000123, "suspended": false},
+                {"symbol": "in_xbtusd", "last": 63990.0, "lastTime": "2024-05-01T00:00:00.000Z"}
+            ],
+            "serverTime": "2024-05-01T00:00:01.000Z"
+        })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/derivatives/api/v3/orderbook"))
+        .and(query_param("symbol", "PF_XBTUSD"))
+        .respond_with(ok(serde_json::json!({
+            "result": "success",
+            "orderBook": {"bids": [[64000.0, 1.2], [63999.5, 3]], "asks": [[64001.5, 0.4]]}
+        })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/derivatives/api/v3/history"))
+        .respond_with(ok(serde_json::json!({
+            "result": "error",
+            "error": "apiLimitExceeded"
+        })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/derivatives/api/v3/openpositions"))
+        .respond_with(ok(serde_json::json!({
+            "result": "success",
+            "openPositions": [{"side": "short", "symbol": "PF_XBTUSD", "price": 64100.0,
+                               "fillTime": "2024-05-01T00:00:00.000Z", "size": 0.5}]
+        })))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/derivatives/api/v3/instruments"))
+        .respond_with(ok(serde_json::json!({
+            "result": "success",
+            "instruments": [{
+                "symbol": "PF_XBTUSD", "type": "flexible_futures", "underlying": "rr_xbtusd",
+                "tickSize": 0.5, "contractSize": 1, "tradeable": true,
+                "contractValueTradePrecision": 4, "postOnly": false,
+                "marginLevels": [
+                    {"numNonContractUnits": 0, "initialMargin": 0.02, "maintenanceMargin": 0.01}
+                ]
+            }]
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let client = KrakenFuturesClient::new(Some(mock_server.uri()));
+    let instruments = client.instruments().await.expect("instruments");
+    let perpetual = &instruments.instruments[0];
+    assert_eq!(perpetual.instrument_type, "flexible_futures");
+    assert_eq!(perpetual.tick_size, Some(0.5));
+    assert_eq!(perpetual.margin_levels[0].contracts, Some(0.0));
+
+    let tickers = client.tickers().await.expect("tickers");
+    assert_eq!(tickers.tickers.len(), 2);
+    assert_eq!(tickers.tickers[0].funding_rate, Some(0.0000123));
+    // Indices only have a last price
+    assert_eq!(tickers.tickers[1].bid, None);
+
+    let book = client.order_book("PF_XBTUSD").await.expect("orderbook");
+    assert_eq!(book.order_book.bids[1], (63999.5, 3.0));
+    assert_eq!(book.order_book.asks, [(64001.5, 0.4)]);
+
+    let err = client.trade_history("PF_XBTUSD", None).await.unwrap_err();
+    assert!(matches!(err.inner(), KrakenError::RateLimitExceeded { .. }));
+
+    let err = client.open_positions().await.unwrap_err();
+    assert!(matches!(err, KrakenError::InvalidUsage(_)));
+
+    let client = client.with_credentials("futures-key", API_SECRET).unwrap();
+    let positions = client.open_positions().await.expect("openpositions");
+    assert_eq!(positions.open_positions[0].side, "short");
+    assert_eq!(positions.open_positions[0].size, 0.5);
+
+    let requests = mock_server.received_requests().await.unwrap();
+    let signed = requests.last().unwrap();
+    let header = |name: &str| signed.headers[name].to_str().unwrap().to_string();
+    assert_eq!(header("APIKey"), "futures-key");
+    let nonce = header("Nonce");
+    // The signature covers the path without `/derivatives`
+    let expected = sign_futures(API_SECRET, "/api/v3/openpositions", &nonce, "").unwrap();
+    assert_eq!(header("Authent"), expected);
+}
+
+#[tokio::test]
+async fn test_client_builder_configures_http() {
+    use onise::error::KrakenError;
+    use onise::rest_client::KrakenClient;
+    use std::time::Duration;
+    use wiremock::matchers::header;
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/0/public/Time"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(5)))
+        .mount(&mock_server)
+        .await;
+    let body = r#"{ "error": [], "result": { "ZUSD": "100.0000" } }"#;
+    Mock::given(method("POST"))
+        .and(path("/0/private/Balance"))
+        .and(header("User-Agent", "desk-bot/1.0"))
+        .and(header("API-Key", "builder-key"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(body, "application/json"))
+        .mount(&mock_server)
+        .await;
+
+    let client = KrakenClient::builder()
+        .base_url(mock_server.uri())
+        .user_agent("desk-bot/1.0")
+        .timeout(Duration::from_millis(200))
+        .connect_timeout(Duration::from_secs(1))
+        .credentials("builder-key", "c2VjcmV0")
+        .unwrap()
+        .build()
+        .unwrap();
+    assert_eq!(client.base_url(), mock_server.uri());
+    let balance = client.get_balance().await.unwrap();
+    assert_eq!(balance.balances["ZUSD"], "100.0000");
+
+    let err = client.get_server_time().await.unwrap_err();
+    assert!(
+        matches!(err.inner(), KrakenError::Reqwest(e) if e.is_timeout()),
+        "{err:?}"
+    );
+
+    let bad_proxy = KrakenClient::builder().proxy("not a proxy url").build();
+    assert!(matches!(bad_proxy, Err(KrakenError::Reqwest(_))));
+}
+
+#[tokio::test]
+async fn test_metrics_usage_by_caller_and_bucket() {
+    use onise::clock::MockClock;
+    use onise::metrics::{UsageBucket, DEFAULT_CALLER};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let mock_server = MockServer::start().await;
+    let body = r#"{ "error": [], "result": { "unixtime": 1688669448, "rfc1123": "Thu, 06 Jul 23 18:50:48 +0000" } }"#;
+    Mock::given(method("GET"))
+        .and(path("/0/public/Time"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(body, "application/json"))
+        .mount(&mock_server)
+        .await;
+
+    // 30 seconds before the top of an hour
+    let hour = 1_699_999_200;
+    let clock = MockClock::new();
+    clock.set_system_time(UNIX_EPOCH + Duration::from_secs(hour + 3570));
+    let ingest = PublicClient::new(Some(mock_server.uri()))
+        .with_clock(clock.clone())
+        .with_caller("ingest");
+    let reporting = ingest.clone().with_caller("reporting");
+    let untagged = PublicClient::new(Some(mock_server.uri()));
+    assert_eq!(untagged.metrics().usage(UsageBucket::Hour).rows.len(), 0);
+
+    ingest.get_server_time().await.unwrap();
+    ingest.get_server_time().await.unwrap();
+    clock.advance(Duration::from_secs(60));
+    ingest.get_server_time().await.unwrap();
+    reporting.get_server_time().await.unwrap();
+
+    let minutes = ingest.metrics().usage(UsageBucket::Minute);
+    assert_eq!(minutes.rows.len(), 3);
+    assert_eq!(minutes.by_caller()["ingest"], 3);
+    assert_eq!(minutes.by_endpoint()["/0/public/Time"], 4);
+    let hours = reporting.metrics().usage(UsageBucket::Hour);
+    let expected = format!(
+        "start,caller,endpoint,calls\n\
+         {hour},ingest,/0/public/Time,2\n\
+         {next},ingest,/0/public/Time,1\n\
+         {next},reporting,/0/public/Time,1\n",
+        next = hour + 3600
+    );
+    assert_eq!(hours.to_csv(), expected);
+
+    untagged.get_server_time().await.unwrap();
+    let hours = untagged.metrics().usage(UsageBucket::Hour);
+    assert_eq!(hours.by_caller()[DEFAULT_CALLER], 1);
+
+    // Counts older than the retention are dropped
+    clock.advance(Duration::from_secs(25 * 60 * 60));
+    ingest.get_server_time().await.unwrap();
+    let hours = ingest.metrics().usage(UsageBucket::Hour);
+    assert_eq!(hours.rows.len(), 1, "{hours:?}");
+}
+
+#[tokio::test]
+async fn test_sandbox_refuses_production_urls() {
+    use onise::environment::{self, is_production_url};
+    use onise::error::KrakenError;
+    use onise::futures_client::KrakenFuturesClient;
+
+    assert!(is_production_url("https://api.kraken.com"));
+    assert!(is_production_url("wss://ws-auth.kraken.com/v2"));
+    assert!(is_production_url("https://FUTURES.kraken.com:443/x"));
+    assert!(!is_production_url("https://demo-futures.kraken.com"));
+    assert!(!is_production_url("http://127.0.0.1:8080/api.kraken.com"));
+
+    let mock_server = MockServer::start().await;
+    let body = r#"{ "error": [], "result": { "unixtime": 1672531199, "rfc1123": "" } }"#;
+    Mock::given(method("GET"))
+        .and(path("/0/public/Time"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(body, "application/json"))
+        .mount(&mock_server)
+        .await;
+
+    // Left on: nothing else in this binary talks to production
+    environment::set_sandbox(true);
+    assert!(environment::is_sandbox());
+
+    let err = PublicClient::new(None).get_server_time().await.unwrap_err();
+    assert!(
+        matches!(&err, KrakenError::ProductionRefused { url } if url == "https://api.kraken.com"),
+        "{err:?}"
+    );
+    let futures = KrakenFuturesClient::new(None).tickers().await.unwrap_err();
+    assert!(matches!(futures, KrakenError::ProductionRefused { .. }));
+    #[cfg(feature = "ws")]
+    {
+        use onise::ws_client::KrakenWsClient;
+        let ws = KrakenWsClient::connect(environment::PRODUCTION_WS_PUBLIC_URL).await;
+        assert!(matches!(ws, Err(KrakenError::ProductionRefused { .. })));
+    }
+
+    // Mock servers are fine
+    let local = PublicClient::new(Some(mock_server.uri()));
+    assert_eq!(local.get_server_time().await.unwrap().unixtime, 1672531199);
+}
@@ -0,0 +1,157 @@
+use onise::error::KrakenError;
+use onise::numeric::{Amount, Lossy};
+
+#[test]
+fn test_lossy_f64_is_explicit() {
+    let cost = String::from("14000.00000");
+    assert_eq!(Amount::from(&cost).to_f64_lossy().unwrap(), 14000.0);
+    let Lossy(fee) = Lossy::<f64>::try_from(Amount("-21.5")).unwrap();
+    assert_eq!(fee, -21.5);
+
+    for bad in ["", "abc", "1.2.3", "NaN", "inf"] {
+        assert!(matches!(
+            Amount(bad).to_f64_lossy(),
+            Err(KrakenError::InvalidAmount(value)) if value == bad
+        ));
+    }
+}
+
+#[cfg(feature = "decimal")]
+#[test]
+fn test_decimal_conversion_is_exact() {
+    use rust_decimal::Decimal;
+
+    let volume = Decimal::try_from(Amount("0.00067643")).unwrap();
+    assert_eq!(volume.to_string(), "0.00067643");
+    // 0.1 + 0.2 stays exact, unlike f64
+    let sum = Amount("0.1").to_decimal().unwrap() + Amount("0.2").to_decimal().unwrap();
+    assert_eq!(sum, Amount("0.3").to_decimal().unwrap());
+    assert_eq!(Amount("1.5e-3").to_decimal().unwrap().to_string(), "0.0015");
+    assert!(Amount("30300,1").to_decimal().is_err());
+}
+
+#[cfg(feature = "bigdecimal")]
+#[test]
+fn test_bigdecimal_conversion_is_exact() {
+    use bigdecimal::BigDecimal;
+
+    let balance = BigDecimal::try_from(Amount("123456789012345678901234567890.123456789")).unwrap();
+    assert_eq!(balance.to_string(), "123456789012345678901234567890.123456789");
+    assert!(Amount("x").to_bigdecimal().is_err());
+}
+
+#[test]
+fn test_exact_number_reads_numbers_and_strings() {
+    use onise::numeric::ExactNumber;
+
+    let userref: ExactNumber = serde_json::from_str("123").unwrap();
+    assert_eq!(userref.as_u64(), Some(123));
+    assert_eq!(serde_json::to_string(&userref).unwrap(), "123");
+
+    let volume: ExactNumber = serde_json::from_str(r#""12345678.123456789""#).unwrap();
+    assert_eq!(volume.to_string(), "12345678.123456789");
+    // The digits an f64 would drop
+    let lossy = volume.amount().to_f64_lossy().unwrap();
+    assert_eq!(lossy.to_string(), "12345678.12345679");
+    assert!(serde_json::from_str::<ExactNumber>(r#""12,5""#).is_err());
+    assert!("-0.5e-3".parse::<ExactNumber>().is_ok());
+}
+
+#[cfg(feature = "arbitrary-precision")]
+#[test]
+fn test_exact_number_round_trips_large_values() {
+    use onise::numeric::ExactNumber;
+
+    for text in ["18446744073709551616", "1688666559.897412345", "0.000000000000000001"] {
+        let number: ExactNumber = serde_json::from_str(text).unwrap();
+        assert_eq!(number.as_str(), text);
+        assert_eq!(serde_json::to_string(&number).unwrap(), text);
+    }
+    // Also exact through a `serde_json::Value`
+    let value: serde_json::Value = serde_json::from_str(r#"{"v":"1","n":1688666559.897412345}"#).unwrap();
+    let number: ExactNumber = serde_json::from_value(value["n"].clone()).unwrap();
+    assert_eq!(number.as_str(), "1688666559.897412345");
+}
+
+#[test]
+fn test_round_amount_is_exact_and_plain() {
+    use onise::numeric::{round_amount, IntoAmount, Rounding};
+
+    let cases = [
+        ("0.30000000000000004", 8, Rounding::Down, "0.3"),
+        ("1.99999999999", 8, Rounding::Nearest, "2"),
+        ("999.96", 1, Rounding::Nearest, "1000"),
+        ("999.96", 1, Rounding::Down, "999.9"),
+        ("2.5E3", 2, Rounding::Down, "2500"),
+        ("7e-9", 8, Rounding::Down, "0"),
+        ("7e-9", 8, Rounding::Nearest, "0.00000001"),
+        ("-0.0000001", 4, Rounding::Down, "0"),
+        ("-1.05", 1, Rounding::Nearest, "-1.1"),
+        (".5", 0, Rounding::Nearest, "1"),
+        ("0012.3400", 4, Rounding::Down, "12.34"),
+    ];
+    for (value, decimals, rounding, expected) in cases {
+        assert_eq!(
+            round_amount(value, decimals, rounding).unwrap(),
+            expected,
+            "{value} to {decimals} ({rounding:?})"
+        );
+    }
+    for bad in ["", "1.2.3", "abc", "1e", "--1", "1e99999"] {
+        assert!(
+            matches!(
+                round_amount(bad, 2, Rounding::Down),
+                Err(KrakenError::InvalidAmount(_))
+            ),
+            "{bad:?}"
+        );
+    }
+
+    assert_eq!(1e-7.into_amount(), "0.0000001");
+    assert_eq!(1e21.into_amount(), "1000000000000000000000");
+    assert_eq!(42u64.into_amount(), "42");
+}
+
+#[cfg(feature = "decimal")]
+#[test]
+fn test_models_expose_decimal_accessors() {
+    use onise::models::{AccountBalanceResponse, OrderBookData, TickerInfo};
+    use onise::ws_models::OrderBookEntry;
+    use rust_decimal::Decimal;
+
+    let ticker: TickerInfo = serde_json::from_str(
+        r#"{"a":["30300.10000","1","1.000"],"b":["30300.00000","2","2.000"],
+            "c":["30300.10000","0.00067643"],"v":["4083.67001100","4412.73601799"],
+            "p":["30265.36730","30264.07910"],"t":[34619,38907],
+            "l":["29868.30000","29868.30000"],"h":["30700.00000","30700.00000"],
+            "o":"30502.80000"}"#,
+    )
+    .unwrap();
+    assert_eq!(ticker.ask_price_decimal().unwrap(), Decimal::new(303001, 1));
+    let spread = ticker.ask_price_decimal().unwrap() - ticker.bid_price_decimal().unwrap();
+    assert_eq!(spread, Decimal::new(1, 1));
+    let last_volume = ticker.last_volume_decimal().unwrap();
+    assert_eq!(last_volume.to_string(), "0.00067643");
+
+    let book: OrderBookData = serde_json::from_str(
+        r#"{"asks":[["30300.10000","1.500",1688666559]],"bids":[["30300.00000","0.1",1688666560]]}"#,
+    )
+    .unwrap();
+    let (price, volume, time) = book.asks_decimal().unwrap()[0];
+    assert_eq!(price, Decimal::new(303001, 1));
+    assert_eq!((volume, time), (Decimal::new(15, 1), 1688666559));
+
+    let balances: AccountBalanceResponse =
+        serde_json::from_str(r#"{"ZUSD":"171288.6158","XXBT":"oops"}"#).unwrap();
+    assert!(matches!(
+        balances.balances_decimal(),
+        Err(KrakenError::InvalidAmount(value)) if value == "oops"
+    ));
+
+    let level = OrderBookEntry {
+        price: "0.1".to_string(),
+        quantity: "0.2".to_string(),
+    };
+    let notional = level.price_decimal().unwrap() * level.quantity_decimal().unwrap();
+    assert_eq!(notional, Decimal::new(2, 2));
+}
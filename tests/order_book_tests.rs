@@ -0,0 +1,159 @@
+use onise::order_book::{scale_price, BookAnalytics, OrderBook, RecentTrades};
+use onise::ws_models::{WsBookMessage, WsTradesMessage};
+
+fn book_msg(json: &str) -> WsBookMessage {
+    serde_json::from_str(json).expect("valid book message")
+}
+
+#[test]
+fn test_snapshot_then_updates() {
+    let mut book = OrderBook::new("BTC/USD", 2);
+    book.apply(&book_msg(
+        r#"{
+            "channel": "book", "type": "snapshot", "symbol": "BTC/USD",
+            "bids": [{"price": "99.5", "quantity": "1"}, {"price": "100.0", "quantity": "2"}],
+            "asks": [{"price": "101.0", "quantity": "3"}, {"price": "100.5", "quantity": "4"}]
+        }"#,
+    ));
+    assert_eq!(book.best_bid().unwrap().price, "100.0");
+    assert_eq!(book.best_ask().unwrap().price, "100.5");
+    assert_eq!(book.spread(), Some(0.5));
+
+    // Remove the best bid, add a better ask; depth 2 trims the worst ask.
+    book.apply(&book_msg(
+        r#"{
+            "channel": "book", "type": "update", "symbol": "BTC/USD",
+            "bids": [{"price": "100.0", "quantity": "0"}],
+            "asks": [{"price": "100.2", "quantity": "1"}]
+        }"#,
+    ));
+    assert_eq!(book.best_bid().unwrap().price, "99.5");
+    let asks: Vec<String> = book.asks().into_iter().map(|l| l.price).collect();
+    assert_eq!(asks, vec!["100.2", "100.5"]);
+
+    // Other symbols are ignored; a new snapshot replaces everything.
+    book.apply(&book_msg(
+        r#"{"channel": "book", "type": "snapshot", "symbol": "ETH/USD", "bids": [], "asks": []}"#,
+    ));
+    assert!(!book.is_empty());
+    book.apply(&book_msg(
+        r#"{"channel": "book", "type": "snapshot", "symbol": "BTC/USD", "bids": [], "asks": []}"#,
+    ));
+    assert!(book.is_empty());
+}
+
+#[test]
+fn test_levels_keyed_by_scaled_price() {
+    assert_eq!(scale_price("100.5", 2), Some(10050));
+    assert_eq!(scale_price("100", 2), Some(10000));
+    assert_eq!(scale_price(".25", 2), Some(25));
+    assert_eq!(scale_price("100.500", 2), Some(10050));
+    assert_eq!(scale_price("100.505", 2), None);
+    assert_eq!(scale_price("-1", 2), None);
+    assert_eq!(scale_price("1e5", 2), None);
+    assert_eq!(scale_price("", 2), None);
+
+    let mut book = OrderBook::new("BTC/USD", 10).with_price_decimals(1);
+    book.apply(&book_msg(
+        r#"{
+            "channel": "book", "type": "snapshot", "symbol": "BTC/USD",
+            "bids": [{"price": "9.9", "quantity": "1"}, {"price": "10.0", "quantity": "2"}],
+            "asks": [{"price": "10.1", "quantity": "3"}, {"price": "10.05", "quantity": "4"}]
+        }"#,
+    ));
+    // "10.05" is finer than the pair's precision and is dropped.
+    assert_eq!(book.asks().len(), 1);
+    // Numeric, not lexicographic, ordering: 10.0 beats 9.9.
+    assert_eq!(book.best_bid().unwrap().price, "10.0");
+
+    // "10.00" is the same level as "10.0".
+    book.apply(&book_msg(
+        r#"{
+            "channel": "book", "type": "update", "symbol": "BTC/USD",
+            "bids": [{"price": "10.00", "quantity": "0.00000000"}], "asks": []
+        }"#,
+    ));
+    let bids: Vec<&str> = book.iter_bids().map(|l| l.price.as_str()).collect();
+    assert_eq!(bids, vec!["9.9"]);
+    assert_eq!(book.spread().map(|s| (s * 10.0).round()), Some(2.0));
+}
+
+#[test]
+fn test_recent_trades_newest_first() {
+    let mut trades = RecentTrades::new("BTC/USD", 2);
+    let msg: WsTradesMessage = serde_json::from_str(
+        r#"{
+            "channel": "trade", "symbol": "BTC/USD",
+            "trades": [
+                {"price": "1", "quantity": "1", "time": 1, "side": "buy"},
+                {"price": "2", "quantity": "1", "time": 2, "side": "sell"},
+                {"price": "3", "quantity": "1", "time": 3, "side": "buy"}
+            ]
+        }"#,
+    )
+    .unwrap();
+    trades.apply(&msg);
+    let prices: Vec<&str> = trades.iter().map(|t| t.price.as_str()).collect();
+    assert_eq!(prices, vec!["3", "2"]);
+}
+
+#[test]
+fn test_book_analytics_follow_each_update() {
+    let close = |a: Option<f64>, b: f64| a.is_some_and(|a| (a - b).abs() < 1e-9);
+    let mut book = OrderBook::new("BTC/USD", 2);
+    assert_eq!(book.analytics(), BookAnalytics::default());
+    book.apply(&book_msg(
+        r#"{
+            "channel": "book", "type": "snapshot", "symbol": "BTC/USD",
+            "bids": [{"price": "100.0", "quantity": "2"}, {"price": "99.5", "quantity": "1"}],
+            "asks": [{"price": "100.5", "quantity": "4"}, {"price": "101.0", "quantity": "3"}]
+        }"#,
+    ));
+    let analytics = book.analytics();
+    // 3 bid vs 7 ask
+    assert!(close(analytics.imbalance, -0.4));
+    // (100.0 × 4 + 100.5 × 2) / 6: leans to the bid, the heavier ask side
+    assert!(close(analytics.microprice, 601.0 / 6.0));
+    // Bids average 299.5 / 3, asks 705 / 7
+    assert!(close(
+        analytics.depth_weighted_mid,
+        (299.5 / 3.0 + 705.0 / 7.0) / 2.0
+    ));
+    assert_eq!(analytics.order_flow_imbalance, 0.0);
+    assert!(close(book.imbalance(1), -1.0 / 3.0));
+    assert!(close(
+        book.clone().with_analytics_levels(1).analytics().imbalance,
+        -1.0 / 3.0
+    ));
+
+    // +3 at the best bid, 3 pulled from the best ask: both buying pressure
+    book.apply(&book_msg(
+        r#"{
+            "channel": "book", "type": "update", "symbol": "BTC/USD",
+            "bids": [{"price": "100.0", "quantity": "5"}],
+            "asks": [{"price": "100.5", "quantity": "1"}]
+        }"#,
+    ));
+    assert_eq!(book.analytics().order_flow_imbalance, 6.0);
+    // A new, lower best ask of 2
+    book.apply(&book_msg(
+        r#"{"channel": "book", "type": "update", "symbol": "BTC/USD", "bids": [],
+            "asks": [{"price": "100.2", "quantity": "2"}]}"#,
+    ));
+    assert_eq!(book.analytics().order_flow_imbalance, 4.0);
+    assert!(close(
+        book.analytics().microprice,
+        (100.0 * 2.0 + 100.2 * 5.0) / 7.0
+    ));
+    // The best bid of 5 disappears
+    book.apply(&book_msg(
+        r#"{"channel": "book", "type": "update", "symbol": "BTC/USD",
+            "bids": [{"price": "100.0", "quantity": "0"}], "asks": []}"#,
+    ));
+    assert_eq!(book.analytics().order_flow_imbalance, -1.0);
+
+    book.apply(&book_msg(
+        r#"{"channel": "book", "type": "snapshot", "symbol": "BTC/USD", "bids": [], "asks": []}"#,
+    ));
+    assert_eq!(book.analytics(), BookAnalytics::default());
+}
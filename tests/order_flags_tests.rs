@@ -0,0 +1,33 @@
+use onise::error::KrakenError;
+use onise::order_flags::OrderFlags;
+
+#[test]
+fn test_order_flags_wire_form() {
+    assert_eq!(OrderFlags::empty().to_string(), "");
+    assert_eq!(
+        (OrderFlags::FCIQ | OrderFlags::POST).to_string(),
+        "post,fciq"
+    );
+    assert_eq!(OrderFlags::all().to_string(), "post,fcib,fciq,nompp,viqc");
+
+    let parsed: OrderFlags = "viqc, POST".parse().unwrap();
+    assert_eq!(parsed, OrderFlags::VIQC | OrderFlags::POST);
+    assert_eq!("".parse::<OrderFlags>().unwrap(), OrderFlags::empty());
+    assert!(matches!(
+        "post,iceberg".parse::<OrderFlags>(),
+        Err(KrakenError::InvalidUsage(_))
+    ));
+    assert_eq!(
+        OrderFlags::from_oflags_lossy("fcib,iceberg,nompp"),
+        OrderFlags::FCIB | OrderFlags::NOMPP
+    );
+}
+
+#[test]
+fn test_order_flags_validate() {
+    assert!((OrderFlags::POST | OrderFlags::FCIQ).validate().is_ok());
+    assert!(matches!(
+        (OrderFlags::FCIB | OrderFlags::FCIQ).validate(),
+        Err(KrakenError::InvalidUsage(_))
+    ));
+}
@@ -0,0 +1,236 @@
+use onise::error::KrakenError;
+use onise::testkit::MockKraken;
+
+#[tokio::test]
+async fn test_typed_order_query_params() {
+    use onise::params::{CloseTime, ClosedOrdersParams, OpenOrdersParams, QueryOrdersParams};
+
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+    let c = kraken.authenticated_client();
+
+    c.open_orders(&OpenOrdersParams::new().with_trades(true).with_userref(7))
+        .await
+        .expect("OpenOrders");
+    c.closed_orders(
+        &ClosedOrdersParams::new()
+            .with_start(1688666559)
+            .with_end("OQCLML-BW3P3-BUCMWZ")
+            .with_closetime(CloseTime::Close),
+    )
+    .await
+    .expect("ClosedOrders");
+    c.query_orders(&QueryOrdersParams::new(["OA", "OB"]).with_consolidate_taker(false))
+        .await
+        .expect("QueryOrders");
+
+    let bodies: Vec<String> = kraken
+        .received_requests()
+        .await
+        .iter()
+        .map(|r| String::from_utf8(r.body.clone()).unwrap())
+        .collect();
+    assert!(bodies[0].ends_with("&trades=true&userref=7"), "{}", bodies[0]);
+    assert!(bodies[1].ends_with("&start=1688666559&end=OQCLML-BW3P3-BUCMWZ&closetime=close"));
+    assert!(bodies[2].ends_with("&txid=OA%2COB&consolidate_taker=false"));
+
+    assert_eq!(c.open_orders_for_pair("xbt/usd").await.unwrap().len(), 1);
+    assert!(c.open_orders_for_pair("ETHUSD").await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_typed_amend_order() {
+    use onise::params::AmendOrderRequest;
+
+    let invalid = [
+        AmendOrderRequest::default().with_limit_price("100"),
+        AmendOrderRequest {
+            cl_ord_id: Some("mine".to_string()),
+            ..AmendOrderRequest::by_txid("OA").with_order_qty("1")
+        },
+        AmendOrderRequest::by_txid("OA"),
+        AmendOrderRequest::by_txid("OA").with_order_qty("-1"),
+        AmendOrderRequest::by_txid("OA").with_limit_price("10%%"),
+        AmendOrderRequest::by_txid("OA").with_trigger_price("+"),
+        AmendOrderRequest::by_txid("OA").with_order_qty("1").with_post_only(true),
+    ];
+    for request in &invalid {
+        assert!(
+            matches!(request.validate(), Err(KrakenError::InvalidUsage(_))),
+            "{request:?}"
+        );
+    }
+
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+    let c = kraken.authenticated_client();
+
+    // Rejected locally: nothing reaches the server
+    assert!(c.amend(&invalid[0]).await.is_err());
+    assert!(kraken.received_requests().await.is_empty());
+
+    let request = AmendOrderRequest::by_cl_ord_id("my-order-1")
+        .with_limit_price("27600.0")
+        .with_trigger_price("-1.5%")
+        .with_post_only(true);
+    let amended = c.amend(&request).await.expect("AmendOrder");
+    assert_eq!(amended.count, 1);
+
+    let received = kraken.received_requests().await;
+    let body = String::from_utf8(received[0].body.clone()).unwrap();
+    assert!(
+        body.ends_with("&cl_ord_id=my-order-1&limit_price=27600.0&trigger_price=-1.5%25&post_only=true"),
+        "{body}"
+    );
+}
+
+#[tokio::test]
+async fn test_typed_add_and_edit_order_flags() {
+    use onise::exchange::Side;
+    use onise::order_flags::OrderFlags;
+    use onise::params::{AddOrderRequest, EditOrderRequest};
+
+    let invalid = [
+        AddOrderRequest::limit("XBTUSD", Side::Buy, "1", "27500")
+            .with_oflags(OrderFlags::FCIB | OrderFlags::FCIQ),
+        AddOrderRequest::market("XBTUSD", Side::Buy, "1").with_oflags(OrderFlags::POST),
+        AddOrderRequest::limit("XBTUSD", Side::Buy, "1", "27500").with_oflags(OrderFlags::NOMPP),
+        AddOrderRequest::new("XBTUSD", Side::Sell, "stop-loss", "1"),
+        AddOrderRequest::market("XBTUSD", Side::Buy, "1e3"),
+    ];
+    for request in &invalid {
+        assert!(
+            matches!(request.validate(), Err(KrakenError::InvalidUsage(_))),
+            "{request:?}"
+        );
+    }
+
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+    let c = kraken.authenticated_client();
+
+    let request = AddOrderRequest::limit("XBTUSD", Side::Buy, "1.25", "27500")
+        .with_oflags(OrderFlags::POST | OrderFlags::FCIQ)
+        .with_cl_ord_id("c-1");
+    c.add(&request).await.expect("AddOrder");
+    let edit = EditOrderRequest::new("OHYO67-6LP66-HMQ437", "XBTUSD")
+        .with_price("27600")
+        .with_oflags(OrderFlags::POST);
+    c.edit(&edit).await.expect("EditOrder");
+
+    let bodies: Vec<String> = kraken
+        .received_requests()
+        .await
+        .iter()
+        .map(|r| String::from_utf8(r.body.clone()).unwrap())
+        .collect();
+    assert!(
+        bodies[0].ends_with(
+            "&pair=XBTUSD&type=buy&ordertype=limit&volume=1.25&price=27500&oflags=post%2Cfciq&cl_ord_id=c-1"
+        ),
+        "{}",
+        bodies[0]
+    );
+    assert!(bodies[1].ends_with("&price=27600&oflags=post"), "{}", bodies[1]);
+
+    let orders = c.get_open_orders(&[]).await.expect("OpenOrders");
+    let order = orders.open.values().next().unwrap();
+    assert_eq!(order.flags(), OrderFlags::FCIQ);
+}
+
+#[tokio::test]
+async fn test_typed_margin_and_trigger_orders() {
+    use onise::exchange::Side;
+    use onise::params::{AddOrderRequest, Trigger};
+
+    let invalid = [
+        AddOrderRequest::stop_loss("XBTUSD", Side::Sell, "1", "#+5"),
+        AddOrderRequest::take_profit("XBTUSD", Side::Sell, "1", "5%%"),
+        AddOrderRequest::new("XBTUSD", Side::Sell, "trailing-stop", "1").with_price("-50"),
+        AddOrderRequest::limit("XBTUSD", Side::Buy, "1", "27500").with_trigger(Trigger::Index),
+        AddOrderRequest::market("XBTUSD", Side::Buy, "1").with_leverage("2.5"),
+        AddOrderRequest::market("XBTUSD", Side::Sell, "1").with_reduce_only(true),
+    ];
+    for request in &invalid {
+        assert!(
+            matches!(request.validate(), Err(KrakenError::InvalidUsage(_))),
+            "{request:?}"
+        );
+    }
+    let valid = [
+        AddOrderRequest::stop_loss("XBTUSD", Side::Sell, "1", "#2.5%"),
+        AddOrderRequest::take_profit("XBTUSD", Side::Sell, "1", "+500"),
+        AddOrderRequest::new("XBTUSD", Side::Sell, "trailing-stop", "1").with_price("+1%"),
+        AddOrderRequest::new("XBTUSD", Side::Buy, "stop-loss-limit", "1")
+            .with_price("27000")
+            .with_price2("-20"),
+    ];
+    for request in &valid {
+        request.validate().expect("valid");
+    }
+
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+    let c = kraken.authenticated_client();
+
+    let request = AddOrderRequest::stop_loss("XBTUSD", Side::Sell, "1", "-2%")
+        .with_trigger(Trigger::Index)
+        .with_leverage("3:1")
+        .with_reduce_only(true);
+    c.add(&request).await.expect("AddOrder");
+
+    let received = kraken.received_requests().await;
+    let body = String::from_utf8(received[0].body.clone()).unwrap();
+    assert!(
+        body.ends_with(
+            "&ordertype=stop-loss&volume=1&price=-2%25&leverage=3%3A1&reduce_only=true&trigger=index"
+        ),
+        "{body}"
+    );
+}
+
+#[tokio::test]
+async fn test_amount_formatter_trims_typed_orders_to_pair_precision() {
+    use onise::exchange::Side;
+    use onise::numeric::AmountFormatter;
+    use onise::params::{AddOrderRequest, EditOrderRequest};
+    use std::time::Duration;
+
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+    let c = kraken
+        .authenticated_client()
+        .with_metadata_cache(Duration::from_secs(60))
+        .with_amount_formatter(AmountFormatter::default());
+
+    // The fixture's XBTUSD has 1 price and 8 lot decimals
+    let precision = c.pair_precision("XBTUSD").await.expect("AssetPairs");
+    assert_eq!(precision.price_decimals, 1);
+    assert_eq!(precision.volume_decimals, 8);
+
+    let order = AddOrderRequest::limit("XBTUSD", Side::Buy, 0.1 + 0.2, 27_500.06);
+    c.add(&order).await.expect("AddOrder");
+    let edit = EditOrderRequest::new("OHYO67-6LP66-HMQ437", "XBTUSD")
+        .with_volume("0.123456789")
+        .with_price("+2.50%");
+    c.edit(&edit).await.expect("EditOrder");
+
+    let bodies: Vec<String> = kraken
+        .received_requests()
+        .await
+        .iter()
+        .filter(|r| r.url.path().starts_with("/0/private"))
+        .map(|r| String::from_utf8(r.body.clone()).unwrap())
+        .collect();
+    assert!(
+        bodies[0].ends_with("&volume=0.3&price=27500.1"),
+        "{}",
+        bodies[0]
+    );
+    // Volumes round down; percentage offsets are left alone
+    assert!(
+        bodies[1].contains("&volume=0.12345678&price=%2B2.50%25"),
+        "{}",
+        bodies[1]
+    );
+}
@@ -0,0 +1,149 @@
+use onise::testkit::MockKraken;
+
+#[tokio::test]
+async fn test_rest_polling_streams_yield_changes_only() {
+    use futures_util::StreamExt;
+    use onise::polling::RestPoller;
+    use std::time::Duration;
+
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+    let poller = RestPoller::new(kraken.authenticated_client(), Duration::from_millis(20));
+    let quiet = Duration::from_millis(120);
+
+    let mut tickers = poller.ticker_stream("XBT/USD");
+    let ticker = tickers.next().await.unwrap();
+    assert_eq!(ticker.symbol, "XBT/USD");
+    assert_eq!(ticker.best_ask_price, "30300.10000");
+    assert_eq!(ticker.trades_24h, 38907);
+    // Unchanged polls are swallowed
+    assert!(tokio::time::timeout(quiet, tickers.next()).await.is_err());
+
+    let mut books = poller.book_stream("XBT/USD", 10);
+    let book = books.next().await.unwrap();
+    assert!(book.is_snapshot());
+    assert!(!book.asks.is_empty() && !book.bids.is_empty());
+
+    let mut orders = poller.open_orders_stream();
+    assert!(orders.next().await.unwrap().contains_key("OQCLML-BW3P3-BUCMWZ"));
+    let mut balances = poller.balances_stream();
+    assert_eq!(balances.next().await.unwrap().channel, "balances");
+
+    // The order fills and the balance moves: both streams yield again
+    kraken.server().reset().await;
+    kraken
+        .mock_result("/0/private/OpenOrders", serde_json::json!({ "open": {} }))
+        .await;
+    kraken
+        .mock_result("/0/private/Balance", serde_json::json!({ "ZUSD": "1.0" }))
+        .await;
+    assert!(orders.next().await.unwrap().is_empty());
+    assert_eq!(balances.next().await.unwrap().balances["ZUSD"], "1.0");
+    // Failed polls (the ticker is no longer mocked) neither yield nor end the stream
+    assert!(tokio::time::timeout(quiet, tickers.next()).await.is_err());
+}
+
+#[tokio::test]
+async fn test_rest_poller_as_market_data_provider() {
+    use futures_util::StreamExt;
+    use onise::market_data::MarketDataProvider;
+    use onise::polling::RestPoller;
+    use std::time::Duration;
+
+    // Strategy code only sees the trait
+    async fn last_close(data: &impl MarketDataProvider, symbol: &str) -> String {
+        let mut candles = data.candles(symbol, 1).await.unwrap();
+        candles.next().await.unwrap().data.last().unwrap().close.clone()
+    }
+
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+    let poller = RestPoller::new(kraken.public_client(), Duration::from_millis(20));
+
+    assert_eq!(last_close(&poller, "XBTUSD").await, "30300.0");
+
+    let mut trades = poller.trades("XBTUSD").await.unwrap();
+    let first = trades.next().await.unwrap();
+    assert_eq!(first.symbol, "XBTUSD");
+    assert_eq!(first.trades.len(), 2);
+    assert_eq!(first.trades[0].side, "buy");
+    assert_eq!(first.trades[1].time, 1688669598);
+
+    // Later polls pass Kraken's `since` cursor; the mock repeats the same
+    // trades, which are not yielded again
+    assert!(tokio::time::timeout(Duration::from_millis(60), trades.next())
+        .await
+        .is_err());
+    let received = kraken.received_requests().await;
+    let polled_trades: Vec<_> = received
+        .iter()
+        .filter(|r| r.url.path() == "/0/public/Trades")
+        .collect();
+    assert!(polled_trades.len() >= 2);
+    assert_eq!(
+        polled_trades[1].url.query(),
+        Some("pair=XBTUSD&since=1688671969993150842")
+    );
+}
+
+#[tokio::test]
+async fn test_candle_gaps_are_filled_over_rest() {
+    use futures_util::StreamExt;
+    use onise::polling::RestPoller;
+    use onise::ws_models::{CandleData, WsCandlesMessage};
+    use serde_json::json;
+    use std::time::Duration;
+
+    let candle = |time: u64| CandleData {
+        time,
+        open: "1".to_string(),
+        high: "1".to_string(),
+        low: "1".to_string(),
+        close: "1".to_string(),
+        volume: "1".to_string(),
+    };
+    let message = |times: &[u64]| WsCandlesMessage {
+        channel: "ohlc".to_string(),
+        symbol: "BTC/USD".to_string(),
+        interval: 1,
+        data: times.iter().map(|&t| candle(t)).collect(),
+    };
+    let row = |time: u64| json!([time, "2", "2", "2", "2", "2", "5", 3]);
+
+    let kraken = MockKraken::start().await;
+    kraken
+        .mock_result(
+            "/0/public/OHLC",
+            json!({
+                "BTC/USD": [row(60), row(120), row(180), row(240), row(300)],
+                "last": 300
+            }),
+        )
+        .await;
+    let poller = RestPoller::new(kraken.public_client(), Duration::from_secs(1));
+
+    // 60 and 120 arrive, the socket drops, and it resumes at 300
+    let live = futures_util::stream::iter(vec![
+        message(&[60]),
+        message(&[60, 120]),
+        message(&[300]),
+        message(&[300, 360]),
+    ]);
+    let filled: Vec<_> = poller.fill_candle_gaps(live).collect().await;
+    let times: Vec<Vec<u64>> = filled
+        .iter()
+        .map(|m| m.data.iter().map(|c| c.time).collect())
+        .collect();
+    assert_eq!(
+        times,
+        vec![vec![60], vec![60, 120], vec![180, 240], vec![300], vec![300, 360]]
+    );
+    assert_eq!(filled[2].data[0].volume, "5");
+
+    let received = kraken.received_requests().await;
+    assert_eq!(received.len(), 1);
+    assert_eq!(
+        received[0].url.query(),
+        Some("pair=BTC%2FUSD&interval=1&since=120")
+    );
+}
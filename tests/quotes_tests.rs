@@ -0,0 +1,245 @@
+use std::time::Duration;
+
+use onise::error::KrakenError;
+use onise::exchange::{OrderKind, Side};
+use onise::order_book::OrderBook;
+use onise::quotes::QuoteCache;
+use onise::ws_models::{OrderBookEntry, WsBookMessage};
+
+mod common;
+use common::ticker;
+
+fn price(kind: &OrderKind) -> &str {
+    match kind {
+        OrderKind::Limit { price } => price,
+        OrderKind::Market => panic!("expected a limit order"),
+    }
+}
+
+#[test]
+fn test_order_helpers_price_off_the_touch() {
+    let quotes = QuoteCache::new();
+    quotes.update_ticker(ticker("BTC/USD", "30000.0", "30010.0"));
+
+    let join = quotes.limit_at_touch("BTC/USD", Side::Buy, "0.5").unwrap();
+    assert_eq!(price(&join.kind), "30000.0");
+    assert_eq!(join.volume, "0.5");
+
+    let cross = quotes
+        .marketable_limit("BTC/USD", Side::Buy, "0.5", 0.001)
+        .unwrap();
+    assert_eq!(price(&cross.kind), "30040.0");
+    let cross = quotes
+        .marketable_limit("BTC/USD", Side::Sell, "0.5", 0.001)
+        .unwrap();
+    assert_eq!(price(&cross.kind), "29970.0");
+
+    // A newer book takes precedence over the ticker
+    let mut book = OrderBook::new("BTC/USD", 10);
+    book.apply(&WsBookMessage {
+        channel: "book".to_string(),
+        update_type: Some("snapshot".to_string()),
+        symbol: "BTC/USD".to_string(),
+        bids: vec![OrderBookEntry {
+            price: "30005.5".to_string(),
+            quantity: "2".to_string(),
+        }],
+        asks: vec![OrderBookEntry {
+            price: "30006.5".to_string(),
+            quantity: "1".to_string(),
+        }],
+    });
+    quotes.update_book(book);
+    let touch = quotes.touch("BTC/USD").unwrap();
+    assert_eq!(
+        (touch.bid.as_str(), touch.ask.as_str()),
+        ("30005.5", "30006.5")
+    );
+}
+
+#[tokio::test]
+async fn test_stale_quotes_are_refused() {
+    let quotes = QuoteCache::new().with_max_age(Duration::from_millis(50));
+    let err = quotes
+        .limit_at_touch("ETH/USD", Side::Buy, "1")
+        .unwrap_err();
+    assert!(
+        matches!(&err, KrakenError::StaleMarketData { symbol, age: None, .. } if symbol == "ETH/USD"),
+        "{err:?}"
+    );
+
+    quotes.update_ticker(ticker("ETH/USD", "2000.00", "2000.50"));
+    assert!(quotes.limit_at_touch("ETH/USD", Side::Buy, "1").is_ok());
+
+    // The feed stalls
+    tokio::time::sleep(Duration::from_millis(80)).await;
+    let err = quotes
+        .marketable_limit("ETH/USD", Side::Sell, "1", 0.01)
+        .unwrap_err();
+    assert!(
+        matches!(err, KrakenError::StaleMarketData { age: Some(age), .. } if age >= Duration::from_millis(50)),
+        "{err:?}"
+    );
+
+    // A fresh quote through a followed feed clears it
+    let feed = quotes.follow_tickers(futures_util::stream::iter(vec![ticker(
+        "ETH/USD", "2001.00", "2001.50",
+    )]));
+    feed.await.unwrap();
+    assert_eq!(quotes.touch("ETH/USD").unwrap().bid, "2001.00");
+}
+
+#[test]
+fn test_quote_age_follows_the_clock() {
+    use onise::clock::MockClock;
+
+    let clock = MockClock::new();
+    let quotes = QuoteCache::new()
+        .with_max_age(Duration::from_secs(5))
+        .with_clock(clock.clone());
+    quotes.update_ticker(ticker("SOL/USD", "20.00", "20.01"));
+
+    clock.advance(Duration::from_secs(5));
+    assert_eq!(quotes.touch("SOL/USD").unwrap().age, Duration::from_secs(5));
+    clock.advance(Duration::from_millis(1));
+    assert!(matches!(
+        quotes.touch("SOL/USD"),
+        Err(KrakenError::StaleMarketData { age: Some(age), .. }) if age == Duration::from_millis(5001)
+    ));
+}
+
+#[test]
+fn test_market_order_guard() {
+    use onise::quotes::MarketOrderGuard;
+
+    // Without a guard, market orders don't need a quote
+    let order = QuoteCache::new().market_buy("BTC/USD", "1").unwrap();
+    assert_eq!(order.kind, OrderKind::Market);
+
+    let quotes = QuoteCache::new().with_market_guard(
+        MarketOrderGuard::default()
+            .with_max_spread_bps(10.0)
+            .with_max_slippage_bps(5.0),
+    );
+    assert!(matches!(
+        quotes.market_sell("BTC/USD", "1").unwrap_err(),
+        KrakenError::StaleMarketData { age: None, .. }
+    ));
+
+    quotes.update_ticker(ticker("BTC/USD", "30000.0", "30060.0"));
+    let err = quotes.market_buy("BTC/USD", "0.5").unwrap_err();
+    assert!(
+        matches!(err, KrakenError::SpreadTooWide { spread_bps, .. } if (spread_bps - 19.98).abs() < 0.01),
+        "{err:?}"
+    );
+
+    let level = |price: &str, quantity: &str| OrderBookEntry {
+        price: price.to_string(),
+        quantity: quantity.to_string(),
+    };
+    let mut book = OrderBook::new("BTC/USD", 10);
+    book.apply(&WsBookMessage {
+        channel: "book".to_string(),
+        update_type: Some("snapshot".to_string()),
+        symbol: "BTC/USD".to_string(),
+        bids: vec![level("30000.0", "1"), level("29990.0", "1")],
+        asks: vec![level("30010.0", "1"), level("30040.0", "1")],
+    });
+    quotes.update_book(book);
+
+    // 1 BTC fills at the best ask
+    assert_eq!(
+        quotes.market_buy("BTC/USD", "1").unwrap().kind,
+        OrderKind::Market
+    );
+    // 2 BTC average 30025 on the ask side (5 bps off) and 29995 on the bid side
+    let slippage = quotes
+        .expected_slippage_bps("BTC/USD", Side::Buy, 2.0)
+        .unwrap();
+    assert!((slippage - 4.998).abs() < 0.01, "{slippage}");
+    assert!(quotes.market_buy("BTC/USD", "2").is_ok());
+    assert!(quotes.market_sell("BTC/USD", "2").is_ok());
+    let strict = quotes
+        .clone()
+        .with_market_guard(MarketOrderGuard::default().with_max_slippage_bps(4.0));
+    let err = strict.market_buy("BTC/USD", "2").unwrap_err();
+    assert!(
+        matches!(
+            err,
+            KrakenError::SlippageTooHigh {
+                expected_bps: Some(_),
+                ..
+            }
+        ),
+        "{err:?}"
+    );
+    let err = quotes.market_buy("BTC/USD", "3").unwrap_err();
+    assert!(
+        matches!(
+            err,
+            KrakenError::SlippageTooHigh {
+                expected_bps: None,
+                ..
+            }
+        ),
+        "{err:?}"
+    );
+}
+
+#[test]
+fn test_quote_volume_order_sizes_from_the_book() {
+    let quotes = QuoteCache::new();
+    assert!(matches!(
+        quotes.quote_volume_order("BTC/USD", Side::Buy, 100.0, 0.001),
+        Err(KrakenError::StaleMarketData { age: None, .. })
+    ));
+
+    let level = |price: &str, quantity: &str| OrderBookEntry {
+        price: price.to_string(),
+        quantity: quantity.to_string(),
+    };
+    let mut book = OrderBook::new("BTC/USD", 10);
+    book.apply(&WsBookMessage {
+        channel: "book".to_string(),
+        update_type: Some("snapshot".to_string()),
+        symbol: "BTC/USD".to_string(),
+        bids: vec![
+            level("30000.0", "1.00000000"),
+            level("29990.0", "1.00000000"),
+        ],
+        asks: vec![
+            level("30010.0", "1.00000000"),
+            level("30040.0", "1.00000000"),
+        ],
+    });
+    quotes.update_book(book);
+
+    // $45,025 takes the first ask and half the second, averaging ~30020;
+    // 0.1% on top caps the price and sizes the volume so the spend fits
+    let order = quotes
+        .quote_volume_order("BTC/USD", Side::Buy, 45_025.0, 0.001)
+        .unwrap();
+    assert_eq!(order.side, Side::Buy);
+    assert_eq!(price(&order.kind), "30050.0");
+    assert_eq!(order.volume, "1.49833610");
+
+    // Selling rounds the other way, so the proceeds cover the amount
+    let order = quotes
+        .quote_volume_order("BTC/USD", Side::Sell, 40_000.0, 0.001)
+        .unwrap();
+    assert_eq!(price(&order.kind), "29967.6");
+    assert_eq!(order.volume, "1.33477490");
+
+    // More than the visible book holds
+    assert!(matches!(
+        quotes.quote_volume_order("BTC/USD", Side::Buy, 100_000.0, 0.001),
+        Err(KrakenError::SlippageTooHigh {
+            expected_bps: None,
+            ..
+        })
+    ));
+    assert!(matches!(
+        quotes.quote_volume_order("BTC/USD", Side::Buy, 0.0, 0.001),
+        Err(KrakenError::InvalidAmount(_))
+    ));
+}
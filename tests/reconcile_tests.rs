@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+
+use onise::models::{LedgerInfo, TradeInfo};
+use onise::reconcile::{Discrepancy, Reconciler};
+
+fn ledger_entry(
+    refid: &str,
+    time: f64,
+    ledger_type: &str,
+    asset: &str,
+    amount: &str,
+    fee: &str,
+    balance: &str,
+) -> LedgerInfo {
+    serde_json::from_value(serde_json::json!({
+        "refid": refid,
+        "time": time,
+        "type": ledger_type,
+        "subtype": "",
+        "aclass": "currency",
+        "asset": asset,
+        "amount": amount,
+        "fee": fee,
+        "balance": balance
+    }))
+    .expect("valid ledger entry")
+}
+
+fn fill(fee: &str) -> TradeInfo {
+    serde_json::from_value(serde_json::json!({
+        "ordertxid": "OQCLML-BW3P3-BUCMWZ",
+        "postxid": "TKH2SE-M7IF5-CFI7LT",
+        "pair": "XXBTZUSD",
+        "time": 1688667796.0,
+        "type": "buy",
+        "ordertype": "limit",
+        "price": "30010.00000",
+        "cost": "600.20000",
+        "fee": fee,
+        "vol": "0.02000000",
+        "margin": "0.00000",
+        "misc": ""
+    }))
+    .expect("valid trade")
+}
+
+#[test]
+fn test_reconcile_clean_ledger() {
+    let mut ledger = HashMap::new();
+    ledger.insert(
+        "L1".to_string(),
+        ledger_entry("DEP1", 1.0, "deposit", "ZUSD", "1000.0", "0.0", "1000.0"),
+    );
+    ledger.insert(
+        "L2".to_string(),
+        ledger_entry("T1", 2.0, "trade", "ZUSD", "-600.2", "0.96", "398.84"),
+    );
+    ledger.insert(
+        "L3".to_string(),
+        ledger_entry("T1", 2.0, "trade", "XXBT", "0.02", "0.0", "0.02"),
+    );
+
+    let mut fills = HashMap::new();
+    fills.insert("T1".to_string(), fill("0.96"));
+
+    let mut reconciler = Reconciler::new();
+    reconciler.record_fills(&fills);
+    reconciler.expect_transfer("DEP1");
+    reconciler.track_balance("ZUSD", "398.84");
+    reconciler.track_balance("XXBT", "0.02");
+
+    let report = reconciler.reconcile(&ledger);
+    assert_eq!(report.entries_checked, 3);
+    assert!(report.is_clean(), "unexpected: {:?}", report.discrepancies);
+}
+
+#[test]
+fn test_reconcile_reports_discrepancies() {
+    let mut ledger = HashMap::new();
+    ledger.insert(
+        "L1".to_string(),
+        ledger_entry("WD1", 1.0, "withdrawal", "ZUSD", "-50.0", "5.0", "950.0"),
+    );
+    ledger.insert(
+        "L2".to_string(),
+        ledger_entry("T2", 2.0, "trade", "ZUSD", "-10.0", "0.1", "939.9"),
+    );
+    ledger.insert(
+        "L3".to_string(),
+        ledger_entry("T1", 3.0, "trade", "ZUSD", "-600.2", "1.5", "338.2"),
+    );
+
+    let mut fills = HashMap::new();
+    fills.insert("T1".to_string(), fill("0.96"));
+    fills.insert("T9".to_string(), fill("0.10"));
+
+    let mut reconciler = Reconciler::new();
+    reconciler.record_fills(&fills);
+    reconciler.track_balance("ZUSD", "400.0");
+
+    let report = reconciler.reconcile(&ledger);
+    let d = &report.discrepancies;
+    assert!(d
+        .iter()
+        .any(|x| matches!(x, Discrepancy::UnaccountedTransfer { refid, .. } if refid == "WD1")));
+    assert!(d
+        .iter()
+        .any(|x| matches!(x, Discrepancy::MissingFill { refid, .. } if refid == "T2")));
+    assert!(d
+        .iter()
+        .any(|x| matches!(x, Discrepancy::UnexpectedFee { refid, .. } if refid == "T1")));
+    assert!(d
+        .iter()
+        .any(|x| matches!(x, Discrepancy::UnmatchedFill { trade_id } if trade_id == "T9")));
+    assert!(d
+        .iter()
+        .any(|x| matches!(x, Discrepancy::BalanceMismatch { asset, .. } if asset == "ZUSD")));
+}
@@ -0,0 +1,110 @@
+use onise::testkit::MockKraken;
+
+#[tokio::test]
+async fn test_pnl_report_fifo_and_average_cost() {
+    use onise::params::TradesHistoryParams;
+    use onise::report::LotMethod;
+    use serde_json::{json, Map, Value};
+
+    // (id, time, side, volume, cost, fee)
+    let fills = [
+        ("T1", 1, "buy", "1", "100", "1"),
+        ("T2", 2, "buy", "1", "200", "2"),
+        ("T3", 3, "sell", "1.5", "450", "3"),
+        ("T4", 4, "sell", "1", "300", "0"),
+        ("T5", 5, "buy", "1", "100", "0"),
+    ];
+    let (mut trades, mut ledger) = (Map::new(), Map::new());
+    for (id, time, side, vol, cost, fee) in fills {
+        trades.insert(
+            id.to_string(),
+            json!({
+                "ordertxid": "O", "postxid": "P", "pair": "XXBTZUSD", "time": time,
+                "type": side, "ordertype": "market", "price": "0", "cost": cost,
+                "fee": fee, "vol": vol, "margin": "0", "misc": ""
+            }),
+        );
+        // T5's ledger legs are missing
+        if id == "T5" {
+            continue;
+        }
+        let sign = |incoming: bool| if incoming { "" } else { "-" };
+        for (asset, amount) in [
+            ("XXBT", format!("{}{vol}", sign(side == "buy"))),
+            ("ZUSD", format!("{}{cost}", sign(side == "sell"))),
+        ] {
+            ledger.insert(
+                format!("L-{id}-{asset}"),
+                json!({
+                    "refid": id, "time": time, "type": "trade", "subtype": "",
+                    "aclass": "currency", "asset": asset, "amount": amount,
+                    "fee": "0", "balance": "0"
+                }),
+            );
+        }
+    }
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+    kraken
+        .mock_result(
+            "/0/private/TradesHistory",
+            json!({ "trades": Value::Object(trades), "count": 5 }),
+        )
+        .await;
+    kraken
+        .mock_result(
+            "/0/private/Ledgers",
+            json!({ "ledger": Value::Object(ledger), "count": 8 }),
+        )
+        .await;
+    let c = kraken.authenticated_client();
+    let filters = TradesHistoryParams::new().with_start(0);
+
+    let fifo = c.pnl_report(&filters, LotMethod::Fifo).await.expect("fifo");
+    assert_eq!(fifo.unmatched, ["T5"]);
+    // T3 sells all of T1 (101 with fee) and half of T2 (101) for 447
+    assert_eq!(fifo.disposals[0].trade_id, "T3");
+    assert!((fifo.disposals[0].cost_basis - 202.0).abs() < 1e-9);
+    assert!((fifo.disposals[0].pnl - 245.0).abs() < 1e-9);
+    // T4 sells the other half of T2 and 0.5 not bought in the report
+    assert!((fifo.disposals[1].cost_basis - 101.0).abs() < 1e-9);
+    assert!((fifo.disposals[1].uncovered_volume - 0.5).abs() < 1e-9);
+
+    let average = c
+        .pnl_report(&filters, LotMethod::AverageCost)
+        .await
+        .expect("average");
+    // 1.5 at the 151.5 average
+    assert!((average.disposals[0].cost_basis - 227.25).abs() < 1e-9);
+    assert!((average.disposals[0].pnl - 219.75).abs() < 1e-9);
+
+    // Once everything is sold, both methods realize the same total
+    for report in [&fifo, &average] {
+        let btc = &report.assets[0];
+        assert_eq!((btc.asset.as_str(), btc.quote.as_str()), ("BTC", "USD"));
+        assert!((report.realized_pnl("USD") - 444.0).abs() < 1e-9);
+        assert!(btc.open_volume.abs() < 1e-9);
+        assert!((btc.fees - 6.0).abs() < 1e-9);
+    }
+
+    let csv = fifo.to_csv();
+    let mut lines = csv.lines();
+    assert_eq!(
+        lines.next(),
+        Some("trade_id,time,asset,quote,volume,proceeds,cost_basis,pnl,uncovered_volume")
+    );
+    assert_eq!(lines.next(), Some("T3,3,BTC,USD,1.5,447,202,245,0"));
+    assert_eq!(lines.count(), 1);
+    let json: Value = serde_json::from_str(&fifo.to_json().unwrap()).unwrap();
+    assert_eq!(json["method"], "fifo");
+    assert_eq!(json["assets"][0]["realized_pnl"], 444.0);
+
+    // Only the start timestamp carries over to the ledger query
+    let requests = kraken.received_requests().await;
+    let ledgers = requests
+        .iter()
+        .find(|r| r.url.path() == "/0/private/Ledgers")
+        .unwrap();
+    let body = String::from_utf8_lossy(&ledgers.body);
+    assert!(body.contains("type=trade") && body.contains("start=0"), "{body}");
+}
@@ -0,0 +1,96 @@
+use onise::testkit::MockKraken;
+
+#[tokio::test]
+async fn test_reprice_order_amends_or_falls_back_to_replace() {
+    use onise::reprice::RepriceOutcome;
+
+    // Amendable: one AmendOrder call
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+    let outcome = kraken
+        .authenticated_client()
+        .reprice_order("OHYO67-6LP66-HMQ437", "26400.0")
+        .await
+        .expect("amended");
+    assert!(matches!(outcome, RepriceOutcome::Amended(_)));
+    let requests = kraken.received_requests().await;
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].url.path(), "/0/private/AmendOrder");
+
+    // Not amendable: cancel, then re-add the unfilled remainder
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+    kraken
+        .mock_errors(
+            "/0/private/AmendOrder",
+            &["EOrder:Amend not supported for order type"],
+        )
+        .await;
+    kraken
+        .mock_result(
+            "/0/private/QueryOrders",
+            serde_json::json!({
+                "OHYO67-6LP66-HMQ437": {
+                    "refid": null, "userref": 7, "cl_ord_id": "ladder-3", "status": "open",
+                    "opentm": 1688666559.8974, "starttm": 0, "expiretm": 0,
+                    "descr": {
+                        "pair": "XBTUSD", "side": "sell", "ordertype": "stop-loss-limit",
+                        "price": "25000.0", "price2": "24900.0", "leverage": "none",
+                        "order": null, "close": null
+                    },
+                    "vol": "1.00000000", "vol_exec": "0.25", "cost": "0", "fee": "0",
+                    "price": "0", "stopprice": "0", "limitprice": "0", "misc": "",
+                    "oflags": "fciq", "trades": null, "reason": null
+                }
+            }),
+        )
+        .await;
+    let outcome = kraken
+        .authenticated_client()
+        .reprice_order("OHYO67-6LP66-HMQ437", "24850.0")
+        .await
+        .expect("replaced");
+    let RepriceOutcome::Replaced { cancelled, order } = outcome else {
+        panic!("expected a replace, got {outcome:?}");
+    };
+    assert_eq!(cancelled, "OHYO67-6LP66-HMQ437");
+    assert!(!order.txid.is_empty());
+
+    let requests = kraken.received_requests().await;
+    let paths: Vec<_> = requests.iter().map(|r| r.url.path()).collect();
+    assert_eq!(
+        paths,
+        [
+            "/0/private/AmendOrder",
+            "/0/private/QueryOrders",
+            "/0/private/CancelOrder",
+            "/0/private/AddOrder"
+        ]
+    );
+    let add = String::from_utf8(requests[3].body.clone()).unwrap();
+    for field in [
+        "pair=XBTUSD",
+        "type=sell",
+        "ordertype=stop-loss-limit",
+        "volume=0.75000000",
+        "price=25000.0",
+        "price2=24850.0",
+        "cl_ord_id=ladder-3",
+    ] {
+        assert!(add.contains(field), "{field} missing from {add}");
+    }
+    assert!(!add.contains("userref"), "{add}");
+
+    // Other amend errors aren't papered over with a replace
+    let kraken = MockKraken::start().await;
+    kraken
+        .mock_errors("/0/private/AmendOrder", &["EOrder:Unknown order"])
+        .await;
+    let err = kraken
+        .authenticated_client()
+        .reprice_order("OHYO67-6LP66-HMQ437", "24850.0")
+        .await
+        .expect_err("unknown order");
+    assert!(!onise::reprice::amend_not_permitted(&err));
+    assert_eq!(kraken.received_requests().await.len(), 1);
+}
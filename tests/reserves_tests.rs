@@ -0,0 +1,66 @@
+use onise::error::KrakenError;
+use onise::reserves::InclusionProof;
+use serde_json::json;
+
+const ROOT: &str = "21ebb3aa23f8c237933b920cd218cb4e2cfbe03622744d6a5975c4e3cac4db7a";
+
+fn proof() -> InclusionProof {
+    serde_json::from_value(json!({
+        "audit_id": "2024-Q2",
+        "record_id": "7f3a9c1e-5b2d-4e8a-9f61-0c3d2b1a4e57",
+        "leaf_hash": "b9ad30ef706ce342dada7e47691472539310c3a51b86ebf5ae38143cfa7eb7fd",
+        "balances": { "XXBT": "1.2500", "XETH": "10.0000" },
+        "path": [
+            {
+                "hash": "b693721234483b94325e74a1d0843884e9a5464c6fecf30a684277bffb58a2b4",
+                "position": "left"
+            },
+            {
+                "hash": "e5f4f8bbeaab1db484280ede48eddd8025c5e348924561d63a1e3bea72e928da",
+                "position": "right"
+            }
+        ],
+        "root_hash": ROOT
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_inclusion_proof_verifies_against_published_root() {
+    let proof = proof();
+    proof.verify().expect("path hashes up to the root");
+    proof.verify_against(ROOT).expect("root is the published one");
+    proof
+        .verify_against(&ROOT.to_ascii_uppercase())
+        .expect("hex case doesn't matter");
+    assert_eq!(proof.balances["XXBT"], "1.2500");
+
+    // A self-consistent proof against some other root is still rejected
+    let other = "00".repeat(32);
+    assert!(matches!(
+        proof.verify_against(&other),
+        Err(KrakenError::InclusionProofInvalid { audit_id, .. }) if audit_id == "2024-Q2"
+    ));
+}
+
+#[test]
+fn test_inclusion_proof_rejects_tampering() {
+    let mut tampered = proof();
+    tampered.leaf_hash.replace_range(0..1, "0");
+    assert!(matches!(
+        tampered.verify(),
+        Err(KrakenError::InclusionProofInvalid { audit_id, .. }) if audit_id == "2024-Q2"
+    ));
+
+    let mut swapped = proof();
+    swapped.path[0].position = "right".to_string();
+    assert!(swapped.verify().is_err());
+
+    let mut unknown = proof();
+    unknown.path[1].position = "up".to_string();
+    assert!(unknown.verify_against(ROOT).is_err());
+
+    let mut not_hex = proof();
+    not_hex.path[0].hash = "zz".repeat(32);
+    assert!(not_hex.verify().is_err());
+}
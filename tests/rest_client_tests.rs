@@ -0,0 +1,171 @@
+use onise::error::KrakenError;
+use onise::testkit::{ErrorClass, MockKraken};
+
+#[tokio::test]
+async fn test_read_only_client_refuses_mutations() {
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+    let c = kraken.authenticated_client().with_read_only(true);
+    let p: &[(&str, &str)] = &[];
+
+    c.get_balance().await.expect("reads still work");
+    c.get_open_orders(p).await.expect("reads still work");
+
+    let err = c.add_order(p).await.expect_err("read-only");
+    assert!(matches!(&err, KrakenError::ReadOnly { path } if path == "/0/private/AddOrder"));
+    assert!(c.withdraw_funds(p).await.is_err());
+    assert!(c.to_public().with_credentials("k", "c2VjcmV0").unwrap().is_read_only());
+
+    let sent: Vec<String> = kraken
+        .received_requests()
+        .await
+        .iter()
+        .map(|r| r.url.path().to_string())
+        .collect();
+    assert!(onise::MUTATING_ENDPOINTS.iter().all(|p| !sent.iter().any(|s| s == p)));
+    assert_eq!(sent.len(), 2);
+}
+
+#[tokio::test]
+async fn test_leverage_checked_against_pair_metadata() {
+    use std::time::Duration;
+
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+    let c = kraken
+        .authenticated_client()
+        .with_metadata_cache(Duration::from_secs(60));
+
+    // The fixture's XBTUSD offers 2-5x on both sides
+    c.validate_leverage("XBTUSD", "sell", "5:1").await.expect("5:1");
+    c.validate_leverage("XBTUSD", "buy", "none").await.expect("no leverage");
+    match c.validate_leverage("XBTUSD", "buy", "10").await {
+        Err(KrakenError::InvalidLeverage {
+            requested, allowed, ..
+        }) => {
+            assert_eq!(requested, "10");
+            assert_eq!(allowed, vec![2, 3, 4, 5]);
+        }
+        other => panic!("expected InvalidLeverage, got {other:?}"),
+    }
+    // Anything but buy/sell is refused before fetching, not checked as a buy
+    assert!(matches!(
+        c.validate_leverage("XBTUSD", "Sell", "3").await,
+        Err(KrakenError::InvalidUsage(_))
+    ));
+
+    let order = |leverage| {
+        [
+            ("pair", "XBTUSD"),
+            ("type", "buy"),
+            ("ordertype", "market"),
+            ("volume", "1"),
+            ("leverage", leverage),
+        ]
+    };
+    assert!(matches!(
+        c.add_order(&order("10")).await,
+        Err(KrakenError::InvalidLeverage { .. })
+    ));
+    c.add_order(&order("3")).await.expect("AddOrder");
+
+    let paths: Vec<String> = kraken
+        .received_requests()
+        .await
+        .iter()
+        .map(|r| r.url.path().to_string())
+        .collect();
+    // One metadata fetch, and only the valid order reached AddOrder
+    assert_eq!(paths, ["/0/public/AssetPairs", "/0/private/AddOrder"]);
+
+    // Without the metadata cache, add_order leaves leverage to Kraken
+    let uncached = kraken.authenticated_client();
+    uncached.add_order(&order("10")).await.expect("sent unchecked");
+    let paths = kraken.received_requests().await;
+    assert_eq!(paths.len(), 3);
+    assert_eq!(paths[2].url.path(), "/0/private/AddOrder");
+}
+
+#[tokio::test]
+async fn test_signature_rejection_carries_signed_payload() {
+    let kraken = MockKraken::start().await;
+    kraken
+        .mock_errors("/0/private/AddOrder", &["EAPI:Invalid signature"])
+        .await;
+    let client = kraken.authenticated_client();
+    let params = [("pair", "XBTUSD"), ("type", "buy"), ("otp", "123456"), ("volume", "1")];
+
+    let err = client.add_order(&params).await.unwrap_err();
+    let KrakenError::SignatureRejected { message, payload } = err.inner() else {
+        panic!("expected SignatureRejected, got {err:?}");
+    };
+    assert_eq!(message, "EAPI:Invalid signature");
+    assert!(err.request_id().is_some());
+
+    // The payload is exactly what went over the wire, nonce first
+    let requests = kraken.received_requests().await;
+    let sent = String::from_utf8(requests[0].body.clone()).unwrap();
+    assert_eq!(payload.post_data(), sent);
+    assert_eq!(
+        sent,
+        format!("nonce={}&pair=XBTUSD&type=buy&otp=123456&volume=1", payload.nonce())
+    );
+    assert_eq!(payload.path(), "/0/private/AddOrder");
+    // ...but logging the error doesn't leak the otp
+    assert!(!format!("{err:?}").contains("123456"));
+    assert!(!format!("{err}").contains("123456"));
+
+    // Other EAPI errors are left alone
+    kraken.server().reset().await;
+    kraken.mock_error("/0/private/AddOrder", ErrorClass::Api).await;
+    let err = client.add_order(&params).await.unwrap_err();
+    assert!(matches!(err.inner(), KrakenError::ApiError { .. }));
+}
+
+#[tokio::test]
+async fn test_otc_quote_request_and_accept() {
+    use onise::exchange::Side;
+    use onise::params::OtcQuoteRequest;
+
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+    let c = kraken.authenticated_client();
+
+    let bad = OtcQuoteRequest::volume("XBTUSD", Side::Buy, "25 BTC");
+    assert!(matches!(
+        c.request_otc_quote(&bad).await,
+        Err(KrakenError::InvalidUsage(_))
+    ));
+    assert!(kraken.received_requests().await.is_empty());
+
+    let request = OtcQuoteRequest::cost("XBTUSD", Side::Buy, "750000");
+    let quote = c.request_otc_quote(&request).await.expect("quote");
+    assert_eq!(quote.quote_id, "QOTC7-2XK4Q-HH3TBN");
+    assert_eq!(quote.price, "30125.40");
+    assert_eq!(quote.status, "pending");
+
+    let accepted = c.accept_otc_quote(&quote.quote_id).await.expect("accept");
+    assert_eq!(accepted.txid.as_deref(), Some("TDLH43-DVQXD-2KHVYY"));
+    let active = c.get_otc_quotes().await.expect("active quotes");
+    assert_eq!(active.quotes[0].quote_id, quote.quote_id);
+
+    let bodies: Vec<_> = kraken
+        .received_requests()
+        .await
+        .iter()
+        .map(|r| String::from_utf8(r.body.clone()).unwrap())
+        .collect();
+    assert!(bodies[0].ends_with("&pair=XBTUSD&type=buy&cost=750000"), "{}", bodies[0]);
+    assert!(
+        bodies[1].ends_with("&quote_id=QOTC7-2XK4Q-HH3TBN&action=accept"),
+        "{}",
+        bodies[1]
+    );
+
+    // Accepting trades, so a read-only client refuses it
+    let read_only = kraken.authenticated_client().with_read_only(true);
+    assert!(matches!(
+        read_only.reject_otc_quote(&quote.quote_id).await,
+        Err(KrakenError::ReadOnly { .. })
+    ));
+}
@@ -0,0 +1,102 @@
+use onise::error::KrakenError;
+use onise::exchange::{ExchangeClient, OrderRequest, Side};
+use onise::positions::PositionTracker;
+use onise::quotes::QuoteCache;
+use onise::risk::{RiskGuard, RiskLimit, RiskLimits};
+use onise::simulated::SimulatedExchange;
+use onise::ws_models::ExecutionData;
+
+mod common;
+use common::ticker;
+
+fn limit_of(err: &KrakenError) -> Option<RiskLimit> {
+    match err {
+        KrakenError::RiskLimitExceeded { limit, .. } => Some(*limit),
+        _ => None,
+    }
+}
+
+fn guard(limits: RiskLimits) -> RiskGuard<SimulatedExchange> {
+    let sim = SimulatedExchange::new()
+        .with_pair("BTC/USD", "XXBT", "ZUSD")
+        .with_pair("ETH/USD", "XETH", "ZUSD")
+        .with_balance("ZUSD", 1_000_000.0);
+    sim.set_quote("BTC/USD", 30_000.0, 30_010.0).unwrap();
+    let quotes = QuoteCache::new();
+    quotes.update_ticker(ticker("BTC/USD", "30000", "30010"));
+    quotes.update_ticker(ticker("ETH/USD", "1999", "2001"));
+    RiskGuard::new(sim, limits, PositionTracker::new(), quotes)
+}
+
+#[tokio::test]
+async fn test_order_notional_and_position_limits() {
+    let risk = guard(
+        RiskLimits::default()
+            .with_max_order_notional(10_000.0)
+            .with_max_position("BTC/USD", 0.5),
+    );
+
+    // A market buy is valued at the ask: 0.4 × 30010 > 10000
+    let big = OrderRequest::market("BTC/USD", Side::Buy, "0.4");
+    let err = risk.place_order(&big).await.unwrap_err();
+    assert_eq!(limit_of(&err), Some(RiskLimit::OrderNotional), "{err:?}");
+
+    risk.place_order(&OrderRequest::market("BTC/USD", Side::Buy, "0.3"))
+        .await
+        .unwrap();
+    risk.positions()
+        .apply_execution(&ExecutionData {
+            symbol: "BTC/USD".to_string(),
+            order_id: "O1".to_string(),
+            exec_id: "E1".to_string(),
+            quantity: "0.3".to_string(),
+            price: "30010".to_string(),
+            side: "buy".to_string(),
+            time: 0,
+            cost: "9003".to_string(),
+            fee: "0".to_string(),
+            fee_currency: "USD".to_string(),
+            liquidity: "taker".to_string(),
+        })
+        .unwrap();
+
+    // 0.3 + 0.3 would breach the 0.5 position cap; selling reduces it
+    let more = OrderRequest::limit("BTC/USD", Side::Buy, "0.3", "29000");
+    let err = risk.check(&more).unwrap_err();
+    assert!(
+        matches!(&err, KrakenError::RiskLimitExceeded { limit: RiskLimit::Position, max, .. } if *max == 0.5),
+        "{err:?}"
+    );
+    assert!(risk
+        .check(&OrderRequest::limit("BTC/USD", Side::Sell, "0.3", "31000"))
+        .is_ok());
+}
+
+#[tokio::test]
+async fn test_total_exposure_counts_every_pair() {
+    let risk = guard(RiskLimits::default().with_max_total_exposure(50_000.0));
+    risk.positions().set_position("BTC/USD", 1.0); // ~30005
+    risk.positions().set_position("ETH/USD", -5.0); // 10000
+
+    assert!(risk
+        .check(&OrderRequest::limit("ETH/USD", Side::Sell, "4", "2100"))
+        .is_ok());
+    let err = risk
+        .check(&OrderRequest::limit("ETH/USD", Side::Sell, "6", "2100"))
+        .unwrap_err();
+    assert_eq!(limit_of(&err), Some(RiskLimit::TotalExposure), "{err:?}");
+    // Reducing the short lowers exposure
+    assert!(risk
+        .check(&OrderRequest::limit("ETH/USD", Side::Buy, "5", "1900"))
+        .is_ok());
+
+    // A pair without a quote can't be valued
+    risk.positions().set_position("SOL/USD", 10.0);
+    let err = risk
+        .check(&OrderRequest::limit("BTC/USD", Side::Buy, "0.01", "30000"))
+        .unwrap_err();
+    assert!(
+        matches!(err, KrakenError::StaleMarketData { .. }),
+        "{err:?}"
+    );
+}
@@ -0,0 +1,148 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::json;
+use tokio::net::TcpListener;
+use tokio_tungstenite::{accept_async, tungstenite::protocol::Message};
+
+use onise::error::KrakenError;
+use onise::exchange::{ExchangeClient, OrderRequest, Side};
+use onise::router::OrderRouter;
+use onise::testkit::MockKraken;
+use onise::ws_client::KrakenWsClient;
+
+/// How the mock socket treats trading requests.
+#[derive(Clone, Copy)]
+enum Ws {
+    Acknowledge,
+    Reject,
+    Ignore,
+}
+
+async fn ws_client(mode: Ws) -> Arc<KrakenWsClient> {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let local_addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws_stream = accept_async(stream).await.unwrap();
+        while let Some(Ok(Message::Text(text))) = ws_stream.next().await {
+            let request: serde_json::Value = serde_json::from_str(&text).unwrap();
+            let reply = match mode {
+                Ws::Acknowledge => json!({
+                    "event": "addOrderStatus", "status": "ok",
+                    "txid": "OWS123-AAAAA-BBBBBB", "req_id": request["req_id"]
+                }),
+                Ws::Reject => json!({
+                    "event": "addOrderStatus", "status": "error",
+                    "error_message": "EOrder:Insufficient funds", "req_id": request["req_id"]
+                }),
+                Ws::Ignore => continue,
+            };
+            ws_stream
+                .send(Message::Text(reply.to_string()))
+                .await
+                .unwrap();
+        }
+    });
+    let mut client = KrakenWsClient::connect(&format!("ws://{local_addr}"))
+        .await
+        .unwrap();
+    client.token = Some("ws-token".to_string());
+    Arc::new(client)
+}
+
+fn paths(requests: &[wiremock::Request]) -> Vec<String> {
+    requests.iter().map(|r| r.url.path().to_string()).collect()
+}
+
+#[tokio::test]
+async fn test_router_prefers_ws_and_keeps_rejections() {
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+    let order = OrderRequest::limit("XBTUSD", Side::Buy, "1", "27500");
+
+    let router =
+        OrderRouter::new(kraken.authenticated_client()).with_ws(ws_client(Ws::Acknowledge).await);
+    let placed = router.place_order(&order).await.expect("placed over WS");
+    assert_eq!(placed.order_id, "OWS123-AAAAA-BBBBBB");
+
+    // A rejection is an answer: no REST retry
+    let router =
+        OrderRouter::new(kraken.authenticated_client()).with_ws(ws_client(Ws::Reject).await);
+    assert!(matches!(
+        router.place_order(&order).await,
+        Err(KrakenError::OrderError { .. })
+    ));
+    assert!(kraken.received_requests().await.is_empty());
+}
+
+#[tokio::test]
+async fn test_router_fails_over_without_duplicating() {
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+    let order = OrderRequest::limit("XBTUSD", Side::Buy, "1", "27500").with_cl_ord_id("c-1");
+    let router = OrderRouter::new(kraken.authenticated_client())
+        .with_ws(ws_client(Ws::Ignore).await)
+        .with_ack_deadline(Duration::from_millis(200));
+
+    // The fixture's open order stands in for the unacknowledged WS order
+    let placed = router
+        .place_order(&order)
+        .await
+        .expect("found by cl_ord_id");
+    assert_eq!(placed.order_id, "OQCLML-BW3P3-BUCMWZ");
+    let received = kraken.received_requests().await;
+    assert_eq!(paths(&received), ["/0/private/OpenOrders"]);
+    let body = String::from_utf8(received[0].body.clone()).unwrap();
+    assert!(body.ends_with("&cl_ord_id=c-1"), "{body}");
+
+    // Nothing on Kraken under that ID: resubmit over REST with the same cl_ord_id
+    kraken.server().reset().await;
+    kraken
+        .mock_result("/0/private/OpenOrders", json!({ "open": {} }))
+        .await;
+    kraken
+        .mock_result("/0/private/ClosedOrders", json!({ "closed": {} }))
+        .await;
+    kraken.mock_all_success().await;
+    router.place_order(&order).await.expect("placed over REST");
+    let received = kraken.received_requests().await;
+    assert_eq!(
+        paths(&received),
+        [
+            "/0/private/OpenOrders",
+            "/0/private/ClosedOrders",
+            "/0/private/AddOrder"
+        ]
+    );
+    let body = String::from_utf8(received[2].body.clone()).unwrap();
+    assert!(body.ends_with("&cl_ord_id=c-1"), "{body}");
+}
+
+#[tokio::test]
+async fn test_router_uses_rest_without_a_usable_socket() {
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+
+    let mut no_token = ws_client(Ws::Acknowledge).await;
+    Arc::get_mut(&mut no_token).unwrap().token = None;
+    let router = OrderRouter::new(kraken.authenticated_client()).with_ws(no_token);
+    router
+        .place_order(&OrderRequest::market("XBTUSD", Side::Sell, "1"))
+        .await
+        .expect("placed over REST");
+    router
+        .cancel_order("OQCLML-BW3P3-BUCMWZ")
+        .await
+        .expect("CancelOrder");
+
+    let received = kraken.received_requests().await;
+    assert_eq!(
+        paths(&received),
+        ["/0/private/AddOrder", "/0/private/CancelOrder"]
+    );
+    // A cl_ord_id is generated for every new order
+    let body = String::from_utf8(received[0].body.clone()).unwrap();
+    assert!(body.contains("&cl_ord_id="), "{body}");
+}
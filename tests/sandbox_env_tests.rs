@@ -0,0 +1,11 @@
+use onise::environment::{self, SANDBOX_ENV_VAR};
+use onise::error::KrakenError;
+
+// Its own test binary: `ONISE_SANDBOX` is read once per process.
+#[test]
+fn test_unrecognized_sandbox_value_keeps_sandbox_on() {
+    std::env::set_var(SANDBOX_ENV_VAR, "off");
+    assert!(environment::is_sandbox());
+    let err = environment::check_sandbox("https://api.kraken.com").unwrap_err();
+    assert!(matches!(err, KrakenError::ProductionRefused { .. }));
+}
@@ -0,0 +1,44 @@
+use onise::testkit::MockKraken;
+
+#[tokio::test]
+async fn test_schema_drift_reports_dropped_fields() {
+    use onise::models::{AssetPairsResponse, LedgersResponse};
+    use onise::schema_drift::missing_fields;
+    use onise::testkit::endpoint;
+
+    let result = |path: &str| {
+        let sample: serde_json::Value =
+            serde_json::from_str(endpoint(path).unwrap().sample()).unwrap();
+        sample["result"].clone()
+    };
+
+    // The samples match the models
+    let mut pairs = result("/0/public/AssetPairs");
+    assert!(missing_fields::<AssetPairsResponse>(&pairs).unwrap().is_empty());
+    let mut ledgers = result("/0/private/Ledgers");
+    assert!(missing_fields::<LedgersResponse>(&ledgers).unwrap().is_empty());
+
+    // Fields Kraken adds show up by path; added nulls lose nothing
+    let pair = pairs.as_object_mut().unwrap().values_mut().next().unwrap();
+    pair["tick_size_v2"] = serde_json::json!("0.1");
+    let entry = ledgers["ledger"].as_object_mut().unwrap().values_mut().next().unwrap();
+    entry["wallet"] = serde_json::json!({"type": "spot"});
+    entry["note"] = serde_json::Value::Null;
+    let pair_name = pairs.as_object().unwrap().keys().next().unwrap().clone();
+    let entry_id = ledgers["ledger"].as_object().unwrap().keys().next().unwrap().clone();
+    assert_eq!(
+        missing_fields::<AssetPairsResponse>(&pairs).unwrap(),
+        [format!("{pair_name}.tick_size_v2")]
+    );
+    assert_eq!(
+        missing_fields::<LedgersResponse>(&ledgers).unwrap(),
+        [format!("ledger.{entry_id}.wallet")]
+    );
+
+    // Detection only logs: drifted responses still parse
+    let kraken = MockKraken::start().await;
+    kraken.mock_result("/0/public/AssetPairs", pairs).await;
+    let client = kraken.public_client().with_schema_drift_detection(true);
+    let parsed = client.get_asset_pairs(&[]).await.unwrap();
+    assert!(parsed.pairs.contains_key(&pair_name));
+}
@@ -0,0 +1,487 @@
+use onise::error::KrakenError;
+use onise::exchange::{ExchangeClient, OrderRequest, Side};
+use onise::models::ExchangeState;
+use onise::session::KrakenSession;
+use onise::testkit::MockKraken;
+use serde_json::json;
+
+async fn add_order_requests(kraken: &MockKraken) -> usize {
+    requests_to(kraken, "/0/private/AddOrder").await
+}
+
+async fn requests_to(kraken: &MockKraken, path: &str) -> usize {
+    kraken
+        .received_requests()
+        .await
+        .iter()
+        .filter(|r| r.url.path() == path)
+        .count()
+}
+
+#[tokio::test]
+async fn test_session_gates_orders_on_exchange_state() {
+    let kraken = MockKraken::start().await;
+    kraken
+        .mock_result(
+            "/0/public/SystemStatus",
+            json!({ "status": "cancel_only", "timestamp": "2023-07-06T18:52:00Z" }),
+        )
+        .await;
+    kraken.mock_all_success().await;
+    let session = KrakenSession::new(kraken.authenticated_client());
+    assert_eq!(session.exchange_state(), ExchangeState::Online);
+    assert_eq!(
+        session.refresh_exchange_state().await.unwrap(),
+        ExchangeState::CancelOnly
+    );
+
+    let limit = OrderRequest::limit("XBTUSD", Side::Buy, "1", "30000");
+    let err = session.place_order(&limit).await.unwrap_err();
+    assert!(
+        matches!(
+            err,
+            KrakenError::ExchangeRestricted {
+                state: ExchangeState::CancelOnly,
+                ..
+            }
+        ),
+        "{err:?}"
+    );
+    assert_eq!(add_order_requests(&kraken).await, 0);
+    // Cancels still go through
+    session.cancel_order("OUF4EM-FRGI2-MQMWZD").await.unwrap();
+
+    // Post-only: limit orders are sent, market orders aren't
+    kraken.server().reset().await;
+    kraken
+        .mock_result(
+            "/0/public/SystemStatus",
+            json!({ "status": "post_only", "timestamp": "2023-07-06T19:00:00Z" }),
+        )
+        .await;
+    kraken.mock_all_success().await;
+    session.refresh_exchange_state().await.unwrap();
+    session.place_order(&limit).await.unwrap();
+    let market = OrderRequest::market("XBTUSD", Side::Buy, "1");
+    assert!(matches!(
+        session.place_order(&market).await.unwrap_err(),
+        KrakenError::ExchangeRestricted { .. }
+    ));
+    assert_eq!(add_order_requests(&kraken).await, 1);
+}
+
+#[tokio::test]
+async fn test_session_learns_cancel_only_from_rejections() {
+    let kraken = MockKraken::start().await;
+    kraken
+        .mock_errors(
+            "/0/private/AddOrder",
+            &["EService:Market in cancel_only mode"],
+        )
+        .await;
+    kraken.mock_all_success().await;
+    let session = KrakenSession::new(kraken.authenticated_client());
+
+    let order = OrderRequest::limit("XBTUSD", Side::Buy, "1", "30000");
+    let err = session.place_order(&order).await.unwrap_err();
+    assert!(
+        matches!(err.inner(), KrakenError::ServiceError { .. }),
+        "{err:?}"
+    );
+    assert_eq!(session.exchange_state(), ExchangeState::CancelOnly);
+    // The next attempt is refused without a round trip
+    assert!(matches!(
+        session.place_order(&order).await.unwrap_err(),
+        KrakenError::ExchangeRestricted { .. }
+    ));
+    assert_eq!(add_order_requests(&kraken).await, 1);
+
+    // SystemStatus says online again
+    session.refresh_exchange_state().await.unwrap();
+    assert_eq!(session.exchange_state(), ExchangeState::Online);
+}
+
+#[tokio::test]
+async fn test_session_rechecks_state_after_a_rejection() {
+    use std::time::Duration;
+
+    let kraken = MockKraken::start().await;
+    kraken
+        .mock_errors(
+            "/0/private/AddOrder",
+            &["EService:Market in post_only mode"],
+        )
+        .await;
+    kraken.mock_all_success().await;
+    let session = KrakenSession::new(kraken.authenticated_client())
+        .with_state_recheck(Duration::from_millis(50));
+
+    let order = OrderRequest::limit("XBTUSD", Side::Buy, "1", "30000");
+    session.place_order(&order).await.unwrap_err();
+    session.place_order(&order).await.unwrap_err();
+    assert_eq!(session.exchange_state(), ExchangeState::PostOnly);
+    assert_eq!(requests_to(&kraken, "/0/public/SystemStatus").await, 0);
+
+    // Without anyone calling refresh_exchange_state, SystemStatus is read once
+    // and the session recovers
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(session.exchange_state(), ExchangeState::Online);
+    assert_eq!(requests_to(&kraken, "/0/public/SystemStatus").await, 1);
+}
+
+#[tokio::test]
+async fn test_kill_switch_cancels_all_and_halts_orders() {
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+    let session = KrakenSession::new(kraken.authenticated_client());
+
+    let report = session.kill_switch().await;
+    assert_eq!(*report.rest.as_ref().unwrap(), 4);
+    assert!(report.ws.is_none(), "no socket to cancel over");
+    assert_eq!(report.cancelled(), 4);
+    assert!(report.confirmed());
+    assert!(session.is_halted());
+
+    let order = OrderRequest::limit("XBTUSD", Side::Buy, "1", "30000");
+    let err = session.place_order(&order).await.unwrap_err();
+    assert!(matches!(err, KrakenError::SessionHalted { .. }), "{err:?}");
+    assert_eq!(add_order_requests(&kraken).await, 0);
+    // Cancels still go through
+    session.cancel_order("OUF4EM-FRGI2-MQMWZD").await.unwrap();
+
+    session.resume();
+    session.place_order(&order).await.unwrap();
+    assert_eq!(add_order_requests(&kraken).await, 1);
+}
+
+#[tokio::test]
+async fn test_session_config_from_json_file() {
+    use onise::config::{CredentialsSource, KrakenSessionConfig};
+    use onise::ws_models::WsSubscriptionPayload;
+    use std::time::Duration;
+
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+    let dir = std::env::temp_dir().join(format!("onise-config-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let secrets = dir.join("kraken.json");
+    std::fs::write(
+        &secrets,
+        json!({ "api_key": "config-key", "api_secret": "c2VjcmV0" }).to_string(),
+    )
+    .unwrap();
+    let path = dir.join("session.json");
+    let config = json!({
+        "environment": {
+            "rest": kraken.uri(),
+            "ws_public": "ws://127.0.0.1:1",
+            "ws_auth": "ws://127.0.0.1:1"
+        },
+        "credentials": { "file": secrets },
+        "read_only": true,
+        "subscriptions": [
+            { "name": "book", "symbol": "BTC/USD", "depth": 10 },
+            { "name": "ticker", "symbol": "ETH/USD" }
+        ],
+        "rate_limits": { "max_orders_per_minute": 30, "ack_deadline_ms": 1500 },
+        "risk_limits": { "max_order_notional": 50000, "max_position": { "BTC/USD": 2.5 } },
+        "reconnect": { "initial_delay_ms": 100, "max_delay_ms": 300, "max_attempts": 4 }
+    });
+    std::fs::write(&path, config.to_string()).unwrap();
+
+    let config = KrakenSessionConfig::from_file(&path).unwrap();
+    assert_eq!(
+        config.subscriptions[0],
+        WsSubscriptionPayload::Book {
+            symbol: "BTC/USD".to_string(),
+            depth: 10
+        }
+    );
+    assert_eq!(config.risk_limits.max_position["BTC/USD"], 2.5);
+    assert_eq!(config.risk_limits.max_total_exposure, None);
+    assert_eq!(config.strategy_limits().max_orders_per_minute, Some(30));
+    let delays: Vec<_> = (1..=4).map(|n| config.reconnect.delay(n)).collect();
+    assert_eq!(
+        delays,
+        vec![
+            Some(Duration::from_millis(100)),
+            Some(Duration::from_millis(200)),
+            Some(Duration::from_millis(300)),
+            None
+        ]
+    );
+
+    let session = config.session().unwrap();
+    assert_eq!(session.rest().api_key(), "config-key");
+    assert!(session.rest().is_read_only());
+    session.refresh_exchange_state().await.unwrap();
+    assert_eq!(kraken.received_requests().await.len(), 1);
+
+    // Out of connect attempts: the last error comes back
+    assert!(config.connect_ws().await.is_err());
+
+    // Left-out sections take their defaults; unknown keys are refused
+    let defaults = KrakenSessionConfig::from_json("{}").unwrap();
+    assert_eq!(defaults.credentials, CredentialsSource::default());
+    assert!(defaults.subscriptions.is_empty());
+    assert!(KrakenSessionConfig::from_json(r#"{ "risk_limits": { "max_notional": 1 } }"#).is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn test_session_config_from_toml() {
+    use onise::config::{CredentialsSource, KrakenSessionConfig};
+
+    let config = KrakenSessionConfig::from_toml(
+        r#"
+        read_only = true
+        subscriptions = [{ name = "trades", symbol = "BTC/USD" }]
+
+        [credentials.env]
+        api_key = "BOT_KEY"
+
+        [risk_limits]
+        max_total_exposure = 100000.0
+        "#,
+    )
+    .unwrap();
+    assert!(config.read_only);
+    assert_eq!(config.risk_limits.max_total_exposure, Some(100000.0));
+    assert_eq!(
+        config.credentials,
+        CredentialsSource::Env {
+            api_key: "BOT_KEY".to_string(),
+            api_secret: "KRAKEN_API_SECRET".to_string()
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_shutdown_drains_cancels_and_flushes() {
+    use onise::audit::{AuditRecord, AuditSink};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use wiremock::matchers::path;
+    use wiremock::{Mock, ResponseTemplate};
+
+    #[derive(Default)]
+    struct Flushes(Arc<AtomicUsize>);
+    impl AuditSink for Flushes {
+        fn record(&self, _: &AuditRecord) {}
+        fn flush(&self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+    Mock::given(path("/0/private/AddOrder"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_raw(
+                    onise::fixtures::rest("add_order").unwrap(),
+                    "application/json",
+                )
+                .set_delay(Duration::from_millis(200)),
+        )
+        .mount(kraken.server())
+        .await;
+    let flushes = Flushes::default();
+    let flushed = flushes.0.clone();
+    let session = Arc::new(
+        KrakenSession::new(kraken.authenticated_client().with_audit_sink(flushes))
+            .with_cancel_on_shutdown(true),
+    );
+
+    // An order already under way when shutdown starts still completes
+    let order = OrderRequest::limit("XBTUSD", Side::Buy, "1", "30000");
+    let pending = {
+        let (session, order) = (session.clone(), order.clone());
+        tokio::spawn(async move { session.place_order(&order).await })
+    };
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let report = session.shutdown(Duration::from_secs(5)).await;
+    assert!(report.drained);
+    assert!(pending.await.unwrap().is_ok());
+    assert_eq!(report.cancelled.unwrap().cancelled(), 4);
+    assert!(report.recorded.is_none() && report.ws_closed.is_none());
+    assert_eq!(flushed.load(Ordering::SeqCst), 1);
+
+    // New orders are refused from then on; cancels still go through
+    let err = session.place_order(&order).await.unwrap_err();
+    assert!(matches!(err, KrakenError::SessionClosed { .. }), "{err:?}");
+    session.cancel_order("OU22CG-KLAF2-FWUDD7").await.unwrap();
+    assert!(session.is_closing() && !session.is_halted());
+
+    // A call outlasting the grace period is reported, not waited on
+    let session = Arc::new(KrakenSession::new(kraken.authenticated_client()));
+    let pending = {
+        let (session, order) = (session.clone(), order.clone());
+        tokio::spawn(async move { session.place_order(&order).await })
+    };
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let report = session.shutdown(Duration::from_millis(10)).await;
+    assert!(!report.drained);
+    assert!(report.cancelled.is_none());
+    assert!(pending.await.unwrap().is_ok());
+
+    // Subaccounts' audit sinks are flushed too
+    let sub = Flushes::default();
+    let sub_flushed = sub.0.clone();
+    let session = KrakenSession::new(kraken.authenticated_client()).with_account(
+        "sub-1",
+        kraken.authenticated_client().with_audit_sink(sub),
+        None,
+    );
+    session.shutdown(Duration::from_secs(1)).await;
+    assert_eq!(sub_flushed.load(Ordering::SeqCst), 1);
+}
+
+fn open_position(pair: &str, side: &str, vol: &str) -> serde_json::Value {
+    json!({
+        "ordertxid": "OQCLML-BW3P3-BUCMWZ", "posstatus": "open", "pair": pair,
+        "type": side, "ordertype": "limit", "cost": "15000", "fee": "0", "vol": vol,
+        "vol_closed": "0", "cost_closed": "0", "fee_closed": "0", "pl_closed": "0",
+        "margin": "3000",
+    })
+}
+
+#[tokio::test]
+async fn test_session_routes_orders_to_subaccounts_and_consolidates() {
+    use onise::session::MAIN_ACCOUNT;
+
+    let (main, desk) = (MockKraken::start().await, MockKraken::start().await);
+    for (kraken, side, vol, balances) in [
+        (&main, "buy", "1", json!({ "ZUSD": "100", "XXBT": "1" })),
+        (&desk, "sell", "0.25", json!({ "ZUSD": "50" })),
+    ] {
+        let positions = json!({ "TF5GVO-T7ZZ2-6NBKBI": open_position("XXBTZUSD", side, vol) });
+        kraken
+            .mock_result("/0/private/OpenPositions", positions)
+            .await;
+        kraken.mock_result("/0/private/Balance", balances).await;
+        kraken.mock_all_success().await;
+    }
+    let session = KrakenSession::new(main.authenticated_client()).with_account(
+        "desk",
+        desk.authenticated_client(),
+        None,
+    );
+    assert_eq!(session.accounts().collect::<Vec<_>>(), ["desk"]);
+
+    let order = OrderRequest::limit("XBTUSD", Side::Buy, "1", "30000").with_account("desk");
+    let placed = session.place_order(&order).await.unwrap();
+    assert_eq!(add_order_requests(&desk).await, 1);
+    assert_eq!(add_order_requests(&main).await, 0);
+    // The cancel follows the order to its account
+    session.cancel_order(&placed.order_id).await.unwrap();
+    assert_eq!(requests_to(&desk, "/0/private/CancelOrder").await, 1);
+    assert_eq!(requests_to(&main, "/0/private/CancelOrder").await, 0);
+
+    let untagged = order.clone().with_account(MAIN_ACCOUNT);
+    session.place_order(&untagged).await.unwrap();
+    assert_eq!(add_order_requests(&main).await, 1);
+    let unknown = order.with_account("nobody");
+    let err = session.place_order(&unknown).await.unwrap_err();
+    assert!(matches!(err, KrakenError::InvalidUsage(_)), "{err:?}");
+
+    let view = session.consolidated().await.unwrap();
+    let accounts: Vec<_> = view.positions.keys().map(String::as_str).collect();
+    assert_eq!(accounts, ["desk", MAIN_ACCOUNT]);
+    let totals = view.total_balances().unwrap();
+    assert_eq!((totals["ZUSD"], totals["XXBT"]), (150.0, 1.0));
+    assert_eq!(view.net_positions().unwrap()["XXBTZUSD"], 0.75);
+
+    let report = session.kill_switch().await;
+    assert_eq!(*report.accounts["desk"].rest.as_ref().unwrap(), 4);
+    assert_eq!(report.cancelled(), 8);
+    assert!(report.confirmed());
+}
+
+#[tokio::test]
+async fn test_session_streams_status_page_incidents() {
+    use futures_util::StreamExt;
+    use onise::status_page::{ComponentStatus, StatusEvent, StatusPage, StatusSummary};
+    use std::time::Duration;
+    use wiremock::matchers::path;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let summary = |incident_status: Option<&str>, spot: &str| {
+        let incidents: Vec<_> = incident_status
+            .map(|status| {
+                json!({
+                    "id": "inc1",
+                    "name": "Delayed order placement",
+                    "status": status,
+                    "impact": "major",
+                    "shortlink": "https://stspg.io/inc1",
+                    "updated_at": "2024-05-01T12:00:00Z",
+                    "incident_updates": [{ "status": status, "body": "Looking into it" }]
+                })
+            })
+            .into_iter()
+            .collect();
+        json!({
+            "page": { "id": "page", "name": "Kraken" },
+            "components": [
+                { "id": "spot", "name": "Spot Trading", "status": spot, "group": false },
+                { "id": "web", "name": "Website", "status": "operational" }
+            ],
+            "incidents": incidents,
+            "status": { "indicator": "major", "description": "Partial System Outage" }
+        })
+    };
+    let server = MockServer::start().await;
+    let mount = |body: Option<serde_json::Value>, times: u64| {
+        let response = match body {
+            Some(body) => ResponseTemplate::new(200).set_body_json(body),
+            None => ResponseTemplate::new(503),
+        };
+        Mock::given(path("/api/v2/summary.json"))
+            .respond_with(response)
+            .up_to_n_times(times)
+            .mount(&server)
+    };
+    // Unavailable once, then an incident, then all clear for good
+    mount(None, 1).await;
+    mount(Some(summary(Some("investigating"), "partial_outage")), 1).await;
+    mount(Some(summary(None, "operational")), u64::MAX).await;
+
+    let kraken = MockKraken::start().await;
+    let page = StatusPage::new(format!("{}/api/v2/summary.json", server.uri()));
+    let session = KrakenSession::new(kraken.authenticated_client()).with_status_page(page);
+    let events = session.status_events(Duration::from_millis(10)).take(4);
+    let events: Vec<_> = tokio::time::timeout(Duration::from_secs(5), events.collect())
+        .await
+        .unwrap();
+
+    assert!(matches!(&events[0], StatusEvent::IncidentOpened(i) if i.impact == "major"));
+    assert!(matches!(
+        &events[1],
+        StatusEvent::ComponentDegraded { component, previous: ComponentStatus::Operational }
+            if component.name == "Spot Trading" && component.status == ComponentStatus::PartialOutage
+    ));
+    assert!(matches!(&events[2], StatusEvent::IncidentResolved(i) if i.id == "inc1"));
+    assert!(matches!(
+        &events[3],
+        StatusEvent::ComponentRecovered {
+            previous: ComponentStatus::PartialOutage,
+            ..
+        }
+    ));
+
+    // A new update on an open incident, and statuses this crate doesn't know
+    let open: StatusSummary =
+        serde_json::from_value(summary(Some("investigating"), "operational")).unwrap();
+    let identified: StatusSummary =
+        serde_json::from_value(summary(Some("identified"), "new_status")).unwrap();
+    let changes = identified.changes_since(&open);
+    assert!(matches!(&changes[0], StatusEvent::IncidentUpdated(i) if i.status == "identified"));
+    assert!(matches!(
+        &changes[1],
+        StatusEvent::ComponentDegraded { component, .. } if component.status == ComponentStatus::Unknown
+    ));
+    assert!(open.changes_since(&open).is_empty());
+}
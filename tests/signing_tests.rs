@@ -0,0 +1,140 @@
+use onise::signing::{
+    encode_post_data, encode_post_data_with_nonce, sign, verify, NonceSource, SignedPayload, Signer,
+};
+
+// Test vector from Kraken's REST authentication documentation.
+const SECRET: &str =
+    "kQH5HW/8p1uGOVjbgWA7FunAmGO8lsSUXNsu3eow76sz84Q18fWxnyRzBHCd3pd5nE9qa99HAZtuZuj6F1huXg==";
+const NONCE: u64 = 1616492376594;
+const POST_DATA: &str =
+    "nonce=1616492376594&ordertype=limit&pair=XBTUSD&price=37500&type=buy&volume=1.25";
+const PATH: &str = "/0/private/AddOrder";
+const EXPECTED: &str =
+    "4/dpxb3iT4tp/ZCVEwSnEsLxx0bqyhLpdfOpc6fn7OR8+UClSV5n9E6aSS8MPtnRfp32bAb0nmbRn6H8ndwLUQ==";
+
+#[test]
+fn test_sign_matches_kraken_vector() {
+    let signature = sign(SECRET, PATH, NONCE, POST_DATA).expect("valid secret");
+    assert_eq!(signature, EXPECTED);
+    assert!(verify(SECRET, PATH, NONCE, POST_DATA, EXPECTED).unwrap());
+    assert!(!verify(SECRET, "/0/private/Balance", NONCE, POST_DATA, EXPECTED).unwrap());
+    // Truncated or not base64 at all: no match, no error
+    assert!(!verify(SECRET, PATH, NONCE, POST_DATA, &EXPECTED[..44]).unwrap());
+    assert!(!verify(SECRET, PATH, NONCE, POST_DATA, "not base64!").unwrap());
+}
+
+#[test]
+fn test_sign_rejects_invalid_secret() {
+    assert!(sign("not base64!", PATH, NONCE, POST_DATA).is_err());
+}
+
+#[test]
+fn test_signer_reuses_key_and_buffer() {
+    let signer = Signer::new(SECRET).expect("valid secret");
+    assert_eq!(signer.sign(PATH, NONCE, POST_DATA), EXPECTED);
+    assert!(signer.verify(PATH, NONCE, POST_DATA, EXPECTED));
+
+    let mut buf = String::from("stale");
+    signer.sign_into(PATH, NONCE, POST_DATA, &mut buf);
+    assert_eq!(buf, EXPECTED);
+    signer.sign_into("/0/private/Balance", 0, "nonce=0", &mut buf);
+    assert_eq!(buf, sign(SECRET, "/0/private/Balance", 0, "nonce=0").unwrap());
+
+    assert!(Signer::new("not base64!").is_err());
+    assert_eq!(format!("{signer:?}"), "Signer(<redacted>)");
+}
+
+#[test]
+fn test_encode_post_data_with_nonce() {
+    let params = [("ordertype", "limit"), ("pair", "XBTUSD"), ("note", "a&b=c d")];
+    let mut with_nonce = vec![("nonce", "1616492376594")];
+    with_nonce.extend(params);
+    assert_eq!(
+        encode_post_data_with_nonce(NONCE, &params),
+        encode_post_data(&with_nonce)
+    );
+    assert_eq!(encode_post_data_with_nonce::<&str, &str>(0, &[]), "nonce=0");
+}
+
+#[test]
+fn test_post_data_keeps_nonce_first_and_insertion_order() {
+    let params = [("pair", "XBTUSD"), ("otp", "123456"), ("nonce", "1"), ("type", "buy")];
+    assert_eq!(
+        encode_post_data_with_nonce(NONCE, &params),
+        "nonce=1616492376594&pair=XBTUSD&otp=123456&type=buy"
+    );
+
+    // Same inputs, same bytes; a new nonce only changes the first field
+    let first = SignedPayload::new(PATH, NONCE, &params);
+    assert_eq!(first, SignedPayload::new(PATH, NONCE, &params));
+    let retry = SignedPayload::new(PATH, NONCE + 1, &params);
+    assert_eq!(
+        retry.post_data().strip_prefix("nonce=1616492376595"),
+        first.post_data().strip_prefix("nonce=1616492376594")
+    );
+    assert_eq!(first.signed_message(), format!("{NONCE}{}", first.post_data()));
+    assert!(first.post_data().contains("otp=123456&type=buy"));
+
+    // Debug (and so KrakenError::SignatureRejected's) never shows the otp
+    let debug = format!("{first:?}");
+    assert!(!debug.contains("123456"));
+    assert!(debug.contains("pair=XBTUSD&otp=<redacted>&type=buy"));
+    assert_eq!(
+        first.redacted_post_data(),
+        "nonce=1616492376594&pair=XBTUSD&otp=<redacted>&type=buy"
+    );
+}
+
+#[test]
+fn test_nonce_source_is_strictly_increasing_across_clones() {
+    let nonces = NonceSource::new();
+    let threads: Vec<_> = (0..8)
+        .map(|_| {
+            let nonces = nonces.clone();
+            std::thread::spawn(move || (0..1000).map(|_| nonces.next()).collect::<Vec<_>>())
+        })
+        .collect();
+    let mut all = Vec::new();
+    for thread in threads {
+        let drawn = thread.join().unwrap();
+        assert!(drawn.windows(2).all(|pair| pair[0] < pair[1]));
+        all.extend(drawn);
+    }
+    all.sort_unstable();
+    all.dedup();
+    assert_eq!(all.len(), 8000, "no nonce is handed out twice");
+    assert!(nonces.next() > *all.last().unwrap());
+    assert!(
+        all[0] >= onise::signing::nonce() - 60_000_000,
+        "seeded from the clock"
+    );
+}
+
+#[test]
+fn test_sign_futures_hashes_body_nonce_then_path() {
+    use base64::Engine;
+    use hmac::{Hmac, Mac};
+    use sha2::{Digest, Sha256, Sha512};
+
+    let post_data = "orderType=lmt&symbol=PF_XBTUSD&side=buy&size=1&limitPrice=60000";
+    let nonce = "1616492376594";
+    let path = "/api/v3/sendorder";
+    let signature = onise::signing::sign_futures(SECRET, path, nonce, post_data).unwrap();
+
+    let engine = base64::engine::general_purpose::STANDARD;
+    let digest = Sha256::digest(format!("{post_data}{nonce}{path}"));
+    let mut mac = Hmac::<Sha512>::new_from_slice(&engine.decode(SECRET).unwrap()).unwrap();
+    mac.update(&digest);
+    assert_eq!(signature, engine.encode(mac.finalize().into_bytes()));
+    assert_ne!(signature, sign(SECRET, path, NONCE, post_data).unwrap());
+    assert!(onise::signing::sign_futures("not base64!", path, nonce, post_data).is_err());
+
+    // The WebSocket challenge is signed the same way, over the challenge alone
+    let challenge = "c100b894-1729-464d-ace1-52dbce11db42";
+    let mut mac = Hmac::<Sha512>::new_from_slice(&engine.decode(SECRET).unwrap()).unwrap();
+    mac.update(&Sha256::digest(challenge));
+    assert_eq!(
+        onise::signing::sign_challenge(SECRET, challenge).unwrap(),
+        engine.encode(mac.finalize().into_bytes())
+    );
+}
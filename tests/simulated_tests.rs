@@ -0,0 +1,88 @@
+use onise::error::KrakenError;
+use onise::exchange::{ExchangeClient, OrderAmendment, OrderRequest, Side};
+use onise::simulated::SimulatedExchange;
+
+fn exchange() -> SimulatedExchange {
+    SimulatedExchange::new()
+        .with_fee_rate(0.001)
+        .with_pair("XBTUSD", "XXBT", "ZUSD")
+        .with_balance("ZUSD", 10_000.0)
+}
+
+/// Strategy code written against the trait only.
+async fn buy_the_dip(venue: &impl ExchangeClient, price: &str) -> String {
+    let order = OrderRequest::limit("XBTUSD", Side::Buy, "0.1", price).with_cl_ord_id("dip-1");
+    venue.place_order(&order).await.unwrap().order_id
+}
+
+#[tokio::test]
+async fn test_market_and_resting_limit_orders() {
+    let sim = exchange();
+    sim.set_quote("XBTUSD", 30_000.0, 30_010.0).unwrap();
+
+    sim.place_order(&OrderRequest::market("XBTUSD", Side::Buy, "0.1"))
+        .await
+        .unwrap();
+    let fills = sim.fills();
+    assert_eq!(fills.len(), 1);
+    assert_eq!(fills[0].price, 30_010.0);
+    assert!((sim.balance("ZUSD") - (10_000.0 - 3_001.0 - 3.001)).abs() < 1e-9);
+    assert!((sim.balance("XXBT") - 0.1).abs() < 1e-12);
+
+    // Below the ask: rests until a quote crosses it
+    let dip = buy_the_dip(&sim, "29000").await;
+    assert_eq!(sim.open_order_ids(), vec![dip.clone()]);
+    assert!(sim.set_quote("XBTUSD", 29_500.0, 29_510.0).unwrap().is_empty());
+    let filled = sim.set_quote("XBTUSD", 28_990.0, 28_995.0).unwrap();
+    assert_eq!(filled.len(), 1);
+    assert_eq!(filled[0].order_id, dip);
+    assert_eq!(filled[0].cl_ord_id.as_deref(), Some("dip-1"));
+    assert_eq!(filled[0].price, 29_000.0);
+    assert!(sim.open_order_ids().is_empty());
+
+    let positions = sim.positions().await.unwrap();
+    assert_eq!(positions.len(), 1);
+    assert_eq!(positions[0].side, Side::Buy);
+    assert_eq!(positions[0].volume, "0.20000000");
+    assert_eq!(positions[0].cost, "5901.00000000");
+    assert_eq!(sim.balances().await.unwrap()["XXBT"], "0.20000000");
+}
+
+#[tokio::test]
+async fn test_rejections_cancel_and_amend() {
+    let sim = exchange();
+    sim.set_quote("XBTUSD", 30_000.0, 30_010.0).unwrap();
+
+    let too_big = OrderRequest::limit("XBTUSD", Side::Buy, "1", "25000");
+    assert!(matches!(
+        sim.place_order(&too_big).await,
+        Err(KrakenError::OrderError { message }) if message == "EOrder:Insufficient funds"
+    ));
+    let no_coins = OrderRequest::market("XBTUSD", Side::Sell, "0.1");
+    assert!(sim.place_order(&no_coins).await.is_err());
+    let unknown = OrderRequest::market("ETHUSD", Side::Buy, "1");
+    assert!(sim.place_order(&unknown).await.is_err());
+
+    // Resting orders hold their funds
+    let first = buy_the_dip(&sim, "25000").await;
+    let second = OrderRequest::limit("XBTUSD", Side::Buy, "0.29", "25000");
+    assert!(sim.place_order(&second).await.is_ok());
+    assert!(sim.place_order(&second).await.is_err());
+
+    sim.cancel_order(&first).await.unwrap();
+    assert!(matches!(
+        sim.cancel_order(&first).await,
+        Err(KrakenError::OrderError { .. })
+    ));
+
+    // Amending the remaining order to a marketable price fills it at the touch
+    let remaining = sim.open_order_ids().pop().unwrap();
+    let amendment = OrderAmendment::new(&remaining)
+        .with_volume("0.2")
+        .with_limit_price("30100");
+    sim.amend_order(&amendment).await.unwrap();
+    let fills = sim.fills();
+    assert_eq!(fills.last().unwrap().order_id, remaining);
+    assert_eq!(fills.last().unwrap().volume, 0.2);
+    assert_eq!(fills.last().unwrap().price, 30_010.0);
+}
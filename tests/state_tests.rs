@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use onise::clock::{Clock, MockClock};
+use onise::error::{KrakenError, KrakenResult};
+use onise::exchange::{ExchangeClient, OrderAmendment, OrderRequest, PlacedOrder, Position, Side};
+use onise::order_tracker::{OrderTracker, TrackingExchange};
+use onise::positions::PositionTracker;
+use onise::scheduler::{OrderScheduler, StartCondition};
+use onise::simulated::SimulatedExchange;
+use onise::state::{FileStateStore, MemoryStateStore, StateStore};
+
+#[test]
+fn test_file_state_store_round_trips() {
+    let dir = std::env::temp_dir().join(format!("onise-state-{}", std::process::id()));
+    let store = FileStateStore::open(&dir).unwrap();
+    assert_eq!(store.load("orders").unwrap(), None);
+    store.save("orders", b"{\"a\":1}").unwrap();
+    store.save("orders", b"{\"a\":2}").unwrap();
+    assert_eq!(store.load("orders").unwrap().unwrap(), b"{\"a\":2}");
+
+    // A second store over the same directory sees it
+    let reopened = FileStateStore::open(&dir).unwrap();
+    assert_eq!(reopened.load("orders").unwrap().unwrap(), b"{\"a\":2}");
+    reopened.remove("orders").unwrap();
+    reopened.remove("orders").unwrap();
+    assert_eq!(store.load("orders").unwrap(), None);
+
+    assert!(matches!(
+        store.save("../escape", b"x"),
+        Err(KrakenError::InvalidUsage(_))
+    ));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_trackers_restore_from_the_store() {
+    let store = MemoryStateStore::new();
+    let sim = SimulatedExchange::new()
+        .with_pair("XBTUSD", "XXBT", "ZUSD")
+        .with_balance("ZUSD", 100_000.0);
+    sim.set_quote("XBTUSD", 30_000.0, 30_010.0).unwrap();
+
+    let tracker = OrderTracker::new()
+        .with_store(Arc::new(store.clone()), "orders")
+        .unwrap();
+    let exchange = TrackingExchange::new(sim, tracker.clone());
+    let resting = OrderRequest::limit("XBTUSD", Side::Buy, "0.1", "20000").with_cl_ord_id("a-1");
+    let placed = exchange.place_order(&resting).await.unwrap();
+    let cancelled = OrderRequest::limit("XBTUSD", Side::Buy, "0.1", "20000").with_cl_ord_id("a-2");
+    let cancelled_id = exchange.place_order(&cancelled).await.unwrap().order_id;
+    exchange.cancel_order(&cancelled_id).await.unwrap();
+    // Refused outright: nothing to remember
+    let refused = OrderRequest::market("XBTUSD", Side::Buy, "100").with_cl_ord_id("a-3");
+    exchange.place_order(&refused).await.unwrap_err();
+    // Sent, but the process "dies" before the acknowledgement
+    let lost = OrderRequest::market("XBTUSD", Side::Sell, "0.1").with_cl_ord_id("a-4");
+    tracker.submitting(&lost).unwrap();
+
+    let positions = PositionTracker::new()
+        .with_store(Arc::new(store.clone()), "positions")
+        .unwrap();
+    positions.apply_fill("XBTUSD", Side::Buy, 0.5);
+    positions.apply_fill("XBTUSD", Side::Sell, 0.2);
+    drop((exchange, tracker, positions));
+
+    // After a restart
+    let tracker = OrderTracker::new()
+        .with_store(Arc::new(store.clone()), "orders")
+        .unwrap();
+    assert_eq!(tracker.order_id("a-1"), Some(placed.order_id.clone()));
+    assert_eq!(tracker.cl_ord_id(&placed.order_id).as_deref(), Some("a-1"));
+    assert!(tracker.get("a-2").is_none());
+    assert!(tracker.get("a-3").is_none());
+    let in_flight = tracker.in_flight();
+    assert_eq!(in_flight.len(), 1);
+    assert_eq!(in_flight[0].order, lost);
+    assert_eq!(tracker.open().len(), 1);
+
+    let positions = PositionTracker::new()
+        .with_store(Arc::new(store), "positions")
+        .unwrap();
+    assert!((positions.position("XBTUSD") - 0.3).abs() < 1e-12);
+}
+
+/// A venue that never answers in time.
+struct Unreachable;
+
+impl ExchangeClient for Unreachable {
+    async fn place_order(&self, _: &OrderRequest) -> KrakenResult<PlacedOrder> {
+        Err(timed_out())
+    }
+
+    async fn cancel_order(&self, _: &str) -> KrakenResult<()> {
+        Err(timed_out())
+    }
+
+    async fn amend_order(&self, _: &OrderAmendment) -> KrakenResult<()> {
+        Err(timed_out())
+    }
+
+    async fn positions(&self) -> KrakenResult<Vec<Position>> {
+        Err(timed_out())
+    }
+
+    async fn balances(&self) -> KrakenResult<HashMap<String, String>> {
+        Err(timed_out())
+    }
+}
+
+fn timed_out() -> KrakenError {
+    KrakenError::Timeout {
+        operation: "place_order".to_string(),
+        after: Duration::from_secs(3),
+    }
+}
+
+#[tokio::test]
+async fn test_scheduler_triggers_on_price_and_time_and_restores() {
+    let store = MemoryStateStore::new();
+    let clock = MockClock::new();
+    let sim = SimulatedExchange::new()
+        .with_pair("XBTUSD", "XXBT", "ZUSD")
+        .with_balance("ZUSD", 100_000.0);
+    sim.set_quote("XBTUSD", 30_000.0, 30_010.0).unwrap();
+    let scheduler = OrderScheduler::new()
+        .with_clock(clock.clone())
+        .with_store(Arc::new(store.clone()), "scheduled")
+        .unwrap();
+
+    let bid = |cl_ord_id: &str| {
+        OrderRequest::limit("XBTUSD", Side::Buy, "0.1", "20000").with_cl_ord_id(cl_ord_id)
+    };
+    let unnamed = OrderRequest::limit("XBTUSD", Side::Buy, "0.1", "20000");
+    let below = StartCondition::PriceBelow { price: 29_000.0 };
+    let err = scheduler.schedule(unnamed, below.clone()).unwrap_err();
+    assert!(matches!(err, KrakenError::InvalidUsage(_)));
+    scheduler.schedule(bid("dip"), below.clone()).unwrap();
+    assert!(scheduler.schedule(bid("dip"), below).is_err(), "duplicate");
+    let in_a_minute = clock.system_time() + Duration::from_secs(60);
+    let at = StartCondition::at(in_a_minute);
+    scheduler.schedule(bid("later"), at).unwrap();
+    let above = StartCondition::PriceAbove { price: 31_000.0 };
+    scheduler.schedule(bid("breakout"), above).unwrap();
+    let lost = OrderRequest::market("ETHUSD", Side::Sell, "1").with_cl_ord_id("lost");
+    let eth_below = StartCondition::PriceBelow { price: 2_000.0 };
+    scheduler.schedule(lost, eth_below).unwrap();
+
+    let triggered = scheduler.on_price(&sim, "XBTUSD", 29_500.0).await;
+    assert!(triggered.is_empty());
+    // Only the ETHUSD order fires, sent to a venue that times out: it may
+    // have been placed, so it stays `Submitting`
+    let triggered = scheduler.on_price(&Unreachable, "ETHUSD", 1_900.0).await;
+    assert_eq!(triggered.len(), 1);
+    assert!(triggered[0].result.is_err());
+    assert_eq!(scheduler.submitting()[0].cl_ord_id(), "lost");
+    let triggered = scheduler.on_price(&sim, "XBTUSD", 28_900.0).await;
+    assert_eq!(triggered[0].scheduled.cl_ord_id(), "dip");
+    assert!(triggered[0].result.is_ok());
+    assert_eq!(sim.open_order_ids().len(), 1);
+
+    assert!(scheduler.on_time(&sim).await.is_empty());
+    clock.advance(Duration::from_secs(61));
+    let triggered = scheduler.on_time(&sim).await;
+    assert_eq!(triggered[0].scheduled.cl_ord_id(), "later");
+    assert_eq!(sim.open_order_ids().len(), 2);
+    drop(scheduler);
+
+    // After a restart: "breakout" still waits, "lost" isn't sent again
+    let scheduler = OrderScheduler::new()
+        .with_store(Arc::new(store), "scheduled")
+        .unwrap();
+    let waiting = scheduler.waiting();
+    assert_eq!(waiting.len(), 1);
+    assert_eq!(waiting[0].cl_ord_id(), "breakout");
+    let submitting = scheduler.submitting();
+    assert_eq!(submitting.len(), 1);
+    assert_eq!(submitting[0].cl_ord_id(), "lost");
+    let triggered = scheduler.on_price(&sim, "XBTUSD", 28_000.0).await;
+    assert!(triggered.is_empty());
+}
@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use onise::clock::MockClock;
+use onise::error::KrakenError;
+use onise::exchange::{ExchangeClient, OrderRequest, Side};
+use onise::strategy_limits::{StrategyLimits, StrategyThrottle};
+
+mod common;
+use common::xbtusd_exchange;
+
+fn resting(strategy: &str) -> OrderRequest {
+    OrderRequest::limit("XBTUSD", Side::Buy, "0.1", "20000").with_strategy(strategy)
+}
+
+#[tokio::test]
+async fn test_order_rate_is_limited_per_strategy() {
+    let clock = MockClock::new();
+    let throttle = StrategyThrottle::new(xbtusd_exchange(1_000_000.0))
+        .with_default_limits(StrategyLimits::default().with_max_orders_per_minute(2))
+        .with_clock(clock.clone());
+
+    throttle.place_order(&resting("a")).await.unwrap();
+    throttle.place_order(&resting("a")).await.unwrap();
+    let err = throttle.place_order(&resting("a")).await.unwrap_err();
+    assert!(
+        matches!(&err, KrakenError::StrategyLimitExceeded { strategy, .. } if strategy == "a"),
+        "{err:?}"
+    );
+    // Other strategies and untagged orders have their own allowance
+    throttle.place_order(&resting("b")).await.unwrap();
+    let untagged = OrderRequest::limit("XBTUSD", Side::Buy, "0.1", "20000");
+    for _ in 0..3 {
+        throttle.place_order(&untagged).await.unwrap();
+    }
+    assert_eq!(throttle.inner().open_order_ids().len(), 6);
+
+    clock.advance(Duration::from_secs(60));
+    throttle.place_order(&resting("a")).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_open_notional_is_limited_and_released() {
+    let throttle = StrategyThrottle::new(xbtusd_exchange(1_000_000.0)).with_limits(
+        "a",
+        StrategyLimits::default().with_max_open_notional(5_000.0),
+    );
+
+    // 0.1 × 20000 = 2000 each
+    let first = throttle.place_order(&resting("a")).await.unwrap();
+    throttle.place_order(&resting("a")).await.unwrap();
+    assert_eq!(throttle.open_notional("a"), 4_000.0);
+    assert!(matches!(
+        throttle.place_order(&resting("a")).await,
+        Err(KrakenError::StrategyLimitExceeded { .. })
+    ));
+
+    throttle.cancel_order(&first.order_id).await.unwrap();
+    assert_eq!(throttle.open_notional("a"), 2_000.0);
+    throttle.place_order(&resting("a")).await.unwrap();
+
+    // Market orders need a price to be valued
+    let market = OrderRequest::market("XBTUSD", Side::Buy, "0.01").with_strategy("a");
+    assert!(matches!(
+        throttle.place_order(&market).await,
+        Err(KrakenError::InvalidUsage(_))
+    ));
+    assert_eq!(throttle.open_notional("a"), 4_000.0);
+}
@@ -0,0 +1,140 @@
+use onise::error::KrakenError;
+use onise::testkit::{ErrorClass, MockKraken, ENDPOINTS};
+
+#[tokio::test]
+async fn test_every_sample_deserializes() {
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+    let c = kraken.authenticated_client();
+    let p: &[(&str, &str)] = &[];
+
+    c.get_server_time().await.expect("Time");
+    c.get_system_status().await.expect("SystemStatus");
+    c.get_asset_info(p).await.expect("Assets");
+    c.get_asset_pairs(p).await.expect("AssetPairs");
+    c.get_ticker_information("XBTUSD").await.expect("Ticker");
+    c.get_ohlc_data(p).await.expect("OHLC");
+    c.get_order_book(p).await.expect("Depth");
+    c.get_recent_trades(p).await.expect("Trades");
+    c.get_recent_spreads(p).await.expect("Spread");
+
+    c.get_balance().await.expect("Balance");
+    c.get_extended_balance().await.expect("BalanceEx");
+    c.get_trade_balance(p).await.expect("TradeBalance");
+    c.get_open_orders(p).await.expect("OpenOrders");
+    c.get_closed_orders(p).await.expect("ClosedOrders");
+    c.query_orders_info(p).await.expect("QueryOrders");
+    c.get_trades_history(p).await.expect("TradesHistory");
+    c.query_trades_info(p).await.expect("QueryTrades");
+    c.get_open_positions(p).await.expect("OpenPositions");
+    c.get_ledgers(p).await.expect("Ledgers");
+    c.query_ledgers(p).await.expect("QueryLedgers");
+    c.get_trade_volume(p).await.expect("TradeVolume");
+    c.request_export_report(p).await.expect("ExportTrades");
+    c.get_export_report_status(p).await.expect("ExportStatus");
+    c.retrieve_export(p).await.expect("RetrieveExport");
+    c.delete_export(p).await.expect("DeleteExport");
+
+    c.add_order(p).await.expect("AddOrder");
+    c.add_order_batch(p).await.expect("AddOrderBatch");
+    c.amend_order(p).await.expect("AmendOrder");
+    c.edit_order(p).await.expect("EditOrder");
+    c.cancel_order(p).await.expect("CancelOrder");
+    c.cancel_all_orders().await.expect("CancelAll");
+    c.cancel_all_orders_after(p)
+        .await
+        .expect("CancelAllOrdersAfter");
+    c.cancel_order_batch(p).await.expect("CancelOrderBatch");
+    c.get_websockets_token().await.expect("GetWebSocketsToken");
+
+    c.get_deposit_methods(p).await.expect("DepositMethods");
+    c.get_deposit_addresses(p).await.expect("DepositAddresses");
+    c.get_deposit_status(p).await.expect("DepositStatus");
+    c.get_withdrawal_methods(p)
+        .await
+        .expect("WithdrawalMethods");
+    c.get_withdrawal_addresses(p)
+        .await
+        .expect("WithdrawalAddresses");
+    c.get_withdrawal_information(p)
+        .await
+        .expect("WithdrawalInformation");
+    c.withdraw_funds(p).await.expect("Withdraw");
+    c.get_withdraw_status(p).await.expect("WithdrawStatus");
+    c.request_withdrawal_cancellation(p)
+        .await
+        .expect("WithdrawCancel");
+    c.request_wallet_transfer(p).await.expect("WalletTransfer");
+    c.create_subaccount(p).await.expect("CreateSubaccount");
+    c.account_transfer(p).await.expect("AccountTransfer");
+
+    c.allocate_earn_funds(p).await.expect("Staking/Stake");
+    c.deallocate_earn_funds(p).await.expect("Staking/Unstake");
+    c.get_allocation_status()
+        .await
+        .expect("Staking/GetStakeStatus");
+    c.get_deallocation_status()
+        .await
+        .expect("Staking/GetUnstakeStatus");
+    c.list_earn_strategies()
+        .await
+        .expect("Staking/ListStakingProducts");
+    c.list_earn_allocations()
+        .await
+        .expect("Staking/ListStakingTransactions");
+
+    c.create_otc_quote(p).await.expect("CreateOtcQuoteRequest");
+    c.update_otc_quote(p).await.expect("UpdateOtcQuote");
+    c.get_otc_quotes().await.expect("GetOtcActiveQuotes");
+
+    assert_eq!(kraken.received_requests().await.len(), ENDPOINTS.len());
+}
+
+#[tokio::test]
+async fn test_error_classes_and_overrides() {
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+    let client = kraken.authenticated_client();
+
+    for class in ErrorClass::ALL {
+        kraken.server().reset().await;
+        kraken.mock_error("/0/private/AddOrder", class).await;
+        kraken.mock_all_success().await;
+
+        let err = client.add_order(&[]).await.expect_err("error expected");
+        let matched = match class {
+            ErrorClass::General => matches!(err.inner(), KrakenError::GeneralError { .. }),
+            ErrorClass::Api => matches!(err.inner(), KrakenError::ApiError { .. }),
+            ErrorClass::Service => matches!(err.inner(), KrakenError::ServiceError { .. }),
+            ErrorClass::Order => matches!(err.inner(), KrakenError::OrderError { .. }),
+            ErrorClass::Trading => matches!(err.inner(), KrakenError::TradingError { .. }),
+            ErrorClass::RateLimit => matches!(err.inner(), KrakenError::RateLimitExceeded { .. }),
+            ErrorClass::Unknown => matches!(err.inner(), KrakenError::Kraken(_)),
+        };
+        assert!(matched, "{class:?} produced {err}");
+
+        // Other endpoints still answer from the fallback mocks.
+        client.get_balance().await.expect("Balance");
+    }
+}
+
+#[tokio::test]
+async fn test_malformed_and_http_failures() {
+    let kraken = MockKraken::start().await;
+    kraken.mock_malformed("/0/public/Time").await;
+    kraken.mock_http_status("/0/private/Balance", 502).await;
+
+    let err = kraken
+        .public_client()
+        .get_server_time()
+        .await
+        .expect_err("malformed");
+    assert!(matches!(err.inner(), KrakenError::Json(_)));
+
+    let err = kraken
+        .authenticated_client()
+        .get_balance()
+        .await
+        .expect_err("502");
+    assert!(matches!(err.inner(), KrakenError::Json(_)));
+}
@@ -0,0 +1,33 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use futures_util::{stream, StreamExt};
+use onise::error::KrakenError;
+use onise::rate_limiter::RateLimiter;
+use onise::throttle::ThrottleExt;
+
+#[tokio::test]
+async fn test_throttle_delays_every_item() {
+    let limiter = Arc::new(RateLimiter::with_interval(Duration::from_millis(40)).unwrap());
+    let started = Instant::now();
+    let items: Vec<u32> = stream::iter(1..=4).throttle(limiter).collect().await;
+    assert_eq!(items, vec![1, 2, 3, 4]);
+    // The first permit is immediate, the other three wait an interval each.
+    assert!(started.elapsed() >= Duration::from_millis(110));
+}
+
+#[tokio::test]
+async fn test_throttle_latest_conflates() {
+    let limiter = Arc::new(RateLimiter::with_interval(Duration::from_millis(40)).unwrap());
+    let items: Vec<u32> = stream::iter(1..=5).throttle_latest(limiter).collect().await;
+    // 1 takes the free permit; 2..=5 arrive while waiting and only 5 survives.
+    assert_eq!(items, vec![1, 5]);
+}
+
+#[test]
+fn test_zero_interval_is_refused() {
+    assert!(matches!(
+        RateLimiter::with_interval(Duration::ZERO),
+        Err(KrakenError::InvalidUsage(_))
+    ));
+}
@@ -0,0 +1,78 @@
+use onise::error::KrakenError;
+use onise::testkit::MockKraken;
+
+#[tokio::test]
+async fn test_safe_withdrawer_guard_rails() {
+    use onise::withdraw::{SafeWithdrawer, WithdrawalRequest};
+
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+    let withdrawer = SafeWithdrawer::new(kraken.authenticated_client()).with_max_amount("XBT", 1.0);
+    let blocked = |r: onise::error::KrakenResult<_>| {
+        matches!(r, Err(KrakenError::WithdrawalBlocked(_)))
+    };
+
+    let ok = WithdrawalRequest::new("XBT", "btc-wallet-1", "0.5");
+    assert!(blocked(withdrawer.withdraw(&WithdrawalRequest::new("ETH", "x", "1")).await));
+    assert!(blocked(withdrawer.withdraw(&WithdrawalRequest::new("XBT", "btc-wallet-1", "2")).await));
+    assert!(blocked(withdrawer.withdraw(&WithdrawalRequest::new("XBT", "stranger", "0.5")).await));
+    assert!(blocked(
+        withdrawer
+            .withdraw(&ok.clone().with_address("bc1qsomewhereelse"))
+            .await
+    ));
+    // Quoted fee is 0.00015
+    let cheap = withdrawer.clone().with_max_fee("XBT", 0.0001);
+    assert!(blocked(cheap.withdraw(&ok).await));
+    let declined = withdrawer.clone().with_confirmation(|plan| plan.destination.name.is_none());
+    assert!(blocked(declined.withdraw(&ok).await));
+
+    let withdraw_calls = |requests: &[wiremock::Request]| {
+        requests.iter().filter(|r| r.url.path() == "/0/private/Withdraw").count()
+    };
+    assert_eq!(withdraw_calls(&kraken.received_requests().await), 0);
+
+    let confirmed = withdrawer
+        .with_max_fee("XBT", 0.001)
+        .with_confirmation(|plan| plan.information.is_some_and(|i| i.fee == "0.00015000"));
+    confirmed.withdraw(&ok).await.expect("all checks pass");
+    assert_eq!(withdraw_calls(&kraken.received_requests().await), 1);
+}
+
+#[tokio::test]
+async fn test_withdrawal_address_book() {
+    use onise::withdraw::{AddressBook, SafeWithdrawer, WithdrawalRequest};
+
+    let kraken = MockKraken::start().await;
+    kraken.mock_all_success().await;
+    let c = kraken.authenticated_client();
+    let book = AddressBook::sync(&c).await.unwrap();
+    assert_eq!(book.len(), 1);
+    let entry = book.get("BTC", "btc-wallet-1").unwrap();
+    assert_eq!(entry.address, "bc1qxdsh4sdd29h6ldehz0se5c61asq8cgwyjf2y3z");
+    assert!(book.is_verified("XXBT", "btc-wallet-1"));
+    assert!(!book.is_verified("XBT", "cold wallet"));
+    assert_eq!(book.for_asset("XBT").map(|(key, _)| key).collect::<Vec<_>>(), ["btc-wallet-1"]);
+    assert_eq!(book.for_asset("ETH").count(), 0);
+
+    let address_reads = |requests: &[wiremock::Request]| {
+        requests
+            .iter()
+            .filter(|r| r.url.path() == "/0/private/WithdrawalAddresses")
+            .count()
+    };
+    let withdrawer = SafeWithdrawer::new(c)
+        .with_max_amount("XBT", 1.0)
+        .with_address_book(book);
+    withdrawer
+        .withdraw(&WithdrawalRequest::new("XBT", "btc-wallet-1", "0.5"))
+        .await
+        .unwrap();
+    let err = withdrawer
+        .withdraw(&WithdrawalRequest::new("XBT", "new-wallet", "0.5"))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, KrakenError::WithdrawalBlocked(_)), "{err:?}");
+    // Only the sync read the address list
+    assert_eq!(address_reads(&kraken.received_requests().await), 1);
+}
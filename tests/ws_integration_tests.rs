@@ -5,7 +5,10 @@ use tokio_tungstenite::{accept_async, tungstenite::protocol::Message};
 
 use onise::error::KrakenResult;
 use onise::ws_client::KrakenWsClient;
-use onise::ws_models::{WsAdminResponse, WsIncomingMessage, WsPingRequest}; // The client we created
+use onise::ws_models::WsPingRequest; // The client we created
+
+mod common;
+use common::API_SECRET;
 
 #[tokio::test]
 async fn test_local_websocket_integration() -> KrakenResult<()> {
@@ -113,3 +116,1016 @@ async fn handle_ws_connection(stream: TcpStream, addr: SocketAddr) {
 
     println!("Server is closing the connection for {addr_string}");
 }
+
+#[tokio::test]
+async fn test_feed_streams_filter_by_channel() -> KrakenResult<()> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let local_addr = listener.local_addr()?;
+
+    // Server: wait for the client's ping, push a trade and a book update, then close
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws_stream = accept_async(stream).await.unwrap();
+        let _ = ws_stream.next().await;
+        let trade = r#"{"channel":"trade","symbol":"BTC/USD","trades":[{"price":"1","quantity":"2","time":3,"side":"buy"}]}"#;
+        let book = r#"{"channel":"book","type":"update","symbol":"BTC/USD","bids":[],"asks":[{"price":"10","quantity":"1"}]}"#;
+        ws_stream.send(Message::Text(trade.to_string())).await.unwrap();
+        ws_stream.send(Message::Text(book.to_string())).await.unwrap();
+        let _ = ws_stream.send(Message::Close(None)).await;
+    });
+
+    let client = KrakenWsClient::connect(&format!("ws://{local_addr}")).await?;
+    let books = client.book_stream();
+    let trades = client.trades_stream();
+    client.send_ping(Some(1)).await?;
+
+    let books: Vec<_> = books.collect().await;
+    let trades: Vec<_> = trades.collect().await;
+    assert_eq!(books.len(), 1);
+    assert_eq!(books[0].asks[0].price, "10");
+    assert_eq!(trades.len(), 1);
+    assert_eq!(trades[0].trades[0].side, "buy");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_correlated_requests_and_deadlines() -> KrakenResult<()> {
+    use onise::error::KrakenError;
+    use onise::ws_models::WsSubscriptionPayload;
+    use std::time::Duration;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let local_addr = listener.local_addr()?;
+
+    // Server: answer pings, reject subscriptions, and never answer anything else.
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws_stream = accept_async(stream).await.unwrap();
+        while let Some(Ok(Message::Text(text))) = ws_stream.next().await {
+            let request: serde_json::Value = serde_json::from_str(&text).unwrap();
+            let req_id = &request["req_id"];
+            let reply = match request["event"].as_str() {
+                Some("ping") => serde_json::json!({"event": "pingStatus", "req_id": req_id}),
+                Some("subscribe") => serde_json::json!({
+                    "event": "subscriptionStatus", "channel": "book", "status": "error",
+                    "req_id": req_id, "error_message": "EGeneral:Invalid arguments"
+                }),
+                _ => continue,
+            };
+            ws_stream.send(Message::Text(reply.to_string())).await.unwrap();
+        }
+    });
+
+    let client = KrakenWsClient::connect(&format!("ws://{local_addr}")).await?;
+    let deadline = Some(Duration::from_secs(5));
+
+    client.ping_and_wait(7, deadline).await?;
+
+    let book = WsSubscriptionPayload::Book {
+        symbol: "NOPE/USD".to_string(),
+        depth: 10,
+    };
+    let err = client
+        .subscribe_and_wait(book, 8, deadline)
+        .await
+        .expect_err("rejected subscription");
+    assert!(matches!(err, KrakenError::GeneralError { .. }));
+
+    let err = client
+        .authorize_and_wait("token", 9, Some(Duration::from_millis(100)))
+        .await
+        .expect_err("never answered");
+    assert!(matches!(err, KrakenError::Timeout { after, .. } if after == Duration::from_millis(100)));
+
+    let no_id = WsPingRequest {
+        event: "ping".to_string(),
+        req_id: None,
+    };
+    assert!(matches!(
+        client.request(&no_id, deadline).await,
+        Err(KrakenError::InvalidUsage(_))
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_replies_reach_their_request_out_of_order() -> KrakenResult<()> {
+    use onise::error::KrakenError;
+    use std::time::Duration;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let local_addr = listener.local_addr()?;
+
+    // Server: hold two pings and answer them newest first, then drop the
+    // connection without answering the third.
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws_stream = accept_async(stream).await.unwrap();
+        let mut req_ids = Vec::new();
+        while let Some(Ok(Message::Text(text))) = ws_stream.next().await {
+            let request: serde_json::Value = serde_json::from_str(&text).unwrap();
+            req_ids.push(request["req_id"].clone());
+            if req_ids.len() == 2 {
+                for req_id in req_ids.iter().rev() {
+                    let reply = serde_json::json!({"event": "pingStatus", "req_id": req_id});
+                    let sent = ws_stream.send(Message::Text(reply.to_string())).await;
+                    sent.unwrap();
+                }
+            } else if req_ids.len() == 3 {
+                break;
+            }
+        }
+    });
+
+    let client = KrakenWsClient::connect(&format!("ws://{local_addr}")).await?;
+    let ping = |req_id| WsPingRequest {
+        event: "ping".to_string(),
+        req_id: Some(req_id),
+    };
+    let (one, two) = (ping(1), ping(2));
+    let deadline = Some(Duration::from_secs(5));
+
+    let (first, twin, second) = tokio::join!(
+        client.request(&one, deadline),
+        async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            client.request(&one, deadline).await
+        },
+        async {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            client.request(&two, deadline).await
+        },
+    );
+    assert_eq!(first?.req_id(), Some(1));
+    assert_eq!(second?.req_id(), Some(2));
+    assert!(matches!(twin, Err(KrakenError::InvalidUsage(_))));
+
+    // The connection drops before the reply: the caller hears it at once,
+    // not at the deadline.
+    let err = client
+        .request(&ping(3), deadline)
+        .await
+        .expect_err("connection dropped");
+    assert!(matches!(err, KrakenError::InvalidUsage(_)));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ws_client_as_market_data_provider() -> KrakenResult<()> {
+    use onise::market_data::MarketDataProvider;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let local_addr = listener.local_addr()?;
+
+    // Server: answer the ticker subscription with another symbol's ticker first
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws_stream = accept_async(stream).await.unwrap();
+        let Some(Ok(Message::Text(text))) = ws_stream.next().await else {
+            return;
+        };
+        let request: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(request["event"], "subscribe");
+        let ticker = |symbol: &str, bid: &str| {
+            serde_json::json!({
+                "channel": "ticker", "symbol": symbol,
+                "best_ask_price": "2", "best_ask_quantity": "1",
+                "best_bid_price": bid, "best_bid_quantity": "1",
+                "last_trade_price": "1.5", "last_trade_quantity": "1",
+                "volume_24h": "10", "vwap_24h": "1.5", "trades_24h": 3,
+                "low_24h": "1", "high_24h": "2", "open_24h": "1"
+            })
+            .to_string()
+        };
+        ws_stream.send(Message::Text(ticker("ETH/USD", "100"))).await.unwrap();
+        ws_stream.send(Message::Text(ticker("BTC/USD", "1"))).await.unwrap();
+        let _ = ws_stream.send(Message::Close(None)).await;
+    });
+
+    let client = KrakenWsClient::connect(&format!("ws://{local_addr}")).await?;
+    let quotes: Vec<_> = client.quotes("BTC/USD").await?.collect().await;
+    assert_eq!(quotes.len(), 1);
+    assert_eq!(quotes[0].best_bid_price, "1");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ws_client_as_exchange_client() -> KrakenResult<()> {
+    use onise::error::KrakenError;
+    use onise::exchange::{ExchangeClient, OrderRequest, Side};
+
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let local_addr = listener.local_addr()?;
+
+    // Server: accept orders, then answer the balances subscription with a snapshot
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws_stream = accept_async(stream).await.unwrap();
+        while let Some(Ok(Message::Text(text))) = ws_stream.next().await {
+            let request: serde_json::Value = serde_json::from_str(&text).unwrap();
+            let req_id = &request["req_id"];
+            let replies = match request["event"].as_str() {
+                Some("addOrder") => {
+                    assert_eq!(request["token"], "ws-token");
+                    assert_eq!(request["orderType"], "limit");
+                    assert_eq!(request["clientOrderId"], "c-1");
+                    vec![serde_json::json!({
+                        "event": "addOrderStatus", "status": "ok",
+                        "txid": "OWS123-AAAAA-BBBBBB", "req_id": req_id
+                    })]
+                }
+                Some("subscribe") => vec![
+                    serde_json::json!({
+                        "event": "subscriptionStatus", "channel": "balances",
+                        "status": "subscribed", "req_id": req_id
+                    }),
+                    serde_json::json!({"channel": "balances", "balances": {"USD": "100.0"}}),
+                ],
+                _ => continue,
+            };
+            for reply in replies {
+                ws_stream.send(Message::Text(reply.to_string())).await.unwrap();
+            }
+        }
+    });
+
+    let mut client = KrakenWsClient::connect(&format!("ws://{local_addr}")).await?;
+    let order = OrderRequest::limit("BTC/USD", Side::Buy, "0.1", "25000").with_cl_ord_id("c-1");
+    assert!(matches!(
+        client.place_order(&order).await,
+        Err(KrakenError::InvalidUsage(_))
+    ));
+
+    client.token = Some("ws-token".to_string());
+    assert_eq!(client.place_order(&order).await?.order_id, "OWS123-AAAAA-BBBBBB");
+    assert_eq!(client.balances().await?["USD"], "100.0");
+    assert!(client.positions().await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_recorder_output_replays_through_order_book() -> KrakenResult<()> {
+    use onise::replay::{Recorder, Replay};
+
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let local_addr = listener.local_addr()?;
+
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws_stream = accept_async(stream).await.unwrap();
+        let _ = ws_stream.next().await;
+        let frames = [
+            r#"{"channel":"book","type":"snapshot","symbol":"BTC/USD","bids":[{"price":"99","quantity":"1"}],"asks":[{"price":"101","quantity":"2"}]}"#,
+            r#"{"channel":"heartbeat"}"#,
+            r#"{"channel":"book","type":"update","symbol":"ETH/USD","bids":[{"price":"5","quantity":"1"}],"asks":[]}"#,
+            r#"{"channel":"book","type":"update","symbol":"BTC/USD","bids":[{"price":"100","quantity":"3"}],"asks":[]}"#,
+        ];
+        for frame in frames {
+            ws_stream.send(Message::Text(frame.to_string())).await.unwrap();
+        }
+        let _ = ws_stream.send(Message::Close(None)).await;
+    });
+
+    let path = std::env::temp_dir().join(format!("onise-recording-{}.ndjson", std::process::id()));
+    let client = KrakenWsClient::connect(&format!("ws://{local_addr}")).await?;
+    let recorder = Recorder::create(&client, &path).await?;
+    let closed = client.message_stream();
+    client.send_ping(Some(1)).await?;
+    closed.count().await;
+    assert_eq!(recorder.stop().await?, 4);
+
+    let messages: Vec<_> = Replay::open(&path).await?.message_stream().collect().await;
+    assert_eq!(messages.len(), 4);
+
+    let books: Vec<_> = Replay::open(&path)
+        .await?
+        .order_book_stream("BTC/USD", 10)
+        .collect()
+        .await;
+    std::fs::remove_file(&path)?;
+    assert_eq!(books.len(), 2);
+    let best_bid = books[1].best_bid().unwrap();
+    assert_eq!((best_bid.price.as_str(), best_bid.quantity.as_str()), ("100", "3"));
+    assert_eq!(books[1].best_ask().unwrap().price, "101");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_replay_speed_follows_recorded_gaps() {
+    use onise::replay::{Replay, ReplaySpeed};
+    use std::time::{Duration, Instant};
+
+    let ticker = |t: u64, bid: &str| {
+        format!(
+            r#"{{"t":{t},"msg":{{"channel":"ticker","symbol":"BTC/USD","best_ask_price":"101","best_ask_quantity":"1","best_bid_price":"{bid}","best_bid_quantity":"1","last_trade_price":"100","last_trade_quantity":"1","volume_24h":"1","vwap_24h":"100","trades_24h":1,"low_24h":"90","high_24h":"110","open_24h":"95"}}}}"#
+        )
+    };
+    let recording = [
+        ticker(1_700_000_000_000, "99"),
+        "not json".to_string(),
+        ticker(1_700_000_001_000, "98"),
+        ticker(1_700_000_003_000, "97"),
+    ]
+    .join("\n");
+
+    let started = Instant::now();
+    let fast: Vec<_> = Replay::from_reader(std::io::Cursor::new(recording.clone()))
+        .ticker_stream()
+        .collect()
+        .await;
+    assert!(started.elapsed() < Duration::from_millis(200));
+    let bids: Vec<_> = fast.iter().map(|t| t.best_bid_price.as_str()).collect();
+    assert_eq!(bids, ["99", "98", "97"]);
+
+    // 3s of recorded gaps at 10x
+    let started = Instant::now();
+    let paced = Replay::from_reader(std::io::Cursor::new(recording))
+        .with_speed(ReplaySpeed::Accelerated(10.0))
+        .ticker_stream()
+        .count()
+        .await;
+    assert_eq!(paced, 3);
+    assert!(started.elapsed() >= Duration::from_millis(290));
+}
+
+#[tokio::test]
+async fn test_subscription_budget_rejects_and_pool_shards() -> KrakenResult<()> {
+    use onise::error::KrakenError;
+    use onise::ws_client::SubscriptionBudget;
+    use onise::ws_models::WsSubscriptionPayload;
+    use onise::ws_pool::WsPool;
+
+    let trades_on = |symbol: &str| WsSubscriptionPayload::Trades {
+        symbol: symbol.to_string(),
+    };
+
+    // Server: every connection answers each subscribe with one trade on its symbol
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let local_addr = listener.local_addr()?;
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(async move {
+                let mut ws_stream = accept_async(stream).await.unwrap();
+                while let Some(Ok(Message::Text(text))) = ws_stream.next().await {
+                    let request: serde_json::Value = serde_json::from_str(&text).unwrap();
+                    if request["event"] != "subscribe" {
+                        continue;
+                    }
+                    let trade = serde_json::json!({
+                        "channel": "trade",
+                        "symbol": request["symbol"],
+                        "trades": [{"price": "1", "quantity": "1", "time": 1, "side": "buy"}],
+                    });
+                    let _ = ws_stream.send(Message::Text(trade.to_string())).await;
+                }
+            });
+        }
+    });
+    let url = format!("ws://{local_addr}");
+
+    let client = KrakenWsClient::connect(&url)
+        .await?
+        .with_subscription_budget(SubscriptionBudget::reject(1));
+    client.subscribe(trades_on("BTC/USD"), None).await?;
+    // Re-subscribing to the same pair doesn't use more budget
+    client.subscribe(trades_on("BTC/USD"), None).await?;
+    let err = client.subscribe(trades_on("ETH/USD"), None).await.unwrap_err();
+    assert!(matches!(err, KrakenError::SubscriptionBudgetExceeded { max: 1, .. }));
+    client.unsubscribe(trades_on("BTC/USD"), None).await?;
+    assert_eq!(client.subscription_count(), 0);
+    client.subscribe(trades_on("ETH/USD"), None).await?;
+
+    let pool = WsPool::new(url, 2);
+    let mut trades = pool.trades_stream();
+    for symbol in ["BTC/USD", "ETH/USD", "SOL/USD", "BTC/USD"] {
+        pool.subscribe(trades_on(symbol)).await?;
+    }
+    let connections = pool.connections().await;
+    assert_eq!(connections.len(), 2);
+    assert_eq!(connections[0].subscription_count(), 2);
+    assert_eq!(connections[1].subscription_count(), 1);
+    assert_eq!(pool.subscription_count().await, 3);
+
+    let mut symbols = Vec::new();
+    for _ in 0..3 {
+        symbols.push(trades.next().await.unwrap().symbol);
+    }
+    symbols.sort();
+    assert_eq!(symbols, ["BTC/USD", "ETH/USD", "SOL/USD"]);
+
+    // Freed room is reused before opening another connection
+    pool.unsubscribe(trades_on("ETH/USD")).await?;
+    pool.subscribe(trades_on("XRP/USD")).await?;
+    assert_eq!(pool.connections().await.len(), 2);
+    Ok(())
+}
+
+/// A frame as a server sends it: unmasked, `rsv1` marking a compressed message.
+fn server_frame(fin: bool, rsv1: bool, opcode: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = vec![(fin as u8) << 7 | (rsv1 as u8) << 6 | opcode];
+    assert!(payload.len() < 126);
+    frame.push(payload.len() as u8);
+    frame.extend_from_slice(payload);
+    frame
+}
+
+#[tokio::test]
+async fn test_permessage_deflate_is_negotiated_and_inflated() -> KrakenResult<()> {
+    use flate2::{Compress, Compression, FlushCompress};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let local_addr = listener.local_addr()?;
+
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        while !request.ends_with(b"\r\n\r\n") {
+            request.push(stream.read_u8().await.unwrap());
+        }
+        let request = String::from_utf8(request).unwrap();
+        let header = |name: &str| {
+            request.lines().find_map(|line| {
+                let (key, value) = line.split_once(": ")?;
+                key.eq_ignore_ascii_case(name).then_some(value)
+            })
+        };
+        assert_eq!(
+            header("Sec-WebSocket-Extensions"),
+            Some("permessage-deflate")
+        );
+        let key = header("Sec-WebSocket-Key").unwrap();
+
+        // One compressor for the whole connection: later messages refer back
+        // to earlier ones (context takeover)
+        let mut compress = Compress::new(Compression::default(), false);
+        let mut deflate = |text: &str| {
+            let mut out = Vec::with_capacity(text.len() + 64);
+            compress
+                .compress_vec(text.as_bytes(), &mut out, FlushCompress::Sync)
+                .unwrap();
+            assert!(out.ends_with(&[0, 0, 0xff, 0xff]));
+            out.truncate(out.len() - 4);
+            out
+        };
+        let trade = r#"{"channel":"trade","symbol":"BTC/USD","trades":[{"price":"1","quantity":"2","time":3,"side":"buy"}]}"#;
+        let book = r#"{"channel":"book","type":"update","symbol":"BTC/USD","bids":[],"asks":[{"price":"10","quantity":"1"}]}"#;
+        let binary = r#"{"channel":"trade","symbol":"BTC/USD","trades":[{"price":"4","quantity":"5","time":6,"side":"sell"}]}"#;
+
+        // The first message rides in the same packet as the handshake response
+        let mut response = format!(
+            "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+             Sec-WebSocket-Accept: {}\r\nSec-WebSocket-Extensions: permessage-deflate\r\n\r\n",
+            derive_accept_key(key.as_bytes())
+        )
+        .into_bytes();
+        response.extend(server_frame(true, true, 0x1, &deflate(trade)));
+        stream.write_all(&response).await.unwrap();
+
+        // Wait for the client's ping so its streams are listening
+        let mut ping = [0; 64];
+        let _ = stream.read(&mut ping).await.unwrap();
+
+        let book = deflate(book);
+        let (first, rest) = book.split_at(book.len() / 2);
+        let mut frames = server_frame(false, true, 0x1, first);
+        frames.extend(server_frame(true, false, 0x9, b"ping"));
+        frames.extend(server_frame(true, false, 0x0, rest));
+        frames.extend(server_frame(true, false, 0x2, binary.as_bytes()));
+        frames.extend(server_frame(true, false, 0x8, b""));
+        stream.write_all(&frames).await.unwrap();
+        let _ = stream.read(&mut ping).await;
+    });
+
+    let client = KrakenWsClient::connect(&format!("ws://{local_addr}")).await?;
+    let books = client.book_stream();
+    let trades = client.trades_stream();
+    client.send_ping(Some(1)).await?;
+
+    let books: Vec<_> = books.collect().await;
+    let trades: Vec<_> = trades.collect().await;
+    assert_eq!(books.len(), 1);
+    assert_eq!(books[0].asks[0].price, "10");
+    // The trade sent with the handshake response may beat the subscription
+    let sides: Vec<_> = trades.iter().map(|t| t.trades[0].side.as_str()).collect();
+    assert!(
+        ["buy", "sell"].ends_with(&sides) && !sides.is_empty(),
+        "{sides:?}"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_read_only_ws_client_refuses_trading_requests() -> KrakenResult<()> {
+    use onise::error::KrakenError;
+    use onise::exchange::{ExchangeClient, OrderRequest, Side};
+
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let local_addr = listener.local_addr()?;
+
+    // Server: return the events of every request that reached it
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws_stream = accept_async(stream).await.unwrap();
+        let mut events = Vec::new();
+        while let Some(Ok(Message::Text(text))) = ws_stream.next().await {
+            let request: serde_json::Value = serde_json::from_str(&text).unwrap();
+            events.push(request["event"].as_str().unwrap_or_default().to_string());
+        }
+        events
+    });
+
+    let mut client = KrakenWsClient::connect(&format!("ws://{local_addr}"))
+        .await?
+        .with_read_only(true);
+    client.token = Some("ws-token".to_string());
+    assert!(client.is_read_only());
+
+    let order = OrderRequest::limit("BTC/USD", Side::Buy, "0.1", "25000");
+    let err = client.place_order(&order).await.unwrap_err();
+    assert!(matches!(&err, KrakenError::ReadOnly { path } if path == "addOrder"));
+    let err = ExchangeClient::cancel_order(&client, "OWS123-AAAAA-BBBBBB").await.unwrap_err();
+    assert!(matches!(&err, KrakenError::ReadOnly { path } if path == "cancelOrder"));
+    assert!(client.outbound_log().records().is_empty());
+
+    client.send_ping(Some(1)).await?;
+    client.close().await?;
+    assert_eq!(server.await.unwrap(), ["ping"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_outbound_log_links_trading_requests_to_replies() -> KrakenResult<()> {
+    use onise::ws_models::{WsAddOrderRequest, WsBatchCancelRequest, WsCancelOrderRequest};
+
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let local_addr = listener.local_addr()?;
+
+    // Server: accept the order, reject the cancel, never answer the batch cancel
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws_stream = accept_async(stream).await.unwrap();
+        while let Some(Ok(Message::Text(text))) = ws_stream.next().await {
+            let request: serde_json::Value = serde_json::from_str(&text).unwrap();
+            let req_id = &request["req_id"];
+            let reply = match request["event"].as_str() {
+                Some("addOrder") => serde_json::json!({
+                    "event": "addOrderStatus", "status": "ok",
+                    "txid": "OWS123-AAAAA-BBBBBB", "req_id": req_id
+                }),
+                Some("cancelOrder") => serde_json::json!({
+                    "event": "cancelOrderStatus", "status": "error",
+                    "error_message": "EOrder:Unknown order", "req_id": req_id
+                }),
+                _ => continue,
+            };
+            ws_stream
+                .send(Message::Text(reply.to_string()))
+                .await
+                .unwrap();
+        }
+    });
+
+    let client = KrakenWsClient::connect(&format!("ws://{local_addr}")).await?;
+    client.send_ping(Some(1)).await?;
+    let add = WsAddOrderRequest {
+        event: "addOrder".into(),
+        token: "ws-token".into(),
+        req_id: Some(10),
+        order_type: "limit".into(),
+        symbol: "BTC/USD".into(),
+        side: "buy".into(),
+        quantity: "0.1".into(),
+        price: Some("25000".into()),
+        client_order_id: Some("c-1".into()),
+        ..Default::default()
+    };
+    client.request(&add, None).await?;
+    let cancel = WsCancelOrderRequest {
+        event: "cancelOrder".into(),
+        token: "ws-token".into(),
+        req_id: Some(11),
+        txid: "OWS123-AAAAA-BBBBBB".into(),
+    };
+    client.request(&cancel, None).await?;
+    client
+        .batch_cancel(WsBatchCancelRequest {
+            event: "batchCancel".into(),
+            token: "ws-token".into(),
+            req_id: Some(12),
+            orders: vec!["OWS123-AAAAA-BBBBBB".into()],
+        })
+        .await?;
+
+    let log = client.outbound_log();
+    let records = log.records();
+    let events: Vec<_> = records.iter().map(|r| r.event.as_str()).collect();
+    assert_eq!(
+        events,
+        ["addOrder", "cancelOrder", "batchCancel"],
+        "no ping"
+    );
+    assert_eq!(records[0].seq, 0);
+    assert!(records.iter().all(|r| r.went_out() && r.sent_ms > 0));
+
+    let placed = log.find_by_cl_ord_id("c-1").expect("recorded");
+    assert_eq!(placed.req_id, Some(10));
+    let reply = placed.reply.expect("answered");
+    assert!(reply.error.is_none() && reply.received_ms >= placed.sent_ms);
+
+    // Did my cancel actually go out? Yes, twice; one was rejected, one is pending
+    let cancels = log.for_txid("OWS123-AAAAA-BBBBBB");
+    assert_eq!(cancels.len(), 2);
+    let rejected = cancels[0].reply.as_ref().expect("answered");
+    assert_eq!(rejected.error.as_deref(), Some("EOrder:Unknown order"));
+    let pending = log.unanswered();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].req_id, Some(12));
+
+    let client = client.with_outbound_log_capacity(1);
+    assert_eq!(client.outbound_log().records()[0].event, "batchCancel");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reconnect_reauthorizes_and_resubscribes() -> KrakenResult<()> {
+    use onise::ws_models::WsSubscriptionPayload;
+    use onise::ws_reconnect::{ConnectionEvent, ReconnectPolicy};
+    use std::time::Duration;
+    use tokio::sync::{broadcast, mpsc, oneshot};
+
+    let trades_on = |symbol: &str| WsSubscriptionPayload::Trades {
+        symbol: symbol.to_string(),
+    };
+
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let local_addr = listener.local_addr()?;
+    let (replayed_tx, mut replayed) = mpsc::unbounded_channel();
+    let (hang_up, hung_up) = oneshot::channel::<()>();
+
+    // Server: drop the first connection once the client unsubscribes, pass on
+    // what the second one replays, then stop listening and drop that too
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut first = accept_async(stream).await.unwrap();
+        while let Some(Ok(Message::Text(text))) = first.next().await {
+            if text.contains("unsubscribe") {
+                break;
+            }
+        }
+        first.close(None).await.unwrap();
+
+        let (stream, _) = listener.accept().await.unwrap();
+        drop(listener);
+        let mut second = accept_async(stream).await.unwrap();
+        for _ in 0..2 {
+            let Some(Ok(Message::Text(text))) = second.next().await else {
+                panic!("connection ended before the replay");
+            };
+            let request: serde_json::Value = serde_json::from_str(&text).unwrap();
+            replayed_tx.send(request).unwrap();
+        }
+        let trade = serde_json::json!({
+            "channel": "trade",
+            "symbol": "BTC/USD",
+            "trades": [{"price": "1", "quantity": "1", "time": 1, "side": "buy"}],
+        });
+        second.send(Message::Text(trade.to_string())).await.unwrap();
+        let _ = hung_up.await;
+        second.close(None).await.unwrap();
+    });
+
+    let policy = ReconnectPolicy {
+        initial_delay_ms: 10,
+        max_delay_ms: 20,
+        max_attempts: Some(2),
+    };
+    let client = KrakenWsClient::connect(&format!("ws://{local_addr}"))
+        .await?
+        .with_reconnect(policy);
+    let mut events = client.connection_events();
+    let mut trades = client.trades_stream();
+    client.authorize("ws-token", None).await?;
+    client.subscribe(trades_on("BTC/USD"), None).await?;
+    client.subscribe(trades_on("ETH/USD"), None).await?;
+    client.unsubscribe(trades_on("ETH/USD"), None).await?;
+
+    async fn next(events: &mut broadcast::Receiver<ConnectionEvent>) -> ConnectionEvent {
+        tokio::time::timeout(Duration::from_secs(5), events.recv())
+            .await
+            .expect("connection event")
+            .unwrap()
+    }
+    let dropped = ConnectionEvent::Disconnected { error: None };
+    assert_eq!(next(&mut events).await, dropped);
+    assert_eq!(
+        next(&mut events).await,
+        ConnectionEvent::Reconnecting {
+            attempt: 1,
+            delay: Duration::from_millis(10),
+        }
+    );
+    assert_eq!(
+        next(&mut events).await,
+        ConnectionEvent::Reconnected {
+            attempt: 1,
+            resubscribed: 1,
+        }
+    );
+    assert!(client.is_connected());
+
+    // The token first, then only the subscription still active
+    let authorize = replayed.recv().await.unwrap();
+    assert_eq!(authorize["event"], "authorize");
+    assert_eq!(authorize["token"], "ws-token");
+    let subscribe = replayed.recv().await.unwrap();
+    assert_eq!(subscribe["event"], "subscribe");
+    assert_eq!(subscribe["symbol"], "BTC/USD");
+    assert!(subscribe.get("req_id").is_none());
+
+    // Streams opened before the drop carry on
+    assert_eq!(trades.next().await.unwrap().symbol, "BTC/USD");
+
+    // Nobody is listening any more: both attempts fail and the streams close
+    hang_up.send(()).unwrap();
+    assert_eq!(next(&mut events).await, dropped);
+    for attempt in 1..=2 {
+        assert!(matches!(
+            next(&mut events).await,
+            ConnectionEvent::Reconnecting { attempt: a, .. } if a == attempt
+        ));
+        assert!(matches!(
+            next(&mut events).await,
+            ConnectionEvent::ReconnectFailed { attempt: a, .. } if a == attempt
+        ));
+    }
+    let gave_up = next(&mut events).await;
+    assert_eq!(gave_up, ConnectionEvent::GaveUp { attempts: 2 });
+    assert!(trades.next().await.is_none());
+    assert!(!client.is_connected());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_subscribe_streams_are_typed_and_filtered() -> KrakenResult<()> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let local_addr = listener.local_addr()?;
+
+    // Server: once both subscriptions are in, push trades on two pairs and a book, then close
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws_stream = accept_async(stream).await.unwrap();
+        let mut channels = Vec::new();
+        while channels.len() < 2 {
+            let Some(Ok(Message::Text(text))) = ws_stream.next().await else {
+                return;
+            };
+            let request: serde_json::Value = serde_json::from_str(&text).unwrap();
+            channels.push(request["name"].as_str().unwrap_or_default().to_string());
+        }
+        assert_eq!(channels, ["trades", "book"]);
+        let trade = |symbol: &str| {
+            serde_json::json!({
+                "channel": "trade",
+                "symbol": symbol,
+                "trades": [{"price": "1", "quantity": "2", "time": 3, "side": "buy"}],
+            })
+        };
+        let book = serde_json::json!({
+            "channel": "book", "type": "snapshot", "symbol": "BTC/USD",
+            "bids": [{"price": "9", "quantity": "1"}], "asks": [],
+        });
+        for frame in [trade("ETH/USD"), trade("BTC/USD"), book] {
+            let frame = Message::Text(frame.to_string());
+            ws_stream.send(frame).await.unwrap();
+        }
+        let _ = ws_stream.send(Message::Close(None)).await;
+    });
+
+    let client = KrakenWsClient::connect(&format!("ws://{local_addr}")).await?;
+    let trades = client.subscribe_trades("BTC/USD").await?;
+    let books = client.subscribe_book("BTC/USD", 10).await?;
+    assert_eq!(client.subscription_count(), 2);
+
+    let trades: Vec<_> = trades.collect().await;
+    let books: Vec<_> = books.collect().await;
+    assert_eq!(trades.len(), 1, "only the subscribed pair");
+    assert_eq!(trades[0].symbol, "BTC/USD");
+    assert_eq!(books.len(), 1);
+    assert_eq!(books[0].bids[0].price, "9");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_drop_copy_consumers_read_private_feed_independently() -> KrakenResult<()> {
+    use onise::drop_copy::PrivateEvent;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let local_addr = listener.local_addr()?;
+    let executions = onise::ws_client::MESSAGE_BUFFER + 6;
+
+    // Server: on the first message, push a ticker, more executions than one
+    // queue holds and a balance update, then close
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws_stream = accept_async(stream).await.unwrap();
+        let _ = ws_stream.next().await;
+        let ticker = serde_json::json!({
+            "channel": "ticker", "type": "update",
+            "data": [{"symbol": "BTC/USD", "bid": 1.0, "ask": 2.0, "last": 1.5}],
+        });
+        let execution = serde_json::json!({
+            "channel": "executions",
+            "executions": [{
+                "symbol": "BTC/USD", "order_id": "O1", "exec_id": "T1", "quantity": "0.5",
+                "price": "30000", "side": "buy", "time": 1, "cost": "15000", "fee": "1",
+                "fee_currency": "USD", "liquidity": "taker",
+            }],
+        });
+        let balances = serde_json::json!({"channel": "balances", "balances": {"USD": "100"}});
+        let frames = std::iter::once(ticker)
+            .chain(std::iter::repeat_n(execution, executions))
+            .chain(std::iter::once(balances));
+        for frame in frames {
+            let frame = Message::Text(frame.to_string());
+            ws_stream.send(frame).await.unwrap();
+        }
+        let _ = ws_stream.send(Message::Close(None)).await;
+    });
+
+    let client = KrakenWsClient::connect(&format!("ws://{local_addr}")).await?;
+    let strategy = client.drop_copy("strategy");
+    let risk = client.drop_copy("risk");
+    let lags = client.drop_copy_lag();
+    assert_eq!(lags.len(), 2);
+    assert_eq!(lags[0].name, "strategy");
+    assert_eq!(lags[1].name, "risk");
+
+    client.send_ping(Some(1)).await?;
+    let strategy_lag = {
+        let mut strategy = strategy;
+        while strategy.next().await.is_some() {}
+        strategy.lag()
+    };
+    let published = executions as u64 + 1;
+    assert_eq!(strategy_lag.received + strategy_lag.missed, published);
+    assert_eq!(strategy_lag.behind, 0);
+
+    // The risk monitor hasn't read a thing: it's behind by everything, and
+    // catching up now only finds the newest events still queued
+    let mut risk = risk;
+    let lag = risk.lag();
+    assert_eq!((lag.received, lag.behind), (0, published));
+    let mut events = Vec::new();
+    while let Some(event) = risk.next().await {
+        events.push(event);
+    }
+    assert_eq!(events.len(), onise::ws_client::MESSAGE_BUFFER);
+    assert!(matches!(events[0], PrivateEvent::Executions(_)));
+    assert!(matches!(events.last(), Some(PrivateEvent::Balances(_))));
+    let lag = risk.lag();
+    assert_eq!(lag.missed, published - events.len() as u64);
+    assert_eq!(lag.behind, 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_futures_ws_challenge_and_typed_feeds() -> KrakenResult<()> {
+    use onise::error::KrakenError;
+    use onise::futures_ws_client::KrakenFuturesWsClient;
+    use onise::futures_ws_models::{FuturesWsFills, FuturesWsOpenOrders};
+    use onise::signing::sign_challenge;
+
+    const CHALLENGE: &str = "c100b894-1729-464d-ace1-52dbce11db42";
+
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let local_addr = listener.local_addr()?;
+
+    // Server: hand out one challenge, accept private feeds signed with it,
+    // and answer each subscription with some data. Returns the challenges asked.
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws_stream = accept_async(stream).await.unwrap();
+        let mut challenges = 0;
+        while let Some(Ok(Message::Text(text))) = ws_stream.next().await {
+            let request: serde_json::Value = serde_json::from_str(&text).unwrap();
+            let replies = match (request["event"].as_str(), request["feed"].as_str()) {
+                (Some("challenge"), _) => {
+                    challenges += 1;
+                    assert_eq!(request["api_key"], "futures-key");
+                    vec![serde_json::json!({"event": "challenge", "message": CHALLENGE})]
+                }
+                (Some("subscribe"), Some("ticker")) => {
+                    let ticker = |product_id: &str| {
+                        serde_json::json!({
+                            "feed": "ticker", "product_id": product_id, "time": 1,
+                            "bid": 64000.0, "ask": 64001.0, "markPrice": 64000.5,
+                        })
+                    };
+                    vec![ticker("PI_ETHUSD"), ticker("PI_XBTUSD")]
+                }
+                (Some("subscribe"), Some(feed)) => {
+                    assert_eq!(request["original_challenge"], CHALLENGE);
+                    let signed = sign_challenge(API_SECRET, CHALLENGE).unwrap();
+                    assert_eq!(request["signed_challenge"], signed.as_str());
+                    let fill = serde_json::json!({
+                        "instrument": "PI_XBTUSD", "time": 2, "price": 64000.0, "seq": 7,
+                        "buy": true, "qty": 10.0, "order_id": "o-1", "fill_id": "f-1",
+                        "fill_type": "maker", "fee_paid": 0.0001, "fee_currency": "BTC",
+                    });
+                    match feed {
+                        "fills" => vec![
+                            serde_json::json!({
+                                "feed": "fills_snapshot", "account": "acc", "fills": [fill]
+                            }),
+                            serde_json::json!({
+                                "feed": "fills", "username": "acc", "fills": [fill]
+                            }),
+                        ],
+                        _ => vec![serde_json::json!({
+                            "feed": "open_orders", "order_id": "o-1", "is_cancel": true,
+                            "reason": "full_fill",
+                        })],
+                    }
+                }
+                _ => continue,
+            };
+            for reply in replies {
+                let reply = Message::Text(reply.to_string());
+                ws_stream.send(reply).await.unwrap();
+            }
+        }
+        challenges
+    });
+
+    let url = format!("ws://{local_addr}");
+    let client = KrakenFuturesWsClient::connect(&url).await?;
+    let unsigned = client.subscribe_fills().await;
+    assert!(matches!(unsigned.err(), Some(KrakenError::InvalidUsage(_))));
+
+    let client = client.with_credentials("futures-key", API_SECRET)?;
+    let mut tickers = client.subscribe_ticker(&["pi_xbtusd"]).await?;
+    let ticker = tickers.next().await.expect("ticker");
+    // PI_ETHUSD came first but isn't one of ours
+    assert_eq!(ticker.product_id, "PI_XBTUSD");
+    assert_eq!(ticker.mark_price, Some(64000.5));
+
+    let mut fills = client.subscribe_fills().await?;
+    let mut open_orders = client.subscribe_open_orders().await?;
+    match fills.next().await.expect("snapshot") {
+        FuturesWsFills::Snapshot(snapshot) => assert_eq!(snapshot.account.as_deref(), Some("acc")),
+        other => panic!("expected the snapshot first, got {other:?}"),
+    }
+    match fills.next().await.expect("update") {
+        FuturesWsFills::Update(update) => {
+            assert!(update.fills[0].buy);
+            assert_eq!(update.fills[0].qty, 10.0);
+        }
+        other => panic!("expected an update, got {other:?}"),
+    }
+    match open_orders.next().await.expect("open_orders") {
+        FuturesWsOpenOrders::Update(update) => {
+            assert!(update.is_cancel);
+            assert_eq!(update.order_id.as_deref(), Some("o-1"));
+        }
+        other => panic!("expected an update, got {other:?}"),
+    }
+
+    client.close().await?;
+    assert_eq!(server.await.unwrap(), 1, "one challenge per connection");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_futures_ws_pings_and_rejects_bad_secrets() -> KrakenResult<()> {
+    use onise::error::KrakenError;
+    use onise::futures_ws_client::KrakenFuturesWsClient;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let local_addr = listener.local_addr()?;
+
+    // Server: return the first message the client sends unprompted
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws_stream = accept_async(stream).await.unwrap();
+        match ws_stream.next().await {
+            Some(Ok(Message::Text(text))) => text.to_string(),
+            other => panic!("expected a text frame, got {other:?}"),
+        }
+    });
+
+    let client = KrakenFuturesWsClient::connect(&format!("ws://{local_addr}"))
+        .await?
+        .with_ping_interval(std::time::Duration::from_millis(20));
+    let first = tokio::time::timeout(std::time::Duration::from_secs(5), server)
+        .await
+        .expect("a ping within the interval")
+        .unwrap();
+    let first: serde_json::Value = serde_json::from_str(&first).unwrap();
+    assert_eq!(first, serde_json::json!({"event": "ping"}));
+
+    let rejected = client.with_credentials("futures-key", "not base64!");
+    assert!(matches!(rejected.err(), Some(KrakenError::InvalidUsage(_))));
+    Ok(())
+}